@@ -0,0 +1,29 @@
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("No OUT_DIR defined");
+    let protos = [
+        "proto/conformance/proto2.proto",
+        "proto/conformance/proto3.proto",
+    ];
+
+    // Generate rust-protobuf structs.
+    let protobuf_out = format!("{out_dir}/protobuf");
+    std::fs::create_dir_all(&protobuf_out).unwrap();
+    protobuf_codegen::Codegen::new()
+        .include("proto")
+        .inputs(protos)
+        .out_dir(&protobuf_out)
+        .run()
+        .unwrap();
+
+    // Generate prost structs.
+    let prost_out = format!("{out_dir}/prost");
+    std::fs::create_dir_all(&prost_out).unwrap();
+    prost_build::Config::new()
+        .out_dir(&prost_out)
+        .compile_protos(&protos, &["proto"])
+        .unwrap();
+
+    for proto in protos {
+        println!("cargo:rerun-if-changed={proto}");
+    }
+}
@@ -0,0 +1,148 @@
+//! Wire conformance of the rust-protobuf codecs against prost and protoc.
+//!
+//! Each case is a message in text format. It is encoded by rust-protobuf,
+//! by prost and by protoc (the C++ implementation, via `protoc --encode`), and
+//! the encodings are checked to be identical, or to decode to the same
+//! message when the encoding is not canonical, e.g. the entry order of maps.
+//! The protoc encoding is also decoded by the codecs of
+//! `tonic-codec-protobuf`, through `tonic::Streaming` like a request read
+//! from the network.
+
+pub mod pb {
+    //! Types generated by rust-protobuf.
+
+    include!(concat!(env!("OUT_DIR"), "/protobuf/mod.rs"));
+}
+
+pub mod prost_pb {
+    //! Types generated by prost.
+
+    pub mod proto2 {
+        include!(concat!(env!("OUT_DIR"), "/prost/conformance.proto2.rs"));
+    }
+    // The oneof of `Proto3` is generated in a `proto3` module.
+    #[allow(clippy::module_inception)]
+    pub mod proto3 {
+        include!(concat!(env!("OUT_DIR"), "/prost/conformance.proto3.rs"));
+    }
+}
+
+pub mod protoc;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use protobuf::{
+    reflect::{MessageRef, ReflectEq, ReflectEqMode},
+    text_format, MessageFull,
+};
+use tonic::{codec::Decoder, transport::Body, Status, Streaming};
+use tonic_codec_protobuf::{DynamicDecoder, ProtobufDecoderV3};
+
+/// How the encodings of a case are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    /// The encodings must be byte for byte identical.
+    Bytes,
+    /// The encodings must decode to the same message. Used for messages
+    /// whose encoding is not deterministic across implementations, e.g.
+    /// maps with more than one entry.
+    Semantic,
+}
+
+/// Checks that rust-protobuf, prost and protoc agree on the encoding of the
+/// message `M` given in text format, and that the codecs decode the protoc
+/// encoding to the same message.
+///
+/// `P` is the prost type generated from the same definition as `M`.
+pub fn check<M, P>(text: &str, compare: Compare) -> Result<(), String>
+where
+    M: MessageFull + PartialEq,
+    P: prost::Message + Default + PartialEq,
+{
+    let full_name = M::descriptor().full_name().to_owned();
+    let message: M = text_format::parse_from_str(text)
+        .map_err(|e| format!("rust-protobuf can not parse the case: {e}"))?;
+
+    let rust = message
+        .write_to_bytes()
+        .map_err(|e| format!("rust-protobuf can not encode: {e}"))?;
+    let cpp = protoc::encode(&full_name, text)?;
+    let from_cpp = P::decode(cpp.as_slice())
+        .map_err(|e| format!("prost can not decode the protoc encoding: {e}"))?;
+    let prost = from_cpp.encode_to_vec();
+
+    match compare {
+        Compare::Bytes => {
+            if rust != cpp {
+                return Err(format!(
+                    "rust-protobuf and protoc encodings differ:\n  rust-protobuf: {rust:02x?}\n  protoc:        {cpp:02x?}"
+                ));
+            }
+            if prost != cpp {
+                return Err(format!(
+                    "prost and protoc encodings differ:\n  prost:  {prost:02x?}\n  protoc: {cpp:02x?}"
+                ));
+            }
+        }
+        Compare::Semantic => {
+            let from_rust = P::decode(rust.as_slice())
+                .map_err(|e| format!("prost can not decode the rust-protobuf encoding: {e}"))?;
+            if from_rust != from_cpp {
+                return Err(
+                    "prost decodes the rust-protobuf and protoc encodings differently".to_owned(),
+                );
+            }
+            let from_prost = M::parse_from_bytes(&prost)
+                .map_err(|e| format!("rust-protobuf can not decode the prost encoding: {e}"))?;
+            if from_prost != message {
+                return Err(format!(
+                    "rust-protobuf decodes the prost encoding to a different message:\n  expected: {message}\n  actual:   {from_prost}"
+                ));
+            }
+        }
+    }
+
+    // protoc must decode the rust-protobuf encoding to the same message.
+    let text_from_cpp = protoc::decode(&full_name, &rust)?;
+    let from_cpp_text: M = text_format::parse_from_str(&text_from_cpp)
+        .map_err(|e| format!("rust-protobuf can not parse the protoc output: {e}"))?;
+    if from_cpp_text != message {
+        return Err(format!(
+            "protoc decodes the rust-protobuf encoding to a different message:\n  expected: {message}\n  actual:   {text_from_cpp}"
+        ));
+    }
+
+    // The codecs must decode the protoc encoding to the same message.
+    let decoded = decode(ProtobufDecoderV3::<M>::new(), &cpp)?;
+    if decoded != message {
+        return Err(format!(
+            "ProtobufDecoderV3 decodes the protoc encoding to a different message:\n  expected: {message}\n  actual:   {decoded}"
+        ));
+    }
+    let decoded = decode(DynamicDecoder::new(M::descriptor()), &cpp)?;
+    if !MessageRef::new(&message).reflect_eq(&MessageRef::new(&*decoded), &ReflectEqMode::default())
+    {
+        return Err(format!(
+            "DynamicDecoder decodes the protoc encoding to a different message:\n  expected: {message}\n  actual:   {decoded}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Decodes a single message from `data` with `decoder`, after framing it as
+/// a gRPC request body.
+pub fn decode<D, T>(decoder: D, data: &[u8]) -> Result<T, String>
+where
+    D: Decoder<Item = T, Error = Status> + Send + 'static,
+    T: Send + 'static,
+{
+    let mut body = BytesMut::with_capacity(5 + data.len());
+    body.put_u8(0);
+    body.put_u32(data.len() as u32);
+    body.put_slice(data);
+    let body = Body::from(Bytes::from(body));
+    let mut stream = Streaming::new_request(decoder, body, None, None);
+    futures::executor::block_on(stream.message())
+        .map_err(|status| format!("decoding failed: {}", status.message()))?
+        .ok_or_else(|| "no message decoded".to_owned())
+}
@@ -0,0 +1,59 @@
+//! Encoding and decoding with protoc, i.e. the C++ implementation.
+//!
+//! protoc is found like `protobuf-codegen` and `prost-build` find it: from the
+//! `PROTOC` environment variable, or else on `PATH`.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// The directory of the conformance protos.
+const INCLUDE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/proto");
+
+/// Encodes the message `full_name` given in text format, with
+/// `protoc --encode`.
+pub fn encode(full_name: &str, text: &str) -> Result<Vec<u8>, String> {
+    run("--encode", full_name, text.as_bytes())
+}
+
+/// Decodes `data` as a message `full_name` to text format, with
+/// `protoc --decode`.
+pub fn decode(full_name: &str, data: &[u8]) -> Result<String, String> {
+    let text = run("--decode", full_name, data)?;
+    String::from_utf8(text).map_err(|e| format!("protoc printed invalid UTF-8: {e}"))
+}
+
+fn run(mode: &str, full_name: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+    let protoc = std::env::var_os("PROTOC").unwrap_or_else(|| "protoc".into());
+    let proto = match full_name.split('.').nth(1) {
+        Some(package) => format!("conformance/{package}.proto"),
+        None => return Err(format!("{full_name} is not a conformance message")),
+    };
+    let mut child = Command::new(protoc)
+        .arg(format!("{mode}={full_name}"))
+        .arg(format!("-I{INCLUDE}"))
+        .arg(proto)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run protoc: {e}"))?;
+    // protoc reads all of its input before writing, so writing first can not
+    // deadlock.
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(input)
+        .map_err(|e| format!("failed to write to protoc: {e}"))?;
+    drop(stdin);
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("failed to run protoc: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "protoc {mode} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,138 @@
+use conformance::{
+    check, decode,
+    pb::proto2::{Groups, Proto2, Required},
+    prost_pb, protoc, Compare,
+};
+use prost::Message as _;
+use protobuf::MessageFull;
+use tonic_codec_protobuf::ProtobufDecoderV3;
+
+#[track_caller]
+fn assert_conforms(text: &str, compare: Compare) {
+    if let Err(e) = check::<Proto2, prost_pb::proto2::Proto2>(text, compare) {
+        panic!("case `{text}`: {e}");
+    }
+}
+
+#[test]
+fn test_empty() {
+    assert_conforms("", Compare::Bytes);
+}
+
+#[test]
+fn test_defaults_are_written() {
+    // Fields set to their default values are present and written.
+    assert_conforms(
+        r#"
+        optional_int32: 0
+        optional_string: ""
+        optional_bytes: ""
+        optional_bool: false
+        optional_sign: ZERO
+        with_default: -7
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_scalars() {
+    assert_conforms(
+        r#"
+        optional_int32: -1
+        optional_int64: -2
+        optional_uint32: 3
+        optional_uint64: 18446744073709551615
+        optional_sint32: -5
+        optional_sint64: -6
+        optional_fixed32: 7
+        optional_fixed64: 8
+        optional_sfixed32: -9
+        optional_sfixed64: -10
+        optional_float: -0
+        optional_double: 1e-300
+        optional_bool: true
+        optional_string: "\xe2\x82\xac"
+        optional_bytes: "\000\001\377"
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_negative_enums() {
+    assert_conforms("optional_sign: NEGATIVE", Compare::Bytes);
+    assert_conforms(
+        "unpacked_sign: NEGATIVE unpacked_sign: POSITIVE",
+        Compare::Bytes,
+    );
+    assert_conforms("packed_sign: ZERO packed_sign: POSITIVE", Compare::Bytes);
+}
+
+#[test]
+#[ignore = "rust-protobuf writes a too short length for packed negative enums"]
+fn test_packed_negative_enums() {
+    assert_conforms("packed_sign: NEGATIVE packed_sign: ZERO", Compare::Bytes);
+}
+
+#[test]
+fn test_packed() {
+    assert_conforms(
+        r#"
+        unpacked_int32: 1
+        unpacked_int32: -1
+        unpacked_int32: 0
+        packed_int32: 1
+        packed_int32: -1
+        packed_int32: 0
+        repeated_string: "x"
+        repeated_string: ""
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_max_field_number() {
+    assert_conforms("max_field_number: -1", Compare::Bytes);
+}
+
+#[test]
+fn test_required() {
+    if let Err(e) = check::<Required, prost_pb::proto2::Required>("id: 0", Compare::Bytes) {
+        panic!("{e}");
+    }
+
+    // protoc only warns about missing required fields, but the codec
+    // rejects such messages, like the C++ parser does.
+    let full_name = Required::descriptor().full_name().to_owned();
+    let missing = protoc::encode(&full_name, "name: \"a\"").unwrap();
+    decode(ProtobufDecoderV3::<Required>::new(), &missing).unwrap_err();
+
+    // prost does not check required fields.
+    prost_pb::proto2::Required::decode(missing.as_slice()).unwrap();
+}
+
+#[test]
+fn test_groups() {
+    // rust-protobuf does not support groups: the codec skips them while
+    // decoding, so they are lost, unlike with prost or protoc.
+    let full_name = Groups::descriptor().full_name().to_owned();
+    for text in [
+        "OptionalGroup { }",
+        "OptionalGroup { a: -1 b: \"b\" }",
+        "RepeatedGroup { a: 1 } RepeatedGroup { } RepeatedGroup { a: 2 }",
+    ] {
+        let cpp = protoc::encode(&full_name, text).unwrap();
+        let decoded = decode(ProtobufDecoderV3::<Groups>::new(), &cpp).unwrap();
+        assert_eq!(decoded, Groups::new(), "case `{text}`");
+
+        let prost = prost_pb::proto2::Groups::decode(cpp.as_slice()).unwrap();
+        assert_eq!(prost.encode_to_vec(), cpp, "case `{text}`");
+    }
+
+    // Fields next to groups are still decoded.
+    let cpp = protoc::encode(&full_name, "OptionalGroup { a: 1 } child { }").unwrap();
+    let decoded = decode(ProtobufDecoderV3::<Groups>::new(), &cpp).unwrap();
+    assert!(decoded.child.is_some());
+}
@@ -0,0 +1,173 @@
+use conformance::{check, pb::proto3::Proto3, prost_pb, Compare};
+
+#[track_caller]
+fn assert_conforms(text: &str, compare: Compare) {
+    if let Err(e) = check::<Proto3, prost_pb::proto3::Proto3>(text, compare) {
+        panic!("case `{text}`: {e}");
+    }
+}
+
+#[test]
+fn test_empty() {
+    assert_conforms("", Compare::Bytes);
+}
+
+#[test]
+fn test_scalars() {
+    assert_conforms(
+        r#"
+        optional_int32: 1
+        optional_int64: 2
+        optional_uint32: 3
+        optional_uint64: 4
+        optional_sint32: 5
+        optional_sint64: 6
+        optional_fixed32: 7
+        optional_fixed64: 8
+        optional_sfixed32: 9
+        optional_sfixed64: 10
+        optional_float: 11.5
+        optional_double: 12.25
+        optional_bool: true
+        optional_string: "héllo"
+        optional_bytes: "\000\377"
+        optional_sign: POSITIVE
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_scalar_limits() {
+    assert_conforms(
+        r#"
+        optional_int32: -2147483648
+        optional_int64: -9223372036854775808
+        optional_uint32: 4294967295
+        optional_uint64: 18446744073709551615
+        optional_sint32: -2147483648
+        optional_sint64: -9223372036854775808
+        optional_fixed32: 4294967295
+        optional_fixed64: 18446744073709551615
+        optional_sfixed32: -2147483648
+        optional_sfixed64: -9223372036854775808
+        optional_float: -inf
+        optional_double: inf
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_negative_enums() {
+    // Negative enum values are sign extended to 10 byte varints.
+    assert_conforms("optional_sign: NEGATIVE", Compare::Bytes);
+    assert_conforms(
+        "map_sint32_sign { key: -1 value: NEGATIVE }",
+        Compare::Bytes,
+    );
+    assert_conforms("choice_sign: NEGATIVE", Compare::Bytes);
+}
+
+#[test]
+#[ignore = "rust-protobuf writes a too short length for packed negative enums"]
+fn test_packed_negative_enums() {
+    assert_conforms(
+        "packed_sign: NEGATIVE packed_sign: ZERO packed_sign: POSITIVE",
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_explicit_presence() {
+    // Default values are written if the field has explicit presence.
+    assert_conforms("explicit_int32: 0 explicit_sign: ZERO", Compare::Bytes);
+    assert_conforms("explicit_sign: NEGATIVE", Compare::Bytes);
+}
+
+#[test]
+fn test_packed() {
+    assert_conforms(
+        r#"
+        packed_int32: 0
+        packed_int32: 1
+        packed_int32: 2147483647
+        packed_int32: -1
+        packed_sint64: 0
+        packed_sint64: -1
+        packed_sint64: -9223372036854775808
+        packed_double: 0
+        packed_double: 1e300
+        packed_double: -0.5
+        unpacked_int32: 1
+        unpacked_int32: -1
+        unpacked_int32: 0
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_repeated() {
+    assert_conforms(
+        r#"
+        repeated_string: ""
+        repeated_string: "a"
+        repeated_string: "b"
+        repeated_bytes: ""
+        repeated_bytes: "\001"
+        repeated_child { }
+        repeated_child { optional_int32: 1 child { optional_string: "nested" } }
+        "#,
+        Compare::Bytes,
+    );
+}
+
+#[test]
+fn test_maps() {
+    assert_conforms("map_string_int32 { key: \"a\" value: 1 }", Compare::Bytes);
+    // prost omits default keys and values of entries, rust-protobuf and
+    // protoc write them.
+    assert_conforms("map_string_int32 { key: \"\" value: 0 }", Compare::Semantic);
+    assert_conforms(
+        "map_bool_bytes { key: false value: \"\" }",
+        Compare::Semantic,
+    );
+    assert_conforms(
+        "map_int64_child { key: 1 value { optional_sign: NEGATIVE } }",
+        Compare::Bytes,
+    );
+
+    // The order of multiple entries depends on the implementation.
+    assert_conforms(
+        r#"
+        map_string_int32 { key: "a" value: 1 }
+        map_string_int32 { key: "b" value: -1 }
+        map_string_int32 { key: "c" value: 0 }
+        map_int64_child { key: -1 value { } }
+        map_int64_child { key: 2 value { map_string_int32 { key: "x" value: 2 } } }
+        map_bool_bytes { key: true value: "t" }
+        map_bool_bytes { key: false value: "f" }
+        map_sint32_sign { key: 1 value: POSITIVE }
+        map_sint32_sign { key: -1 value: NEGATIVE }
+        "#,
+        Compare::Semantic,
+    );
+}
+
+#[test]
+fn test_oneof() {
+    assert_conforms("choice_uint32: 0", Compare::Bytes);
+    assert_conforms("choice_string: \"\"", Compare::Bytes);
+    assert_conforms("choice_child { }", Compare::Bytes);
+    assert_conforms("choice_child { choice_uint32: 7 }", Compare::Bytes);
+}
+
+#[test]
+fn test_nested() {
+    assert_conforms("child { }", Compare::Bytes);
+    assert_conforms(
+        "child { child { child { optional_int64: -1 packed_int32: 1 packed_int32: 2 } } }",
+        Compare::Bytes,
+    );
+}
@@ -1,8 +1,13 @@
 fn main() {
     // Generate protobuf structs.
     protobuf_codegen::Codegen::new()
-        .includes(["include", "proto"])
-        .input("proto/debugpb.proto")
+        .includes(["include", "proto", "../tonic-types-protobuf/proto"])
+        .inputs([
+            "proto/debugpb.proto",
+            "proto/jobpb.proto",
+            "proto/accountpb.proto",
+            "include/validate/validate.proto",
+        ])
         .cargo_out_dir("protos")
         .run()
         .unwrap();
@@ -12,17 +17,106 @@ fn main() {
         "{}/protos",
         std::env::var("OUT_DIR").expect("No OUT_DIR defined")
     );
+    // Parse the protos once for all builders.
+    let protos = tonic_build_protobuf::Builder::new().parse_protos(
+        &[
+            "proto/debugpb.proto",
+            "proto/jobpb.proto",
+            "proto/accountpb.proto",
+        ],
+        &["proto", "include", "../tonic-types-protobuf/proto"],
+    );
     tonic_build_protobuf::Builder::new()
         .out_dir(&out_dir)
         .proto_path("crate")
+        .verify_message_paths(&out_dir)
+        .mod_file("services.rs")
+        .register_types(true)
         .file_name(|pkg, svc| format!("{pkg}_{svc}_tonic"))
-        .compile(&["proto/debugpb.proto"], &["proto", "include"]);
+        .build_call_options(true)
+        .build_iter_methods(true)
+        .build_service_descriptors(true)
+        .build_schema_hash(true)
+        .build_uds_connect(true)
+        .build_blocking_client(true)
+        .build_smoke_tests(true)
+        .build_health_wait(true)
+        .error_option("debugpb.errors")
+        .trailer_option("debugpb.trailers")
+        .retry_option("debugpb.retry")
+        .idempotent_option("debugpb.idempotent")
+        .domain_type_option("accountpb.domain_type")
+        .auth_option("accountpb.auth")
+        .longrunning_operations(true)
+        .build_web(true)
+        .build_tracing(true)
+        .build_metrics(true)
+        .validate_requests(true)
+        .runtime_helpers(true)
+        .server_defaults(
+            tonic_build_protobuf::ServerDefaults::new()
+                .concurrency_limit(64)
+                .timeout(std::time::Duration::from_secs(10))
+                .interceptor("crate::accept_all"),
+        )
+        .compile_set(
+            &protos,
+            &[
+                "proto/debugpb.proto",
+                "proto/jobpb.proto",
+                "proto/accountpb.proto",
+            ],
+        );
+
+    // Generate a server of the jobs service that also speaks JSON.
+    tonic_build_protobuf::Builder::new()
+        .out_dir(&out_dir)
+        .proto_path("crate")
+        .file_name(|pkg, svc| format!("{pkg}_{svc}_json_tonic"))
+        .build_client(false)
+        .select_codec(true)
+        .codec_path("::tonic_dynamic_protobuf::ProtobufOrJsonCodec")
+        .longrunning_operations(true)
+        .compile_set(&protos, &["proto/jobpb.proto"]);
+
+    // Generate starter implementations of the services, to check that they
+    // compile.
+    let skeletons = tonic_build_protobuf::Builder::new()
+        .proto_path("crate")
+        .longrunning_operations(true)
+        .generate_skeletons_set(
+            &protos,
+            &[
+                "proto/debugpb.proto",
+                "proto/jobpb.proto",
+                "proto/accountpb.proto",
+            ],
+            "crate::services",
+        );
+    for file in skeletons {
+        let path = std::path::Path::new(&out_dir).join(format!("skeleton_{}", file.name));
+        std::fs::write(path, file.content).unwrap();
+    }
 
     // Generate mod file.
-    let content = r"
+    let content = r#"
+pub mod accountpb;
 pub mod debugpb;
-pub mod debugpb_debug_tonic;
-";
+pub mod jobpb;
+pub mod services;
+pub mod validate;
+pub use ::tonic_types_protobuf::pb::operations;
+pub mod skeletons {
+    include!("skeleton_accountpb_accounts.rs");
+    include!("skeleton_debugpb_debug.rs");
+    include!("skeleton_jobpb_jobs.rs");
+}
+pub mod json {
+    pub mod jobpb {
+        include!("jobpb_jobs_json_tonic.rs");
+    }
+}
+"#;
     let mod_path = std::path::Path::new(&out_dir).join("mod.rs");
     let previous_content = std::fs::read(&mod_path);
     if previous_content
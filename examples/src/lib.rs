@@ -3,3 +3,76 @@ mod generated {
 }
 
 pub use generated::*;
+
+/// Constructs an interceptor that accepts every request.
+pub fn accept_all() -> fn(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+    Ok
+}
+
+/// Domain types of the messages of the accounts service.
+pub mod model {
+    use crate::accountpb;
+
+    /// An account to create.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NewAccount {
+        pub name: String,
+        pub email: String,
+        pub age: u32,
+        /// The ISO 3166 code of the country, e.g. `NL`.
+        pub country: String,
+    }
+
+    impl From<NewAccount> for accountpb::CreateAccountRequest {
+        fn from(account: NewAccount) -> Self {
+            let mut address = accountpb::Address::new();
+            address.country = account.country;
+            let mut request = accountpb::CreateAccountRequest::new();
+            request.name = account.name;
+            request.email = account.email;
+            request.age = account.age;
+            request.plan = accountpb::Plan::PLAN_FREE.into();
+            request.address = Some(address).into();
+            request
+        }
+    }
+
+    impl TryFrom<accountpb::CreateAccountRequest> for NewAccount {
+        type Error = &'static str;
+
+        fn try_from(request: accountpb::CreateAccountRequest) -> Result<Self, Self::Error> {
+            let address = request.address.into_option().ok_or("missing address")?;
+            Ok(NewAccount {
+                name: request.name,
+                email: request.email,
+                age: request.age,
+                country: address.country,
+            })
+        }
+    }
+
+    /// A created account.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Profile {
+        pub name: String,
+    }
+
+    impl From<Profile> for accountpb::Account {
+        fn from(profile: Profile) -> Self {
+            let mut account = accountpb::Account::new();
+            account.name = profile.name;
+            account
+        }
+    }
+
+    impl TryFrom<accountpb::Account> for Profile {
+        type Error = &'static str;
+
+        fn try_from(account: accountpb::Account) -> Result<Self, Self::Error> {
+            if account.name.is_empty() {
+                return Err("missing name");
+            }
+            Ok(Profile { name: account.name })
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use examples::{
+    accountpb::{Account, CreateAccountRequest},
+    services::accountpb::{
+        accounts_client::AccountsClient,
+        accounts_server::{Accounts, AccountsServer},
+    },
+};
+use tonic::{Request, Response, Status};
+use tonic_testing_protobuf::duplex;
+
+/// Answers the credentials of every request as the account name.
+struct Echo;
+
+#[tonic::async_trait]
+impl Accounts for Echo {
+    async fn create_account(
+        &self,
+        request: Request<CreateAccountRequest>,
+    ) -> Result<Response<Account>, Status> {
+        let metadata = request.metadata();
+        let credentials = metadata
+            .get("authorization")
+            .or_else(|| metadata.get("x-account-key"))
+            .ok_or_else(|| Status::unauthenticated("no credentials"))?;
+        let mut account = Account::new();
+        account.name = credentials.to_str().unwrap().to_owned();
+        Ok(Response::new(account))
+    }
+}
+
+fn request() -> CreateAccountRequest {
+    let mut request = CreateAccountRequest::new();
+    request.name = "alice".to_owned();
+    request.email = "alice@example.com".to_owned();
+    request.age = 30;
+    request.plan = examples::accountpb::Plan::PLAN_PRO.into();
+    request.address = Some(examples::accountpb::Address {
+        country: "NL".to_owned(),
+        ..Default::default()
+    })
+    .into();
+    request
+}
+
+#[tokio::test]
+async fn test_bearer() {
+    let mut client = duplex::client(AccountsServer::new(Echo), |channel| {
+        AccountsClient::with_bearer(channel, "secret").unwrap()
+    })
+    .await;
+    let account = client.create_account(request()).await.unwrap().into_inner();
+    assert_eq!(account.name, "Bearer secret");
+}
+
+#[tokio::test]
+async fn test_api_key() {
+    let mut client = duplex::client(AccountsServer::new(Echo), |channel| {
+        AccountsClient::with_api_key(channel, "key").unwrap()
+    })
+    .await;
+    let account = client.create_account(request()).await.unwrap().into_inner();
+    assert_eq!(account.name, "key");
+}
+
+#[tokio::test]
+async fn test_invalid_token() {
+    let channel = tonic::transport::Endpoint::from_static("http://[::1]:1").connect_lazy();
+    assert!(AccountsClient::with_bearer(channel, "new\nline").is_err());
+}
@@ -0,0 +1,125 @@
+use std::pin::Pin;
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::{BlockingDebugClient, DebugClient},
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, Stream, StreamExt};
+use tonic::{transport::Server, Code, Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Answers `get` with the key, unless it is empty, and streams every byte of
+/// the key.
+struct Echo;
+
+#[tonic::async_trait]
+impl Debug for Echo {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let key = request.into_inner().key;
+        if key.is_empty() {
+            return Err(Status::not_found("no key"));
+        }
+        let mut response = GetResponse::new();
+        response.value = key;
+        Ok(Response::new(response))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        request: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let responses: Vec<_> = request
+            .into_inner()
+            .key
+            .into_iter()
+            .map(|byte| {
+                let mut response = GetResponse::new();
+                response.value = vec![byte];
+                response
+            })
+            .collect();
+        let stream: ResponseStream = Box::pin(tokio_stream::iter(responses).map(Ok));
+        Ok(Response::new(stream))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+fn get_request(key: &[u8]) -> GetRequest {
+    let mut request = GetRequest::new();
+    request.key = key.to_vec();
+    request
+}
+
+#[test]
+fn test_blocking_client() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client: DebugClient<_> =
+        runtime.block_on(duplex::client(DebugServer::new(Echo), DebugClient::new));
+    let mut client = BlockingDebugClient::new(client, runtime.handle().clone());
+
+    let response = client.get(get_request(b"k")).unwrap();
+    assert_eq!(response.into_inner().value, b"k");
+    let status = client.get(GetRequest::new()).unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let values: Vec<_> = client
+        .get_server_streaming(get_request(b"abc"))
+        .unwrap()
+        .into_inner()
+        .map(|response| response.unwrap().value)
+        .collect();
+    assert_eq!(values, [b"a", b"b", b"c"]);
+
+    // Streaming requests go through the async client.
+    let status = runtime
+        .block_on(
+            client
+                .inner_mut()
+                .get_client_streaming(tokio_stream::empty()),
+        )
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented);
+}
+
+#[test]
+fn test_blocking_client_connect() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let addr = listener.local_addr().unwrap();
+    runtime.spawn(
+        Server::builder()
+            .add_service(DebugServer::new(Echo))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let mut client = BlockingDebugClient::connect(format!("http://{addr}")).unwrap();
+    let response = client.get(get_request(b"k")).unwrap();
+    assert_eq!(response.into_inner().value, b"k");
+}
@@ -0,0 +1,140 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::{DebugClient, IDEMPOTENT_METHODS},
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio_stream::Stream;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic_dynamic_protobuf::ResponseCache;
+use tonic_testing_protobuf::duplex;
+use tower_layer::Layer;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Answers keys with their call number, and counts calls.
+#[derive(Clone, Default)]
+struct Counting {
+    calls: Arc<AtomicU32>,
+}
+
+#[tonic::async_trait]
+impl Debug for Counting {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst);
+        let key = request.into_inner().key;
+        if key == b"missing" {
+            return Err(Status::not_found("key not found"));
+        }
+        let mut response = GetResponse::new();
+        response.value = format!("{}#{}", String::from_utf8_lossy(&key), n).into_bytes();
+        Ok(Response::new(response))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(Response::new(ScanResponse::new()))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+fn get(key: &str) -> GetRequest {
+    let mut request = GetRequest::new();
+    request.key = key.as_bytes().to_vec();
+    request
+}
+
+#[test]
+fn test_idempotent_methods() {
+    assert_eq!(IDEMPOTENT_METHODS, ["/debugpb.Debug/Get"]);
+}
+
+#[tokio::test]
+async fn test_response_cache() {
+    let service = Counting::default();
+    let calls = service.calls.clone();
+    let cache = ResponseCache::new(Duration::from_millis(300)).methods(IDEMPOTENT_METHODS);
+    let layer = cache.clone().into_layer();
+    let mut client = duplex::client(DebugServer::new(service), |channel| {
+        DebugClient::new(layer.layer(channel))
+    })
+    .await;
+
+    let value = |response: Response<GetResponse>| response.into_inner().value;
+    // Identical requests are answered from the cache.
+    assert_eq!(value(client.get(get("a")).await.unwrap()), b"a#0");
+    assert_eq!(value(client.get(get("a")).await.unwrap()), b"a#0");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(value(client.get(get("b")).await.unwrap()), b"b#1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // Failures are not cached, nor are other methods.
+    for _ in 0..2 {
+        let status = client.get(get("missing")).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        client.scan(ScanRequest::new()).await.unwrap();
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+    // Responses expire after their time to live, or when cleared.
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(value(client.get(get("a")).await.unwrap()), b"a#6");
+    cache.clear();
+    assert_eq!(value(client.get(get("a")).await.unwrap()), b"a#7");
+    assert_eq!(value(client.get(get("a")).await.unwrap()), b"a#7");
+}
+
+#[tokio::test]
+async fn test_max_entries() {
+    let service = Counting::default();
+    let calls = service.calls.clone();
+    let cache = ResponseCache::new(Duration::from_secs(60))
+        .methods(IDEMPOTENT_METHODS)
+        .max_entries(1);
+    let mut client = duplex::client(DebugServer::new(service), |channel| {
+        DebugClient::new(cache.into_layer().layer(channel))
+    })
+    .await;
+
+    client.get(get("a")).await.unwrap();
+    client.get(get("b")).await.unwrap();
+    client.get(get("b")).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    // "a" was evicted to make room for "b".
+    client.get(get("a")).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
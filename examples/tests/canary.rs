@@ -0,0 +1,143 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use protobuf::well_known_types::duration::Duration;
+use protobuf2::well_known_types::{
+    Duration as DurationV2, Int32Value as Int32ValueV2, StringValue as StringValueV2,
+};
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    server::{NamedService, UnaryService},
+    transport::Channel,
+    Code, Request, Response, Status,
+};
+use tonic_codec_protobuf::{
+    CanaryCodecV2, CanaryCodecV3, Divergence, DivergenceKind, FieldDiff, PreEncoded,
+    PreEncodedCodec, RawCodec,
+};
+use tonic_testing_protobuf::duplex;
+
+/// Answers requests with their encoded messages.
+#[derive(Clone)]
+struct Echo;
+
+impl UnaryService<Bytes> for Echo {
+    type Response = Bytes;
+    type Future = BoxFuture<Response<Bytes>, Status>;
+
+    fn call(&mut self, request: Request<Bytes>) -> Self::Future {
+        Box::pin(async move { Ok(Response::new(request.into_inner())) })
+    }
+}
+
+impl<B> Service<http::Request<B>> for Echo
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(RawCodec::default());
+            Ok(grpc.unary(Echo, req).await)
+        })
+    }
+}
+
+impl NamedService for Echo {
+    const NAME: &'static str = "echo.Echo";
+}
+
+type Reporter = Arc<dyn Fn(Divergence) + Send + Sync>;
+
+/// Returns a reporter that collects divergences.
+fn collect() -> (Reporter, Arc<Mutex<Vec<Divergence>>>) {
+    let divergences = Arc::new(Mutex::new(Vec::new()));
+    let reported = divergences.clone();
+    let reporter = Arc::new(move |d| reported.lock().unwrap().push(d));
+    (reporter, divergences)
+}
+
+/// Echoes `bytes`, and decodes them with `codec`.
+async fn echo<C>(channel: Channel, codec: C, bytes: &[u8]) -> Result<C::Decode, Status>
+where
+    C: tonic::codec::Codec,
+    C::Encode: Send + 'static,
+    C::Decode: Send + Sync + 'static,
+    PreEncodedCodec<C>: tonic::codec::Codec<Encode = PreEncoded<C::Encode>, Decode = C::Decode>,
+{
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let request = PreEncoded::new(bytes.to_vec());
+    let response = grpc
+        .unary(
+            Request::new(request),
+            "/echo.Echo/Echo".parse().unwrap(),
+            PreEncodedCodec::new(codec),
+        )
+        .await?;
+    Ok(response.into_inner())
+}
+
+#[tokio::test]
+async fn test_canary_serves_primary() {
+    let channel = duplex::connect_service(Echo).await;
+    let (reporter, divergences) = collect();
+
+    // `seconds = 1, nanos = 2` decodes the same with both libraries.
+    let codec = CanaryCodecV3::<Duration, Duration, DurationV2, _>::new(reporter.clone());
+    let duration = echo(channel.clone(), codec, b"\x08\x01\x10\x02")
+        .await
+        .unwrap();
+    assert_eq!((duration.seconds, duration.nanos), (1, 2));
+    let codec = CanaryCodecV2::<DurationV2, DurationV2, Duration, _>::new(reporter);
+    let duration = echo(channel, codec, b"\x08\x01\x10\x02").await.unwrap();
+    assert_eq!((duration.seconds, duration.nanos), (1, 2));
+
+    assert_eq!(*divergences.lock().unwrap(), []);
+}
+
+#[tokio::test]
+async fn test_canary_reports_divergences() {
+    let channel = duplex::connect_service(Echo).await;
+    let (reporter, divergences) = collect();
+
+    // A v2 message decoding `seconds` as an int32 truncates 2^32 + 1.
+    let codec = CanaryCodecV3::<Duration, Duration, Int32ValueV2, _>::new(reporter.clone());
+    let duration = echo(channel.clone(), codec, b"\x08\x81\x80\x80\x80\x10")
+        .await
+        .unwrap();
+    assert_eq!(duration.seconds, (1 << 32) + 1);
+    // A v2 message expecting a string fails, and v3 is still served.
+    let codec = CanaryCodecV3::<Duration, Duration, StringValueV2, _>::new(reporter.clone());
+    echo(channel.clone(), codec, b"\x08\x01").await.unwrap();
+    // Only v2 is served.
+    let codec = CanaryCodecV2::<StringValueV2, StringValueV2, Duration, _>::new(reporter);
+    let status = echo(channel, codec, b"\x08\x01").await.unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+
+    let divergences = divergences.lock().unwrap();
+    assert_eq!(divergences.len(), 3, "{divergences:?}");
+    assert_eq!(divergences[0].message, "google.protobuf.Duration");
+    assert_eq!(
+        divergences[0].kind,
+        DivergenceKind::Fields(vec![FieldDiff {
+            field: "seconds".to_owned(),
+            v2: "1".to_owned(),
+            v3: "4294967297".to_owned(),
+        }])
+    );
+    assert!(matches!(divergences[1].kind, DivergenceKind::V2Error(_)));
+    assert!(matches!(divergences[2].kind, DivergenceKind::V2Error(_)));
+}
@@ -0,0 +1,46 @@
+use examples::debugpb::{GetRequest, KeyValue, ScanResponse};
+use protobuf::Message;
+use tonic::codegen::Bytes;
+use tonic_codec_protobuf::ProtobufDecoderV3;
+
+fn encode(message: &impl Message) -> Bytes {
+    message.write_to_bytes().unwrap().into()
+}
+
+#[test]
+fn test_decode_into_reuses_message() {
+    let mut decoder = ProtobufDecoderV3::<GetRequest>::new();
+    let mut message = GetRequest::new();
+    message.cf = "stale".to_owned();
+    message.key = b"stale".to_vec();
+
+    let mut request = GetRequest::new();
+    request.key = b"a".to_vec();
+    decoder.decode_into(encode(&request), &mut message).unwrap();
+    // Fields absent from the payload are cleared, not kept.
+    assert_eq!(message, request);
+
+    let mut decoder = ProtobufDecoderV3::<ScanResponse>::new();
+    let mut kv = KeyValue::new();
+    kv.key = b"k".to_vec();
+    let mut response = ScanResponse::new();
+    response.kvs.push(kv);
+    let mut message = response.clone();
+    // Repeated fields are replaced, not appended to.
+    decoder
+        .decode_into(encode(&response), &mut message)
+        .unwrap();
+    assert_eq!(message, response);
+}
+
+#[test]
+fn test_decode_into_errors() {
+    let mut decoder = ProtobufDecoderV3::<GetRequest>::new();
+    let mut message = GetRequest::new();
+    // A length-delimited field running past the end of the payload.
+    let truncated = Bytes::from_static(&[0x1a, 0x05, b'a']);
+    decoder.decode_into(truncated, &mut message).unwrap_err();
+
+    decoder.decode_into(Bytes::new(), &mut message).unwrap();
+    assert_eq!(message, GetRequest::new());
+}
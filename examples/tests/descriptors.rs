@@ -0,0 +1,20 @@
+use examples::services::accountpb::{accounts_client, accounts_server};
+
+#[test]
+fn test_service_descriptor() {
+    let service = accounts_server::service_descriptor();
+    assert_eq!(service.proto().name(), "Accounts");
+    let method = service.methods().next().unwrap();
+    assert_eq!(method.proto().name(), "CreateAccount");
+    assert_eq!(
+        method.input_type().full_name(),
+        "accountpb.CreateAccountRequest"
+    );
+    assert_eq!(method.output_type().full_name(), "accountpb.Account");
+
+    assert!(accounts_client::service_descriptor() == service);
+    assert_eq!(
+        accounts_server::file_descriptor().proto().name(),
+        "accountpb.proto"
+    );
+}
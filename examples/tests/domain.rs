@@ -0,0 +1,74 @@
+use examples::{
+    accountpb::{Account, CreateAccountRequest},
+    model::{NewAccount, Profile},
+    services::accountpb::{
+        accounts_client::AccountsClient,
+        accounts_server::{Accounts, AccountsServer, IntoDomain},
+    },
+};
+use tonic::{Code, Request, Response, Status};
+use tonic_testing_protobuf::duplex;
+
+/// Creates accounts from their domain types, and answers an empty account to
+/// the name "nobody".
+struct Registry;
+
+#[tonic::async_trait]
+impl Accounts for Registry {
+    async fn create_account(
+        &self,
+        request: Request<CreateAccountRequest>,
+    ) -> Result<Response<Account>, Status> {
+        let account = request.into_domain()?.into_inner();
+        if account.name == "nobody" {
+            return Ok(Response::new(Account::new()));
+        }
+        let profile = Profile { name: account.name };
+        Ok(Response::new(profile.into()))
+    }
+}
+
+fn new_account(name: &str) -> NewAccount {
+    NewAccount {
+        name: name.to_owned(),
+        email: format!("{}@example.com", name),
+        age: 30,
+        country: "NL".to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn test_domain_types() {
+    let mut client = duplex::client(AccountsServer::new(Registry), AccountsClient::new).await;
+    let profile = client
+        .create_account_domain(new_account("alice"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        profile,
+        Profile {
+            name: "alice".to_owned()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_domain_response() {
+    let mut client = duplex::client(AccountsServer::new(Registry), AccountsClient::new).await;
+    let status = client
+        .create_account_domain(new_account("nobody"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "invalid response: missing name");
+}
+
+#[test]
+fn test_invalid_domain_request() {
+    let status = Request::new(CreateAccountRequest::new())
+        .into_domain()
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "invalid request: missing address");
+}
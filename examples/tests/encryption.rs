@@ -0,0 +1,174 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use examples::debugpb::{GetRequest, GetResponse};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Service, StdError},
+    server::{NamedService, UnaryService},
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_codec_protobuf::{
+    Algorithm, EncryptedCodec, EncryptionKey, KeyError, KeyProvider, ProtobufCodecV3, StaticKeys,
+};
+
+const GET: &str = "/debugpb.Debug/Get";
+
+fn key(id: &str, byte: u8) -> EncryptionKey {
+    EncryptionKey::new(id, Algorithm::Aes256Gcm, &[byte; 32]).unwrap()
+}
+
+/// Echoes the key of requests as values.
+struct Echo;
+
+impl UnaryService<GetRequest> for Echo {
+    type Response = GetResponse;
+    type Future = BoxFuture<Response<GetResponse>, Status>;
+
+    fn call(&mut self, request: Request<GetRequest>) -> Self::Future {
+        let mut response = GetResponse::new();
+        response.value = request.into_inner().key;
+        Box::pin(async move { Ok(Response::new(response)) })
+    }
+}
+
+/// Serves `Debug/Get` with payloads encrypted by the keys of `K`.
+#[derive(Clone)]
+struct EncryptedDebug<K> {
+    keys: K,
+}
+
+impl<K, B> Service<http::Request<B>> for EncryptedDebug<K>
+where
+    K: KeyProvider + Clone,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let codec = EncryptedCodec::new(ProtobufCodecV3::default(), self.keys.clone());
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(codec);
+            Ok(grpc.unary(Echo, req).await)
+        })
+    }
+}
+
+impl<K> NamedService for EncryptedDebug<K> {
+    const NAME: &'static str = "debugpb.Debug";
+}
+
+async fn serve<K: KeyProvider + Clone>(keys: K) -> Channel {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(EncryptedDebug { keys })
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn get<C>(channel: Channel, codec: C, key: &[u8]) -> Result<Vec<u8>, Status>
+where
+    C: tonic::codec::Codec<Encode = GetRequest, Decode = GetResponse>,
+{
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let mut request = GetRequest::new();
+    request.key = key.to_vec();
+    let response = grpc
+        .unary(Request::new(request), GET.parse().unwrap(), codec)
+        .await?;
+    Ok(response.into_inner().value)
+}
+
+type Codec = EncryptedCodec<ProtobufCodecV3<GetRequest, GetResponse>, StaticKeys>;
+
+/// A client codec that encrypts with the key `id`.
+fn encrypted(id: &str, byte: u8) -> Codec {
+    EncryptedCodec::new(ProtobufCodecV3::default(), StaticKeys::new(key(id, byte)))
+}
+
+#[tokio::test]
+async fn test_encrypted_calls() {
+    let channel = serve(StaticKeys::new(key("k1", 1))).await;
+    let value = get(channel.clone(), encrypted("k1", 1), b"a")
+        .await
+        .unwrap();
+    assert_eq!(value, b"a");
+
+    // Peers must encrypt with a key the server knows.
+    let status = get(channel.clone(), encrypted("k2", 2), b"a")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    // A key of the same id, but different material, does not open messages.
+    let status = get(channel.clone(), encrypted("k1", 2), b"a")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    // Plaintext requests are rejected.
+    let status = get(channel, ProtobufCodecV3::default(), b"a")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_key_rotation() {
+    // The server seals with a new key, and still opens the old one.
+    let channel = serve(StaticKeys::new(key("k2", 2)).with_decryption_key(key("k1", 1))).await;
+
+    let client = StaticKeys::new(key("k1", 1)).with_decryption_key(key("k2", 2));
+    let codec = Codec::new(ProtobufCodecV3::default(), client);
+    let value = get(channel.clone(), codec, b"r").await.unwrap();
+    assert_eq!(value, b"r");
+
+    // The request is accepted, but the client can not open the response.
+    let status = get(channel, encrypted("k1", 1), b"r").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+/// A key provider whose key service is down.
+#[derive(Clone)]
+struct Unavailable;
+
+impl KeyProvider for Unavailable {
+    fn encryption_key(&self) -> Result<Arc<EncryptionKey>, KeyError> {
+        Err(KeyError::Unavailable("key service is down".to_owned()))
+    }
+
+    fn decryption_key(&self, _: &str) -> Result<Arc<EncryptionKey>, KeyError> {
+        Err(KeyError::Unavailable("key service is down".to_owned()))
+    }
+}
+
+#[tokio::test]
+async fn test_key_provider_errors() {
+    let channel = serve(Arc::new(Unavailable)).await;
+    let status = get(channel, encrypted("k1", 1), b"a").await.unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("key service is down"), "{status}");
+
+    assert!(matches!(
+        EncryptionKey::new("short", Algorithm::ChaCha20Poly1305, &[0; 16]),
+        Err(KeyError::Invalid(_))
+    ));
+}
@@ -0,0 +1,29 @@
+use examples::{
+    debugpb::ServerIsBusy,
+    services::debugpb::{debug_client::DebugError, debug_server},
+};
+
+#[test]
+fn test_error_status_round_trip() {
+    let status = tonic::Status::from(debug_server::DebugError::key_not_found());
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    assert_eq!(status.message(), "key not found");
+    match DebugError::from(status) {
+        DebugError::KeyNotFound { message } => assert_eq!(message, "key not found"),
+        e => panic!("{e:?}"),
+    }
+
+    let mut busy = ServerIsBusy::new();
+    busy.backoff_ms = 100;
+    let status = tonic::Status::from(debug_server::DebugError::server_is_busy(busy.clone()));
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+    match DebugError::from(status) {
+        DebugError::ServerIsBusy { detail, .. } => assert_eq!(detail, busy),
+        e => panic!("{e:?}"),
+    }
+
+    let status = tonic::Status::internal("boom");
+    let error = DebugError::from(status);
+    assert_eq!(error.code(), tonic::Code::Internal);
+    assert_eq!(error.reason(), None);
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use examples::{
+    jobpb::CompactRequest,
+    services::jobpb::{
+        jobs_client::JobsClient,
+        jobs_server::{Jobs, JobsServer},
+    },
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{transport::Server, Code, Request, Response, Status};
+use tonic_health_protobuf::health_reporter;
+use tonic_types_protobuf::longrunning::Operation;
+
+struct Compactions;
+
+#[tonic::async_trait]
+impl Jobs for Compactions {
+    async fn compact(
+        &self,
+        request: Request<CompactRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        let mut operation = Operation::new();
+        operation.name = format!("operations/{}", request.into_inner().cf);
+        Ok(Response::new(operation))
+    }
+}
+
+#[tokio::test]
+async fn test_connect_when_serving() {
+    let (mut reporter, health) = health_reporter();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(
+        Server::builder()
+            .add_service(health)
+            .add_service(JobsServer::new(Compactions))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    // The service is not reported yet.
+    let status = JobsClient::connect_when_serving(addr.clone(), Duration::from_millis(200))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(status.message().contains("jobpb.Jobs"), "{status}");
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        reporter.set_serving::<JobsServer<Compactions>>().await;
+    });
+    let mut client = JobsClient::connect_when_serving(addr, Duration::from_secs(5))
+        .await
+        .unwrap();
+    let mut request = CompactRequest::new();
+    request.cf = "default".to_owned();
+    let operation = client.compact(request).await.unwrap().into_inner();
+    assert_eq!(operation.name, "operations/default");
+
+    let status = JobsClient::connect_when_serving("not a uri", Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
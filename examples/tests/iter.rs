@@ -0,0 +1,103 @@
+use std::pin::Pin;
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Answers the keys of requests as values.
+struct Echo;
+
+fn response(key: Vec<u8>) -> GetResponse {
+    let mut response = GetResponse::new();
+    response.value = key;
+    response
+}
+
+#[tonic::async_trait]
+impl Debug for Echo {
+    async fn get(&self, _: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        request: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        let mut requests = request.into_inner();
+        let mut keys = vec![];
+        while let Some(request) = requests.message().await? {
+            keys.extend(request.key);
+        }
+        Ok(Response::new(response(keys)))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        request: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        let mut requests = request.into_inner();
+        let mut responses = vec![];
+        while let Some(request) = requests.message().await? {
+            responses.push(response(request.key));
+        }
+        Ok(Response::new(Box::pin(
+            tokio_stream::iter(responses).map(Ok),
+        )))
+    }
+}
+
+fn request(key: &[u8]) -> GetRequest {
+    let mut request = GetRequest::new();
+    request.key = key.to_vec();
+    request
+}
+
+#[tokio::test]
+async fn test_client_streaming_iter() {
+    let mut client = duplex::client(DebugServer::new(Echo), DebugClient::new).await;
+    let response = client
+        .get_client_streaming_iter(vec![request(b"a"), request(b"bc")])
+        .await
+        .unwrap();
+    assert_eq!(response.into_inner().value, b"abc");
+
+    let response = client.get_client_streaming_iter([]).await.unwrap();
+    assert!(response.into_inner().value.is_empty());
+}
+
+#[tokio::test]
+async fn test_bidirectional_streaming_iter() {
+    let mut client = duplex::client(DebugServer::new(Echo), DebugClient::new).await;
+    let keys = [b"a", b"b", b"c"];
+    let responses = client
+        .get_bidirectional_streaming_iter(keys.map(|key| request(key)))
+        .await
+        .unwrap()
+        .into_inner();
+    let values: Vec<_> = responses.map(|r| r.unwrap().value).collect().await;
+    assert_eq!(values, keys);
+}
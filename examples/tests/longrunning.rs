@@ -0,0 +1,196 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use examples::{
+    jobpb::{CompactProgress, CompactRequest, CompactResult},
+    services::jobpb::{
+        jobs_client::JobsClient,
+        jobs_server::{Jobs, JobsServer},
+    },
+};
+use protobuf::{
+    well_known_types::{any::Any, empty::Empty},
+    MessageField,
+};
+use tonic::{transport::Server, Code, Request, Response, Status};
+use tonic_testing_protobuf::duplex;
+use tonic_types_protobuf::{
+    longrunning::{
+        operation, CancelOperationRequest, DeleteOperationRequest, GetOperationRequest,
+        ListOperationsRequest, ListOperationsResponse, Operation, Operations, OperationsClient,
+        OperationsServer, WaitOperationRequest,
+    },
+    pb::status::Status as RpcStatus,
+};
+
+/// Compactions complete after `polls` calls of `GetOperation`, with `result`.
+#[derive(Clone)]
+struct Compactions {
+    operation: Arc<Mutex<Operation>>,
+    polls: Arc<Mutex<u32>>,
+    result: operation::Result,
+}
+
+impl Compactions {
+    fn new(polls: u32, result: operation::Result) -> Self {
+        Compactions {
+            operation: Arc::default(),
+            polls: Arc::new(Mutex::new(polls)),
+            result,
+        }
+    }
+}
+
+fn progress(percent: u32) -> MessageField<Any> {
+    let mut progress = CompactProgress::new();
+    progress.percent = percent;
+    MessageField::some(Any::pack(&progress).unwrap())
+}
+
+#[tonic::async_trait]
+impl Jobs for Compactions {
+    async fn compact(&self, _: Request<CompactRequest>) -> Result<Response<Operation>, Status> {
+        let mut operation = Operation::new();
+        operation.name = "operations/compact-1".to_owned();
+        operation.metadata = progress(0);
+        *self.operation.lock().unwrap() = operation.clone();
+        Ok(Response::new(operation))
+    }
+}
+
+#[tonic::async_trait]
+impl Operations for Compactions {
+    async fn list_operations(
+        &self,
+        _: Request<ListOperationsRequest>,
+    ) -> Result<Response<ListOperationsResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_operation(
+        &self,
+        request: Request<GetOperationRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        let mut operation = self.operation.lock().unwrap();
+        if request.get_ref().name != operation.name {
+            return Err(Status::not_found(request.into_inner().name));
+        }
+        let mut polls = self.polls.lock().unwrap();
+        *polls = polls.saturating_sub(1);
+        if *polls == 0 {
+            operation.done = true;
+            operation.metadata = progress(100);
+            operation.result = Some(self.result.clone());
+        } else {
+            operation.metadata = progress(50);
+        }
+        Ok(Response::new(operation.clone()))
+    }
+
+    async fn delete_operation(
+        &self,
+        _: Request<DeleteOperationRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn cancel_operation(
+        &self,
+        _: Request<CancelOperationRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn wait_operation(
+        &self,
+        _: Request<WaitOperationRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+async fn clients(
+    service: Compactions,
+) -> (
+    JobsClient<tonic::transport::Channel>,
+    OperationsClient<tonic::transport::Channel>,
+) {
+    let channel = duplex::connect(
+        Server::builder()
+            .add_service(JobsServer::new(service.clone()))
+            .add_service(OperationsServer::new(service)),
+    )
+    .await;
+    (
+        JobsClient::new(channel.clone()),
+        OperationsClient::new(channel),
+    )
+}
+
+#[tokio::test]
+async fn test_wait_for_response() {
+    let mut result = CompactResult::new();
+    result.reclaimed_bytes = 42;
+    let response = operation::Result::Response(Any::pack(&result).unwrap());
+    let (mut jobs, mut operations) = clients(Compactions::new(2, response)).await;
+
+    let mut operation = jobs
+        .compact_operation(CompactRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(operation.name(), "operations/compact-1");
+    assert!(!operation.is_done());
+    assert_eq!(operation.metadata().unwrap().unwrap().percent, 0);
+    assert_eq!(operation.result().unwrap(), None);
+
+    operation.refresh(&mut operations).await.unwrap();
+    assert_eq!(operation.metadata().unwrap().unwrap().percent, 50);
+
+    let response = operation
+        .wait(&mut operations, Duration::from_millis(1))
+        .await
+        .unwrap();
+    assert_eq!(response, result);
+}
+
+#[tokio::test]
+async fn test_wait_for_error() {
+    let mut error = RpcStatus::new();
+    error.code = Code::ResourceExhausted as i32;
+    error.message = "disk full".to_owned();
+    let (mut jobs, mut operations) =
+        clients(Compactions::new(1, operation::Result::Error(error))).await;
+
+    let operation = jobs
+        .compact_operation(CompactRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    let status = operation
+        .wait(&mut operations, Duration::from_millis(1))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(status.message(), "disk full");
+}
+
+#[tokio::test]
+async fn test_unexpected_response_type() {
+    let response = operation::Result::Response(Any::pack(&CompactProgress::new()).unwrap());
+    let (mut jobs, mut operations) = clients(Compactions::new(1, response)).await;
+
+    let operation = jobs
+        .compact_operation(CompactRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    let status = operation
+        .wait(&mut operations, Duration::from_millis(1))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("jobpb.CompactResult"), "{status}");
+}
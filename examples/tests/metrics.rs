@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, pin::Pin};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{Debug, DebugServer},
+    },
+};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Answers `get` with the key, unless it is empty.
+struct Echo;
+
+#[tonic::async_trait]
+impl Debug for Echo {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let key = request.into_inner().key;
+        if key.is_empty() {
+            return Err(Status::not_found("no key"));
+        }
+        let mut response = GetResponse::new();
+        response.value = key;
+        Ok(Response::new(response))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let stream: ResponseStream = Box::pin(tokio_stream::once(Ok(GetResponse::new())));
+        Ok(Response::new(stream))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+/// Returns the recorded metrics by name and labels, e.g.
+/// `grpc_server_started_total{grpc_method=Get,..}`.
+fn snapshot(snapshotter: &Snapshotter) -> BTreeMap<String, DebugValue> {
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            let labels: Vec<_> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            (format!("{}{{{}}}", key.name(), labels.join(",")), value)
+        })
+        .collect()
+}
+
+fn counter(metrics: &BTreeMap<String, DebugValue>, key: &str) -> u64 {
+    match metrics.get(key) {
+        Some(DebugValue::Counter(n)) => *n,
+        value => panic!("{key} is {value:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_server_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let _guard = metrics::set_default_local_recorder(&recorder);
+    let mut client = duplex::client(DebugServer::new(Echo), DebugClient::new).await;
+
+    let mut request = GetRequest::new();
+    request.key = b"k".to_vec();
+    client.get(request.clone()).await.unwrap();
+    client.get(request).await.unwrap();
+    let status = client.get(GetRequest::new()).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    let response = client
+        .get_server_streaming(GetRequest::new())
+        .await
+        .unwrap();
+    let _: Vec<_> = response.into_inner().collect().await;
+
+    let metrics = snapshot(&snapshotter);
+    let get = "grpc_service=debugpb.Debug,grpc_method=Get,grpc_type=unary";
+    assert_eq!(
+        counter(&metrics, &format!("grpc_server_started_total{{{get}}}")),
+        3
+    );
+    assert_eq!(
+        counter(
+            &metrics,
+            &format!("grpc_server_handled_total{{{get},grpc_code=Ok}}")
+        ),
+        2
+    );
+    assert_eq!(
+        counter(
+            &metrics,
+            &format!("grpc_server_handled_total{{{get},grpc_code=NotFound}}")
+        ),
+        1
+    );
+    match &metrics[&format!("grpc_server_handling_seconds{{{get}}}")] {
+        DebugValue::Histogram(seconds) => assert_eq!(seconds.len(), 3),
+        value => panic!("{value:?}"),
+    }
+    // Every call left the gauge.
+    match &metrics[&format!("grpc_server_in_flight{{{get}}}")] {
+        DebugValue::Gauge(n) => assert_eq!(n.0, 0.0),
+        value => panic!("{value:?}"),
+    }
+
+    let streaming =
+        "grpc_service=debugpb.Debug,grpc_method=GetServerStreaming,grpc_type=server_streaming";
+    assert_eq!(
+        counter(
+            &metrics,
+            &format!("grpc_server_handled_total{{{streaming},grpc_code=Ok}}")
+        ),
+        1
+    );
+}
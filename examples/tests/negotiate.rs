@@ -0,0 +1,114 @@
+use std::task::{Context, Poll};
+
+use examples::{
+    jobpb::CompactRequest,
+    json::jobpb::jobs_server::{Jobs, JobsServer},
+    services::jobpb::jobs_client::JobsClient,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Service},
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_codec_protobuf::{RawCodec, GRPC_JSON_CONTENT_TYPE};
+use tonic_dynamic_protobuf::JsonCodec;
+use tonic_types_protobuf::longrunning::Operation;
+
+/// Names operations after the compacted column family.
+struct Compactions;
+
+#[tonic::async_trait]
+impl Jobs for Compactions {
+    async fn compact(
+        &self,
+        request: Request<CompactRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        let mut operation = Operation::new();
+        operation.name = format!("operations/{}", request.into_inner().cf);
+        Ok(Response::new(operation))
+    }
+}
+
+async fn serve() -> Channel {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(JobsServer::new(Compactions))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// A channel that sends requests as `application/grpc+json`, which tonic
+/// clients can not set via metadata.
+#[derive(Clone)]
+struct JsonChannel(Channel);
+
+impl Service<http::Request<BoxBody>> for JsonChannel {
+    type Response = <Channel as Service<http::Request<BoxBody>>>::Response;
+    type Error = <Channel as Service<http::Request<BoxBody>>>::Error;
+    type Future = <Channel as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Service::poll_ready(&mut self.0, cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<BoxBody>) -> Self::Future {
+        let content_type = http::HeaderValue::from_static(GRPC_JSON_CONTENT_TYPE);
+        request
+            .headers_mut()
+            .insert(http::header::CONTENT_TYPE, content_type);
+        self.0.call(request)
+    }
+}
+
+#[tokio::test]
+async fn test_negotiated_encodings() {
+    let channel = serve().await;
+    let path: http::uri::PathAndQuery = "/jobpb.Jobs/Compact".parse().unwrap();
+
+    // Protobuf clients keep working.
+    let mut request = CompactRequest::new();
+    request.cf = "default".to_owned();
+    let operation = JobsClient::new(channel.clone())
+        .compact(request.clone())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(operation.name, "operations/default");
+
+    // The same service answers JSON requests in JSON.
+    let mut grpc = tonic::client::Grpc::new(JsonChannel(channel));
+    grpc.ready().await.unwrap();
+    let codec = JsonCodec::<CompactRequest, Operation>::default();
+    let operation = grpc
+        .unary(Request::new(request), path.clone(), codec)
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(operation.name, "operations/default");
+
+    grpc.ready().await.unwrap();
+    let payload = r#"{"cf": "write"}"#.into();
+    let response = grpc
+        .unary(Request::new(payload), path.clone(), RawCodec::default())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response, r#"{"name":"operations/write"}"#);
+
+    grpc.ready().await.unwrap();
+    let status = grpc
+        .unary(Request::new("{".into()), path, RawCodec::default())
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+}
@@ -0,0 +1,147 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    thread::{self, ThreadId},
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::debug_server::{Debug, DebugServer},
+};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{transport::Channel, Code, Request, Response, Status, Streaming};
+use tonic_codec_protobuf::{Deferred, OffloadCodec, PayloadCodec, ProtobufCodecV3};
+use tonic_testing_protobuf::duplex;
+
+const GET_SERVER_STREAMING: &str = "/debugpb.Debug/GetServerStreaming";
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Streams a value of every size in the key of requests.
+struct Sizes;
+
+#[tonic::async_trait]
+impl Debug for Sizes {
+    async fn get(&self, _: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        request: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let sizes = request.into_inner().key;
+        let responses = sizes.into_iter().map(|size| {
+            let mut response = GetResponse::new();
+            response.value = vec![size; size as usize];
+            response
+        });
+        Ok(Response::new(Box::pin(
+            tokio_stream::iter(responses).map(Ok),
+        )))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+type Codec = ProtobufCodecV3<GetRequest, GetResponse>;
+
+/// Records the threads that decoded payloads, and fails on empty ones.
+#[derive(Clone, Default)]
+struct Recording {
+    threads: Arc<Mutex<Vec<ThreadId>>>,
+}
+
+impl PayloadCodec for Recording {
+    type Encode = GetRequest;
+    type Decode = GetResponse;
+
+    fn encode_payload(&self, item: GetRequest) -> Result<Vec<u8>, String> {
+        Codec::default().encode_payload(item)
+    }
+
+    fn decode_payload(&self, payload: &[u8]) -> Result<GetResponse, String> {
+        self.threads.lock().unwrap().push(thread::current().id());
+        if payload.is_empty() {
+            return Err("empty payload".to_owned());
+        }
+        Codec::default().decode_payload(payload)
+    }
+}
+
+async fn get_server_streaming(
+    channel: Channel,
+    codec: OffloadCodec<Recording>,
+    sizes: &[u8],
+) -> Streaming<Deferred<Recording>> {
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let mut request = GetRequest::new();
+    request.key = sizes.to_vec();
+    grpc.server_streaming(
+        Request::new(request),
+        GET_SERVER_STREAMING.parse().unwrap(),
+        codec,
+    )
+    .await
+    .unwrap()
+    .into_inner()
+}
+
+#[tokio::test]
+async fn test_offload_large_messages() {
+    let channel = duplex::connect_service(DebugServer::new(Sizes)).await;
+    let recording = Recording::default();
+    let codec = OffloadCodec::new(recording.clone(), 64);
+    let mut stream = get_server_streaming(channel, codec, &[8, 200]).await;
+
+    let small = stream.next().await.unwrap().unwrap();
+    assert!(small.len() <= 64, "{small:?}");
+    assert_eq!(small.decode().await.unwrap().value, vec![8; 8]);
+    let large = stream.next().await.unwrap().unwrap();
+    assert!(large.len() > 64, "{large:?}");
+    assert_eq!(large.decode().await.unwrap().value, vec![200; 200]);
+    assert!(stream.next().await.is_none());
+
+    // The test runtime has a single thread, so only the large message was
+    // decoded on another one.
+    let threads = recording.threads.lock().unwrap().clone();
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0], thread::current().id());
+    assert_ne!(threads[1], thread::current().id());
+}
+
+#[tokio::test]
+async fn test_offload_decode_errors() {
+    let channel = duplex::connect_service(DebugServer::new(Sizes)).await;
+    let codec = OffloadCodec::new(Recording::default(), 0);
+    // An empty response is encoded as an empty payload.
+    let mut stream = get_server_streaming(channel, codec, &[0, 1]).await;
+
+    let error = stream.next().await.unwrap().unwrap().decode().await;
+    let status = Status::from(error.unwrap_err());
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "empty payload");
+    let deferred = stream.next().await.unwrap().unwrap();
+    assert_eq!(deferred.decode_inline().unwrap().value, vec![1]);
+}
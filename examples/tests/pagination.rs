@@ -0,0 +1,148 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, KeyValue, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// The page token and the `tenant` header of a call.
+type Call = (String, Option<String>);
+
+/// Serves `keys` in pages of `page_size`, with the offset as page token.
+#[derive(Clone, Default)]
+struct Pages {
+    keys: Vec<&'static str>,
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+#[tonic::async_trait]
+impl Debug for Pages {
+    async fn get(&self, _: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn scan(&self, request: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        let tenant = request
+            .metadata()
+            .get("tenant")
+            .map(|v| v.to_str().unwrap().to_owned());
+        let request = request.into_inner();
+        self.calls
+            .lock()
+            .unwrap()
+            .push((request.page_token.clone(), tenant));
+        let start: usize = match request.page_token.as_str() {
+            "" => 0,
+            token => token
+                .parse()
+                .map_err(|_| Status::invalid_argument("bad token"))?,
+        };
+        let end = (start + request.page_size as usize).min(self.keys.len());
+        let mut response = ScanResponse::new();
+        for key in &self.keys[start..end] {
+            let mut kv = KeyValue::new();
+            kv.key = key.as_bytes().to_vec();
+            response.kvs.push(kv);
+        }
+        if end < self.keys.len() {
+            response.next_page_token = end.to_string();
+        }
+        Ok(Response::new(response))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+fn scan_request(page_size: i32) -> Request<ScanRequest> {
+    let mut scan = ScanRequest::new();
+    scan.page_size = page_size;
+    let mut request = Request::new(scan);
+    request
+        .metadata_mut()
+        .insert("tenant", "a".parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn test_next_item() {
+    let service = Pages {
+        keys: vec!["a", "b", "c", "d", "e"],
+        ..Default::default()
+    };
+    let calls = service.calls.clone();
+    let client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let mut pages = client.scan_pages(scan_request(2));
+    let mut keys = vec![];
+    while let Some(kv) = pages.next_item().await.unwrap() {
+        keys.push(String::from_utf8(kv.key).unwrap());
+    }
+    assert_eq!(keys, ["a", "b", "c", "d", "e"]);
+    assert!(pages.next_item().await.unwrap().is_none());
+
+    let tenant = Some("a".to_owned());
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            (String::new(), tenant.clone()),
+            ("2".to_owned(), tenant.clone()),
+            ("4".to_owned(), tenant),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_next_page() {
+    let service = Pages {
+        keys: vec!["a", "b", "c"],
+        ..Default::default()
+    };
+    let client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let mut request = scan_request(2);
+    request.get_mut().page_token = "1".to_owned();
+    let mut pages = client.scan_pages(request);
+    let page = pages.next_page().await.unwrap().unwrap();
+    assert_eq!(page.kvs.len(), 2);
+    assert_eq!(page.next_page_token, "");
+    assert!(pages.next_page().await.unwrap().is_none());
+
+    let mut request = scan_request(1);
+    request.get_mut().page_token = "x".to_owned();
+    let status = client.scan_pages(request).next_item().await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
@@ -0,0 +1,107 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse},
+    services::debugpb::debug_client::DebugClient,
+};
+use protobuf::Message;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Service, StdError},
+    server::{NamedService, UnaryService},
+    Request, Response, Status,
+};
+use tonic_codec_protobuf::{PreEncoded, PreEncodedCodec, ProtobufCodecV3};
+use tonic_testing_protobuf::duplex;
+
+const GET: &str = "/debugpb.Debug/Get";
+
+/// Answers every request with a response encoded once.
+#[derive(Clone)]
+struct Cached {
+    response: PreEncoded<GetResponse>,
+}
+
+impl UnaryService<GetRequest> for Cached {
+    type Response = PreEncoded<GetResponse>;
+    type Future = BoxFuture<Response<PreEncoded<GetResponse>>, Status>;
+
+    fn call(&mut self, _: Request<GetRequest>) -> Self::Future {
+        let response = self.response.clone();
+        Box::pin(async move { Ok(Response::new(response)) })
+    }
+}
+
+/// Serves `Debug/Get` with the responses of [`Cached`].
+#[derive(Clone)]
+struct CachedDebug {
+    cached: Cached,
+}
+
+impl<B> Service<http::Request<B>> for CachedDebug
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let cached = self.cached.clone();
+        Box::pin(async move {
+            let codec = PreEncodedCodec::<ProtobufCodecV3<GetResponse, GetRequest>>::default();
+            let mut grpc = tonic::server::Grpc::new(codec);
+            Ok(grpc.unary(cached, req).await)
+        })
+    }
+}
+
+impl NamedService for CachedDebug {
+    const NAME: &'static str = "debugpb.Debug";
+}
+
+#[tokio::test]
+async fn test_pre_encoded_responses() {
+    let mut response = GetResponse::new();
+    response.value = b"cached".to_vec();
+    let bytes = response.write_to_bytes().unwrap();
+    let cached = Cached {
+        response: PreEncoded::new(bytes),
+    };
+    let mut client = duplex::client(CachedDebug { cached }, DebugClient::new).await;
+
+    for _ in 0..2 {
+        let got = client.get(GetRequest::new()).await.unwrap().into_inner();
+        assert_eq!(got, response);
+    }
+}
+
+#[tokio::test]
+async fn test_pre_encoded_requests() {
+    let mut response = GetResponse::new();
+    response.value = b"v".to_vec();
+    let cached = Cached {
+        response: PreEncoded::new(response.write_to_bytes().unwrap()),
+    };
+    let channel = duplex::connect_service(CachedDebug { cached }).await;
+
+    let mut request = GetRequest::new();
+    request.key = b"k".to_vec();
+    let request = PreEncoded::<GetRequest>::new(request.write_to_bytes().unwrap());
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let codec = PreEncodedCodec::<ProtobufCodecV3<GetRequest, GetResponse>>::default();
+    let got = grpc
+        .unary(Request::new(request), GET.parse().unwrap(), codec)
+        .await
+        .unwrap();
+    assert_eq!(got.into_inner(), response);
+}
@@ -0,0 +1,44 @@
+use examples::{
+    jobpb::{CompactProgress, CompactResult},
+    services,
+};
+use protobuf::well_known_types::any::Any;
+use tonic_types_protobuf::any::{TypeRegistry, UnpackError};
+
+#[test]
+fn test_register_types() {
+    let mut registry = TypeRegistry::new();
+    services::register_types(&mut registry);
+    assert!(registry.find("jobpb.CompactRequest").is_some());
+    assert!(registry
+        .find("type.googleapis.com/jobpb.CompactResult")
+        .is_some());
+    // Imports and lite runtime files are not registered.
+    assert!(registry.find("google.longrunning.Operation").is_none());
+    assert!(registry.find("debugpb.GetRequest").is_none());
+
+    let mut result = CompactResult::new();
+    result.reclaimed_bytes = 42;
+    let message = registry.unpack(&Any::pack(&result).unwrap()).unwrap();
+    assert_eq!(*message.downcast_box::<CompactResult>().unwrap(), result);
+}
+
+#[test]
+fn test_global_registry() {
+    let mut progress = CompactProgress::new();
+    progress.percent = 50;
+    let any = Any::pack(&progress).unwrap();
+
+    services::register_types(&mut TypeRegistry::global().write().unwrap());
+    let registry = TypeRegistry::global().read().unwrap();
+    let message = registry.unpack(&any).unwrap();
+    assert!(message.downcast_ref::<CompactResult>().is_none());
+    assert_eq!(message.downcast_ref::<CompactProgress>(), Some(&progress));
+
+    let mut any = Any::new();
+    any.type_url = "type.googleapis.com/jobpb.Missing".to_owned();
+    assert!(matches!(
+        registry.unpack(&any),
+        Err(UnpackError::UnknownType(_))
+    ));
+}
@@ -0,0 +1,181 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::{retry_policies, DebugClient},
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio_stream::Stream;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Fails the first `failures` calls with `code`, and delays the first call
+/// by `delay`.
+#[derive(Clone)]
+struct Flaky {
+    calls: Arc<AtomicU32>,
+    failures: u32,
+    code: Code,
+    delay: Duration,
+}
+
+impl Flaky {
+    async fn attempt(&self) -> Result<GetResponse, Status> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst);
+        if n == 0 {
+            tokio::time::sleep(self.delay).await;
+        }
+        if n < self.failures {
+            return Err(Status::new(self.code, format!("attempt {}", n)));
+        }
+        let mut response = GetResponse::new();
+        response.value = n.to_string().into_bytes();
+        Ok(response)
+    }
+}
+
+#[tonic::async_trait]
+impl Debug for Flaky {
+    async fn get(&self, _: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        self.attempt().await.map(Response::new)
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let response = self.attempt().await?;
+        let stream: ResponseStream = Box::pin(tokio_stream::once(Ok(response)));
+        Ok(Response::new(stream))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+fn flaky(failures: u32, code: Code) -> Flaky {
+    Flaky {
+        calls: Arc::default(),
+        failures,
+        code,
+        delay: Duration::ZERO,
+    }
+}
+
+#[test]
+fn test_retry_policy_constants() {
+    let policy = retry_policies::GET;
+    assert_eq!(policy.max_attempts, 3);
+    assert_eq!(policy.retryable_codes, &[Code::Unavailable]);
+    assert!(policy.is_retryable(Code::Unavailable));
+    assert!(!policy.is_retryable(Code::NotFound));
+    assert_eq!(policy.backoff(1), Duration::from_millis(10));
+    assert_eq!(policy.backoff(2), Duration::from_millis(20));
+    assert_eq!(policy.backoff(3), Duration::from_millis(40));
+    assert_eq!(policy.backoff(10), Duration::from_millis(100));
+    assert_eq!(policy.hedging_delay, None);
+
+    let policy = retry_policies::GET_SERVER_STREAMING;
+    assert_eq!(
+        policy.retryable_codes,
+        &[Code::Unavailable, Code::ResourceExhausted]
+    );
+    assert_eq!(policy.hedging_delay, Some(Duration::from_millis(50)));
+}
+
+#[tokio::test]
+async fn test_retry_until_success() {
+    let service = flaky(2, Code::Unavailable);
+    let calls = service.calls.clone();
+    let mut client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let response = client.get_with_retry(GetRequest::new()).await.unwrap();
+    assert_eq!(response.into_inner().value, b"2");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up() {
+    let service = flaky(u32::MAX, Code::Unavailable);
+    let calls = service.calls.clone();
+    let mut client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let status = client.get_with_retry(GetRequest::new()).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(status.message(), "attempt 2");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retry_skips_fatal_codes() {
+    let service = flaky(1, Code::NotFound);
+    let calls = service.calls.clone();
+    let mut client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let status = client.get_with_retry(GetRequest::new()).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_hedging() {
+    // The first attempt is slow, the hedged one answers first.
+    let service = Flaky {
+        delay: Duration::from_secs(5),
+        ..flaky(0, Code::Unavailable)
+    };
+    let calls = service.calls.clone();
+    let mut client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+
+    let start = Instant::now();
+    let mut stream = client
+        .get_server_streaming_with_retry(GetRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(stream.message().await.unwrap().unwrap().value, b"1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // A retryable failure sends the hedged attempt right away.
+    let service = flaky(1, Code::ResourceExhausted);
+    let calls = service.calls.clone();
+    let mut client = duplex::client(DebugServer::new(service), DebugClient::new).await;
+    let mut stream = client
+        .get_server_streaming_with_retry(GetRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(stream.message().await.unwrap().unwrap().value, b"1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
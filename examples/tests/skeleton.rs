@@ -0,0 +1,11 @@
+use examples::{
+    services::{accountpb::AccountsServer, debugpb::DebugServer, jobpb::JobsServer},
+    skeletons::{AccountsService, DebugService, JobsService},
+};
+
+#[test]
+fn test_skeletons_implement_services() {
+    let _ = DebugServer::new(DebugService::default());
+    let _ = JobsServer::new(JobsService::default());
+    let _ = AccountsServer::new(AccountsService::default());
+}
@@ -0,0 +1,107 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio::sync::oneshot;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+use tonic_types_protobuf::stream::{self, ResponseStream};
+
+/// Streams 16 KiB responses until the client goes away, counting the sent ones.
+struct Counter {
+    sent: Arc<AtomicUsize>,
+    done: std::sync::Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[tonic::async_trait]
+impl Debug for Counter {
+    async fn get(&self, _: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream<GetResponse>;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let sent = self.sent.clone();
+        let done = self.done.lock().unwrap().take();
+        Ok(stream::spawn(1, |tx| async move {
+            let mut response = GetResponse::new();
+            response.value = vec![0; 16 * 1024];
+            while tx.send(response.clone()).await.is_ok() {
+                sent.fetch_add(1, Ordering::SeqCst);
+            }
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+        })
+        .into_response())
+    }
+
+    type GetBidirectionalStreamingStream =
+        Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+#[tokio::test]
+async fn test_backpressure() {
+    let sent = Arc::new(AtomicUsize::new(0));
+    let (done_tx, done_rx) = oneshot::channel();
+    let server = DebugServer::new(Counter {
+        sent: sent.clone(),
+        done: std::sync::Mutex::new(Some(done_tx)),
+    });
+    let mut client = duplex::client(server, DebugClient::new).await;
+    let mut responses = client
+        .get_server_streaming(GetRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    for _ in 0..3 {
+        responses.message().await.unwrap().unwrap();
+    }
+
+    // The producer waits for the client instead of queueing responses, once
+    // the buffers of the channel and the transport are full.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let queued = sent.load(Ordering::SeqCst);
+    assert!(queued < 1000, "{queued}");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(sent.load(Ordering::SeqCst), queued);
+
+    // Dropping the stream cancels the call and stops the producer.
+    drop(responses);
+    done_rx.await.unwrap();
+}
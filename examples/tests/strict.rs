@@ -0,0 +1,218 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use examples::jobpb::{CompactProgress, CompactRequest};
+use protobuf::well_known_types::struct_::Struct;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    server::{NamedService, UnaryService},
+    transport::Channel,
+    Code, Request, Response, Status,
+};
+use tonic_codec_protobuf::{PreEncoded, PreEncodedCodec, RawCodec, StrictCodec};
+use tonic_testing_protobuf::duplex;
+use tonic_types_protobuf::longrunning::Operation;
+
+const COMPACT: &str = "/jobpb.Jobs/Compact";
+
+/// Answers requests with their encoded messages.
+#[derive(Clone)]
+struct Echo;
+
+impl UnaryService<Bytes> for Echo {
+    type Response = Bytes;
+    type Future = BoxFuture<Response<Bytes>, Status>;
+
+    fn call(&mut self, request: Request<Bytes>) -> Self::Future {
+        Box::pin(async move { Ok(Response::new(request.into_inner())) })
+    }
+}
+
+impl<B> Service<http::Request<B>> for Echo
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(RawCodec::default());
+            Ok(grpc.unary(Echo, req).await)
+        })
+    }
+}
+
+impl NamedService for Echo {
+    const NAME: &'static str = "jobpb.Jobs";
+}
+
+/// Echoes `bytes`, and strictly decodes them as a `U`.
+async fn echo<T, U>(channel: Channel, bytes: &[u8]) -> Result<U, Status>
+where
+    T: protobuf::Message,
+    U: protobuf::MessageFull,
+{
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+    let codec = PreEncodedCodec::<StrictCodec<T, U>>::default();
+    let request = PreEncoded::new(bytes.to_vec());
+    let response = grpc
+        .unary(Request::new(request), COMPACT.parse().unwrap(), codec)
+        .await?;
+    Ok(response.into_inner())
+}
+
+#[tokio::test]
+async fn test_strict_duplicate_fields() {
+    let channel = duplex::connect_service(Echo).await;
+
+    // `cf = "a"` twice is accepted.
+    let request = echo::<CompactRequest, CompactRequest>(channel.clone(), b"\x0a\x01a\x0a\x01a")
+        .await
+        .unwrap();
+    assert_eq!(request.cf, "a");
+    // An overlong encoding of `percent = 1` is the same value.
+    let progress =
+        echo::<CompactRequest, CompactProgress>(channel.clone(), b"\x08\x01\x08\x81\x00")
+            .await
+            .unwrap();
+    assert_eq!(progress.percent, 1);
+
+    // `cf = "a"` then `cf = "b"` is rejected, instead of keeping "b".
+    let status = echo::<CompactRequest, CompactRequest>(channel.clone(), b"\x0a\x01a\x0a\x01b")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status.message().contains("jobpb.CompactRequest.cf"),
+        "{status}"
+    );
+    let status = echo::<CompactRequest, CompactProgress>(channel.clone(), b"\x08\x01\x08\x02")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    // Malformed messages still fail to parse.
+    let status = echo::<CompactRequest, CompactRequest>(channel, b"\x0a\x05a")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+}
+
+#[tokio::test]
+async fn test_strict_nested_messages() {
+    let channel = duplex::connect_service(Echo).await;
+    let operation =
+        |bytes: &'static [u8]| echo::<CompactRequest, Operation>(channel.clone(), bytes);
+
+    // Repeated fields, e.g. the `details` of the `error`, may appear many
+    // times with different values.
+    let op = operation(b"\x22\x0a\x1a\x03\x0a\x01a\x1a\x03\x0a\x01b")
+        .await
+        .unwrap();
+    assert_eq!(op.error().details.len(), 2);
+
+    // The `type_url` of the `metadata` appears twice.
+    let status = operation(b"\x12\x06\x0a\x01a\x0a\x01b").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status.message().contains("google.protobuf.Any.type_url"),
+        "{status}"
+    );
+    // The `type_url` of an element of `details` appears twice.
+    let status = operation(b"\x22\x08\x1a\x06\x0a\x01a\x0a\x01b")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    // Messages would be merged, which is as ambiguous.
+    let status = operation(b"\x12\x03\x0a\x01a\x12\x03\x0a\x01b")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status
+            .message()
+            .contains("google.longrunning.Operation.metadata"),
+        "{status}"
+    );
+}
+
+#[tokio::test]
+async fn test_strict_deeply_nested_groups() {
+    let channel = duplex::connect_service(Echo).await;
+
+    // Groups of an unknown field nested a million times fail to parse,
+    // instead of overflowing the stack while being checked.
+    let bytes = vec![0x0b; 1 << 20];
+    let status = echo::<CompactRequest, CompactRequest>(channel, &bytes)
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+}
+
+#[tokio::test]
+async fn test_strict_oneofs() {
+    let channel = duplex::connect_service(Echo).await;
+    let operation =
+        |bytes: &'static [u8]| echo::<CompactRequest, Operation>(channel.clone(), bytes);
+
+    // The same field of the `result` oneof twice is accepted.
+    let op = operation(b"\x22\x00\x22\x00").await.unwrap();
+    assert!(op.has_error());
+
+    // Both the `error` and the `response` of the `result` oneof are set.
+    let status = operation(b"\x22\x00\x2a\x00").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status
+            .message()
+            .contains("google.longrunning.Operation.error"),
+        "{status}"
+    );
+}
+
+#[tokio::test]
+async fn test_strict_maps() {
+    let channel = duplex::connect_service(Echo).await;
+    let fields = |bytes: &'static [u8]| echo::<CompactRequest, Struct>(channel.clone(), bytes);
+
+    // `fields["a"] = "x"` twice is accepted, as are different keys.
+    let value = fields(b"\x0a\x08\x0a\x01a\x12\x03\x1a\x01x\x0a\x08\x0a\x01a\x12\x03\x1a\x01x")
+        .await
+        .unwrap();
+    assert_eq!(value.fields["a"].string_value(), "x");
+    let value = fields(b"\x0a\x08\x0a\x01a\x12\x03\x1a\x01x\x0a\x08\x0a\x01b\x12\x03\x1a\x01y")
+        .await
+        .unwrap();
+    assert_eq!(value.fields.len(), 2);
+
+    // `fields["a"] = "x"` then `fields["a"] = "y"` is rejected.
+    let status = fields(b"\x0a\x08\x0a\x01a\x12\x03\x1a\x01x\x0a\x08\x0a\x01a\x12\x03\x1a\x01y")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status.message().contains("google.protobuf.Struct.fields"),
+        "{status}"
+    );
+    // Values of maps are checked too, here both `string_value` and
+    // `bool_value` of `fields["a"]` are set.
+    let status = fields(b"\x0a\x0a\x0a\x01a\x12\x05\x1a\x01x\x20\x01")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status.message().contains("google.protobuf.Value"),
+        "{status}"
+    );
+}
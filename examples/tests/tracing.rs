@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{Debug, DebugServer},
+    },
+};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic_testing_protobuf::duplex;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Answers `get` with the key, unless it is empty.
+struct Echo;
+
+#[tonic::async_trait]
+impl Debug for Echo {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let key = request.into_inner().key;
+        if key.is_empty() {
+            return Err(Status::not_found("no key"));
+        }
+        let mut response = GetResponse::new();
+        response.value = key;
+        Ok(Response::new(response))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        _: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let stream: ResponseStream = Box::pin(tokio_stream::once(Ok(GetResponse::new())));
+        Ok(Response::new(stream))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+/// A span, with its fields formatted.
+#[derive(Debug, Clone, PartialEq)]
+struct Recorded {
+    name: &'static str,
+    fields: BTreeMap<&'static str, String>,
+}
+
+struct Fields<'a>(&'a mut BTreeMap<&'static str, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+/// Records the gRPC call spans created while it is the default subscriber.
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl Recorder {
+    fn spans(&self) -> Vec<Recorded> {
+        self.spans.lock().unwrap().clone()
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Leaves out the spans of h2 and hyper.
+        metadata.is_span() && metadata.fields().field("rpc.system").is_some()
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let mut span = Recorded {
+            name: attrs.metadata().name(),
+            fields: BTreeMap::new(),
+        };
+        attrs.record(&mut Fields(&mut span.fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span);
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let span = &mut spans[id.into_u64() as usize - 1];
+        values.record(&mut Fields(&mut span.fields));
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+fn get_span(code: Code) -> Recorded {
+    let fields = [
+        ("rpc.system", "grpc".to_owned()),
+        ("rpc.service", "debugpb.Debug".to_owned()),
+        ("rpc.method", "Get".to_owned()),
+        ("rpc.kind", "unary".to_owned()),
+        ("rpc.grpc.status_code", (code as i32).to_string()),
+    ];
+    Recorded {
+        name: "/debugpb.Debug/Get",
+        fields: fields.into_iter().collect(),
+    }
+}
+
+#[tokio::test]
+async fn test_tracing_spans() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    let mut client = duplex::client(DebugServer::new(Echo), DebugClient::new).await;
+
+    let mut request = GetRequest::new();
+    request.key = b"k".to_vec();
+    client.get(request).await.unwrap();
+    // One span on each side of the call.
+    assert_eq!(recorder.spans(), [get_span(Code::Ok), get_span(Code::Ok)]);
+
+    let status = client.get(GetRequest::new()).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(
+        recorder.spans()[2..],
+        [get_span(Code::NotFound), get_span(Code::NotFound)]
+    );
+
+    let response = client
+        .get_server_streaming(GetRequest::new())
+        .await
+        .unwrap();
+    let responses: Vec<_> = response.into_inner().collect().await;
+    assert_eq!(responses.len(), 1);
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 6);
+    for span in &spans[4..] {
+        assert_eq!(span.name, "/debugpb.Debug/GetServerStreaming");
+        assert_eq!(span.fields["rpc.kind"], "server_streaming");
+        assert_eq!(span.fields["rpc.grpc.status_code"], "0");
+    }
+}
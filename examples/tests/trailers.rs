@@ -0,0 +1,25 @@
+use examples::{
+    debugpb::ReadStats,
+    services::debugpb::{debug_client::DebugTrailers, debug_server},
+};
+
+#[test]
+fn test_trailer_round_trip() {
+    let mut stats = ReadStats::new();
+    stats.read_bytes = 42;
+
+    let mut response = tonic::Response::new(());
+    assert!(response.read_stats().is_none());
+    debug_server::DebugTrailers::set_read_stats(&mut response, &stats);
+    assert_eq!(response.read_stats().unwrap().unwrap(), stats);
+
+    let mut status = tonic::Status::internal("boom");
+    status.metadata_mut().insert_bin(
+        "read-stats-bin",
+        tonic::metadata::MetadataValue::from_bytes(b"\xff"),
+    );
+    assert_eq!(
+        status.read_stats().unwrap().unwrap_err().code(),
+        tonic::Code::Internal
+    );
+}
@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+use examples::{
+    jobpb::CompactRequest,
+    services::jobpb::{
+        jobs_client::{JobsClient, UdsConnector},
+        jobs_server::{Jobs, JobsServer},
+    },
+};
+use tokio::net::UnixListener;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::{
+    transport::{Endpoint, Server},
+    Request, Response, Status,
+};
+use tonic_types_protobuf::longrunning::Operation;
+
+/// Names operations after the compacted column family.
+struct Compactions;
+
+#[tonic::async_trait]
+impl Jobs for Compactions {
+    async fn compact(
+        &self,
+        request: Request<CompactRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        let mut operation = Operation::new();
+        operation.name = format!("operations/{}", request.into_inner().cf);
+        Ok(Response::new(operation))
+    }
+}
+
+fn compact_request(cf: &str) -> CompactRequest {
+    let mut request = CompactRequest::new();
+    request.cf = cf.to_owned();
+    request
+}
+
+#[tokio::test]
+async fn test_connect_uds() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("jobs.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(JobsServer::new(Compactions))
+            .serve_with_incoming(UnixListenerStream::new(listener)),
+    );
+
+    let mut client = JobsClient::connect_uds(&path).await.unwrap();
+    let operation = client
+        .compact(compact_request("default"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(operation.name, "operations/default");
+
+    // The connector also works with a customized endpoint.
+    let channel = Endpoint::from_static("http://jobs")
+        .timeout(std::time::Duration::from_secs(5))
+        .connect_with_connector(UdsConnector::new(&path))
+        .await
+        .unwrap();
+    let operation = JobsClient::new(channel)
+        .compact(compact_request("write"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(operation.name, "operations/write");
+
+    let missing = dir.path().join("missing.sock");
+    assert!(JobsClient::connect_uds(missing).await.is_err());
+}
@@ -0,0 +1,119 @@
+use examples::{
+    accountpb::{Account, Address, CreateAccountRequest, Plan},
+    services::accountpb::{
+        accounts_client::AccountsClient,
+        accounts_server::{self, Accounts, AccountsServer},
+    },
+};
+use tonic::{Code, Request, Response, Status};
+use tonic_testing_protobuf::duplex;
+use tonic_types_protobuf::{ErrorDetails, StatusExt};
+
+/// Creates every requested account.
+struct Registry;
+
+#[tonic::async_trait]
+impl Accounts for Registry {
+    async fn create_account(
+        &self,
+        request: Request<CreateAccountRequest>,
+    ) -> Result<Response<Account>, Status> {
+        let mut account = Account::new();
+        account.name = request.into_inner().name;
+        Ok(Response::new(account))
+    }
+}
+
+fn valid_request() -> CreateAccountRequest {
+    let mut address = Address::new();
+    address.country = "NL".to_owned();
+    let mut request = CreateAccountRequest::new();
+    request.name = "alice".to_owned();
+    request.email = "alice@example.com".to_owned();
+    request.age = 30;
+    request.plan = Plan::PLAN_PRO.into();
+    request.tags = vec!["admin".to_owned(), "beta".to_owned()];
+    request.address = Some(address).into();
+    request
+}
+
+/// Returns the fields of the violations in `status`.
+fn violated_fields(status: &Status) -> Vec<String> {
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let details = status.get_error_details();
+    let bad_request = details.bad_request().expect("no bad request");
+    bad_request
+        .field_violations
+        .iter()
+        .map(|violation| violation.field.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_validate_requests() {
+    let mut client = duplex::client(AccountsServer::new(Registry), AccountsClient::new).await;
+
+    let response = client.create_account(valid_request()).await.unwrap();
+    assert_eq!(response.into_inner().name, "alice");
+
+    let mut request = valid_request();
+    request.name = String::new();
+    let status = client.create_account(request).await.unwrap_err();
+    assert_eq!(violated_fields(&status), ["name"]);
+    assert_eq!(
+        status.message(),
+        "invalid name: value length must be at least 1 runes"
+    );
+
+    let mut request = valid_request();
+    request.email = "alice".to_owned();
+    request.age = 7;
+    request.plan = Plan::PLAN_UNSPECIFIED.into();
+    request.tags = vec!["beta".to_owned(), String::new(), "beta".to_owned()];
+    request.address.as_mut().unwrap().country = "Netherlands".to_owned();
+    let status = client.create_account(request).await.unwrap_err();
+    assert_eq!(
+        violated_fields(&status),
+        [
+            "email",
+            "age",
+            "plan",
+            "tags",
+            "tags[1]",
+            "address.country",
+            "address.country",
+        ]
+    );
+
+    let mut request = valid_request();
+    request.address.clear();
+    request.plan = protobuf::EnumOrUnknown::from_i32(7);
+    let status = client.create_account(request).await.unwrap_err();
+    assert_eq!(violated_fields(&status), ["plan", "address"]);
+}
+
+#[test]
+fn test_validate_function() {
+    let mut details = ErrorDetails::new();
+    accounts_server::validate_accountpb_create_account_request(
+        &valid_request(),
+        "requests[0].",
+        &mut details,
+    );
+    assert!(accounts_server::validation_status(details).is_none());
+
+    let mut request = valid_request();
+    request.address.as_mut().unwrap().country = "DE".to_owned();
+    let mut details = ErrorDetails::new();
+    accounts_server::validate_accountpb_create_account_request(
+        &request,
+        "requests[0].",
+        &mut details,
+    );
+    let status = accounts_server::validation_status(details).unwrap();
+    assert_eq!(violated_fields(&status), ["requests[0].address.country"]);
+    assert_eq!(
+        status.message(),
+        "invalid requests[0].address.country: value must be in list [\"NL\", \"US\"]"
+    );
+}
@@ -0,0 +1,177 @@
+use std::pin::Pin;
+
+use examples::{
+    debugpb::{GetRequest, GetResponse, ScanRequest, ScanResponse},
+    services::debugpb::{
+        debug_client::DebugClient,
+        debug_server::{self, Debug},
+    },
+};
+use hyper::{body::HttpBody, Body, Method};
+use protobuf::Message;
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, Stream};
+use tonic::{Request, Response, Status, Streaming};
+use tonic_web::GrpcWebClientService;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<GetResponse, Status>> + Send>>;
+
+/// Echoes the key of requests as values.
+#[derive(Clone)]
+struct Echo;
+
+fn echo(request: &GetRequest) -> GetResponse {
+    let mut response = GetResponse::new();
+    response.value.clone_from(&request.key);
+    response
+}
+
+#[tonic::async_trait]
+impl Debug for Echo {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        if request.get_ref().key.is_empty() {
+            return Err(Status::invalid_argument("empty key"));
+        }
+        Ok(Response::new(echo(request.get_ref())))
+    }
+
+    async fn scan(&self, _: Request<ScanRequest>) -> Result<Response<ScanResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_client_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<GetResponse>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    type GetServerStreamingStream = ResponseStream;
+
+    async fn get_server_streaming(
+        &self,
+        request: Request<GetRequest>,
+    ) -> Result<Response<Self::GetServerStreamingStream>, Status> {
+        let response = echo(request.get_ref());
+        let stream: ResponseStream =
+            Box::pin(tokio_stream::iter(vec![Ok(response.clone()), Ok(response)]));
+        Ok(Response::new(stream))
+    }
+
+    type GetBidirectionalStreamingStream = ResponseStream;
+
+    async fn get_bidirectional_streaming(
+        &self,
+        _: Request<Streaming<GetRequest>>,
+    ) -> Result<Response<Self::GetBidirectionalStreamingStream>, Status> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+/// Serves `Echo` to browsers of `https://app.example.com`, and returns the
+/// address of the server.
+async fn serve() -> String {
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::exact(
+            "https://app.example.com".parse().unwrap(),
+        ))
+        .allow_headers(tower_http::cors::Any)
+        .allow_methods([Method::POST]);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        debug_server::web_server_builder(cors)
+            .add_service(debug_server::web_service(Echo))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    format!("http://{addr}")
+}
+
+fn get_request(key: &[u8]) -> GetRequest {
+    let mut request = GetRequest::new();
+    request.key = key.to_vec();
+    request
+}
+
+#[tokio::test]
+async fn test_generated_client_over_grpc_web() {
+    let origin = serve().await;
+    let http = hyper::Client::builder().build_http();
+    let mut client =
+        DebugClient::with_origin(GrpcWebClientService::new(http), origin.parse().unwrap());
+
+    let response = client.get(get_request(b"k")).await.unwrap().into_inner();
+    assert_eq!(response.value, b"k");
+
+    let status = client.get(get_request(b"")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert_eq!(status.message(), "empty key");
+
+    let mut stream = client
+        .get_server_streaming(get_request(b"s"))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut values = vec![];
+    while let Some(response) = stream.message().await.unwrap() {
+        values.push(response.value);
+    }
+    assert_eq!(values, [b"s", b"s"]);
+}
+
+#[tokio::test]
+async fn test_browser_requests() {
+    let origin = serve().await;
+    let client = hyper::Client::new();
+
+    // The CORS preflight of a browser.
+    let preflight = hyper::Request::builder()
+        .method(Method::OPTIONS)
+        .uri(format!("{origin}/debugpb.Debug/Get"))
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "content-type,x-grpc-web")
+        .body(Body::empty())
+        .unwrap();
+    let response = client.request(preflight).await.unwrap();
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+
+    // A grpc-web call, framed like gRPC with the status in a trailer frame.
+    let message = get_request(b"web").write_to_bytes().unwrap();
+    let mut body = vec![0];
+    body.extend((message.len() as u32).to_be_bytes());
+    body.extend(message);
+    let call = hyper::Request::builder()
+        .method(Method::POST)
+        .uri(format!("{origin}/debugpb.Debug/Get"))
+        .header("origin", "https://app.example.com")
+        .header("content-type", "application/grpc-web+proto")
+        .header("x-grpc-web", "1")
+        .body(Body::from(body))
+        .unwrap();
+    let mut response = client.request(call).await.unwrap();
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/grpc-web+proto"
+    );
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    let mut body = vec![];
+    while let Some(chunk) = response.data().await {
+        body.extend(chunk.unwrap());
+    }
+    assert_eq!(body[0], 0);
+    let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+    let reply = GetResponse::parse_from_bytes(&body[5..5 + len]).unwrap();
+    assert_eq!(reply.value, b"web");
+    let trailers = &body[5 + len..];
+    assert_eq!(trailers[0], 0x80);
+    let trailers = String::from_utf8_lossy(&trailers[5..]).to_lowercase();
+    assert!(trailers.contains("grpc-status:0"), "{trailers}");
+}
@@ -0,0 +1,19 @@
+//! Decodes request bodies with `DynamicDecoder`, and prints the decoded
+//! messages as JSON like `grpc-call` does.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protobuf::{well_known_types::struct_::Struct, MessageFull};
+use tonic_codec_protobuf::DynamicDecoder;
+use tonic_dynamic_protobuf::json;
+use tonic_protobuf_fuzz::decode_stream;
+
+fuzz_target!(|data: &[u8]| {
+    // `Struct` is mutually recursive with `Value` and `ListValue`, and has
+    // special JSON mappings.
+    let decoder = DynamicDecoder::new(Struct::descriptor());
+    decode_stream(decoder, data, |message| {
+        json::print_to_string(&*message);
+    });
+});
@@ -0,0 +1,12 @@
+//! Decodes request bodies with `ProtobufDecoderV2`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protobuf2::descriptor::FileDescriptorProto;
+use tonic_codec_protobuf::ProtobufDecoderV2;
+use tonic_protobuf_fuzz::decode_stream;
+
+fuzz_target!(|data: &[u8]| {
+    decode_stream(ProtobufDecoderV2::<FileDescriptorProto>::new(), data, drop);
+});
@@ -0,0 +1,16 @@
+//! Decodes request bodies with `ProtobufDecoderV3`.
+//!
+//! `FileDescriptorProto` covers most field kinds: nested and recursive
+//! messages, enums, maps of options, packed and unpacked repeated fields and
+//! proto2 presence.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protobuf::descriptor::FileDescriptorProto;
+use tonic_codec_protobuf::ProtobufDecoderV3;
+use tonic_protobuf_fuzz::decode_stream;
+
+fuzz_target!(|data: &[u8]| {
+    decode_stream(ProtobufDecoderV3::<FileDescriptorProto>::new(), data, drop);
+});
@@ -0,0 +1,18 @@
+//! Splits captures into gRPC frames, as `grpc-decode` does.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tonic_dynamic_protobuf::frame;
+
+fuzz_target!(|data: &[u8]| {
+    let mut total = 0;
+    for frame in frame::frames(data) {
+        let Ok(frame) = frame else {
+            break;
+        };
+        assert!(frame.offset + frame::HEADER_LEN + frame.data.len() <= data.len());
+        total += frame::HEADER_LEN + frame.data.len();
+    }
+    assert!(total <= data.len());
+});
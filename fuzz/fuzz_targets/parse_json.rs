@@ -0,0 +1,18 @@
+//! Parses JSON into dynamic messages, and prints the parsed messages back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protobuf::{descriptor::FileDescriptorProto, well_known_types::struct_::Struct, MessageFull};
+use tonic_dynamic_protobuf::json;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    for descriptor in [FileDescriptorProto::descriptor(), Struct::descriptor()] {
+        if let Ok(message) = json::parse_from_str(&descriptor, input) {
+            json::print_to_string(&*message);
+        }
+    }
+});
@@ -0,0 +1,34 @@
+//! Shared harness of the fuzz targets.
+//!
+//! The targets feed the fuzzer input to the decoders exactly as tonic does
+//! with a request body read from the network: the bytes are split into
+//! length-prefixed gRPC frames by `tonic::Streaming`, and each frame is handed
+//! to the decoder through a `DecodeBuf`.
+
+use bytes::Bytes;
+use tonic::{codec::Decoder, transport::Body, Status, Streaming};
+
+/// The largest message accepted by the targets, tonic's default limit for
+/// servers. Frames announcing a larger message are rejected before their
+/// payload is buffered, which keeps the memory use of a run bounded.
+pub const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Decodes the messages of the request body `data` with `decoder`, and
+/// passes each of them to `on_message` until the stream ends or fails.
+///
+/// Errors are expected for most inputs, only panics are bugs.
+pub fn decode_stream<D, T>(decoder: D, data: &[u8], mut on_message: impl FnMut(T))
+where
+    D: Decoder<Item = T, Error = Status> + Send + 'static,
+    T: Send + 'static,
+{
+    // `new_request` is hidden from the docs of tonic, but it is the only
+    // public way to drive a decoder with a body outside of a server.
+    let body = Body::from(Bytes::copy_from_slice(data));
+    let mut stream = Streaming::new_request(decoder, body, None, Some(MAX_MESSAGE_SIZE));
+    futures::executor::block_on(async {
+        while let Ok(Some(message)) = stream.message().await {
+            on_message(message);
+        }
+    });
+}
@@ -0,0 +1,43 @@
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("No OUT_DIR defined");
+    let protos = [
+        "proto/grpc/testing/empty.proto",
+        "proto/grpc/testing/messages.proto",
+        "proto/grpc/testing/test.proto",
+    ];
+
+    // Generate protobuf structs.
+    protobuf_codegen::Codegen::new()
+        .include("proto")
+        .inputs(protos)
+        .out_dir(&out_dir)
+        .run()
+        .unwrap();
+
+    // Generate tonic service stubs.
+    tonic_build_protobuf::Builder::new()
+        .out_dir(&out_dir)
+        .proto_path("crate::pb")
+        .file_name(|_pkg, svc| format!("{svc}_tonic"))
+        .compile(&protos, &["proto"]);
+
+    // Generate mod file.
+    let content = r"
+pub mod empty;
+pub mod messages;
+pub mod test;
+pub mod test_service_tonic;
+pub mod unimplemented_service_tonic;
+";
+    let mod_path = std::path::Path::new(&out_dir).join("mod.rs");
+    let previous_content = std::fs::read(&mod_path);
+    if previous_content
+        .map(|previous_content| previous_content != content.as_bytes())
+        .unwrap_or(true)
+    {
+        std::fs::write(mod_path, content).unwrap();
+    }
+    for proto in protos {
+        println!("cargo:rerun-if-changed={proto}");
+    }
+}
@@ -0,0 +1,44 @@
+//! The interop test client.
+//!
+//! Usage: `client [--server_host=HOST] [--server_port=PORT]
+//! [--test_case=CASES]`, where `CASES` is a comma separated list of test cases,
+//! all supported test cases are run by default.
+
+use interop::client::TestCase;
+use tonic::transport::Endpoint;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut host = "localhost".to_owned();
+    let mut port = 10000u16;
+    let mut cases = TestCase::ALL.to_vec();
+    for arg in std::env::args().skip(1) {
+        match arg.split_once('=') {
+            Some(("--server_host", value)) => host = value.to_owned(),
+            Some(("--server_port", value)) => port = value.parse()?,
+            Some(("--test_case", value)) => {
+                cases = value.split(',').map(str::parse).collect::<Result<_, _>>()?;
+            }
+            Some(("--use_tls", "false")) => {}
+            _ => return Err(format!("unsupported argument: {arg}").into()),
+        }
+    }
+
+    let channel = Endpoint::from_shared(format!("http://{host}:{port}"))?
+        .connect()
+        .await?;
+    let mut failures = 0;
+    for case in cases {
+        match case.run(channel.clone()).await {
+            Ok(()) => println!("{case}: passed"),
+            Err(failure) => {
+                failures += 1;
+                println!("{case}: FAILED: {failure}");
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format!("{failures} test case(s) failed").into());
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! The interop test server.
+//!
+//! Usage: `server [--port=PORT]`, the port defaults to 10000.
+
+use std::net::SocketAddr;
+
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut port = 10000;
+    for arg in std::env::args().skip(1) {
+        match arg.split_once('=') {
+            Some(("--port", value)) => port = value.parse()?,
+            Some(("--use_tls", "false")) => {}
+            _ => return Err(format!("unsupported argument: {arg}").into()),
+        }
+    }
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = TcpListener::bind(addr).await?;
+    println!("interop server listening on {addr}");
+    interop::server::serve(listener).await?;
+    Ok(())
+}
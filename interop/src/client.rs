@@ -0,0 +1,390 @@
+//! The interop test cases run by the client.
+
+use std::{fmt, str::FromStr};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    metadata::{BinaryMetadataValue, MetadataValue},
+    transport::Channel,
+    Code, Request,
+};
+
+use crate::{
+    pb::{
+        empty::Empty,
+        messages::{
+            EchoStatus, ResponseParameters, SimpleRequest, StreamingInputCallRequest,
+            StreamingOutputCallRequest,
+        },
+        test_service_tonic::test_service_client::TestServiceClient,
+        unimplemented_service_tonic::unimplemented_service_client::UnimplementedServiceClient,
+    },
+    zeros,
+};
+
+const LARGE_REQUEST_SIZE: i32 = 271828;
+const LARGE_RESPONSE_SIZE: i32 = 314159;
+const REQUEST_SIZES: [i32; 4] = [27182, 8, 1828, 45904];
+const RESPONSE_SIZES: [i32; 4] = [31415, 9, 2653, 58979];
+const TEST_STATUS_MESSAGE: &str = "test status message";
+const SPECIAL_STATUS_MESSAGE: &str =
+    "\t\ntest with whitespace\r\nand Unicode BMP ☺ and non-BMP 😈\t\n";
+const ECHO_INITIAL_KEY: &str = "x-grpc-test-echo-initial";
+const ECHO_INITIAL_VALUE: &str = "test_initial_metadata_value";
+const ECHO_TRAILING_KEY: &str = "x-grpc-test-echo-trailing-bin";
+const ECHO_TRAILING_VALUE: &[u8] = &[0xab, 0xab, 0xab];
+
+/// An interop test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestCase {
+    EmptyUnary,
+    LargeUnary,
+    ClientStreaming,
+    ServerStreaming,
+    PingPong,
+    EmptyStream,
+    StatusCodeAndMessage,
+    SpecialStatusMessage,
+    UnimplementedMethod,
+    UnimplementedService,
+    CustomMetadata,
+}
+
+impl TestCase {
+    /// All supported test cases, in the order they are run by default.
+    pub const ALL: &'static [TestCase] = &[
+        TestCase::EmptyUnary,
+        TestCase::LargeUnary,
+        TestCase::ClientStreaming,
+        TestCase::ServerStreaming,
+        TestCase::PingPong,
+        TestCase::EmptyStream,
+        TestCase::StatusCodeAndMessage,
+        TestCase::SpecialStatusMessage,
+        TestCase::UnimplementedMethod,
+        TestCase::UnimplementedService,
+        TestCase::CustomMetadata,
+    ];
+
+    /// The name of the test case, e.g. `empty_unary`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestCase::EmptyUnary => "empty_unary",
+            TestCase::LargeUnary => "large_unary",
+            TestCase::ClientStreaming => "client_streaming",
+            TestCase::ServerStreaming => "server_streaming",
+            TestCase::PingPong => "ping_pong",
+            TestCase::EmptyStream => "empty_stream",
+            TestCase::StatusCodeAndMessage => "status_code_and_message",
+            TestCase::SpecialStatusMessage => "special_status_message",
+            TestCase::UnimplementedMethod => "unimplemented_method",
+            TestCase::UnimplementedService => "unimplemented_service",
+            TestCase::CustomMetadata => "custom_metadata",
+        }
+    }
+
+    /// Runs the test case against the server behind `channel`.
+    pub async fn run(&self, channel: Channel) -> Result<(), Failure> {
+        let mut client = TestServiceClient::new(channel.clone());
+        match self {
+            TestCase::EmptyUnary => empty_unary(&mut client).await,
+            TestCase::LargeUnary => large_unary(&mut client).await,
+            TestCase::ClientStreaming => client_streaming(&mut client).await,
+            TestCase::ServerStreaming => server_streaming(&mut client).await,
+            TestCase::PingPong => ping_pong(&mut client).await,
+            TestCase::EmptyStream => empty_stream(&mut client).await,
+            TestCase::StatusCodeAndMessage => {
+                status(&mut client, Code::Unknown, TEST_STATUS_MESSAGE).await
+            }
+            TestCase::SpecialStatusMessage => {
+                status(&mut client, Code::Unknown, SPECIAL_STATUS_MESSAGE).await
+            }
+            TestCase::UnimplementedMethod => unimplemented_method(&mut client).await,
+            TestCase::UnimplementedService => {
+                unimplemented_service(UnimplementedServiceClient::new(channel)).await
+            }
+            TestCase::CustomMetadata => custom_metadata(&mut client).await,
+        }
+    }
+}
+
+impl fmt::Display for TestCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TestCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TestCase::ALL
+            .iter()
+            .find(|case| case.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unsupported test case: {s}"))
+    }
+}
+
+/// Why a test case failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure(String);
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl From<tonic::Status> for Failure {
+    fn from(status: tonic::Status) -> Self {
+        Failure(format!("unexpected status: {status}"))
+    }
+}
+
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err(Failure(format!($($arg)+)));
+        }
+    };
+}
+
+type Client = TestServiceClient<Channel>;
+
+async fn empty_unary(client: &mut Client) -> Result<(), Failure> {
+    let response = client.empty_call(Empty::new()).await?.into_inner();
+    ensure!(response == Empty::new(), "response is not empty");
+    Ok(())
+}
+
+fn large_request() -> SimpleRequest {
+    let mut request = SimpleRequest::new();
+    request.response_size = LARGE_RESPONSE_SIZE;
+    request.payload = Some(zeros(LARGE_REQUEST_SIZE)).into();
+    request
+}
+
+async fn large_unary(client: &mut Client) -> Result<(), Failure> {
+    let response = client.unary_call(large_request()).await?.into_inner();
+    let body = &response.payload.body;
+    ensure!(
+        body.len() == LARGE_RESPONSE_SIZE as usize,
+        "payload size is {}, want {}",
+        body.len(),
+        LARGE_RESPONSE_SIZE
+    );
+    ensure!(body.iter().all(|b| *b == 0), "payload is not zeroed");
+    Ok(())
+}
+
+async fn client_streaming(client: &mut Client) -> Result<(), Failure> {
+    let requests = REQUEST_SIZES.map(|size| {
+        let mut request = StreamingInputCallRequest::new();
+        request.payload = Some(zeros(size)).into();
+        request
+    });
+    let response = client
+        .streaming_input_call(tokio_stream::iter(requests))
+        .await?
+        .into_inner();
+    let want: i32 = REQUEST_SIZES.iter().sum();
+    ensure!(
+        response.aggregated_payload_size == want,
+        "aggregated payload size is {}, want {}",
+        response.aggregated_payload_size,
+        want
+    );
+    Ok(())
+}
+
+fn streaming_request(sizes: &[i32]) -> StreamingOutputCallRequest {
+    let mut request = StreamingOutputCallRequest::new();
+    for size in sizes {
+        let mut param = ResponseParameters::new();
+        param.size = *size;
+        request.response_parameters.push(param);
+    }
+    request
+}
+
+async fn server_streaming(client: &mut Client) -> Result<(), Failure> {
+    let mut stream = client
+        .streaming_output_call(streaming_request(&RESPONSE_SIZES))
+        .await?
+        .into_inner();
+    let mut sizes = vec![];
+    while let Some(response) = stream.message().await? {
+        sizes.push(response.payload.body.len() as i32);
+    }
+    ensure!(
+        sizes == RESPONSE_SIZES,
+        "response sizes are {:?}, want {:?}",
+        sizes,
+        RESPONSE_SIZES
+    );
+    Ok(())
+}
+
+async fn ping_pong(client: &mut Client) -> Result<(), Failure> {
+    let (tx, rx) = mpsc::channel(1);
+    let mut stream = client
+        .full_duplex_call(ReceiverStream::new(rx))
+        .await?
+        .into_inner();
+    for (request_size, response_size) in REQUEST_SIZES.into_iter().zip(RESPONSE_SIZES) {
+        let mut request = streaming_request(&[response_size]);
+        request.payload = Some(zeros(request_size)).into();
+        ensure!(tx.send(request).await.is_ok(), "request stream is closed");
+
+        let response = stream.message().await?;
+        let size = response.map(|r| r.payload.body.len() as i32);
+        ensure!(
+            size == Some(response_size),
+            "response size is {:?}, want {}",
+            size,
+            response_size
+        );
+    }
+    drop(tx);
+    ensure!(
+        stream.message().await?.is_none(),
+        "unexpected response after the request stream is closed"
+    );
+    Ok(())
+}
+
+async fn empty_stream(client: &mut Client) -> Result<(), Failure> {
+    let mut stream = client
+        .full_duplex_call(tokio_stream::empty())
+        .await?
+        .into_inner();
+    ensure!(
+        stream.message().await?.is_none(),
+        "response stream is not empty"
+    );
+    Ok(())
+}
+
+fn check_status(status: &tonic::Status, code: Code, message: &str) -> Result<(), Failure> {
+    ensure!(
+        status.code() == code && status.message() == message,
+        "status is {:?} {:?}, want {:?} {:?}",
+        status.code(),
+        status.message(),
+        code,
+        message
+    );
+    Ok(())
+}
+
+async fn status(client: &mut Client, code: Code, message: &str) -> Result<(), Failure> {
+    let mut echo = EchoStatus::new();
+    echo.code = code as i32;
+    echo.message = message.to_owned();
+
+    let mut request = SimpleRequest::new();
+    request.response_status = Some(echo.clone()).into();
+    match client.unary_call(request).await {
+        Ok(_) => return Err(Failure("unary call succeeded".to_owned())),
+        Err(status) => check_status(&status, code, message)?,
+    }
+
+    let mut request = StreamingOutputCallRequest::new();
+    request.response_status = Some(echo).into();
+    let result = match client.full_duplex_call(tokio_stream::iter([request])).await {
+        Ok(response) => response.into_inner().message().await.map(|_| ()),
+        Err(status) => Err(status),
+    };
+    match result {
+        Ok(()) => Err(Failure("full duplex call succeeded".to_owned())),
+        Err(status) => check_status(&status, code, message),
+    }
+}
+
+async fn unimplemented_method(client: &mut Client) -> Result<(), Failure> {
+    match client.unimplemented_call(Empty::new()).await {
+        Ok(_) => Err(Failure("unimplemented call succeeded".to_owned())),
+        Err(status) => {
+            ensure!(
+                status.code() == Code::Unimplemented,
+                "status code is {:?}, want Unimplemented",
+                status.code()
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn unimplemented_service(
+    mut client: UnimplementedServiceClient<Channel>,
+) -> Result<(), Failure> {
+    match client.unimplemented_call(Empty::new()).await {
+        Ok(_) => Err(Failure("unimplemented service call succeeded".to_owned())),
+        Err(status) => {
+            ensure!(
+                status.code() == Code::Unimplemented,
+                "status code is {:?}, want Unimplemented",
+                status.code()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn with_echo_metadata<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    let metadata = request.metadata_mut();
+    metadata.insert(
+        ECHO_INITIAL_KEY,
+        MetadataValue::from_static(ECHO_INITIAL_VALUE),
+    );
+    metadata.insert_bin(
+        ECHO_TRAILING_KEY,
+        BinaryMetadataValue::from_bytes(ECHO_TRAILING_VALUE),
+    );
+    request
+}
+
+fn check_echo_metadata(
+    initial: &tonic::metadata::MetadataMap,
+    trailing: &tonic::metadata::MetadataMap,
+) -> Result<(), Failure> {
+    let value = initial.get(ECHO_INITIAL_KEY);
+    ensure!(
+        value.is_some_and(|v| v == ECHO_INITIAL_VALUE),
+        "initial metadata is {:?}, want {:?}",
+        value,
+        ECHO_INITIAL_VALUE
+    );
+    let value = trailing.get_bin(ECHO_TRAILING_KEY);
+    ensure!(
+        value.and_then(|v| v.to_bytes().ok()).as_deref() == Some(ECHO_TRAILING_VALUE),
+        "trailing metadata is {:?}, want {:?}",
+        value,
+        ECHO_TRAILING_VALUE
+    );
+    Ok(())
+}
+
+async fn custom_metadata(client: &mut Client) -> Result<(), Failure> {
+    // tonic merges the trailers of a unary call into the response metadata.
+    let response = client
+        .unary_call(with_echo_metadata(large_request()))
+        .await?;
+    check_echo_metadata(response.metadata(), response.metadata())?;
+
+    let mut request = streaming_request(&[LARGE_RESPONSE_SIZE]);
+    request.payload = Some(zeros(LARGE_REQUEST_SIZE)).into();
+    let response = client
+        .full_duplex_call(with_echo_metadata(tokio_stream::iter([request])))
+        .await?;
+    let initial = response.metadata().clone();
+    let mut stream = response.into_inner();
+    while stream.message().await?.is_some() {}
+    let trailing = stream.trailers().await?.unwrap_or_default();
+    check_echo_metadata(&initial, &trailing)
+}
@@ -0,0 +1,24 @@
+//! gRPC interoperability test client and server.
+//!
+//! Implements the test cases described in
+//! <https://github.com/grpc/grpc/blob/master/doc/interop-test-descriptions.md>
+//! with stubs generated by `tonic-build-protobuf`, so that this crate can be
+//! checked against the gRPC implementations of other languages.
+
+pub mod pb {
+    //! Generated types of `grpc.testing`.
+
+    include!(concat!(env!("OUT_DIR"), "/mod.rs"));
+}
+
+pub mod client;
+pub mod server;
+
+use pb::messages::Payload;
+
+/// Returns a payload with `size` zero bytes.
+pub(crate) fn zeros(size: i32) -> Payload {
+    let mut payload = Payload::new();
+    payload.body = vec![0; size as usize];
+    payload
+}
@@ -0,0 +1,274 @@
+//! The interop test server.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use http_body::Body;
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::{
+    body::BoxBody,
+    codegen::{
+        http::{self, HeaderMap, HeaderName},
+        BoxFuture, Service,
+    },
+    transport::Server,
+    Code, Request, Response, Status, Streaming,
+};
+use tower_layer::Layer;
+
+use crate::{
+    pb::{
+        empty::Empty,
+        messages::{
+            EchoStatus, SimpleRequest, SimpleResponse, StreamingInputCallRequest,
+            StreamingInputCallResponse, StreamingOutputCallRequest, StreamingOutputCallResponse,
+        },
+        test_service_tonic::test_service_server::{self, TestServiceServer},
+    },
+    zeros,
+};
+
+type Result<T> = std::result::Result<Response<T>, Status>;
+type ResponseStream = ReceiverStream<std::result::Result<StreamingOutputCallResponse, Status>>;
+
+const ECHO_INITIAL_KEY: &str = "x-grpc-test-echo-initial";
+const ECHO_TRAILING_KEY: &str = "x-grpc-test-echo-trailing-bin";
+
+/// Serves `grpc.testing.TestService` on `listener` until it fails.
+///
+/// `grpc.testing.UnimplementedService` is deliberately not served.
+pub async fn serve(listener: TcpListener) -> std::result::Result<(), tonic::transport::Error> {
+    Server::builder()
+        .layer(EchoHeadersLayer)
+        .add_service(TestServiceServer::new(TestService))
+        .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+        .await
+}
+
+/// The implementation of `grpc.testing.TestService`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TestService;
+
+#[allow(clippy::result_large_err)]
+fn echo_status(status: Option<&EchoStatus>) -> std::result::Result<(), Status> {
+    match status {
+        Some(status) if status.code != 0 => Err(Status::new(
+            Code::from_i32(status.code),
+            status.message.clone(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Sends the responses requested by `request` to `tx`, returns false if the
+/// client went away.
+async fn stream_responses(
+    request: StreamingOutputCallRequest,
+    tx: &mpsc::Sender<std::result::Result<StreamingOutputCallResponse, Status>>,
+) -> bool {
+    if let Err(status) = echo_status(request.response_status.as_ref()) {
+        let _ = tx.send(Err(status)).await;
+        return false;
+    }
+    for param in &request.response_parameters {
+        if param.interval_us > 0 {
+            tokio::time::sleep(Duration::from_micros(param.interval_us as u64)).await;
+        }
+        let mut response = StreamingOutputCallResponse::new();
+        response.payload = Some(zeros(param.size)).into();
+        if tx.send(Ok(response)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+#[tonic::async_trait]
+impl test_service_server::TestService for TestService {
+    async fn empty_call(&self, _request: Request<Empty>) -> Result<Empty> {
+        Ok(Response::new(Empty::new()))
+    }
+
+    async fn unary_call(&self, request: Request<SimpleRequest>) -> Result<SimpleResponse> {
+        let request = request.into_inner();
+        echo_status(request.response_status.as_ref())?;
+
+        let mut response = SimpleResponse::new();
+        response.payload = Some(zeros(request.response_size)).into();
+        Ok(Response::new(response))
+    }
+
+    async fn cacheable_unary_call(
+        &self,
+        request: Request<SimpleRequest>,
+    ) -> Result<SimpleResponse> {
+        self.unary_call(request).await
+    }
+
+    type StreamingOutputCallStream = ResponseStream;
+
+    async fn streaming_output_call(
+        &self,
+        request: Request<StreamingOutputCallRequest>,
+    ) -> Result<Self::StreamingOutputCallStream> {
+        let request = request.into_inner();
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            stream_responses(request, &tx).await;
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn streaming_input_call(
+        &self,
+        request: Request<Streaming<StreamingInputCallRequest>>,
+    ) -> Result<StreamingInputCallResponse> {
+        let mut stream = request.into_inner();
+        let mut aggregated_payload_size = 0;
+        while let Some(request) = stream.message().await? {
+            aggregated_payload_size += request.payload.body.len() as i32;
+        }
+
+        let mut response = StreamingInputCallResponse::new();
+        response.aggregated_payload_size = aggregated_payload_size;
+        Ok(Response::new(response))
+    }
+
+    type FullDuplexCallStream = ResponseStream;
+
+    async fn full_duplex_call(
+        &self,
+        request: Request<Streaming<StreamingOutputCallRequest>>,
+    ) -> Result<Self::FullDuplexCallStream> {
+        let mut stream = request.into_inner();
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            while let Some(request) = stream.next().await {
+                let request = match request {
+                    Ok(request) => request,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+                if !stream_responses(request, &tx).await {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type HalfDuplexCallStream = ResponseStream;
+
+    async fn half_duplex_call(
+        &self,
+        _request: Request<Streaming<StreamingOutputCallRequest>>,
+    ) -> Result<Self::HalfDuplexCallStream> {
+        Err(Status::unimplemented("HalfDuplexCall is not implemented"))
+    }
+
+    async fn unimplemented_call(&self, _request: Request<Empty>) -> Result<Empty> {
+        Err(Status::unimplemented(""))
+    }
+}
+
+/// Echoes the `x-grpc-test-echo-initial` request header as a response header
+/// and `x-grpc-test-echo-trailing-bin` as a trailer, as required by the
+/// `custom_metadata` test case.
+#[derive(Debug, Clone, Copy)]
+struct EchoHeadersLayer;
+
+impl<S> Layer<S> for EchoHeadersLayer {
+    type Service = EchoHeadersSvc<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        EchoHeadersSvc { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EchoHeadersSvc<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for EchoHeadersSvc<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let initial = request.headers().get(ECHO_INITIAL_KEY).cloned();
+        let trailing = request.headers().get(ECHO_TRAILING_KEY).cloned();
+        let call = self.inner.call(request);
+
+        Box::pin(async move {
+            let mut response = call.await?;
+            if let Some(initial) = initial {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(ECHO_INITIAL_KEY), initial);
+            }
+            let trailers = trailing.map(|trailing| {
+                let mut trailers = HeaderMap::new();
+                trailers.insert(HeaderName::from_static(ECHO_TRAILING_KEY), trailing);
+                trailers
+            });
+            Ok(response.map(|body| BoxBody::new(MergeTrailers { body, trailers })))
+        })
+    }
+}
+
+/// A body that appends `trailers` to the trailers of `body`.
+struct MergeTrailers {
+    body: BoxBody,
+    trailers: Option<HeaderMap>,
+}
+
+impl Body for MergeTrailers {
+    type Data = <BoxBody as Body>::Data;
+    type Error = <BoxBody as Body>::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.body).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<Option<HeaderMap>, Self::Error>> {
+        let this = &mut *self;
+        Pin::new(&mut this.body)
+            .poll_trailers(cx)
+            .map_ok(|trailers| match (trailers, this.trailers.take()) {
+                (Some(mut trailers), Some(extra)) => {
+                    trailers.extend(extra);
+                    Some(trailers)
+                }
+                (trailers, extra) => trailers.or(extra),
+            })
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream() && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
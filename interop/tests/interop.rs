@@ -0,0 +1,21 @@
+use interop::client::TestCase;
+use tokio::net::TcpListener;
+use tonic::transport::Endpoint;
+
+#[tokio::test]
+async fn test_all_cases() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(interop::server::serve(listener));
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    for case in TestCase::ALL {
+        if let Err(failure) = case.run(channel.clone()).await {
+            panic!("{case}: {failure}");
+        }
+    }
+}
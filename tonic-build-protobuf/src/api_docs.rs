@@ -0,0 +1,324 @@
+//! Generation of a Markdown reference of the compiled services, see
+//! [`Builder::api_docs`](crate::Builder::api_docs).
+
+use std::collections::{HashMap, HashSet};
+
+use protobuf::descriptor::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+
+use crate::ParsedProtos;
+
+/// Field numbers of `FileDescriptorProto` and `DescriptorProto`, which make
+/// up the paths of source code locations.
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+/// A message or enum, with the comments of the file defining it.
+struct Definition<'a> {
+    kind: Kind<'a>,
+    comments: &'a Comments,
+    /// The source code location path of the definition.
+    path: Vec<i32>,
+}
+
+enum Kind<'a> {
+    Message(&'a DescriptorProto),
+    Enum(&'a EnumDescriptorProto),
+}
+
+/// The comments of a file, by source code location path.
+#[derive(Default)]
+struct Comments(HashMap<Vec<i32>, String>);
+
+impl Comments {
+    fn new(fd: &FileDescriptorProto) -> Self {
+        let comments = fd
+            .source_code_info
+            .location
+            .iter()
+            .filter_map(|location| {
+                let comment = match location.leading_comments() {
+                    "" => location.trailing_comments(),
+                    leading => leading,
+                };
+                let comment = comment
+                    .lines()
+                    .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let comment = comment.trim();
+                (!comment.is_empty()).then(|| (location.path.clone(), comment.to_owned()))
+            })
+            .collect();
+        Comments(comments)
+    }
+
+    /// Returns the comment of the element at `path` as paragraphs.
+    fn get(&self, path: &[i32]) -> Option<&str> {
+        self.0.get(path).map(String::as_str)
+    }
+
+    /// Returns the comment of the element at `path` on a single line, for
+    /// table cells.
+    fn cell(&self, path: &[i32]) -> String {
+        self.get(path)
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('|', "\\|")
+    }
+}
+
+/// Renders a Markdown reference of the services of the input files of
+/// `parsed`: their methods with streaming shapes and comments, followed by
+/// the request and response messages, the messages and enums their fields
+/// refer to, and the comments of all of them.
+///
+/// Comments are only available if the descriptors include source code info.
+/// Well-known types are referred to, but not documented.
+pub(crate) fn render(parsed: &ParsedProtos) -> String {
+    let comments: Vec<_> = parsed.fds.file.iter().map(Comments::new).collect();
+    let mut definitions = HashMap::new();
+    for (fd, comments) in parsed.fds.file.iter().zip(&comments) {
+        let prefix = match fd.package() {
+            "" => String::new(),
+            package => format!("{}.", package),
+        };
+        for (i, message) in fd.message_type.iter().enumerate() {
+            collect(
+                &prefix,
+                message,
+                comments,
+                vec![FILE_MESSAGE_TYPE, i as i32],
+                &mut definitions,
+            );
+        }
+        for (i, e) in fd.enum_type.iter().enumerate() {
+            definitions.insert(
+                format!("{}{}", prefix, e.name()),
+                Definition {
+                    kind: Kind::Enum(e),
+                    comments,
+                    path: vec![FILE_ENUM_TYPE, i as i32],
+                },
+            );
+        }
+    }
+
+    let mut out = String::from("# API reference\n");
+    // The types to document, in the order they are referred to first.
+    let mut types = vec![];
+    for (fd, comments) in parsed.fds.file.iter().zip(&comments) {
+        if !parsed.inputs.contains(fd.name()) {
+            continue;
+        }
+        for (i, service) in fd.service.iter().enumerate() {
+            let full_name = match fd.package() {
+                "" => service.name().to_owned(),
+                package => format!("{}.{}", package, service.name()),
+            };
+            let path = [FILE_SERVICE, i as i32];
+            out.push_str(&format!("\n## `{}`\n", full_name));
+            out.push_str(&format!("\nDefined in `{}`.\n", fd.name()));
+            push_paragraph(&mut out, comments.get(&path));
+            if !service.method.is_empty() {
+                out.push_str("\n| Method | Request | Response | Description |\n");
+                out.push_str("| --- | --- | --- | --- |\n");
+            }
+            for (j, method) in service.method.iter().enumerate() {
+                let input = method.input_type().trim_start_matches('.');
+                let output = method.output_type().trim_start_matches('.');
+                let stream = |streaming| if streaming { "stream " } else { "" };
+                out.push_str(&format!(
+                    "| `{}` | `{}{}` | `{}{}` | {} |\n",
+                    method.name(),
+                    stream(method.client_streaming()),
+                    input,
+                    stream(method.server_streaming()),
+                    output,
+                    comments.cell(&[FILE_SERVICE, i as i32, SERVICE_METHOD, j as i32]),
+                ));
+                types.push(input.to_owned());
+                types.push(output.to_owned());
+            }
+            for (j, method) in service.method.iter().enumerate() {
+                let shape = match (method.client_streaming(), method.server_streaming()) {
+                    (false, false) => "Unary",
+                    (false, true) => "Server streaming",
+                    (true, false) => "Client streaming",
+                    (true, true) => "Bidirectional streaming",
+                };
+                out.push_str(&format!("\n### `{}.{}`\n", full_name, method.name()));
+                out.push_str(&format!(
+                    "\n{}, with the route `/{}/{}`.\n",
+                    shape,
+                    full_name,
+                    method.name()
+                ));
+                push_paragraph(
+                    &mut out,
+                    comments.get(&[FILE_SERVICE, i as i32, SERVICE_METHOD, j as i32]),
+                );
+            }
+        }
+    }
+    if types.is_empty() {
+        return out;
+    }
+
+    out.push_str("\n## Types\n");
+    let mut documented = HashSet::new();
+    let mut pending = types;
+    pending.reverse();
+    while let Some(name) = pending.pop() {
+        if name.starts_with("google.protobuf.") || !documented.insert(name.clone()) {
+            continue;
+        }
+        let Some(definition) = definitions.get(&name) else {
+            continue;
+        };
+        let comments = definition.comments;
+        out.push_str(&format!("\n### `{}`\n", name));
+        push_paragraph(&mut out, comments.get(&definition.path));
+        match definition.kind {
+            Kind::Message(message) => {
+                if message.field.is_empty() {
+                    continue;
+                }
+                out.push_str("\n| Field | Type | Description |\n");
+                out.push_str("| --- | --- | --- |\n");
+                let mut referred = vec![];
+                for (i, field) in message.field.iter().enumerate() {
+                    let mut path = definition.path.clone();
+                    path.extend([MESSAGE_FIELD, i as i32]);
+                    out.push_str(&format!(
+                        "| `{}` | `{}` | {} |\n",
+                        field.name(),
+                        field_type(field, &definitions, &mut referred),
+                        comments.cell(&path),
+                    ));
+                }
+                pending.extend(referred.into_iter().rev());
+            }
+            Kind::Enum(e) => {
+                out.push_str("\n| Value | Number | Description |\n");
+                out.push_str("| --- | --- | --- |\n");
+                for (i, value) in e.value.iter().enumerate() {
+                    let mut path = definition.path.clone();
+                    path.extend([ENUM_VALUE, i as i32]);
+                    out.push_str(&format!(
+                        "| `{}` | {} | {} |\n",
+                        value.name(),
+                        value.number(),
+                        comments.cell(&path),
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Adds `message` and its nested messages and enums to `definitions`.
+fn collect<'a>(
+    prefix: &str,
+    message: &'a DescriptorProto,
+    comments: &'a Comments,
+    path: Vec<i32>,
+    definitions: &mut HashMap<String, Definition<'a>>,
+) {
+    let full_name = format!("{}{}", prefix, message.name());
+    let nested_prefix = format!("{}.", full_name);
+    for (i, nested) in message.nested_type.iter().enumerate() {
+        let mut nested_path = path.clone();
+        nested_path.extend([MESSAGE_NESTED_TYPE, i as i32]);
+        collect(&nested_prefix, nested, comments, nested_path, definitions);
+    }
+    for (i, e) in message.enum_type.iter().enumerate() {
+        let mut enum_path = path.clone();
+        enum_path.extend([MESSAGE_ENUM_TYPE, i as i32]);
+        definitions.insert(
+            format!("{}{}", nested_prefix, e.name()),
+            Definition {
+                kind: Kind::Enum(e),
+                comments,
+                path: enum_path,
+            },
+        );
+    }
+    definitions.insert(
+        full_name,
+        Definition {
+            kind: Kind::Message(message),
+            comments,
+            path,
+        },
+    );
+}
+
+/// Returns the type of `field` as written in .proto files, e.g.
+/// `repeated string` or `map<string, a.Account>`, and adds the messages and
+/// enums it refers to to `referred`.
+fn field_type(
+    field: &FieldDescriptorProto,
+    definitions: &HashMap<String, Definition<'_>>,
+    referred: &mut Vec<String>,
+) -> String {
+    let type_name = field.type_name().trim_start_matches('.');
+    if let Some(Definition {
+        kind: Kind::Message(entry),
+        ..
+    }) = definitions.get(type_name)
+    {
+        if entry.options.map_entry() && entry.field.len() == 2 {
+            let key = field_type(&entry.field[0], definitions, referred);
+            let value = field_type(&entry.field[1], definitions, referred);
+            return format!("map<{}, {}>", key, value);
+        }
+    }
+    let ty = match field.type_() {
+        Type::TYPE_DOUBLE => "double",
+        Type::TYPE_FLOAT => "float",
+        Type::TYPE_INT64 => "int64",
+        Type::TYPE_UINT64 => "uint64",
+        Type::TYPE_INT32 => "int32",
+        Type::TYPE_FIXED64 => "fixed64",
+        Type::TYPE_FIXED32 => "fixed32",
+        Type::TYPE_BOOL => "bool",
+        Type::TYPE_STRING => "string",
+        Type::TYPE_BYTES => "bytes",
+        Type::TYPE_UINT32 => "uint32",
+        Type::TYPE_SFIXED32 => "sfixed32",
+        Type::TYPE_SFIXED64 => "sfixed64",
+        Type::TYPE_SINT32 => "sint32",
+        Type::TYPE_SINT64 => "sint64",
+        Type::TYPE_GROUP | Type::TYPE_MESSAGE | Type::TYPE_ENUM => {
+            referred.push(type_name.to_owned());
+            type_name
+        }
+    };
+    if field.label() == Label::LABEL_REPEATED {
+        format!("repeated {}", ty)
+    } else if field.proto3_optional() {
+        format!("optional {}", ty)
+    } else {
+        ty.to_owned()
+    }
+}
+
+fn push_paragraph(out: &mut String, comment: Option<&str>) {
+    if let Some(comment) = comment {
+        out.push('\n');
+        out.push_str(comment);
+        out.push('\n');
+    }
+}
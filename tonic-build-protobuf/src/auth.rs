@@ -0,0 +1,208 @@
+//! Generation of authenticating client constructors from custom options.
+//!
+//! The auth schemes a service accepts are declared with a message typed
+//! extension of `google.protobuf.ServiceOptions`, e.g.
+//!
+//! ```proto
+//! message AuthScheme {
+//!   // One of "BEARER", "API_KEY" and "MTLS", a string or an enum value.
+//!   string scheme = 1;
+//!   // The metadata key carrying API keys, "x-api-key" by default.
+//!   string header = 2;
+//! }
+//!
+//! extend google.protobuf.ServiceOptions {
+//!   repeated AuthScheme auth = 50006;
+//! }
+//! ```
+//!
+//! Clients then get a constructor per scheme, `with_bearer` and
+//! `with_api_key`, which install an interceptor adding the credentials to
+//! every request, and `connect_mtls`, which connects with a client
+//! certificate.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{options::OptionValue, Service};
+
+/// The metadata key of API keys, unless declared otherwise.
+const DEFAULT_API_KEY_HEADER: &str = "x-api-key";
+
+/// An auth scheme accepted by a service.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AuthScheme {
+    /// A token in the `authorization` metadata, e.g. `Bearer <token>`.
+    Bearer,
+    /// A key in the metadata `header`.
+    ApiKey { header: String },
+    /// A client certificate.
+    Mtls,
+}
+
+/// Decodes the schemes declared by `values` of the auth option.
+///
+/// Invalid schemes are reported to `warnings`, with `context` describing
+/// where they are declared.
+pub(crate) fn decode(
+    values: &[OptionValue],
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<AuthScheme> {
+    let mut schemes = vec![];
+    for value in values {
+        let scheme = value
+            .field("scheme")
+            .and_then(OptionValue::as_str)
+            .unwrap_or_default();
+        let scheme = match scheme.to_ascii_uppercase().as_str() {
+            "BEARER" => AuthScheme::Bearer,
+            "API_KEY" => {
+                let header = value
+                    .field("header")
+                    .and_then(OptionValue::as_str)
+                    .filter(|header| !header.is_empty())
+                    .unwrap_or(DEFAULT_API_KEY_HEADER);
+                let valid = header
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"-_.".contains(&b));
+                if !valid || header.ends_with("-bin") {
+                    warnings.push(format!(
+                        "{}: API key header {:?} is not a lowercase ASCII metadata key, \
+                         scheme is ignored",
+                        context, header
+                    ));
+                    continue;
+                }
+                AuthScheme::ApiKey {
+                    header: header.to_owned(),
+                }
+            }
+            "MTLS" => AuthScheme::Mtls,
+            _ => {
+                warnings.push(format!(
+                    "{}: unknown auth scheme {:?} is ignored",
+                    context, scheme
+                ));
+                continue;
+            }
+        };
+        if !schemes.contains(&scheme) {
+            schemes.push(scheme);
+        }
+    }
+    schemes
+}
+
+/// Generate the constructors of the auth schemes of `service`, and the
+/// interceptor they install, to be appended to its client module.
+///
+/// The constructors create clients over a `tonic::transport::Channel`, so
+/// they are only generated together with the transport.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    if service.auth.is_empty() {
+        return TokenStream::new();
+    }
+    let client = format_ident!("{}Client", service.name);
+    let intercepted = quote!(#client<tonic::codegen::InterceptedService<tonic::transport::Channel, AuthInterceptor>>);
+    let constructors = service.auth.iter().map(|scheme| match scheme {
+        AuthScheme::Bearer => quote! {
+            /// Create a client sending `token` as bearer token in the
+            /// `authorization` metadata of every request.
+            pub fn with_bearer(
+                channel: tonic::transport::Channel,
+                token: &str,
+            ) -> Result<#intercepted, tonic::metadata::errors::InvalidMetadataValue> {
+                let value = format!("Bearer {}", token).parse()?;
+                Ok(Self::with_interceptor(
+                    channel,
+                    AuthInterceptor::new(tonic::metadata::AsciiMetadataKey::from_static("authorization"), value),
+                ))
+            }
+        },
+        AuthScheme::ApiKey { header } => {
+            let doc = format!(
+                " Create a client sending `key` in the `{}` metadata of every request.",
+                header
+            );
+            quote! {
+                #[doc = #doc]
+                pub fn with_api_key(
+                    channel: tonic::transport::Channel,
+                    key: &str,
+                ) -> Result<#intercepted, tonic::metadata::errors::InvalidMetadataValue> {
+                    let value = key.parse()?;
+                    Ok(Self::with_interceptor(
+                        channel,
+                        AuthInterceptor::new(tonic::metadata::AsciiMetadataKey::from_static(#header), value),
+                    ))
+                }
+            }
+        }
+        AuthScheme::Mtls => quote! {
+            /// Attempt to create a new client by connecting to the given
+            /// endpoint with the client certificate of `identity`, trusting
+            /// the server certificates signed by `ca`.
+            ///
+            /// Requires the `tls` feature of tonic.
+            pub async fn connect_mtls<D>(
+                dst: D,
+                identity: tonic::transport::Identity,
+                ca: tonic::transport::Certificate,
+            ) -> Result<#client<tonic::transport::Channel>, tonic::transport::Error>
+            where
+                D: TryInto<tonic::transport::Endpoint>,
+                D::Error: Into<StdError>,
+            {
+                let tls = tonic::transport::ClientTlsConfig::new()
+                    .identity(identity)
+                    .ca_certificate(ca);
+                let channel = tonic::transport::Endpoint::new(dst)?
+                    .tls_config(tls)?
+                    .connect()
+                    .await?;
+                Ok(Self::new(channel))
+            }
+        },
+    });
+    let interceptor = service
+        .auth
+        .iter()
+        .any(|scheme| *scheme != AuthScheme::Mtls)
+        .then(|| {
+            quote! {
+                /// Adds credentials to the metadata of every request.
+                #[derive(Debug, Clone)]
+                pub struct AuthInterceptor {
+                    key: tonic::metadata::AsciiMetadataKey,
+                    value: tonic::metadata::AsciiMetadataValue,
+                }
+                impl AuthInterceptor {
+                    /// Create an interceptor setting the metadata `key` to
+                    /// `value`, which is marked sensitive.
+                    pub fn new(
+                        key: tonic::metadata::AsciiMetadataKey,
+                        mut value: tonic::metadata::AsciiMetadataValue,
+                    ) -> Self {
+                        value.set_sensitive(true);
+                        Self { key, value }
+                    }
+                }
+                impl tonic::service::Interceptor for AuthInterceptor {
+                    fn call(
+                        &mut self,
+                        mut request: tonic::Request<()>,
+                    ) -> Result<tonic::Request<()>, tonic::Status> {
+                        request.metadata_mut().insert(self.key.clone(), self.value.clone());
+                        Ok(request)
+                    }
+                }
+            }
+        });
+    quote! {
+        #interceptor
+        impl #client<tonic::transport::Channel> {
+            #(#constructors)*
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Generation of criterion benches, which measure round trips of the
+//! messages of services through their codecs with
+//! `tonic_testing_protobuf::bench::roundtrip`.
+
+use heck::ToSnakeCase;
+use quote::{format_ident, quote};
+
+use crate::Service;
+
+/// Renders a criterion bench of `service`, with a benchmark group per
+/// method, as a pair of the bench name and the file content.
+///
+/// Message types are referred to through
+/// [`Builder::proto_path`](crate::Builder::proto_path), and samples are read
+/// from `samples_dir`, relative to the manifest directory of the crate of the
+/// bench.
+pub(crate) fn render(service: &Service, package: &str, samples_dir: &str) -> (String, String) {
+    let snake = service.name.to_snake_case();
+    let name = if package.is_empty() {
+        snake
+    } else {
+        format!("{}_{}", package.replace('.', "_").to_snake_case(), snake)
+    };
+
+    let functions: Vec<_> = service
+        .methods
+        .iter()
+        .map(|method| format_ident!("bench_{}", method.route_name.to_snake_case()))
+        .collect();
+    let benches = service
+        .methods
+        .iter()
+        .zip(&functions)
+        .map(|(method, function)| {
+            let group = format!("{}/{}", service.full_name(), method.route_name);
+            let codec: syn::Path = syn::parse_str(&method.codec_path).expect("invalid codec path");
+            let (request, response) = method.request_response_types();
+            let directions = [("request", request), ("response", response)].map(|(kind, ty)| {
+                let id = format!("{}/{{}}", kind);
+                quote! {
+                    for (name, message) in bench::samples::<#ty>(SAMPLES) {
+                        group.bench_function(format!(#id, name), |b| {
+                            b.iter(|| bench::roundtrip(#codec::default(), message.clone()))
+                        });
+                    }
+                }
+            });
+            quote! {
+                fn #function(c: &mut Criterion) {
+                    let mut group = c.benchmark_group(#group);
+                    #(#directions)*
+                    group.finish();
+                }
+            }
+        });
+
+    let doc = format!(
+        " Benchmarks round trips of the messages of the `{}` service through its codec.",
+        service.full_name()
+    );
+    let samples = format!("/{}", samples_dir.trim_matches('/'));
+    let tokens = quote! {
+        #![doc = #doc]
+
+        use criterion::{criterion_group, criterion_main, Criterion};
+        use tonic_testing_protobuf::bench;
+
+        /// The directory of the sample messages, see
+        /// `tonic_testing_protobuf::bench::samples`.
+        const SAMPLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), #samples);
+
+        #(#benches)*
+
+        criterion_group!(benches, #(#functions),*);
+        criterion_main!(benches);
+    };
+    let ast: syn::File = syn::parse2(tokens).expect("not a valid tokenstream");
+    (name, prettyplease::unparse(&ast))
+}
@@ -0,0 +1,127 @@
+//! A protoc plugin that generates tonic services with rust-protobuf codecs.
+//!
+//! ```sh
+//! protoc --tonic-rust-protobuf_out=codec_path=crate::Codec,build_client=false:out \
+//!     --plugin=protoc-gen-tonic-rust-protobuf foo.proto
+//! ```
+//!
+//! Parameters are comma separated `key=value` pairs that map to the
+//! [`Builder`] methods of the same name, see [`Builder::parameters`]:
+//!
+//! * `codec_path`, `proto_path`, `default_package`, `api_docs`, `mod_file`,
+//! * `build_client`, `build_server`, `build_transport`, `build_call_options`,
+//!   `inner_lint_attributes` (`true` or `false`),
+//! * `tonic_version` (`0.11`, `0.12`, `0.13` or `0.14`),
+//! * `file_naming` (`package_service` or `proto_file`),
+//! * `allow_lint`, which may be repeated.
+//!
+//! Warnings are written to stderr, which protoc forwards.
+
+use std::io::{self, Read, Write};
+
+use protobuf::{
+    plugin::{code_generator_response, CodeGeneratorRequest, CodeGeneratorResponse},
+    Message,
+};
+use tonic_build_protobuf::Builder;
+
+fn main() {
+    let mut input = vec![];
+    io::stdin().read_to_end(&mut input).unwrap();
+    let request = CodeGeneratorRequest::parse_from_bytes(&input).unwrap();
+
+    let response = generate(request);
+    let output = response.write_to_bytes().unwrap();
+    io::stdout().write_all(&output).unwrap();
+}
+
+fn generate(request: CodeGeneratorRequest) -> CodeGeneratorResponse {
+    let mut response = CodeGeneratorResponse::new();
+    response
+        .set_supported_features(code_generator_response::Feature::FEATURE_PROTO3_OPTIONAL as u64);
+
+    let builder = match Builder::new().parameters(request.parameter()) {
+        Ok(builder) => builder,
+        Err(e) => {
+            response.set_error(e);
+            return response;
+        }
+    };
+    let mut warnings = vec![];
+    let files = builder.generate_from_descriptors(
+        request.proto_file,
+        &request.file_to_generate,
+        &mut warnings,
+    );
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    for file in files {
+        let mut output = code_generator_response::File::new();
+        output.set_name(file.name);
+        output.set_content(file.content);
+        response.file.push(output);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::plugin::CodeGeneratorRequest;
+
+    use super::generate;
+
+    fn request(parameter: &str) -> CodeGeneratorRequest {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("greeter.proto"),
+            r#"
+                syntax = "proto3";
+                package demo;
+                message Hello {
+                    optional string name = 1;
+                }
+                service Greeter {
+                    rpc Greet(Hello) returns (Hello);
+                }
+            "#,
+        )
+        .unwrap();
+        let fds = protobuf_parse::Parser::new()
+            .pure()
+            .include(dir.path())
+            .input(dir.path().join("greeter.proto"))
+            .file_descriptor_set()
+            .unwrap();
+
+        let mut request = CodeGeneratorRequest::new();
+        request.set_parameter(parameter.to_owned());
+        request.file_to_generate.push("greeter.proto".to_owned());
+        request.proto_file = fds.file;
+        request
+    }
+
+    #[test]
+    fn test_generate() {
+        let response = generate(request("codec_path=crate::Codec,build_client=false"));
+        assert!(!response.has_error(), "{}", response.error());
+        // proto3 optional fields are supported.
+        assert_eq!(response.supported_features(), 1);
+        let names: Vec<_> = response.file.iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["demo_greeter.rs"]);
+        let content = response.file[0].content();
+        assert!(content.contains("pub mod greeter_server"), "{}", content);
+        assert!(!content.contains("pub mod greeter_client"), "{}", content);
+        assert!(content.contains("crate::Codec"), "{}", content);
+    }
+
+    #[test]
+    fn test_generate_invalid_parameter() {
+        let response = generate(request("build_client=no"));
+        assert_eq!(
+            response.error(),
+            r#"invalid value "no" of build_client, expected a bool"#
+        );
+        assert!(response.file.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Regenerates tonic services whenever the .proto files in directories
+//! change, see [`tonic_build_protobuf::watch`].
+//!
+//! ```sh
+//! tonic-rust-protobuf-watch --out src/generated --param proto_path=crate,mod_file=mod.rs proto
+//! ```
+
+use std::{path::PathBuf, time::Duration};
+
+use tonic_build_protobuf::{watch::Watcher, Builder};
+
+const USAGE: &str = "\
+Usage: tonic-rust-protobuf-watch [OPTIONS] --out DIR DIR...
+
+Generates the services of the .proto files in the directories DIR into the
+output directory, and regenerates them whenever a .proto file is added,
+removed or modified.
+
+Options:
+    --out DIR        the output directory
+    --param PARAMS   comma separated key=value parameters, like those of
+                     protoc-gen-tonic-rust-protobuf, e.g. proto_path=crate
+    -I, --include DIR
+                     an include directory, may be repeated, defaults to DIR...
+    --protoc PATH    the protoc binary, defaults to protoc in PATH
+    --interval MS    the interval of polling for changes, defaults to 500
+    --once           generate once and exit
+    -h, --help       print this help
+";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut out = None;
+    let mut parameter = String::new();
+    let mut includes = vec![];
+    let mut protoc = PathBuf::from("protoc");
+    let mut interval = Duration::from_millis(500);
+    let mut once = false;
+    let mut dirs = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value of {}", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--param" => parameter = value()?,
+            "-I" | "--include" => includes.push(PathBuf::from(value()?)),
+            "--protoc" => protoc = PathBuf::from(value()?),
+            "--interval" => {
+                let millis = value()?;
+                let millis = millis
+                    .parse()
+                    .map_err(|_| format!("invalid --interval {:?}", millis))?;
+                interval = Duration::from_millis(millis);
+            }
+            "--once" => once = true,
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE))
+            }
+            _ => dirs.push(PathBuf::from(arg)),
+        }
+    }
+    let out = out.ok_or_else(|| format!("--out is required\n\n{}", USAGE))?;
+    if dirs.is_empty() {
+        return Err(format!("no directory to watch\n\n{}", USAGE));
+    }
+    if includes.is_empty() {
+        includes = dirs.clone();
+    }
+    // Fail early on invalid parameters rather than on every change.
+    Builder::new().parameters(&parameter)?;
+
+    let regenerate = |protos: &[PathBuf]| {
+        let builder = Builder::new()
+            .parameters(&parameter)
+            .unwrap()
+            .out_dir(&out)
+            .protoc_path(&protoc)
+            // Reports warnings on stderr instead of as cargo instructions.
+            .hermetic(true);
+        builder.compile(protos, &includes);
+        eprintln!(
+            "generated {} .proto files into {}",
+            protos.len(),
+            out.display()
+        );
+    };
+    let mut watcher = Watcher::new(&dirs);
+    if once {
+        regenerate(&watcher.poll().unwrap_or_default());
+        return Ok(());
+    }
+    watcher.watch(interval, regenerate)
+}
@@ -0,0 +1,155 @@
+//! Generation of blocking clients, which wrap the generated async clients and
+//! run their calls to completion on a tokio runtime.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{client, Service, TonicVersion};
+
+/// Generate the `Blocking{Service}Client` of `service`, and the iterator over
+/// its response streams, to be appended to its client module.
+///
+/// Client and bidirectional streaming methods are not wrapped, as their
+/// requests are async streams. The generated code refers to the `tokio` crate
+/// with the `rt-multi-thread` feature when the transport is generated, which
+/// the crate including it must depend on.
+pub(crate) fn generate(
+    service: &Service,
+    tonic_version: TonicVersion,
+    build_transport: bool,
+) -> TokenStream {
+    let client = format_ident!("{}Client", service.name);
+    let blocking = format_ident!("Blocking{}Client", service.name);
+    let where_clause = client::where_clause(tonic_version, quote!());
+
+    let methods = service
+        .methods
+        .iter()
+        .filter(|method| !method.client_streaming)
+        .map(|method| {
+            let name = format_ident!("{}", method.name);
+            let (request, response) = client::method_types(method);
+            if method.server_streaming {
+                let (_, message) = method.request_response_types();
+                let doc = format!(
+                    " Blocking version of [`{}::{}`], whose responses are read by \
+                     iterating the returned stream.",
+                    client, method.name
+                );
+                quote! {
+                    #[doc = #doc]
+                    pub fn #name(
+                        &mut self,
+                        request: #request,
+                    ) -> std::result::Result<tonic::Response<BlockingStreaming<#message>>, tonic::Status> {
+                        let response = self.handle.block_on(self.inner.#name(request))?;
+                        let handle = self.handle.clone();
+                        Ok(response.map(|inner| BlockingStreaming { inner, handle }))
+                    }
+                }
+            } else {
+                let doc = format!(" Blocking version of [`{}::{}`].", client, method.name);
+                quote! {
+                    #[doc = #doc]
+                    pub fn #name(
+                        &mut self,
+                        request: #request,
+                    ) -> std::result::Result<#response, tonic::Status> {
+                        self.handle.block_on(self.inner.#name(request))
+                    }
+                }
+            }
+        });
+
+    let connect = build_transport.then(|| {
+        quote! {
+            impl #blocking<tonic::transport::Channel> {
+                /// Attempt to create a new client by connecting to the given
+                /// endpoint, running calls on a new runtime owned by the
+                /// client.
+                pub fn connect<D>(dst: D) -> Result<Self, StdError>
+                where
+                    D: TryInto<tonic::transport::Endpoint>,
+                    D::Error: Into<StdError>,
+                {
+                    // Worker threads drive the connection while callers block.
+                    let runtime = tokio::runtime::Builder::new_multi_thread()
+                        .worker_threads(1)
+                        .enable_all()
+                        .build()?;
+                    let inner = runtime.block_on(#client::connect(dst))?;
+                    Ok(Self {
+                        inner,
+                        handle: runtime.handle().clone(),
+                        _runtime: Some(std::sync::Arc::new(runtime)),
+                    })
+                }
+            }
+        }
+    });
+
+    let service_doc = format!(
+        " A blocking client of the {} service, running the calls of [`{}`] to \
+         completion on a tokio runtime.\n\n Calls must not be made from an \
+         async context, and client and bidirectional streaming methods are \
+         only available on the async client, see [`Self::inner_mut`].",
+        service.name, client
+    );
+    quote! {
+        #[doc = #service_doc]
+        #[derive(Debug, Clone)]
+        pub struct #blocking<T> {
+            inner: #client<T>,
+            handle: tokio::runtime::Handle,
+            /// Keeps the runtime created by `connect` alive.
+            _runtime: Option<std::sync::Arc<tokio::runtime::Runtime>>,
+        }
+        #connect
+        impl<T> #blocking<T> {
+            /// Create a blocking client running the calls of `inner` on the
+            /// runtime of `handle`, which must be a multi-threaded runtime
+            /// unless another thread is driving it.
+            pub fn new(inner: #client<T>, handle: tokio::runtime::Handle) -> Self {
+                Self {
+                    inner,
+                    handle,
+                    _runtime: None,
+                }
+            }
+            /// Returns a mutable reference to the async client.
+            pub fn inner_mut(&mut self) -> &mut #client<T> {
+                &mut self.inner
+            }
+            /// Returns the handle of the runtime running the calls.
+            pub fn handle(&self) -> &tokio::runtime::Handle {
+                &self.handle
+            }
+        }
+        #[allow(clippy::result_large_err)]
+        impl<T> #blocking<T>
+        #where_clause
+        {
+            #(#methods)*
+        }
+        /// A stream of responses of a blocking client, read by iterating it.
+        #[derive(Debug)]
+        pub struct BlockingStreaming<M> {
+            inner: tonic::codec::Streaming<M>,
+            handle: tokio::runtime::Handle,
+        }
+        #[allow(clippy::result_large_err)]
+        impl<M> BlockingStreaming<M> {
+            /// Returns the trailers of the response, once all messages were
+            /// read.
+            pub fn trailers(&mut self) -> Result<Option<tonic::metadata::MetadataMap>, tonic::Status> {
+                self.handle.block_on(self.inner.trailers())
+            }
+        }
+        impl<M> Iterator for BlockingStreaming<M> {
+            type Item = Result<M, tonic::Status>;
+            fn next(&mut self) -> Option<Self::Item> {
+                self.handle.block_on(self.inner.message()).transpose()
+            }
+        }
+    }
+}
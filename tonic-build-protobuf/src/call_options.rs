@@ -0,0 +1,111 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{client, Service, TonicVersion};
+
+/// Generate the `CallOptions` struct and the `{method}_with_options` variants
+/// of `service`, to be appended to its client module.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let methods = service.methods.iter().map(|method| {
+        let name = format_ident!("{}", method.name);
+        let with_options = format_ident!("{}_with_options", method.name);
+        let (request, response) = client::method_types(method);
+        let into_request = client::into_request(method);
+        let doc = format!(
+            " Like [`Self::{}`], but applies `options` to the call.",
+            method.name
+        );
+        quote! {
+            #[doc = #doc]
+            pub async fn #with_options(
+                &mut self,
+                request: #request,
+                options: &CallOptions,
+            ) -> std::result::Result<#response, tonic::Status> {
+                let mut request = #into_request;
+                options.apply(&mut request);
+                let mut client = self.clone();
+                if let Some(encoding) = options.send_compression {
+                    client = client.send_compressed(encoding);
+                }
+                if let Some(encoding) = options.accept_compression {
+                    client = client.accept_compressed(encoding);
+                }
+                client.#name(request).await
+            }
+        }
+    });
+    let methods = client::impl_block(
+        service,
+        tonic_version,
+        quote!(+ Clone),
+        quote!(#(#methods)*),
+    );
+
+    quote! {
+        /// Options applied to a single call, similar to grpcio's `CallOption`.
+        #[derive(Debug, Clone, Default)]
+        pub struct CallOptions {
+            /// The timeout of the call.
+            pub timeout: Option<std::time::Duration>,
+            /// The compression used to send the request.
+            pub send_compression: Option<CompressionEncoding>,
+            /// The compression accepted for the response.
+            pub accept_compression: Option<CompressionEncoding>,
+            /// Metadata added to the request, unless the request already
+            /// contains the same key.
+            pub metadata: tonic::metadata::MetadataMap,
+        }
+        impl CallOptions {
+            /// Create new options that change nothing.
+            pub fn new() -> Self {
+                Self::default()
+            }
+            /// Set the timeout of the call.
+            #[must_use]
+            pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+                self.timeout = Some(timeout);
+                self
+            }
+            /// Compress the request with the given encoding.
+            #[must_use]
+            pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                self.send_compression = Some(encoding);
+                self
+            }
+            /// Accept a response compressed with the given encoding.
+            #[must_use]
+            pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                self.accept_compression = Some(encoding);
+                self
+            }
+            /// Add default metadata to the request.
+            #[must_use]
+            pub fn metadata(mut self, metadata: tonic::metadata::MetadataMap) -> Self {
+                self.metadata = metadata;
+                self
+            }
+            /// Apply the timeout and default metadata to `request`.
+            pub fn apply<R>(&self, request: &mut tonic::Request<R>) {
+                if let Some(timeout) = self.timeout {
+                    request.set_timeout(timeout);
+                }
+                for entry in self.metadata.iter() {
+                    match entry {
+                        tonic::metadata::KeyAndValueRef::Ascii(key, value) => {
+                            if !request.metadata().contains_key(key) {
+                                request.metadata_mut().insert(key.clone(), value.clone());
+                            }
+                        }
+                        tonic::metadata::KeyAndValueRef::Binary(key, value) => {
+                            if !request.metadata().contains_key(key) {
+                                request.metadata_mut().insert_bin(key.clone(), value.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        #methods
+    }
+}
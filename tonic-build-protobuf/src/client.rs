@@ -0,0 +1,65 @@
+//! Helpers for generating additional client methods.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{Method, Service, TonicVersion};
+
+/// Returns an `impl<T> {Service}Client<T>` block containing `items`, with the
+/// same bounds as the generated client methods plus `extra_bounds`.
+pub(crate) fn impl_block(
+    service: &Service,
+    tonic_version: TonicVersion,
+    extra_bounds: TokenStream,
+    items: TokenStream,
+) -> TokenStream {
+    let client = format_ident!("{}Client", service.name);
+    let where_clause = where_clause(tonic_version, extra_bounds);
+    quote! {
+        impl<T> #client<T>
+        #where_clause
+        {
+            #items
+        }
+    }
+}
+
+/// Returns the where clause of the generated client methods, bounding the
+/// transport `T`, plus `extra_bounds` on `T`.
+pub(crate) fn where_clause(tonic_version: TonicVersion, extra_bounds: TokenStream) -> TokenStream {
+    let body = tonic_version.body_type();
+    quote! {
+        where
+            T: tonic::client::GrpcService<#body> #extra_bounds,
+            T::Error: Into<StdError>,
+            T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+            <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    }
+}
+
+/// Returns the type of the `request` argument and the response type of the
+/// generated client method for `method`.
+pub(crate) fn method_types(method: &Method) -> (TokenStream, TokenStream) {
+    let (request, response) = method.request_response_types();
+    let request = if method.client_streaming {
+        quote!(impl tonic::IntoStreamingRequest<Message = #request>)
+    } else {
+        quote!(impl tonic::IntoRequest<#request>)
+    };
+    let response = if method.server_streaming {
+        quote!(tonic::Response<tonic::codec::Streaming<#response>>)
+    } else {
+        quote!(tonic::Response<#response>)
+    };
+    (request, response)
+}
+
+/// Returns an expression converting the `request` argument of the generated
+/// client method for `method` into a `tonic::Request`.
+pub(crate) fn into_request(method: &Method) -> TokenStream {
+    if method.client_streaming {
+        quote!(tonic::IntoStreamingRequest::into_streaming_request(request))
+    } else {
+        quote!(tonic::IntoRequest::into_request(request))
+    }
+}
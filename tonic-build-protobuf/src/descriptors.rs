@@ -0,0 +1,42 @@
+//! Generation of accessors of the descriptors of services, see
+//! [`Builder::build_service_descriptors`](crate::Builder::build_service_descriptors).
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::Service;
+
+/// Generate `service_descriptor()` and `file_descriptor()` functions
+/// returning the descriptors of `service` and its .proto file, to be appended
+/// to its client and server modules.
+///
+/// The descriptors are those rust-protobuf generates, so nothing is
+/// generated for files of the lite runtime.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let Some(file_module) = &service.file_module else {
+        return TokenStream::new();
+    };
+    let file_module: syn::Path = syn::parse_str(file_module).expect("invalid module path");
+    let name = &service.name;
+    let service_doc = format!(
+        " Returns the descriptor of the `{}` service.",
+        service.full_name()
+    );
+    let file_doc = format!(
+        " Returns the descriptor of `{}`, the file defining the service.",
+        service.proto_file
+    );
+    quote! {
+        #[doc = #service_doc]
+        pub fn service_descriptor() -> ::protobuf::reflect::ServiceDescriptor {
+            #file_module::file_descriptor()
+                .services()
+                .find(|service| service.proto().name() == #name)
+                .expect("service is in its file descriptor")
+        }
+        #[doc = #file_doc]
+        pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
+            #file_module::file_descriptor()
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Generation of conversions to domain types from custom options.
+//!
+//! The Rust domain type of a message is declared with a string extension of
+//! `google.protobuf.MessageOptions`, e.g.
+//!
+//! ```proto
+//! extend google.protobuf.MessageOptions {
+//!   string domain_type = 50005;
+//! }
+//!
+//! message Account {
+//!   option (myorg.domain_type) = "crate::model::Account";
+//!   string name = 1;
+//! }
+//! ```
+//!
+//! The crate including the generated code implements `From<Domain>` for the
+//! message and `TryFrom<Message>` for the domain type, whose error implements
+//! `Display`. Unary client methods whose request or response has a domain
+//! type get a `{method}_domain` variant taking and returning domain types,
+//! and server modules an `IntoDomain` trait converting requests for handlers.
+
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{
+    client, options, options::OptionValue, DescriptorIndex, Method, Service, TonicVersion,
+};
+
+/// The domain types of the request and response of a method.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DomainTypes {
+    /// The domain type of the request message.
+    pub(crate) request: Option<String>,
+    /// The domain type of the response message.
+    pub(crate) response: Option<String>,
+}
+
+impl DomainTypes {
+    fn is_empty(&self) -> bool {
+        self.request.is_none() && self.response.is_none()
+    }
+}
+
+/// Decodes the domain type declared by the `option` of the message `ty`,
+/// e.g. `.myorg.Account`.
+///
+/// Invalid values are reported to `warnings`.
+pub(crate) fn decode(
+    ty: &str,
+    option: &str,
+    index: &DescriptorIndex,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let message = index.messages.get(ty)?;
+    let context = format!("message {}", ty.trim_start_matches('.'));
+    let value = options::decode(
+        &message.options,
+        &index.message_extensions,
+        index,
+        &context,
+        warnings,
+    )
+    .into_iter()
+    .find(|custom| custom.full_name == option)?
+    .value()
+    .cloned();
+    let Some(OptionValue::String(domain)) = value else {
+        warnings.push(format!(
+            "{}: domain type option must be a string and is ignored",
+            context
+        ));
+        return None;
+    };
+    if syn::parse_str::<syn::Type>(&domain).is_err() {
+        warnings.push(format!(
+            "{}: domain type `{}` is not a Rust type and is ignored",
+            context, domain
+        ));
+        return None;
+    }
+    Some(domain)
+}
+
+fn parse_type(ty: &str) -> TokenStream {
+    let ty: syn::Type = syn::parse_str(ty).expect("invalid domain type");
+    quote!(#ty)
+}
+
+fn is_unary(method: &Method) -> bool {
+    !method.client_streaming && !method.server_streaming
+}
+
+/// Generate the `{method}_domain` variants of the unary client methods of
+/// `service` with domain types, to be appended to its client module.
+pub(crate) fn generate_client(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let methods: Vec<_> = service
+        .methods
+        .iter()
+        .filter(|method| is_unary(method) && !method.domain.is_empty())
+        .map(|method| {
+            let name = format_ident!("{}", method.name);
+            let with_domain = format_ident!("{}_domain", method.name);
+            let (request_message, response_message) = method.request_response_types();
+            let (request, convert_request) = match &method.domain.request {
+                Some(domain) => {
+                    let domain = parse_type(domain);
+                    (
+                        domain.clone(),
+                        quote!(let request = <#request_message as From<#domain>>::from(request);),
+                    )
+                }
+                None => (quote!(impl tonic::IntoRequest<#request_message>), quote!()),
+            };
+            let (response, convert_response) = match &method.domain.response {
+                Some(domain) => {
+                    let domain = parse_type(domain);
+                    (
+                        domain.clone(),
+                        quote! {
+                            let (metadata, message, extensions) = response.into_parts();
+                            let message = <#domain as TryFrom<#response_message>>::try_from(message)
+                                .map_err(|e| tonic::Status::internal(format!("invalid response: {}", e)))?;
+                            Ok(tonic::Response::from_parts(metadata, message, extensions))
+                        },
+                    )
+                }
+                None => (response_message, quote!(Ok(response))),
+            };
+            let doc = format!(
+                " Like [`Self::{}`], with the domain types of the request and response.",
+                method.name
+            );
+            quote! {
+                #[doc = #doc]
+                pub async fn #with_domain(
+                    &mut self,
+                    request: #request,
+                ) -> std::result::Result<tonic::Response<#response>, tonic::Status> {
+                    #convert_request
+                    let response = self.#name(request).await?;
+                    #convert_response
+                }
+            }
+        })
+        .collect();
+    if methods.is_empty() {
+        return TokenStream::new();
+    }
+    client::impl_block(service, tonic_version, quote!(), quote!(#(#methods)*))
+}
+
+/// Generate the `IntoDomain` trait of `service`, implemented for the requests
+/// with domain types, to be appended to its server module.
+pub(crate) fn generate_server(service: &Service) -> TokenStream {
+    let mut seen = HashSet::new();
+    let impls: Vec<_> = service
+        .methods
+        .iter()
+        .filter(|method| !method.client_streaming)
+        .filter_map(|method| Some((method, method.domain.request.as_ref()?)))
+        .filter(|(method, _)| seen.insert(method.input_type.clone()))
+        .map(|(method, domain)| {
+            let (message, _) = method.request_response_types();
+            let domain = parse_type(domain);
+            quote! {
+                impl IntoDomain for tonic::Request<#message> {
+                    type Domain = #domain;
+                    fn into_domain(self) -> std::result::Result<tonic::Request<#domain>, tonic::Status> {
+                        let (metadata, extensions, message) = self.into_parts();
+                        let message = <#domain as TryFrom<#message>>::try_from(message)
+                            .map_err(|e| tonic::Status::invalid_argument(format!("invalid request: {}", e)))?;
+                        Ok(tonic::Request::from_parts(metadata, extensions, message))
+                    }
+                }
+            }
+        })
+        .collect();
+    if impls.is_empty() {
+        return TokenStream::new();
+    }
+    quote! {
+        /// Converts requests to the domain types of their messages, e.g.
+        /// `let request = request.into_domain()?;` in handlers.
+        pub trait IntoDomain {
+            /// The domain type of the request message.
+            type Domain;
+            /// Converts the request, failing with `InvalidArgument` if the
+            /// message has no valid domain representation.
+            #[allow(clippy::result_large_err)]
+            fn into_domain(self) -> std::result::Result<tonic::Request<Self::Domain>, tonic::Status>;
+        }
+        #(#impls)*
+    }
+}
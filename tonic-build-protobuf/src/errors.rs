@@ -0,0 +1,290 @@
+//! Generation of per-service error enums from custom options.
+//!
+//! The errors of a service are declared with a repeated, message typed
+//! extension of `google.protobuf.ServiceOptions`, e.g.
+//!
+//! ```proto
+//! message ErrorCode {
+//!   // The reason of the error, e.g. "KEY_NOT_FOUND".
+//!   string name = 1;
+//!   // The gRPC status code, by name (e.g. "NOT_FOUND", or a value of an enum
+//!   // like `google.rpc.Code`) or by number.
+//!   string code = 2;
+//!   // The default error message.
+//!   string message = 3;
+//!   // The fully qualified name of a message carried as status details.
+//!   string detail = 4;
+//! }
+//!
+//! extend google.protobuf.ServiceOptions {
+//!   repeated ErrorCode errors = 50001;
+//! }
+//! ```
+
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{options::OptionValue, DescriptorIndex, Service};
+
+/// gRPC status code names, indexed by code.
+const CODES: &[&str] = &[
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+
+/// An application error of a service.
+#[derive(Debug, Clone)]
+pub(crate) struct ServiceError {
+    /// The reason of the error, e.g. `KEY_NOT_FOUND`.
+    name: String,
+    /// The gRPC status code name, e.g. `NOT_FOUND`.
+    code: &'static str,
+    /// The default error message.
+    message: String,
+    /// The full path of the Rust type of the status details.
+    pub(crate) detail: Option<String>,
+}
+
+/// Returns the name of the gRPC status code `value`, given by name or by
+/// number.
+pub(crate) fn status_code(value: &OptionValue) -> Option<&'static str> {
+    match value {
+        OptionValue::String(code) | OptionValue::Enum(code) => {
+            CODES.iter().find(|c| *c == code).copied()
+        }
+        OptionValue::I32(code) => usize::try_from(*code)
+            .ok()
+            .and_then(|c| CODES.get(c).copied()),
+        OptionValue::U32(code) => CODES.get(*code as usize).copied(),
+        _ => None,
+    }
+}
+
+/// Decodes the errors declared by `values` of the error option.
+///
+/// Invalid errors are reported to `warnings`, with `context` describing where
+/// they are declared.
+pub(crate) fn decode(
+    values: &[OptionValue],
+    index: &DescriptorIndex,
+    proto_path: &str,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<ServiceError> {
+    let mut errors = vec![];
+    for value in values {
+        let Some(name) = value.field("name").and_then(OptionValue::as_str) else {
+            warnings.push(format!("{}: error without a name is ignored", context));
+            continue;
+        };
+        let Some(code) = value.field("code").and_then(status_code) else {
+            warnings.push(format!(
+                "{}: error {} has an invalid status code and is ignored",
+                context, name
+            ));
+            continue;
+        };
+        let message = value
+            .field("message")
+            .and_then(OptionValue::as_str)
+            .unwrap_or_default()
+            .to_owned();
+        let detail = match value.field("detail").and_then(OptionValue::as_str) {
+            None | Some("") => None,
+            Some(detail) => {
+                let ty = format!(".{}", detail.trim_start_matches('.'));
+                match index.rust_type(proto_path, &ty) {
+                    Some(path) => Some(path),
+                    None => {
+                        warnings.push(format!(
+                            "{}: can not resolve detail type {} of error {}, error is ignored",
+                            context, detail, name
+                        ));
+                        continue;
+                    }
+                }
+            }
+        };
+        errors.push(ServiceError {
+            name: name.to_owned(),
+            code,
+            message,
+            detail,
+        });
+    }
+    errors
+}
+
+/// Generates the `{Service}Error` enum of `service`, to be appended to its
+/// client and server modules.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    if service.errors.is_empty() {
+        return TokenStream::new();
+    }
+    let error = format_ident!("{}Error", service.name);
+    let doc = format!(" Application errors of the `{}` service.", service.name);
+
+    let mut variants = vec![];
+    let mut constructors = vec![];
+    let mut codes = vec![];
+    let mut reasons = vec![];
+    let mut into_status = vec![];
+    let mut from_status = vec![];
+    for e in &service.errors {
+        let variant = format_ident!("{}", e.name.to_upper_camel_case());
+        let constructor = format_ident!("{}", e.name.to_snake_case());
+        let code = format_ident!("{}", e.code.to_upper_camel_case());
+        let name = &e.name;
+        let message = &e.message;
+        let doc = if message.is_empty() {
+            format!(" `{}` (`{}`).", e.name, e.code)
+        } else {
+            format!(" `{}` (`{}`): {}", e.name, e.code, message)
+        };
+        codes.push(quote! { #error::#variant { .. } => tonic::Code::#code, });
+        reasons.push(quote! { #error::#variant { .. } => Some(#name), });
+        match &e.detail {
+            None => {
+                variants.push(quote! {
+                    #[doc = #doc]
+                    #variant { message: String },
+                });
+                constructors.push(quote! {
+                    #[doc = #doc]
+                    pub fn #constructor() -> Self {
+                        #error::#variant { message: #message.to_owned() }
+                    }
+                });
+                into_status.push(quote! {
+                    #error::#variant { message } => {
+                        tonic::Status::with_metadata(tonic::Code::#code, message, metadata)
+                    }
+                });
+                from_status.push(quote! {
+                    Some(#name) => #error::#variant {
+                        message: status.message().to_owned(),
+                    },
+                });
+            }
+            Some(detail) => {
+                let detail: syn::Path = syn::parse_str(detail).unwrap();
+                variants.push(quote! {
+                    #[doc = #doc]
+                    #variant { message: String, detail: #detail },
+                });
+                constructors.push(quote! {
+                    #[doc = #doc]
+                    pub fn #constructor(detail: #detail) -> Self {
+                        #error::#variant { message: #message.to_owned(), detail }
+                    }
+                });
+                into_status.push(quote! {
+                    #error::#variant { message, detail } => {
+                        let details = ::protobuf::Message::write_to_bytes(&detail)
+                            .unwrap_or_default();
+                        tonic::Status::with_details_and_metadata(
+                            tonic::Code::#code,
+                            message,
+                            details.into(),
+                            metadata,
+                        )
+                    }
+                });
+                from_status.push(quote! {
+                    Some(#name) => match ::protobuf::Message::parse_from_bytes(status.details()) {
+                        Ok(detail) => #error::#variant {
+                            message: status.message().to_owned(),
+                            detail,
+                        },
+                        Err(_) => #error::Other(status),
+                    },
+                });
+            }
+        }
+    }
+
+    quote! {
+        /// The metadata key that carries the reason of an application error.
+        pub const ERROR_REASON_KEY: &str = "error-reason";
+        #[doc = #doc]
+        ///
+        /// Errors convert to a `tonic::Status` with their code and message, the
+        /// reason in the [`ERROR_REASON_KEY`] metadata and the encoded detail
+        /// message, if any, as status details. Statuses convert back to the
+        /// matching error, or to the `Other` variant.
+        #[derive(Debug, Clone)]
+        pub enum #error {
+            #(#variants)*
+            /// A status that is not an application error.
+            Other(tonic::Status),
+        }
+        impl #error {
+            #(#constructors)*
+            /// Returns the status code of the error.
+            pub fn code(&self) -> tonic::Code {
+                match self {
+                    #(#codes)*
+                    #error::Other(status) => status.code(),
+                }
+            }
+            /// Returns the reason of the error, unless it is `Other`.
+            pub fn reason(&self) -> Option<&'static str> {
+                match self {
+                    #(#reasons)*
+                    #error::Other(_) => None,
+                }
+            }
+        }
+        impl From<#error> for tonic::Status {
+            fn from(error: #error) -> Self {
+                let mut metadata = tonic::metadata::MetadataMap::new();
+                if let Some(reason) = error.reason() {
+                    metadata.insert(
+                        ERROR_REASON_KEY,
+                        tonic::metadata::MetadataValue::from_static(reason),
+                    );
+                }
+                match error {
+                    #(#into_status)*
+                    #error::Other(status) => status,
+                }
+            }
+        }
+        impl From<tonic::Status> for #error {
+            fn from(status: tonic::Status) -> Self {
+                let reason = status
+                    .metadata()
+                    .get(ERROR_REASON_KEY)
+                    .and_then(|reason| reason.to_str().ok())
+                    .map(|reason| reason.to_owned());
+                match reason.as_deref() {
+                    #(#from_status)*
+                    _ => #error::Other(status),
+                }
+            }
+        }
+        impl std::fmt::Display for #error {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let status = tonic::Status::from(self.clone());
+                write!(f, "{}: {}", status.code(), status.message())
+            }
+        }
+        impl std::error::Error for #error {}
+    }
+}
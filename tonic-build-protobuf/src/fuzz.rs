@@ -0,0 +1,64 @@
+//! Generation of cargo-fuzz targets, which feed arbitrary request bodies to
+//! the methods of services with `tonic_testing_protobuf::fuzz::call`.
+
+use heck::ToSnakeCase;
+
+use crate::{skeleton::package_path, Service};
+
+/// Renders a cargo-fuzz target per method of `service`, as pairs of the
+/// target name and the file content.
+///
+/// The server is referred to through `services_path`, the module including
+/// the [mod file](crate::Builder::mod_file), and the implementation to fuzz is
+/// created by `{factories_path}::{service}()`, e.g. `my_fuzz::storage()`.
+pub(crate) fn render(
+    service: &Service,
+    package: &str,
+    services_path: &str,
+    factories_path: &str,
+) -> Vec<(String, String)> {
+    let snake = service.name.to_snake_case();
+    let server = format!(
+        "{}::{}Server",
+        package_path(services_path, package),
+        service.name
+    );
+    let factory = format!("{}::{}", factories_path, snake);
+    for path in [&server, &factory] {
+        syn::parse_str::<syn::Path>(path).expect("invalid path");
+    }
+    let prefix = if package.is_empty() {
+        snake
+    } else {
+        format!("{}_{}", package.replace('.', "_").to_snake_case(), snake)
+    };
+
+    // The body of `fuzz_target!` is not formatted by prettyplease, so the
+    // file is rendered as text.
+    service
+        .methods
+        .iter()
+        .map(|method| {
+            let name = format!("{}_{}", prefix, method.route_name.to_snake_case());
+            let content = format!(
+                r#"//! Feeds arbitrary request bodies to the `{method}` method of the `{service}` service.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {{
+    ::tonic_testing_protobuf::fuzz::call(
+        {server}::new({factory}()),
+        "/{service}/{method}",
+        data,
+    );
+}});
+"#,
+                method = method.route_name,
+                service = service.full_name(),
+            );
+            (name, content)
+        })
+        .collect()
+}
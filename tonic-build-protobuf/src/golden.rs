@@ -0,0 +1,124 @@
+//! Golden-file (snapshot) testing of generated code.
+//!
+//! Render generated code with [`Builder::generate`](crate::Builder::generate)
+//! and compare it against checked-in files with [`assert_golden`]:
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn generated_code_is_up_to_date() {
+//!     let files = tonic_build_protobuf::Builder::new()
+//!         .proto_path("crate")
+//!         .generate(&["proto/debugpb.proto"], &["proto", "include"]);
+//!     tonic_build_protobuf::golden::assert_golden(&files, "tests/golden");
+//! }
+//! ```
+//!
+//! Run the test with `TONIC_BUILD_PROTOBUF_BLESS=1` to (re)write the golden
+//! files after an intended change.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::GeneratedFile;
+
+/// The environment variable that makes [`assert_golden`] overwrite golden
+/// files instead of comparing against them.
+pub const BLESS_ENV: &str = "TONIC_BUILD_PROTOBUF_BLESS";
+
+/// Asserts that `files` match the golden files in `dir`.
+///
+/// Every generated file must have a file with the same name and content in
+/// `dir`, and `dir` and its subdirectories must not contain stale ".rs" files
+/// that were not generated. If the [`BLESS_ENV`] environment variable is set,
+/// `dir` is updated to match `files` instead.
+///
+/// # Panics
+///
+/// Panics with a description of the first mismatch, if any.
+pub fn assert_golden(files: &[GeneratedFile], dir: impl AsRef<Path>) {
+    let dir = dir.as_ref();
+    let bless = std::env::var_os(BLESS_ENV).is_some();
+    if bless {
+        fs::create_dir_all(dir).unwrap();
+    }
+
+    let generated: BTreeSet<&str> = files.iter().map(|f| f.name.as_str()).collect();
+    for name in golden_file_names(dir) {
+        if generated.contains(name.as_str()) {
+            continue;
+        }
+        if bless {
+            fs::remove_file(dir.join(&name)).unwrap();
+        } else {
+            panic!(
+                "stale golden file {}, rerun with {BLESS_ENV}=1 to remove it",
+                dir.join(&name).display()
+            );
+        }
+    }
+
+    for file in files {
+        let path = dir.join(&file.name);
+        if bless {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(&path, &file.content).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!(
+                "missing golden file {}: {e}, rerun with {BLESS_ENV}=1 to create it",
+                path.display()
+            )
+        });
+        if let Some(mismatch) = first_mismatch(&expected, &file.content) {
+            panic!(
+                "generated code does not match golden file {}, rerun with {BLESS_ENV}=1 to \
+                 update it\n{mismatch}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Returns the paths of the ".rs" files in `dir` and its subdirectories,
+/// relative to `dir`, e.g. `"a/b/storage.rs"`.
+fn golden_file_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut names = vec![];
+    for entry in entries {
+        let entry = entry.unwrap();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type().unwrap().is_dir() {
+            let nested = golden_file_names(&entry.path());
+            names.extend(nested.into_iter().map(|n| format!("{}/{}", name, n)));
+        } else if name.ends_with(".rs") {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Describes the first line that differs between `expected` and `actual`.
+pub(crate) fn first_mismatch(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return Some(format!(
+                    "line {line}:\n  expected: {}\n    actual: {}",
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Generation of constructors that wait for services to report `SERVING`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::Service;
+
+/// Generate the `connect_when_serving` constructor of the client of
+/// `service`, to be appended to its client module.
+///
+/// The generated code refers to the `tonic_health_protobuf` crate, which the
+/// crate including it must depend on.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let client = format_ident!("{}Client", service.name);
+    let name = service.full_name();
+    let doc = format!(
+        " Attempt to create a new client of `dst`, once its health service \
+         reports\n `{name}` as `SERVING`, for at most `timeout`."
+    );
+
+    quote! {
+        impl #client<tonic::transport::Channel> {
+            #[doc = #doc]
+            ///
+            /// The channel connects lazily, so servers that are still starting
+            /// are waited for too.
+            pub async fn connect_when_serving<D>(
+                dst: D,
+                timeout: std::time::Duration,
+            ) -> Result<Self, tonic::Status>
+            where
+                D: TryInto<tonic::transport::Endpoint>,
+                D::Error: Into<StdError>,
+            {
+                let endpoint = dst
+                    .try_into()
+                    .map_err(|e| tonic::Status::invalid_argument(Into::<StdError>::into(e).to_string()))?;
+                let channel = endpoint.connect_lazy();
+                ::tonic_health_protobuf::pb::health_tonic::health_client::HealthClient::new(
+                    channel.clone(),
+                )
+                .wait_for_serving(#name, timeout)
+                .await?;
+                Ok(Self::new(channel))
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Injection of user provided fragments into the generated code, see
+//! [`Builder::client_method_prologue`](crate::Builder::client_method_prologue)
+//! and the related builder methods.
+
+use std::fmt;
+
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::visit_mut::VisitMut;
+
+use crate::{spans, Service};
+
+type MethodHook = Box<dyn Fn(&str) -> TokenStream>;
+type ModuleHook = Box<dyn Fn(&str, &str) -> TokenStream>;
+
+/// The configured hooks.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub(crate) client_prologue: Option<MethodHook>,
+    pub(crate) client_epilogue: Option<MethodHook>,
+    pub(crate) server_trait: Option<MethodHook>,
+    pub(crate) module_header: Option<ModuleHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("client_prologue", &self.client_prologue.is_some())
+            .field("client_epilogue", &self.client_epilogue.is_some())
+            .field("server_trait", &self.server_trait.is_some())
+            .field("module_header", &self.module_header.is_some())
+            .finish()
+    }
+}
+
+/// The fragments injected into the modules generated for one service.
+#[derive(Default)]
+pub(crate) struct Fragments {
+    client_header: TokenStream,
+    server_header: TokenStream,
+    server_trait: TokenStream,
+    /// The name of the server trait.
+    trait_name: String,
+}
+
+impl Hooks {
+    /// Returns the fragments the service and module hooks return for
+    /// `service`.
+    pub(crate) fn fragments(&self, service: &Service) -> Fragments {
+        let full_name = service.full_name();
+        let snake = service.name.to_snake_case();
+        let header = |suffix: &str| match &self.module_header {
+            Some(hook) => hook(&full_name, &format!("{}_{}", snake, suffix)),
+            None => TokenStream::new(),
+        };
+        Fragments {
+            client_header: header("client"),
+            server_header: header("server"),
+            server_trait: match &self.server_trait {
+                Some(hook) => hook(&full_name),
+                None => TokenStream::new(),
+            },
+            trait_name: service.name.clone(),
+        }
+    }
+
+    /// Injects the client method prologue and epilogue into every method of
+    /// the generated clients in `ast` that makes a call.
+    pub(crate) fn rewrite_client(&self, ast: &mut syn::File) {
+        if self.client_prologue.is_none() && self.client_epilogue.is_none() {
+            return;
+        }
+        ClientMethods { hooks: self }.visit_file_mut(ast);
+    }
+}
+
+impl Fragments {
+    /// Prepends the module header to the client module in `ast`.
+    pub(crate) fn apply_client(&mut self, ast: &mut syn::File) {
+        let header = std::mem::take(&mut self.client_header);
+        with_module(ast, "_client", |items| prepend(items, header));
+    }
+
+    /// Prepends the module header to the server module in `ast`, and appends
+    /// the server trait items to its trait.
+    pub(crate) fn apply_server(&mut self, ast: &mut syn::File) {
+        let header = std::mem::take(&mut self.server_header);
+        let trait_items = std::mem::take(&mut self.server_trait);
+        let trait_name = std::mem::take(&mut self.trait_name);
+        with_module(ast, "_server", |items| {
+            if !trait_items.is_empty() {
+                let extra: syn::ItemTrait =
+                    syn::parse2(quote!(trait Hooks { #trait_items })).expect("invalid trait items");
+                for item in items.iter_mut() {
+                    if let syn::Item::Trait(item) = item {
+                        if item.ident == trait_name {
+                            item.items.extend(extra.items);
+                            break;
+                        }
+                    }
+                }
+            }
+            prepend(items, header);
+        });
+    }
+}
+
+/// Runs `f` on the items of the generated module whose name ends with
+/// `suffix`.
+fn with_module(ast: &mut syn::File, suffix: &str, f: impl FnOnce(&mut Vec<syn::Item>)) {
+    for item in &mut ast.items {
+        if let syn::Item::Mod(module) = item {
+            if module.ident.to_string().ends_with(suffix) {
+                if let Some((_, content)) = &mut module.content {
+                    f(content);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn prepend(items: &mut Vec<syn::Item>, header: TokenStream) {
+    if header.is_empty() {
+        return;
+    }
+    let header: syn::File = syn::parse2(header).expect("invalid module header");
+    items.splice(0..0, header.items);
+}
+
+struct ClientMethods<'a> {
+    hooks: &'a Hooks,
+}
+
+impl VisitMut for ClientMethods<'_> {
+    fn visit_impl_item_fn_mut(&mut self, f: &mut syn::ImplItemFn) {
+        if f.sig.asyncness.is_none() {
+            return;
+        }
+        let Some(path) = spans::client_path(&mut f.block) else {
+            return;
+        };
+        let prologue = match &self.hooks.client_prologue {
+            Some(hook) => hook(&path),
+            None => TokenStream::new(),
+        };
+        let epilogue = match &self.hooks.client_epilogue {
+            Some(hook) => hook(&path),
+            None => TokenStream::new(),
+        };
+        let block = &f.block;
+        let block = if epilogue.is_empty() {
+            let stmts = &block.stmts;
+            quote!({
+                #prologue
+                #(#stmts)*
+            })
+        } else {
+            quote!({
+                #prologue
+                let response = async move #block.await;
+                #epilogue
+                response
+            })
+        };
+        f.block = syn::parse2(block).expect("invalid client method prologue or epilogue");
+    }
+}
@@ -0,0 +1,73 @@
+//! Generation of the list of idempotent methods from a custom option.
+//!
+//! Methods are marked idempotent with a bool extension of
+//! `google.protobuf.MethodOptions`, e.g.
+//!
+//! ```proto
+//! extend google.protobuf.MethodOptions {
+//!   bool idempotent = 50004;
+//! }
+//!
+//! service Config {
+//!   rpc Fetch(FetchRequest) returns (FetchResponse) {
+//!     option (myorg.idempotent) = true;
+//!   }
+//! }
+//! ```
+//!
+//! Client modules then list the paths of the marked unary methods in an
+//! `IDEMPOTENT_METHODS` constant, e.g. for a response cache layer on the
+//! channel, which sees the path of calls but not their options.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{options::OptionValue, Method, Service};
+
+/// Decodes whether `value` of the idempotent option marks `method`
+/// idempotent.
+///
+/// Invalid values and streaming methods are reported to `warnings`, with
+/// `context` describing where they are declared.
+pub(crate) fn decode(
+    value: &OptionValue,
+    method: &Method,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> bool {
+    let OptionValue::Bool(idempotent) = value else {
+        warnings.push(format!(
+            "{}: idempotent option must be a bool and is ignored",
+            context
+        ));
+        return false;
+    };
+    if *idempotent && (method.client_streaming || method.server_streaming) {
+        warnings.push(format!(
+            "{}: only unary methods are listed as idempotent, option is ignored",
+            context
+        ));
+        return false;
+    }
+    *idempotent
+}
+
+/// Generate the `IDEMPOTENT_METHODS` constant of `service`, to be appended to
+/// its client module.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let name = service.full_name();
+    let paths: Vec<_> = service
+        .methods
+        .iter()
+        .filter(|method| method.idempotent)
+        .map(|method| format!("/{}/{}", name, method.route_name))
+        .collect();
+    if paths.is_empty() {
+        return TokenStream::new();
+    }
+    quote! {
+        /// The paths of the unary methods marked idempotent, whose responses
+        /// can be cached, e.g. by `tonic_dynamic_protobuf::ResponseCache`.
+        pub const IDEMPOTENT_METHODS: &[&str] = &[#(#paths),*];
+    }
+}
@@ -0,0 +1,44 @@
+//! Generation of client methods taking the messages of a request stream as a
+//! plain iterator.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{client, Service, TonicVersion};
+
+/// Generate the `{method}_iter` variants of the client and bidirectional
+/// streaming methods of `service`, to be appended to its client module.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let methods: Vec<_> = service
+        .methods
+        .iter()
+        .filter(|method| method.client_streaming)
+        .map(|method| {
+            let name = format_ident!("{}", method.name);
+            let with_iter = format_ident!("{}_iter", method.name);
+            let (request, _) = method.request_response_types();
+            let (_, response) = client::method_types(method);
+            let doc = format!(
+                " Like [`Self::{}`], but sends the messages of `requests`.",
+                method.name
+            );
+            quote! {
+                #[doc = #doc]
+                pub async fn #with_iter<I>(
+                    &mut self,
+                    requests: I,
+                ) -> std::result::Result<#response, tonic::Status>
+                where
+                    I: IntoIterator<Item = #request>,
+                    I::IntoIter: std::marker::Send + 'static,
+                {
+                    self.#name(tonic::codegen::tokio_stream::iter(requests)).await
+                }
+            }
+        })
+        .collect();
+    if methods.is_empty() {
+        return TokenStream::new();
+    }
+    client::impl_block(service, tonic_version, quote!(), quote!(#(#methods)*))
+}
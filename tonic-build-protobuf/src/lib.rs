@@ -2,15 +2,59 @@
 
 use core::fmt;
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
 };
 
-use heck::{ToSnakeCase, ToUpperCamelCase};
+use heck::ToSnakeCase;
 use proc_macro2::TokenStream;
 use protobuf::descriptor;
 use quote::ToTokens;
-use tonic_build::CodeGenBuilder;
+
+mod api_docs;
+mod auth;
+mod bench;
+mod blocking;
+mod call_options;
+mod client;
+mod descriptors;
+mod domain;
+mod errors;
+mod fuzz;
+pub mod golden;
+mod health;
+mod hooks;
+mod idempotent;
+mod iter_methods;
+mod longrunning;
+mod manifest;
+mod metrics;
+mod mod_file;
+mod options;
+mod pagination;
+mod parameters;
+mod proto_set;
+mod retry;
+mod routing;
+mod runtime_helpers;
+mod schema_hash;
+mod select_codec;
+mod server_defaults;
+mod skeleton;
+mod smoke_tests;
+mod spans;
+mod standalone;
+mod trailers;
+mod uds;
+mod validate;
+mod verify;
+pub mod watch;
+mod web;
+
+pub use proto_set::ProtoSet;
+pub use server_defaults::ServerDefaults;
+pub use standalone::StandaloneCrate;
 
 /// A service descriptor.
 #[derive(Debug, Default)]
@@ -19,33 +63,47 @@ struct Service {
     name: String,
     /// The package name as it appears in the .proto file.
     package: String,
+    /// The name of the .proto file that defines the service.
+    proto_file: String,
+    /// The full path of the module rust-protobuf generates for `proto_file`,
+    /// unless it is generated for the lite runtime, without descriptors.
+    file_module: Option<String>,
+    /// The hash of the schema of the service, see [`schema_hash::compute`].
+    schema_hash: String,
+    /// The custom options of the service.
+    options: Vec<options::CustomOption>,
+    /// The application errors of the service.
+    errors: Vec<errors::ServiceError>,
+    /// The auth schemes declared by the custom service option.
+    auth: Vec<auth::AuthScheme>,
+    /// The binary trailers declared by the methods of the service.
+    trailers: Vec<trailers::Trailer>,
     /// The service methods.
     methods: Vec<Method>,
+    /// The validation functions of the requests of the methods.
+    validators: validate::Validators,
 }
 
-impl tonic_build::Service for Service {
-    type Comment = String;
-
-    type Method = Method;
-
-    fn name(&self) -> &str {
-        &self.name
-    }
-
-    fn package(&self) -> &str {
-        &self.package
-    }
-
-    fn identifier(&self) -> &str {
-        &self.name
-    }
-
-    fn methods(&self) -> &[Self::Method] {
-        &self.methods
+impl Service {
+    /// Returns the fully qualified name of the service, e.g.
+    /// `helloworld.Greeter`, or just its name if it has no package.
+    fn full_name(&self) -> String {
+        if self.package.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.package, self.name)
+        }
     }
 
-    fn comment(&self) -> &[Self::Comment] {
-        &[]
+    /// Returns the full paths of all Rust types referenced by the service.
+    fn rust_types(&self) -> impl Iterator<Item = &str> {
+        let methods = self
+            .methods
+            .iter()
+            .flat_map(|m| [m.input_type.as_str(), m.output_type.as_str()]);
+        let errors = self.errors.iter().filter_map(|e| e.detail.as_deref());
+        let trailers = self.trailers.iter().map(|t| t.rust_type.as_str());
+        methods.chain(errors).chain(trailers)
     }
 }
 
@@ -56,9 +114,9 @@ struct Method {
     name: String,
     /// The name of the method as should be used when constructing a route
     route_name: String,
-    /// The input Rust type.
+    /// The full path of the input Rust type.
     input_type: String,
-    /// The output Rust type.
+    /// The full path of the output Rust type.
     output_type: String,
     /// Identifies if client streams multiple client messages.
     client_streaming: bool,
@@ -66,55 +124,208 @@ struct Method {
     server_streaming: bool,
     /// The path to the codec to use for this method
     codec_path: String,
+    /// The retry policy declared by the custom method option.
+    retry: Option<retry::RetryPolicy>,
+    /// The page items, if this is a list method.
+    pagination: Option<pagination::Pagination>,
+    /// The response and metadata types, if this is a long-running method.
+    operation: Option<longrunning::OperationInfo>,
+    /// Whether the method is marked idempotent by the custom method option.
+    idempotent: bool,
+    /// The function validating requests, if the request type has rules.
+    validator: Option<String>,
+    /// The domain types of the request and response, declared by the custom
+    /// message option.
+    domain: domain::DomainTypes,
 }
 
-impl tonic_build::Method for Method {
-    type Comment = String;
+impl Method {
+    /// Returns the request and response Rust types.
+    fn request_response_types(&self) -> (TokenStream, TokenStream) {
+        let convert_type = |rust_type: &str| -> TokenStream {
+            syn::parse_str::<syn::Path>(rust_type)
+                .unwrap()
+                .to_token_stream()
+        };
 
-    fn name(&self) -> &str {
-        &self.name
+        let request = convert_type(&self.input_type);
+        let response = convert_type(&self.output_type);
+        (request, response)
     }
+}
 
-    fn identifier(&self) -> &str {
-        &self.route_name
-    }
+/// Implements the `Service` and `Method` traits of the given tonic-build
+/// crate.
+macro_rules! impl_tonic_build {
+    ($tonic_build:ident) => {
+        impl $tonic_build::Service for Service {
+            type Comment = String;
 
-    fn codec_path(&self) -> &str {
-        &self.codec_path
-    }
+            type Method = Method;
 
-    fn client_streaming(&self) -> bool {
-        self.client_streaming
-    }
+            fn name(&self) -> &str {
+                &self.name
+            }
 
-    fn server_streaming(&self) -> bool {
-        self.server_streaming
-    }
+            fn package(&self) -> &str {
+                &self.package
+            }
 
-    fn comment(&self) -> &[Self::Comment] {
-        &[]
-    }
+            fn identifier(&self) -> &str {
+                &self.name
+            }
 
-    fn request_response_name(
-        &self,
-        proto_path: &str,
-        _compile_well_known_types: bool,
-    ) -> (TokenStream, TokenStream) {
-        let convert_type = |rust_type: &str| -> TokenStream {
-            if rust_type.is_empty() {
-                syn::parse_str::<syn::Path>(rust_type)
-                    .unwrap()
-                    .to_token_stream()
-            } else {
-                syn::parse_str::<syn::Path>(&format!("{}{}", proto_path, rust_type))
-                    .unwrap()
-                    .to_token_stream()
+            fn methods(&self) -> &[Self::Method] {
+                &self.methods
             }
-        };
 
-        let request = convert_type(&self.input_type);
-        let response = convert_type(&self.output_type);
-        (request, response)
+            fn comment(&self) -> &[Self::Comment] {
+                &[]
+            }
+        }
+
+        impl $tonic_build::Method for Method {
+            type Comment = String;
+
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn identifier(&self) -> &str {
+                &self.route_name
+            }
+
+            fn codec_path(&self) -> &str {
+                &self.codec_path
+            }
+
+            fn client_streaming(&self) -> bool {
+                self.client_streaming
+            }
+
+            fn server_streaming(&self) -> bool {
+                self.server_streaming
+            }
+
+            fn comment(&self) -> &[Self::Comment] {
+                &[]
+            }
+
+            fn request_response_name(
+                &self,
+                _proto_path: &str,
+                _compile_well_known_types: bool,
+            ) -> (TokenStream, TokenStream) {
+                self.request_response_types()
+            }
+        }
+    };
+}
+
+impl_tonic_build!(tonic_build);
+#[cfg(feature = "tonic-0-12")]
+impl_tonic_build!(tonic_build012);
+#[cfg(feature = "tonic-0-13")]
+impl_tonic_build!(tonic_build013);
+#[cfg(feature = "tonic-0-14")]
+impl_tonic_build!(tonic_build014);
+
+/// Generates a client or a server with the given tonic-build crate.
+macro_rules! code_gen {
+    ($tonic_build:ident, $builder:expr, $service:expr,server) => {
+        $tonic_build::CodeGenBuilder::new()
+            .emit_package(true)
+            .compile_well_known_types(false)
+            .generate_server($service, &$builder.proto_path)
+    };
+    ($tonic_build:ident, $builder:expr, $service:expr,client) => {
+        $tonic_build::CodeGenBuilder::new()
+            .emit_package(true)
+            .compile_well_known_types(false)
+            .build_transport($builder.build_transport)
+            .generate_client($service, &$builder.proto_path)
+    };
+}
+
+/// How generated files are named, unless named by [`Builder::file_name`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum FileNaming {
+    /// After the package and the service, e.g. `a_b_storage.rs` for the
+    /// service `Storage` in package `a.b`.
+    #[default]
+    PackageService,
+    /// After the .proto file that defines the service, e.g. `storage.rs` for
+    /// `a/b/storage.proto`, like the files of prost-build. If the file defines
+    /// several services, the service is appended, e.g. `storage_admin.rs`.
+    /// Files of the same name in different packages are prefixed with their
+    /// package, e.g. `a_b_storage.rs`.
+    ProtoFile,
+}
+
+/// The tonic release that generated code targets.
+///
+/// Generated code is emitted by the matching tonic-build release, so it has
+/// the exact shape that release of tonic expects. Versions other than
+/// [`TonicVersion::V0_11`] require enabling the corresponding `tonic-0-*`
+/// feature, and the same feature of `tonic-codec-protobuf` so that its codecs
+/// implement the matching tonic traits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TonicVersion {
+    /// tonic 0.11.
+    #[default]
+    V0_11,
+    /// tonic 0.12.
+    #[cfg(feature = "tonic-0-12")]
+    V0_12,
+    /// tonic 0.13.
+    #[cfg(feature = "tonic-0-13")]
+    V0_13,
+    /// tonic 0.14.
+    #[cfg(feature = "tonic-0-14")]
+    V0_14,
+}
+
+impl TonicVersion {
+    /// The request body type of generated clients and servers.
+    fn body_type(self) -> TokenStream {
+        match self {
+            TonicVersion::V0_11 => quote::quote!(tonic::body::BoxBody),
+            #[cfg(feature = "tonic-0-12")]
+            TonicVersion::V0_12 => quote::quote!(tonic::body::BoxBody),
+            #[cfg(feature = "tonic-0-13")]
+            TonicVersion::V0_13 => quote::quote!(tonic::body::Body),
+            #[cfg(feature = "tonic-0-14")]
+            TonicVersion::V0_14 => quote::quote!(tonic::body::Body),
+        }
+    }
+
+    /// The version requirement of tonic in manifests, e.g. `"0.11"`.
+    fn crate_version(self) -> &'static str {
+        match self {
+            TonicVersion::V0_11 => "0.11",
+            #[cfg(feature = "tonic-0-12")]
+            TonicVersion::V0_12 => "0.12",
+            #[cfg(feature = "tonic-0-13")]
+            TonicVersion::V0_13 => "0.13",
+            #[cfg(feature = "tonic-0-14")]
+            TonicVersion::V0_14 => "0.14",
+        }
+    }
+
+    /// The feature of this crate and of `tonic-codec-protobuf` that
+    /// supports the version, if it is not the default one.
+    fn feature(self) -> Option<&'static str> {
+        match self {
+            TonicVersion::V0_11 => None,
+            #[cfg(feature = "tonic-0-12")]
+            TonicVersion::V0_12 => Some("tonic-0-12"),
+            #[cfg(feature = "tonic-0-13")]
+            TonicVersion::V0_13 => Some("tonic-0-13"),
+            #[cfg(feature = "tonic-0-14")]
+            TonicVersion::V0_14 => Some("tonic-0-14"),
+        }
     }
 }
 
@@ -122,27 +333,190 @@ struct ServiceGenerator {
     builder: Builder,
     clients: TokenStream,
     servers: TokenStream,
+    /// Extra items appended to the generated client module.
+    client_items: TokenStream,
+    /// Extra items appended to the generated server module.
+    server_items: TokenStream,
+    /// The smoke tests appended to the generated file.
+    smoke_tests: TokenStream,
+    /// The request validation functions, by method path.
+    validators: HashMap<String, String>,
+    /// The fragments of the hooks injected into the generated modules.
+    fragments: hooks::Fragments,
 }
 
 impl ServiceGenerator {
     fn generate(&mut self, service: &Service) {
+        self.fragments = self.builder.hooks.fragments(service);
+        assert!(
+            !self.builder.runtime_helpers || self.builder.tonic_version == TonicVersion::V0_11,
+            "`Builder::runtime_helpers` requires tonic 0.11"
+        );
         if self.builder.build_server {
-            let server = CodeGenBuilder::new()
-                .emit_package(true)
-                .compile_well_known_types(false)
-                .generate_server(service, &self.builder.proto_path);
+            let builder = &self.builder;
+            let server = match builder.tonic_version {
+                TonicVersion::V0_11 => code_gen!(tonic_build, builder, service, server),
+                #[cfg(feature = "tonic-0-12")]
+                TonicVersion::V0_12 => code_gen!(tonic_build012, builder, service, server),
+                #[cfg(feature = "tonic-0-13")]
+                TonicVersion::V0_13 => code_gen!(tonic_build013, builder, service, server),
+                #[cfg(feature = "tonic-0-14")]
+                TonicVersion::V0_14 => code_gen!(tonic_build014, builder, service, server),
+            };
 
             self.servers.extend(server);
+            self.server_items.extend(options::generate_constants(
+                "service_options",
+                " Custom options of the service.",
+                &service.options,
+            ));
+            self.server_items.extend(errors::generate(service));
+            self.server_items.extend(trailers::generate(service));
+            self.server_items.extend(domain::generate_server(service));
+            if builder.build_service_descriptors {
+                self.server_items.extend(descriptors::generate(service));
+            }
+            if builder.build_schema_hash {
+                self.server_items.extend(schema_hash::generate(service));
+            }
+            self.server_items.extend(routing::generate(
+                service,
+                builder.tonic_version,
+                builder.build_transport,
+                &builder.codec_path,
+            ));
+            if let Some(server_defaults) = &builder.server_defaults {
+                if builder.build_transport {
+                    self.server_items.extend(server_defaults.generate(service));
+                }
+            }
+            if builder.build_web && builder.build_transport {
+                self.server_items.extend(web::generate(service));
+            }
+            if builder.validate_requests {
+                assert!(
+                    builder.tonic_version == TonicVersion::V0_11,
+                    "`Builder::validate_requests` requires tonic 0.11"
+                );
+                self.server_items.extend(service.validators.generate());
+                for method in &service.methods {
+                    if let Some(validator) = &method.validator {
+                        let path = format!("/{}/{}", service.full_name(), method.route_name);
+                        self.validators.insert(path, validator.clone());
+                    }
+                }
+            }
         }
 
         if self.builder.build_client {
-            let client = CodeGenBuilder::new()
-                .emit_package(true)
-                .compile_well_known_types(false)
-                .build_transport(self.builder.build_transport)
-                .generate_client(service, &self.builder.proto_path);
+            let builder = &self.builder;
+            let client = match builder.tonic_version {
+                TonicVersion::V0_11 => code_gen!(tonic_build, builder, service, client),
+                #[cfg(feature = "tonic-0-12")]
+                TonicVersion::V0_12 => code_gen!(tonic_build012, builder, service, client),
+                #[cfg(feature = "tonic-0-13")]
+                TonicVersion::V0_13 => code_gen!(tonic_build013, builder, service, client),
+                #[cfg(feature = "tonic-0-14")]
+                TonicVersion::V0_14 => code_gen!(tonic_build014, builder, service, client),
+            };
 
             self.clients.extend(client);
+            if builder.build_blocking_client {
+                self.client_items.extend(blocking::generate(
+                    service,
+                    builder.tonic_version,
+                    builder.build_transport,
+                ));
+            }
+            if builder.build_transport {
+                self.client_items.extend(auth::generate(service));
+            }
+            if builder.build_uds_connect && builder.build_transport {
+                self.client_items
+                    .extend(uds::generate(service, builder.tonic_version));
+            }
+            if builder.build_health_wait && builder.build_transport {
+                assert!(
+                    builder.tonic_version == TonicVersion::V0_11,
+                    "`Builder::build_health_wait` requires tonic 0.11"
+                );
+                self.client_items.extend(health::generate(service));
+            }
+            if builder.build_call_options {
+                self.client_items
+                    .extend(call_options::generate(service, builder.tonic_version));
+            }
+            if builder.build_iter_methods {
+                self.client_items
+                    .extend(iter_methods::generate(service, builder.tonic_version));
+            }
+            self.client_items.extend(options::generate_constants(
+                "service_options",
+                " Custom options of the service.",
+                &service.options,
+            ));
+            self.client_items.extend(errors::generate(service));
+            self.client_items.extend(trailers::generate(service));
+            self.client_items
+                .extend(retry::generate(service, builder.tonic_version));
+            self.client_items.extend(idempotent::generate(service));
+            if builder.build_service_descriptors {
+                self.client_items.extend(descriptors::generate(service));
+            }
+            if builder.build_schema_hash {
+                self.client_items.extend(schema_hash::generate(service));
+            }
+            self.client_items
+                .extend(domain::generate_client(service, builder.tonic_version));
+            self.client_items
+                .extend(pagination::generate(service, builder.tonic_version));
+            self.client_items
+                .extend(longrunning::generate(service, builder.tonic_version));
+        }
+
+        let builder = &self.builder;
+        if builder.build_smoke_tests
+            && builder.build_client
+            && builder.build_server
+            && builder.build_transport
+        {
+            assert!(
+                builder.tonic_version == TonicVersion::V0_11,
+                "`Builder::build_smoke_tests` requires tonic 0.11"
+            );
+            self.smoke_tests.extend(smoke_tests::generate(service));
+        }
+    }
+
+    /// Adds the configured `#[allow(...)]` attribute to every generated item,
+    /// unless it is emitted as an inner attribute at the top of the file.
+    fn allow_lints(&self, ast: &mut syn::File) {
+        if self.builder.lint_allows.is_empty() || self.builder.inner_lint_attributes {
+            return;
+        }
+        let attr = self.builder.lint_allows_attribute();
+        for item in &mut ast.items {
+            if let syn::Item::Mod(item) = item {
+                item.attrs.push(attr.clone());
+            }
+        }
+    }
+
+    /// Appends `items` to the generated module whose name ends with `suffix`.
+    fn extend_module(ast: &mut syn::File, suffix: &str, items: TokenStream) {
+        if items.is_empty() {
+            return;
+        }
+        let items: syn::File = syn::parse2(items).expect("not a valid tokenstream");
+        for item in &mut ast.items {
+            if let syn::Item::Mod(module) = item {
+                if module.ident.to_string().ends_with(suffix) {
+                    if let Some((_, content)) = &mut module.content {
+                        content.extend(items.items);
+                        return;
+                    }
+                }
+            }
         }
     }
 
@@ -154,7 +528,17 @@ impl ServiceGenerator {
                 #clients
             };
 
-            let ast: syn::File = syn::parse2(client_service).expect("not a valid tokenstream");
+            let mut ast: syn::File = syn::parse2(client_service).expect("not a valid tokenstream");
+            Self::extend_module(&mut ast, "_client", std::mem::take(&mut self.client_items));
+            self.fragments.apply_client(&mut ast);
+            self.builder.hooks.rewrite_client(&mut ast);
+            if self.builder.build_tracing {
+                spans::rewrite_client(&mut ast);
+            }
+            if self.builder.runtime_helpers {
+                runtime_helpers::rewrite_client(&mut ast);
+            }
+            self.allow_lints(&mut ast);
             let code = prettyplease::unparse(&ast);
             buf.push_str(&code);
 
@@ -168,17 +552,175 @@ impl ServiceGenerator {
                 #servers
             };
 
-            let ast: syn::File = syn::parse2(server_service).expect("not a valid tokenstream");
+            let mut ast: syn::File = syn::parse2(server_service).expect("not a valid tokenstream");
+            Self::extend_module(&mut ast, "_server", std::mem::take(&mut self.server_items));
+            self.fragments.apply_server(&mut ast);
+            if self.builder.select_codec {
+                select_codec::rewrite(&mut ast);
+            }
+            if self.builder.validate_requests {
+                validate::rewrite_server(&mut ast, &std::mem::take(&mut self.validators));
+            }
+            if self.builder.build_metrics {
+                metrics::rewrite_server(&mut ast);
+            }
+            if self.builder.build_tracing {
+                spans::rewrite_server(&mut ast);
+            }
+            if self.builder.runtime_helpers {
+                runtime_helpers::rewrite_server(&mut ast);
+            }
+            self.allow_lints(&mut ast);
             let code = prettyplease::unparse(&ast);
             buf.push_str(&code);
 
             self.servers = TokenStream::default();
         }
+
+        if !self.smoke_tests.is_empty() {
+            let tests = std::mem::take(&mut self.smoke_tests);
+            let mut ast: syn::File = syn::parse2(tests).expect("not a valid tokenstream");
+            self.allow_lints(&mut ast);
+            let code = prettyplease::unparse(&ast);
+            buf.push_str(&code);
+        }
+    }
+}
+
+/// Prints `warnings` to stderr in hermetic mode, and as cargo warnings
+/// otherwise.
+fn report_warnings(warnings: Vec<String>, hermetic: bool) {
+    for warning in warnings {
+        if hermetic {
+            eprintln!("warning: {}", warning);
+        } else {
+            println!("cargo:warning={}", warning);
+        }
+    }
+}
+
+/// Writes `files` into `dir`, except those that already exist, which are
+/// returned as warnings.
+fn write_new_files(files: Vec<GeneratedFile>, dir: &Path) -> Vec<String> {
+    let mut warnings = vec![];
+    for file in files {
+        let path = dir.join(&file.name);
+        if path.exists() {
+            warnings.push(format!(
+                "{} already exists and is not overwritten",
+                path.display()
+            ));
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, file.content).unwrap();
+    }
+    warnings
+}
+
+/// Returns the names of the files generated for `services`, see
+/// [`service_file_name`].
+///
+/// Services whose default names collide, e.g. two `admin.proto` files in
+/// different packages with [`FileNaming::ProtoFile`], are disambiguated with
+/// their full package. Remaining collisions, and those of names returned by
+/// `file_name_fn`, panic instead of overwriting each other.
+fn service_file_names(
+    builder: &Builder,
+    file_name_fn: Option<&FileNameFn>,
+    services: &[Service],
+) -> Vec<String> {
+    let mut names: Vec<_> = services
+        .iter()
+        .map(|service| service_file_name(builder, file_name_fn, service, services))
+        .collect();
+    let collisions = |names: &[String]| {
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let mut collisions = BTreeMap::new();
+        for (i, name) in names.iter().enumerate() {
+            if let Some(&first) = seen.get(name.as_str()) {
+                collisions.entry(first).or_insert_with(Vec::new).push(i);
+            } else {
+                seen.insert(name, i);
+            }
+        }
+        collisions
+    };
+    if file_name_fn.is_none() && !builder.package_dirs {
+        for (first, others) in collisions(&names) {
+            for i in std::iter::once(first).chain(others) {
+                let package = builder.rust_package(&services[i].package);
+                if !package.is_empty() {
+                    let package = rust_mod_name_convention(package);
+                    names[i] = format!("{}_{}", package, names[i]);
+                }
+            }
+        }
+    }
+    let collisions = collisions(&names);
+    if !collisions.is_empty() {
+        let lines: Vec<_> = collisions
+            .into_iter()
+            .map(|(first, others)| {
+                let services: Vec<_> = std::iter::once(first)
+                    .chain(others)
+                    .map(|i| services[i].full_name())
+                    .collect();
+                format!("{}: {}", names[first], services.join(", "))
+            })
+            .collect();
+        panic!(
+            "services would be generated into the same files, name them apart with \
+             `Builder::file_name`:\n{}",
+            lines.join("\n")
+        );
+    }
+    names
+}
+
+/// Returns the name of the file generated for `service`, one of `services`,
+/// see [`Builder::file_name`], [`Builder::file_naming`] and
+/// [`Builder::package_dirs`].
+fn service_file_name(
+    builder: &Builder,
+    file_name_fn: Option<&FileNameFn>,
+    service: &Service,
+    services: &[Service],
+) -> String {
+    let package = builder.rust_package(&service.package);
+    let file_name = match file_name_fn {
+        Some(file_name) => (file_name.0)(&service.proto_file, &service.package, &service.name),
+        None if builder.file_naming == FileNaming::ProtoFile => {
+            let file = service.proto_file.rsplit('/').next().unwrap_or_default();
+            let stem = file.strip_suffix(".proto").unwrap_or(file);
+            let shared = services
+                .iter()
+                .filter(|other| other.proto_file == service.proto_file)
+                .count()
+                > 1;
+            if shared {
+                format!("{}_{}", stem, service.name)
+            } else {
+                stem.to_owned()
+            }
+        }
+        // The package is already in the directory.
+        None if builder.package_dirs || package.is_empty() => service.name.clone(),
+        None => format!("{}_{}", package, service.name),
+    };
+    let mut name = format!("{}.rs", rust_mod_name_convention(&file_name));
+    if builder.package_dirs {
+        for segment in package.rsplit('.').filter(|s| !s.is_empty()) {
+            name = format!("{}/{}", rust_mod_name_convention(segment), name);
+        }
     }
+    name
 }
 
 #[allow(clippy::type_complexity)]
-struct FileNameFn(Box<dyn Fn(&str, &str) -> String>);
+struct FileNameFn(Box<dyn Fn(&str, &str, &str) -> String>);
 
 impl fmt::Debug for FileNameFn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -186,6 +728,27 @@ impl fmt::Debug for FileNameFn {
     }
 }
 
+/// A file generated by [`Builder::generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// The file name, including the extension. With
+    /// [`Builder::package_dirs`], the path relative to the output directory,
+    /// e.g. `"a/b/storage.rs"`.
+    pub name: String,
+    /// The generated Rust code, or Markdown for the
+    /// [API reference](Builder::api_docs).
+    pub content: String,
+    /// The .proto file that defines the service, e.g. `"debugpb.proto"`.
+    /// Empty for the [mod file](Builder::mod_file) and the API reference.
+    pub proto_file: String,
+    /// The full package of the service, e.g. `"debugpb"`. Empty for the mod
+    /// file and the API reference.
+    pub package: String,
+    /// The name of the service, e.g. `"Debug"`. Empty for the mod file and
+    /// the API reference.
+    pub service: String,
+}
+
 /// Service generator builder.
 #[derive(Debug)]
 pub struct Builder {
@@ -195,8 +758,44 @@ pub struct Builder {
     build_client: bool,
     build_transport: bool,
     codec_path: String,
+    tonic_version: TonicVersion,
+    lint_allows: Vec<String>,
+    inner_lint_attributes: bool,
+    server_defaults: Option<ServerDefaults>,
+    build_web: bool,
+    build_call_options: bool,
+    build_iter_methods: bool,
+    build_service_descriptors: bool,
+    build_schema_hash: bool,
+    build_uds_connect: bool,
+    build_blocking_client: bool,
+    build_smoke_tests: bool,
+    build_health_wait: bool,
+    select_codec: bool,
+    build_tracing: bool,
+    build_metrics: bool,
+    validate_requests: bool,
+    runtime_helpers: bool,
+    hooks: hooks::Hooks,
+    error_option: Option<String>,
+    auth_option: Option<String>,
+    trailer_option: Option<String>,
+    retry_option: Option<String>,
+    idempotent_option: Option<String>,
+    domain_type_option: Option<String>,
+    longrunning_operations: bool,
+    verify_dir: Option<PathBuf>,
+    mod_file: Option<String>,
+    api_docs: Option<String>,
+    file_naming: FileNaming,
+    default_package: Option<String>,
+    package_dirs: bool,
+    register_types: bool,
+    protoc_path: Option<PathBuf>,
+    hermetic: bool,
 
     out_dir: Option<PathBuf>,
+    manifest: Option<PathBuf>,
 }
 
 impl Default for Builder {
@@ -204,13 +803,47 @@ impl Default for Builder {
         Self {
             proto_path: "super".to_owned(),
             codec_path: "::tonic_codec_protobuf::ProtobufCodecV3".to_string(),
-            file_name_fn: Some(FileNameFn(Box::new(|package_name, service_name| {
-                format!("{}_{}", package_name, service_name)
-            }))),
+            file_name_fn: None,
             build_server: true,
             build_client: true,
             build_transport: true,
+            tonic_version: TonicVersion::default(),
+            lint_allows: Vec::new(),
+            inner_lint_attributes: false,
+            server_defaults: None,
+            build_web: false,
+            build_call_options: false,
+            build_iter_methods: false,
+            build_service_descriptors: false,
+            build_schema_hash: false,
+            build_uds_connect: false,
+            build_blocking_client: false,
+            build_smoke_tests: false,
+            build_health_wait: false,
+            select_codec: false,
+            build_tracing: false,
+            build_metrics: false,
+            validate_requests: false,
+            runtime_helpers: false,
+            hooks: hooks::Hooks::default(),
+            error_option: None,
+            auth_option: None,
+            trailer_option: None,
+            retry_option: None,
+            idempotent_option: None,
+            domain_type_option: None,
+            longrunning_operations: false,
+            verify_dir: None,
+            mod_file: None,
+            api_docs: None,
+            file_naming: FileNaming::default(),
+            default_package: None,
+            package_dirs: false,
+            register_types: false,
+            protoc_path: None,
+            hermetic: false,
             out_dir: None,
+            manifest: None,
         }
     }
 }
@@ -246,10 +879,31 @@ impl Builder {
     /// with `package_name` and `service_name`, and it should return a name
     /// without ".rs" extension.
     ///
-    /// This defaults to `"{package_name}_{service_name}"`.
+    /// `package_name` is the full dotted package as it appears in the .proto
+    /// file, e.g. `"a.b.storage"`.
+    ///
+    /// This defaults to `"{package_name}_{service_name}"`, or to
+    /// `"{service_name}"` with [`Builder::package_dirs`], see also
+    /// [`Builder::file_naming`].
+    ///
+    /// Generation panics if the names of several services are the same,
+    /// instead of letting one file overwrite the other.
     pub fn file_name<F>(mut self, file_name_fn: F) -> Self
     where
         F: Fn(&str, &str) -> String + 'static,
+    {
+        self.file_name_fn = Some(FileNameFn(Box::new(
+            move |_proto_file, package_name, service_name| file_name_fn(package_name, service_name),
+        )));
+        self
+    }
+
+    /// Like [`Builder::file_name`], but the `file_name_fn` is also provided
+    /// with the name of the .proto file that defines the service, e.g.
+    /// `"storage/v1/storage.proto"`.
+    pub fn file_name_with_proto<F>(mut self, file_name_fn: F) -> Self
+    where
+        F: Fn(&str, &str, &str) -> String + 'static,
     {
         self.file_name_fn = Some(FileNameFn(Box::new(file_name_fn)));
         self
@@ -280,178 +934,2922 @@ impl Builder {
         self
     }
 
-    /// Set the output directory to generate code to.
+    /// Select the tonic release that generated code targets.
     ///
-    /// Defaults to the `OUT_DIR` environment variable.
-    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Self {
-        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+    /// This defaults to [`TonicVersion::V0_11`].
+    pub fn tonic_version(mut self, tonic_version: TonicVersion) -> Self {
+        self.tonic_version = tonic_version;
         self
     }
 
-    /// Performs code generation for the provided services.
+    /// Allow a lint in generated code, e.g. `"clippy::pedantic"`,
+    /// `"missing_docs"` or `"unused"`.
     ///
-    /// Generated services will be output into the directory specified by
-    /// `out_dir` with files named specified by [`Builder::file_name`].
-    pub fn compile(self, protos: &[impl AsRef<Path>], includes: &[impl AsRef<Path>]) {
-        let fds = self.build_file_descriptor_set(protos, includes);
-        let mut services = vec![];
-        for fd in fds.file {
-            services.extend(self.build_services(fd));
-        }
-        self.compile_svc(&services);
+    /// By default, allowed lints are emitted as an `#[allow(...)]` attribute
+    /// on every generated module, which works with both `mod` and
+    /// `include!`. See [`Builder::inner_lint_attributes`].
+    pub fn allow_lint(mut self, lint: impl AsRef<str>) -> Self {
+        self.lint_allows.push(lint.as_ref().to_string());
+        self
     }
 
-    fn build_file_descriptor_set(
-        &self,
-        protos: &[impl AsRef<Path>],
-        includes: &[impl AsRef<Path>],
-    ) -> descriptor::FileDescriptorSet {
-        protobuf_parse::Parser::new()
-            .protoc()
-            .inputs(protos)
-            .includes(includes)
-            .file_descriptor_set()
-            .expect("protoc failed")
+    /// Emit allowed lints as a single `#![allow(...)]` inner attribute at the
+    /// top of generated files instead of on every generated module.
+    ///
+    /// Inner attributes are not permitted in files that are `include!`d, so
+    /// only enable this if generated files are declared as modules.
+    ///
+    /// Defaults to `false`.
+    pub fn inner_lint_attributes(mut self, enable: bool) -> Self {
+        self.inner_lint_attributes = enable;
+        self
     }
 
-    /// Performs code generation for the provided services.
-    fn compile_svc(mut self, services: &[Service]) {
-        let out_dir = if let Some(out_dir) = self.out_dir.as_ref() {
-            out_dir.clone()
-        } else {
-            PathBuf::from(std::env::var("OUT_DIR").unwrap())
-        };
-
-        let file_name = self.file_name_fn.take().unwrap();
-        let mut generator = ServiceGenerator {
-            builder: self,
-            clients: TokenStream::default(),
-            servers: TokenStream::default(),
-        };
+    /// Generate helpers that serve each service with the given defaults, see
+    /// [`ServerDefaults`].
+    ///
+    /// Helpers are only generated if transport code is enabled, see
+    /// [`Builder::build_transport`].
+    pub fn server_defaults(mut self, server_defaults: ServerDefaults) -> Self {
+        self.server_defaults = Some(server_defaults);
+        self
+    }
 
-        for service in services {
-            generator.generate(service);
-            let mut output = String::new();
-            generator.finalize(&mut output);
+    /// Enable or disable generating the glue that exposes services to
+    /// browsers via `tonic-web`.
+    ///
+    /// Every server module then contains `web_service(inner)`, which wraps the
+    /// service in the grpc-web translation layer, and
+    /// `web_server_builder(cors)`, which returns a server builder accepting
+    /// HTTP/1.1 with a CORS layer of your choice applied. The crate including
+    /// the generated code must depend on `tonic-web` and `tower-layer`.
+    /// Requires [`Builder::build_transport`].
+    ///
+    /// Defaults to `false`.
+    pub fn build_web(mut self, enable: bool) -> Self {
+        self.build_web = enable;
+        self
+    }
 
-            let file_name = (file_name.0)(&service.package, &service.name);
-            let mod_name = rust_mod_name_convention(&file_name);
-            let out_file = out_dir.join(&format!("{}.rs", mod_name));
-            fs::write(out_file, output).unwrap();
-        }
+    /// Enable or disable generating a `CallOptions` struct in client modules,
+    /// together with a `{method}_with_options` variant of every client method
+    /// that applies a timeout, compression and default metadata to the call.
+    ///
+    /// Defaults to `false`.
+    pub fn build_call_options(mut self, enable: bool) -> Self {
+        self.build_call_options = enable;
+        self
     }
 
-    /// Build services from the provided `FileDescriptorProto`.
-    fn build_services(&self, fd: descriptor::FileDescriptorProto) -> Vec<Service> {
-        let package_name = &protobuf_path_to_rust_mod(fd.package());
+    /// Enable or disable generating a `{method}_iter` variant of every client
+    /// and bidirectional streaming client method, which takes the request
+    /// messages as an `IntoIterator`, e.g. a `Vec`, instead of a stream.
+    ///
+    /// The generated client methods already take any `Stream` of messages.
+    ///
+    /// Defaults to `false`.
+    pub fn build_iter_methods(mut self, enable: bool) -> Self {
+        self.build_iter_methods = enable;
+        self
+    }
 
-        let mut services = vec![];
-        for svc in &fd.service {
-            let build_method = |m: &descriptor::MethodDescriptorProto| Method {
-                name: rust_method_name_convention(m.name()),
-                route_name: m.name().to_owned(),
-                input_type: protobuf_path_to_rust_path(m.input_type()),
-                output_type: protobuf_path_to_rust_path(m.output_type()),
-                codec_path: self.codec_path.to_owned(),
-                client_streaming: m.client_streaming(),
-                server_streaming: m.server_streaming(),
-            };
-            let build_service = |svc: &descriptor::ServiceDescriptorProto| Service {
-                name: svc.name().to_owned(),
-                package: package_name.to_owned(),
-                methods: svc.method.iter().map(build_method).collect(),
-            };
-            services.push(build_service(svc));
-        }
+    /// Enable or disable generating `service_descriptor()` and
+    /// `file_descriptor()` functions in client and server modules, which
+    /// return the rust-protobuf descriptors of the service and its .proto
+    /// file, e.g. for reflection or validation of exactly the served
+    /// services.
+    ///
+    /// The descriptors are found under [`Builder::proto_path`]. Files
+    /// generated for the lite runtime have no descriptors and are skipped.
+    ///
+    /// Defaults to `false`.
+    pub fn build_service_descriptors(mut self, enable: bool) -> Self {
+        self.build_service_descriptors = enable;
+        self
+    }
 
-        services
+    /// Enable or disable generating a `SCHEMA_HASH` constant in client and
+    /// server modules, the hash of the methods of the service and of the
+    /// messages and enums they use.
+    ///
+    /// Clients send it with
+    /// `tonic_types_protobuf::schema::SchemaHashInterceptor`, and servers
+    /// compare it with their own with `SchemaCheckLayer`, to warn
+    /// about or reject calls of peers built from other versions of the .proto
+    /// files. Comments and options do not change the hash.
+    ///
+    /// Defaults to `false`.
+    pub fn build_schema_hash(mut self, enable: bool) -> Self {
+        self.build_schema_hash = enable;
+        self
     }
-}
 
-fn rust_mod_name_convention(name: &str) -> String {
-    name.to_snake_case()
-}
+    /// Enable or disable generating a `connect_uds(path)` constructor for
+    /// clients, which connects to a Unix domain socket, and the `UdsConnector`
+    /// it passes to `Endpoint::connect_with_connector`.
+    ///
+    /// Both are only available on Unix. The crate including the generated code
+    /// must depend on `tokio` with the `net` feature, and on `hyper-util` when
+    /// targeting tonic 0.12 or newer. Requires [`Builder::build_transport`].
+    ///
+    /// Defaults to `false`.
+    pub fn build_uds_connect(mut self, enable: bool) -> Self {
+        self.build_uds_connect = enable;
+        self
+    }
 
-fn rust_method_name_convention(name: &str) -> String {
-    name.to_snake_case()
-}
+    /// Enable or disable generating a `Blocking{Service}Client` per service,
+    /// which wraps the async client and a tokio runtime handle, and blocks
+    /// the calling thread until each call completes.
+    ///
+    /// Server streaming responses are read by iterating them. Client and
+    /// bidirectional streaming methods are only available on the wrapped
+    /// async client. The crate including the generated code must depend on
+    /// `tokio`, with the `rt-multi-thread` feature for the `connect`
+    /// constructor generated with [`Builder::build_transport`].
+    ///
+    /// Defaults to `false`.
+    pub fn build_blocking_client(mut self, enable: bool) -> Self {
+        self.build_blocking_client = enable;
+        self
+    }
 
-fn rust_struct_name_convention(name: &str) -> String {
-    name.to_upper_camel_case()
-}
+    /// Enable or disable generating a `#[cfg(test)]` module of smoke tests
+    /// per service, which serves a stub answering with default messages over
+    /// an in-process duplex transport, and calls every method once with the
+    /// generated client.
+    ///
+    /// Tests are only generated together with the client, the server and
+    /// the transport. The crate including the generated code must have
+    /// `tonic-testing-protobuf` and `tokio` with the `macros` and `rt`
+    /// features as dev-dependencies. Requires tonic 0.11.
+    ///
+    /// Default requests may violate the rules checked with
+    /// [`validate_requests`](Self::validate_requests), so calls of validated
+    /// methods may also fail with `InvalidArgument`.
+    ///
+    /// Defaults to `false`.
+    pub fn build_smoke_tests(mut self, enable: bool) -> Self {
+        self.build_smoke_tests = enable;
+        self
+    }
 
-// ".package_1.package_2.package_3" -> "package_3"
-fn protobuf_path_to_rust_mod(path: &str) -> String {
-    path.split('.').last().unwrap().to_owned()
-}
+    /// Enable or disable generating a `connect_when_serving(dst, timeout)`
+    /// constructor for clients, which waits until the health service of `dst`
+    /// reports the service as `SERVING`, so binaries can block startup on
+    /// their dependencies.
+    ///
+    /// The crate including the generated code must depend on
+    /// `tonic-health-protobuf`, which is built on tonic 0.11, the only version
+    /// supported. Requires [`Builder::build_transport`].
+    ///
+    /// Defaults to `false`.
+    pub fn build_health_wait(mut self, enable: bool) -> Self {
+        self.build_health_wait = enable;
+        self
+    }
 
-// ".package.Message" -> "::package::Message"
-fn protobuf_path_to_rust_path(path: &str) -> String {
-    let mut rust_path = String::new();
-    let mut parts = path.split('.');
-    let mut last_item = parts.next();
-    loop {
-        let Some(item) = parts.next() else {
-            break;
-        };
-        if last_item.unwrap().is_empty() {
-            // Skip root.
-            last_item = Some(item);
-            continue;
-        }
-        rust_path.push_str("::");
-        rust_path.push_str(last_item.unwrap());
-        last_item = Some(item);
+    /// Enable or disable building the codec of every server call from the
+    /// content-type of its request, with
+    /// `tonic_codec_protobuf::SelectCodec::select_codec` instead of
+    /// `Default::default`.
+    ///
+    /// Point [`Builder::codec_path`] to a codec that negotiates the encoding,
+    /// e.g. `tonic_codec_protobuf::Negotiated`, so one service implementation
+    /// serves both `application/grpc+proto` and `application/grpc+json`. The
+    /// crate including the generated code must depend on
+    /// `tonic-codec-protobuf`.
+    ///
+    /// Defaults to `false`.
+    pub fn select_codec(mut self, enable: bool) -> Self {
+        self.select_codec = enable;
+        self
     }
-    rust_path.push_str("::");
-    rust_path.push_str(&rust_struct_name_convention(last_item.unwrap()));
-    rust_path
-}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_streaming_rpc() {
-        let proto_content = r#"
-            syntax = "proto3";
-            package testing;
-            service Streaming {
-                rpc GetUnary(GetRequest) returns (GetResponse) {}
-                rpc GetClientStreaming(stream GetRequest) returns (GetResponse) {}
-                rpc GetServerStreaming(GetRequest) returns (stream GetResponse) {}
-                rpc GetBidirectionalStreaming(stream GetRequest) returns (stream GetResponse) {}
-            }
-            message GetRequest {}
-            message GetResponse {}
-        "#;
+    /// Enable or disable running every call of generated clients and every
+    /// handler of generated servers in a `tracing` span named after the full
+    /// method path, e.g. `/helloworld.Greeter/SayHello`.
+    ///
+    /// Spans carry the fields `rpc.system`, `rpc.service`, `rpc.method`,
+    /// `rpc.kind`, the streaming kind such as `server_streaming`, and
+    /// `rpc.grpc.status_code`, recorded once the call returned. The crate
+    /// including the generated code must depend on `tracing`.
+    ///
+    /// Defaults to `false`.
+    pub fn build_tracing(mut self, enable: bool) -> Self {
+        self.build_tracing = enable;
+        self
+    }
 
-        let tmp_dir = tempfile::TempDir::new().unwrap();
+    /// Enable or disable recording the rate, errors and duration of every
+    /// handler of generated servers with the `metrics` facade.
+    ///
+    /// Servers record the counters `grpc_server_started_total` and
+    /// `grpc_server_handled_total`, the gauge `grpc_server_in_flight` and the
+    /// histogram `grpc_server_handling_seconds`, labeled with `grpc_service`,
+    /// `grpc_method` and `grpc_type`, the streaming kind such as
+    /// `server_streaming`. Handled calls are also labeled with `grpc_code`,
+    /// e.g. `NotFound`. The crate including the generated code must depend on
+    /// `metrics`.
+    ///
+    /// Defaults to `false`.
+    pub fn build_metrics(mut self, enable: bool) -> Self {
+        self.build_metrics = enable;
+        self
+    }
+
+    /// Enable or disable validating requests against their
+    /// [protoc-gen-validate](https://github.com/bufbuild/protoc-gen-validate)
+    /// rules in generated servers.
+    ///
+    /// Handlers of unary and server streaming methods check the request
+    /// before calling the service implementation, and fail the call with
+    /// `INVALID_ARGUMENT` and a `BadRequest` listing the field violations.
+    /// Server modules also get a public `validate_{message}` function per
+    /// validated message, which client streaming handlers can call on the
+    /// received messages. Rules are declared with the `(validate.rules)`
+    /// field option, so `validate/validate.proto` must be importable.
+    /// Generated code depends on `tonic-types-protobuf`, and on `regex` if
+    /// string `pattern` rules are used. Requires tonic 0.11.
+    ///
+    /// Defaults to `false`.
+    pub fn validate_requests(mut self, enable: bool) -> Self {
+        self.validate_requests = enable;
+        self
+    }
+
+    /// Enable or disable making and serving the calls of generated clients
+    /// and servers with the generic helpers of `tonic-runtime-protobuf`,
+    /// instead of expanding the call glue of every method, which shrinks the
+    /// generated code and its compile time for services with many methods.
+    ///
+    /// The crate including the generated code must depend on
+    /// `tonic-runtime-protobuf`. Requires tonic 0.11.
+    ///
+    /// Defaults to `false`.
+    pub fn runtime_helpers(mut self, enable: bool) -> Self {
+        self.runtime_helpers = enable;
+        self
+    }
+
+    /// Inject the statements `prologue_fn` returns at the start of every
+    /// generated client method. The `prologue_fn` is provided with the full
+    /// method path, e.g. `"/helloworld.Greeter/SayHello"`.
+    ///
+    /// The statements see the `request` argument, and may shadow it with a
+    /// `tonic::Request`, e.g. to add metadata, converted with
+    /// `into_streaming_request` in client streaming methods:
+    ///
+    /// ```rust,ignore
+    /// builder.client_method_prologue(|_path| quote::quote! {
+    ///     let request = crate::auth::authorize(request.into_request());
+    /// })
+    /// ```
+    pub fn client_method_prologue<F>(mut self, prologue_fn: F) -> Self
+    where
+        F: Fn(&str) -> TokenStream + 'static,
+    {
+        self.hooks.client_prologue = Some(Box::new(prologue_fn));
+        self
+    }
+
+    /// Inject the statements `epilogue_fn` returns at the end of every
+    /// generated client method. The `epilogue_fn` is provided with the full
+    /// method path.
+    ///
+    /// The statements see the `response` of the call, a
+    /// `Result<tonic::Response<_>, tonic::Status>` that is returned after
+    /// them.
+    pub fn client_method_epilogue<F>(mut self, epilogue_fn: F) -> Self
+    where
+        F: Fn(&str) -> TokenStream + 'static,
+    {
+        self.hooks.client_epilogue = Some(Box::new(epilogue_fn));
+        self
+    }
+
+    /// Append the items `items_fn` returns to every generated server trait,
+    /// e.g. provided methods that implementations can override. The
+    /// `items_fn` is provided with the full service name, e.g.
+    /// `"helloworld.Greeter"`.
+    pub fn server_trait_items<F>(mut self, items_fn: F) -> Self
+    where
+        F: Fn(&str) -> TokenStream + 'static,
+    {
+        self.hooks.server_trait = Some(Box::new(items_fn));
+        self
+    }
+
+    /// Insert the items `header_fn` returns at the top of every generated
+    /// client and server module, e.g. `use` declarations for the other hooks.
+    /// The `header_fn` is provided with the full service name and the name of
+    /// the module, e.g. `"greeter_client"`.
+    pub fn module_header<F>(mut self, header_fn: F) -> Self
+    where
+        F: Fn(&str, &str) -> TokenStream + 'static,
+    {
+        self.hooks.module_header = Some(Box::new(header_fn));
+        self
+    }
+
+    /// Generate a `{Service}Error` enum in client and server modules from the
+    /// errors declared by the custom service option `full_name`, e.g.
+    /// `"myorg.errors"`.
+    ///
+    /// The option must be a repeated message with the fields `name`, `code`,
+    /// `message` and `detail`. The errors convert from and into
+    /// `tonic::Status`. Generated code depends on the `protobuf` crate to
+    /// encode detail messages.
+    pub fn error_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.error_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Generate authenticating constructors of clients, such as
+    /// `FooClient::with_bearer(channel, token)`, from the auth schemes
+    /// declared by the custom service option `full_name`, e.g.
+    /// `"myorg.auth"`.
+    ///
+    /// The option must be a, possibly repeated, message with the fields
+    /// `scheme`, one of `BEARER`, `API_KEY` and `MTLS`, and `header`, the
+    /// metadata key of API keys, `x-api-key` by default. Bearer tokens and API
+    /// keys are added to every request by an interceptor, `connect_mtls`
+    /// connects with a client certificate and requires the `tls` feature of
+    /// tonic. Constructors are only generated with the transport.
+    pub fn auth_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.auth_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Generate a `{Service}Trailers` trait in client and server modules from
+    /// the binary trailers declared by the custom method option `full_name`,
+    /// e.g. `"myorg.trailers"`.
+    ///
+    /// The option must be a repeated message with the fields `key`, a
+    /// metadata key ending with "-bin", and `type`, the fully qualified name
+    /// of the message carried in the trailer. The trait is implemented for
+    /// `tonic::Response`, `tonic::Status` and `tonic::metadata::MetadataMap`,
+    /// with a getter that parses each trailer and a setter that encodes it.
+    /// Generated code depends on the `protobuf` crate.
+    pub fn trailer_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.trailer_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Generate a `{method}_with_retry` variant of every client method with a
+    /// retry or hedging policy declared by the custom method option
+    /// `full_name`, e.g. `"myorg.retry"`.
+    ///
+    /// The option must be a message with the fields `max_attempts`,
+    /// `retryable_codes` (status code names), `initial_backoff_ms`,
+    /// `max_backoff_ms`, `backoff_multiplier` and `hedging_delay_ms`, like
+    /// the retry and hedging policies of the gRPC service config. The
+    /// policies are also exported as constants of a `retry_policies` module.
+    /// Client streaming methods are not retried. Generated code depends on
+    /// the `time` feature of the `tokio` crate.
+    pub fn retry_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.retry_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Generate an `IDEMPOTENT_METHODS` constant in client modules, listing
+    /// the paths of the unary methods marked by the custom bool method option
+    /// `full_name`, e.g. `"myorg.idempotent"`.
+    ///
+    /// Pass it to `tonic_dynamic_protobuf::ResponseCache::methods` to cache
+    /// the responses of those methods in clients.
+    pub fn idempotent_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.idempotent_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Generate conversions to the Rust domain types named by the custom
+    /// string message option `full_name`, e.g. `"myorg.domain_type"`.
+    ///
+    /// Unary client methods whose request or response has a domain type get
+    /// a `{method}_domain` variant taking and returning the domain types, and
+    /// server modules an `IntoDomain` trait converting requests in handlers.
+    /// The crate including the generated code must implement `From<Domain>`
+    /// for the messages and `TryFrom<Message>` for the domain types, with an
+    /// error implementing `Display`.
+    pub fn domain_type_option(mut self, full_name: impl AsRef<str>) -> Self {
+        self.domain_type_option = Some(full_name.as_ref().trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Enable or disable support for long-running operations.
+    ///
+    /// If enabled, `google.longrunning` types resolve to those of the
+    /// `tonic-types-protobuf` crate, so `google/longrunning/operations.proto`
+    /// (shipped in its `proto` directory) must only be included, not
+    /// compiled. Re-export `tonic_types_protobuf::pb::operations` next to the
+    /// modules generated by protobuf-codegen instead, which refer to it.
+    /// Every client method with a
+    /// `google.longrunning.operation_info` option gets a `{method}_operation`
+    /// variant returning a `TypedOperation`, which unpacks the declared
+    /// response and metadata types and polls the operation until it is done.
+    ///
+    /// Defaults to `false`.
+    pub fn longrunning_operations(mut self, enable: bool) -> Self {
+        self.longrunning_operations = enable;
+        self
+    }
+
+    /// Verify that every message type referenced under
+    /// [`Builder::proto_path`] is defined by the files protobuf-codegen
+    /// generated to `dir`, e.g. `"$OUT_DIR/protos"`.
+    ///
+    /// Code generation panics with a description of every missing type,
+    /// instead of producing code that fails to compile with unresolved path
+    /// errors. Disabled by default.
+    pub fn verify_message_paths(mut self, dir: impl AsRef<Path>) -> Self {
+        self.verify_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Additionally generate a mod file named `name`, e.g. `"services.rs"`,
+    /// that includes the generated files in nested modules mirroring their
+    /// packages, so that the client of service `Foo` in package `a.b` is
+    /// available as `a::b::FooClient`.
+    ///
+    /// The top-level messages and enums of files imported with
+    /// `import public` are re-exported in the module of the importing file's
+    /// package, so callers keep their paths when types move to another file.
+    ///
+    /// Include the mod file in its own module, its top-level modules may
+    /// collide with the modules generated by protobuf-codegen.
+    pub fn mod_file(mut self, name: impl AsRef<str>) -> Self {
+        self.mod_file = Some(name.as_ref().to_owned());
+        self
+    }
+
+    /// Additionally generate a Markdown reference of the services named
+    /// `name`, e.g. `"API.md"`, from the same descriptors as the code.
+    ///
+    /// The reference lists the methods of every service with their streaming
+    /// shapes, and the fields of their request and response messages and of
+    /// the messages and enums these refer to, each with its comments from the
+    /// .proto files.
+    pub fn api_docs(mut self, name: impl AsRef<str>) -> Self {
+        self.api_docs = Some(name.as_ref().to_owned());
+        self
+    }
+
+    /// Select how generated files are named, unless named by
+    /// [`Builder::file_name`].
+    ///
+    /// This defaults to [`FileNaming::PackageService`].
+    pub fn file_naming(mut self, file_naming: FileNaming) -> Self {
+        self.file_naming = file_naming;
+        self
+    }
+
+    /// Set the package, e.g. `"unpackaged"`, whose file names and modules in
+    /// the [mod file](Builder::mod_file) hold the services of .proto files
+    /// without a `package` statement.
+    ///
+    /// Routes keep the unqualified service name, e.g. `/Greeter/SayHello`.
+    /// By default, such services are named after the service alone, e.g.
+    /// `greeter.rs`, and included at the root of the mod file.
+    pub fn default_package(mut self, package: impl AsRef<str>) -> Self {
+        self.default_package = Some(package.as_ref().to_owned());
+        self
+    }
+
+    /// Enable or disable writing generated files into directories mirroring
+    /// the packages of their services, e.g. `a/b/storage.rs` for the service
+    /// `Storage` in package `a.b`, instead of `a_b_storage.rs`.
+    ///
+    /// Directories are created as needed, and file names are relative to the
+    /// output directory, e.g. in the [mod file](Builder::mod_file) and the
+    /// [manifest](Builder::manifest).
+    ///
+    /// Defaults to `false`.
+    pub fn package_dirs(mut self, enable: bool) -> Self {
+        self.package_dirs = enable;
+        self
+    }
+
+    /// Enable or disable generating a `register_types` function at the root of
+    /// the [mod file](Builder::mod_file).
+    ///
+    /// The function registers the message types of every compiled .proto
+    /// file, found under [`Builder::proto_path`], with a
+    /// `tonic_types_protobuf::any::TypeRegistry`, e.g. the process-wide one:
+    ///
+    /// ```rust,ignore
+    /// services::register_types(&mut TypeRegistry::global().write().unwrap());
+    /// ```
+    ///
+    /// `Any`s unpacked by the registry are then the generated Rust types, and
+    /// can be downcast to them. Files generated for the lite runtime have no
+    /// descriptors and are skipped. Defaults to `false`.
+    pub fn register_types(mut self, enable: bool) -> Self {
+        self.register_types = enable;
+        self
+    }
+
+    /// Set the protoc binary used to parse .proto files.
+    ///
+    /// Defaults to looking up `protoc` in `PATH`.
+    pub fn protoc_path(mut self, protoc: impl AsRef<Path>) -> Self {
+        self.protoc_path = Some(protoc.as_ref().to_path_buf());
+        self
+    }
+
+    /// Enable or disable hermetic mode, for build systems like Bazel that run
+    /// code generation outside of cargo in a sandbox.
+    ///
+    /// In hermetic mode, nothing is discovered from the environment:
+    /// [`Builder::out_dir`] and [`Builder::protoc_path`] must be set
+    /// explicitly instead of falling back to `OUT_DIR` and `PATH`, warnings
+    /// are written to stderr instead of as cargo instructions, and paths in
+    /// the [manifest](Builder::manifest) are relative to the output
+    /// directory. Output only depends on the inputs and the builder
+    /// configuration.
+    ///
+    /// Defaults to `false`.
+    pub fn hermetic(mut self, enable: bool) -> Self {
+        self.hermetic = enable;
+        self
+    }
+
+    /// Set the output directory to generate code to.
+    ///
+    /// This may also be a directory in the source tree, to check generated
+    /// code in, see [`Builder::verify`].
+    ///
+    /// Defaults to the `OUT_DIR` environment variable.
+    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Write a JSON manifest listing every generated file, together with the
+    /// .proto file, package and service it was generated from, to `path`.
+    ///
+    /// This lets external build systems declare the outputs of
+    /// [`Builder::compile`] precisely. A relative `path` is resolved against
+    /// the output directory.
+    pub fn manifest(mut self, path: impl AsRef<Path>) -> Self {
+        self.manifest = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Applies `parameter`, comma separated `key=value` pairs in the syntax
+    /// of the parameters of the protoc plugin, e.g.
+    /// `"proto_path=crate,build_client=false"`, which map to the methods of
+    /// the same name.
+    ///
+    /// Keys are the string options `codec_path`, `proto_path`,
+    /// `default_package`, `api_docs` and `mod_file`, the flags
+    /// `build_*`, `select_codec`, `validate_requests`, `runtime_helpers`,
+    /// `package_dirs` and `inner_lint_attributes`, `true` or `false`,
+    /// `tonic_version`, e.g. `0.11`, `file_naming`, `package_service` or
+    /// `proto_file`, and `allow_lint`, which may be repeated.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first unknown key or invalid value.
+    pub fn parameters(self, parameter: &str) -> Result<Self, String> {
+        parameters::configure(self, parameter)
+    }
+
+    /// Performs code generation for the provided services.
+    ///
+    /// Generated services will be output into the directory specified by
+    /// `out_dir` with files named specified by [`Builder::file_name`]. Files
+    /// whose content did not change are not rewritten.
+    ///
+    /// Only the services of `protos` are generated. Services of the files
+    /// they import are skipped without a warning, since they are usually
+    /// generated on their own, e.g. by the crate owning those files; list
+    /// such files in `protos` to generate them as well.
+    pub fn compile(self, protos: &[impl AsRef<Path>], includes: &[impl AsRef<Path>]) {
+        let parsed = self.build_file_descriptor_set(protos, includes);
+        self.write_outputs(parsed);
+    }
+
+    /// Performs code generation for the services of `protos`, which were
+    /// parsed beforehand with [`Builder::parse_protos`], like
+    /// [`Builder::compile`].
+    ///
+    /// `protos` are given as they were parsed, or relative to their include
+    /// directory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `protos` is not an input of `set`.
+    pub fn compile_set(self, set: &ProtoSet, protos: &[impl AsRef<Path>]) {
+        let parsed = set.select(protos);
+        self.write_outputs(parsed);
+    }
+
+    /// Writes the files generated from `parsed`, except those whose content
+    /// did not change.
+    fn write_outputs(self, parsed: ParsedProtos) {
+        for (path, content) in self.outputs(parsed) {
+            if fs::read(&path).is_ok_and(|existing| existing == content.as_bytes()) {
+                continue;
+            }
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).unwrap();
+            }
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    /// Checks that the code in `out_dir` is up to date, i.e. that
+    /// [`Builder::compile`] would not change it.
+    ///
+    /// This is meant for generated code checked into the source tree, e.g.
+    /// `src/generated`, instead of written to `OUT_DIR` by a build script:
+    /// code is regenerated with [`Builder::compile`] after changing the
+    /// protos, and a test or CI step calls `verify` with the same
+    /// configuration. Files in `out_dir` that are not generated, such as
+    /// hand-written modules, are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing every generated file that is missing or differs, or if
+    /// [`Builder::out_dir`] is not set.
+    pub fn verify(self, protos: &[impl AsRef<Path>], includes: &[impl AsRef<Path>]) {
+        assert!(
+            self.out_dir.is_some(),
+            "`Builder::verify` requires an explicit `Builder::out_dir`"
+        );
+        let out_dir = self.out_dir.clone().unwrap();
+        let parsed = self.build_file_descriptor_set(protos, includes);
+        let stale: Vec<_> = self
+            .outputs(parsed)
+            .into_iter()
+            .filter_map(|(path, content)| match fs::read_to_string(&path) {
+                Ok(existing) => golden::first_mismatch(&existing, &content)
+                    .map(|mismatch| format!("{} differs at {}", path.display(), mismatch)),
+                Err(e) => Some(format!("{} can not be read: {}", path.display(), e)),
+            })
+            .collect();
+        if !stale.is_empty() {
+            panic!(
+                "generated code in {} is stale, regenerate it with `Builder::compile`:\n{}",
+                out_dir.display(),
+                stale.join("\n")
+            );
+        }
+    }
+
+    /// Generates code for the services of `parsed`, and returns the path and
+    /// content of every file [`Builder::compile`] writes, the manifest
+    /// included.
+    fn outputs(mut self, parsed: ParsedProtos) -> Vec<(PathBuf, String)> {
+        let out_dir = match (self.out_dir.as_ref(), self.hermetic) {
+            (Some(out_dir), _) => out_dir.clone(),
+            (None, true) => panic!("hermetic mode requires an explicit `Builder::out_dir`"),
+            (None, false) => PathBuf::from(std::env::var("OUT_DIR").unwrap()),
+        };
+        let manifest = self.manifest.take();
+        // Hermetic manifests must not depend on where the sandbox is.
+        let manifest_root = if self.hermetic {
+            PathBuf::new()
+        } else {
+            out_dir.clone()
+        };
+
+        let files = self.generate_reported(parsed);
+        let mut outputs: Vec<_> = files
+            .iter()
+            .map(|file| (out_dir.join(&file.name), file.content.clone()))
+            .collect();
+        if let Some(manifest) = manifest {
+            outputs.push((
+                out_dir.join(manifest),
+                manifest::render(&files, &manifest_root),
+            ));
+        }
+        outputs
+    }
+
+    /// Performs code generation for the provided services and returns the
+    /// generated files instead of writing them to `out_dir`.
+    ///
+    /// This is mainly useful for snapshot tests of generated code, see
+    /// [`golden::assert_golden`].
+    pub fn generate(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Vec<GeneratedFile> {
+        let parsed = self.build_file_descriptor_set(protos, includes);
+        self.generate_reported(parsed)
+    }
+
+    /// Performs code generation for the services of `protos`, which were
+    /// parsed beforehand with [`Builder::parse_protos`], and returns the
+    /// generated files like [`Builder::generate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `protos` is not an input of `set`.
+    pub fn generate_set(self, set: &ProtoSet, protos: &[impl AsRef<Path>]) -> Vec<GeneratedFile> {
+        self.generate_reported(set.select(protos))
+    }
+
+    /// Generates code for the services of `parsed`, and reports warnings.
+    fn generate_reported(self, parsed: ParsedProtos) -> Vec<GeneratedFile> {
+        let hermetic = self.hermetic;
+        let mut warnings = vec![];
+        let files = self.generate_parsed(parsed, &mut warnings);
+        report_warnings(warnings, hermetic);
+        files
+    }
+
+    /// Generates a starter implementation of every service, a
+    /// `{Service}Service` struct implementing the service trait with
+    /// `todo!()` method bodies, instead of the service code.
+    ///
+    /// Files are named like the generated service files. The service traits
+    /// are referred to through `services_path`, the module including the
+    /// [mod file](Builder::mod_file), e.g. `"crate::services"`, and message
+    /// types through [`Builder::proto_path`], which should be absolute, e.g.
+    /// `"crate"`.
+    pub fn generate_skeletons(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        services_path: &str,
+    ) -> Vec<GeneratedFile> {
+        let parsed = self.build_file_descriptor_set(protos, includes);
+        self.skeletons_parsed(parsed, services_path)
+    }
+
+    /// Generates the starter implementations of [`Builder::generate_skeletons`]
+    /// for the services of `protos`, which were parsed beforehand with
+    /// [`Builder::parse_protos`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `protos` is not an input of `set`.
+    pub fn generate_skeletons_set(
+        self,
+        set: &ProtoSet,
+        protos: &[impl AsRef<Path>],
+        services_path: &str,
+    ) -> Vec<GeneratedFile> {
+        self.skeletons_parsed(set.select(protos), services_path)
+    }
+
+    fn skeletons_parsed(self, parsed: ParsedProtos, services_path: &str) -> Vec<GeneratedFile> {
+        let mut index = DescriptorIndex::new(&parsed.fds);
+        if self.longrunning_operations {
+            longrunning::map_types(&mut index);
+        }
+        // Warnings are reported when generating the services themselves, with
+        // the options of the service code configured.
+        let mut warnings = vec![];
+        let mut services = vec![];
+        for fd in parsed.fds.file {
+            if parsed.inputs.contains(fd.name()) {
+                services.extend(self.build_services(fd, &index, &mut warnings));
+            }
+        }
+        let names = service_file_names(&self, self.file_name_fn.as_ref(), &services);
+        services
+            .iter()
+            .zip(names)
+            .map(|(service, name)| GeneratedFile {
+                name,
+                content: skeleton::render(
+                    service,
+                    self.rust_package(&service.package),
+                    services_path,
+                    self.tonic_version,
+                ),
+                proto_file: service.proto_file.clone(),
+                package: service.package.clone(),
+                service: service.name.clone(),
+            })
+            .collect()
+    }
+
+    /// Writes the starter implementations of [`Builder::generate_skeletons`]
+    /// into `dir`, e.g. once when adding a service.
+    ///
+    /// Existing files are never overwritten, so that implementations in
+    /// progress are kept, and are reported as warnings instead.
+    pub fn write_skeletons(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        services_path: &str,
+        dir: impl AsRef<Path>,
+    ) {
+        let hermetic = self.hermetic;
+        let files = self.generate_skeletons(protos, includes, services_path);
+        let warnings = write_new_files(files, dir.as_ref());
+        report_warnings(warnings, hermetic);
+    }
+
+    /// Generates a cargo-fuzz target per method of the services of `protos`,
+    /// instead of the service code, e.g. into the `fuzz_targets` directory
+    /// of a fuzz crate.
+    ///
+    /// Each target feeds the input of the fuzzer as the request body of a
+    /// call to the server with `tonic_testing_protobuf::fuzz::call`, so that
+    /// it passes through the gRPC framing and the codec into the handler.
+    /// Targets are named after the package, service and method, e.g.
+    /// `a_b_storage_get`, and need a `[[bin]]` entry in the manifest of the
+    /// fuzz crate, like those added by `cargo fuzz add`.
+    ///
+    /// Servers are referred to through `services_path`, the module including
+    /// the [mod file](Builder::mod_file), e.g. `"my_crate::services"`. The
+    /// implementation to fuzz is created by a function per service in the
+    /// module `factories_path`, named like the service in snake case, e.g.
+    /// `my_crate::fuzz::storage()` for the `Storage` service of
+    /// `"my_crate::fuzz"`.
+    pub fn generate_fuzz_targets(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        services_path: &str,
+        factories_path: &str,
+    ) -> Vec<GeneratedFile> {
+        self.render_services(protos, includes, |service, package| {
+            fuzz::render(service, package, services_path, factories_path)
+        })
+    }
+
+    /// Writes the cargo-fuzz targets of [`Builder::generate_fuzz_targets`]
+    /// into `dir`, e.g. `fuzz/fuzz_targets`.
+    ///
+    /// Existing files are never overwritten, so that edited targets are kept,
+    /// and are reported as warnings instead.
+    pub fn write_fuzz_targets(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        services_path: &str,
+        factories_path: &str,
+        dir: impl AsRef<Path>,
+    ) {
+        let hermetic = self.hermetic;
+        let files = self.generate_fuzz_targets(protos, includes, services_path, factories_path);
+        let warnings = write_new_files(files, dir.as_ref());
+        report_warnings(warnings, hermetic);
+    }
+
+    /// Generates a criterion bench per service of `protos`, instead of the
+    /// service code, e.g. into the `benches` directory of the crate including
+    /// the service code, as a standing check of the performance of its
+    /// message shapes.
+    ///
+    /// The bench has a benchmark group per method, which measures round trips
+    /// of requests and responses through the codec of the method with
+    /// `tonic_testing_protobuf::bench::roundtrip`: of the default messages,
+    /// and of the sample messages in `samples_dir`, relative to the manifest
+    /// directory, e.g. `benches/samples/a.b.GetRequest/large.txtpb`, see
+    /// `tonic_testing_protobuf::bench::samples`. Benches are named after the
+    /// package and service, e.g. `a_b_storage`, and need a `[[bench]]` entry
+    /// with `harness = false` in the manifest, and `criterion` and
+    /// `tonic-testing-protobuf` as dev-dependencies.
+    ///
+    /// Message types are referred to through [`Builder::proto_path`], which
+    /// should name the crate, e.g. `"my_crate::protos"`, and must have
+    /// descriptors.
+    pub fn generate_benches(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        samples_dir: &str,
+    ) -> Vec<GeneratedFile> {
+        self.render_services(protos, includes, |service, package| {
+            vec![bench::render(service, package, samples_dir)]
+        })
+    }
+
+    /// Writes the criterion benches of [`Builder::generate_benches`] into
+    /// `dir`, e.g. `benches`.
+    ///
+    /// Existing files are never overwritten, so that edited benches are kept,
+    /// and are reported as warnings instead.
+    pub fn write_benches(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        samples_dir: &str,
+        dir: impl AsRef<Path>,
+    ) {
+        let hermetic = self.hermetic;
+        let files = self.generate_benches(protos, includes, samples_dir);
+        let warnings = write_new_files(files, dir.as_ref());
+        report_warnings(warnings, hermetic);
+    }
+
+    /// Writes a standalone crate of the messages and services of `protos`
+    /// into `dir`, so that they can be published as a versioned crate of
+    /// stubs, e.g. from the repository of the .proto files.
+    ///
+    /// The crate consists of:
+    ///
+    /// * `Cargo.toml`, the package of `krate` with the dependencies the
+    ///   generated code needs, with their features: `protobuf` at the exact
+    ///   version of the generated messages, the configured [tonic
+    ///   version](Builder::tonic_version), and the crates referred to by the
+    ///   enabled options, e.g. `tokio` for the [blocking
+    ///   client](Builder::build_blocking_client),
+    /// * `src/protos`, the messages generated by protobuf-codegen, a module per
+    ///   .proto file,
+    /// * `src/services`, the service code, with a [mod file](Builder::mod_file)
+    ///   mirroring the packages,
+    /// * `src/lib.rs`, which declares both modules.
+    ///
+    /// [`Builder::proto_path`], [`Builder::out_dir`] and
+    /// [`Builder::mod_file`] are set by the layout of the crate, and message
+    /// paths are [verified](Builder::verify_message_paths). The files above
+    /// are rewritten, and `src/protos` and `src/services` are cleared, so
+    /// that files of removed .proto files do not linger. Other files, e.g. a
+    /// README, are kept.
+    pub fn write_crate(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        krate: &StandaloneCrate,
+        dir: impl AsRef<Path>,
+    ) {
+        let src = dir.as_ref().join("src");
+        let protos_dir = src.join("protos");
+        let services_dir = src.join("services");
+        for generated in [&protos_dir, &services_dir] {
+            if generated.exists() {
+                fs::remove_dir_all(generated).unwrap();
+            }
+            fs::create_dir_all(generated).unwrap();
+        }
+
+        let mut codegen = protobuf_codegen::Codegen::new();
+        codegen
+            .protoc()
+            .includes(includes)
+            .inputs(protos)
+            .out_dir(&protos_dir);
+        match &self.protoc_path {
+            Some(protoc) => {
+                codegen.protoc_path(protoc);
+            }
+            None if self.hermetic => {
+                panic!("hermetic mode requires an explicit `Builder::protoc_path`")
+            }
+            // Look up protoc in `PATH`.
+            None => (),
+        }
+        codegen.run().expect("protobuf-codegen failed");
+
+        let tonic_version = self.tonic_version;
+        let files = self
+            .proto_path("crate::protos")
+            .mod_file("mod.rs")
+            .verify_message_paths(&protos_dir)
+            .generate(protos, includes);
+        let code: String = files.iter().map(|file| file.content.as_str()).collect();
+        for file in files {
+            let path = services_dir.join(&file.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(path, file.content).unwrap();
+        }
+        fs::write(src.join("lib.rs"), krate.render_lib()).unwrap();
+        fs::write(
+            dir.as_ref().join("Cargo.toml"),
+            krate.render_manifest(tonic_version, &code),
+        )
+        .unwrap();
+    }
+
+    /// Renders files of the services of `protos` with `render`, which
+    /// returns the names, without extension, and contents of the files of a
+    /// service, given the service and its Rust package.
+    fn render_services<F>(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        render: F,
+    ) -> Vec<GeneratedFile>
+    where
+        F: Fn(&Service, &str) -> Vec<(String, String)>,
+    {
+        let parsed = self.build_file_descriptor_set(protos, includes);
+        let mut index = DescriptorIndex::new(&parsed.fds);
+        if self.longrunning_operations {
+            longrunning::map_types(&mut index);
+        }
+        // Warnings are reported when generating the services themselves, with
+        // the options of the service code configured.
+        let mut warnings = vec![];
+        let mut files = vec![];
+        for fd in parsed.fds.file {
+            if !parsed.inputs.contains(fd.name()) {
+                continue;
+            }
+            for service in self.build_services(fd, &index, &mut warnings) {
+                let rendered = render(&service, self.rust_package(&service.package));
+                files.extend(rendered.into_iter().map(|(name, content)| GeneratedFile {
+                    name: format!("{}.rs", name),
+                    content,
+                    proto_file: service.proto_file.clone(),
+                    package: service.package.clone(),
+                    service: service.name.clone(),
+                }));
+            }
+        }
+        files
+    }
+
+    /// Performs code generation for the services defined in `inputs`, given
+    /// already parsed descriptors of the input files and all of their imports,
+    /// e.g. from a `CodeGeneratorRequest` of protoc.
+    ///
+    /// Constructs that can not be fully handled are reported to `warnings`
+    /// instead of being printed as cargo warnings.
+    pub fn generate_from_descriptors(
+        self,
+        files: Vec<descriptor::FileDescriptorProto>,
+        inputs: &[impl AsRef<str>],
+        warnings: &mut Vec<String>,
+    ) -> Vec<GeneratedFile> {
+        let mut fds = descriptor::FileDescriptorSet::new();
+        fds.file = files;
+        let parsed = ParsedProtos {
+            inputs: inputs.iter().map(|i| i.as_ref().to_owned()).collect(),
+            fds,
+        };
+        self.generate_parsed(parsed, warnings)
+    }
+
+    fn generate_parsed(
+        self,
+        parsed: ParsedProtos,
+        warnings: &mut Vec<String>,
+    ) -> Vec<GeneratedFile> {
+        let mut index = DescriptorIndex::new(&parsed.fds);
+        if self.longrunning_operations {
+            longrunning::map_types(&mut index);
+        }
+        let type_modules: Vec<_> = parsed
+            .fds
+            .file
+            .iter()
+            .filter(|fd| self.register_types && parsed.inputs.contains(fd.name()))
+            .filter(|fd| !lite_runtime(fd))
+            .map(|fd| self.file_mod_path(fd.name()))
+            .collect();
+        let reexports = self.public_imports(&parsed, warnings);
+        let api_docs = self
+            .api_docs
+            .clone()
+            .map(|name| (name, api_docs::render(&parsed)));
+        let mut services = vec![];
+        for fd in parsed.fds.file {
+            if parsed.inputs.contains(fd.name()) {
+                services.extend(self.build_services(fd, &index, warnings));
+            }
+        }
+        if let Some(dir) = &self.verify_dir {
+            let errors = verify::verify(&services, &self.proto_path, dir);
+            if !errors.is_empty() {
+                panic!(
+                    "referenced message types do not exist, make sure protobuf-codegen runs \
+                     first and `proto_path` points at its output:\n{}",
+                    errors.join("\n")
+                );
+            }
+        }
+        let mut files = self.compile_svc(&services, &type_modules, &reexports);
+        if let Some((name, content)) = api_docs {
+            if let Some(file) = files.iter().find(|file| file.name == name) {
+                panic!("the API reference {} would overwrite {}", name, file.name);
+            }
+            files.push(GeneratedFile {
+                name,
+                content,
+                proto_file: String::new(),
+                package: String::new(),
+                service: String::new(),
+            });
+        }
+        files
+    }
+
+    /// Returns the full paths of the top-level types of the files that the
+    /// input files in each package import with `import public`, transitively.
+    ///
+    /// Types whose name is already re-exported in the package are reported to
+    /// `warnings` and skipped.
+    fn public_imports(
+        &self,
+        parsed: &ParsedProtos,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<String, BTreeSet<String>> {
+        if self.mod_file.is_none() {
+            return BTreeMap::new();
+        }
+        let files: HashMap<_, _> = parsed.fds.file.iter().map(|fd| (fd.name(), fd)).collect();
+        // The re-exported paths by name, by package.
+        let mut packages: BTreeMap<&str, HashMap<String, String>> = BTreeMap::new();
+        for fd in &parsed.fds.file {
+            if !parsed.inputs.contains(fd.name()) {
+                continue;
+            }
+            let names = packages.entry(self.rust_package(fd.package())).or_default();
+            let mut visited = HashSet::new();
+            let mut pending = vec![fd];
+            while let Some(file) = pending.pop() {
+                for &index in &file.public_dependency {
+                    let Some(dep) = usize::try_from(index)
+                        .ok()
+                        .and_then(|index| file.dependency.get(index))
+                        .and_then(|name| files.get(name.as_str()))
+                    else {
+                        continue;
+                    };
+                    if !visited.insert(dep.name()) {
+                        continue;
+                    }
+                    pending.push(*dep);
+                    let module = self.file_mod_path(dep.name());
+                    let types = dep
+                        .message_type
+                        .iter()
+                        .map(|m| m.name())
+                        .chain(dep.enum_type.iter().map(|e| e.name()));
+                    for name in types {
+                        let name = rust_protobuf_ident(&rust_protobuf_capitalize(name));
+                        let path = format!("{}::{}", module, name);
+                        match names.get(&name) {
+                            Some(other) if *other != path => warnings.push(format!(
+                                "{}: {} and {} are both imported publicly, only the former \
+                                 is re-exported",
+                                fd.name(),
+                                other,
+                                path
+                            )),
+                            Some(_) => {}
+                            None => {
+                                names.insert(name, path);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        packages
+            .into_iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(package, names)| (package.to_owned(), names.into_values().collect()))
+            .collect()
+    }
+
+    /// Returns the full path of the module rust-protobuf generates for
+    /// `proto_file`.
+    fn file_mod_path(&self, proto_file: &str) -> String {
+        let name = rust_protobuf_file_mod(proto_file);
+        if name.starts_with("::") {
+            name
+        } else if self.longrunning_operations && proto_file == longrunning::OPERATIONS_PROTO {
+            longrunning::OPERATIONS_PATH.to_owned()
+        } else {
+            format!("{}::{}", self.proto_path, name)
+        }
+    }
+
+    /// Parses `protos` and all of their imports in a single protoc
+    /// invocation, for several builders to generate code from with
+    /// [`Builder::compile_set`], [`Builder::generate_set`] and
+    /// [`Builder::generate_skeletons_set`].
+    ///
+    /// Only the protoc configuration of this builder applies, i.e.
+    /// [`Builder::protoc_path`] and [`Builder::hermetic`]. Comments are kept,
+    /// so that any builder can write an [API reference](Builder::api_docs).
+    pub fn parse_protos(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> ProtoSet {
+        self.run_protoc(protos, includes, true)
+    }
+
+    fn build_file_descriptor_set(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> ParsedProtos {
+        // Comments are only needed for the API reference.
+        self.run_protoc(protos, includes, self.api_docs.is_some())
+            .into_parsed()
+    }
+
+    fn run_protoc(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+        source_info: bool,
+    ) -> ProtoSet {
+        let mut parser = protobuf_parse::Parser::new();
+        parser.protoc().inputs(protos).includes(includes);
+        if source_info {
+            parser.protoc_extra_args(["--include_source_info"]);
+        }
+        match &self.protoc_path {
+            Some(protoc) => {
+                parser.protoc_path(protoc);
+            }
+            None if self.hermetic => {
+                panic!("hermetic mode requires an explicit `Builder::protoc_path`")
+            }
+            // Look up protoc in `PATH`.
+            None => (),
+        }
+        let parsed = parser.parse_and_typecheck().expect("protoc failed");
+        let mut fds = descriptor::FileDescriptorSet::new();
+        fds.file = parsed.file_descriptors;
+        // protobuf-parse returns the relative paths in the order of the
+        // inputs.
+        let inputs = protos
+            .iter()
+            .map(|proto| proto.as_ref().to_path_buf())
+            .zip(parsed.relative_paths.iter().map(|path| path.to_string()))
+            .collect();
+        ProtoSet::new(inputs, fds)
+    }
+
+    /// Performs code generation for the provided services.
+    ///
+    /// `type_modules` are the rust-protobuf modules whose message types are
+    /// registered by the mod file, see [`Builder::register_types`], and
+    /// `reexports` the types it re-exports by package.
+    fn compile_svc(
+        mut self,
+        services: &[Service],
+        type_modules: &[String],
+        reexports: &BTreeMap<String, BTreeSet<String>>,
+    ) -> Vec<GeneratedFile> {
+        let file_name = self.file_name_fn.take();
+        let header = if self.inner_lint_attributes && !self.lint_allows.is_empty() {
+            // Validate lint names.
+            self.lint_allows_attribute();
+            format!("#![allow({})]\n", self.lint_allows.join(", "))
+        } else {
+            String::new()
+        };
+        let mut generator = ServiceGenerator {
+            builder: self,
+            clients: TokenStream::default(),
+            servers: TokenStream::default(),
+            client_items: TokenStream::default(),
+            server_items: TokenStream::default(),
+            smoke_tests: TokenStream::default(),
+            validators: HashMap::new(),
+            fragments: hooks::Fragments::default(),
+        };
+
+        let names = service_file_names(&generator.builder, file_name.as_ref(), services);
+        let mut files = Vec::with_capacity(services.len());
+        for (service, name) in services.iter().zip(names) {
+            generator.generate(service);
+            let mut output = header.clone();
+            generator.finalize(&mut output);
+
+            files.push(GeneratedFile {
+                name,
+                content: output,
+                proto_file: service.proto_file.clone(),
+                package: service.package.clone(),
+                service: service.name.clone(),
+            });
+        }
+        if let Some(name) = &generator.builder.mod_file {
+            if let Some(file) = files.iter().find(|file| &file.name == name) {
+                panic!(
+                    "the mod file {} would overwrite the service {}.{}",
+                    name, file.package, file.service
+                );
+            }
+            let builder = &generator.builder;
+            let content = mod_file::render(
+                &files,
+                builder.build_client,
+                builder.build_server,
+                type_modules,
+                reexports,
+                builder.default_package.as_deref(),
+            );
+            files.push(GeneratedFile {
+                name: name.clone(),
+                content,
+                proto_file: String::new(),
+                package: String::new(),
+                service: String::new(),
+            });
+        }
+        files
+    }
+
+    /// Returns the package whose modules and file names hold the Rust code
+    /// of services in `package`, see [`Builder::default_package`].
+    fn rust_package<'a>(&'a self, package: &'a str) -> &'a str {
+        match &self.default_package {
+            Some(default_package) if package.is_empty() => default_package,
+            _ => package,
+        }
+    }
+
+    /// Returns the `#[allow(...)]` attribute for the configured lints.
+    fn lint_allows_attribute(&self) -> syn::Attribute {
+        let lints = self.lint_allows.iter().map(|lint| {
+            syn::parse_str::<syn::Path>(lint)
+                .unwrap_or_else(|_| panic!("invalid lint name: {lint}"))
+        });
+        syn::parse_quote!(#[allow(#(#lints),*)])
+    }
+
+    /// Build services from the provided `FileDescriptorProto`.
+    ///
+    /// Constructs that can not be fully handled are reported to `warnings`.
+    fn build_services(
+        &self,
+        fd: descriptor::FileDescriptorProto,
+        index: &DescriptorIndex,
+        warnings: &mut Vec<String>,
+    ) -> Vec<Service> {
+        let package_name = fd.package();
+        if fd.syntax() == "editions" && !fd.service.is_empty() {
+            warnings.push(format!(
+                "{}: editions are not fully supported, generated services may be incomplete",
+                fd.name()
+            ));
+        }
+
+        let mut services = vec![];
+        for svc in &fd.service {
+            let svc_name = if package_name.is_empty() {
+                svc.name().to_owned()
+            } else {
+                format!("{}.{}", package_name, svc.name())
+            };
+            let context = format!("{}: service {}", fd.name(), svc_name);
+            let options = options::decode(
+                &svc.options,
+                &index.service_extensions,
+                index,
+                &context,
+                warnings,
+            );
+            let errors = match &self.error_option {
+                Some(error_option) => options
+                    .iter()
+                    .find(|option| &option.full_name == error_option)
+                    .map(|option| {
+                        errors::decode(&option.values, index, &self.proto_path, &context, warnings)
+                    })
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+            let auth = match &self.auth_option {
+                Some(auth_option) => options
+                    .iter()
+                    .find(|option| &option.full_name == auth_option)
+                    .map(|option| auth::decode(&option.values, &context, warnings))
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+            let rust_type = |ty: &str| {
+                index.rust_type(&self.proto_path, ty).unwrap_or_else(|| {
+                    // Already reported, keep generating so that the error
+                    // points at the generated code.
+                    format!("{}{}", self.proto_path, ty.replace('.', "::"))
+                })
+            };
+            let mut methods = vec![];
+            let mut trailers: Vec<trailers::Trailer> = vec![];
+            let mut validators = validate::Validators::default();
+            for m in &svc.method {
+                let context = format!("{}: method {}.{}", fd.name(), svc_name, m.name());
+                let method_options = options::decode(
+                    &m.options,
+                    &index.method_extensions,
+                    index,
+                    &context,
+                    warnings,
+                );
+                let mut retry = None;
+                let mut idempotent = None;
+                let mut operation_info = None;
+                for option in &method_options {
+                    if self.trailer_option.as_ref() == Some(&option.full_name) {
+                        let decoded = trailers::decode(
+                            &option.values,
+                            index,
+                            &self.proto_path,
+                            &context,
+                            warnings,
+                        );
+                        trailers::merge(&mut trailers, decoded, &context, warnings);
+                    } else if self.retry_option.as_ref() == Some(&option.full_name) {
+                        retry = Some(option);
+                    } else if self.idempotent_option.as_ref() == Some(&option.full_name) {
+                        idempotent = Some(option);
+                    } else if self.longrunning_operations
+                        && option.full_name == longrunning::OPERATION_INFO
+                    {
+                        operation_info = Some(option);
+                    } else {
+                        warnings.push(format!(
+                            "{}: custom option ({}) is ignored",
+                            context, option.full_name
+                        ));
+                    }
+                }
+                for ty in [m.input_type(), m.output_type()] {
+                    if !index.message_types.contains_key(ty) {
+                        warnings.push(format!(
+                            "{}: can not resolve type {} of method {}.{}",
+                            fd.name(),
+                            ty,
+                            svc_name,
+                            m.name()
+                        ));
+                    }
+                }
+                let mut method = Method {
+                    name: rust_method_name_convention(m.name()),
+                    route_name: m.name().to_owned(),
+                    input_type: rust_type(m.input_type()),
+                    output_type: rust_type(m.output_type()),
+                    codec_path: self.codec_path.to_owned(),
+                    client_streaming: m.client_streaming(),
+                    server_streaming: m.server_streaming(),
+                    retry: None,
+                    pagination: pagination::detect(m, index, &self.proto_path),
+                    operation: operation_info
+                        .and_then(options::CustomOption::value)
+                        .and_then(|value| {
+                            longrunning::decode(
+                                value,
+                                m,
+                                package_name,
+                                index,
+                                &self.proto_path,
+                                &context,
+                                warnings,
+                            )
+                        }),
+                    idempotent: false,
+                    validator: None,
+                    domain: domain::DomainTypes::default(),
+                };
+                method.retry = retry
+                    .and_then(options::CustomOption::value)
+                    .and_then(|value| retry::decode(value, &method, &context, warnings));
+                method.idempotent = idempotent
+                    .and_then(options::CustomOption::value)
+                    .is_some_and(|value| idempotent::decode(value, &method, &context, warnings));
+                if let Some(option) = &self.domain_type_option {
+                    method.domain = domain::DomainTypes {
+                        request: domain::decode(m.input_type(), option, index, warnings),
+                        response: domain::decode(m.output_type(), option, index, warnings),
+                    };
+                }
+                if self.validate_requests && self.build_server {
+                    method.validator =
+                        validators.add(m.input_type(), index, &self.proto_path, warnings);
+                    if method.validator.is_some() && method.client_streaming {
+                        warnings.push(format!(
+                            "{}: requests of client streaming methods are not validated \
+                             automatically, handlers can call `{}`",
+                            context,
+                            method.validator.as_deref().unwrap_or_default()
+                        ));
+                    }
+                }
+                methods.push(method);
+            }
+
+            services.push(Service {
+                name: svc.name().to_owned(),
+                package: package_name.to_owned(),
+                proto_file: fd.name().to_owned(),
+                file_module: (!lite_runtime(&fd)).then(|| self.file_mod_path(fd.name())),
+                schema_hash: schema_hash::compute(package_name, svc, index),
+                options,
+                errors,
+                auth,
+                trailers,
+                methods,
+                validators,
+            });
+        }
+
+        services
+    }
+}
+
+/// The parsed descriptors of the input files and all of their imports.
+struct ParsedProtos {
+    /// Names of the input .proto files, relative to their include directory.
+    inputs: HashSet<String>,
+    /// Descriptors of the input files and all of their imports.
+    fds: descriptor::FileDescriptorSet,
+}
+
+/// Lookup tables over the parsed descriptors.
+struct DescriptorIndex {
+    /// The fully qualified names (with a leading dot) of all message types,
+    /// mapped to the path of the Rust type generated by rust-protobuf. The
+    /// path is relative to [`Builder::proto_path`] unless it starts with
+    /// `::`.
+    message_types: HashMap<String, String>,
+    /// The fully qualified names (with a leading dot) of all message types,
+    /// mapped to their descriptors.
+    messages: HashMap<String, descriptor::DescriptorProto>,
+    /// The fully qualified names (with a leading dot) of the message types
+    /// defined in proto3 files, whose singular fields have no presence unless
+    /// they are `optional`.
+    proto3_messages: HashSet<String>,
+    /// The fully qualified names (with a leading dot) of all enums, mapped to
+    /// their values.
+    enums: HashMap<String, Vec<(String, i32)>>,
+    /// Extensions of `google.protobuf.ServiceOptions`.
+    service_extensions: HashMap<u32, options::Extension>,
+    /// Extensions of `google.protobuf.MethodOptions`.
+    method_extensions: HashMap<u32, options::Extension>,
+    /// Extensions of `google.protobuf.MessageOptions`.
+    message_extensions: HashMap<u32, options::Extension>,
+    /// Extensions of `google.protobuf.FieldOptions`.
+    field_extensions: HashMap<u32, options::Extension>,
+    /// Extensions of `google.protobuf.OneofOptions`.
+    oneof_extensions: HashMap<u32, options::Extension>,
+}
+
+impl DescriptorIndex {
+    fn new(fds: &descriptor::FileDescriptorSet) -> Self {
+        fn collect(
+            scope: &str,
+            rust_scope: &str,
+            messages: &[descriptor::DescriptorProto],
+            proto3: bool,
+            index: &mut DescriptorIndex,
+        ) {
+            for message in messages {
+                let name = format!("{}.{}", scope, message.name());
+                let rust_name = format!(
+                    "{}::{}",
+                    rust_scope,
+                    rust_protobuf_ident(&rust_protobuf_capitalize(message.name()))
+                );
+                let nested_scope = format!(
+                    "{}::{}",
+                    rust_scope,
+                    rust_protobuf_ident(&protobuf_parse::snake_case(message.name()))
+                );
+                collect_enums(&name, &message.enum_type, index);
+                collect(&name, &nested_scope, &message.nested_type, proto3, index);
+                if proto3 {
+                    index.proto3_messages.insert(name.clone());
+                }
+                index.message_types.insert(name.clone(), rust_name);
+                index.messages.insert(name, message.clone());
+            }
+        }
+        fn collect_enums(
+            scope: &str,
+            enums: &[descriptor::EnumDescriptorProto],
+            index: &mut DescriptorIndex,
+        ) {
+            for e in enums {
+                let values = e
+                    .value
+                    .iter()
+                    .map(|v| (v.name().to_owned(), v.number()))
+                    .collect();
+                index
+                    .enums
+                    .insert(format!("{}.{}", scope, e.name()), values);
+            }
+        }
+
+        let mut index = DescriptorIndex {
+            message_types: HashMap::new(),
+            messages: HashMap::new(),
+            proto3_messages: HashSet::new(),
+            enums: HashMap::new(),
+            service_extensions: options::collect_extensions(fds, ".google.protobuf.ServiceOptions"),
+            method_extensions: options::collect_extensions(fds, ".google.protobuf.MethodOptions"),
+            message_extensions: options::collect_extensions(fds, ".google.protobuf.MessageOptions"),
+            field_extensions: options::collect_extensions(fds, ".google.protobuf.FieldOptions"),
+            oneof_extensions: options::collect_extensions(fds, ".google.protobuf.OneofOptions"),
+        };
+        for fd in &fds.file {
+            let scope = if fd.package().is_empty() {
+                String::new()
+            } else {
+                format!(".{}", fd.package())
+            };
+            collect_enums(&scope, &fd.enum_type, &mut index);
+            collect(
+                &scope,
+                &rust_protobuf_file_mod(fd.name()),
+                &fd.message_type,
+                fd.syntax() == "proto3",
+                &mut index,
+            );
+        }
+        index
+    }
+
+    /// Returns the full path of the Rust type of the message `ty` (a fully
+    /// qualified name with a leading dot) under `proto_path`.
+    fn rust_type(&self, proto_path: &str, ty: &str) -> Option<String> {
+        let path = self.message_types.get(ty)?;
+        if path.starts_with("::") {
+            Some(path.clone())
+        } else {
+            Some(format!("{}::{}", proto_path, path))
+        }
+    }
+}
+
+fn rust_mod_name_convention(name: &str) -> String {
+    name.to_snake_case()
+}
+
+fn rust_method_name_convention(name: &str) -> String {
+    name.to_snake_case()
+}
+
+/// Well-known types that rust-protobuf generates into the `protobuf` crate.
+const WELL_KNOWN_TYPES: &[&str] = &[
+    "google/protobuf/any.proto",
+    "google/protobuf/api.proto",
+    "google/protobuf/duration.proto",
+    "google/protobuf/empty.proto",
+    "google/protobuf/field_mask.proto",
+    "google/protobuf/source_context.proto",
+    "google/protobuf/struct.proto",
+    "google/protobuf/timestamp.proto",
+    "google/protobuf/type.proto",
+    "google/protobuf/wrappers.proto",
+];
+
+/// Returns the path of the module rust-protobuf generates for `proto_file`.
+///
+/// Well-known types and descriptors live in the `protobuf` crate, their paths
+/// start with `::`. Other paths are relative to [`Builder::proto_path`].
+fn rust_protobuf_file_mod(proto_file: &str) -> String {
+    let without_dir = proto_file.rsplit('/').next().unwrap();
+    let without_suffix = without_dir.strip_suffix(".proto").unwrap_or(without_dir);
+    let name: String = without_suffix
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let valid = if i == 0 {
+                c.is_ascii_alphabetic() || c == '_'
+            } else {
+                c.is_ascii_alphanumeric() || c == '_'
+            };
+            if valid {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = rust_protobuf_ident(&name);
+    if proto_file == "google/protobuf/descriptor.proto" {
+        "::protobuf::descriptor".to_owned()
+    } else if WELL_KNOWN_TYPES.contains(&proto_file) {
+        format!("::protobuf::well_known_types::{}", name)
+    } else {
+        name
+    }
+}
+
+/// Returns whether rust-protobuf generates `fd` for the lite runtime, without
+/// descriptors.
+fn lite_runtime(fd: &descriptor::FileDescriptorProto) -> bool {
+    /// The number of the `rustproto.lite_runtime_all` file option.
+    const LITE_RUNTIME_ALL: u32 = 17035;
+
+    let options = fd.options.get_or_default();
+    options.optimize_for() == descriptor::file_options::OptimizeMode::LITE_RUNTIME
+        || options
+            .special_fields
+            .unknown_fields()
+            .get(LITE_RUNTIME_ALL)
+            .is_some_and(|v| v == protobuf::UnknownValueRef::Varint(1))
+}
+
+/// Identifiers that rust-protobuf escapes.
+const RUST_PROTOBUF_KEYWORDS: &[&str] = &[
+    "_", "as", "async", "await", "break", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "self", "Self", "struct", "super", "true", "trait", "type", "unsafe",
+    "use", "while", "continue", "box", "const", "where", "virtual", "proc", "alignof", "become",
+    "offsetof", "priv", "pure", "sizeof", "typeof", "unsized", "yield", "do", "abstract", "final",
+    "override", "macro",
+];
+
+/// Escapes `ident` the way rust-protobuf does, by appending `_` to keywords.
+fn rust_protobuf_ident(ident: &str) -> String {
+    if RUST_PROTOBUF_KEYWORDS.contains(&ident) {
+        format!("{}_", ident)
+    } else {
+        ident.to_owned()
+    }
+}
+
+/// Uppercases the first character of `name`, the way rust-protobuf names
+/// message structs.
+fn rust_protobuf_capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_streaming_rpc() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            service Streaming {
+                rpc GetUnary(GetRequest) returns (GetResponse) {}
+                rpc GetClientStreaming(stream GetRequest) returns (GetResponse) {}
+                rpc GetServerStreaming(GetRequest) returns (stream GetResponse) {}
+                rpc GetBidirectionalStreaming(stream GetRequest) returns (stream GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
         let proto_file_path = tmp_dir.path().join("test_streaming_rpc.proto");
         std::fs::write(&proto_file_path, proto_content).unwrap();
 
-        let fds = crate::Builder::new()
+        let fds = crate::Builder::new()
+            .out_dir(tmp_dir.path())
+            .build_file_descriptor_set(&[proto_file_path], &[tmp_dir.path()])
+            .fds;
+        assert_eq!(fds.file[0].service.len(), 1);
+        assert_eq!(fds.file[0].service[0].method.len(), 4);
+
+        let assert = |rpc: &str, client_streaming, server_streaming| {
+            let method = fds.file[0].service[0]
+                .method
+                .iter()
+                .find(|m| m.name() == rpc)
+                .unwrap();
+            assert_eq!(method.client_streaming(), client_streaming, "{fds}");
+            assert_eq!(method.server_streaming(), server_streaming, "{fds}");
+        };
+
+        // Unary
+        assert("GetUnary", false, false);
+        // Client streaming
+        assert("GetClientStreaming", true, false);
+        // Server streaming
+        assert("GetServerStreaming", false, true);
+        // Bidirectional Streaming
+        assert("GetBidirectionalStreaming", true, true);
+    }
+
+    #[test]
+    fn test_file_name_full_package() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let protos = [
+            ("a_storage.proto", "a.b.storage"),
+            ("x_storage.proto", "x.storage"),
+        ];
+        for (file, package) in protos {
+            let proto_content = format!(
+                r#"
+                syntax = "proto3";
+                package {package};
+                service Storage {{
+                    rpc Get(GetRequest) returns (GetResponse) {{}}
+                }}
+                message GetRequest {{}}
+                message GetResponse {{}}
+            "#
+            );
+            std::fs::write(tmp_dir.path().join(file), proto_content).unwrap();
+        }
+
+        crate::Builder::new()
+            .out_dir(tmp_dir.path())
+            .file_name_with_proto(|proto_file, pkg, svc| {
+                assert!(proto_file.ends_with("_storage.proto"), "{proto_file}");
+                format!("{pkg}_{svc}")
+            })
+            .compile(
+                &[
+                    tmp_dir.path().join("a_storage.proto"),
+                    tmp_dir.path().join("x_storage.proto"),
+                ],
+                &[tmp_dir.path()],
+            );
+
+        let a = std::fs::read_to_string(tmp_dir.path().join("a_b_storage_storage.rs")).unwrap();
+        assert!(a.contains("/a.b.storage.Storage/Get"), "{a}");
+        let x = std::fs::read_to_string(tmp_dir.path().join("x_storage_storage.rs")).unwrap();
+        assert!(x.contains("/x.storage.Storage/Get"), "{x}");
+    }
+
+    #[test]
+    fn test_file_naming_proto_file() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let protos = [
+            (
+                "greeter.proto",
+                "a.b",
+                "service Greeter { rpc Get(Empty) returns (Empty) {} }",
+            ),
+            (
+                "storage.proto",
+                "c",
+                "service Storage { rpc Get(Empty) returns (Empty) {} } \
+                 service Admin { rpc Get(Empty) returns (Empty) {} }",
+            ),
+        ];
+        for (file, package, services) in protos {
+            let proto_content =
+                format!("syntax = \"proto3\"; package {package}; {services} message Empty {{}}");
+            std::fs::write(tmp_dir.path().join(file), proto_content).unwrap();
+        }
+
+        let files = crate::Builder::new()
+            .file_naming(crate::FileNaming::ProtoFile)
+            .generate(
+                &[
+                    tmp_dir.path().join("greeter.proto"),
+                    tmp_dir.path().join("storage.proto"),
+                ],
+                &[tmp_dir.path()],
+            );
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["greeter.rs", "storage_storage.rs", "storage_admin.rs"]
+        );
+    }
+
+    #[test]
+    fn test_file_name_collisions() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        for package in ["a", "b"] {
+            let proto_content = format!(
+                "syntax = \"proto3\"; package {package}; \
+                 service Admin {{ rpc Get(Empty) returns (Empty) {{}} }} message Empty {{}}"
+            );
+            std::fs::create_dir(tmp_dir.path().join(package)).unwrap();
+            std::fs::write(
+                tmp_dir.path().join(package).join("admin.proto"),
+                proto_content,
+            )
+            .unwrap();
+        }
+        let protos = [
+            tmp_dir.path().join("a/admin.proto"),
+            tmp_dir.path().join("b/admin.proto"),
+        ];
+
+        let files = crate::Builder::new()
+            .file_naming(crate::FileNaming::ProtoFile)
+            .generate(&protos, &[tmp_dir.path()]);
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["a_admin.rs", "b_admin.rs"]);
+
+        let panic = std::panic::catch_unwind(|| {
+            crate::Builder::new()
+                .file_name(|_pkg, svc| svc.to_owned())
+                .generate(&protos, &[tmp_dir.path()])
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.contains("admin.rs: a.Admin, b.Admin"), "{message}");
+    }
+
+    #[test]
+    fn test_hermetic() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let panic = std::panic::catch_unwind(|| {
+            crate::Builder::new()
+                .hermetic(true)
+                .out_dir(tmp_dir.path())
+                .compile(&[&proto_file_path], &[tmp_dir.path()]);
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<&str>().unwrap();
+        assert!(message.contains("Builder::protoc_path"), "{message}");
+
+        let path = std::env::var_os("PATH").unwrap();
+        let protoc = std::env::split_paths(&path)
+            .map(|dir| dir.join("protoc"))
+            .find(|protoc| protoc.exists())
+            .unwrap();
+        crate::Builder::new()
+            .hermetic(true)
+            .protoc_path(protoc)
+            .out_dir(tmp_dir.path())
+            .manifest("manifest.json")
+            .compile(&[&proto_file_path], &[tmp_dir.path()]);
+        let manifest = std::fs::read_to_string(tmp_dir.path().join("manifest.json")).unwrap();
+        assert!(
+            manifest.contains(r#""path": "a_b_storage.rs""#),
+            "{manifest}"
+        );
+    }
+
+    #[test]
+    fn test_manifest() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        crate::Builder::new()
             .out_dir(tmp_dir.path())
-            .build_file_descriptor_set(&[proto_file_path], &[tmp_dir.path()]);
-        assert_eq!(fds.file[0].service.len(), 1);
-        assert_eq!(fds.file[0].service[0].method.len(), 4);
+            .manifest("manifest.json")
+            .compile(&[proto_file_path], &[tmp_dir.path()]);
+
+        let manifest = std::fs::read_to_string(tmp_dir.path().join("manifest.json")).unwrap();
+        let path = tmp_dir.path().join("a_b_storage.rs");
+        assert!(path.exists());
+        let expected = format!(
+            r#"{{
+  "files": [
+    {{
+      "path": "{}",
+      "name": "a_b_storage.rs",
+      "proto": "storage.proto",
+      "package": "a.b",
+      "service": "Storage"
+    }}
+  ]
+}}
+"#,
+            path.display()
+        );
+        assert_eq!(manifest, expected);
+    }
 
-        let assert = |rpc: &str, client_streaming, server_streaming| {
-            let method = fds.file[0].service[0]
-                .method
-                .iter()
-                .find(|m| m.name() == rpc)
-                .unwrap();
-            assert_eq!(method.client_streaming(), client_streaming, "{fds}");
-            assert_eq!(method.server_streaming(), server_streaming, "{fds}");
+    #[test]
+    fn test_write_skeletons() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let dir = tmp_dir.path().join("server");
+        let write = || {
+            crate::Builder::new()
+                .proto_path("crate")
+                .package_dirs(true)
+                .write_skeletons(&[&proto_file_path], &[tmp_dir.path()], "crate", &dir);
         };
 
-        // Unary
-        assert("GetUnary", false, false);
-        // Client streaming
-        assert("GetClientStreaming", true, false);
-        // Server streaming
-        assert("GetServerStreaming", false, true);
-        // Bidirectional Streaming
-        assert("GetBidirectionalStreaming", true, true);
+        write();
+        let path = dir.join("a/b/storage.rs");
+        let skeleton = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            skeleton.contains("impl crate::a::b::Storage for StorageService"),
+            "{skeleton}"
+        );
+
+        // Implementations in progress are kept.
+        std::fs::write(&path, "").unwrap();
+        write();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_write_fuzz_targets() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+                rpc BatchGet(stream GetRequest) returns (stream GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let dir = tmp_dir.path().join("fuzz_targets");
+        crate::Builder::new().write_fuzz_targets(
+            &[&proto_file_path],
+            &[tmp_dir.path()],
+            "my_crate::services",
+            "my_crate::fuzz",
+            &dir,
+        );
+
+        let target = std::fs::read_to_string(dir.join("a_b_storage_get.rs")).unwrap();
+        let expected = r#"//! Feeds arbitrary request bodies to the `Get` method of the `a.b.Storage` service.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ::tonic_testing_protobuf::fuzz::call(
+        my_crate::services::a::b::StorageServer::new(my_crate::fuzz::storage()),
+        "/a.b.Storage/Get",
+        data,
+    );
+});
+"#;
+        assert_eq!(target, expected);
+        let target = std::fs::read_to_string(dir.join("a_b_storage_batch_get.rs")).unwrap();
+        assert!(target.contains(r#""/a.b.Storage/BatchGet""#), "{target}");
+    }
+
+    #[test]
+    fn test_write_benches() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let dir = tmp_dir.path().join("benches");
+        crate::Builder::new().proto_path("my_crate").write_benches(
+            &[&proto_file_path],
+            &[tmp_dir.path()],
+            "benches/samples",
+            &dir,
+        );
+
+        let bench = std::fs::read_to_string(dir.join("a_b_storage.rs")).unwrap();
+        let expected = r#"//! Benchmarks round trips of the messages of the `a.b.Storage` service through its codec.
+use criterion::{criterion_group, criterion_main, Criterion};
+use tonic_testing_protobuf::bench;
+/// The directory of the sample messages, see
+/// `tonic_testing_protobuf::bench::samples`.
+const SAMPLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/samples");
+fn bench_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("a.b.Storage/Get");
+    for (name, message) in bench::samples::<my_crate::storage::GetRequest>(SAMPLES) {
+        group
+            .bench_function(
+                format!("request/{}", name),
+                |b| {
+                    b.iter(|| bench::roundtrip(
+                        ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                        message.clone(),
+                    ))
+                },
+            );
+    }
+    for (name, message) in bench::samples::<my_crate::storage::GetResponse>(SAMPLES) {
+        group
+            .bench_function(
+                format!("response/{}", name),
+                |b| {
+                    b.iter(|| bench::roundtrip(
+                        ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                        message.clone(),
+                    ))
+                },
+            );
+    }
+    group.finish();
+}
+criterion_group!(benches, bench_get);
+criterion_main!(benches);
+"#;
+        assert_eq!(bench, expected);
+    }
+
+    #[test]
+    fn test_write_crate() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let dir = tmp_dir.path().join("my-protos");
+        std::fs::create_dir_all(dir.join("src/services")).unwrap();
+        std::fs::write(dir.join("src/services/removed.rs"), "").unwrap();
+        std::fs::write(dir.join("README.md"), "").unwrap();
+        let krate = crate::StandaloneCrate::new("my-protos", "1.2.0")
+            .description("Stubs of \"storage\".")
+            .license("MIT");
+        crate::Builder::new()
+            .build_blocking_client(true)
+            .build_smoke_tests(true)
+            .write_crate(&[&proto_file_path], &[tmp_dir.path()], &krate, &dir);
+
+        let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        let expected = format!(
+            r#"# Generated by tonic-build-protobuf, do not edit.
+
+[package]
+name = "my-protos"
+version = "1.2.0"
+edition = "2021"
+description = "Stubs of \"storage\"."
+license = "MIT"
+
+[lib]
+doctest = false
+
+[dependencies]
+protobuf = "={}"
+tokio = {{ version = "1", features = ["rt-multi-thread"] }}
+tonic = "0.11"
+tonic-codec-protobuf = "0.1"
+
+[dev-dependencies]
+tokio = {{ version = "1", features = ["macros", "rt"] }}
+tonic-testing-protobuf = "0.1"
+"#,
+            protobuf::VERSION
+        );
+        assert_eq!(manifest, expected);
+        let lib = std::fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+        assert!(lib.starts_with("//! Stubs of \"storage\".\n//!\n"), "{lib}");
+        assert!(lib.contains("pub mod protos;\n"), "{lib}");
+        assert!(lib.contains("pub mod services;\n"), "{lib}");
+
+        let protos = std::fs::read_to_string(dir.join("src/protos/mod.rs")).unwrap();
+        assert!(protos.contains("pub mod storage;"), "{protos}");
+        assert!(dir.join("src/protos/storage.rs").exists());
+        let services = std::fs::read_to_string(dir.join("src/services/mod.rs")).unwrap();
+        assert!(
+            services.contains("include!(\"a_b_storage.rs\")"),
+            "{services}"
+        );
+        let code = std::fs::read_to_string(dir.join("src/services/a_b_storage.rs")).unwrap();
+        assert!(
+            code.contains("crate::protos::storage::GetRequest"),
+            "{code}"
+        );
+        // Files of removed protos are cleared, others are kept.
+        assert!(!dir.join("src/services/removed.rs").exists());
+        assert!(dir.join("README.md").exists());
+    }
+
+    #[test]
+    fn test_standalone_dependencies() {
+        let krate = crate::StandaloneCrate::new("x", "0.1.0");
+        let code = "fn f(_: crate::protos::metrics::Sample) { ::tracing::info!(); }";
+        let manifest = krate.render_manifest(crate::TonicVersion::V0_11, code);
+        assert!(manifest.contains("\ntracing = \"0.1\"\n"), "{manifest}");
+        // Paths of messages in packages named like crates are not crates.
+        assert!(!manifest.contains("metrics"), "{manifest}");
+    }
+
+    #[test]
+    fn test_verify() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a.b;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("storage.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let out_dir = tmp_dir.path().join("generated");
+        std::fs::create_dir(&out_dir).unwrap();
+        let builder = || crate::Builder::new().out_dir(&out_dir).mod_file("mod.rs");
+
+        builder().compile(&[&proto_file_path], &[tmp_dir.path()]);
+        builder().verify(&[&proto_file_path], &[tmp_dir.path()]);
+        // Hand-written files are ignored.
+        std::fs::write(out_dir.join("extra.rs"), "").unwrap();
+        builder().verify(&[&proto_file_path], &[tmp_dir.path()]);
+
+        let path = out_dir.join("a_b_storage.rs");
+        let code = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, code.replace("Get", "Put")).unwrap();
+        std::fs::remove_file(out_dir.join("mod.rs")).unwrap();
+        let panic = std::panic::catch_unwind(|| {
+            builder().verify(&[&proto_file_path], &[tmp_dir.path()]);
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains("a_b_storage.rs differs at line"),
+            "{message}"
+        );
+        assert!(message.contains("mod.rs can not be read"), "{message}");
+
+        builder().compile(&[&proto_file_path], &[tmp_dir.path()]);
+        builder().verify(&[&proto_file_path], &[tmp_dir.path()]);
+    }
+
+    #[test]
+    fn test_mod_file() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let protos = [
+            ("a_b.proto", "a.b", "Foo"),
+            ("a_type.proto", "a.type", "Bar"),
+        ];
+        for (file, package, service) in protos {
+            let proto_content = format!(
+                r#"
+                syntax = "proto3";
+                package {package};
+                service {service} {{
+                    rpc Get(Empty) returns (Empty) {{}}
+                }}
+                message Empty {{}}
+            "#
+            );
+            std::fs::write(tmp_dir.path().join(file), proto_content).unwrap();
+        }
+
+        let files = crate::Builder::new()
+            .build_server(false)
+            .mod_file("services.rs")
+            .generate(
+                &[
+                    tmp_dir.path().join("a_b.proto"),
+                    tmp_dir.path().join("a_type.proto"),
+                ],
+                &[tmp_dir.path()],
+            );
+        let mod_file = files.iter().find(|f| f.name == "services.rs").unwrap();
+        assert_eq!(
+            mod_file.content,
+            r#"pub mod a {
+    pub mod b {
+        include!("a_b_foo.rs");
+        pub use foo_client::FooClient;
+    }
+    pub mod type_ {
+        include!("a_type_bar.rs");
+        pub use bar_client::BarClient;
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_mod_file_without_package() {
+        let proto_content = r#"
+            syntax = "proto3";
+            service Greeter {
+                rpc SayHello(Empty) returns (Empty) {}
+            }
+            message Empty {}
+        "#;
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("greeter.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let files = crate::Builder::new()
+            .build_server(false)
+            .mod_file("services.rs")
+            .generate(&[proto_file_path], &[tmp_dir.path()]);
+        assert_eq!(files[0].name, "greeter.rs");
+        assert!(files[0].content.contains("\"/Greeter/SayHello\""));
+        assert_eq!(
+            files[1].content,
+            "include!(\"greeter.rs\");\npub use greeter_client::GreeterClient;\n"
+        );
+    }
+
+    #[test]
+    fn test_mod_file_register_types() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package a;
+            import "google/protobuf/empty.proto";
+            service Foo {
+                rpc Get(google.protobuf.Empty) returns (google.protobuf.Empty) {}
+            }
+        "#;
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("a-b.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let files = crate::Builder::new()
+            .build_server(false)
+            .proto_path("crate::protos")
+            .mod_file("services.rs")
+            .register_types(true)
+            .generate(&[proto_file_path], &[tmp_dir.path()]);
+        let mod_file = files.iter().find(|f| f.name == "services.rs").unwrap();
+        assert!(
+            mod_file.content.ends_with(
+                r#"/// Registers the message types of the compiled .proto files with
+/// `registry`.
+pub fn register_types(registry: &mut ::tonic_types_protobuf::any::TypeRegistry) {
+    registry.register_file(crate::protos::a_b::file_descriptor());
+}
+"#
+            ),
+            "{}",
+            mod_file.content
+        );
+    }
+
+    #[test]
+    fn test_mod_file_public_imports() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let protos = [
+            (
+                "moved.proto",
+                "syntax = \"proto3\"; package a; message Moved {} enum Kind { KIND_UNSPECIFIED = 0; }",
+            ),
+            (
+                "old.proto",
+                r#"
+                syntax = "proto3";
+                package a;
+                import public "moved.proto";
+                service Foo {
+                    rpc Get(Moved) returns (Moved) {}
+                }
+            "#,
+            ),
+        ];
+        for (file, proto_content) in protos {
+            std::fs::write(tmp_dir.path().join(file), proto_content).unwrap();
+        }
+
+        let files = crate::Builder::new()
+            .build_server(false)
+            .proto_path("crate::protos")
+            .mod_file("services.rs")
+            .generate(&[tmp_dir.path().join("old.proto")], &[tmp_dir.path()]);
+        let mod_file = files.iter().find(|f| f.name == "services.rs").unwrap();
+        assert_eq!(
+            mod_file.content,
+            r#"pub mod a {
+    include!("a_foo.rs");
+    pub use foo_client::FooClient;
+    pub use crate::protos::moved::Kind;
+    pub use crate::protos::moved::Moved;
+}
+"#
+        );
+    }
+
+    #[cfg(feature = "tonic-0-13")]
+    #[test]
+    fn test_tonic_version() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            service Versioned {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_tonic_version.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let compile = |version| {
+            crate::Builder::new()
+                .out_dir(tmp_dir.path())
+                .tonic_version(version)
+                .compile(&[&proto_file_path], &[tmp_dir.path()]);
+            std::fs::read_to_string(tmp_dir.path().join("testing_versioned.rs")).unwrap()
+        };
+
+        let v0_11 = compile(crate::TonicVersion::V0_11);
+        assert!(v0_11.contains("tonic::body::BoxBody"), "{v0_11}");
+        let v0_13 = compile(crate::TonicVersion::V0_13);
+        assert!(!v0_13.contains("tonic::body::BoxBody"), "{v0_13}");
+        assert!(v0_13.contains("tonic::body::Body"), "{v0_13}");
+    }
+
+    #[test]
+    fn test_select_codec() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            service Negotiated {
+                rpc Get(GetRequest) returns (GetResponse) {}
+                rpc Watch(GetRequest) returns (stream GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_select_codec.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        crate::Builder::new()
+            .out_dir(tmp_dir.path())
+            .select_codec(true)
+            .compile(&[&proto_file_path], &[tmp_dir.path()]);
+        let code = std::fs::read_to_string(tmp_dir.path().join("testing_negotiated.rs")).unwrap();
+        let (client, server) = code.split_once("pub mod negotiated_server").unwrap();
+        // Clients keep encoding with the default codec.
+        assert_eq!(client.matches("ProtobufCodecV3::default()").count(), 2);
+        assert!(!server.contains("ProtobufCodecV3::default()"), "{server}");
+        let selected = server.matches("ProtobufCodecV3::select_codec(").count();
+        assert_eq!(selected, 2, "{server}");
+    }
+
+    #[test]
+    fn test_allow_lint() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            service Linted {
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_allow_lint.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let compile = |inner| {
+            crate::Builder::new()
+                .out_dir(tmp_dir.path())
+                .allow_lint("missing_docs")
+                .allow_lint("clippy::pedantic")
+                .inner_lint_attributes(inner)
+                .compile(&[&proto_file_path], &[tmp_dir.path()]);
+            std::fs::read_to_string(tmp_dir.path().join("testing_linted.rs")).unwrap()
+        };
+
+        let outer = compile(false);
+        assert_eq!(
+            outer
+                .matches("#[allow(missing_docs, clippy::pedantic)]\npub mod")
+                .count(),
+            2,
+            "{outer}"
+        );
+        let inner = compile(true);
+        assert!(
+            inner.starts_with("#![allow(missing_docs, clippy::pedantic)]\n"),
+            "{inner}"
+        );
+        assert!(!inner.contains("#[allow(missing_docs"), "{inner}");
+    }
+
+    #[test]
+    fn test_warnings() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            import "google/protobuf/descriptor.proto";
+            extend google.protobuf.MethodOptions {
+                string owner = 50000;
+            }
+            service Warned {
+                rpc Get(Outer.Inner) returns (Outer) {
+                    option (owner) = "storage";
+                }
+            }
+            message Outer {
+                message Inner {}
+            }
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_warnings.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let builder = crate::Builder::new();
+        let parsed = builder.build_file_descriptor_set(&[&proto_file_path], &[tmp_dir.path()]);
+        let index = crate::DescriptorIndex::new(&parsed.fds);
+        let fd = parsed
+            .fds
+            .file
+            .into_iter()
+            .find(|fd| fd.name() == "test_warnings.proto")
+            .unwrap();
+        let mut warnings = vec![];
+        builder.build_services(fd, &index, &mut warnings);
+        assert_eq!(
+            warnings,
+            ["test_warnings.proto: method testing.Warned.Get: custom option (testing.owner) is ignored"]
+        );
+    }
+
+    #[test]
+    fn test_imported_services() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            tmp_dir.path().join("imported.proto"),
+            r#"
+                syntax = "proto3";
+                package imported;
+                message Ping {}
+                service Imported {
+                    rpc Get(Ping) returns (Ping);
+                }
+            "#,
+        )
+        .unwrap();
+        let proto_file_path = tmp_dir.path().join("importing.proto");
+        std::fs::write(
+            &proto_file_path,
+            r#"
+                syntax = "proto3";
+                package importing;
+                import "imported.proto";
+                service Importing {
+                    rpc Get(imported.Ping) returns (imported.Ping);
+                }
+            "#,
+        )
+        .unwrap();
+
+        // Services of imported files are neither generated nor reported.
+        let builder = crate::Builder::new();
+        let parsed = builder.build_file_descriptor_set(&[&proto_file_path], &[tmp_dir.path()]);
+        let mut warnings = vec![];
+        let files = builder.generate_parsed(parsed, &mut warnings);
+        let services: Vec<_> = files.iter().map(|f| f.proto_file.as_str()).collect();
+        assert_eq!(services, ["importing.proto"]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let files = crate::Builder::new().generate(
+            &[&proto_file_path, &tmp_dir.path().join("imported.proto")],
+            &[tmp_dir.path()],
+        );
+        let mut services: Vec<_> = files.iter().map(|f| f.proto_file.as_str()).collect();
+        services.sort();
+        assert_eq!(services, ["imported.proto", "importing.proto"]);
+    }
+
+    #[test]
+    fn test_packed_options() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            import "google/protobuf/descriptor.proto";
+            extend google.protobuf.ServiceOptions {
+                repeated int32 ports = 50000;
+                repeated fixed64 ids = 50001 [packed = false];
+                repeated Level levels = 50002;
+                Limits limits = 50003;
+            }
+            enum Level {
+                LEVEL_UNSPECIFIED = 0;
+                LEVEL_HIGH = 1;
+            }
+            message Limits {
+                repeated double ratios = 1;
+            }
+            service Packed {
+                option (ports) = 80;
+                option (ports) = 443;
+                option (ids) = 1;
+                option (ids) = 2;
+                option (levels) = LEVEL_HIGH;
+                option (limits) = { ratios: [0.5, 1.5] };
+                rpc Get(Limits) returns (Limits);
+            }
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_packed_options.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+
+        let builder = crate::Builder::new();
+        let parsed = builder.build_file_descriptor_set(&[&proto_file_path], &[tmp_dir.path()]);
+        let index = crate::DescriptorIndex::new(&parsed.fds);
+        let fd = parsed
+            .fds
+            .file
+            .into_iter()
+            .find(|fd| fd.name() == "test_packed_options.proto")
+            .unwrap();
+        let mut warnings = vec![];
+        let services = builder.build_services(fd, &index, &mut warnings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        // proto3 packs repeated scalar options, which are unpacked into one
+        // value each.
+        use crate::options::OptionValue::*;
+        let values: Vec<_> = services[0]
+            .options
+            .iter()
+            .map(|o| (o.name.as_str(), o.values.clone()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("ids", vec![U64(1), U64(2)]),
+                ("levels", vec![Enum("LEVEL_HIGH".to_owned())]),
+                (
+                    "limits",
+                    vec![Message(vec![
+                        ("ratios".to_owned(), F64(0.5)),
+                        ("ratios".to_owned(), F64(1.5)),
+                    ])]
+                ),
+                ("ports", vec![I32(80), I32(443)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_warnings() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            import "validate/validate.proto";
+            service Validated {
+                rpc Get(Request) returns (Request);
+            }
+            message Request {
+                string name = 1 [(validate.rules).string.pattern = "^[a-z]+$"];
+                string quoted = 2 [(validate.rules).string.pattern = "\\Qa.b\\E"];
+                string ahead = 3 [(validate.rules).string.pattern = "^(?=a)"];
+            }
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_validate_warnings.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let include = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/include");
+
+        let builder = crate::Builder::new().validate_requests(true);
+        let parsed =
+            builder.build_file_descriptor_set(&[&proto_file_path], &[tmp_dir.path(), &include]);
+        let index = crate::DescriptorIndex::new(&parsed.fds);
+        let fd = parsed
+            .fds
+            .file
+            .into_iter()
+            .find(|fd| fd.name() == "test_validate_warnings.proto")
+            .unwrap();
+        let mut warnings = vec![];
+        builder.build_services(fd, &index, &mut warnings);
+        // Patterns that would fail to compile at request time are reported
+        // and not checked.
+        assert_eq!(
+            warnings,
+            [
+                r#"message testing.Request: field quoted: string rule pattern "\\Qa.b\\E" is not supported by the regex crate and is ignored: unrecognized escape sequence"#,
+                r#"message testing.Request: field ahead: string rule pattern "^(?=a)" is not supported by the regex crate and is ignored: look-around, including look-ahead and look-behind, is not supported"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_message_paths() {
+        let proto_content = r#"
+            syntax = "proto3";
+            package testing;
+            service Verified {
+                rpc Get(Outer.Request) returns (Response) {}
+            }
+            message Outer {
+                message Request {}
+            }
+            message Response {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let proto_file_path = tmp_dir.path().join("test_verify.proto");
+        std::fs::write(&proto_file_path, proto_content).unwrap();
+        let generate = || {
+            crate::Builder::new()
+                .proto_path("crate")
+                .verify_message_paths(tmp_dir.path())
+                .generate(&[&proto_file_path], &[tmp_dir.path()])
+        };
+
+        let panic = std::panic::catch_unwind(generate).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains("test_verify.rs was not generated"),
+            "{message}"
+        );
+
+        std::fs::write(
+            tmp_dir.path().join("test_verify.rs"),
+            "pub mod outer { pub struct Request {} }",
+        )
+        .unwrap();
+        let panic = std::panic::catch_unwind(generate).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains("crate::test_verify::Response referenced by service testing.Verified"),
+            "{message}"
+        );
+        assert!(!message.contains("Request"), "{message}");
+
+        std::fs::write(
+            tmp_dir.path().join("test_verify.rs"),
+            "pub mod outer { pub struct Request {} } pub struct Response {}",
+        )
+        .unwrap();
+        assert_eq!(generate().len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_types() {
+        let common = r#"
+            syntax = "proto3";
+            package common.v1;
+            message Page {
+                message Token {}
+            }
+        "#;
+        let service = r#"
+            syntax = "proto3";
+            package storage.v1;
+            import "common/v1/type.proto";
+            import "google/protobuf/empty.proto";
+            service Storage {
+                rpc List(common.v1.Page.Token) returns (list_response) {}
+                rpc Clear(google.protobuf.Empty) returns (google.protobuf.Empty) {}
+            }
+            message list_response {}
+        "#;
+
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp_dir.path().join("common/v1")).unwrap();
+        std::fs::write(tmp_dir.path().join("common/v1/type.proto"), common).unwrap();
+        let proto_file_path = tmp_dir.path().join("storage-service.proto");
+        std::fs::write(&proto_file_path, service).unwrap();
+
+        let files = crate::Builder::new()
+            .proto_path("crate")
+            .generate(&[proto_file_path], &[tmp_dir.path()]);
+        let content = &files[0].content;
+        for path in [
+            "crate::type_::page::Token",
+            "crate::storage_service::List_response",
+            "::protobuf::well_known_types::empty::Empty",
+        ] {
+            assert!(content.contains(path), "{path} not found in {content}");
+        }
+    }
+
+    #[test]
+    fn test_schema_hash() {
+        let hash = |proto: &str| {
+            let tmp_dir = tempfile::TempDir::new().unwrap();
+            let proto_file_path = tmp_dir.path().join("storage.proto");
+            std::fs::write(&proto_file_path, proto).unwrap();
+            let files = crate::Builder::new()
+                .build_schema_hash(true)
+                .generate(&[proto_file_path], &[tmp_dir.path()]);
+            let content = &files[0].content;
+            let start = content.find("SCHEMA_HASH: &str = \"").unwrap() + 21;
+            content[start..start + 16].to_owned()
+        };
+
+        let base = hash(
+            r#"
+            syntax = "proto3";
+            package storage;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+                rpc Delete(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {
+                string key = 1;
+                Mode mode = 2;
+                oneof at { uint64 version = 3; uint64 time = 4; }
+                oneof scope { string prefix = 5; }
+            }
+            message GetResponse { Value value = 1; }
+            message Value { bytes data = 1; }
+            enum Mode { MODE_UNSPECIFIED = 0; MODE_STRONG = 1; }
+        "#,
+        );
+        // Comments, declaration order of methods, fields and oneofs, and
+        // unrelated types do not matter.
+        let same = hash(
+            r#"
+            syntax = "proto3";
+            package storage;
+            // Stores values.
+            service Storage {
+                rpc Delete(GetRequest) returns (GetResponse) {}
+                rpc Get(GetRequest) returns (GetResponse) {}
+            }
+            message Unrelated {}
+            message GetRequest {
+                oneof scope { string prefix = 5; }
+                Mode mode = 2;
+                string key = 1;
+                oneof at { uint64 time = 4; uint64 version = 3; }
+            }
+            message GetResponse { Value value = 1; }
+            message Value { bytes data = 1; }
+            enum Mode { MODE_UNSPECIFIED = 0; MODE_STRONG = 1; }
+        "#,
+        );
+        assert_eq!(base, same);
+        // A field moved to another oneof.
+        let moved = hash(
+            r#"
+            syntax = "proto3";
+            package storage;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+                rpc Delete(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {
+                string key = 1;
+                Mode mode = 2;
+                oneof at { uint64 version = 3; }
+                oneof scope { string prefix = 5; uint64 time = 4; }
+            }
+            message GetResponse { Value value = 1; }
+            message Value { bytes data = 1; }
+            enum Mode { MODE_UNSPECIFIED = 0; MODE_STRONG = 1; }
+        "#,
+        );
+        assert_ne!(base, moved);
+        // A field of a nested message changed its type.
+        let changed = hash(
+            r#"
+            syntax = "proto3";
+            package storage;
+            service Storage {
+                rpc Get(GetRequest) returns (GetResponse) {}
+                rpc Delete(GetRequest) returns (GetResponse) {}
+            }
+            message GetRequest {
+                string key = 1;
+                Mode mode = 2;
+                oneof at { uint64 version = 3; uint64 time = 4; }
+                oneof scope { string prefix = 5; }
+            }
+            message GetResponse { Value value = 1; }
+            message Value { string data = 1; }
+            enum Mode { MODE_UNSPECIFIED = 0; MODE_STRONG = 1; }
+        "#,
+        );
+        assert_ne!(base, changed);
     }
 }
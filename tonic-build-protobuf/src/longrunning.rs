@@ -0,0 +1,146 @@
+//! Generation of typed methods for long-running operations.
+//!
+//! Methods returning a `google.longrunning.Operation` declare the messages
+//! packed in its response and metadata with the standard method option
+//!
+//! ```proto
+//! rpc CreateBook(CreateBookRequest) returns (google.longrunning.Operation) {
+//!   option (google.longrunning.operation_info) = {
+//!     response_type: "Book"
+//!     metadata_type: "CreateBookMetadata"
+//!   };
+//! }
+//! ```
+//!
+//! The `google.longrunning` types are mapped to those of the
+//! `tonic-types-protobuf` crate, whose `TypedOperation` unpacks them and polls
+//! the operation until it is done.
+
+use proc_macro2::TokenStream;
+use protobuf::descriptor::MethodDescriptorProto;
+use quote::{format_ident, quote};
+
+use crate::{client, options::OptionValue, DescriptorIndex, Service, TonicVersion};
+
+/// The full name of the option declaring the types of an operation.
+pub(crate) const OPERATION_INFO: &str = "google.longrunning.operation_info";
+
+/// The file defining the `google.longrunning` types.
+pub(crate) const OPERATIONS_PROTO: &str = "google/longrunning/operations.proto";
+
+/// The module of the `google.longrunning` types in `tonic-types-protobuf`.
+pub(crate) const OPERATIONS_PATH: &str = "::tonic_types_protobuf::pb::operations";
+
+/// The response and metadata types of a long-running operation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OperationInfo {
+    /// The full path of the Rust type of the response.
+    response_type: String,
+    /// The full path of the Rust type of the metadata.
+    metadata_type: String,
+}
+
+/// Maps the messages of `google/longrunning/operations.proto` to the types
+/// generated in `tonic-types-protobuf`.
+pub(crate) fn map_types(index: &mut DescriptorIndex) {
+    for (name, path) in &mut index.message_types {
+        if !name.starts_with(".google.longrunning.") {
+            continue;
+        }
+        if let Some(relative) = path.strip_prefix("operations::") {
+            *path = format!("{}::{}", OPERATIONS_PATH, relative);
+        }
+    }
+}
+
+/// Decodes `value` of the `operation_info` option of `method`, defined in
+/// `package`.
+///
+/// Invalid options are reported to `warnings`, with `context` describing
+/// where they are declared.
+pub(crate) fn decode(
+    value: &OptionValue,
+    method: &MethodDescriptorProto,
+    package: &str,
+    index: &DescriptorIndex,
+    proto_path: &str,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Option<OperationInfo> {
+    if method.output_type() != ".google.longrunning.Operation" || method.server_streaming() {
+        warnings.push(format!(
+            "{}: method does not return a google.longrunning.Operation, {} is ignored",
+            context, OPERATION_INFO
+        ));
+        return None;
+    }
+    // Types in the package of the method may be unqualified.
+    let mut resolve = |field: &str| {
+        let name = value
+            .field(field)
+            .and_then(OptionValue::as_str)
+            .unwrap_or_default()
+            .trim_start_matches('.');
+        let path = if name.is_empty() {
+            None
+        } else {
+            index
+                .rust_type(proto_path, &format!(".{}.{}", package, name))
+                .or_else(|| index.rust_type(proto_path, &format!(".{}", name)))
+        };
+        if path.is_none() {
+            warnings.push(format!(
+                "{}: can not resolve {} {:?} of {}, option is ignored",
+                context, field, name, OPERATION_INFO
+            ));
+        }
+        path
+    };
+    let response_type = resolve("response_type");
+    let metadata_type = resolve("metadata_type");
+    Some(OperationInfo {
+        response_type: response_type?,
+        metadata_type: metadata_type?,
+    })
+}
+
+/// Generates the `{method}_operation` variants of the long-running methods of
+/// `service`, to be appended to its client module.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let methods: Vec<_> = service
+        .methods
+        .iter()
+        .filter_map(|method| {
+            let info = method.operation.as_ref()?;
+            let name = format_ident!("{}", method.name);
+            let with_operation = format_ident!("{}_operation", method.name);
+            let (request, _) = client::method_types(method);
+            let into_request = client::into_request(method);
+            let response: syn::Path = syn::parse_str(&info.response_type).unwrap();
+            let metadata: syn::Path = syn::parse_str(&info.metadata_type).unwrap();
+            let doc = format!(
+                " Like [`Self::{}`], but returns the operation typed by its `operation_info`.",
+                method.name
+            );
+            Some(quote! {
+                #[doc = #doc]
+                pub async fn #with_operation(
+                    &mut self,
+                    request: #request,
+                ) -> std::result::Result<
+                    tonic::Response<
+                        ::tonic_types_protobuf::longrunning::TypedOperation<#response, #metadata>,
+                    >,
+                    tonic::Status,
+                > {
+                    let response = self.#name(#into_request).await?;
+                    Ok(response.map(::tonic_types_protobuf::longrunning::TypedOperation::new))
+                }
+            })
+        })
+        .collect();
+    if methods.is_empty() {
+        return TokenStream::new();
+    }
+    client::impl_block(service, tonic_version, quote!(), quote!(#(#methods)*))
+}
@@ -0,0 +1,70 @@
+//! A machine-readable manifest of generated files.
+
+use std::{fmt::Write, path::Path};
+
+use crate::GeneratedFile;
+
+/// Renders a JSON manifest of `files`, with paths joined to `out_dir`, e.g.
+///
+/// ```json
+/// {
+///   "files": [
+///     {
+///       "path": "/out/debugpb_debug.rs",
+///       "name": "debugpb_debug.rs",
+///       "proto": "debugpb.proto",
+///       "package": "debugpb",
+///       "service": "Debug"
+///     }
+///   ]
+/// }
+/// ```
+pub(crate) fn render(files: &[GeneratedFile], out_dir: &Path) -> String {
+    let mut json = String::from("{\n  \"files\": [");
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let path = out_dir.join(&file.name);
+        let path = path.to_string_lossy();
+        let fields = [
+            ("path", path.as_ref()),
+            ("name", file.name.as_str()),
+            ("proto", file.proto_file.as_str()),
+            ("package", file.package.as_str()),
+            ("service", file.service.as_str()),
+        ];
+        json.push_str("\n    {");
+        for (j, (key, value)) in fields.iter().enumerate() {
+            if j > 0 {
+                json.push(',');
+            }
+            write!(json, "\n      \"{}\": {}", key, quote(value)).unwrap();
+        }
+        json.push_str("\n    }");
+    }
+    if !files.is_empty() {
+        json.push_str("\n  ");
+    }
+    json.push_str("]\n}\n");
+    json
+}
+
+/// Quotes `s` as a JSON string.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
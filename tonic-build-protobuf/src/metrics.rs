@@ -0,0 +1,81 @@
+//! Rewriting of generated servers to record the rate, errors and duration of
+//! every call with the `metrics` facade.
+//!
+//! Metrics carry the labels `grpc_service` and `grpc_method`, e.g.
+//! `helloworld.Greeter` and `SayHello`, and `grpc_type`, the streaming kind
+//! such as `server_streaming`:
+//!
+//! * `grpc_server_started_total`, a counter of the calls received,
+//! * `grpc_server_handled_total`, a counter of the calls completed, with the
+//!   label `grpc_code`, e.g. `NotFound`,
+//! * `grpc_server_in_flight`, a gauge of the calls being handled,
+//! * `grpc_server_handling_seconds`, a histogram of the time until the
+//!   response, or for streaming responses, the response stream, was returned.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::spans;
+
+/// Records metrics about the handler of every method of the generated servers
+/// in `ast`.
+pub(crate) fn rewrite_server(ast: &mut syn::File) {
+    spans::rewrite_handlers(ast, measure);
+}
+
+/// Returns a future that awaits `fut`, the handler of the method `path`, and
+/// records its metrics.
+fn measure(fut: &syn::Expr, path: &str, kind: &str) -> TokenStream {
+    let name = path.trim_start_matches('/');
+    let (service, method) = name.split_once('/').unwrap_or((name, ""));
+    quote! {
+        async move {
+            struct InFlight(::metrics::Gauge);
+            impl Drop for InFlight {
+                fn drop(&mut self) {
+                    self.0.decrement(1.0);
+                }
+            }
+
+            let handler = #fut;
+            ::metrics::counter!(
+                "grpc_server_started_total",
+                "grpc_service" => #service,
+                "grpc_method" => #method,
+                "grpc_type" => #kind,
+            )
+            .increment(1);
+            let in_flight = ::metrics::gauge!(
+                "grpc_server_in_flight",
+                "grpc_service" => #service,
+                "grpc_method" => #method,
+                "grpc_type" => #kind,
+            );
+            in_flight.increment(1.0);
+            // Also leaves the gauge when the call is cancelled.
+            let _in_flight = InFlight(in_flight);
+            let start = ::std::time::Instant::now();
+            let result = handler.await;
+            let code = match &result {
+                Ok(_) => tonic::Code::Ok,
+                Err(status) => status.code(),
+            };
+            ::metrics::histogram!(
+                "grpc_server_handling_seconds",
+                "grpc_service" => #service,
+                "grpc_method" => #method,
+                "grpc_type" => #kind,
+            )
+            .record(start.elapsed());
+            ::metrics::counter!(
+                "grpc_server_handled_total",
+                "grpc_service" => #service,
+                "grpc_method" => #method,
+                "grpc_type" => #kind,
+                "grpc_code" => format!("{:?}", code),
+            )
+            .increment(1);
+            result
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Generation of a mod file that arranges generated services in a module
+//! hierarchy mirroring their proto packages.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{rust_protobuf_ident, GeneratedFile};
+
+#[derive(Default)]
+struct Module<'a> {
+    children: BTreeMap<String, Module<'a>>,
+    files: Vec<&'a GeneratedFile>,
+    /// The full paths of the types re-exported by the module.
+    reexports: Vec<&'a str>,
+}
+
+/// Renders a mod file that includes each of `files` in nested modules named
+/// after the segments of its package, e.g. `pub mod a { pub mod b { ... } }`
+/// for package `a.b`, and re-exports the client, server and service trait.
+///
+/// The package modules also re-export `reexports`, the full paths of types
+/// by package, e.g. the types of files imported with `import public`. Files
+/// without a package are included in the module of `default_package`, if
+/// set.
+///
+/// If `type_modules` is not empty, a `register_types` function registers the
+/// message types of these rust-protobuf modules with a `TypeRegistry`.
+pub(crate) fn render(
+    files: &[GeneratedFile],
+    build_client: bool,
+    build_server: bool,
+    type_modules: &[String],
+    reexports: &BTreeMap<String, BTreeSet<String>>,
+    default_package: Option<&str>,
+) -> String {
+    let mut root = Module::default();
+    for file in files {
+        let package = match default_package {
+            Some(default_package) if file.package.is_empty() => default_package,
+            _ => &file.package,
+        };
+        package_module(&mut root, package).files.push(file);
+    }
+    for (package, paths) in reexports {
+        let module = package_module(&mut root, package);
+        module.reexports.extend(paths.iter().map(String::as_str));
+    }
+
+    let mut tokens = render_module(&root, build_client, build_server);
+    if !type_modules.is_empty() {
+        let modules = type_modules
+            .iter()
+            .map(|m| syn::parse_str::<syn::Path>(m).expect("invalid module path"));
+        tokens.extend(quote! {
+            /// Registers the message types of the compiled .proto files with
+            /// `registry`.
+            pub fn register_types(registry: &mut ::tonic_types_protobuf::any::TypeRegistry) {
+                #(registry.register_file(#modules::file_descriptor());)*
+            }
+        });
+    }
+    let ast: syn::File = syn::parse2(tokens).expect("not a valid tokenstream");
+    prettyplease::unparse(&ast)
+}
+
+/// Returns the module of `package`, creating it and its parents if needed.
+fn package_module<'m, 'a>(root: &'m mut Module<'a>, package: &str) -> &'m mut Module<'a> {
+    let mut module = root;
+    for segment in package.split('.').filter(|s| !s.is_empty()) {
+        let name = rust_protobuf_ident(&segment.to_snake_case());
+        module = module.children.entry(name).or_default();
+    }
+    module
+}
+
+fn render_module(module: &Module<'_>, build_client: bool, build_server: bool) -> TokenStream {
+    let files = module.files.iter().map(|file| {
+        let name = &file.name;
+        let snake = file.service.to_snake_case();
+        let client_mod = format_ident!("{}_client", snake);
+        let server_mod = format_ident!("{}_server", snake);
+        let client = format_ident!("{}Client", file.service);
+        let server = format_ident!("{}Server", file.service);
+        let service = format_ident!("{}", file.service);
+        let client = build_client.then(|| quote!(pub use #client_mod::#client;));
+        let server = build_server.then(|| quote!(pub use #server_mod::{#service, #server};));
+        quote! {
+            include!(#name);
+            #client
+            #server
+        }
+    });
+    let reexports = module
+        .reexports
+        .iter()
+        .map(|path| syn::parse_str::<syn::Path>(path).expect("invalid type path"));
+    let children = module.children.iter().map(|(name, child)| {
+        let name = format_ident!("{}", name);
+        let child = render_module(child, build_client, build_server);
+        quote! {
+            pub mod #name {
+                #child
+            }
+        }
+    });
+    quote! {
+        #(#files)*
+        #(pub use #reexports;)*
+        #(#children)*
+    }
+}
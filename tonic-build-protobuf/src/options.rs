@@ -0,0 +1,354 @@
+//! Decoding of custom options.
+//!
+//! protoc encodes custom options as unknown fields of the options messages,
+//! since rust-protobuf does not know their extensions. They are decoded here
+//! using the extension definitions found in the parsed descriptors.
+
+use std::collections::HashMap;
+
+use heck::ToShoutySnakeCase;
+use proc_macro2::{Literal, TokenStream};
+use protobuf::{
+    descriptor::{
+        field_descriptor_proto::{Label, Type},
+        FieldDescriptorProto, FileDescriptorSet,
+    },
+    well_known_types::empty::Empty,
+    CodedInputStream, Message, MessageField, UnknownValueRef,
+};
+use quote::{format_ident, quote, ToTokens};
+
+use crate::DescriptorIndex;
+
+/// A decoded custom option value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum OptionValue {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// The name of an enum value.
+    Enum(String),
+    /// The set fields of a message, by field name. Repeated fields appear
+    /// once per value.
+    Message(Vec<(String, OptionValue)>),
+}
+
+impl OptionValue {
+    /// Returns the value of the field `name`, if it is a message.
+    pub(crate) fn field(&self, name: &str) -> Option<&OptionValue> {
+        match self {
+            OptionValue::Message(fields) => {
+                fields.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a string or an enum value name.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            OptionValue::String(v) | OptionValue::Enum(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn rust_type(&self) -> TokenStream {
+        match self {
+            OptionValue::Bool(_) => quote!(bool),
+            OptionValue::I32(_) => quote!(i32),
+            OptionValue::I64(_) => quote!(i64),
+            OptionValue::U32(_) => quote!(u32),
+            OptionValue::U64(_) => quote!(u64),
+            OptionValue::F32(_) => quote!(f32),
+            OptionValue::F64(_) => quote!(f64),
+            OptionValue::String(_) | OptionValue::Enum(_) => quote!(&str),
+            OptionValue::Bytes(_) => quote!(&[u8]),
+            OptionValue::Message(_) => unreachable!("messages have no constant representation"),
+        }
+    }
+
+    pub(crate) fn literal(&self) -> TokenStream {
+        match self {
+            OptionValue::Bool(v) => quote!(#v),
+            OptionValue::I32(v) => Literal::i32_unsuffixed(*v).to_token_stream(),
+            OptionValue::I64(v) => Literal::i64_unsuffixed(*v).to_token_stream(),
+            OptionValue::U32(v) => Literal::u32_unsuffixed(*v).to_token_stream(),
+            OptionValue::U64(v) => Literal::u64_unsuffixed(*v).to_token_stream(),
+            OptionValue::F32(v) if v.is_nan() => quote!(f32::NAN),
+            OptionValue::F32(v) if v.is_infinite() && *v > 0.0 => quote!(f32::INFINITY),
+            OptionValue::F32(v) if v.is_infinite() => quote!(f32::NEG_INFINITY),
+            OptionValue::F32(v) => Literal::f32_unsuffixed(*v).to_token_stream(),
+            OptionValue::F64(v) if v.is_nan() => quote!(f64::NAN),
+            OptionValue::F64(v) if v.is_infinite() && *v > 0.0 => quote!(f64::INFINITY),
+            OptionValue::F64(v) if v.is_infinite() => quote!(f64::NEG_INFINITY),
+            OptionValue::F64(v) => Literal::f64_unsuffixed(*v).to_token_stream(),
+            OptionValue::String(v) | OptionValue::Enum(v) => quote!(#v),
+            OptionValue::Bytes(v) => Literal::byte_string(v).to_token_stream(),
+            OptionValue::Message(_) => unreachable!("messages have no constant representation"),
+        }
+    }
+}
+
+/// A custom option set on a service or method.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CustomOption {
+    /// The name of the extension field, e.g. `tier`.
+    pub(crate) name: String,
+    /// The fully qualified name of the extension, e.g. `myorg.tier`.
+    pub(crate) full_name: String,
+    /// The decoded values, exactly one unless the option is repeated.
+    pub(crate) values: Vec<OptionValue>,
+    /// Whether the extension is a repeated field.
+    pub(crate) repeated: bool,
+}
+
+impl CustomOption {
+    /// Returns the single value of a non-repeated option.
+    pub(crate) fn value(&self) -> Option<&OptionValue> {
+        if self.repeated {
+            None
+        } else {
+            self.values.last()
+        }
+    }
+}
+
+/// An extension of an options message, e.g.
+/// `extend google.protobuf.ServiceOptions { string tier = 50001; }`.
+#[derive(Debug, Clone)]
+pub(crate) struct Extension {
+    /// The fully qualified name of the extension, without a leading dot.
+    pub(crate) full_name: String,
+    /// The extension field.
+    pub(crate) field: FieldDescriptorProto,
+}
+
+/// Returns all extensions of `extendee` (e.g.
+/// `.google.protobuf.ServiceOptions`) in `fds`, keyed by field number.
+pub(crate) fn collect_extensions(
+    fds: &FileDescriptorSet,
+    extendee: &str,
+) -> HashMap<u32, Extension> {
+    fn collect(
+        scope: &str,
+        fields: &[FieldDescriptorProto],
+        extendee: &str,
+        extensions: &mut HashMap<u32, Extension>,
+    ) {
+        for field in fields.iter().filter(|f| f.extendee() == extendee) {
+            let full_name = if scope.is_empty() {
+                field.name().to_owned()
+            } else {
+                format!("{}.{}", scope, field.name())
+            };
+            extensions.insert(
+                field.number() as u32,
+                Extension {
+                    full_name,
+                    field: field.clone(),
+                },
+            );
+        }
+    }
+    fn collect_nested(
+        scope: &str,
+        messages: &[protobuf::descriptor::DescriptorProto],
+        extendee: &str,
+        extensions: &mut HashMap<u32, Extension>,
+    ) {
+        for message in messages {
+            let scope = format!("{}.{}", scope, message.name());
+            collect(&scope, &message.extension, extendee, extensions);
+            collect_nested(&scope, &message.nested_type, extendee, extensions);
+        }
+    }
+
+    let mut extensions = HashMap::new();
+    for fd in &fds.file {
+        collect(fd.package(), &fd.extension, extendee, &mut extensions);
+        collect_nested(fd.package(), &fd.message_type, extendee, &mut extensions);
+    }
+    extensions
+}
+
+/// Decodes the custom options set in `options`.
+///
+/// Enum and message typed options are resolved with `index`. Options that
+/// can not be decoded are reported to `warnings`, with `context` describing
+/// where they are set.
+pub(crate) fn decode<M: Message>(
+    options: &MessageField<M>,
+    extensions: &HashMap<u32, Extension>,
+    index: &DescriptorIndex,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<CustomOption> {
+    let Some(options) = options.as_ref() else {
+        return vec![];
+    };
+
+    let mut decoded: Vec<CustomOption> = vec![];
+    for (number, value) in options.special_fields().unknown_fields().iter() {
+        let Some(extension) = extensions.get(&number) else {
+            warnings.push(format!(
+                "{}: custom option {} can not be resolved and is ignored",
+                context, number
+            ));
+            continue;
+        };
+        let Some(values) = decode_values(&extension.field, value, index) else {
+            warnings.push(format!(
+                "{}: custom option ({}) has an unsupported type and is ignored",
+                context, extension.full_name
+            ));
+            continue;
+        };
+        for value in values {
+            match decoded
+                .iter_mut()
+                .find(|o| o.full_name == extension.full_name)
+            {
+                Some(option) => option.values.push(value),
+                None => decoded.push(CustomOption {
+                    name: extension.field.name().to_owned(),
+                    full_name: extension.full_name.clone(),
+                    values: vec![value],
+                    repeated: extension.field.label() == Label::LABEL_REPEATED,
+                }),
+            }
+        }
+    }
+    // Unknown fields are not ordered, sort options for deterministic output.
+    decoded.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+    decoded
+}
+
+/// Decodes the values of `field` in `value`, which holds several values if
+/// `field` is a packed repeated scalar field.
+fn decode_values(
+    field: &FieldDescriptorProto,
+    value: UnknownValueRef<'_>,
+    index: &DescriptorIndex,
+) -> Option<Vec<OptionValue>> {
+    let bytes = match value {
+        UnknownValueRef::LengthDelimited(bytes) if field.label() == Label::LABEL_REPEATED => bytes,
+        value => return Some(vec![decode_value(field, value, index)?]),
+    };
+    let read: fn(&mut CodedInputStream) -> protobuf::Result<UnknownValueRef<'static>> =
+        match field.type_() {
+            Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE | Type::TYPE_GROUP => {
+                return Some(vec![decode_value(field, value, index)?]);
+            }
+            Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 | Type::TYPE_FLOAT => {
+                |input| input.read_fixed32().map(UnknownValueRef::Fixed32)
+            }
+            Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 | Type::TYPE_DOUBLE => {
+                |input| input.read_fixed64().map(UnknownValueRef::Fixed64)
+            }
+            _ => |input| input.read_raw_varint64().map(UnknownValueRef::Varint),
+        };
+    let mut input = CodedInputStream::from_bytes(bytes);
+    let mut values = vec![];
+    while !input.eof().ok()? {
+        values.push(decode_value(field, read(&mut input).ok()?, index)?);
+    }
+    Some(values)
+}
+
+fn decode_value(
+    field: &FieldDescriptorProto,
+    value: UnknownValueRef<'_>,
+    index: &DescriptorIndex,
+) -> Option<OptionValue> {
+    let value = match (field.type_(), value) {
+        (Type::TYPE_BOOL, UnknownValueRef::Varint(v)) => OptionValue::Bool(v != 0),
+        (Type::TYPE_INT32, UnknownValueRef::Varint(v)) => OptionValue::I32(v as i32),
+        (Type::TYPE_INT64, UnknownValueRef::Varint(v)) => OptionValue::I64(v as i64),
+        (Type::TYPE_UINT32, UnknownValueRef::Varint(v)) => OptionValue::U32(v as u32),
+        (Type::TYPE_UINT64, UnknownValueRef::Varint(v)) => OptionValue::U64(v),
+        (Type::TYPE_SINT32, UnknownValueRef::Varint(v)) => {
+            OptionValue::I32(((v >> 1) as i32) ^ -((v & 1) as i32))
+        }
+        (Type::TYPE_SINT64, UnknownValueRef::Varint(v)) => {
+            OptionValue::I64(((v >> 1) as i64) ^ -((v & 1) as i64))
+        }
+        (Type::TYPE_ENUM, UnknownValueRef::Varint(v)) => {
+            let number = v as i32;
+            let values = index.enums.get(field.type_name())?;
+            let (name, _) = values.iter().find(|(_, n)| *n == number)?;
+            OptionValue::Enum(name.clone())
+        }
+        (Type::TYPE_FIXED32, UnknownValueRef::Fixed32(v)) => OptionValue::U32(v),
+        (Type::TYPE_SFIXED32, UnknownValueRef::Fixed32(v)) => OptionValue::I32(v as i32),
+        (Type::TYPE_FLOAT, UnknownValueRef::Fixed32(v)) => OptionValue::F32(f32::from_bits(v)),
+        (Type::TYPE_FIXED64, UnknownValueRef::Fixed64(v)) => OptionValue::U64(v),
+        (Type::TYPE_SFIXED64, UnknownValueRef::Fixed64(v)) => OptionValue::I64(v as i64),
+        (Type::TYPE_DOUBLE, UnknownValueRef::Fixed64(v)) => OptionValue::F64(f64::from_bits(v)),
+        (Type::TYPE_STRING, UnknownValueRef::LengthDelimited(v)) => {
+            OptionValue::String(String::from_utf8(v.to_vec()).ok()?)
+        }
+        (Type::TYPE_BYTES, UnknownValueRef::LengthDelimited(v)) => OptionValue::Bytes(v.to_vec()),
+        (Type::TYPE_MESSAGE, UnknownValueRef::LengthDelimited(v)) => {
+            let message = index.messages.get(field.type_name())?;
+            // All fields of an empty message are unknown fields, which lets
+            // the message be decoded with its descriptor.
+            let raw = Empty::parse_from_bytes(v).ok()?;
+            let mut fields = vec![];
+            for (number, value) in raw.special_fields.unknown_fields().iter() {
+                let field = message.field.iter().find(|f| f.number() == number as i32)?;
+                for value in decode_values(field, value, index)? {
+                    fields.push((field.name().to_owned(), value));
+                }
+            }
+            OptionValue::Message(fields)
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Generates a module named `module` with a constant for each option.
+///
+/// Message typed options have no constant representation and are skipped.
+pub(crate) fn generate_constants(module: &str, doc: &str, options: &[CustomOption]) -> TokenStream {
+    let options: Vec<_> = options
+        .iter()
+        .filter(|o| !matches!(o.values[0], OptionValue::Message(_)))
+        .collect();
+    if options.is_empty() {
+        return TokenStream::new();
+    }
+    let module = format_ident!("{}", module);
+    let constants = options.iter().map(|option| {
+        let name = format_ident!("{}", option.name.to_shouty_snake_case());
+        let doc = format!(" `({})`", option.full_name);
+        if option.repeated {
+            let ty = option.values[0].rust_type();
+            let values = option.values.iter().map(OptionValue::literal);
+            quote! {
+                #[doc = #doc]
+                pub const #name: &[#ty] = &[#(#values),*];
+            }
+        } else {
+            let value = option.value().unwrap();
+            let ty = value.rust_type();
+            let value = value.literal();
+            quote! {
+                #[doc = #doc]
+                pub const #name: #ty = #value;
+            }
+        }
+    });
+    quote! {
+        #[doc = #doc]
+        pub mod #module {
+            #(#constants)*
+        }
+    }
+}
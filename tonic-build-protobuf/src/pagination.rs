@@ -0,0 +1,202 @@
+//! Generation of pagers for list methods.
+//!
+//! Unary methods following the pagination conventions of
+//! [AIP-158](https://google.aip.dev/158) are detected from their descriptors:
+//! the request has an `int32 page_size` and a `string page_token` field, the
+//! response a `string next_page_token` field and a repeated field holding the
+//! page items, the first one if there are several. Fields must not have
+//! presence, as in proto3 without `optional`.
+
+use heck::ToUpperCamelCase;
+use proc_macro2::TokenStream;
+use protobuf::descriptor::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, MethodDescriptorProto,
+};
+use quote::{format_ident, quote};
+
+use crate::{client, rust_protobuf_ident, DescriptorIndex, Service, TonicVersion};
+
+/// The page items of a list method.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Pagination {
+    /// The Rust name of the repeated response field.
+    items_field: String,
+    /// The full path of the Rust type of the items.
+    item_type: String,
+}
+
+/// Returns the page items of `method` if it is a list method.
+pub(crate) fn detect(
+    method: &MethodDescriptorProto,
+    index: &DescriptorIndex,
+    proto_path: &str,
+) -> Option<Pagination> {
+    if method.client_streaming() || method.server_streaming() {
+        return None;
+    }
+    let request = message(index, method.input_type())?;
+    let response = message(index, method.output_type())?;
+    let singular = |message: &DescriptorProto, name: &str, ty: Type| {
+        message.field.iter().any(|field| {
+            field.name() == name
+                && field.type_() == ty
+                && field.label() == Label::LABEL_OPTIONAL
+                && !field.proto3_optional()
+                && !field.has_oneof_index()
+        })
+    };
+    if !singular(request, "page_size", Type::TYPE_INT32)
+        || !singular(request, "page_token", Type::TYPE_STRING)
+        || !singular(response, "next_page_token", Type::TYPE_STRING)
+    {
+        return None;
+    }
+    let items = response
+        .field
+        .iter()
+        .find(|field| field.label() == Label::LABEL_REPEATED && !is_map(index, field))?;
+    let item_type = match items.type_() {
+        Type::TYPE_MESSAGE => index.rust_type(proto_path, items.type_name())?,
+        Type::TYPE_STRING => "::std::string::String".to_owned(),
+        _ => return None,
+    };
+    Some(Pagination {
+        items_field: rust_protobuf_ident(items.name()),
+        item_type,
+    })
+}
+
+/// Returns the descriptor of the message `ty`, if it is defined in a proto3
+/// file.
+fn message<'a>(index: &'a DescriptorIndex, ty: &str) -> Option<&'a DescriptorProto> {
+    if !index.proto3_messages.contains(ty) {
+        return None;
+    }
+    index.messages.get(ty)
+}
+
+fn is_map(index: &DescriptorIndex, field: &FieldDescriptorProto) -> bool {
+    field.type_() == Type::TYPE_MESSAGE
+        && index
+            .messages
+            .get(field.type_name())
+            .is_some_and(|entry| entry.options.map_entry())
+}
+
+/// Generates the `{Method}Pages` pagers and the `{method}_pages` methods of
+/// `service`, to be appended to its client module.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let client_type = format_ident!("{}Client", service.name);
+    let mut pagers = vec![];
+    let mut methods = vec![];
+    for method in &service.methods {
+        let Some(pagination) = &method.pagination else {
+            continue;
+        };
+        let name = format_ident!("{}", method.name);
+        let pages = format_ident!("{}_pages", method.name);
+        let pager = format_ident!("{}Pages", method.route_name.to_upper_camel_case());
+        let (request, response) = method.request_response_types();
+        let items = format_ident!("{}", pagination.items_field);
+        let item: syn::Path = syn::parse_str(&pagination.item_type).unwrap();
+        let pager_doc = format!(
+            " Pages through the results of `{}`, see [`{}::{}`].",
+            method.route_name, client_type, pages
+        );
+        let items_doc = format!(
+            " Returns the next item of the `{}` field of the pages, fetching pages as needed.",
+            pagination.items_field
+        );
+        let where_clause = client::where_clause(tonic_version, quote!());
+        pagers.push(quote! {
+            #[doc = #pager_doc]
+            #[derive(Debug)]
+            pub struct #pager<T> {
+                client: #client_type<T>,
+                metadata: tonic::metadata::MetadataMap,
+                request: #request,
+                items: std::collections::VecDeque<#item>,
+                done: bool,
+            }
+            impl<T> #pager<T>
+            #where_clause
+            {
+                /// Fetches the next page, or returns `None` after the last
+                /// page. Items not returned by [`Self::next_item`] yet are
+                /// skipped.
+                pub async fn next_page(
+                    &mut self,
+                ) -> std::result::Result<Option<#response>, tonic::Status> {
+                    self.items.clear();
+                    self.fetch().await
+                }
+                #[doc = #items_doc]
+                /// Returns `None` after the last item.
+                pub async fn next_item(
+                    &mut self,
+                ) -> std::result::Result<Option<#item>, tonic::Status> {
+                    loop {
+                        if let Some(item) = self.items.pop_front() {
+                            return Ok(Some(item));
+                        }
+                        match self.fetch().await? {
+                            Some(mut page) => {
+                                self.items.extend(std::mem::take(&mut page.#items));
+                            }
+                            None => return Ok(None),
+                        }
+                    }
+                }
+                async fn fetch(&mut self) -> std::result::Result<Option<#response>, tonic::Status> {
+                    if self.done {
+                        return Ok(None);
+                    }
+                    let request = tonic::Request::from_parts(
+                        self.metadata.clone(),
+                        Default::default(),
+                        self.request.clone(),
+                    );
+                    let page = self.client.#name(request).await?.into_inner();
+                    if page.next_page_token.is_empty() {
+                        self.done = true;
+                    } else {
+                        self.request.page_token.clone_from(&page.next_page_token);
+                    }
+                    Ok(Some(page))
+                }
+            }
+        });
+
+        let doc = format!(
+            " Returns a pager over the pages of `{}`, starting at the page token of `request`.",
+            method.route_name
+        );
+        methods.push(quote! {
+            #[doc = #doc]
+            pub fn #pages(&self, request: impl tonic::IntoRequest<#request>) -> #pager<T> {
+                let (metadata, _, request) = tonic::IntoRequest::into_request(request).into_parts();
+                #pager {
+                    client: self.clone(),
+                    metadata,
+                    request,
+                    items: Default::default(),
+                    done: false,
+                }
+            }
+        });
+    }
+    if methods.is_empty() {
+        return TokenStream::new();
+    }
+    let methods = client::impl_block(
+        service,
+        tonic_version,
+        quote!(+ Clone),
+        quote!(#(#methods)*),
+    );
+    quote! {
+        #(#pagers)*
+        #methods
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Builder, FileNaming, TonicVersion};
+
+/// Applies `parameter` to `builder`, see [`Builder::parameters`].
+pub(crate) fn configure(mut builder: Builder, parameter: &str) -> Result<Builder, String> {
+    for pair in parameter.split(',').filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("invalid parameter {:?}, expected key=value", pair))?;
+        let flag = || match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!(
+                "invalid value {:?} of {}, expected a bool",
+                value, key
+            )),
+        };
+        builder = match key {
+            "codec_path" => builder.codec_path(value),
+            "proto_path" => builder.proto_path(value),
+            "build_client" => builder.build_client(flag()?),
+            "build_server" => builder.build_server(flag()?),
+            "build_transport" => builder.build_transport(flag()?),
+            "build_call_options" => builder.build_call_options(flag()?),
+            "build_iter_methods" => builder.build_iter_methods(flag()?),
+            "build_service_descriptors" => builder.build_service_descriptors(flag()?),
+            "build_schema_hash" => builder.build_schema_hash(flag()?),
+            "build_uds_connect" => builder.build_uds_connect(flag()?),
+            "build_blocking_client" => builder.build_blocking_client(flag()?),
+            "build_smoke_tests" => builder.build_smoke_tests(flag()?),
+            "build_health_wait" => builder.build_health_wait(flag()?),
+            "select_codec" => builder.select_codec(flag()?),
+            "build_web" => builder.build_web(flag()?),
+            "build_tracing" => builder.build_tracing(flag()?),
+            "build_metrics" => builder.build_metrics(flag()?),
+            "validate_requests" => builder.validate_requests(flag()?),
+            "runtime_helpers" => builder.runtime_helpers(flag()?),
+            "package_dirs" => builder.package_dirs(flag()?),
+            "inner_lint_attributes" => builder.inner_lint_attributes(flag()?),
+            "allow_lint" => builder.allow_lint(value),
+            "tonic_version" => builder.tonic_version(tonic_version(value)?),
+            "default_package" => builder.default_package(value),
+            "file_naming" => builder.file_naming(file_naming(value)?),
+            "api_docs" => builder.api_docs(value),
+            "mod_file" => builder.mod_file(value),
+            _ => return Err(format!("unknown parameter {:?}", key)),
+        };
+    }
+    Ok(builder)
+}
+
+fn file_naming(value: &str) -> Result<FileNaming, String> {
+    match value {
+        "package_service" => Ok(FileNaming::PackageService),
+        "proto_file" => Ok(FileNaming::ProtoFile),
+        _ => Err(format!("unsupported file_naming {:?}", value)),
+    }
+}
+
+fn tonic_version(value: &str) -> Result<TonicVersion, String> {
+    match value {
+        "0.11" => Ok(TonicVersion::V0_11),
+        #[cfg(feature = "tonic-0-12")]
+        "0.12" => Ok(TonicVersion::V0_12),
+        #[cfg(feature = "tonic-0-13")]
+        "0.13" => Ok(TonicVersion::V0_13),
+        #[cfg(feature = "tonic-0-14")]
+        "0.14" => Ok(TonicVersion::V0_14),
+        _ => Err(format!(
+            "unsupported tonic_version {:?}, note that versions other than 0.11 require \
+             the corresponding tonic-0-* feature",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::configure;
+    use crate::{Builder, FileNaming, TonicVersion};
+
+    fn error(parameter: &str) -> String {
+        configure(Builder::new(), parameter).err().unwrap()
+    }
+
+    #[test]
+    fn test_configure() {
+        let builder = configure(
+            Builder::new(),
+            "codec_path=crate::Codec,build_client=false,build_web=true,tonic_version=0.11,\
+             file_naming=proto_file,allow_lint=dead_code,allow_lint=unused_imports",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(builder.codec_path, "crate::Codec");
+        assert!(!builder.build_client);
+        assert!(builder.build_server);
+        assert!(builder.build_web);
+        assert_eq!(builder.tonic_version, TonicVersion::V0_11);
+        assert_eq!(builder.file_naming, FileNaming::ProtoFile);
+        assert_eq!(builder.lint_allows, ["dead_code", "unused_imports"]);
+
+        // Empty pairs, e.g. of an empty parameter, are ignored.
+        let builder = configure(Builder::new(), ",mod_file=mod.rs,").ok().unwrap();
+        assert_eq!(builder.mod_file.as_deref(), Some("mod.rs"));
+        assert!(configure(Builder::new(), "").is_ok());
+    }
+
+    #[test]
+    fn test_configure_errors() {
+        assert_eq!(error("build_clients=true"), r#"unknown parameter "build_clients""#);
+        assert_eq!(
+            error("build_client"),
+            r#"invalid parameter "build_client", expected key=value"#
+        );
+        assert_eq!(
+            error("build_client=yes"),
+            r#"invalid value "yes" of build_client, expected a bool"#
+        );
+        assert_eq!(
+            error("file_naming=package"),
+            r#"unsupported file_naming "package""#
+        );
+        assert!(error("tonic_version=0.10").starts_with(r#"unsupported tonic_version "0.10""#));
+        // The first invalid pair is reported.
+        assert_eq!(
+            error("codec_path=crate::Codec,foo=1,build_client=no"),
+            r#"unknown parameter "foo""#
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use protobuf::{
+    descriptor::{FileDescriptorProto, FileDescriptorSet},
+    rt::WireType,
+    CodedInputStream, Message,
+};
+
+use crate::ParsedProtos;
+
+/// .proto files parsed once for several builders.
+///
+/// Build scripts that configure several builders, e.g. one for clients and
+/// one for a differently configured server, would otherwise run protoc for
+/// every [`Builder::compile`](crate::Builder::compile) call. Parse all inputs
+/// with [`Builder::parse_protos`](crate::Builder::parse_protos) instead, and
+/// generate code for some of them with
+/// [`Builder::compile_set`](crate::Builder::compile_set):
+///
+/// ```rust,ignore
+/// let protos = Builder::new().parse_protos(&["proto/a.proto", "proto/b.proto"], &["proto"]);
+/// Builder::new().compile_set(&protos, &["proto/a.proto", "proto/b.proto"]);
+/// Builder::new()
+///     .build_client(false)
+///     .file_name(|pkg, svc| format!("{pkg}_{svc}_json"))
+///     .compile_set(&protos, &["proto/b.proto"]);
+/// ```
+///
+/// Descriptor sets built beforehand, e.g. by `buf build`, are loaded with
+/// [`ProtoSet::load_descriptor_set`] instead.
+#[derive(Clone)]
+pub struct ProtoSet {
+    /// The input .proto files as given, and relative to their include
+    /// directory.
+    inputs: Vec<(PathBuf, String)>,
+    /// Descriptors of the input files and all of their imports.
+    fds: FileDescriptorSet,
+}
+
+impl fmt::Debug for ProtoSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtoSet")
+            .field("inputs", &self.inputs().collect::<Vec<_>>())
+            .field("files", &self.fds.file.len())
+            .finish()
+    }
+}
+
+impl ProtoSet {
+    pub(crate) fn new(inputs: Vec<(PathBuf, String)>, fds: FileDescriptorSet) -> Self {
+        ProtoSet { inputs, fds }
+    }
+
+    /// Loads the descriptors of `inputs` and all of their imports from the
+    /// encoded `FileDescriptorSet` at `path`, e.g. written by `protoc -o` or
+    /// `buf build -o image.binpb`. `inputs` are names of files in the set,
+    /// e.g. `a/b.proto`.
+    ///
+    /// The file is memory-mapped, and only the descriptors of the inputs and
+    /// their imports are decoded, so that whole-monorepo images of tens of MB
+    /// do not need to be read into memory and decoded as a whole.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, is not a valid
+    /// `FileDescriptorSet`, or lacks any of `inputs` or their imports.
+    pub fn load_descriptor_set(
+        path: impl AsRef<Path>,
+        inputs: &[impl AsRef<str>],
+    ) -> io::Result<ProtoSet> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // SAFETY: The map is only read, before this function returns. Build
+        // inputs are not expected to be modified concurrently, and a
+        // truncated file at worst fails decoding.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let entries = index(&map).map_err(invalid_data)?;
+        let by_name: HashMap<_, _> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry))
+            .collect();
+
+        let mut needed = HashSet::new();
+        let mut pending: Vec<_> = inputs.iter().map(|input| input.as_ref()).collect();
+        while let Some(name) = pending.pop() {
+            if !needed.insert(name) {
+                continue;
+            }
+            let entry = by_name.get(name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in {}", name, path.display()),
+                )
+            })?;
+            pending.extend(entry.dependencies.iter().map(String::as_str));
+        }
+
+        // Decode in the order of the set, which lists the imports of a file
+        // before it.
+        let mut fds = FileDescriptorSet::new();
+        for entry in &entries {
+            if needed.contains(entry.name.as_str()) {
+                let fd = FileDescriptorProto::parse_from_bytes(&map[entry.range.clone()])
+                    .map_err(invalid_data)?;
+                fds.file.push(fd);
+            }
+        }
+        let inputs = inputs
+            .iter()
+            .map(|input| (PathBuf::from(input.as_ref()), input.as_ref().to_owned()))
+            .collect();
+        Ok(ProtoSet { inputs, fds })
+    }
+
+    /// Returns the names of the parsed input files, relative to their include
+    /// directory, e.g. `a.proto` for `proto/a.proto`.
+    pub fn inputs(&self) -> impl Iterator<Item = &str> {
+        self.inputs.iter().map(|(_, name)| name.as_str())
+    }
+
+    /// Returns all parsed files, all inputs included.
+    pub(crate) fn into_parsed(self) -> ParsedProtos {
+        ParsedProtos {
+            inputs: self.inputs.into_iter().map(|(_, name)| name).collect(),
+            fds: self.fds,
+        }
+    }
+
+    /// Returns all parsed files, with the inputs `protos`, given as they were
+    /// parsed or relative to their include directory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `protos` was not parsed as input.
+    pub(crate) fn select(&self, protos: &[impl AsRef<Path>]) -> ParsedProtos {
+        let inputs = protos
+            .iter()
+            .map(|proto| {
+                let proto = proto.as_ref();
+                self.inputs
+                    .iter()
+                    .find(|(path, name)| path == proto || Path::new(name) == proto)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "{} is not an input of the parsed protos, add it to \
+                             `Builder::parse_protos`",
+                            proto.display()
+                        )
+                    })
+            })
+            .collect();
+        ParsedProtos {
+            inputs,
+            fds: self.fds.clone(),
+        }
+    }
+}
+
+/// A file of an encoded `FileDescriptorSet`, which is not decoded yet.
+struct Entry {
+    name: String,
+    dependencies: Vec<String>,
+    /// The range of the encoded `FileDescriptorProto` in the set.
+    range: Range<usize>,
+}
+
+/// Field numbers of `FileDescriptorSet` and `FileDescriptorProto`.
+const SET_FILE: u32 = 1;
+const FILE_NAME: u32 = 1;
+const FILE_DEPENDENCY: u32 = 3;
+
+/// Returns the files of the encoded `FileDescriptorSet` `bytes`, with only
+/// their names and imports decoded.
+fn index(bytes: &[u8]) -> protobuf::Result<Vec<Entry>> {
+    let mut entries = vec![];
+    let mut is = CodedInputStream::from_bytes(bytes);
+    while let Some(tag) = is.read_raw_tag_or_eof()? {
+        let kind = wire_type(tag)?;
+        if tag >> 3 != SET_FILE || kind != WireType::LengthDelimited {
+            is.skip_field(kind)?;
+            continue;
+        }
+        let len = is.read_raw_varint32()?;
+        let start = is.pos() as usize;
+        is.skip_raw_bytes(len)?;
+        let range = start..start + len as usize;
+
+        let mut file = CodedInputStream::from_bytes(&bytes[range.clone()]);
+        let mut entry = Entry {
+            name: String::new(),
+            dependencies: vec![],
+            range,
+        };
+        while let Some(tag) = file.read_raw_tag_or_eof()? {
+            match (tag >> 3, wire_type(tag)?) {
+                (FILE_NAME, WireType::LengthDelimited) => entry.name = file.read_string()?,
+                (FILE_DEPENDENCY, WireType::LengthDelimited) => {
+                    entry.dependencies.push(file.read_string()?)
+                }
+                (_, kind) => file.skip_field(kind)?,
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn wire_type(tag: u32) -> protobuf::Result<WireType> {
+    WireType::new(tag & 7).ok_or_else(|| {
+        protobuf::Error::from(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid wire type in tag {}", tag),
+        ))
+    })
+}
+
+fn invalid_data(e: protobuf::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
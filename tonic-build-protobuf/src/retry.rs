@@ -0,0 +1,329 @@
+//! Generation of retrying client methods from custom options.
+//!
+//! The retry or hedging policy of a method is declared with a message typed
+//! extension of `google.protobuf.MethodOptions`, modeled after the service
+//! config of gRPC, e.g.
+//!
+//! ```proto
+//! message RetryPolicy {
+//!   // The maximum number of attempts, including the first one.
+//!   uint32 max_attempts = 1;
+//!   // The status codes that are retried, by name, e.g. "UNAVAILABLE".
+//!   repeated string retryable_codes = 2;
+//!   // The backoff before the first retry.
+//!   uint32 initial_backoff_ms = 3;
+//!   // The maximum backoff, the initial backoff by default.
+//!   uint32 max_backoff_ms = 4;
+//!   // The factor the backoff grows by after each retry, 1 by default.
+//!   double backoff_multiplier = 5;
+//!   // If set, hedge the call: send another attempt whenever no response
+//!   // arrived within this delay, instead of waiting for a failure.
+//!   uint32 hedging_delay_ms = 6;
+//! }
+//!
+//! extend google.protobuf.MethodOptions {
+//!   RetryPolicy retry = 50003;
+//! }
+//! ```
+//!
+//! Policies are applied by `{method}_with_retry` client methods, which rebuild
+//! the request from its message for every attempt. A tower layer on the
+//! channel can not do that, since it only sees the encoded, possibly
+//! streaming, request body. Client streaming methods are not retried.
+
+use heck::{ToShoutySnakeCase, ToUpperCamelCase};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::{client, errors, options::OptionValue, Method, Service, TonicVersion};
+
+/// The retry or hedging policy of a method.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RetryPolicy {
+    max_attempts: u32,
+    /// The gRPC status code names, e.g. `UNAVAILABLE`.
+    retryable_codes: Vec<&'static str>,
+    initial_backoff_ms: u64,
+    max_backoff_ms: u64,
+    backoff_multiplier: f64,
+    hedging_delay_ms: Option<u64>,
+}
+
+/// Decodes the policy declared by `value` of the retry option of `method`.
+///
+/// Invalid policies are reported to `warnings`, with `context` describing
+/// where they are declared.
+pub(crate) fn decode(
+    value: &OptionValue,
+    method: &Method,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Option<RetryPolicy> {
+    if method.client_streaming {
+        warnings.push(format!(
+            "{}: client streaming methods can not be retried, retry policy is ignored",
+            context
+        ));
+        return None;
+    }
+    let max_attempts = value.field("max_attempts").and_then(as_u64).unwrap_or(0);
+    if max_attempts < 2 {
+        warnings.push(format!(
+            "{}: retry policy needs at least 2 max_attempts and is ignored",
+            context
+        ));
+        return None;
+    }
+    let mut retryable_codes = vec![];
+    if let OptionValue::Message(fields) = value {
+        for (_, code) in fields.iter().filter(|(name, _)| name == "retryable_codes") {
+            match errors::status_code(code) {
+                Some(code) if !retryable_codes.contains(&code) => retryable_codes.push(code),
+                Some(_) => (),
+                None => warnings.push(format!(
+                    "{}: invalid retryable status code {:?} is ignored",
+                    context, code
+                )),
+            }
+        }
+    }
+    if retryable_codes.is_empty() {
+        warnings.push(format!(
+            "{}: retry policy has no retryable_codes and is ignored",
+            context
+        ));
+        return None;
+    }
+    let initial_backoff_ms = value
+        .field("initial_backoff_ms")
+        .and_then(as_u64)
+        .unwrap_or(0);
+    let max_backoff_ms = value
+        .field("max_backoff_ms")
+        .and_then(as_u64)
+        .unwrap_or(0)
+        .max(initial_backoff_ms);
+    let backoff_multiplier = match value.field("backoff_multiplier").and_then(as_f64) {
+        Some(multiplier) if multiplier >= 1.0 => multiplier,
+        _ => 1.0,
+    };
+    let hedging_delay_ms = value
+        .field("hedging_delay_ms")
+        .and_then(as_u64)
+        .filter(|delay| *delay > 0);
+    Some(RetryPolicy {
+        max_attempts: u32::try_from(max_attempts).unwrap_or(u32::MAX),
+        retryable_codes,
+        initial_backoff_ms,
+        max_backoff_ms,
+        backoff_multiplier,
+        hedging_delay_ms,
+    })
+}
+
+fn as_u64(value: &OptionValue) -> Option<u64> {
+    match value {
+        OptionValue::U32(v) => Some(u64::from(*v)),
+        OptionValue::U64(v) => Some(*v),
+        OptionValue::I32(v) => u64::try_from(*v).ok(),
+        OptionValue::I64(v) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &OptionValue) -> Option<f64> {
+    match value {
+        OptionValue::F32(v) => Some(f64::from(*v)),
+        OptionValue::F64(v) => Some(*v),
+        value => as_u64(value).map(|v| v as f64),
+    }
+}
+
+/// Generates the `RetryPolicy` struct, the `retry_policies` constants and the
+/// `{method}_with_retry` variants of `service`, to be appended to its client
+/// module.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let mut constants = vec![];
+    let mut methods = vec![];
+    for method in &service.methods {
+        let Some(policy) = &method.retry else {
+            continue;
+        };
+        let constant = format_ident!("{}", method.name.to_shouty_snake_case());
+        let max_attempts = policy.max_attempts;
+        let codes = policy.retryable_codes.iter().map(|code| {
+            let code = format_ident!("{}", code.to_upper_camel_case());
+            quote!(tonic::Code::#code)
+        });
+        let initial_backoff = policy.initial_backoff_ms;
+        let max_backoff = policy.max_backoff_ms;
+        let multiplier = Literal::f64_unsuffixed(policy.backoff_multiplier);
+        let hedging_delay = match policy.hedging_delay_ms {
+            Some(delay) => quote!(Some(std::time::Duration::from_millis(#delay))),
+            None => quote!(None),
+        };
+        let doc = format!(" The policy of `{}`.", method.route_name);
+        constants.push(quote! {
+            #[doc = #doc]
+            pub const #constant: super::RetryPolicy = super::RetryPolicy {
+                max_attempts: #max_attempts,
+                retryable_codes: &[#(#codes),*],
+                initial_backoff: std::time::Duration::from_millis(#initial_backoff),
+                max_backoff: std::time::Duration::from_millis(#max_backoff),
+                backoff_multiplier: #multiplier,
+                hedging_delay: #hedging_delay,
+            };
+        });
+
+        let name = format_ident!("{}", method.name);
+        let with_retry = format_ident!("{}_with_retry", method.name);
+        let (request, response) = client::method_types(method);
+        let doc = format!(
+            " Like [`Self::{}`], but retries the call per [`retry_policies::{}`].",
+            method.name, constant
+        );
+        methods.push(quote! {
+            #[doc = #doc]
+            pub async fn #with_retry(
+                &mut self,
+                request: #request,
+            ) -> std::result::Result<#response, tonic::Status> {
+                let client = self.clone();
+                retry_policies::#constant
+                    .call(tonic::IntoRequest::into_request(request), move |request| {
+                        let mut client = client.clone();
+                        async move { client.#name(request).await }
+                    })
+                    .await
+            }
+        });
+    }
+    if methods.is_empty() {
+        return TokenStream::new();
+    }
+    let methods = client::impl_block(
+        service,
+        tonic_version,
+        quote!(+ Clone),
+        quote!(#(#methods)*),
+    );
+
+    quote! {
+        /// The retry or hedging policy of a method.
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct RetryPolicy {
+            /// The maximum number of attempts, including the first one.
+            pub max_attempts: u32,
+            /// The status codes that are retried.
+            pub retryable_codes: &'static [tonic::Code],
+            /// The backoff before the first retry.
+            pub initial_backoff: std::time::Duration,
+            /// The maximum backoff.
+            pub max_backoff: std::time::Duration,
+            /// The factor the backoff grows by after each retry.
+            pub backoff_multiplier: f64,
+            /// If set, another attempt is sent whenever no response arrived
+            /// within this delay, and backoffs are not used.
+            pub hedging_delay: Option<std::time::Duration>,
+        }
+        impl RetryPolicy {
+            /// Returns whether a call failing with `code` is retried.
+            pub fn is_retryable(&self, code: tonic::Code) -> bool {
+                self.retryable_codes.contains(&code)
+            }
+            /// Returns the backoff before retry `n`, starting at 1.
+            pub fn backoff(&self, n: u32) -> std::time::Duration {
+                let exponent = i32::try_from(n.saturating_sub(1)).unwrap_or(i32::MAX);
+                let backoff =
+                    self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+                std::time::Duration::try_from_secs_f64(backoff)
+                    .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+            }
+            /// Calls `call` with `request` until an attempt succeeds, fails
+            /// with a status code that is not retryable, or all attempts
+            /// failed. Returns the result of the last attempt.
+            ///
+            /// Attempts receive a copy of the metadata and the message of
+            /// `request`, only the first one receives its extensions.
+            pub async fn call<M, R, F, Fut>(
+                &self,
+                request: tonic::Request<M>,
+                mut call: F,
+            ) -> std::result::Result<R, tonic::Status>
+            where
+                M: Clone,
+                F: FnMut(tonic::Request<M>) -> Fut,
+                Fut: std::future::Future<Output = std::result::Result<R, tonic::Status>>,
+            {
+                let (metadata, extensions, message) = request.into_parts();
+                let mut extensions = Some(extensions);
+                let mut attempt = move || {
+                    call(tonic::Request::from_parts(
+                        metadata.clone(),
+                        extensions.take().unwrap_or_default(),
+                        message.clone(),
+                    ))
+                };
+                let max_attempts = self.max_attempts.max(1);
+                let Some(hedging_delay) = self.hedging_delay else {
+                    let mut attempts = 1;
+                    loop {
+                        match attempt().await {
+                            Err(status)
+                                if attempts < max_attempts && self.is_retryable(status.code()) =>
+                            {
+                                tokio::time::sleep(self.backoff(attempts)).await;
+                                attempts += 1;
+                            }
+                            result => return result,
+                        }
+                    }
+                };
+                let mut pending = vec![Box::pin(attempt())];
+                let mut attempts = 1;
+                let mut last_error = None;
+                let mut delay = Box::pin(tokio::time::sleep(hedging_delay));
+                std::future::poll_fn(|cx| loop {
+                    let mut i = 0;
+                    while i < pending.len() {
+                        match std::future::Future::poll(pending[i].as_mut(), cx) {
+                            std::task::Poll::Ready(Err(status))
+                                if self.is_retryable(status.code()) =>
+                            {
+                                drop(pending.swap_remove(i));
+                                last_error = Some(status);
+                            }
+                            std::task::Poll::Ready(result) => {
+                                return std::task::Poll::Ready(result);
+                            }
+                            std::task::Poll::Pending => i += 1,
+                        }
+                    }
+                    // Hedge immediately after a retryable failure, otherwise
+                    // once the delay elapsed.
+                    let hedge = attempts < max_attempts
+                        && (pending.is_empty()
+                            || std::future::Future::poll(delay.as_mut(), cx).is_ready());
+                    if !hedge {
+                        if pending.is_empty() {
+                            let status = last_error.take().expect("all attempts failed");
+                            return std::task::Poll::Ready(Err(status));
+                        }
+                        return std::task::Poll::Pending;
+                    }
+                    pending.push(Box::pin(attempt()));
+                    attempts += 1;
+                    delay
+                        .as_mut()
+                        .reset(tokio::time::Instant::now() + hedging_delay);
+                })
+                .await
+            }
+        }
+        /// The retry policies of the methods, declared by their custom options.
+        pub mod retry_policies {
+            #(#constants)*
+        }
+        #methods
+    }
+}
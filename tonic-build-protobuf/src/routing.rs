@@ -0,0 +1,58 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{Service, TonicVersion};
+
+/// Generate the service name and codec constants and routing helpers of
+/// `service`, to be appended to its server module.
+///
+/// tonic-build 0.12 and newer already emit `SERVICE_NAME`, so it is only
+/// generated for tonic 0.11 to make the constant available for every version.
+pub(crate) fn generate(
+    service: &Service,
+    tonic_version: TonicVersion,
+    build_transport: bool,
+    codec_path: &str,
+) -> TokenStream {
+    let service_trait = format_ident!("{}", service.name);
+    let server = format_ident!("{}Server", service.name);
+    let service_name = service.full_name();
+    let service_path = format!("/{}", service_name);
+
+    let service_name_const = (tonic_version == TonicVersion::V0_11).then(|| {
+        quote! {
+            /// The fully qualified name of the service.
+            pub const SERVICE_NAME: &str = #service_name;
+        }
+    });
+    let routes = build_transport.then(|| {
+        let routes = if tonic_version == TonicVersion::V0_11 {
+            quote!(tonic::transport::server::Routes)
+        } else {
+            quote!(tonic::service::Routes)
+        };
+        quote! {
+            /// Returns `inner` as routes that can be mounted on an existing router.
+            ///
+            /// To serve the service at a custom prefix, convert the routes with
+            /// `into_router()` and nest them with axum's
+            /// `Router::nest_service(prefix, router)`. Clients reach it by
+            /// connecting with an origin that includes the prefix, see
+            /// `with_origin`.
+            pub fn routes<T: #service_trait>(inner: T) -> #routes {
+                #routes::new(#server::new(inner))
+            }
+        }
+    });
+
+    quote! {
+        #service_name_const
+        /// The path prefix of all methods of the service, i.e.
+        /// `/{SERVICE_NAME}`.
+        pub const SERVICE_PATH: &str = #service_path;
+        /// The path of the codec used by the service, as configured in
+        /// codegen.
+        pub const CODEC_PATH: &str = #codec_path;
+        #routes
+    }
+}
@@ -0,0 +1,276 @@
+//! Rewriting of generated clients and servers to make and serve calls with
+//! the generic helpers of `tonic-runtime-protobuf`, see
+//! [`Builder::runtime_helpers`](crate::Builder::runtime_helpers).
+//!
+//! Only the glue tonic-build emits per method is replaced. Client method
+//! bodies and server handler futures rewritten before, e.g. by hooks or
+//! tracing, are kept as they are.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::visit_mut::VisitMut;
+
+/// Replaces the calls of the generated clients in `ast` with calls of the
+/// client helpers.
+pub(crate) fn rewrite_client(ast: &mut syn::File) {
+    ClientCalls.visit_file_mut(ast);
+}
+
+/// Replaces the per-method services of the generated servers in `ast` with
+/// calls of the server helpers.
+pub(crate) fn rewrite_server(ast: &mut syn::File) {
+    ServerCalls.visit_file_mut(ast);
+}
+
+/// Returns the initializer of `stmt` if it declares the local variable
+/// `name`.
+fn local<'a>(stmt: &'a syn::Stmt, name: &str) -> Option<&'a syn::Expr> {
+    let syn::Stmt::Local(local) = stmt else {
+        return None;
+    };
+    let syn::Pat::Ident(pat) = &local.pat else {
+        return None;
+    };
+    if pat.ident != name {
+        return None;
+    }
+    Some(&local.init.as_ref()?.expr)
+}
+
+/// Returns the arguments of the first call of a function named `name` in
+/// `tokens`.
+fn call_args(tokens: impl ToTokens, name: &str) -> Option<Vec<syn::Expr>> {
+    struct Find<'a> {
+        name: &'a str,
+        args: Option<Vec<syn::Expr>>,
+    }
+    impl VisitMut for Find<'_> {
+        fn visit_expr_call_mut(&mut self, call: &mut syn::ExprCall) {
+            if self.args.is_some() {
+                return;
+            }
+            if let syn::Expr::Path(func) = &*call.func {
+                let segments: Vec<_> = func.path.segments.iter().map(|s| &s.ident).collect();
+                if segments.len() >= 2
+                    && format!(
+                        "{}::{}",
+                        segments[segments.len() - 2],
+                        segments[segments.len() - 1]
+                    ) == self.name
+                {
+                    self.args = Some(call.args.iter().cloned().collect());
+                    return;
+                }
+            }
+            syn::visit_mut::visit_expr_call_mut(self, call);
+        }
+    }
+    let mut expr: syn::Expr = syn::parse2(quote!({ #tokens })).ok()?;
+    let mut find = Find { name, args: None };
+    find.visit_expr_mut(&mut expr);
+    find.args
+}
+
+/// Returns the method of the `self.inner.{method}(req, path, codec).await`
+/// expression `stmt`, the call of a generated client method.
+fn client_call(stmt: &syn::Stmt) -> Option<String> {
+    let syn::Stmt::Expr(syn::Expr::Await(expr), None) = stmt else {
+        return None;
+    };
+    let syn::Expr::MethodCall(call) = &*expr.base else {
+        return None;
+    };
+    let receiver = call.receiver.to_token_stream().to_string();
+    let args: Vec<_> = call
+        .args
+        .iter()
+        .map(|arg| arg.to_token_stream().to_string())
+        .collect();
+    (receiver == "self . inner" && args == ["req", "path", "codec"])
+        .then(|| call.method.to_string())
+}
+
+struct ClientCalls;
+
+impl VisitMut for ClientCalls {
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        syn::visit_mut::visit_block_mut(self, block);
+        // self.inner.ready().await.map_err(..)?;
+        // let codec = ..;
+        // let path = http::uri::PathAndQuery::from_static("/a.B/C");
+        // let mut req = request.into_request();
+        // req.extensions_mut().insert(GrpcMethod::new("a.B", "C"));
+        // self.inner.unary(req, path, codec).await
+        let stmts = &block.stmts;
+        let Some(start) = stmts.len().checked_sub(6) else {
+            return;
+        };
+        let ready = stmts[start].to_token_stream().to_string();
+        let (Some(codec), Some(path), Some(request)) = (
+            local(&stmts[start + 1], "codec"),
+            local(&stmts[start + 2], "path"),
+            local(&stmts[start + 3], "req"),
+        ) else {
+            return;
+        };
+        let Some(kind) = client_call(&stmts[start + 5]) else {
+            return;
+        };
+        let (Some(path), Some(method)) = (
+            call_args(path, "PathAndQuery::from_static"),
+            call_args(&stmts[start + 4], "GrpcMethod::new"),
+        ) else {
+            return;
+        };
+        if !ready.starts_with("self . inner . ready ()") || path.len() != 1 || method.len() != 2 {
+            return;
+        }
+        let helper = syn::Ident::new(&kind, proc_macro2::Span::call_site());
+        let path = &path[0];
+        let (service, method) = (&method[0], &method[1]);
+        let call: syn::Expr = syn::parse2(quote! {
+            ::tonic_runtime_protobuf::client::#helper(
+                &mut self.inner,
+                #request,
+                #path,
+                #service,
+                #method,
+                #codec,
+            )
+            .await
+        })
+        .expect("not a valid expression");
+        block.stmts.truncate(start);
+        block.stmts.push(syn::Stmt::Expr(call, None));
+    }
+}
+
+/// Returns the server helper serving methods with the `tonic::server` trait
+/// `service`, e.g. `server_streaming` for `ServerStreamingService`.
+fn server_helper(service: &str) -> Option<&'static str> {
+    match service {
+        "UnaryService" => Some("unary"),
+        "ClientStreamingService" => Some("client_streaming"),
+        "ServerStreamingService" => Some("server_streaming"),
+        "StreamingService" => Some("streaming"),
+        _ => None,
+    }
+}
+
+/// Returns the helper call replacing the body of the match arm of a method
+/// in the `call` function of a generated server, if it has the shape
+/// tonic-build emits.
+fn server_arm(body: &syn::Block) -> Option<TokenStream> {
+    // struct UnarySvc<T: Golden>(pub Arc<T>);
+    // impl<T: Golden> tonic::server::UnaryService<Req> for UnarySvc<T> {
+    //     ..
+    //     fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+    //         let inner = Arc::clone(&self.0);
+    //         let fut = async move { .. };
+    //         Box::pin(fut)
+    //     }
+    // }
+    // ..
+    // let fut = async move {
+    //     ..
+    //     let codec = ..;
+    //     ..
+    // };
+    // Box::pin(fut)
+    let mut helper = None;
+    let mut handler = None;
+    let mut codec = None;
+    for stmt in &body.stmts {
+        if let syn::Stmt::Item(syn::Item::Impl(item)) = stmt {
+            let (_, trait_path, _) = item.trait_.as_ref()?;
+            helper = server_helper(&trait_path.segments.last()?.ident.to_string());
+            for impl_item in &item.items {
+                if let syn::ImplItem::Fn(f) = impl_item {
+                    if f.sig.ident == "call" {
+                        handler = Some(f);
+                    }
+                }
+            }
+        }
+        if let Some(syn::Expr::Async(fut)) = local(stmt, "fut") {
+            codec = fut.block.stmts.iter().find_map(|stmt| local(stmt, "codec"));
+        }
+    }
+    let helper = syn::Ident::new(helper?, proc_macro2::Span::call_site());
+    let handler = handler?;
+    let codec = codec?;
+    let syn::FnArg::Typed(request) = handler.sig.inputs.iter().nth(1)? else {
+        return None;
+    };
+    let request_ty = &request.ty;
+    let (first, rest) = handler.block.stmts.split_first()?;
+    if first.to_token_stream().to_string() != "let inner = Arc :: clone (& self . 0) ;" {
+        return None;
+    }
+    Some(quote! {
+        {
+            let service = Arc::clone(&inner.0);
+            let codec = #codec;
+            ::tonic_runtime_protobuf::server::#helper(
+                req,
+                codec,
+                config,
+                move |request: #request_ty| {
+                    let inner = Arc::clone(&service);
+                    #(#rest)*
+                },
+            )
+        }
+    })
+}
+
+struct ServerCalls;
+
+impl VisitMut for ServerCalls {
+    fn visit_impl_item_fn_mut(&mut self, f: &mut syn::ImplItemFn) {
+        if f.sig.ident != "call" {
+            return;
+        }
+        // let inner = self.inner.clone();
+        // match req.uri().path() { .. }
+        let mut rewritten = false;
+        let mut position = None;
+        for (i, stmt) in f.block.stmts.iter_mut().enumerate() {
+            let syn::Stmt::Expr(syn::Expr::Match(expr), _) = stmt else {
+                continue;
+            };
+            if expr.expr.to_token_stream().to_string() != "req . uri () . path ()" {
+                continue;
+            }
+            position = Some(i);
+            for arm in &mut expr.arms {
+                let is_method = matches!(
+                    &arm.pat,
+                    syn::Pat::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. })
+                        if path.value().starts_with('/')
+                );
+                let syn::Expr::Block(body) = &*arm.body else {
+                    continue;
+                };
+                if !is_method {
+                    continue;
+                }
+                if let Some(call) = server_arm(&body.block) {
+                    *arm.body = syn::parse2(call).expect("not a valid expression");
+                    rewritten = true;
+                }
+            }
+        }
+        if let (true, Some(position)) = (rewritten, position) {
+            let config: syn::Stmt = syn::parse_quote! {
+                let config = ::tonic_runtime_protobuf::server::Config {
+                    accept_compression_encodings: self.accept_compression_encodings,
+                    send_compression_encodings: self.send_compression_encodings,
+                    max_decoding_message_size: self.max_decoding_message_size,
+                    max_encoding_message_size: self.max_encoding_message_size,
+                };
+            };
+            f.block.stmts.insert(position, config);
+        }
+    }
+}
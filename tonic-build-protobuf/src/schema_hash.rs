@@ -0,0 +1,118 @@
+//! Hashes of the schemas of services, see
+//! [`Builder::build_schema_hash`](crate::Builder::build_schema_hash).
+
+use std::collections::BTreeSet;
+
+use proc_macro2::TokenStream;
+use protobuf::descriptor::{field_descriptor_proto::Type, ServiceDescriptorProto};
+use quote::quote;
+
+use crate::{DescriptorIndex, Service};
+
+/// Returns the hash of the schema of the service `svc` of `package`, as 16
+/// hex digits.
+///
+/// The hash covers what peers must agree on to talk to each other: the
+/// methods of the service, and the fields of every message and the values of
+/// every enum reachable from their requests and responses, in a canonical
+/// form, where methods are sorted by name, fields by number and oneofs are
+/// referred to by name. Comments, options, declaration order and unrelated
+/// types of the same files do not change it.
+pub(crate) fn compute(
+    package: &str,
+    svc: &ServiceDescriptorProto,
+    index: &DescriptorIndex,
+) -> String {
+    let mut schema = format!("service {}.{}\n", package, svc.name());
+    let mut pending = vec![];
+    let mut methods: Vec<_> = svc.method.iter().collect();
+    methods.sort_by_key(|m| m.name());
+    for method in methods {
+        schema.push_str(&format!(
+            "method {} {} {} {} {}\n",
+            method.name(),
+            method.input_type(),
+            method.output_type(),
+            method.client_streaming(),
+            method.server_streaming()
+        ));
+        pending.extend([method.input_type(), method.output_type()]);
+    }
+
+    let mut types = BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if !types.insert(name) {
+            continue;
+        }
+        if let Some(message) = index.messages.get(name) {
+            pending.extend(
+                message
+                    .field
+                    .iter()
+                    .filter(|f| {
+                        matches!(
+                            f.type_(),
+                            Type::TYPE_MESSAGE | Type::TYPE_GROUP | Type::TYPE_ENUM
+                        )
+                    })
+                    .map(|f| f.type_name()),
+            );
+        }
+    }
+    for name in types {
+        if let Some(message) = index.messages.get(name) {
+            schema.push_str(&format!("message {}\n", name));
+            let mut fields: Vec<_> = message.field.iter().collect();
+            fields.sort_by_key(|f| f.number());
+            for f in fields {
+                schema.push_str(&format!(
+                    "field {} {} {} {} {} {} {}\n",
+                    f.number(),
+                    f.name(),
+                    f.label() as i32,
+                    f.type_() as i32,
+                    f.type_name(),
+                    f.oneof_index
+                        .and_then(|i| message.oneof_decl.get(usize::try_from(i).ok()?))
+                        .map_or("-", |oneof| oneof.name()),
+                    f.proto3_optional()
+                ));
+            }
+        } else if let Some(values) = index.enums.get(name) {
+            schema.push_str(&format!("enum {}\n", name));
+            let mut values = values.clone();
+            values.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+            for (value, number) in values {
+                schema.push_str(&format!("value {} {}\n", number, value));
+            }
+        } else {
+            schema.push_str(&format!("unresolved {}\n", name));
+        }
+    }
+    format!("{:016x}", fnv1a(schema.as_bytes()))
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hashers of `std` is stable
+/// across Rust releases, so that services built by different toolchains
+/// agree on their hashes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Generate the `SCHEMA_HASH` constant of `service`, to be appended to its
+/// client and server modules.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let doc = format!(
+        " The hash of the schema of the `{}` service, which \
+         `tonic_types_protobuf::schema` compares with the hash of peers to \
+         detect skewed .proto files.",
+        service.full_name()
+    );
+    let hash = &service.schema_hash;
+    quote! {
+        #[doc = #doc]
+        pub const SCHEMA_HASH: &str = #hash;
+    }
+}
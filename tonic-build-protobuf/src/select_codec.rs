@@ -0,0 +1,51 @@
+//! Rewriting of generated servers to select their codec per call.
+
+use quote::quote;
+use syn::visit_mut::VisitMut;
+
+/// Rewrites every `let codec = Codec::default();` of the generated servers in
+/// `ast` into `Codec::select_codec(content_type)`, with the content-type of the
+/// request `req` being served.
+pub(crate) fn rewrite(ast: &mut syn::File) {
+    SelectCodec.visit_file_mut(ast);
+}
+
+struct SelectCodec;
+
+impl VisitMut for SelectCodec {
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        syn::visit_mut::visit_local_mut(self, local);
+        let syn::Pat::Ident(pat) = &local.pat else {
+            return;
+        };
+        let Some(init) = &mut local.init else {
+            return;
+        };
+        let syn::Expr::Call(call) = &*init.expr else {
+            return;
+        };
+        let syn::Expr::Path(func) = &*call.func else {
+            return;
+        };
+        let segments = &func.path.segments;
+        if pat.ident != "codec"
+            || !call.args.is_empty()
+            || segments.last().is_none_or(|s| s.ident != "default")
+        {
+            return;
+        }
+        let leading = &func.path.leading_colon;
+        let codec = segments.iter().take(segments.len() - 1);
+        *init.expr = syn::parse2(quote! {
+            {
+                use ::tonic_codec_protobuf::SelectCodec as _;
+                let content_type = req
+                    .headers()
+                    .get("content-type")
+                    .and_then(|value| value.to_str().ok());
+                #leading #(#codec)::* ::select_codec(content_type)
+            }
+        })
+        .expect("not a valid expression");
+    }
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::Service;
+
+/// Defaults applied by the generated `serve_with_defaults` helpers.
+///
+/// When configured via
+/// [`Builder::server_defaults`](crate::Builder::server_defaults),
+/// every generated server module additionally contains:
+///
+/// * `server_builder_with_defaults()`, a `tonic::transport::Server` configured
+///   with the concurrency limit and timeout,
+/// * `with_default_interceptors(inner)`, which wraps the service in its server
+///   with the configured interceptors applied in order,
+/// * `serve_with_defaults(inner, addr)`, which combines both and serves the
+///   service on `addr`.
+#[derive(Debug, Clone, Default)]
+pub struct ServerDefaults {
+    concurrency_limit: Option<usize>,
+    timeout: Option<Duration>,
+    interceptors: Vec<String>,
+}
+
+impl ServerDefaults {
+    /// Create new server defaults that apply nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the concurrency limit applied to each connection.
+    pub fn concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Set the timeout applied to each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add an interceptor by the path of a function that constructs it, e.g.
+    /// `"crate::auth::interceptor"`.
+    ///
+    /// The function takes no arguments and returns a
+    /// `tonic::service::Interceptor` that is `Clone + Send + 'static`.
+    /// Interceptors run in the order they are added.
+    pub fn interceptor(mut self, constructor: impl AsRef<str>) -> Self {
+        self.interceptors.push(constructor.as_ref().to_string());
+        self
+    }
+
+    /// Generate the helpers of `service`, to be appended to its server module.
+    pub(crate) fn generate(&self, service: &Service) -> TokenStream {
+        let service_trait = format_ident!("{}", service.name);
+        let server = format_ident!("{}Server", service.name);
+
+        let concurrency_limit = self.concurrency_limit.map(|limit| {
+            quote! { .concurrency_limit_per_connection(#limit) }
+        });
+        let timeout = self.timeout.map(|timeout| {
+            let secs = timeout.as_secs();
+            let nanos = timeout.subsec_nanos();
+            quote! { .timeout(std::time::Duration::new(#secs, #nanos)) }
+        });
+
+        let interceptor_vars: Vec<_> = (0..self.interceptors.len())
+            .map(|i| format_ident!("interceptor_{}", i))
+            .collect();
+        let constructors = self.interceptors.iter().map(|constructor| {
+            syn::parse_str::<syn::Path>(constructor)
+                .unwrap_or_else(|_| panic!("invalid interceptor constructor: {constructor}"))
+        });
+
+        quote! {
+            /// Returns a server builder configured with the defaults from codegen.
+            pub fn server_builder_with_defaults() -> tonic::transport::Server {
+                tonic::transport::Server::builder() #concurrency_limit #timeout
+            }
+            /// Wraps `inner` in a server with the interceptors from codegen.
+            #[allow(clippy::result_large_err)]
+            pub fn with_default_interceptors<T: #service_trait>(
+                inner: T,
+            ) -> InterceptedService<
+                #server<T>,
+                impl tonic::service::Interceptor + Clone + Send + 'static,
+            > {
+                #(let mut #interceptor_vars = #constructors();)*
+                #server::with_interceptor(
+                    inner,
+                    move |request: tonic::Request<()>| -> Result<tonic::Request<()>, tonic::Status> {
+                        #(let request = tonic::service::Interceptor::call(&mut #interceptor_vars, request)?;)*
+                        Ok(request)
+                    },
+                )
+            }
+            /// Serves `inner` on `addr` with the defaults from codegen.
+            pub async fn serve_with_defaults<T: #service_trait>(
+                inner: T,
+                addr: std::net::SocketAddr,
+            ) -> Result<(), tonic::transport::Error> {
+                server_builder_with_defaults()
+                    .add_service(with_default_interceptors(inner))
+                    .serve(addr)
+                    .await
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Generation of starter implementations of services, with `todo!()` bodies.
+
+use heck::ToSnakeCase;
+use quote::{format_ident, quote};
+
+use crate::{rust_protobuf_ident, Service, TonicVersion};
+
+/// Returns the path of the module of `package` in the module including the
+/// [mod file](crate::Builder::mod_file), `services_path`, e.g.
+/// `crate::services::a::b` for `a.b`.
+pub(crate) fn package_path(services_path: &str, package: &str) -> String {
+    let mut path = services_path.to_owned();
+    for segment in package.split('.').filter(|s| !s.is_empty()) {
+        path.push_str("::");
+        path.push_str(&rust_protobuf_ident(&segment.to_snake_case()));
+    }
+    path
+}
+
+/// Renders a file with a `{Service}Service` struct implementing the service
+/// trait of `service`, whose methods are `todo!()`.
+///
+/// The trait is referred to through `services_path`, the module including
+/// the [mod file](crate::Builder::mod_file), e.g. `crate::services`, and the
+/// modules of `package` in it.
+pub(crate) fn render(
+    service: &Service,
+    package: &str,
+    services_path: &str,
+    tonic_version: TonicVersion,
+) -> String {
+    let trait_path = package_path(services_path, package);
+    let server_path: syn::Path =
+        syn::parse_str(&format!("{}::{}Server", trait_path, service.name)).expect("invalid path");
+    let trait_path: syn::Path =
+        syn::parse_str(&format!("{}::{}", trait_path, service.name)).expect("invalid path");
+    let name = format_ident!("{}Service", service.name);
+    let full_name = service.full_name();
+
+    let methods = service.methods.iter().map(|method| {
+        let method_name = format_ident!("{}", method.name);
+        let (request, response) = method.request_response_types();
+        let request = if method.client_streaming {
+            quote!(tonic::Request<tonic::Streaming<#request>>)
+        } else {
+            quote!(tonic::Request<#request>)
+        };
+        if !method.server_streaming {
+            return quote! {
+                async fn #method_name(
+                    &self,
+                    _request: #request,
+                ) -> Result<tonic::Response<#response>, tonic::Status> {
+                    todo!()
+                }
+            };
+        }
+        let stream = format_ident!("{}Stream", method.route_name);
+        quote! {
+            type #stream = std::pin::Pin<
+                Box<
+                    dyn tonic::codegen::tokio_stream::Stream<
+                        Item = Result<#response, tonic::Status>,
+                    > + Send,
+                >,
+            >;
+
+            async fn #method_name(
+                &self,
+                _request: #request,
+            ) -> Result<tonic::Response<Self::#stream>, tonic::Status> {
+                todo!()
+            }
+        }
+    });
+
+    let async_trait = match tonic_version {
+        TonicVersion::V0_11 => Some(quote!(#[tonic::async_trait])),
+        #[cfg(feature = "tonic-0-12")]
+        TonicVersion::V0_12 => Some(quote!(#[tonic::async_trait])),
+        // Service traits use native async functions since tonic 0.13.
+        #[allow(unreachable_patterns)]
+        _ => None,
+    };
+    let doc = format!(" Implementation of the `{}` service.", full_name);
+    let serve_doc = format!(
+        " Serve it with `{}::new({}::default())`.",
+        quote!(#server_path).to_string().replace(' ', ""),
+        name
+    );
+    let tokens = quote! {
+        #[doc = #doc]
+        ///
+        #[doc = #serve_doc]
+        #[derive(Debug, Default)]
+        pub struct #name {}
+
+        #async_trait
+        impl #trait_path for #name {
+            #(#methods)*
+        }
+    };
+    let ast: syn::File = syn::parse2(tokens).expect("not a valid tokenstream");
+    prettyplease::unparse(&ast)
+}
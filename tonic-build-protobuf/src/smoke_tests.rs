@@ -0,0 +1,143 @@
+//! Generation of smoke tests, which serve a service answering with default
+//! messages over an in-process transport and call each method once with the
+//! generated client.
+
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::Service;
+
+/// Generate the `#[cfg(test)]` module of smoke tests of `service`, to be
+/// appended to its generated file.
+///
+/// The generated code refers to `tonic-testing-protobuf` and to `tokio` with
+/// the `macros` and `rt` features, which the crate including it must have as
+/// dev-dependencies.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let snake = service.name.to_snake_case();
+    let module = format_ident!("{}_smoke_tests", snake);
+    let client_mod = format_ident!("{}_client", snake);
+    let server_mod = format_ident!("{}_server", snake);
+    let client = format_ident!("{}Client", service.name);
+    let server = format_ident!("{}Server", service.name);
+    let service_trait = format_ident!("{}", service.name);
+
+    let handlers = service.methods.iter().map(|method| {
+        let name = format_ident!("{}", method.name);
+        let (request, response) = method.request_response_types();
+        let request = if method.client_streaming {
+            quote!(tonic::Request<tonic::Streaming<#request>>)
+        } else {
+            quote!(tonic::Request<#request>)
+        };
+        if !method.server_streaming {
+            return quote! {
+                async fn #name(
+                    &self,
+                    _request: #request,
+                ) -> Result<tonic::Response<#response>, tonic::Status> {
+                    Ok(tonic::Response::new(Default::default()))
+                }
+            };
+        }
+        let stream = format_ident!("{}Stream", method.route_name);
+        quote! {
+            type #stream = ResponseStream<#response>;
+
+            async fn #name(
+                &self,
+                _request: #request,
+            ) -> Result<tonic::Response<Self::#stream>, tonic::Status> {
+                let response = tonic::codegen::tokio_stream::once(Ok(Default::default()));
+                Ok(tonic::Response::new(Box::pin(response)))
+            }
+        }
+    });
+
+    let tests = service.methods.iter().map(|method| {
+        let name = format_ident!("{}", method.name);
+        let test = format_ident!("test_{}", method.name);
+        let (request, response) = method.request_response_types();
+        let call_request = if method.client_streaming {
+            quote!(tonic::codegen::tokio_stream::once(#request::default()))
+        } else {
+            quote!(#request::default())
+        };
+        let check = if method.server_streaming {
+            quote! {
+                let mut responses = response.into_inner();
+                let first = responses.message().await.unwrap();
+                assert_eq!(first, Some(#response::default()));
+                let end = responses.message().await.unwrap();
+                assert_eq!(end, None);
+            }
+        } else {
+            quote!(assert_eq!(response.into_inner(), #response::default());)
+        };
+        if method.validator.is_none() {
+            return quote! {
+                #[tokio::test]
+                async fn #test() {
+                    let mut client = client().await;
+                    let response = client.#name(#call_request).await.unwrap();
+                    #check
+                }
+            };
+        }
+        // Default requests may violate the validation rules.
+        quote! {
+            #[tokio::test]
+            async fn #test() {
+                let mut client = client().await;
+                match client.#name(#call_request).await {
+                    Ok(response) => {
+                        #check
+                    }
+                    Err(status) => assert_eq!(status.code(), tonic::Code::InvalidArgument),
+                }
+            }
+        }
+    });
+
+    let response_stream = service
+        .methods
+        .iter()
+        .any(|method| method.server_streaming)
+        .then(|| {
+            quote! {
+                type ResponseStream<T> = std::pin::Pin<
+                    Box<dyn tonic::codegen::tokio_stream::Stream<Item = Result<T, tonic::Status>> + Send>,
+                >;
+            }
+        });
+    let doc = format!(
+        " Smoke tests calling every method of the {} service once.",
+        service.name
+    );
+    quote! {
+        #[doc = #doc]
+        #[cfg(test)]
+        #[allow(clippy::items_after_test_module)]
+        mod #module {
+            use super::#client_mod::#client;
+            use super::#server_mod::{#service_trait, #server};
+
+            #response_stream
+
+            /// Answers every call with default messages.
+            struct Defaults;
+
+            #[tonic::async_trait]
+            impl #service_trait for Defaults {
+                #(#handlers)*
+            }
+
+            async fn client() -> #client<tonic::transport::Channel> {
+                ::tonic_testing_protobuf::duplex::client(#server::new(Defaults), #client::new).await
+            }
+
+            #(#tests)*
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Rewriting of generated clients and servers to run every call in a
+//! `tracing` span.
+//!
+//! Spans are named after the full method path, e.g.
+//! `/helloworld.Greeter/SayHello`, and carry the fields:
+//!
+//! * `rpc.system`, always `grpc`,
+//! * `rpc.service` and `rpc.method`, e.g. `helloworld.Greeter` and `SayHello`,
+//! * `rpc.kind`, one of `unary`, `client_streaming`, `server_streaming` and
+//!   `bidi_streaming`,
+//! * `rpc.grpc.status_code`, the numeric status code, recorded once the
+//!   response, or for streaming responses, the response stream, was returned.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::visit_mut::VisitMut;
+
+/// Wraps the body of every method of the generated clients in `ast` in a
+/// span.
+pub(crate) fn rewrite_client(ast: &mut syn::File) {
+    ClientSpans.visit_file_mut(ast);
+}
+
+/// Wraps the handler future of every method of the generated servers in `ast`
+/// in a span.
+pub(crate) fn rewrite_server(ast: &mut syn::File) {
+    rewrite_handlers(ast, |fut, path, kind| instrument(quote!(#fut), path, kind));
+}
+
+/// Replaces the handler future `fut` of every method of the generated servers
+/// in `ast` with the expression `wrap(fut, path, kind)` returns, e.g.
+/// `wrap(async move { .. }, "/helloworld.Greeter/SayHello", "unary")`.
+pub(crate) fn rewrite_handlers(
+    ast: &mut syn::File,
+    wrap: impl FnMut(&syn::Expr, &str, &'static str) -> TokenStream,
+) {
+    ServerHandlers {
+        path: None,
+        kind: None,
+        wrap,
+    }
+    .visit_file_mut(ast);
+}
+
+/// Returns the `rpc.kind` of calls made with the `tonic::client::Grpc`
+/// method `call`, e.g. `server_streaming`.
+fn client_kind(call: &str) -> Option<&'static str> {
+    match call {
+        "unary" => Some("unary"),
+        "client_streaming" => Some("client_streaming"),
+        "server_streaming" => Some("server_streaming"),
+        "streaming" => Some("bidi_streaming"),
+        _ => None,
+    }
+}
+
+/// Returns the `rpc.kind` of methods served by the `tonic::server` trait
+/// `service`, e.g. `ServerStreamingService`.
+fn server_kind(service: &str) -> Option<&'static str> {
+    match service {
+        "UnaryService" => Some("unary"),
+        "ClientStreamingService" => Some("client_streaming"),
+        "ServerStreamingService" => Some("server_streaming"),
+        "StreamingService" => Some("bidi_streaming"),
+        _ => None,
+    }
+}
+
+/// Returns a future that awaits `fut` in the span of the call of `path`, and
+/// records the status code of its result.
+fn instrument(fut: TokenStream, path: &str, kind: &str) -> TokenStream {
+    let name = path.trim_start_matches('/');
+    let (service, method) = name.split_once('/').unwrap_or((name, ""));
+    quote! {
+        {
+            let span = ::tracing::info_span!(
+                #path,
+                rpc.system = "grpc",
+                rpc.service = #service,
+                rpc.method = #method,
+                rpc.kind = #kind,
+                rpc.grpc.status_code = ::tracing::field::Empty,
+            );
+            let record = span.clone();
+            ::tracing::Instrument::instrument(
+                async move {
+                    let result = #fut.await;
+                    let code = match &result {
+                        Ok(_) => tonic::Code::Ok,
+                        Err(status) => status.code(),
+                    };
+                    record.record("rpc.grpc.status_code", code as i32);
+                    result
+                },
+                span,
+            )
+        }
+    }
+}
+
+struct ClientSpans;
+
+impl VisitMut for ClientSpans {
+    fn visit_impl_item_fn_mut(&mut self, f: &mut syn::ImplItemFn) {
+        if f.sig.asyncness.is_none() {
+            return;
+        }
+        let mut call = ClientCall::default();
+        call.visit_block_mut(&mut f.block);
+        let (Some(path), Some(kind)) = (call.path, call.kind) else {
+            return;
+        };
+        let block = &f.block;
+        let fut = instrument(quote!(async move #block), &path, kind);
+        f.block = syn::parse2(quote!({
+            let fut = #fut;
+            fut.await
+        }))
+        .expect("not a valid block");
+    }
+}
+
+/// Returns the path of the call the generated client method with the body
+/// `block` makes, if any.
+pub(crate) fn client_path(block: &mut syn::Block) -> Option<String> {
+    let mut call = ClientCall::default();
+    call.visit_block_mut(block);
+    call.path.zip(call.kind).map(|(path, _)| path)
+}
+
+/// Finds the path and kind of the call a generated client method makes.
+#[derive(Default)]
+struct ClientCall {
+    path: Option<String>,
+    kind: Option<&'static str>,
+}
+
+impl VisitMut for ClientCall {
+    fn visit_expr_call_mut(&mut self, call: &mut syn::ExprCall) {
+        syn::visit_mut::visit_expr_call_mut(self, call);
+        let syn::Expr::Path(func) = &*call.func else {
+            return;
+        };
+        if func
+            .path
+            .segments
+            .last()
+            .is_none_or(|s| s.ident != "from_static")
+        {
+            return;
+        }
+        if let Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(path),
+            ..
+        })) = call.args.first()
+        {
+            self.path = Some(path.value()).filter(|path| path.starts_with('/'));
+        }
+    }
+
+    fn visit_expr_method_call_mut(&mut self, call: &mut syn::ExprMethodCall) {
+        syn::visit_mut::visit_expr_method_call_mut(self, call);
+        // `self.inner.unary(req, path, codec)`
+        let syn::Expr::Field(receiver) = &*call.receiver else {
+            return;
+        };
+        if matches!(&receiver.member, syn::Member::Named(name) if name == "inner")
+            && call.args.len() == 3
+        {
+            self.kind = client_kind(&call.method.to_string()).or(self.kind);
+        }
+    }
+}
+
+struct ServerHandlers<F> {
+    /// The path of the method whose match arm is visited.
+    path: Option<String>,
+    /// The kind of the method whose `tonic::server` trait impl is visited.
+    kind: Option<&'static str>,
+    wrap: F,
+}
+
+impl<F> VisitMut for ServerHandlers<F>
+where
+    F: FnMut(&syn::Expr, &str, &'static str) -> TokenStream,
+{
+    fn visit_arm_mut(&mut self, arm: &mut syn::Arm) {
+        let path = match &arm.pat {
+            syn::Pat::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) => Some(path.value()).filter(|path| path.starts_with('/')),
+            _ => None,
+        };
+        let outer = std::mem::replace(&mut self.path, path);
+        syn::visit_mut::visit_arm_mut(self, arm);
+        self.path = outer;
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut syn::ItemImpl) {
+        let kind = item
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .and_then(|s| server_kind(&s.ident.to_string()));
+        let outer = std::mem::replace(&mut self.kind, kind);
+        syn::visit_mut::visit_item_impl_mut(self, item);
+        self.kind = outer;
+    }
+
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        syn::visit_mut::visit_local_mut(self, local);
+        let (Some(path), Some(kind)) = (&self.path, self.kind) else {
+            return;
+        };
+        // `let fut = async move { <T as Service>::method(&inner, request).await };`
+        let syn::Pat::Ident(pat) = &local.pat else {
+            return;
+        };
+        let Some(init) = &mut local.init else {
+            return;
+        };
+        if pat.ident != "fut" || !matches!(&*init.expr, syn::Expr::Async(_)) {
+            return;
+        }
+        *init.expr =
+            syn::parse2((self.wrap)(&init.expr, path, kind)).expect("not a valid expression");
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::TonicVersion;
+
+/// The package of a standalone crate written by
+/// [`Builder::write_crate`](crate::Builder::write_crate), e.g. a versioned
+/// crate of stubs published from a repository of .proto files.
+#[derive(Debug, Clone, Default)]
+pub struct StandaloneCrate {
+    name: String,
+    version: String,
+    description: Option<String>,
+    license: Option<String>,
+    repository: Option<String>,
+}
+
+impl StandaloneCrate {
+    /// Create a crate named `name`, e.g. `"my-protos"`, at `version`, e.g.
+    /// `"1.2.0"`.
+    pub fn new(name: impl AsRef<str>, version: impl AsRef<str>) -> Self {
+        Self {
+            name: name.as_ref().to_owned(),
+            version: version.as_ref().to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the description of the crate, which is also its crate-level
+    /// documentation.
+    pub fn description(mut self, description: impl AsRef<str>) -> Self {
+        self.description = Some(description.as_ref().to_owned());
+        self
+    }
+
+    /// Set the SPDX license expression of the crate, e.g. `"MIT"`.
+    pub fn license(mut self, license: impl AsRef<str>) -> Self {
+        self.license = Some(license.as_ref().to_owned());
+        self
+    }
+
+    /// Set the URL of the repository of the crate, e.g. of the .proto files.
+    pub fn repository(mut self, repository: impl AsRef<str>) -> Self {
+        self.repository = Some(repository.as_ref().to_owned());
+        self
+    }
+
+    /// Renders the `Cargo.toml` of the crate, with the dependencies that
+    /// `code`, the generated services, refers to.
+    pub(crate) fn render_manifest(&self, tonic_version: TonicVersion, code: &str) -> String {
+        let mut manifest = String::from("# Generated by tonic-build-protobuf, do not edit.\n\n");
+        manifest.push_str("[package]\n");
+        manifest.push_str(&format!("name = {}\n", toml_string(&self.name)));
+        manifest.push_str(&format!("version = {}\n", toml_string(&self.version)));
+        manifest.push_str("edition = \"2021\"\n");
+        let optional = [
+            ("description", &self.description),
+            ("license", &self.license),
+            ("repository", &self.repository),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                manifest.push_str(&format!("{} = {}\n", key, toml_string(value)));
+            }
+        }
+        // Comments of the .proto files become doc comments, whose indented
+        // lines are not Rust code.
+        manifest.push_str("\n[lib]\ndoctest = false\n");
+
+        let (dependencies, dev_dependencies) = dependencies(tonic_version, code);
+        for (table, dependencies) in [
+            ("dependencies", dependencies),
+            ("dev-dependencies", dev_dependencies),
+        ] {
+            if dependencies.is_empty() {
+                continue;
+            }
+            manifest.push_str(&format!("\n[{}]\n", table));
+            for (name, (version, features)) in dependencies {
+                let version = toml_string(&version);
+                if features.is_empty() {
+                    manifest.push_str(&format!("{} = {}\n", name, version));
+                } else {
+                    let features: Vec<_> = features.iter().map(|f| toml_string(f)).collect();
+                    manifest.push_str(&format!(
+                        "{} = {{ version = {}, features = [{}] }}\n",
+                        name,
+                        version,
+                        features.join(", ")
+                    ));
+                }
+            }
+        }
+        manifest
+    }
+
+    /// Renders the `lib.rs` of the crate, which declares the modules of the
+    /// messages and of the services.
+    pub(crate) fn render_lib(&self) -> String {
+        let mut lib = String::new();
+        if let Some(description) = &self.description {
+            for line in description.lines() {
+                lib.push_str(format!("//! {}", line).trim_end());
+                lib.push('\n');
+            }
+            lib.push_str("//!\n");
+        }
+        lib.push_str(
+            "//! Generated by tonic-build-protobuf, do not edit.
+
+/// The messages and enums of the .proto files, a module per file.
+pub mod protos;
+/// The clients and servers of the services, in modules mirroring their
+/// packages.
+pub mod services;
+",
+        );
+        lib
+    }
+}
+
+/// The version and features of a dependency.
+type Dependency = (String, BTreeSet<&'static str>);
+
+/// Returns the dependencies and dev-dependencies of a crate of the generated
+/// services `code`, by the crates it refers to.
+fn dependencies(
+    tonic_version: TonicVersion,
+    code: &str,
+) -> (
+    BTreeMap<&'static str, Dependency>,
+    BTreeMap<&'static str, Dependency>,
+) {
+    // The crates of this repository are released together.
+    let runtime_version = concat!(
+        env!("CARGO_PKG_VERSION_MAJOR"),
+        ".",
+        env!("CARGO_PKG_VERSION_MINOR")
+    );
+    let tonic = tonic_version.crate_version();
+
+    let mut dependencies = BTreeMap::new();
+    let mut add = |name, version: &str, features: &[&'static str]| {
+        let (_, existing): &mut Dependency = dependencies
+            .entry(name)
+            .or_insert_with(|| (version.to_owned(), BTreeSet::new()));
+        existing.extend(features);
+    };
+    add("protobuf", &format!("={}", protobuf::VERSION), &[]);
+    add("tonic", tonic, &[]);
+
+    let crates = [
+        (
+            "tonic_codec_protobuf::",
+            "tonic-codec-protobuf",
+            runtime_version,
+        ),
+        (
+            "tonic_types_protobuf::",
+            "tonic-types-protobuf",
+            runtime_version,
+        ),
+        (
+            "tonic_runtime_protobuf::",
+            "tonic-runtime-protobuf",
+            runtime_version,
+        ),
+        (
+            "tonic_health_protobuf::",
+            "tonic-health-protobuf",
+            runtime_version,
+        ),
+        ("tonic_web::", "tonic-web", tonic),
+        ("tower_layer::", "tower-layer", "0.3"),
+        ("metrics::", "metrics", "0.24"),
+        ("tracing::", "tracing", "0.1"),
+        ("hyper_util::", "hyper-util", "0.1"),
+    ];
+    for (path, name, version) in crates {
+        if refers_to(code, path) {
+            add(name, version, &[]);
+        }
+    }
+    if refers_to(code, "tonic_codec_protobuf::") {
+        let codecs = [
+            ("ProtobufCodecV2", "protobuf-v2"),
+            ("EncryptedCodec", "encryption"),
+            ("OffloadCodec", "offload"),
+        ];
+        for (codec, feature) in codecs {
+            if refers_to(code, &format!("tonic_codec_protobuf::{}", codec)) {
+                add("tonic-codec-protobuf", runtime_version, &[feature]);
+            }
+        }
+        if let Some(feature) = tonic_version.feature() {
+            add("tonic-codec-protobuf", runtime_version, &[feature]);
+        }
+    }
+    let runtime = [
+        ("tokio::runtime::", "rt-multi-thread"),
+        ("tokio::time::", "time"),
+        ("tokio::net::", "net"),
+    ];
+    for (path, feature) in runtime {
+        if refers_to(code, path) {
+            add("tokio", "1", &[feature]);
+        }
+    }
+
+    // Smoke tests run on the runtime of `#[tokio::test]`.
+    let mut dev_dependencies = BTreeMap::new();
+    if code.contains("#[tokio::test]") {
+        dev_dependencies.insert("tokio", ("1".to_owned(), BTreeSet::from(["macros", "rt"])));
+    }
+    if refers_to(code, "tonic_testing_protobuf::") {
+        dev_dependencies.insert(
+            "tonic-testing-protobuf",
+            (runtime_version.to_owned(), BTreeSet::new()),
+        );
+    }
+    (dependencies, dev_dependencies)
+}
+
+/// Returns whether `code` refers to `path`, e.g. `"tokio::time::"`, as an
+/// absolute path rather than as a module of another path, e.g. of a message
+/// type in a package named `tokio.time`.
+fn refers_to(code: &str, path: &str) -> bool {
+    code.match_indices(path).any(|(i, _)| {
+        let before = &code[..i];
+        let before = before.strip_suffix("::").unwrap_or(before);
+        !before.ends_with(|c: char| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Quotes `value` as a TOML basic string.
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
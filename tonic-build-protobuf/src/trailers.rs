@@ -0,0 +1,181 @@
+//! Generation of typed accessors for binary trailers.
+//!
+//! The trailers of a method are declared with a repeated, message typed
+//! extension of `google.protobuf.MethodOptions`, e.g.
+//!
+//! ```proto
+//! message Trailer {
+//!   // The metadata key, which must end with "-bin".
+//!   string key = 1;
+//!   // The fully qualified name of the message carried in the trailer.
+//!   string type = 2;
+//! }
+//!
+//! extend google.protobuf.MethodOptions {
+//!   repeated Trailer trailers = 50002;
+//! }
+//! ```
+
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{options::OptionValue, DescriptorIndex, Service};
+
+/// A binary trailer carrying a protobuf message.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Trailer {
+    /// The metadata key, e.g. `server-stats-bin`.
+    key: String,
+    /// The full path of the Rust type of the message.
+    pub(crate) rust_type: String,
+}
+
+/// Decodes the trailers declared by `values` of the trailer option.
+///
+/// Invalid trailers are reported to `warnings`, with `context` describing
+/// where they are declared.
+pub(crate) fn decode(
+    values: &[OptionValue],
+    index: &DescriptorIndex,
+    proto_path: &str,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<Trailer> {
+    let mut trailers = vec![];
+    for value in values {
+        let key = value
+            .field("key")
+            .and_then(OptionValue::as_str)
+            .unwrap_or_default();
+        if !key.ends_with("-bin") || key.len() == "-bin".len() {
+            warnings.push(format!(
+                "{}: trailer key {:?} does not end with \"-bin\" and is ignored",
+                context, key
+            ));
+            continue;
+        }
+        let ty = value
+            .field("type")
+            .and_then(OptionValue::as_str)
+            .unwrap_or_default();
+        let Some(rust_type) =
+            index.rust_type(proto_path, &format!(".{}", ty.trim_start_matches('.')))
+        else {
+            warnings.push(format!(
+                "{}: can not resolve type {:?} of trailer {}, trailer is ignored",
+                context, ty, key
+            ));
+            continue;
+        };
+        trailers.push(Trailer {
+            key: key.to_ascii_lowercase(),
+            rust_type,
+        });
+    }
+    trailers
+}
+
+/// Merges `decoded` into the trailers of a service, methods may share the
+/// same trailer.
+pub(crate) fn merge(
+    trailers: &mut Vec<Trailer>,
+    decoded: Vec<Trailer>,
+    context: &str,
+    warnings: &mut Vec<String>,
+) {
+    for trailer in decoded {
+        match trailers.iter().find(|t| t.key == trailer.key) {
+            Some(existing) if *existing == trailer => (),
+            Some(_) => warnings.push(format!(
+                "{}: trailer {} is declared with another type by another method and is ignored",
+                context, trailer.key
+            )),
+            None => trailers.push(trailer),
+        }
+    }
+}
+
+/// Generates the `{Service}Trailers` trait of `service`, to be appended to its
+/// client and server modules.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    if service.trailers.is_empty() {
+        return TokenStream::new();
+    }
+    let ext = format_ident!("{}Trailers", service.name);
+    let doc = format!(
+        " Typed access to the binary trailers of the `{}` service.",
+        service.name
+    );
+
+    let mut methods = vec![];
+    for trailer in &service.trailers {
+        let name = trailer.key.trim_end_matches("-bin").to_snake_case();
+        let getter = format_ident!("{}", name);
+        let setter = format_ident!("set_{}", name);
+        let key = &trailer.key;
+        let ty: syn::Path = syn::parse_str(&trailer.rust_type).unwrap();
+        let get_doc = format!(" Parses the `{}` trailer, if present.", key);
+        let set_doc = format!(" Encodes `value` into the `{}` trailer.", key);
+        methods.push(quote! {
+            #[doc = #get_doc]
+            fn #getter(&self) -> Option<Result<#ty, tonic::Status>> {
+                let value = self.trailer_metadata().get_bin(#key)?;
+                Some(
+                    value
+                        .to_bytes()
+                        .map_err(|e| tonic::Status::internal(format!("invalid {}: {}", #key, e)))
+                        .and_then(|bytes| {
+                            ::protobuf::Message::parse_from_bytes(&bytes).map_err(|e| {
+                                tonic::Status::internal(format!("invalid {}: {}", #key, e))
+                            })
+                        }),
+                )
+            }
+            #[doc = #set_doc]
+            fn #setter(&mut self, value: &#ty) {
+                let bytes = ::protobuf::Message::write_to_bytes(value).unwrap_or_default();
+                self.trailer_metadata_mut().insert_bin(
+                    #key,
+                    tonic::metadata::MetadataValue::from_bytes(&bytes),
+                );
+            }
+        });
+    }
+
+    quote! {
+        #[doc = #doc]
+        #[allow(clippy::result_large_err)]
+        pub trait #ext {
+            /// The metadata that carries the trailers.
+            fn trailer_metadata(&self) -> &tonic::metadata::MetadataMap;
+            /// The mutable metadata that carries the trailers.
+            fn trailer_metadata_mut(&mut self) -> &mut tonic::metadata::MetadataMap;
+            #(#methods)*
+        }
+        impl #ext for tonic::metadata::MetadataMap {
+            fn trailer_metadata(&self) -> &tonic::metadata::MetadataMap {
+                self
+            }
+            fn trailer_metadata_mut(&mut self) -> &mut tonic::metadata::MetadataMap {
+                self
+            }
+        }
+        impl<T> #ext for tonic::Response<T> {
+            fn trailer_metadata(&self) -> &tonic::metadata::MetadataMap {
+                self.metadata()
+            }
+            fn trailer_metadata_mut(&mut self) -> &mut tonic::metadata::MetadataMap {
+                self.metadata_mut()
+            }
+        }
+        impl #ext for tonic::Status {
+            fn trailer_metadata(&self) -> &tonic::metadata::MetadataMap {
+                self.metadata()
+            }
+            fn trailer_metadata_mut(&mut self) -> &mut tonic::metadata::MetadataMap {
+                self.metadata_mut()
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Generation of constructors that connect clients over Unix domain sockets.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{Service, TonicVersion};
+
+/// Generate the `connect_uds` constructor of the client of `service`, and the
+/// connector it uses, to be appended to its client module.
+///
+/// The generated code refers to the `tokio` crate with the `net` feature, and
+/// to `hyper-util` for tonic 0.12 and newer, which the crate including it must
+/// depend on.
+pub(crate) fn generate(service: &Service, tonic_version: TonicVersion) -> TokenStream {
+    let client = format_ident!("{}Client", service.name);
+    let (stream, wrap) = if tonic_version == TonicVersion::V0_11 {
+        (quote!(tokio::net::UnixStream), quote!(stream))
+    } else {
+        (
+            quote!(::hyper_util::rt::TokioIo<tokio::net::UnixStream>),
+            quote!(::hyper_util::rt::TokioIo::new(stream)),
+        )
+    };
+
+    quote! {
+        #[cfg(unix)]
+        impl #client<tonic::transport::Channel> {
+            /// Attempt to create a new client by connecting to the Unix domain
+            /// socket at `path`.
+            pub async fn connect_uds<P>(path: P) -> Result<Self, tonic::transport::Error>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                // The connector ignores the URI, which only sets the
+                // `:authority` of requests.
+                let channel = tonic::transport::Endpoint::from_static("http://localhost")
+                    .connect_with_connector(UdsConnector::new(path))
+                    .await?;
+                Ok(Self::new(channel))
+            }
+        }
+        /// A connector that opens a Unix domain socket for every connection of
+        /// a channel, e.g. to customize the endpoint with
+        /// `Endpoint::connect_with_connector`.
+        #[cfg(unix)]
+        #[derive(Debug, Clone)]
+        pub struct UdsConnector {
+            path: std::path::PathBuf,
+        }
+        #[cfg(unix)]
+        impl UdsConnector {
+            /// Create a connector of the socket at `path`.
+            pub fn new(path: impl AsRef<std::path::Path>) -> Self {
+                Self {
+                    path: path.as_ref().to_path_buf(),
+                }
+            }
+        }
+        #[cfg(unix)]
+        impl Service<http::Uri> for UdsConnector {
+            type Response = #stream;
+            type Error = std::io::Error;
+            type Future = BoxFuture<Self::Response, Self::Error>;
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+            fn call(&mut self, _uri: http::Uri) -> Self::Future {
+                let path = self.path.clone();
+                Box::pin(async move {
+                    let stream = tokio::net::UnixStream::connect(path).await?;
+                    Ok(#wrap)
+                })
+            }
+        }
+    }
+}
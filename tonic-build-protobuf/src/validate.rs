@@ -0,0 +1,1221 @@
+//! Generation of request validation from protoc-gen-validate rules.
+//!
+//! Fields declare their rules with the `(validate.rules)` option of
+//! [protoc-gen-validate](https://github.com/bufbuild/protoc-gen-validate),
+//! e.g.
+//!
+//! ```proto
+//! import "validate/validate.proto";
+//!
+//! message CreateUserRequest {
+//!   string name = 1 [(validate.rules).string = {min_len: 1, max_len: 64}];
+//!   uint32 age = 2 [(validate.rules).uint32.lte = 150];
+//! }
+//! ```
+//!
+//! Server modules then get a `validate_{message}` function for every request
+//! message with rules, which also checks the nested messages, and handlers of
+//! unary and server streaming methods call it before the service
+//! implementation. Requests that violate rules fail with `INVALID_ARGUMENT`,
+//! carrying a `BadRequest` with a field violation per violated rule.
+//!
+//! Rules of the well-known types `Any`, `Duration` and `Timestamp`, the
+//! string formats `email`, `hostname`, `uri`, `uri_ref`, `address` and
+//! `well_known_regex`, `pattern` of bytes and `unique` of messages are not
+//! supported, and reported as warnings, as are string patterns that the
+//! `regex` crate can not compile, e.g. with the `\Q...\E` quoting of RE2.
+
+use std::collections::{BTreeMap, HashMap};
+
+use heck::ToSnakeCase;
+use proc_macro2::{Literal, TokenStream};
+use protobuf::descriptor::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto,
+};
+use quote::{format_ident, quote, ToTokens};
+
+use crate::{
+    options::{self, OptionValue},
+    rust_protobuf_capitalize, rust_protobuf_ident, spans, DescriptorIndex,
+};
+
+/// The field option holding the rules of a field.
+const RULES: &str = "validate.rules";
+/// The message option disabling validation of a message.
+const DISABLED: &str = "validate.disabled";
+/// The message option skipping generation of validation of a message.
+const IGNORED: &str = "validate.ignored";
+/// The oneof option requiring one of its fields to be set.
+const REQUIRED: &str = "validate.required";
+
+/// The validation functions of the request messages of a service.
+#[derive(Debug, Default)]
+pub(crate) struct Validators {
+    /// The generated functions, by message type.
+    functions: BTreeMap<String, TokenStream>,
+    /// Whether the decoded message types, fully qualified names with a
+    /// leading dot, need validation.
+    needed: HashMap<String, bool>,
+}
+
+impl Validators {
+    /// Returns the name of the function validating the message `ty`, a fully
+    /// qualified name with a leading dot, or `None` if it has no rules.
+    ///
+    /// The functions of `ty` and of the messages nested in it are generated
+    /// once. Rules that can not be checked are reported to `warnings`.
+    pub(crate) fn add(
+        &mut self,
+        ty: &str,
+        index: &DescriptorIndex,
+        proto_path: &str,
+        warnings: &mut Vec<String>,
+    ) -> Option<String> {
+        if let Some(needed) = self.needed.get(ty) {
+            return needed.then(|| function_name(ty));
+        }
+
+        // Decode the messages reachable from `ty` that were not decoded yet.
+        let mut messages = BTreeMap::new();
+        let mut pending = vec![ty.to_owned()];
+        while let Some(name) = pending.pop() {
+            if self.needed.contains_key(&name) || messages.contains_key(&name) {
+                continue;
+            }
+            let Some(message) = index.messages.get(&name) else {
+                continue;
+            };
+            let rules = MessageRules::decode(&name, message, index, warnings);
+            pending.extend(rules.nested(index).map(str::to_owned));
+            messages.insert(name, rules);
+        }
+
+        // Messages need validation if they have checks of their own, or
+        // contain messages that need validation.
+        let mut needed: HashMap<String, bool> = messages
+            .iter()
+            .map(|(name, rules)| {
+                let mut generator = Generator {
+                    index,
+                    proto_path,
+                    needed: &|_| false,
+                    warnings,
+                };
+                let checks = generator.message_checks(name, rules);
+                (name.clone(), !rules.disabled && !checks.is_empty())
+            })
+            .collect();
+        loop {
+            let mut changed = false;
+            for (name, rules) in &messages {
+                if needed[name] || rules.disabled {
+                    continue;
+                }
+                let nested_needed = rules
+                    .validated_nested(index)
+                    .any(|nested| self.needed.get(nested).or(needed.get(nested)) == Some(&true));
+                if nested_needed {
+                    needed.insert(name.clone(), true);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let is_needed = |name: &str| {
+            self.needed
+                .get(name)
+                .or(needed.get(name))
+                .copied()
+                .unwrap_or(false)
+        };
+        let mut functions = vec![];
+        for (name, rules) in &messages {
+            if !needed[name] {
+                continue;
+            }
+            // Rules were already reported while decoding.
+            let mut generator = Generator {
+                index,
+                proto_path,
+                needed: &is_needed,
+                warnings: &mut vec![],
+            };
+            let checks = generator.message_checks(name, rules);
+            let Some(rust_type) = index.rust_type(proto_path, name) else {
+                continue;
+            };
+            let rust_type: syn::Path = syn::parse_str(&rust_type).expect("not a valid path");
+            let function = format_ident!("{}", function_name(name));
+            let doc = format!(
+                " Checks a `{}` against its validation rules, adding a field violation to \
+                 `details` for every violated rule, with the field path prefixed by `prefix`.",
+                name.trim_start_matches('.')
+            );
+            functions.push((
+                name.clone(),
+                quote! {
+                    #[doc = #doc]
+                    pub fn #function(
+                        message: &#rust_type,
+                        prefix: &str,
+                        details: &mut ::tonic_types_protobuf::ErrorDetails,
+                    ) {
+                        #(#checks)*
+                    }
+                },
+            ));
+        }
+        let function = is_needed(ty).then(|| function_name(ty));
+        self.needed.extend(needed);
+        self.functions.extend(functions);
+        function
+    }
+}
+
+impl Validators {
+    /// Returns the validation functions, and the helper turning field
+    /// violations into a status.
+    pub(crate) fn generate(&self) -> TokenStream {
+        if self.functions.is_empty() {
+            return TokenStream::new();
+        }
+        let functions = self.functions.values();
+        quote! {
+            #(#functions)*
+
+            /// Returns an `INVALID_ARGUMENT` status carrying the field
+            /// violations in `details`, if there are any.
+            pub fn validation_status(
+                details: ::tonic_types_protobuf::ErrorDetails,
+            ) -> Option<tonic::Status> {
+                let violation = details.bad_request()?.field_violations.first()?;
+                let message = format!("invalid {}: {}", violation.field, violation.description);
+                Some(<tonic::Status as ::tonic_types_protobuf::StatusExt>::with_error_details(
+                    tonic::Code::InvalidArgument,
+                    message,
+                    details,
+                ))
+            }
+        }
+    }
+}
+
+/// Validates the requests of the handlers of the generated servers in `ast`
+/// before calling the service implementation, `validators` holding the
+/// validation function of every method path with rules.
+pub(crate) fn rewrite_server(ast: &mut syn::File, validators: &HashMap<String, String>) {
+    spans::rewrite_handlers(ast, |fut, path, _| match validators.get(path) {
+        Some(function) => validate(fut, function),
+        None => fut.to_token_stream(),
+    });
+}
+
+/// Returns a future that validates the request with `function` before
+/// awaiting `fut`.
+fn validate(fut: &syn::Expr, function: &str) -> TokenStream {
+    let function = format_ident!("{}", function);
+    quote! {
+        async move {
+            let mut details = ::tonic_types_protobuf::ErrorDetails::new();
+            #function(request.get_ref(), "", &mut details);
+            if let Some(status) = validation_status(details) {
+                return Err(status);
+            }
+            #fut.await
+        }
+    }
+}
+
+/// Returns the name of the function validating the message `ty`, e.g.
+/// `validate_helloworld_hello_request`.
+fn function_name(ty: &str) -> String {
+    format!(
+        "validate_{}",
+        ty.trim_start_matches('.').replace('.', "_").to_snake_case()
+    )
+}
+
+/// The decoded rules of a message.
+struct MessageRules {
+    /// Whether validation of the message is disabled or ignored.
+    disabled: bool,
+    /// The fields, with their rules.
+    fields: Vec<(FieldDescriptorProto, Option<OptionValue>)>,
+    /// The names of the oneofs that must have a field set.
+    required_oneofs: Vec<String>,
+    /// The descriptor of the message.
+    message: DescriptorProto,
+}
+
+impl MessageRules {
+    fn decode(
+        name: &str,
+        message: &DescriptorProto,
+        index: &DescriptorIndex,
+        warnings: &mut Vec<String>,
+    ) -> Self {
+        let context = format!("message {}", name.trim_start_matches('.'));
+        let message_options = options::decode(
+            &message.options,
+            &index.message_extensions,
+            index,
+            &context,
+            warnings,
+        );
+        let disabled = message_options.iter().any(|option| {
+            (option.full_name == DISABLED || option.full_name == IGNORED)
+                && option.value() == Some(&OptionValue::Bool(true))
+        });
+        let fields = message
+            .field
+            .iter()
+            .map(|field| {
+                let context = format!("{}: field {}", context, field.name());
+                let rules = options::decode(
+                    &field.options,
+                    &index.field_extensions,
+                    index,
+                    &context,
+                    warnings,
+                )
+                .into_iter()
+                .find(|option| option.full_name == RULES)
+                .and_then(|option| option.value().cloned());
+                (field.clone(), rules)
+            })
+            .collect();
+        let required_oneofs = message
+            .oneof_decl
+            .iter()
+            .filter(|oneof| {
+                let context = format!("{}: oneof {}", context, oneof.name());
+                options::decode(
+                    &oneof.options,
+                    &index.oneof_extensions,
+                    index,
+                    &context,
+                    warnings,
+                )
+                .iter()
+                .any(|option| {
+                    option.full_name == REQUIRED && option.value() == Some(&OptionValue::Bool(true))
+                })
+            })
+            .map(|oneof| oneof.name().to_owned())
+            .collect();
+        MessageRules {
+            disabled,
+            fields,
+            required_oneofs,
+            message: message.clone(),
+        }
+    }
+
+    /// Returns the types of the messages held by fields, map values
+    /// included.
+    fn nested<'a>(&'a self, index: &'a DescriptorIndex) -> impl Iterator<Item = &'a str> + 'a {
+        self.fields
+            .iter()
+            .filter_map(|(field, _)| element_field(index, field))
+            .filter(|field| field.type_() == Type::TYPE_MESSAGE)
+            .map(|field| field.type_name())
+    }
+
+    /// Returns the types of the nested messages that are validated, unless
+    /// their fields skip them.
+    fn validated_nested<'a>(
+        &'a self,
+        index: &'a DescriptorIndex,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.fields
+            .iter()
+            .filter(|(_, rules)| !skips_nested(rules.as_ref()))
+            .filter_map(|(field, _)| element_field(index, field))
+            .filter(|field| field.type_() == Type::TYPE_MESSAGE)
+            .map(|field| field.type_name())
+    }
+}
+
+/// Returns the map entry message of `field`, if it is a map.
+fn map_entry<'a>(
+    index: &'a DescriptorIndex,
+    field: &FieldDescriptorProto,
+) -> Option<&'a DescriptorProto> {
+    if field.label() != Label::LABEL_REPEATED || field.type_() != Type::TYPE_MESSAGE {
+        return None;
+    }
+    index
+        .messages
+        .get(field.type_name())
+        .filter(|entry| entry.options.map_entry())
+}
+
+/// Returns the field describing the elements of `field`: the value field of
+/// maps, and the field itself otherwise.
+fn element_field<'a>(
+    index: &'a DescriptorIndex,
+    field: &'a FieldDescriptorProto,
+) -> Option<&'a FieldDescriptorProto> {
+    match map_entry(index, field) {
+        Some(entry) => entry.field.iter().find(|f| f.number() == 2),
+        None => Some(field),
+    }
+}
+
+/// Returns whether `rules` of a field skip the validation of the messages it
+/// holds.
+fn skips_nested(rules: Option<&OptionValue>) -> bool {
+    let Some(rules) = rules else {
+        return false;
+    };
+    let element = rules
+        .field("repeated")
+        .and_then(|r| r.field("items"))
+        .or_else(|| rules.field("map").and_then(|r| r.field("values")))
+        .unwrap_or(rules);
+    element.field("message").and_then(|r| r.field("skip")) == Some(&OptionValue::Bool(true))
+}
+
+/// The path of a field in field violations, e.g. `items[0].name`, as the
+/// format string and arguments of `format!`.
+#[derive(Clone)]
+struct FieldPath {
+    format: String,
+    args: Vec<TokenStream>,
+}
+
+impl FieldPath {
+    /// The path of the field `name` of the validated message.
+    fn field(name: &str) -> Self {
+        FieldPath {
+            format: format!("{{}}{}", name),
+            args: vec![quote!(prefix)],
+        }
+    }
+
+    /// The path of the element `key` of this repeated or map field.
+    fn element(&self, key: TokenStream, debug: bool) -> Self {
+        let mut path = self.clone();
+        path.format.push_str(if debug { "[{:?}]" } else { "[{}]" });
+        path.args.push(key);
+        path
+    }
+
+    fn to_string_expr(&self) -> TokenStream {
+        let format = &self.format;
+        let args = &self.args;
+        quote!(format!(#format, #(#args),*))
+    }
+
+    /// The prefix of the paths of the fields of the message at this path.
+    fn to_prefix_expr(&self) -> TokenStream {
+        let format = format!("{}.", self.format);
+        let args = &self.args;
+        quote!(&format!(#format, #(#args),*))
+    }
+
+    fn violation(&self, description: &str) -> TokenStream {
+        let path = self.to_string_expr();
+        quote!(details.add_bad_request_violation(#path, #description);)
+    }
+}
+
+struct Generator<'a, 'w> {
+    index: &'a DescriptorIndex,
+    proto_path: &'a str,
+    /// Returns whether the message type needs validation.
+    needed: &'a dyn Fn(&str) -> bool,
+    warnings: &'w mut Vec<String>,
+}
+
+impl Generator<'_, '_> {
+    /// Returns the checks of the message `name`, whose fields are accessed
+    /// through `message`.
+    fn message_checks(&mut self, name: &str, rules: &MessageRules) -> Vec<TokenStream> {
+        if rules.disabled {
+            return vec![];
+        }
+        let mut checks = vec![];
+        for oneof in &rules.required_oneofs {
+            let ident = format_ident!("{}", rust_protobuf_ident(oneof));
+            let violation = FieldPath::field(oneof).violation("value is required");
+            checks.push(quote! {
+                if message.#ident.is_none() {
+                    #violation
+                }
+            });
+        }
+        for (field, field_rules) in &rules.fields {
+            let context = format!(
+                "message {}: field {}",
+                name.trim_start_matches('.'),
+                field.name()
+            );
+            if let Some(check) =
+                self.field_checks(name, &rules.message, field, field_rules.as_ref(), &context)
+            {
+                checks.push(check);
+            }
+        }
+        checks
+    }
+
+    /// Returns the checks of `field` of the message `name`.
+    fn field_checks(
+        &mut self,
+        name: &str,
+        message: &DescriptorProto,
+        field: &FieldDescriptorProto,
+        rules: Option<&OptionValue>,
+        context: &str,
+    ) -> Option<TokenStream> {
+        let ident = format_ident!("{}", rust_protobuf_ident(field.name()));
+        let path = FieldPath::field(field.name());
+
+        if let Some(entry) = map_entry(self.index, field) {
+            let map_rules = self.kind_rules(rules, "map", context);
+            let key = entry.field.iter().find(|f| f.number() == 1)?;
+            let value = entry.field.iter().find(|f| f.number() == 2)?;
+            return self.map_checks(&ident, key, value, map_rules, &path, context);
+        }
+        if field.label() == Label::LABEL_REPEATED {
+            let repeated_rules = self.kind_rules(rules, "repeated", context);
+            return self.repeated_checks(&ident, field, repeated_rules, &path, context);
+        }
+
+        let element = self.element_checks(field, rules, &path, context);
+        let required = field.type_() == Type::TYPE_MESSAGE
+            && rules
+                .and_then(|r| r.field("message"))
+                .and_then(|r| r.field("required"))
+                == Some(&OptionValue::Bool(true));
+        if element.is_empty() && !required {
+            return None;
+        }
+        let in_oneof = field.has_oneof_index() && !field.proto3_optional();
+        if in_oneof {
+            let oneof = message.oneof_decl.get(field.oneof_index() as usize)?.name();
+            let oneof_ident = format_ident!("{}", rust_protobuf_ident(oneof));
+            let rust_type = self.index.rust_type(self.proto_path, name)?;
+            let module = match rust_type.rsplit_once("::") {
+                Some((scope, _)) => format!(
+                    "{}::{}",
+                    scope,
+                    rust_protobuf_ident(&protobuf_parse::snake_case(message.name()))
+                ),
+                None => rust_protobuf_ident(&protobuf_parse::snake_case(message.name())),
+            };
+            let oneof_type: syn::Path = syn::parse_str(&format!(
+                "{}::{}",
+                module,
+                rust_protobuf_ident(&rust_protobuf_capitalize(oneof))
+            ))
+            .expect("not a valid path");
+            let variant = format_ident!(
+                "{}",
+                rust_protobuf_ident(&protobuf_parse::camel_case(field.name()))
+            );
+            if !required {
+                return Some(quote! {
+                    if let Some(#oneof_type::#variant(value)) = &message.#oneof_ident {
+                        #(#element)*
+                    }
+                });
+            }
+            let violation = path.violation("value is required");
+            return Some(quote! {
+                match &message.#oneof_ident {
+                    Some(#oneof_type::#variant(value)) => {
+                        #(#element)*
+                    }
+                    _ => {
+                        #violation
+                    }
+                }
+            });
+        }
+        if field.type_() == Type::TYPE_MESSAGE && required {
+            let violation = path.violation("value is required");
+            return Some(quote! {
+                match message.#ident.as_ref() {
+                    Some(value) => {
+                        #(#element)*
+                    }
+                    None => {
+                        #violation
+                    }
+                }
+            });
+        }
+        if field.type_() == Type::TYPE_MESSAGE {
+            return Some(quote! {
+                if let Some(value) = message.#ident.as_ref() {
+                    #(#element)*
+                }
+            });
+        }
+        let proto3 = self.index.proto3_messages.contains(name);
+        if proto3 && !field.proto3_optional() {
+            Some(quote! {
+                {
+                    let value = &message.#ident;
+                    #(#element)*
+                }
+            })
+        } else {
+            Some(quote! {
+                if let Some(value) = &message.#ident {
+                    #(#element)*
+                }
+            })
+        }
+    }
+
+    /// Returns the rules of `kind`, e.g. `repeated`, from the field rules
+    /// `rules`, reporting rules of other kinds to `warnings`.
+    fn kind_rules<'r>(
+        &mut self,
+        rules: Option<&'r OptionValue>,
+        kind: &str,
+        context: &str,
+    ) -> Option<&'r OptionValue> {
+        let OptionValue::Message(fields) = rules? else {
+            return None;
+        };
+        for (name, _) in fields {
+            if name != kind {
+                self.warnings.push(format!(
+                    "{}: {} rules do not apply to a {} field and are ignored",
+                    context, name, kind
+                ));
+            }
+        }
+        rules?.field(kind)
+    }
+
+    fn repeated_checks(
+        &mut self,
+        ident: &syn::Ident,
+        field: &FieldDescriptorProto,
+        rules: Option<&OptionValue>,
+        path: &FieldPath,
+        context: &str,
+    ) -> Option<TokenStream> {
+        let mut checks = vec![];
+        if let Some(min) = rules.and_then(|r| r.field("min_items")).and_then(as_count) {
+            let violation = path.violation(&format!("value must contain at least {} item(s)", min));
+            checks.push(quote!(if values.len() < #min { #violation }));
+        }
+        if let Some(max) = rules.and_then(|r| r.field("max_items")).and_then(as_count) {
+            let violation =
+                path.violation(&format!("value must contain no more than {} item(s)", max));
+            checks.push(quote!(if values.len() > #max { #violation }));
+        }
+        if rules.and_then(|r| r.field("unique")) == Some(&OptionValue::Bool(true)) {
+            let key = match field.type_() {
+                Type::TYPE_FLOAT | Type::TYPE_DOUBLE => Some(quote!(value.to_bits())),
+                Type::TYPE_ENUM => Some(quote!(value.value())),
+                Type::TYPE_MESSAGE | Type::TYPE_GROUP => None,
+                _ => Some(quote!(value)),
+            };
+            match key {
+                Some(key) => {
+                    let violation = path.violation("repeated value must contain unique items");
+                    checks.push(quote! {
+                        let mut seen = ::std::collections::HashSet::new();
+                        if !values.iter().all(|value| seen.insert(#key)) {
+                            #violation
+                        }
+                    });
+                }
+                None => self.warnings.push(format!(
+                    "{}: unique rule of repeated messages is not supported and is ignored",
+                    context
+                )),
+            }
+        }
+        let item = path.element(quote!(i), false);
+        let items =
+            self.element_checks(field, rules.and_then(|r| r.field("items")), &item, context);
+        if !items.is_empty() {
+            checks.push(quote! {
+                for (i, value) in values.iter().enumerate() {
+                    #(#items)*
+                }
+            });
+        }
+        if checks.is_empty() {
+            return None;
+        }
+        let checks = wrap_ignore_empty(rules, quote!(!values.is_empty()), quote!(#(#checks)*));
+        Some(quote! {
+            {
+                let values = &message.#ident;
+                #checks
+            }
+        })
+    }
+
+    fn map_checks(
+        &mut self,
+        ident: &syn::Ident,
+        key: &FieldDescriptorProto,
+        value: &FieldDescriptorProto,
+        rules: Option<&OptionValue>,
+        path: &FieldPath,
+        context: &str,
+    ) -> Option<TokenStream> {
+        let mut checks = vec![];
+        if let Some(min) = rules.and_then(|r| r.field("min_pairs")).and_then(as_count) {
+            let violation = path.violation(&format!("value must contain at least {} pair(s)", min));
+            checks.push(quote!(if values.len() < #min { #violation }));
+        }
+        if let Some(max) = rules.and_then(|r| r.field("max_pairs")).and_then(as_count) {
+            let violation =
+                path.violation(&format!("value must contain no more than {} pair(s)", max));
+            checks.push(quote!(if values.len() > #max { #violation }));
+        }
+        let element = path.element(quote!(key), true);
+        let keys = self.element_checks(key, rules.and_then(|r| r.field("keys")), &element, context);
+        let values = self.element_checks(
+            value,
+            rules.and_then(|r| r.field("values")),
+            &element,
+            context,
+        );
+        if !keys.is_empty() || !values.is_empty() {
+            let keys = (!keys.is_empty()).then(|| {
+                quote! {
+                    {
+                        let value = key;
+                        #(#keys)*
+                    }
+                }
+            });
+            checks.push(quote! {
+                for (key, value) in values.iter() {
+                    #keys
+                    #(#values)*
+                }
+            });
+        }
+        if checks.is_empty() {
+            return None;
+        }
+        let checks = wrap_ignore_empty(rules, quote!(!values.is_empty()), quote!(#(#checks)*));
+        Some(quote! {
+            {
+                let values = &message.#ident;
+                #checks
+            }
+        })
+    }
+
+    /// Returns the checks of `rules`, a `FieldRules`, on `value`, a reference
+    /// to a value of the type of `field`.
+    fn element_checks(
+        &mut self,
+        field: &FieldDescriptorProto,
+        rules: Option<&OptionValue>,
+        path: &FieldPath,
+        context: &str,
+    ) -> Vec<TokenStream> {
+        let mut checks = vec![];
+        if let Some(OptionValue::Message(fields)) = rules {
+            for (kind, rules) in fields {
+                let field_kind = match field.type_() {
+                    Type::TYPE_FLOAT => "float",
+                    Type::TYPE_DOUBLE => "double",
+                    Type::TYPE_INT32 => "int32",
+                    Type::TYPE_INT64 => "int64",
+                    Type::TYPE_UINT32 => "uint32",
+                    Type::TYPE_UINT64 => "uint64",
+                    Type::TYPE_SINT32 => "sint32",
+                    Type::TYPE_SINT64 => "sint64",
+                    Type::TYPE_FIXED32 => "fixed32",
+                    Type::TYPE_FIXED64 => "fixed64",
+                    Type::TYPE_SFIXED32 => "sfixed32",
+                    Type::TYPE_SFIXED64 => "sfixed64",
+                    Type::TYPE_BOOL => "bool",
+                    Type::TYPE_STRING => "string",
+                    Type::TYPE_BYTES => "bytes",
+                    Type::TYPE_ENUM => "enum",
+                    Type::TYPE_MESSAGE | Type::TYPE_GROUP => "message",
+                };
+                if kind == "message" && field_kind == "message" {
+                    // Checked below, or by the caller.
+                    continue;
+                }
+                if matches!(kind.as_str(), "any" | "duration" | "timestamp") {
+                    self.warnings.push(format!(
+                        "{}: {} rules are not supported and are ignored",
+                        context, kind
+                    ));
+                    continue;
+                }
+                if kind != field_kind {
+                    self.warnings.push(format!(
+                        "{}: {} rules do not apply to a {} field and are ignored",
+                        context, kind, field_kind
+                    ));
+                    continue;
+                }
+                let kind_checks = match field_kind {
+                    "bool" => self.bool_checks(rules, path),
+                    "string" => self.string_checks(rules, path, context),
+                    "bytes" => self.bytes_checks(rules, path, context),
+                    "enum" => self.enum_checks(rules, path),
+                    _ => self.number_checks(rules, path),
+                };
+                checks.extend(kind_checks);
+            }
+        }
+        if field.type_() == Type::TYPE_MESSAGE
+            && !skips_nested(rules)
+            && (self.needed)(field.type_name())
+        {
+            let function = format_ident!("{}", function_name(field.type_name()));
+            let prefix = path.to_prefix_expr();
+            checks.push(quote!(#function(value, #prefix, details);));
+        }
+        checks
+    }
+
+    fn bool_checks(&mut self, rules: &OptionValue, path: &FieldPath) -> Vec<TokenStream> {
+        match rules.field("const") {
+            Some(OptionValue::Bool(expected)) => {
+                let violation = path.violation(&format!("value must equal {}", expected));
+                vec![quote!(if *value != #expected { #violation })]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn number_checks(&mut self, rules: &OptionValue, path: &FieldPath) -> Vec<TokenStream> {
+        let mut checks = vec![];
+        let value = quote!(*value);
+        if let Some(expected) = rules.field("const") {
+            let literal = expected.literal();
+            let violation = path.violation(&format!("value must equal {}", display(expected)));
+            checks.push(quote!(if #value != #literal { #violation }));
+        }
+        checks.extend(bounds_check(rules, &value, path));
+        checks.extend(list_checks(rules, &value, path));
+        vec![wrap_ignore_empty(
+            Some(rules),
+            quote!(*value != Default::default()),
+            quote!(#(#checks)*),
+        )]
+    }
+
+    fn enum_checks(&mut self, rules: &OptionValue, path: &FieldPath) -> Vec<TokenStream> {
+        let mut checks = vec![];
+        let value = quote!(value.value());
+        if let Some(expected) = rules.field("const") {
+            let literal = expected.literal();
+            let violation = path.violation(&format!("value must equal {}", display(expected)));
+            checks.push(quote!(if #value != #literal { #violation }));
+        }
+        if rules.field("defined_only") == Some(&OptionValue::Bool(true)) {
+            let violation = path.violation("value must be one of the defined enum values");
+            checks.push(quote!(if value.enum_value().is_err() { #violation }));
+        }
+        checks.extend(list_checks(rules, &value, path));
+        checks
+    }
+
+    fn string_checks(
+        &mut self,
+        rules: &OptionValue,
+        path: &FieldPath,
+        context: &str,
+    ) -> Vec<TokenStream> {
+        let OptionValue::Message(fields) = rules else {
+            return vec![];
+        };
+        let mut checks = vec![];
+        for (rule, expected) in fields {
+            let check = match (rule.as_str(), expected) {
+                ("const", OptionValue::String(s)) => {
+                    let violation = path.violation(&format!("value must equal {:?}", s));
+                    quote!(if value != #s { #violation })
+                }
+                ("len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation = path.violation(&format!("value length must be {} runes", n));
+                    quote!(if value.chars().count() != #n { #violation })
+                }
+                ("min_len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at least {} runes", n));
+                    quote!(if value.chars().count() < #n { #violation })
+                }
+                ("max_len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at most {} runes", n));
+                    quote!(if value.chars().count() > #n { #violation })
+                }
+                ("len_bytes", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation = path.violation(&format!("value length must be {} bytes", n));
+                    quote!(if value.len() != #n { #violation })
+                }
+                ("min_bytes", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at least {} bytes", n));
+                    quote!(if value.len() < #n { #violation })
+                }
+                ("max_bytes", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at most {} bytes", n));
+                    quote!(if value.len() > #n { #violation })
+                }
+                ("pattern", OptionValue::String(pattern)) => {
+                    // Patterns are compiled by the generated code, so they
+                    // must not fail there.
+                    if let Err(e) = regex::Regex::new(pattern) {
+                        // Syntax errors span lines that point at the error.
+                        let e = e.to_string();
+                        let reason = e.lines().last().unwrap_or_default();
+                        self.warnings.push(format!(
+                            "{}: string rule pattern {:?} is not supported by the regex crate \
+                             and is ignored: {}",
+                            context,
+                            pattern,
+                            reason.trim_start_matches("error: ")
+                        ));
+                        continue;
+                    }
+                    let violation = path
+                        .violation(&format!("value does not match regex pattern {:?}", pattern));
+                    quote! {{
+                        static PATTERN: ::std::sync::OnceLock<::regex::Regex> =
+                            ::std::sync::OnceLock::new();
+                        let pattern = PATTERN
+                            .get_or_init(|| ::regex::Regex::new(#pattern).expect("invalid pattern"));
+                        if !pattern.is_match(value) {
+                            #violation
+                        }
+                    }}
+                }
+                ("prefix", OptionValue::String(s)) => {
+                    let violation = path.violation(&format!("value does not have prefix {:?}", s));
+                    quote!(if !value.starts_with(#s) { #violation })
+                }
+                ("suffix", OptionValue::String(s)) => {
+                    let violation = path.violation(&format!("value does not have suffix {:?}", s));
+                    quote!(if !value.ends_with(#s) { #violation })
+                }
+                ("contains", OptionValue::String(s)) => {
+                    let violation =
+                        path.violation(&format!("value does not contain substring {:?}", s));
+                    quote!(if !value.contains(#s) { #violation })
+                }
+                ("not_contains", OptionValue::String(s)) => {
+                    let violation = path.violation(&format!("value contains substring {:?}", s));
+                    quote!(if value.contains(#s) { #violation })
+                }
+                ("ip", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IP address");
+                    quote!(if value.parse::<::std::net::IpAddr>().is_err() { #violation })
+                }
+                ("ipv4", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IPv4 address");
+                    quote!(if value.parse::<::std::net::Ipv4Addr>().is_err() { #violation })
+                }
+                ("ipv6", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IPv6 address");
+                    quote!(if value.parse::<::std::net::Ipv6Addr>().is_err() { #violation })
+                }
+                ("uuid", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid UUID");
+                    quote! {{
+                        let uuid = value.len() == 36
+                            && value.char_indices().all(|(i, c)| match i {
+                                8 | 13 | 18 | 23 => c == '-',
+                                _ => c.is_ascii_hexdigit(),
+                            });
+                        if !uuid {
+                            #violation
+                        }
+                    }}
+                }
+                ("in" | "not_in" | "ignore_empty" | "strict", _) => continue,
+                (_, OptionValue::Bool(false)) => continue,
+                (rule, _) => {
+                    self.warnings.push(format!(
+                        "{}: string rule {} is not supported and is ignored",
+                        context, rule
+                    ));
+                    continue;
+                }
+            };
+            checks.push(check);
+        }
+        checks.extend(list_checks(rules, &quote!(value), path));
+        vec![wrap_ignore_empty(
+            Some(rules),
+            quote!(!value.is_empty()),
+            quote! {
+                let value: &str = value;
+                #(#checks)*
+            },
+        )]
+    }
+
+    fn bytes_checks(
+        &mut self,
+        rules: &OptionValue,
+        path: &FieldPath,
+        context: &str,
+    ) -> Vec<TokenStream> {
+        let OptionValue::Message(fields) = rules else {
+            return vec![];
+        };
+        let mut checks = vec![];
+        for (rule, expected) in fields {
+            let check = match (rule.as_str(), expected) {
+                ("const", OptionValue::Bytes(b)) => {
+                    let violation =
+                        path.violation(&format!("value must equal {}", display(expected)));
+                    let b = Literal::byte_string(b);
+                    quote!(if value != &#b[..] { #violation })
+                }
+                ("len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation = path.violation(&format!("value length must be {} bytes", n));
+                    quote!(if value.len() != #n { #violation })
+                }
+                ("min_len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at least {} bytes", n));
+                    quote!(if value.len() < #n { #violation })
+                }
+                ("max_len", OptionValue::U64(n)) => {
+                    let n = count(*n);
+                    let violation =
+                        path.violation(&format!("value length must be at most {} bytes", n));
+                    quote!(if value.len() > #n { #violation })
+                }
+                ("prefix", OptionValue::Bytes(b)) => {
+                    let violation = path
+                        .violation(&format!("value does not have prefix {}", display(expected)));
+                    let b = Literal::byte_string(b);
+                    quote!(if !value.starts_with(#b) { #violation })
+                }
+                ("suffix", OptionValue::Bytes(b)) => {
+                    let violation = path
+                        .violation(&format!("value does not have suffix {}", display(expected)));
+                    let b = Literal::byte_string(b);
+                    quote!(if !value.ends_with(#b) { #violation })
+                }
+                ("contains", OptionValue::Bytes(b)) if !b.is_empty() => {
+                    let violation =
+                        path.violation(&format!("value does not contain {}", display(expected)));
+                    let n = b.len();
+                    let b = Literal::byte_string(b);
+                    quote!(if !value.windows(#n).any(|window| window == #b) { #violation })
+                }
+                ("ip", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IP address");
+                    quote!(if !matches!(value.len(), 4 | 16) { #violation })
+                }
+                ("ipv4", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IPv4 address");
+                    quote!(if value.len() != 4 { #violation })
+                }
+                ("ipv6", OptionValue::Bool(true)) => {
+                    let violation = path.violation("value must be a valid IPv6 address");
+                    quote!(if value.len() != 16 { #violation })
+                }
+                ("contains" | "in" | "not_in" | "ignore_empty", _) => continue,
+                (_, OptionValue::Bool(false)) => continue,
+                (rule, _) => {
+                    self.warnings.push(format!(
+                        "{}: bytes rule {} is not supported and is ignored",
+                        context, rule
+                    ));
+                    continue;
+                }
+            };
+            checks.push(check);
+        }
+        checks.extend(list_checks(rules, &quote!(value), path));
+        vec![wrap_ignore_empty(
+            Some(rules),
+            quote!(!value.is_empty()),
+            quote! {
+                let value: &[u8] = value;
+                #(#checks)*
+            },
+        )]
+    }
+}
+
+/// Returns `checks`, only run if `non_empty` holds when `rules` has
+/// `ignore_empty` set.
+fn wrap_ignore_empty(
+    rules: Option<&OptionValue>,
+    non_empty: TokenStream,
+    checks: TokenStream,
+) -> TokenStream {
+    if rules.and_then(|r| r.field("ignore_empty")) == Some(&OptionValue::Bool(true)) {
+        quote!(if #non_empty { #checks })
+    } else {
+        checks
+    }
+}
+
+/// Returns the check of the `gt`, `gte`, `lt` and `lte` rules on `value`.
+///
+/// Like protoc-gen-validate, a lower bound above the upper bound requires
+/// values outside of the range instead.
+fn bounds_check(rules: &OptionValue, value: &TokenStream, path: &FieldPath) -> Option<TokenStream> {
+    let lower = match (rules.field("gt"), rules.field("gte")) {
+        (Some(bound), _) => Some((bound, quote!(>), "greater than")),
+        (None, Some(bound)) => Some((bound, quote!(>=), "greater than or equal to")),
+        (None, None) => None,
+    };
+    let upper = match (rules.field("lt"), rules.field("lte")) {
+        (Some(bound), _) => Some((bound, quote!(<), "less than")),
+        (None, Some(bound)) => Some((bound, quote!(<=), "less than or equal to")),
+        (None, None) => None,
+    };
+    let check = |(bound, op, text): &(&OptionValue, TokenStream, &str)| {
+        let literal = bound.literal();
+        (
+            quote!(#value #op #literal),
+            format!("{} {}", text, display(bound)),
+        )
+    };
+    let (valid, description) = match (&lower, &upper) {
+        (Some(lower_bound), Some(upper_bound)) => {
+            let (lower, lower_text) = check(lower_bound);
+            let (upper, upper_text) = check(upper_bound);
+            if as_f64(upper_bound.0) < as_f64(lower_bound.0) {
+                (
+                    quote!(#lower || #upper),
+                    format!("value must be {} or {}", lower_text, upper_text),
+                )
+            } else {
+                (
+                    quote!(#lower && #upper),
+                    format!("value must be {} and {}", lower_text, upper_text),
+                )
+            }
+        }
+        (Some(bound), None) | (None, Some(bound)) => {
+            let (valid, text) = check(bound);
+            (valid, format!("value must be {}", text))
+        }
+        (None, None) => return None,
+    };
+    let violation = path.violation(&description);
+    Some(quote!(if !(#valid) { #violation }))
+}
+
+/// Returns the checks of the `in` and `not_in` rules on `value`.
+fn list_checks(rules: &OptionValue, value: &TokenStream, path: &FieldPath) -> Vec<TokenStream> {
+    let OptionValue::Message(fields) = rules else {
+        return vec![];
+    };
+    let mut checks = vec![];
+    for (rule, negated) in [("in", false), ("not_in", true)] {
+        let values: Vec<_> = fields
+            .iter()
+            .filter(|(name, _)| name == rule)
+            .map(|(_, v)| v)
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        let list = values
+            .iter()
+            .map(|v| display(v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let literals = values.iter().map(|v| match v {
+            OptionValue::Bytes(b) => {
+                let b = Literal::byte_string(b);
+                quote!(&#b[..])
+            }
+            v => v.literal(),
+        });
+        let (condition, description) = if negated {
+            (
+                quote!(LIST.contains(&#value)),
+                format!("value must not be in list [{}]", list),
+            )
+        } else {
+            (
+                quote!(!LIST.contains(&#value)),
+                format!("value must be in list [{}]", list),
+            )
+        };
+        let ty = match values[0] {
+            OptionValue::Bytes(_) => quote!(&[u8]),
+            v => v.rust_type(),
+        };
+        let len = Literal::usize_unsuffixed(values.len());
+        let violation = path.violation(&description);
+        checks.push(quote! {
+            {
+                const LIST: [#ty; #len] = [#(#literals),*];
+                if #condition {
+                    #violation
+                }
+            }
+        });
+    }
+    checks
+}
+
+/// Returns a count rule, e.g. `min_items`, as a `usize` literal.
+fn as_count(value: &OptionValue) -> Option<Literal> {
+    match value {
+        OptionValue::U64(v) => Some(count(*v)),
+        _ => None,
+    }
+}
+
+fn count(n: u64) -> Literal {
+    Literal::usize_unsuffixed(n as usize)
+}
+
+fn as_f64(value: &OptionValue) -> f64 {
+    match value {
+        OptionValue::I32(v) => *v as f64,
+        OptionValue::I64(v) => *v as f64,
+        OptionValue::U32(v) => *v as f64,
+        OptionValue::U64(v) => *v as f64,
+        OptionValue::F32(v) => *v as f64,
+        OptionValue::F64(v) => *v,
+        _ => 0.0,
+    }
+}
+
+/// Formats a rule value for the description of a violation.
+fn display(value: &OptionValue) -> String {
+    match value {
+        OptionValue::Bool(v) => v.to_string(),
+        OptionValue::I32(v) => v.to_string(),
+        OptionValue::I64(v) => v.to_string(),
+        OptionValue::U32(v) => v.to_string(),
+        OptionValue::U64(v) => v.to_string(),
+        OptionValue::F32(v) => v.to_string(),
+        OptionValue::F64(v) => v.to_string(),
+        OptionValue::String(v) => format!("{:?}", v),
+        OptionValue::Bytes(v) => format!("{:?}", String::from_utf8_lossy(v)),
+        OptionValue::Enum(v) => v.clone(),
+        OptionValue::Message(_) => String::new(),
+    }
+}
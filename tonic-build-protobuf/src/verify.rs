@@ -0,0 +1,84 @@
+//! Verification that referenced message types exist in the output of
+//! protobuf-codegen.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::Service;
+
+/// Checks that every message type referenced by `services` under
+/// `proto_path` is defined by the files protobuf-codegen generated to `dir`.
+///
+/// Returns a description of each missing type.
+pub(crate) fn verify(services: &[Service], proto_path: &str, dir: &Path) -> Vec<String> {
+    let mut files = HashMap::new();
+    let mut errors = vec![];
+    for service in services {
+        for rust_type in service.rust_types() {
+            let Some(relative) = rust_type
+                .strip_prefix(proto_path)
+                .and_then(|path| path.strip_prefix("::"))
+            else {
+                // Types outside of `proto_path`, e.g. well-known types.
+                continue;
+            };
+            let mut segments: Vec<&str> = relative.split("::").collect();
+            let name = segments.pop().unwrap();
+            let file = dir.join(format!("{}.rs", segments[0]));
+            let ast = files.entry(file.clone()).or_insert_with(|| parse(&file));
+            let Some(ast) = ast else {
+                errors.push(format!(
+                    "{} referenced by service {}.{} does not exist: {} was not generated",
+                    rust_type,
+                    service.package,
+                    service.name,
+                    file.display()
+                ));
+                continue;
+            };
+            if !defines(&ast.items, &segments[1..], name) {
+                errors.push(format!(
+                    "{} referenced by service {}.{} does not exist: {} does not define {}",
+                    rust_type,
+                    service.package,
+                    service.name,
+                    file.display(),
+                    relative
+                        .strip_prefix(segments[0])
+                        .unwrap()
+                        .trim_start_matches("::")
+                ));
+            }
+        }
+    }
+    errors
+}
+
+fn parse(file: &PathBuf) -> Option<syn::File> {
+    let content = fs::read_to_string(file).ok()?;
+    Some(syn::parse_file(&content).unwrap_or_else(|e| {
+        panic!("failed to parse {}: {}", file.display(), e);
+    }))
+}
+
+/// Whether `items` define the type `name` in the nested modules `modules`.
+fn defines(items: &[syn::Item], modules: &[&str], name: &str) -> bool {
+    match modules.split_first() {
+        Some((module, rest)) => items.iter().any(|item| match item {
+            syn::Item::Mod(m) if m.ident == module => m
+                .content
+                .as_ref()
+                .is_some_and(|(_, items)| defines(items, rest, name)),
+            _ => false,
+        }),
+        None => items.iter().any(|item| match item {
+            syn::Item::Struct(s) => s.ident == name,
+            syn::Item::Enum(e) => e.ident == name,
+            syn::Item::Type(t) => t.ident == name,
+            _ => false,
+        }),
+    }
+}
@@ -0,0 +1,118 @@
+//! Regeneration of code whenever .proto files change, outside of build
+//! scripts.
+//!
+//! Build scripts only run when cargo builds, which is too coarse while
+//! iterating on a schema, or for servers that reload their schemas at
+//! runtime. A [`Watcher`] polls directories of .proto files and regenerates
+//! code whenever one is added, removed or modified:
+//!
+//! ```rust,ignore
+//! tonic_build_protobuf::watch::Watcher::new(&["proto"]).watch(Duration::from_millis(500), |protos| {
+//!     tonic_build_protobuf::Builder::new()
+//!         .out_dir("src/generated")
+//!         .mod_file("mod.rs")
+//!         .compile(protos, &["proto"]);
+//! });
+//! ```
+//!
+//! The `tonic-rust-protobuf-watch` binary does the same from the command
+//! line.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// The modification time and length of a .proto file.
+type Stamp = (Option<SystemTime>, u64);
+
+/// Polls directories for changes of the .proto files in them.
+#[derive(Debug)]
+pub struct Watcher {
+    dirs: Vec<PathBuf>,
+    /// The .proto files seen by the last poll, `None` before the first one.
+    seen: Option<BTreeMap<PathBuf, Stamp>>,
+}
+
+impl Watcher {
+    /// Create a watcher of the .proto files in `dirs` and their
+    /// subdirectories.
+    pub fn new(dirs: &[impl AsRef<Path>]) -> Self {
+        Self {
+            dirs: dirs.iter().map(|dir| dir.as_ref().to_path_buf()).collect(),
+            seen: None,
+        }
+    }
+
+    /// Returns the .proto files in the watched directories, in the order of
+    /// their paths, on the first poll and whenever one was added, removed or
+    /// modified since the previous poll, and `None` otherwise.
+    ///
+    /// Unreadable directories are skipped, e.g. while they are recreated.
+    pub fn poll(&mut self) -> Option<Vec<PathBuf>> {
+        let mut stamps = BTreeMap::new();
+        for dir in &self.dirs {
+            scan(dir, &mut stamps);
+        }
+        if self.seen.as_ref() == Some(&stamps) {
+            return None;
+        }
+        let protos = stamps.keys().cloned().collect();
+        self.seen = Some(stamps);
+        Some(protos)
+    }
+
+    /// Calls `regenerate` with the .proto files in the watched directories,
+    /// once initially and then whenever they change, polling them every
+    /// `interval`. Changes are only acted upon once the files stay unchanged
+    /// for an `interval`, so that editors are done writing them.
+    ///
+    /// A panic of `regenerate`, e.g. because protoc rejects a file being
+    /// edited, is reported on stderr by the panic hook, and the watcher
+    /// waits for the next change. Never returns.
+    pub fn watch<F>(mut self, interval: Duration, mut regenerate: F) -> !
+    where
+        F: FnMut(&[PathBuf]),
+    {
+        loop {
+            if let Some(mut protos) = self.poll() {
+                thread::sleep(interval);
+                while let Some(changed) = self.poll() {
+                    protos = changed;
+                    thread::sleep(interval);
+                }
+                let result = panic::catch_unwind(AssertUnwindSafe(|| regenerate(&protos)));
+                if result.is_err() {
+                    eprintln!("warning: regeneration failed, waiting for changes");
+                }
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+/// Adds the stamps of the .proto files in `dir` and its subdirectories to
+/// `stamps`.
+fn scan(dir: &Path, stamps: &mut BTreeMap<PathBuf, Stamp>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            scan(&path, stamps);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "proto")
+        {
+            stamps.insert(path, (metadata.modified().ok(), metadata.len()));
+        }
+    }
+}
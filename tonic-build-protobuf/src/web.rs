@@ -0,0 +1,37 @@
+//! Generation of the glue that exposes services to browsers via `tonic-web`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::Service;
+
+/// Generate the grpc-web helpers of `service`, to be appended to its server
+/// module.
+///
+/// The generated code refers to the `tonic_web` and `tower_layer` crates,
+/// which the crate including it must depend on.
+pub(crate) fn generate(service: &Service) -> TokenStream {
+    let service_trait = format_ident!("{}", service.name);
+    let server = format_ident!("{}Server", service.name);
+
+    quote! {
+        /// Wraps `inner` in its server behind the grpc-web translation of
+        /// `tonic-web`, so browsers can call it. Add it to a server that
+        /// accepts HTTP/1.1, e.g. one returned by [`web_server_builder`].
+        pub fn web_service<T: #service_trait>(
+            inner: T,
+        ) -> ::tonic_web::GrpcWebService<#server<T>> {
+            ::tower_layer::Layer::layer(&::tonic_web::GrpcWebLayer::new(), #server::new(inner))
+        }
+        /// Returns a server builder that accepts the HTTP/1.1 requests of
+        /// browsers and applies `cors` in front of all services, e.g. a
+        /// `tower_http::cors::CorsLayer` allowing the origins of the web app.
+        /// Pass `tower_layer::Identity::new()` to skip CORS, e.g. when the app
+        /// is served from the same origin.
+        pub fn web_server_builder<L>(
+            cors: L,
+        ) -> tonic::transport::Server<::tower_layer::Stack<L, ::tower_layer::Identity>> {
+            tonic::transport::Server::builder().accept_http1(true).layer(cors)
+        }
+    }
+}
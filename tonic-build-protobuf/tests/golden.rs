@@ -0,0 +1,222 @@
+use std::{path::PathBuf, time::Duration};
+
+use protobuf::Message;
+use quote::quote;
+use tonic_build_protobuf::{golden::assert_golden, Builder, ProtoSet, ServerDefaults};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn check(builder: Builder, golden_dir: &str) {
+    check_proto(builder, "golden.proto", golden_dir);
+}
+
+fn check_proto(builder: Builder, proto: &str, golden_dir: &str) {
+    let protos = manifest_dir().join("tests/protos");
+    // For `validate/validate.proto`.
+    let include = manifest_dir().join("../examples/include");
+    let files = builder.generate(&[protos.join(proto)], &[&protos, &include]);
+    assert_golden(&files, manifest_dir().join("tests/golden").join(golden_dir));
+}
+
+#[test]
+fn test_golden_default() {
+    check(Builder::new(), "default");
+}
+
+#[test]
+fn test_golden_server_defaults() {
+    check(
+        Builder::new().build_client(false).server_defaults(
+            ServerDefaults::new()
+                .concurrency_limit(32)
+                .timeout(Duration::from_millis(1500))
+                .interceptor("crate::auth::interceptor")
+                .interceptor("crate::tracing::interceptor"),
+        ),
+        "server_defaults",
+    );
+}
+
+#[test]
+fn test_golden_blocking_client() {
+    check(
+        Builder::new()
+            .build_server(false)
+            .build_blocking_client(true),
+        "blocking_client",
+    );
+}
+
+#[test]
+fn test_golden_package_dirs() {
+    check(
+        Builder::new().package_dirs(true).mod_file("mod.rs"),
+        "package_dirs",
+    );
+}
+
+#[test]
+fn test_golden_skeletons() {
+    let protos = manifest_dir().join("tests/protos");
+    let files = Builder::new().proto_path("crate").generate_skeletons(
+        &[protos.join("golden.proto")],
+        &[&protos],
+        "crate::services",
+    );
+    assert_golden(&files, manifest_dir().join("tests/golden/skeletons"));
+}
+
+#[test]
+fn test_golden_smoke_tests() {
+    check(Builder::new().build_smoke_tests(true), "smoke_tests");
+}
+
+#[test]
+fn test_golden_unpackaged() {
+    check_proto(
+        Builder::new()
+            .validate_requests(true)
+            .mod_file("mod.rs")
+            .default_package("unpackaged"),
+        "unpackaged.proto",
+        "unpackaged",
+    );
+}
+
+#[test]
+fn test_golden_auth() {
+    check_proto(
+        Builder::new().auth_option("authpb.auth"),
+        "auth.proto",
+        "auth",
+    );
+}
+
+#[test]
+fn test_golden_api_docs() {
+    check_proto(
+        Builder::new().build_server(false).api_docs("API.md"),
+        "documented.proto",
+        "api_docs",
+    );
+}
+
+#[test]
+fn test_golden_iter_methods() {
+    check(
+        Builder::new().build_server(false).build_iter_methods(true),
+        "iter_methods",
+    );
+}
+
+#[test]
+fn test_golden_service_descriptors() {
+    check(
+        Builder::new().build_service_descriptors(true),
+        "service_descriptors",
+    );
+}
+
+#[test]
+fn test_golden_schema_hash() {
+    check(Builder::new().build_schema_hash(true), "schema_hash");
+}
+
+#[test]
+fn test_golden_hooks() {
+    check(
+        Builder::new()
+            .module_header(|_service, _module| quote! { use crate::platform; })
+            .client_method_prologue(|path| {
+                quote! { let request = platform::authorize(#path, request.into_request()); }
+            })
+            .client_method_epilogue(|path| quote! { platform::record(#path, &response); })
+            .server_trait_items(|service| {
+                quote! {
+                    /// The name of the service, for the platform wrappers.
+                    fn platform_name(&self) -> &'static str {
+                        #service
+                    }
+                }
+            }),
+        "hooks",
+    );
+}
+
+#[test]
+fn test_golden_tracing() {
+    check(Builder::new().build_tracing(true), "tracing");
+}
+
+#[test]
+fn test_golden_metrics() {
+    check(Builder::new().build_metrics(true), "metrics");
+}
+
+#[test]
+fn test_golden_validate() {
+    check_proto(
+        Builder::new().validate_requests(true),
+        "validated.proto",
+        "validate",
+    );
+}
+
+#[test]
+fn test_golden_runtime_helpers() {
+    check(Builder::new().runtime_helpers(true), "runtime_helpers");
+}
+
+#[test]
+fn test_golden_proto_set() {
+    let protos = manifest_dir().join("tests/protos");
+    let include = manifest_dir().join("../examples/include");
+    // Builders generating code for different inputs share a single parse.
+    let set = Builder::new().parse_protos(
+        &[protos.join("golden.proto"), protos.join("documented.proto")],
+        &[&protos, &include],
+    );
+    assert_eq!(
+        set.inputs().collect::<Vec<_>>(),
+        ["golden.proto", "documented.proto"]
+    );
+    let golden = manifest_dir().join("tests/golden");
+    assert_golden(
+        &Builder::new().generate_set(&set, &[protos.join("golden.proto")]),
+        golden.join("default"),
+    );
+    assert_golden(
+        &Builder::new()
+            .build_server(false)
+            .api_docs("API.md")
+            .generate_set(&set, &["documented.proto"]),
+        golden.join("api_docs"),
+    );
+}
+
+#[test]
+fn test_golden_descriptor_set() {
+    let protos = manifest_dir().join("tests/protos");
+    let include = manifest_dir().join("../examples/include");
+    let parsed = protobuf_parse::Parser::new()
+        .protoc()
+        .inputs([protos.join("golden.proto"), protos.join("documented.proto")])
+        .includes([&protos, &include])
+        .parse_and_typecheck()
+        .unwrap();
+    let mut fds = protobuf::descriptor::FileDescriptorSet::new();
+    fds.file = parsed.file_descriptors;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("image.binpb");
+    std::fs::write(&path, fds.write_to_bytes().unwrap()).unwrap();
+
+    let set = ProtoSet::load_descriptor_set(&path, &["golden.proto"]).unwrap();
+    assert_golden(
+        &Builder::new().generate_set(&set, &["golden.proto"]),
+        manifest_dir().join("tests/golden/default"),
+    );
+    let err = ProtoSet::load_descriptor_set(&path, &["missing.proto"]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
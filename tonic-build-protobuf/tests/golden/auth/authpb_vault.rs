@@ -0,0 +1,406 @@
+/// Generated client implementations.
+pub mod vault_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct VaultClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl VaultClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> VaultClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> VaultClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            VaultClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::auth::GetRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::auth::GetResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static("/authpb.Vault/Get");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("authpb.Vault", "Get"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+    /// Adds credentials to the metadata of every request.
+    #[derive(Debug, Clone)]
+    pub struct AuthInterceptor {
+        key: tonic::metadata::AsciiMetadataKey,
+        value: tonic::metadata::AsciiMetadataValue,
+    }
+    impl AuthInterceptor {
+        /// Create an interceptor setting the metadata `key` to
+        /// `value`, which is marked sensitive.
+        pub fn new(
+            key: tonic::metadata::AsciiMetadataKey,
+            mut value: tonic::metadata::AsciiMetadataValue,
+        ) -> Self {
+            value.set_sensitive(true);
+            Self { key, value }
+        }
+    }
+    impl tonic::service::Interceptor for AuthInterceptor {
+        fn call(
+            &mut self,
+            mut request: tonic::Request<()>,
+        ) -> Result<tonic::Request<()>, tonic::Status> {
+            request.metadata_mut().insert(self.key.clone(), self.value.clone());
+            Ok(request)
+        }
+    }
+    impl VaultClient<tonic::transport::Channel> {
+        /// Create a client sending `token` as bearer token in the
+        /// `authorization` metadata of every request.
+        pub fn with_bearer(
+            channel: tonic::transport::Channel,
+            token: &str,
+        ) -> Result<
+            VaultClient<
+                tonic::codegen::InterceptedService<
+                    tonic::transport::Channel,
+                    AuthInterceptor,
+                >,
+            >,
+            tonic::metadata::errors::InvalidMetadataValue,
+        > {
+            let value = format!("Bearer {}", token).parse()?;
+            Ok(
+                Self::with_interceptor(
+                    channel,
+                    AuthInterceptor::new(
+                        tonic::metadata::AsciiMetadataKey::from_static("authorization"),
+                        value,
+                    ),
+                ),
+            )
+        }
+        /// Create a client sending `key` in the `x-vault-key` metadata of every request.
+        pub fn with_api_key(
+            channel: tonic::transport::Channel,
+            key: &str,
+        ) -> Result<
+            VaultClient<
+                tonic::codegen::InterceptedService<
+                    tonic::transport::Channel,
+                    AuthInterceptor,
+                >,
+            >,
+            tonic::metadata::errors::InvalidMetadataValue,
+        > {
+            let value = key.parse()?;
+            Ok(
+                Self::with_interceptor(
+                    channel,
+                    AuthInterceptor::new(
+                        tonic::metadata::AsciiMetadataKey::from_static("x-vault-key"),
+                        value,
+                    ),
+                ),
+            )
+        }
+        /// Attempt to create a new client by connecting to the given
+        /// endpoint with the client certificate of `identity`, trusting
+        /// the server certificates signed by `ca`.
+        ///
+        /// Requires the `tls` feature of tonic.
+        pub async fn connect_mtls<D>(
+            dst: D,
+            identity: tonic::transport::Identity,
+            ca: tonic::transport::Certificate,
+        ) -> Result<VaultClient<tonic::transport::Channel>, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let tls = tonic::transport::ClientTlsConfig::new()
+                .identity(identity)
+                .ca_certificate(ca);
+            let channel = tonic::transport::Endpoint::new(dst)?
+                .tls_config(tls)?
+                .connect()
+                .await?;
+            Ok(Self::new(channel))
+        }
+    }
+}
+/// Generated server implementations.
+pub mod vault_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with VaultServer.
+    #[async_trait]
+    pub trait Vault: Send + Sync + 'static {
+        async fn get(
+            &self,
+            request: tonic::Request<super::auth::GetRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::auth::GetResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct VaultServer<T: Vault> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Vault> VaultServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for VaultServer<T>
+    where
+        T: Vault,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/authpb.Vault/Get" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSvc<T: Vault>(pub Arc<T>);
+                    impl<T: Vault> tonic::server::UnaryService<super::auth::GetRequest>
+                    for GetSvc<T> {
+                        type Response = super::auth::GetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::auth::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Vault>::get(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: Vault> Clone for VaultServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: Vault> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: Vault> tonic::server::NamedService for VaultServer<T> {
+        const NAME: &'static str = "authpb.Vault";
+    }
+    /// The fully qualified name of the service.
+    pub const SERVICE_NAME: &str = "authpb.Vault";
+    /// The path prefix of all methods of the service, i.e.
+    /// `/{SERVICE_NAME}`.
+    pub const SERVICE_PATH: &str = "/authpb.Vault";
+    /// The path of the codec used by the service, as configured in
+    /// codegen.
+    pub const CODEC_PATH: &str = "::tonic_codec_protobuf::ProtobufCodecV3";
+    /// Returns `inner` as routes that can be mounted on an existing router.
+    ///
+    /// To serve the service at a custom prefix, convert the routes with
+    /// `into_router()` and nest them with axum's
+    /// `Router::nest_service(prefix, router)`. Clients reach it by
+    /// connecting with an origin that includes the prefix, see
+    /// `with_origin`.
+    pub fn routes<T: Vault>(inner: T) -> tonic::transport::server::Routes {
+        tonic::transport::server::Routes::new(VaultServer::new(inner))
+    }
+}
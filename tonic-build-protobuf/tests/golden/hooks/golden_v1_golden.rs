@@ -0,0 +1,624 @@
+/// Generated client implementations.
+pub mod golden_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use crate::platform;
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct GoldenClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl GoldenClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> GoldenClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> GoldenClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            GoldenClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn unary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            let request = platform::authorize(
+                "/golden.v1.Golden/Unary",
+                request.into_request(),
+            );
+            let response = async move {
+                self.inner
+                    .ready()
+                    .await
+                    .map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                let path = http::uri::PathAndQuery::from_static(
+                    "/golden.v1.Golden/Unary",
+                );
+                let mut req = request.into_request();
+                req.extensions_mut()
+                    .insert(GrpcMethod::new("golden.v1.Golden", "Unary"));
+                self.inner.unary(req, path, codec).await
+            }
+                .await;
+            platform::record("/golden.v1.Golden/Unary", &response);
+            response
+        }
+        pub async fn client_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            let request = platform::authorize(
+                "/golden.v1.Golden/ClientStreaming",
+                request.into_request(),
+            );
+            let response = async move {
+                self.inner
+                    .ready()
+                    .await
+                    .map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                let path = http::uri::PathAndQuery::from_static(
+                    "/golden.v1.Golden/ClientStreaming",
+                );
+                let mut req = request.into_streaming_request();
+                req.extensions_mut()
+                    .insert(GrpcMethod::new("golden.v1.Golden", "ClientStreaming"));
+                self.inner.client_streaming(req, path, codec).await
+            }
+                .await;
+            platform::record("/golden.v1.Golden/ClientStreaming", &response);
+            response
+        }
+        pub async fn server_streaming(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            let request = platform::authorize(
+                "/golden.v1.Golden/ServerStreaming",
+                request.into_request(),
+            );
+            let response = async move {
+                self.inner
+                    .ready()
+                    .await
+                    .map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                let path = http::uri::PathAndQuery::from_static(
+                    "/golden.v1.Golden/ServerStreaming",
+                );
+                let mut req = request.into_request();
+                req.extensions_mut()
+                    .insert(GrpcMethod::new("golden.v1.Golden", "ServerStreaming"));
+                self.inner.server_streaming(req, path, codec).await
+            }
+                .await;
+            platform::record("/golden.v1.Golden/ServerStreaming", &response);
+            response
+        }
+        pub async fn bidirectional_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            let request = platform::authorize(
+                "/golden.v1.Golden/BidirectionalStreaming",
+                request.into_request(),
+            );
+            let response = async move {
+                self.inner
+                    .ready()
+                    .await
+                    .map_err(|e| {
+                        tonic::Status::new(
+                            tonic::Code::Unknown,
+                            format!("Service was not ready: {}", e.into()),
+                        )
+                    })?;
+                let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                let path = http::uri::PathAndQuery::from_static(
+                    "/golden.v1.Golden/BidirectionalStreaming",
+                );
+                let mut req = request.into_streaming_request();
+                req.extensions_mut()
+                    .insert(
+                        GrpcMethod::new("golden.v1.Golden", "BidirectionalStreaming"),
+                    );
+                self.inner.streaming(req, path, codec).await
+            }
+                .await;
+            platform::record("/golden.v1.Golden/BidirectionalStreaming", &response);
+            response
+        }
+    }
+    /// Custom options of the service.
+    pub mod service_options {
+        /// `(golden.v1.owner)`
+        pub const OWNER: &str = "storage-team";
+        /// `(golden.v1.tags)`
+        pub const TAGS: &[&str] = &["storage", "internal"];
+        /// `(golden.v1.tier)`
+        pub const TIER: &str = "TIER_CRITICAL";
+    }
+}
+/// Generated server implementations.
+pub mod golden_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use crate::platform;
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with GoldenServer.
+    #[async_trait]
+    pub trait Golden: Send + Sync + 'static {
+        async fn unary(
+            &self,
+            request: tonic::Request<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        >;
+        async fn client_streaming(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::golden::Request>>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ServerStreaming method.
+        type ServerStreamingStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::golden::Response, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn server_streaming(
+            &self,
+            request: tonic::Request<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ServerStreamingStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the BidirectionalStreaming method.
+        type BidirectionalStreamingStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::golden::Response, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn bidirectional_streaming(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::golden::Request>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::BidirectionalStreamingStream>,
+            tonic::Status,
+        >;
+        /// The name of the service, for the platform wrappers.
+        fn platform_name(&self) -> &'static str {
+            "golden.v1.Golden"
+        }
+    }
+    #[derive(Debug)]
+    pub struct GoldenServer<T: Golden> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Golden> GoldenServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for GoldenServer<T>
+    where
+        T: Golden,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/golden.v1.Golden/Unary" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnarySvc<T: Golden>(pub Arc<T>);
+                    impl<T: Golden> tonic::server::UnaryService<super::golden::Request>
+                    for UnarySvc<T> {
+                        type Response = super::golden::Response;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::golden::Request>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Golden>::unary(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnarySvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/golden.v1.Golden/ClientStreaming" => {
+                    #[allow(non_camel_case_types)]
+                    struct ClientStreamingSvc<T: Golden>(pub Arc<T>);
+                    impl<
+                        T: Golden,
+                    > tonic::server::ClientStreamingService<super::golden::Request>
+                    for ClientStreamingSvc<T> {
+                        type Response = super::golden::Response;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::golden::Request>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Golden>::client_streaming(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ClientStreamingSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/golden.v1.Golden/ServerStreaming" => {
+                    #[allow(non_camel_case_types)]
+                    struct ServerStreamingSvc<T: Golden>(pub Arc<T>);
+                    impl<
+                        T: Golden,
+                    > tonic::server::ServerStreamingService<super::golden::Request>
+                    for ServerStreamingSvc<T> {
+                        type Response = super::golden::Response;
+                        type ResponseStream = T::ServerStreamingStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::golden::Request>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Golden>::server_streaming(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ServerStreamingSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/golden.v1.Golden/BidirectionalStreaming" => {
+                    #[allow(non_camel_case_types)]
+                    struct BidirectionalStreamingSvc<T: Golden>(pub Arc<T>);
+                    impl<
+                        T: Golden,
+                    > tonic::server::StreamingService<super::golden::Request>
+                    for BidirectionalStreamingSvc<T> {
+                        type Response = super::golden::Response;
+                        type ResponseStream = T::BidirectionalStreamingStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::golden::Request>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Golden>::bidirectional_streaming(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BidirectionalStreamingSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: Golden> Clone for GoldenServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: Golden> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: Golden> tonic::server::NamedService for GoldenServer<T> {
+        const NAME: &'static str = "golden.v1.Golden";
+    }
+    /// Custom options of the service.
+    pub mod service_options {
+        /// `(golden.v1.owner)`
+        pub const OWNER: &str = "storage-team";
+        /// `(golden.v1.tags)`
+        pub const TAGS: &[&str] = &["storage", "internal"];
+        /// `(golden.v1.tier)`
+        pub const TIER: &str = "TIER_CRITICAL";
+    }
+    /// The fully qualified name of the service.
+    pub const SERVICE_NAME: &str = "golden.v1.Golden";
+    /// The path prefix of all methods of the service, i.e.
+    /// `/{SERVICE_NAME}`.
+    pub const SERVICE_PATH: &str = "/golden.v1.Golden";
+    /// The path of the codec used by the service, as configured in
+    /// codegen.
+    pub const CODEC_PATH: &str = "::tonic_codec_protobuf::ProtobufCodecV3";
+    /// Returns `inner` as routes that can be mounted on an existing router.
+    ///
+    /// To serve the service at a custom prefix, convert the routes with
+    /// `into_router()` and nest them with axum's
+    /// `Router::nest_service(prefix, router)`. Clients reach it by
+    /// connecting with an origin that includes the prefix, see
+    /// `with_origin`.
+    pub fn routes<T: Golden>(inner: T) -> tonic::transport::server::Routes {
+        tonic::transport::server::Routes::new(GoldenServer::new(inner))
+    }
+}
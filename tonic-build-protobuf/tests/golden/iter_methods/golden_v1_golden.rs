@@ -0,0 +1,227 @@
+/// Generated client implementations.
+pub mod golden_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct GoldenClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl GoldenClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> GoldenClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> GoldenClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            GoldenClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn unary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static("/golden.v1.Golden/Unary");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("golden.v1.Golden", "Unary"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn client_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/golden.v1.Golden/ClientStreaming",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("golden.v1.Golden", "ClientStreaming"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn server_streaming(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/golden.v1.Golden/ServerStreaming",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("golden.v1.Golden", "ServerStreaming"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn bidirectional_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/golden.v1.Golden/BidirectionalStreaming",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("golden.v1.Golden", "BidirectionalStreaming"));
+            self.inner.streaming(req, path, codec).await
+        }
+    }
+    impl<T> GoldenClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        /// Like [`Self::client_streaming`], but sends the messages of `requests`.
+        pub async fn client_streaming_iter<I>(
+            &mut self,
+            requests: I,
+        ) -> std::result::Result<tonic::Response<super::golden::Response>, tonic::Status>
+        where
+            I: IntoIterator<Item = super::golden::Request>,
+            I::IntoIter: std::marker::Send + 'static,
+        {
+            self.client_streaming(tonic::codegen::tokio_stream::iter(requests)).await
+        }
+        /// Like [`Self::bidirectional_streaming`], but sends the messages of `requests`.
+        pub async fn bidirectional_streaming_iter<I>(
+            &mut self,
+            requests: I,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        >
+        where
+            I: IntoIterator<Item = super::golden::Request>,
+            I::IntoIter: std::marker::Send + 'static,
+        {
+            self.bidirectional_streaming(tonic::codegen::tokio_stream::iter(requests))
+                .await
+        }
+    }
+    /// Custom options of the service.
+    pub mod service_options {
+        /// `(golden.v1.owner)`
+        pub const OWNER: &str = "storage-team";
+        /// `(golden.v1.tags)`
+        pub const TAGS: &[&str] = &["storage", "internal"];
+        /// `(golden.v1.tier)`
+        pub const TIER: &str = "TIER_CRITICAL";
+    }
+}
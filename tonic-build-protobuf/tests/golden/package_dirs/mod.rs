@@ -0,0 +1,7 @@
+pub mod golden {
+    pub mod v1 {
+        include!("golden/v1/golden.rs");
+        pub use golden_client::GoldenClient;
+        pub use golden_server::{Golden, GoldenServer};
+    }
+}
@@ -0,0 +1,438 @@
+/// Generated client implementations.
+pub mod golden_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct GoldenClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl GoldenClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> GoldenClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> GoldenClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            GoldenClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn unary(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            ::tonic_runtime_protobuf::client::unary(
+                    &mut self.inner,
+                    request.into_request(),
+                    "/golden.v1.Golden/Unary",
+                    "golden.v1.Golden",
+                    "Unary",
+                    ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                )
+                .await
+        }
+        pub async fn client_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        > {
+            ::tonic_runtime_protobuf::client::client_streaming(
+                    &mut self.inner,
+                    request.into_streaming_request(),
+                    "/golden.v1.Golden/ClientStreaming",
+                    "golden.v1.Golden",
+                    "ClientStreaming",
+                    ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                )
+                .await
+        }
+        pub async fn server_streaming(
+            &mut self,
+            request: impl tonic::IntoRequest<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            ::tonic_runtime_protobuf::client::server_streaming(
+                    &mut self.inner,
+                    request.into_request(),
+                    "/golden.v1.Golden/ServerStreaming",
+                    "golden.v1.Golden",
+                    "ServerStreaming",
+                    ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                )
+                .await
+        }
+        pub async fn bidirectional_streaming(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::golden::Response>>,
+            tonic::Status,
+        > {
+            ::tonic_runtime_protobuf::client::streaming(
+                    &mut self.inner,
+                    request.into_streaming_request(),
+                    "/golden.v1.Golden/BidirectionalStreaming",
+                    "golden.v1.Golden",
+                    "BidirectionalStreaming",
+                    ::tonic_codec_protobuf::ProtobufCodecV3::default(),
+                )
+                .await
+        }
+    }
+    /// Custom options of the service.
+    pub mod service_options {
+        /// `(golden.v1.owner)`
+        pub const OWNER: &str = "storage-team";
+        /// `(golden.v1.tags)`
+        pub const TAGS: &[&str] = &["storage", "internal"];
+        /// `(golden.v1.tier)`
+        pub const TIER: &str = "TIER_CRITICAL";
+    }
+}
+/// Generated server implementations.
+pub mod golden_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with GoldenServer.
+    #[async_trait]
+    pub trait Golden: Send + Sync + 'static {
+        async fn unary(
+            &self,
+            request: tonic::Request<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        >;
+        async fn client_streaming(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::golden::Request>>,
+        ) -> std::result::Result<
+            tonic::Response<super::golden::Response>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ServerStreaming method.
+        type ServerStreamingStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::golden::Response, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn server_streaming(
+            &self,
+            request: tonic::Request<super::golden::Request>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ServerStreamingStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the BidirectionalStreaming method.
+        type BidirectionalStreamingStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::golden::Response, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn bidirectional_streaming(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::golden::Request>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::BidirectionalStreamingStream>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct GoldenServer<T: Golden> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Golden> GoldenServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for GoldenServer<T>
+    where
+        T: Golden,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            let config = ::tonic_runtime_protobuf::server::Config {
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            };
+            match req.uri().path() {
+                "/golden.v1.Golden/Unary" => {
+                    let service = Arc::clone(&inner.0);
+                    let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                    ::tonic_runtime_protobuf::server::unary(
+                        req,
+                        codec,
+                        config,
+                        move |request: tonic::Request<super::golden::Request>| {
+                            let inner = Arc::clone(&service);
+                            let fut = async move {
+                                <T as Golden>::unary(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        },
+                    )
+                }
+                "/golden.v1.Golden/ClientStreaming" => {
+                    let service = Arc::clone(&inner.0);
+                    let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                    ::tonic_runtime_protobuf::server::client_streaming(
+                        req,
+                        codec,
+                        config,
+                        move |
+                            request: tonic::Request<
+                                tonic::Streaming<super::golden::Request>,
+                            >|
+                        {
+                            let inner = Arc::clone(&service);
+                            let fut = async move {
+                                <T as Golden>::client_streaming(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        },
+                    )
+                }
+                "/golden.v1.Golden/ServerStreaming" => {
+                    let service = Arc::clone(&inner.0);
+                    let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                    ::tonic_runtime_protobuf::server::server_streaming(
+                        req,
+                        codec,
+                        config,
+                        move |request: tonic::Request<super::golden::Request>| {
+                            let inner = Arc::clone(&service);
+                            let fut = async move {
+                                <T as Golden>::server_streaming(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        },
+                    )
+                }
+                "/golden.v1.Golden/BidirectionalStreaming" => {
+                    let service = Arc::clone(&inner.0);
+                    let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                    ::tonic_runtime_protobuf::server::streaming(
+                        req,
+                        codec,
+                        config,
+                        move |
+                            request: tonic::Request<
+                                tonic::Streaming<super::golden::Request>,
+                            >|
+                        {
+                            let inner = Arc::clone(&service);
+                            let fut = async move {
+                                <T as Golden>::bidirectional_streaming(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        },
+                    )
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: Golden> Clone for GoldenServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: Golden> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: Golden> tonic::server::NamedService for GoldenServer<T> {
+        const NAME: &'static str = "golden.v1.Golden";
+    }
+    /// Custom options of the service.
+    pub mod service_options {
+        /// `(golden.v1.owner)`
+        pub const OWNER: &str = "storage-team";
+        /// `(golden.v1.tags)`
+        pub const TAGS: &[&str] = &["storage", "internal"];
+        /// `(golden.v1.tier)`
+        pub const TIER: &str = "TIER_CRITICAL";
+    }
+    /// The fully qualified name of the service.
+    pub const SERVICE_NAME: &str = "golden.v1.Golden";
+    /// The path prefix of all methods of the service, i.e.
+    /// `/{SERVICE_NAME}`.
+    pub const SERVICE_PATH: &str = "/golden.v1.Golden";
+    /// The path of the codec used by the service, as configured in
+    /// codegen.
+    pub const CODEC_PATH: &str = "::tonic_codec_protobuf::ProtobufCodecV3";
+    /// Returns `inner` as routes that can be mounted on an existing router.
+    ///
+    /// To serve the service at a custom prefix, convert the routes with
+    /// `into_router()` and nest them with axum's
+    /// `Router::nest_service(prefix, router)`. Clients reach it by
+    /// connecting with an origin that includes the prefix, see
+    /// `with_origin`.
+    pub fn routes<T: Golden>(inner: T) -> tonic::transport::server::Routes {
+        tonic::transport::server::Routes::new(GoldenServer::new(inner))
+    }
+}
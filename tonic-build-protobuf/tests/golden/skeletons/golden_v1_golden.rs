@@ -0,0 +1,46 @@
+/// Implementation of the `golden.v1.Golden` service.
+///
+/// Serve it with `crate::services::golden::v1::GoldenServer::new(GoldenService::default())`.
+#[derive(Debug, Default)]
+pub struct GoldenService {}
+#[tonic::async_trait]
+impl crate::services::golden::v1::Golden for GoldenService {
+    async fn unary(
+        &self,
+        _request: tonic::Request<crate::golden::Request>,
+    ) -> Result<tonic::Response<crate::golden::Response>, tonic::Status> {
+        todo!()
+    }
+    async fn client_streaming(
+        &self,
+        _request: tonic::Request<tonic::Streaming<crate::golden::Request>>,
+    ) -> Result<tonic::Response<crate::golden::Response>, tonic::Status> {
+        todo!()
+    }
+    type ServerStreamingStream = std::pin::Pin<
+        Box<
+            dyn tonic::codegen::tokio_stream::Stream<
+                Item = Result<crate::golden::Response, tonic::Status>,
+            > + Send,
+        >,
+    >;
+    async fn server_streaming(
+        &self,
+        _request: tonic::Request<crate::golden::Request>,
+    ) -> Result<tonic::Response<Self::ServerStreamingStream>, tonic::Status> {
+        todo!()
+    }
+    type BidirectionalStreamingStream = std::pin::Pin<
+        Box<
+            dyn tonic::codegen::tokio_stream::Stream<
+                Item = Result<crate::golden::Response, tonic::Status>,
+            > + Send,
+        >,
+    >;
+    async fn bidirectional_streaming(
+        &self,
+        _request: tonic::Request<tonic::Streaming<crate::golden::Request>>,
+    ) -> Result<tonic::Response<Self::BidirectionalStreamingStream>, tonic::Status> {
+        todo!()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod unpackaged {
+    include!("unpackaged_greeter.rs");
+    pub use greeter_client::GreeterClient;
+    pub use greeter_server::{Greeter, GreeterServer};
+}
@@ -0,0 +1,768 @@
+/// Generated client implementations.
+pub mod accounts_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct AccountsClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl AccountsClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> AccountsClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> AccountsClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            AccountsClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn create_account(
+            &mut self,
+            request: impl tonic::IntoRequest<super::validated::CreateAccountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::validated::Account>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/validated.v1.Accounts/CreateAccount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("validated.v1.Accounts", "CreateAccount"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn import_accounts(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::validated::CreateAccountRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::validated::Account>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/validated.v1.Accounts/ImportAccounts",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("validated.v1.Accounts", "ImportAccounts"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn watch_account(
+            &mut self,
+            request: impl tonic::IntoRequest<super::validated::WatchAccountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::validated::Account>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/validated.v1.Accounts/WatchAccount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("validated.v1.Accounts", "WatchAccount"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod accounts_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AccountsServer.
+    #[async_trait]
+    pub trait Accounts: Send + Sync + 'static {
+        async fn create_account(
+            &self,
+            request: tonic::Request<super::validated::CreateAccountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::validated::Account>,
+            tonic::Status,
+        >;
+        async fn import_accounts(
+            &self,
+            request: tonic::Request<
+                tonic::Streaming<super::validated::CreateAccountRequest>,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::validated::Account>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the WatchAccount method.
+        type WatchAccountStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::validated::Account, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn watch_account(
+            &self,
+            request: tonic::Request<super::validated::WatchAccountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchAccountStream>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct AccountsServer<T: Accounts> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Accounts> AccountsServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for AccountsServer<T>
+    where
+        T: Accounts,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/validated.v1.Accounts/CreateAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateAccountSvc<T: Accounts>(pub Arc<T>);
+                    impl<
+                        T: Accounts,
+                    > tonic::server::UnaryService<super::validated::CreateAccountRequest>
+                    for CreateAccountSvc<T> {
+                        type Response = super::validated::Account;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::validated::CreateAccountRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                let mut details = ::tonic_types_protobuf::ErrorDetails::new();
+                                validate_validated_v1_create_account_request(
+                                    request.get_ref(),
+                                    "",
+                                    &mut details,
+                                );
+                                if let Some(status) = validation_status(details) {
+                                    return Err(status);
+                                }
+                                async move {
+                                    <T as Accounts>::create_account(&inner, request).await
+                                }
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateAccountSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/validated.v1.Accounts/ImportAccounts" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportAccountsSvc<T: Accounts>(pub Arc<T>);
+                    impl<
+                        T: Accounts,
+                    > tonic::server::ClientStreamingService<
+                        super::validated::CreateAccountRequest,
+                    > for ImportAccountsSvc<T> {
+                        type Response = super::validated::Account;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::validated::CreateAccountRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                let mut details = ::tonic_types_protobuf::ErrorDetails::new();
+                                validate_validated_v1_create_account_request(
+                                    request.get_ref(),
+                                    "",
+                                    &mut details,
+                                );
+                                if let Some(status) = validation_status(details) {
+                                    return Err(status);
+                                }
+                                async move {
+                                    <T as Accounts>::import_accounts(&inner, request).await
+                                }
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportAccountsSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/validated.v1.Accounts/WatchAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchAccountSvc<T: Accounts>(pub Arc<T>);
+                    impl<
+                        T: Accounts,
+                    > tonic::server::ServerStreamingService<
+                        super::validated::WatchAccountRequest,
+                    > for WatchAccountSvc<T> {
+                        type Response = super::validated::Account;
+                        type ResponseStream = T::WatchAccountStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::validated::WatchAccountRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                let mut details = ::tonic_types_protobuf::ErrorDetails::new();
+                                validate_validated_v1_watch_account_request(
+                                    request.get_ref(),
+                                    "",
+                                    &mut details,
+                                );
+                                if let Some(status) = validation_status(details) {
+                                    return Err(status);
+                                }
+                                async move {
+                                    <T as Accounts>::watch_account(&inner, request).await
+                                }
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchAccountSvc(inner);
+                        let codec = ::tonic_codec_protobuf::ProtobufCodecV3::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: Accounts> Clone for AccountsServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: Accounts> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: Accounts> tonic::server::NamedService for AccountsServer<T> {
+        const NAME: &'static str = "validated.v1.Accounts";
+    }
+    /// The fully qualified name of the service.
+    pub const SERVICE_NAME: &str = "validated.v1.Accounts";
+    /// The path prefix of all methods of the service, i.e.
+    /// `/{SERVICE_NAME}`.
+    pub const SERVICE_PATH: &str = "/validated.v1.Accounts";
+    /// The path of the codec used by the service, as configured in
+    /// codegen.
+    pub const CODEC_PATH: &str = "::tonic_codec_protobuf::ProtobufCodecV3";
+    /// Returns `inner` as routes that can be mounted on an existing router.
+    ///
+    /// To serve the service at a custom prefix, convert the routes with
+    /// `into_router()` and nest them with axum's
+    /// `Router::nest_service(prefix, router)`. Clients reach it by
+    /// connecting with an origin that includes the prefix, see
+    /// `with_origin`.
+    pub fn routes<T: Accounts>(inner: T) -> tonic::transport::server::Routes {
+        tonic::transport::server::Routes::new(AccountsServer::new(inner))
+    }
+    /// Checks a `validated.v1.Address` against its validation rules, adding a field violation to `details` for every violated rule, with the field path prefixed by `prefix`.
+    pub fn validate_validated_v1_address(
+        message: &super::validated::Address,
+        prefix: &str,
+        details: &mut ::tonic_types_protobuf::ErrorDetails,
+    ) {
+        {
+            let value = &message.country;
+            let value: &str = value;
+            if value.chars().count() != 2 {
+                details
+                    .add_bad_request_violation(
+                        format!("{}country", prefix),
+                        "value length must be 2 runes",
+                    );
+            }
+            {
+                const LIST: [&str; 2] = ["NL", "US"];
+                if !LIST.contains(&value) {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}country", prefix),
+                            "value must be in list [\"NL\", \"US\"]",
+                        );
+                }
+            }
+        }
+        {
+            let value = &message.latitude;
+            if !(*value >= -90.0 && *value <= 90.0) {
+                details
+                    .add_bad_request_violation(
+                        format!("{}latitude", prefix),
+                        "value must be greater than or equal to -90 and less than or equal to 90",
+                    );
+            }
+        }
+    }
+    /// Checks a `validated.v1.CreateAccountRequest` against its validation rules, adding a field violation to `details` for every violated rule, with the field path prefixed by `prefix`.
+    pub fn validate_validated_v1_create_account_request(
+        message: &super::validated::CreateAccountRequest,
+        prefix: &str,
+        details: &mut ::tonic_types_protobuf::ErrorDetails,
+    ) {
+        if message.contact.is_none() {
+            details
+                .add_bad_request_violation(
+                    format!("{}contact", prefix),
+                    "value is required",
+                );
+        }
+        {
+            let value = &message.name;
+            let value: &str = value;
+            if value.chars().count() < 1 {
+                details
+                    .add_bad_request_violation(
+                        format!("{}name", prefix),
+                        "value length must be at least 1 runes",
+                    );
+            }
+            if value.chars().count() > 64 {
+                details
+                    .add_bad_request_violation(
+                        format!("{}name", prefix),
+                        "value length must be at most 64 runes",
+                    );
+            }
+        }
+        {
+            let value = &message.email;
+            let value: &str = value;
+            {
+                static PATTERN: ::std::sync::OnceLock<::regex::Regex> = ::std::sync::OnceLock::new();
+                let pattern = PATTERN
+                    .get_or_init(|| {
+                        ::regex::Regex::new("^[^@]+@[^@]+$").expect("invalid pattern")
+                    });
+                if !pattern.is_match(value) {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}email", prefix),
+                            "value does not match regex pattern \"^[^@]+@[^@]+$\"",
+                        );
+                }
+            }
+        }
+        {
+            let value = &message.age;
+            if !(*value >= 13 && *value < 150) {
+                details
+                    .add_bad_request_violation(
+                        format!("{}age", prefix),
+                        "value must be greater than or equal to 13 and less than 150",
+                    );
+            }
+        }
+        {
+            let value = &message.plan;
+            if value.enum_value().is_err() {
+                details
+                    .add_bad_request_violation(
+                        format!("{}plan", prefix),
+                        "value must be one of the defined enum values",
+                    );
+            }
+            {
+                const LIST: [i32; 1] = [0];
+                if LIST.contains(&value.value()) {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}plan", prefix),
+                            "value must not be in list [0]",
+                        );
+                }
+            }
+        }
+        {
+            let values = &message.tags;
+            if values.len() > 8 {
+                details
+                    .add_bad_request_violation(
+                        format!("{}tags", prefix),
+                        "value must contain no more than 8 item(s)",
+                    );
+            }
+            let mut seen = ::std::collections::HashSet::new();
+            if !values.iter().all(|value| seen.insert(value)) {
+                details
+                    .add_bad_request_violation(
+                        format!("{}tags", prefix),
+                        "repeated value must contain unique items",
+                    );
+            }
+            for (i, value) in values.iter().enumerate() {
+                let value: &str = value;
+                if value.chars().count() < 1 {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}tags[{}]", prefix, i),
+                            "value length must be at least 1 runes",
+                        );
+                }
+            }
+        }
+        {
+            let values = &message.quotas;
+            for (key, value) in values.iter() {
+                if !(*value >= 0) {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}quotas[{:?}]", prefix, key),
+                            "value must be greater than or equal to 0",
+                        );
+                }
+            }
+        }
+        match message.address.as_ref() {
+            Some(value) => {
+                validate_validated_v1_address(
+                    value,
+                    &format!("{}address.", prefix),
+                    details,
+                );
+            }
+            None => {
+                details
+                    .add_bad_request_violation(
+                        format!("{}address", prefix),
+                        "value is required",
+                    );
+            }
+        }
+        if let Some(value) = &message.referrer {
+            let value: &str = value;
+            {
+                let uuid = value.len() == 36
+                    && value
+                        .char_indices()
+                        .all(|(i, c)| match i {
+                            8 | 13 | 18 | 23 => c == '-',
+                            _ => c.is_ascii_hexdigit(),
+                        });
+                if !uuid {
+                    details
+                        .add_bad_request_violation(
+                            format!("{}referrer", prefix),
+                            "value must be a valid UUID",
+                        );
+                }
+            }
+        }
+        if let Some(super::validated::create_account_request::Contact::Phone(value)) = &message
+            .contact
+        {
+            let value: &str = value;
+            if !value.starts_with("+") {
+                details
+                    .add_bad_request_violation(
+                        format!("{}phone", prefix),
+                        "value does not have prefix \"+\"",
+                    );
+            }
+        }
+        if let Some(super::validated::create_account_request::Contact::Ip(value)) = &message
+            .contact
+        {
+            let value: &[u8] = value;
+            if !matches!(value.len(), 4 | 16) {
+                details
+                    .add_bad_request_violation(
+                        format!("{}ip", prefix),
+                        "value must be a valid IP address",
+                    );
+            }
+        }
+    }
+    /// Checks a `validated.v1.WatchAccountRequest` against its validation rules, adding a field violation to `details` for every violated rule, with the field path prefixed by `prefix`.
+    pub fn validate_validated_v1_watch_account_request(
+        message: &super::validated::WatchAccountRequest,
+        prefix: &str,
+        details: &mut ::tonic_types_protobuf::ErrorDetails,
+    ) {
+        if let Some(value) = message.address.as_ref() {
+            validate_validated_v1_address(
+                value,
+                &format!("{}address.", prefix),
+                details,
+            );
+        }
+    }
+    /// Returns an `INVALID_ARGUMENT` status carrying the field
+    /// violations in `details`, if there are any.
+    pub fn validation_status(
+        details: ::tonic_types_protobuf::ErrorDetails,
+    ) -> Option<tonic::Status> {
+        let violation = details.bad_request()?.field_violations.first()?;
+        let message = format!("invalid {}: {}", violation.field, violation.description);
+        Some(
+            <tonic::Status as ::tonic_types_protobuf::StatusExt>::with_error_details(
+                tonic::Code::InvalidArgument,
+                message,
+                details,
+            ),
+        )
+    }
+}
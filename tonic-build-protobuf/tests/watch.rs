@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    path::Path,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tonic_build_protobuf::watch::Watcher;
+
+fn service(name: &str) -> String {
+    format!(
+        r#"
+            syntax = "proto3";
+            package a.b;
+            service {name} {{
+                rpc Get({name}Request) returns ({name}Response) {{}}
+            }}
+            message {name}Request {{}}
+            message {name}Response {{}}
+        "#
+    )
+}
+
+#[test]
+fn test_poll() {
+    let tmp_dir = tempfile::TempDir::new().unwrap();
+    let nested = tmp_dir.path().join("a/b");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(nested.join("storage.proto"), service("Storage")).unwrap();
+    fs::write(nested.join("notes.txt"), "").unwrap();
+
+    let mut watcher = Watcher::new(&[tmp_dir.path()]);
+    assert_eq!(watcher.poll(), Some(vec![nested.join("storage.proto")]));
+    assert_eq!(watcher.poll(), None);
+
+    // Other files are ignored.
+    fs::write(nested.join("notes.txt"), "changed").unwrap();
+    assert_eq!(watcher.poll(), None);
+
+    fs::write(tmp_dir.path().join("admin.proto"), service("Admin")).unwrap();
+    assert_eq!(
+        watcher.poll(),
+        Some(vec![
+            tmp_dir.path().join("a/b/storage.proto"),
+            tmp_dir.path().join("admin.proto"),
+        ])
+    );
+    fs::write(nested.join("storage.proto"), service("Store")).unwrap();
+    assert_eq!(
+        watcher.poll(),
+        Some(vec![
+            tmp_dir.path().join("a/b/storage.proto"),
+            tmp_dir.path().join("admin.proto"),
+        ])
+    );
+    fs::remove_file(tmp_dir.path().join("admin.proto")).unwrap();
+    assert_eq!(watcher.poll(), Some(vec![nested.join("storage.proto")]));
+    assert_eq!(watcher.poll(), None);
+}
+
+/// Kills the watcher when dropped, e.g. when an assertion fails.
+struct Watch(Child);
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Waits for `path` to contain `text`.
+fn wait_for(path: &Path, text: &str) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !fs::read_to_string(path).is_ok_and(|content| content.contains(text)) {
+        assert!(
+            Instant::now() < deadline,
+            "{} never contained {}",
+            path.display(),
+            text
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_watch_binary() {
+    let tmp_dir = tempfile::TempDir::new().unwrap();
+    let protos = tmp_dir.path().join("proto");
+    let out = tmp_dir.path().join("generated");
+    fs::create_dir(&protos).unwrap();
+    fs::write(protos.join("storage.proto"), service("Storage")).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_tonic-rust-protobuf-watch"))
+        .arg("--out")
+        .arg(&out)
+        .args(["--param", "mod_file=mod.rs,build_client=false"])
+        .args(["--interval", "20"])
+        .arg(&protos)
+        .spawn()
+        .unwrap();
+    let _watch = Watch(child);
+    wait_for(&out.join("mod.rs"), "a_b_storage.rs");
+    let code = fs::read_to_string(out.join("a_b_storage.rs")).unwrap();
+    assert!(code.contains("StorageServer"), "{code}");
+    assert!(!code.contains("StorageClient"), "{code}");
+
+    fs::write(protos.join("admin.proto"), service("Admin")).unwrap();
+    wait_for(&out.join("mod.rs"), "a_b_admin.rs");
+}
+
+#[test]
+fn test_watch_binary_invalid_parameter() {
+    let output = Command::new(env!("CARGO_BIN_EXE_tonic-rust-protobuf-watch"))
+        .args([
+            "--out",
+            "generated",
+            "--param",
+            "build_client=yes",
+            "--once",
+            "proto",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid value \"yes\" of build_client, expected a bool"),
+        "{stderr}"
+    );
+}
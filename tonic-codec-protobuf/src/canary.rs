@@ -0,0 +1,325 @@
+use std::{fmt, marker::PhantomData, sync::Arc};
+
+use bytes::Buf;
+use protobuf::{
+    reflect::{MessageDescriptor, ReflectFieldRef},
+    MessageDyn, MessageFull,
+};
+
+use crate::{ProtobufEncoderV2, ProtobufEncoderV3};
+
+/// A difference between the messages decoded by rust-protobuf v2 and v3 from
+/// the same payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The full name of the message type, e.g. `helloworld.HelloRequest`.
+    pub message: String,
+    /// How the results differ.
+    pub kind: DivergenceKind,
+}
+
+/// How the results of rust-protobuf v2 and v3 differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// Only rust-protobuf v2 failed to decode the message, with the error.
+    V2Error(String),
+    /// Only rust-protobuf v3 failed to decode the message, with the error.
+    V3Error(String),
+    /// Both decoded the message, with different values of fields.
+    Fields(Vec<FieldDiff>),
+}
+
+/// A field whose values differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The name of the field.
+    pub field: String,
+    /// The value decoded by rust-protobuf v2.
+    pub v2: String,
+    /// The value decoded by rust-protobuf v3.
+    pub v3: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DivergenceKind::V2Error(e) => write!(f, "{}: only v2 failed: {}", self.message, e),
+            DivergenceKind::V3Error(e) => write!(f, "{}: only v3 failed: {}", self.message, e),
+            DivergenceKind::Fields(diffs) => {
+                write!(f, "{}: fields differ:", self.message)?;
+                for diff in diffs {
+                    write!(f, " {} (v2: {}, v3: {})", diff.field, diff.v2, diff.v3)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Receives the [`Divergence`]s found by canary codecs.
+///
+/// Reports are made while decoding, so they should be cheap, e.g. logging or
+/// counting them.
+pub trait DivergenceReporter: Clone + Send + 'static {
+    /// Reports that the libraries decoded a message differently.
+    fn report(&self, divergence: Divergence);
+}
+
+impl<F: Fn(Divergence) + Send + Sync + ?Sized + 'static> DivergenceReporter for Arc<F> {
+    fn report(&self, divergence: Divergence) {
+        self(divergence)
+    }
+}
+
+/// A [`Codec`](tonic::codec::Codec) that decodes every message with both
+/// rust-protobuf v2 and v3, serves the v2 message `U`, and reports how the v3
+/// message `U3` differs, e.g. before migrating a service to v3.
+///
+/// `U3` must be the v3 message generated from the same definition as `U`.
+/// Messages are encoded with v2.
+pub struct CanaryCodecV2<T, U, U3, R> {
+    reporter: R,
+    _pd: PhantomData<(T, U, U3)>,
+}
+
+/// A [`Codec`](tonic::codec::Codec) that decodes every message with both
+/// rust-protobuf v3 and v2, serves the v3 message `U`, and reports how the v2
+/// message `U2` differs, e.g. after migrating a service to v3.
+///
+/// `U2` must be the v2 message generated from the same definition as `U`.
+/// Messages are encoded with v3.
+pub struct CanaryCodecV3<T, U, U2, R> {
+    reporter: R,
+    _pd: PhantomData<(T, U, U2)>,
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that serves v2 messages `U`, and
+/// compares them with v3 messages `U3`.
+pub struct CanaryDecoderV2<U, U3, R> {
+    reporter: R,
+    _pd: PhantomData<(U, U3)>,
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that serves v3 messages `U`, and
+/// compares them with v2 messages `U2`.
+pub struct CanaryDecoderV3<U, U2, R> {
+    reporter: R,
+    _pd: PhantomData<(U, U2)>,
+}
+
+/// Implements the constructors and common traits of the canary codecs and
+/// decoders, whose phantom type parameters need no bounds.
+macro_rules! impl_canary_common {
+    ($($ty:ident<$($p:ident),*>),*) => {$(
+        impl<$($p,)* R> $ty<$($p,)* R> {
+            /// Create a codec that reports divergences to `reporter`.
+            pub fn new(reporter: R) -> Self {
+                $ty {
+                    reporter,
+                    _pd: PhantomData,
+                }
+            }
+        }
+
+        impl<$($p,)* R: Default> Default for $ty<$($p,)* R> {
+            fn default() -> Self {
+                $ty::new(R::default())
+            }
+        }
+
+        impl<$($p,)* R: Clone> Clone for $ty<$($p,)* R> {
+            fn clone(&self) -> Self {
+                $ty::new(self.reporter.clone())
+            }
+        }
+
+        impl<$($p,)* R> fmt::Debug for $ty<$($p,)* R> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($ty)).finish_non_exhaustive()
+            }
+        }
+    )*};
+}
+
+impl_canary_common!(
+    CanaryCodecV2<T, U, U3>,
+    CanaryCodecV3<T, U, U2>,
+    CanaryDecoderV2<U, U3>,
+    CanaryDecoderV3<U, U2>
+);
+
+/// Decodes `payload` with both libraries, and returns the results with how
+/// they differ, if they do.
+#[allow(clippy::type_complexity)]
+fn decode_both<U2, U3>(
+    payload: &[u8],
+) -> (Result<U2, String>, Result<U3, String>, Option<Divergence>)
+where
+    U2: protobuf2::Message,
+    U3: MessageFull,
+{
+    let v2 = U2::parse_from_bytes(payload).map_err(|e| e.to_string());
+    let v3 = U3::parse_from_bytes(payload).map_err(|e| e.to_string());
+    let descriptor = U3::descriptor();
+    let kind = match (&v2, &v3) {
+        (Ok(v2), Ok(v3)) => compare(&descriptor, v2, v3),
+        (Err(e), Ok(_)) => Some(DivergenceKind::V2Error(e.clone())),
+        (Ok(_), Err(e)) => Some(DivergenceKind::V3Error(e.clone())),
+        (Err(_), Err(_)) => None,
+    };
+    let divergence = kind.map(|kind| Divergence {
+        message: descriptor.full_name().to_owned(),
+        kind,
+    });
+    (v2, v3, divergence)
+}
+
+/// Compares the fields of the v2 message `v2` and the v3 message `v3`, by
+/// decoding the reencoded v2 message with the v3 `descriptor`.
+fn compare<U2: protobuf2::Message>(
+    descriptor: &MessageDescriptor,
+    v2: &U2,
+    v3: &dyn MessageDyn,
+) -> Option<DivergenceKind> {
+    let v2 = v2
+        .write_to_bytes()
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| {
+            descriptor
+                .parse_from_bytes(&bytes)
+                .map_err(|e| e.to_string())
+        });
+    let v2 = match v2 {
+        Ok(v2) => v2,
+        Err(e) => return Some(DivergenceKind::V2Error(e)),
+    };
+    let diffs: Vec<_> = descriptor
+        .fields()
+        .filter_map(|field| {
+            let (a, b) = (field.get_reflect(&*v2), field.get_reflect(v3));
+            (a != b).then(|| FieldDiff {
+                field: field.name().to_owned(),
+                v2: format_field(a),
+                v3: format_field(b),
+            })
+        })
+        .collect();
+    (!diffs.is_empty()).then_some(DivergenceKind::Fields(diffs))
+}
+
+fn format_field(field: ReflectFieldRef<'_>) -> String {
+    match field {
+        ReflectFieldRef::Optional(value) => match value.value() {
+            Some(value) => value.to_string(),
+            None => "<unset>".to_owned(),
+        },
+        ReflectFieldRef::Repeated(values) => format!("{:?}", values),
+        ReflectFieldRef::Map(values) => format!("{:?}", values),
+    }
+}
+
+/// Implements the `Codec` and `Decoder` traits of the given tonic crate for
+/// the canary codecs.
+macro_rules! impl_canary_codec {
+    ($tonic:ident) => {
+        impl<T, U, U3, R> $tonic::codec::Codec for CanaryCodecV2<T, U, U3, R>
+        where
+            T: protobuf2::Message + Default + Send + 'static,
+            U: protobuf2::Message + Send + 'static,
+            U3: MessageFull + Send + 'static,
+            R: DivergenceReporter,
+        {
+            type Encode = T;
+            type Decode = U;
+
+            type Encoder = ProtobufEncoderV2<T>;
+            type Decoder = CanaryDecoderV2<U, U3, R>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                ProtobufEncoderV2::default()
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                CanaryDecoderV2::new(self.reporter.clone())
+            }
+        }
+
+        impl<T, U, U2, R> $tonic::codec::Codec for CanaryCodecV3<T, U, U2, R>
+        where
+            T: protobuf::Message + Send + 'static,
+            U: MessageFull + Send + 'static,
+            U2: protobuf2::Message + Send + 'static,
+            R: DivergenceReporter,
+        {
+            type Encode = T;
+            type Decode = U;
+
+            type Encoder = ProtobufEncoderV3<T>;
+            type Decoder = CanaryDecoderV3<U, U2, R>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                ProtobufEncoderV3::default()
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                CanaryDecoderV3::new(self.reporter.clone())
+            }
+        }
+
+        impl<U, U3, R> $tonic::codec::Decoder for CanaryDecoderV2<U, U3, R>
+        where
+            U: protobuf2::Message,
+            U3: MessageFull,
+            R: DivergenceReporter,
+        {
+            type Item = U;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let payload = buf.copy_to_bytes(buf.remaining());
+                let (item, _, divergence) = decode_both::<U, U3>(&payload);
+                if let Some(divergence) = divergence {
+                    self.reporter.report(divergence);
+                }
+                // Map Protobuf parse errors to an INTERNAL status code, as per
+                // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                item.map(Some).map_err($tonic::Status::internal)
+            }
+        }
+
+        impl<U, U2, R> $tonic::codec::Decoder for CanaryDecoderV3<U, U2, R>
+        where
+            U: MessageFull,
+            U2: protobuf2::Message,
+            R: DivergenceReporter,
+        {
+            type Item = U;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let payload = buf.copy_to_bytes(buf.remaining());
+                let (_, item, divergence) = decode_both::<U2, U>(&payload);
+                if let Some(divergence) = divergence {
+                    self.reporter.report(divergence);
+                }
+                // Map Protobuf parse errors to an INTERNAL status code, as per
+                // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                item.map(Some).map_err($tonic::Status::internal)
+            }
+        }
+    };
+}
+
+impl_canary_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_canary_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_canary_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_canary_codec!(tonic014);
@@ -0,0 +1,103 @@
+use bytes::{Buf, BufMut};
+use protobuf::{reflect::MessageDescriptor, MessageDyn};
+
+/// A [`Codec`](tonic::codec::Codec) of messages whose types are only known
+/// at runtime, e.g. from descriptors fetched via server reflection.
+///
+/// Any [`MessageDyn`] can be encoded, decoded messages are instances of the
+/// descriptor passed to [`DynamicCodec::new`].
+#[derive(Debug, Clone)]
+pub struct DynamicCodec {
+    pub(crate) decode: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    /// Create a codec that decodes messages of type `decode`.
+    pub fn new(decode: MessageDescriptor) -> Self {
+        DynamicCodec { decode }
+    }
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) that encodes any [`MessageDyn`].
+#[derive(Debug, Clone, Default)]
+pub struct DynamicEncoder {
+    _priv: (),
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that decodes messages of a runtime
+/// type.
+#[derive(Debug, Clone)]
+pub struct DynamicDecoder {
+    descriptor: MessageDescriptor,
+}
+
+impl DynamicDecoder {
+    /// Create a decoder of messages of type `descriptor`.
+    pub fn new(descriptor: MessageDescriptor) -> Self {
+        DynamicDecoder { descriptor }
+    }
+}
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for the dynamic codec.
+macro_rules! impl_dynamic_codec {
+    ($tonic:ident) => {
+        impl $tonic::codec::Codec for DynamicCodec {
+            type Encode = Box<dyn MessageDyn>;
+            type Decode = Box<dyn MessageDyn>;
+
+            type Encoder = DynamicEncoder;
+            type Decoder = DynamicDecoder;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                DynamicEncoder::default()
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                DynamicDecoder::new(self.decode.clone())
+            }
+        }
+
+        impl $tonic::codec::Encoder for DynamicEncoder {
+            type Item = Box<dyn MessageDyn>;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                let mut writer = buf.writer();
+                item.write_to_writer_dyn(&mut writer)
+                    .map_err(|error| $tonic::Status::new($tonic::Code::Internal, error.to_string()))
+            }
+        }
+
+        impl $tonic::codec::Decoder for DynamicDecoder {
+            type Item = Box<dyn MessageDyn>;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let bytes = buf.copy_to_bytes(buf.remaining());
+                let item = self.descriptor.parse_from_bytes(&bytes).map_err(|error| {
+                    // Map Protobuf parse errors to an INTERNAL status code, as per
+                    // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                    $tonic::Status::new($tonic::Code::Internal, error.to_string())
+                })?;
+
+                Ok(Some(item))
+            }
+        }
+    };
+}
+
+impl_dynamic_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_dynamic_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_dynamic_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_dynamic_codec!(tonic014);
@@ -0,0 +1,363 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use bytes::{Buf, BufMut};
+use ring::{
+    aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+
+use crate::PayloadCodec;
+
+/// The version of the envelope of encrypted payloads.
+const VERSION: u8 = 1;
+
+/// A [`Codec`](tonic::codec::Codec) that encrypts the serialized payloads of
+/// another codec with an AEAD, for application-layer encryption on top of
+/// TLS.
+///
+/// Payloads are sealed with the current key of a [`KeyProvider`] after the
+/// inner codec serialized them, and opened before it parses them, so each gRPC
+/// frame carries one encrypted envelope: a version byte, the id of the key,
+/// a random nonce and the ciphertext with its tag. The id of the key lets
+/// receivers keep opening messages sealed with rotated keys.
+///
+/// Messages that can not be opened, e.g. sealed with an unknown key, not
+/// encrypted or tampered with, fail with `UNAUTHENTICATED`. Failures of the
+/// key provider fail with `INTERNAL`.
+///
+/// Both peers must use the codec. With generated code, point
+/// `Builder::codec_path` to an alias whose key provider implements `Default`,
+/// e.g. `type Codec<T, U> = EncryptedCodec<ProtobufCodecV3<T, U>, MyKeys>;`.
+#[derive(Debug, Clone, Default)]
+pub struct EncryptedCodec<C, K> {
+    codec: C,
+    keys: K,
+}
+
+impl<C, K> EncryptedCodec<C, K> {
+    /// Create a codec that encrypts the payloads of `codec` with the keys of
+    /// `keys`.
+    pub fn new(codec: C, keys: K) -> Self {
+        EncryptedCodec { codec, keys }
+    }
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) that encrypts encoded messages.
+#[derive(Debug, Clone)]
+pub struct EncryptedEncoder<C, K> {
+    codec: C,
+    keys: K,
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that decrypts messages before
+/// decoding them.
+#[derive(Debug, Clone)]
+pub struct EncryptedDecoder<C, K> {
+    codec: C,
+    keys: K,
+}
+
+/// The AEAD algorithm of an [`EncryptionKey`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// AES-256 in GCM mode, with a 32 byte key.
+    Aes256Gcm,
+    /// ChaCha20-Poly1305, with a 32 byte key.
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    fn aead(self) -> &'static aead::Algorithm {
+        match self {
+            Algorithm::Aes256Gcm => &aead::AES_256_GCM,
+            Algorithm::ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+        }
+    }
+}
+
+/// A named key of an AEAD algorithm.
+///
+/// Nonces are random, so a key should be rotated well before it sealed 2^32
+/// messages.
+pub struct EncryptionKey {
+    id: String,
+    algorithm: Algorithm,
+    key: LessSafeKey,
+}
+
+impl EncryptionKey {
+    /// Create the key `id` of `algorithm` from its raw bytes.
+    ///
+    /// Fails if `key` has the wrong length for `algorithm`, or if `id` is
+    /// longer than 255 bytes.
+    pub fn new(id: impl Into<String>, algorithm: Algorithm, key: &[u8]) -> Result<Self, KeyError> {
+        let id = id.into();
+        if id.len() > u8::MAX as usize {
+            return Err(KeyError::Invalid(format!("key id {:?} is too long", id)));
+        }
+        let key = UnboundKey::new(algorithm.aead(), key)
+            .map_err(|_| KeyError::Invalid(format!("key {:?} has a wrong length", id)))?;
+        Ok(EncryptionKey {
+            id,
+            algorithm,
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// The id of the key, sent along with the messages it sealed.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The algorithm of the key.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the key material.
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Errors of [`KeyProvider`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// No key has the given id, e.g. because the peer uses a key that was
+    /// never distributed or already retired.
+    NotFound(String),
+    /// Keys can not be provided, e.g. because a key service is unreachable.
+    Unavailable(String),
+    /// The key material is invalid.
+    Invalid(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::NotFound(id) => write!(f, "no key {:?}", id),
+            KeyError::Unavailable(msg) => write!(f, "keys unavailable: {}", msg),
+            KeyError::Invalid(msg) => write!(f, "invalid key: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Provides the keys of an [`EncryptedCodec`].
+pub trait KeyProvider: Send + Sync + 'static {
+    /// The key that seals outgoing messages.
+    fn encryption_key(&self) -> Result<Arc<EncryptionKey>, KeyError>;
+
+    /// The key `id` that opens incoming messages, which may be the current
+    /// key or one it replaced.
+    fn decryption_key(&self, id: &str) -> Result<Arc<EncryptionKey>, KeyError>;
+}
+
+impl<K: KeyProvider + ?Sized> KeyProvider for Arc<K> {
+    fn encryption_key(&self) -> Result<Arc<EncryptionKey>, KeyError> {
+        (**self).encryption_key()
+    }
+
+    fn decryption_key(&self, id: &str) -> Result<Arc<EncryptionKey>, KeyError> {
+        (**self).decryption_key(id)
+    }
+}
+
+/// A [`KeyProvider`] of a fixed set of keys.
+#[derive(Debug, Clone)]
+pub struct StaticKeys {
+    current: Arc<EncryptionKey>,
+    keys: HashMap<String, Arc<EncryptionKey>>,
+}
+
+impl StaticKeys {
+    /// Create a provider that seals and opens messages with `key`.
+    pub fn new(key: EncryptionKey) -> Self {
+        let current = Arc::new(key);
+        let keys = HashMap::from([(current.id.clone(), current.clone())]);
+        StaticKeys { current, keys }
+    }
+
+    /// Additionally open messages sealed with `key`, e.g. a rotated key that
+    /// peers may still use.
+    pub fn with_decryption_key(mut self, key: EncryptionKey) -> Self {
+        self.keys.insert(key.id.clone(), Arc::new(key));
+        self
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn encryption_key(&self) -> Result<Arc<EncryptionKey>, KeyError> {
+        Ok(self.current.clone())
+    }
+
+    fn decryption_key(&self, id: &str) -> Result<Arc<EncryptionKey>, KeyError> {
+        self.keys
+            .get(id)
+            .cloned()
+            .ok_or_else(|| KeyError::NotFound(id.to_owned()))
+    }
+}
+
+/// Why a payload could not be sealed or opened, mapped to a status code of
+/// each tonic version.
+enum Failure {
+    Unauthenticated(String),
+    Internal(String),
+}
+
+impl From<KeyError> for Failure {
+    fn from(error: KeyError) -> Self {
+        match error {
+            KeyError::NotFound(_) => Failure::Unauthenticated(error.to_string()),
+            KeyError::Unavailable(_) | KeyError::Invalid(_) => Failure::Internal(error.to_string()),
+        }
+    }
+}
+
+/// Seals `payload` with the current key of `keys`, into an envelope.
+fn seal<K: KeyProvider>(keys: &K, mut payload: Vec<u8>) -> Result<Vec<u8>, Failure> {
+    let key = keys.encryption_key().map_err(|e| match e {
+        // Without a key to seal with, the local configuration is broken.
+        KeyError::NotFound(_) => Failure::Internal(e.to_string()),
+        e => e.into(),
+    })?;
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| Failure::Internal("failed to generate a nonce".to_owned()))?;
+
+    let mut envelope = Vec::with_capacity(2 + key.id.len() + NONCE_LEN + payload.len() + 16);
+    envelope.push(VERSION);
+    envelope.push(key.id.len() as u8);
+    envelope.extend_from_slice(key.id.as_bytes());
+    // The header is authenticated along with the payload.
+    key.key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&envelope),
+            &mut payload,
+        )
+        .map_err(|_| Failure::Internal(format!("failed to seal with key {:?}", key.id)))?;
+    envelope.extend_from_slice(&nonce);
+    envelope.extend_from_slice(&payload);
+    Ok(envelope)
+}
+
+/// Opens an envelope sealed by [`seal`] and returns its payload.
+fn open<K: KeyProvider>(keys: &K, mut envelope: Vec<u8>) -> Result<Vec<u8>, Failure> {
+    let malformed = || Failure::Unauthenticated("message is not encrypted".to_owned());
+    if envelope.len() < 2 || envelope[0] != VERSION {
+        return Err(malformed());
+    }
+    let header_len = 2 + envelope[1] as usize;
+    if envelope.len() < header_len + NONCE_LEN {
+        return Err(malformed());
+    }
+    let id = std::str::from_utf8(&envelope[2..header_len]).map_err(|_| malformed())?;
+    let key = keys.decryption_key(id)?;
+    let nonce = Nonce::try_assume_unique_for_key(&envelope[header_len..header_len + NONCE_LEN])
+        .map_err(|_| malformed())?;
+
+    let mut ciphertext = envelope.split_off(header_len + NONCE_LEN);
+    let len = key
+        .key
+        .open_in_place(nonce, Aad::from(&envelope[..header_len]), &mut ciphertext)
+        .map_err(|_| {
+            Failure::Unauthenticated(format!("failed to open message with key {:?}", key.id))
+        })?
+        .len();
+    ciphertext.truncate(len);
+    Ok(ciphertext)
+}
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for the encrypted codec.
+macro_rules! impl_encrypted_codec {
+    ($tonic:ident) => {
+        impl<C: PayloadCodec, K: KeyProvider + Clone> $tonic::codec::Codec
+            for EncryptedCodec<C, K>
+        {
+            type Encode = C::Encode;
+            type Decode = C::Decode;
+
+            type Encoder = EncryptedEncoder<C, K>;
+            type Decoder = EncryptedDecoder<C, K>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                EncryptedEncoder {
+                    codec: self.codec.clone(),
+                    keys: self.keys.clone(),
+                }
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                EncryptedDecoder {
+                    codec: self.codec.clone(),
+                    keys: self.keys.clone(),
+                }
+            }
+        }
+
+        impl<C: PayloadCodec, K: KeyProvider> $tonic::codec::Encoder for EncryptedEncoder<C, K> {
+            type Item = C::Encode;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                let to_status = |failure| match failure {
+                    Failure::Unauthenticated(msg) => $tonic::Status::unauthenticated(msg),
+                    Failure::Internal(msg) => $tonic::Status::internal(msg),
+                };
+                let payload = self
+                    .codec
+                    .encode_payload(item)
+                    .map_err($tonic::Status::internal)?;
+                let envelope = seal(&self.keys, payload).map_err(to_status)?;
+                buf.put_slice(&envelope);
+                Ok(())
+            }
+        }
+
+        impl<C: PayloadCodec, K: KeyProvider> $tonic::codec::Decoder for EncryptedDecoder<C, K> {
+            type Item = C::Decode;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let to_status = |failure| match failure {
+                    Failure::Unauthenticated(msg) => $tonic::Status::unauthenticated(msg),
+                    Failure::Internal(msg) => $tonic::Status::internal(msg),
+                };
+                let envelope = buf.copy_to_bytes(buf.remaining()).to_vec();
+                let payload = open(&self.keys, envelope).map_err(to_status)?;
+                // Map parse errors to an INTERNAL status code, like the inner
+                // codecs.
+                let item = self
+                    .codec
+                    .decode_payload(&payload)
+                    .map_err($tonic::Status::internal)?;
+                Ok(Some(item))
+            }
+        }
+    };
+}
+
+impl_encrypted_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_encrypted_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_encrypted_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_encrypted_codec!(tonic014);
@@ -0,0 +1,318 @@
+//! The gRPC message framing, i.e. the length-prefixed messages carried by the
+//! body of a gRPC HTTP/2 stream.
+//!
+//! Each frame is a one byte compressed flag, followed by the message length
+//! as a big-endian `u32` and the message itself.
+//!
+//! The framing does not depend on tonic, so it also serves to store messages
+//! in files or message queues, or to carry them over custom transports:
+//! [`encode`] and [`frames`] work on buffers, [`FrameReader`] on data that
+//! arrives in chunks, and [`write_frame`] and [`read_frame`] on
+//! [`std::io`] writers and readers.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+/// The length of a frame header.
+pub const HEADER_LEN: usize = 5;
+
+/// The default limit of the message length of a [`FrameReader`], 4MB like
+/// the default decoding limit of tonic.
+pub const DEFAULT_MAX_LEN: usize = 4 * 1024 * 1024;
+
+/// A gRPC frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// The offset of the frame header in the buffer.
+    pub offset: usize,
+    /// Whether the message is compressed with the `grpc-encoding` of the call.
+    pub compressed: bool,
+    /// The message bytes.
+    pub data: &'a [u8],
+}
+
+/// An error when splitting a buffer into frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// The compressed flag is neither 0 nor 1.
+    InvalidFlag { offset: usize, flag: u8 },
+    /// The buffer ends in the middle of a frame.
+    Truncated {
+        offset: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// The frame header announces a message longer than the limit of a
+    /// [`FrameReader`].
+    TooLarge {
+        offset: usize,
+        len: usize,
+        max_len: usize,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::InvalidFlag { offset, flag } => {
+                write!(f, "invalid compressed flag {} at offset {}", flag, offset)
+            }
+            FrameError::Truncated {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "truncated frame at offset {}: expected {} bytes, got {}",
+                offset, expected, actual
+            ),
+            FrameError::TooLarge {
+                offset,
+                len,
+                max_len,
+            } => write!(
+                f,
+                "frame of {} bytes at offset {} exceeds the limit of {} bytes",
+                len, offset, max_len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Returns an iterator over the frames in `buf`.
+///
+/// The iterator stops after the first error.
+pub fn frames(buf: &[u8]) -> Frames<'_> {
+    Frames { buf, offset: 0 }
+}
+
+/// Appends `data` to `buf` as a frame.
+///
+/// Messages longer than `u32::MAX` bytes fail with
+/// [`io::ErrorKind::InvalidInput`], leaving `buf` unchanged.
+pub fn encode(compressed: bool, data: &[u8], buf: &mut Vec<u8>) -> io::Result<()> {
+    let len = frame_len(data)?;
+    buf.reserve(HEADER_LEN + data.len());
+    buf.push(compressed as u8);
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(data);
+    Ok(())
+}
+
+/// Returns the length of `data` for a frame header.
+fn frame_len(data: &[u8]) -> io::Result<u32> {
+    u32::try_from(data.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("message of {} bytes is too large for a frame", data.len()),
+        )
+    })
+}
+
+/// A frame that owns its message, see [`read_frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedFrame {
+    /// Whether the message is compressed.
+    pub compressed: bool,
+    /// The message bytes.
+    pub data: Vec<u8>,
+}
+
+/// Writes `data` to `writer` as a frame.
+///
+/// Messages longer than `u32::MAX` bytes fail with
+/// [`io::ErrorKind::InvalidInput`], before anything is written.
+pub fn write_frame(writer: &mut impl Write, compressed: bool, data: &[u8]) -> io::Result<()> {
+    let len = frame_len(data)?;
+    let mut header = [0; HEADER_LEN];
+    header[0] = compressed as u8;
+    header[1..].copy_from_slice(&len.to_be_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(data)
+}
+
+/// Reads the next frame from `reader`, or returns `None` if it ends before
+/// the frame.
+///
+/// Input that ends in the middle of a frame fails with
+/// [`io::ErrorKind::UnexpectedEof`], and frames with an invalid compressed
+/// flag or a message longer than `max_len` bytes with
+/// [`io::ErrorKind::InvalidData`], before reading the message.
+pub fn read_frame(reader: &mut impl Read, max_len: usize) -> io::Result<Option<OwnedFrame>> {
+    let mut header = [0; HEADER_LEN];
+    let mut read = 0;
+    while read < HEADER_LEN {
+        match reader.read(&mut header[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let compressed = match header[0] {
+        0 => false,
+        1 => true,
+        flag => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid compressed flag {}", flag),
+            ))
+        }
+    };
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "frame of {} bytes exceeds the limit of {} bytes",
+                len, max_len
+            ),
+        ));
+    }
+    let mut data = vec![0; len];
+    reader.read_exact(&mut data)?;
+    Ok(Some(OwnedFrame { compressed, data }))
+}
+
+/// Splits data read in chunks, e.g. from a body, into frames.
+///
+/// Messages are buffered until their frame is complete, so frames whose
+/// header announces a message longer than the limit fail with
+/// [`FrameError::TooLarge`] instead.
+#[derive(Debug, Clone)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+    max_len: usize,
+    error: Option<FrameError>,
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        FrameReader::new()
+    }
+}
+
+impl FrameReader {
+    /// Create a reader expecting a frame header, with messages of at most
+    /// [`DEFAULT_MAX_LEN`] bytes.
+    pub fn new() -> Self {
+        FrameReader::with_max_len(DEFAULT_MAX_LEN)
+    }
+
+    /// Create a reader expecting a frame header, with messages of at most
+    /// `max_len` bytes.
+    pub fn with_max_len(max_len: usize) -> Self {
+        FrameReader {
+            buf: vec![],
+            max_len,
+            error: None,
+        }
+    }
+
+    /// Appends `data`, and calls `f` with the frames completed by it. Frame
+    /// offsets are relative to the data not split into frames before.
+    ///
+    /// Once a frame is malformed, further data is ignored and the error is
+    /// returned again.
+    pub fn feed(&mut self, data: &[u8], mut f: impl FnMut(Frame<'_>)) -> Result<(), FrameError> {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        }
+        self.buf.extend_from_slice(data);
+        let mut consumed = 0;
+        for frame in frames(&self.buf) {
+            // Once the header is complete, the expected length is known.
+            let (offset, len) = match &frame {
+                Ok(frame) => (frame.offset, frame.data.len()),
+                Err(FrameError::Truncated {
+                    offset, expected, ..
+                }) => (*offset, expected - HEADER_LEN),
+                Err(_) => (0, 0),
+            };
+            let frame = if len > self.max_len {
+                Err(FrameError::TooLarge {
+                    offset,
+                    len,
+                    max_len: self.max_len,
+                })
+            } else {
+                frame
+            };
+            match frame {
+                Ok(frame) => {
+                    consumed = frame.offset + HEADER_LEN + frame.data.len();
+                    f(frame);
+                }
+                // The last frame is incomplete.
+                Err(FrameError::Truncated { .. }) => break,
+                Err(e) => {
+                    self.buf = vec![];
+                    self.error = Some(e.clone());
+                    return Err(e);
+                }
+            }
+        }
+        self.buf.drain(..consumed);
+        Ok(())
+    }
+
+    /// Returns the error of the malformed frame, if any.
+    pub fn error(&self) -> Option<&FrameError> {
+        self.error.as_ref()
+    }
+}
+
+/// An iterator over the frames of a buffer, see [`frames`].
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<Frame<'a>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let rest = &self.buf[offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        // Stop after this item, whatever its outcome.
+        self.offset = self.buf.len();
+
+        if rest.len() < HEADER_LEN {
+            return Some(Err(FrameError::Truncated {
+                offset,
+                expected: HEADER_LEN,
+                actual: rest.len(),
+            }));
+        }
+        let compressed = match rest[0] {
+            0 => false,
+            1 => true,
+            flag => return Some(Err(FrameError::InvalidFlag { offset, flag })),
+        };
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let data = &rest[HEADER_LEN..];
+        if data.len() < len {
+            return Some(Err(FrameError::Truncated {
+                offset,
+                expected: HEADER_LEN + len,
+                actual: rest.len(),
+            }));
+        }
+
+        self.offset = offset + HEADER_LEN + len;
+        Some(Ok(Frame {
+            offset,
+            compressed,
+            data: &data[..len],
+        }))
+    }
+}
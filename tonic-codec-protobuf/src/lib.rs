@@ -1,63 +1,95 @@
 //! A [`tonic::Codec`](https://docs.rs/tonic/0.11.0/tonic/codec/trait.Codec.html)
 //! that implements `application/grpc+proto` via the rust-protobuf.
+//!
+//! The codecs implement the codec traits of tonic 0.11. Enable the
+//! `tonic-0-12`, `tonic-0-13` or `tonic-0-14` features to additionally
+//! implement the codec traits of the corresponding tonic release, so that code
+//! generated by `tonic-build-protobuf` for any of those versions can share the
+//! same codec types.
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for a rust-protobuf codec.
+macro_rules! impl_codec {
+    ($tonic:ident, $codec:ident, $encoder:ident, $decoder:ident, $message:path, $parse:expr) => {
+        impl<T, U> $tonic::codec::Codec for $codec<T, U>
+        where
+            T: $message + Send + 'static,
+            U: $message + Default + Send + 'static,
+        {
+            type Encode = T;
+            type Decode = U;
+
+            type Encoder = $encoder<T>;
+            type Decoder = $decoder<U>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                $encoder { _pd: PhantomData }
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                $decoder { _pd: PhantomData }
+            }
+        }
+
+        impl<T: $message> $tonic::codec::Encoder for $encoder<T> {
+            type Item = T;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                let mut writer = buf.writer();
+                item.write_to_writer(&mut writer)
+                    .expect("Message only errors if not enough space");
+
+                Ok(())
+            }
+        }
+
+        impl<U: $message + Default> $tonic::codec::Decoder for $decoder<U> {
+            type Item = U;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let mut reader = buf.reader();
+                #[allow(deprecated)]
+                let item = $parse(&mut reader).map_err(|error| {
+                    // Map Protobuf parse errors to an INTERNAL status code, as per
+                    // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                    $tonic::Status::new($tonic::Code::Internal, error.to_string())
+                })?;
+
+                Ok(Some(item))
+            }
+        }
+    };
+}
 
 #[cfg(feature = "protobuf-v3")]
 mod protobuf_v3 {
     use std::marker::PhantomData;
 
     use bytes::{Buf, BufMut};
-    use protobuf::Message;
-    use tonic::{
-        codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
-        Code, Status,
-    };
+    use protobuf::{CodedInputStream, Message};
 
-    /// A [`Codec`] that implements `application/grpc+proto` via the [rust-protobuf v3](https://crates.io/crates/protobuf) library.
+    /// A [`Codec`](tonic::codec::Codec) that implements `application/grpc+proto` via the [rust-protobuf v3](https://crates.io/crates/protobuf) library.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufCodecV3<T, U> {
         _pd: PhantomData<(T, U)>,
     }
 
-    impl<T, U> Codec for ProtobufCodecV3<T, U>
-    where
-        T: Message + Send + 'static,
-        U: Message + Default + Send + 'static,
-    {
-        type Encode = T;
-        type Decode = U;
-
-        type Encoder = ProtobufEncoderV3<T>;
-        type Decoder = ProtobufDecoderV3<U>;
-
-        fn encoder(&mut self) -> Self::Encoder {
-            ProtobufEncoderV3 { _pd: PhantomData }
-        }
-
-        fn decoder(&mut self) -> Self::Decoder {
-            ProtobufDecoderV3 { _pd: PhantomData }
-        }
-    }
-
-    /// A [`Encoder`] that knows how to encode `T`.
+    /// A [`Encoder`](tonic::codec::Encoder) that knows how to encode `T`.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufEncoderV3<T> {
         _pd: PhantomData<T>,
     }
 
-    impl<T: Message> Encoder for ProtobufEncoderV3<T> {
-        type Item = T;
-        type Error = Status;
-
-        fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
-            let mut writer = buf.writer();
-            item.write_to_writer(&mut writer)
-                .expect("Message only errors if not enough space");
-
-            Ok(())
-        }
-    }
-
-    /// A [`Decoder`] that knows how to decode `U`.
+    /// A [`Decoder`](tonic::codec::Decoder) that knows how to decode `U`.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufDecoderV3<U> {
         _pd: PhantomData<U>,
@@ -70,85 +102,122 @@ mod protobuf_v3 {
         }
     }
 
-    impl<U: Message + Default> Decoder for ProtobufDecoderV3<U> {
-        type Item = U;
-        type Error = Status;
-
-        fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+    impl<U: Message> ProtobufDecoderV3<U> {
+        /// Decode the message in `buf` into `message`, which is cleared first,
+        /// instead of constructing a new one, e.g. to reuse messages managed by
+        /// the caller.
+        ///
+        /// On error, `message` may be partially merged.
+        pub fn decode_into<B: Buf>(
+            &mut self,
+            buf: B,
+            message: &mut U,
+        ) -> Result<(), protobuf::Error> {
+            message.clear();
             let mut reader = buf.reader();
-            let item = <U as Message>::parse_from_reader(&mut reader).map_err(from_decode_error)?;
-
-            Ok(Some(item))
+            let mut is = CodedInputStream::new(&mut reader);
+            message.merge_from(&mut is)?;
+            is.check_eof()?;
+            message.check_initialized()
         }
     }
 
-    fn from_decode_error(error: protobuf::Error) -> Status {
-        // Map Protobuf parse errors to an INTERNAL status code, as per
-        // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
-        Status::new(Code::Internal, error.to_string())
-    }
+    impl_codec!(
+        tonic,
+        ProtobufCodecV3,
+        ProtobufEncoderV3,
+        ProtobufDecoderV3,
+        Message,
+        <U as Message>::parse_from_reader
+    );
+    #[cfg(feature = "tonic-0-12")]
+    impl_codec!(
+        tonic012,
+        ProtobufCodecV3,
+        ProtobufEncoderV3,
+        ProtobufDecoderV3,
+        Message,
+        <U as Message>::parse_from_reader
+    );
+    #[cfg(feature = "tonic-0-13")]
+    impl_codec!(
+        tonic013,
+        ProtobufCodecV3,
+        ProtobufEncoderV3,
+        ProtobufDecoderV3,
+        Message,
+        <U as Message>::parse_from_reader
+    );
+    #[cfg(feature = "tonic-0-14")]
+    impl_codec!(
+        tonic014,
+        ProtobufCodecV3,
+        ProtobufEncoderV3,
+        ProtobufDecoderV3,
+        Message,
+        <U as Message>::parse_from_reader
+    );
 }
 
 #[cfg(feature = "protobuf-v3")]
 pub use protobuf_v3::*;
 
+pub mod frame;
+
+mod raw;
+pub use raw::*;
+
+mod negotiate;
+pub use negotiate::*;
+
+mod payload;
+pub use payload::*;
+
+#[cfg(feature = "protobuf-v3")]
+mod dynamic;
+#[cfg(feature = "protobuf-v3")]
+pub use dynamic::*;
+
+#[cfg(feature = "protobuf-v3")]
+mod strict;
+#[cfg(feature = "protobuf-v3")]
+pub use strict::*;
+
+#[cfg(feature = "protobuf-v3")]
+mod streaming_field;
+#[cfg(feature = "protobuf-v3")]
+pub use streaming_field::*;
+
+#[cfg(feature = "encryption")]
+mod encrypt;
+#[cfg(feature = "encryption")]
+pub use encrypt::*;
+
+#[cfg(feature = "offload")]
+mod offload;
+#[cfg(feature = "offload")]
+pub use offload::*;
+
 #[cfg(feature = "protobuf-v2")]
 mod protobuf_v2 {
     use std::marker::PhantomData;
 
     use bytes::{Buf, BufMut};
-    use protobuf2::Message;
-    use tonic::{
-        codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
-        Code, Status,
-    };
+    use protobuf2::{CodedInputStream, Message};
 
-    /// A [`Codec`] that implements `application/grpc+proto` via the [rust-protobuf v2](https://crates.io/crates/protobuf/2.28.0) library.
+    /// A [`Codec`](tonic::codec::Codec) that implements `application/grpc+proto` via the [rust-protobuf v2](https://crates.io/crates/protobuf/2.28.0) library.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufCodecV2<T, U> {
         _pd: PhantomData<(T, U)>,
     }
 
-    impl<T, U> Codec for ProtobufCodecV2<T, U>
-    where
-        T: Message + Send + 'static,
-        U: Message + Default + Send + 'static,
-    {
-        type Encode = T;
-        type Decode = U;
-
-        type Encoder = ProtobufEncoderV2<T>;
-        type Decoder = ProtobufDecoderV2<U>;
-
-        fn encoder(&mut self) -> Self::Encoder {
-            ProtobufEncoderV2 { _pd: PhantomData }
-        }
-
-        fn decoder(&mut self) -> Self::Decoder {
-            ProtobufDecoderV2 { _pd: PhantomData }
-        }
-    }
-
-    /// A [`Encoder`] that knows how to encode `T`.
+    /// A [`Encoder`](tonic::codec::Encoder) that knows how to encode `T`.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufEncoderV2<T> {
         _pd: PhantomData<T>,
     }
 
-    impl<T: Message> Encoder for ProtobufEncoderV2<T> {
-        type Item = T;
-        type Error = Status;
-
-        fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
-            let mut writer = buf.writer();
-            item.write_to_writer(&mut writer)
-                .expect("Message only errors if not enough space");
-
-            Ok(())
-        }
-    }
-
-    /// A [`Decoder`] that knows how to decode `U`.
+    /// A [`Decoder`](tonic::codec::Decoder) that knows how to decode `U`.
     #[derive(Debug, Clone, Default)]
     pub struct ProtobufDecoderV2<U> {
         _pd: PhantomData<U>,
@@ -161,24 +230,66 @@ mod protobuf_v2 {
         }
     }
 
-    impl<U: Message + Default> Decoder for ProtobufDecoderV2<U> {
-        type Item = U;
-        type Error = Status;
-
-        fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+    impl<U: Message> ProtobufDecoderV2<U> {
+        /// Decode the message in `buf` into `message`, which is cleared first,
+        /// instead of constructing a new one, e.g. to reuse messages managed by
+        /// the caller.
+        ///
+        /// On error, `message` may be partially merged.
+        pub fn decode_into<B: Buf>(
+            &mut self,
+            buf: B,
+            message: &mut U,
+        ) -> Result<(), protobuf2::ProtobufError> {
+            message.clear();
             let mut reader = buf.reader();
-            #[allow(deprecated)]
-            let item = protobuf2::parse_from_reader(&mut reader).map_err(from_decode_error)?;
-
-            Ok(Some(item))
+            let mut is = CodedInputStream::new(&mut reader);
+            message.merge_from(&mut is)?;
+            is.check_eof()?;
+            message.check_initialized()
         }
     }
 
-    fn from_decode_error(error: protobuf2::error::ProtobufError) -> Status {
-        // Map Protobuf parse errors to an INTERNAL status code, as per
-        // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
-        Status::new(Code::Internal, error.to_string())
-    }
+    impl_codec!(
+        tonic,
+        ProtobufCodecV2,
+        ProtobufEncoderV2,
+        ProtobufDecoderV2,
+        Message,
+        protobuf2::parse_from_reader::<U>
+    );
+    #[cfg(feature = "tonic-0-12")]
+    impl_codec!(
+        tonic012,
+        ProtobufCodecV2,
+        ProtobufEncoderV2,
+        ProtobufDecoderV2,
+        Message,
+        protobuf2::parse_from_reader::<U>
+    );
+    #[cfg(feature = "tonic-0-13")]
+    impl_codec!(
+        tonic013,
+        ProtobufCodecV2,
+        ProtobufEncoderV2,
+        ProtobufDecoderV2,
+        Message,
+        protobuf2::parse_from_reader::<U>
+    );
+    #[cfg(feature = "tonic-0-14")]
+    impl_codec!(
+        tonic014,
+        ProtobufCodecV2,
+        ProtobufEncoderV2,
+        ProtobufDecoderV2,
+        Message,
+        protobuf2::parse_from_reader::<U>
+    );
 }
 #[cfg(feature = "protobuf-v2")]
 pub use protobuf_v2::*;
+
+#[cfg(all(feature = "protobuf-v2", feature = "protobuf-v3"))]
+mod canary;
+#[cfg(all(feature = "protobuf-v2", feature = "protobuf-v3"))]
+pub use canary::*;
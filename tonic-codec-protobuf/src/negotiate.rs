@@ -0,0 +1,172 @@
+/// The content-type of gRPC requests whose messages are encoded in the JSON
+/// mapping of protobuf.
+pub const GRPC_JSON_CONTENT_TYPE: &str = "application/grpc+json";
+
+/// A codec that is built for every call of a server from the content-type of
+/// its request, e.g. to serve several encodings of messages from one service
+/// implementation.
+///
+/// Servers generated with `Builder::select_codec(true)` build their codec with
+/// [`SelectCodec::select_codec`] instead of `Default`, so `Builder::codec_path`
+/// must point to a type implementing it, e.g. [`Negotiated`].
+pub trait SelectCodec {
+    /// Build the codec of a call whose request has `content_type`, e.g.
+    /// `application/grpc+proto`.
+    fn select_codec(content_type: Option<&str>) -> Self;
+}
+
+/// A codec that dispatches every call to the protobuf codec `P`, or to the
+/// JSON codec `J` when the request has the `application/grpc+json`
+/// content-type.
+///
+/// Both codecs must encode and decode the same message types. Clients send
+/// `application/grpc` requests, so [`Default`] selects `P`; responses keep the
+/// `application/grpc` content-type that tonic sets.
+#[derive(Debug, Clone)]
+pub enum Negotiated<P, J> {
+    /// Messages are encoded in the protobuf wire format.
+    Proto(P),
+    /// Messages are encoded in the JSON mapping of protobuf.
+    Json(J),
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) of the selected codec.
+#[derive(Debug, Clone)]
+pub enum NegotiatedEncoder<P, J> {
+    /// Encodes messages in the protobuf wire format.
+    Proto(P),
+    /// Encodes messages in the JSON mapping of protobuf.
+    Json(J),
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) of the selected codec.
+#[derive(Debug, Clone)]
+pub enum NegotiatedDecoder<P, J> {
+    /// Decodes messages in the protobuf wire format.
+    Proto(P),
+    /// Decodes messages in the JSON mapping of protobuf.
+    Json(J),
+}
+
+impl<P: Default, J> Default for Negotiated<P, J> {
+    fn default() -> Self {
+        Negotiated::Proto(P::default())
+    }
+}
+
+impl<P: Default, J: Default> SelectCodec for Negotiated<P, J> {
+    fn select_codec(content_type: Option<&str>) -> Self {
+        // Ignore parameters, e.g. `application/grpc+json; charset=utf-8`.
+        let essence = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(str::trim);
+        match essence {
+            Some(essence) if essence.eq_ignore_ascii_case(GRPC_JSON_CONTENT_TYPE) => {
+                Negotiated::Json(J::default())
+            }
+            _ => Negotiated::Proto(P::default()),
+        }
+    }
+}
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for the negotiated codec.
+macro_rules! impl_negotiated_codec {
+    ($tonic:ident) => {
+        impl<P, J> $tonic::codec::Codec for Negotiated<P, J>
+        where
+            P: $tonic::codec::Codec,
+            J: $tonic::codec::Codec<Encode = P::Encode, Decode = P::Decode>,
+        {
+            type Encode = P::Encode;
+            type Decode = P::Decode;
+
+            type Encoder = NegotiatedEncoder<P::Encoder, J::Encoder>;
+            type Decoder = NegotiatedDecoder<P::Decoder, J::Decoder>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                match self {
+                    Negotiated::Proto(codec) => NegotiatedEncoder::Proto(codec.encoder()),
+                    Negotiated::Json(codec) => NegotiatedEncoder::Json(codec.encoder()),
+                }
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                match self {
+                    Negotiated::Proto(codec) => NegotiatedDecoder::Proto(codec.decoder()),
+                    Negotiated::Json(codec) => NegotiatedDecoder::Json(codec.decoder()),
+                }
+            }
+        }
+
+        impl<P, J> $tonic::codec::Encoder for NegotiatedEncoder<P, J>
+        where
+            P: $tonic::codec::Encoder<Error = $tonic::Status>,
+            J: $tonic::codec::Encoder<Item = P::Item, Error = $tonic::Status>,
+        {
+            type Item = P::Item;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                match self {
+                    NegotiatedEncoder::Proto(encoder) => encoder.encode(item, buf),
+                    NegotiatedEncoder::Json(encoder) => encoder.encode(item, buf),
+                }
+            }
+        }
+
+        impl<P, J> $tonic::codec::Decoder for NegotiatedDecoder<P, J>
+        where
+            P: $tonic::codec::Decoder<Error = $tonic::Status>,
+            J: $tonic::codec::Decoder<Item = P::Item, Error = $tonic::Status>,
+        {
+            type Item = P::Item;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                match self {
+                    NegotiatedDecoder::Proto(decoder) => decoder.decode(buf),
+                    NegotiatedDecoder::Json(decoder) => decoder.decode(buf),
+                }
+            }
+        }
+    };
+}
+
+impl_negotiated_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_negotiated_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_negotiated_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_negotiated_codec!(tonic014);
+
+// Codecs of a single encoding ignore the content-type, so servers generated
+// with `Builder::select_codec(true)` keep working with them.
+
+impl SelectCodec for crate::RawCodec {
+    fn select_codec(_content_type: Option<&str>) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "protobuf-v3")]
+impl<T: Default, U: Default> SelectCodec for crate::ProtobufCodecV3<T, U> {
+    fn select_codec(_content_type: Option<&str>) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "protobuf-v2")]
+impl<T: Default, U: Default> SelectCodec for crate::ProtobufCodecV2<T, U> {
+    fn select_codec(_content_type: Option<&str>) -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::PayloadCodec;
+
+/// The size of payloads above which [`OffloadCodec`] decodes messages on the
+/// blocking thread pool by default, 1 MiB.
+pub const DEFAULT_OFFLOAD_THRESHOLD: usize = 1 << 20;
+
+/// A [`Codec`](tonic::codec::Codec) that defers decoding messages of another
+/// codec, so that huge messages do not block the executor thread polling the
+/// call.
+///
+/// The decoder only splits the frames of a call, and yields every message as
+/// a [`Deferred`] payload. [`Deferred::decode`] then decodes payloads larger
+/// than the threshold with `tokio::task::spawn_blocking`, and smaller ones
+/// inline, where spawning would cost more than decoding.
+///
+/// Messages are encoded by the wrapped codec as usual.
+#[derive(Debug, Clone)]
+pub struct OffloadCodec<C> {
+    codec: C,
+    threshold: usize,
+}
+
+impl<C> OffloadCodec<C> {
+    /// Create a codec that decodes payloads of `codec` larger than `threshold`
+    /// bytes on the blocking thread pool.
+    pub fn new(codec: C, threshold: usize) -> Self {
+        OffloadCodec { codec, threshold }
+    }
+}
+
+impl<C: Default> Default for OffloadCodec<C> {
+    fn default() -> Self {
+        OffloadCodec::new(C::default(), DEFAULT_OFFLOAD_THRESHOLD)
+    }
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) that encodes messages with the wrapped
+/// codec.
+#[derive(Debug, Clone)]
+pub struct OffloadEncoder<C> {
+    codec: C,
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that yields [`Deferred`] payloads.
+#[derive(Debug, Clone)]
+pub struct OffloadDecoder<C> {
+    codec: C,
+    threshold: usize,
+}
+
+/// A received message that is not decoded yet.
+pub struct Deferred<C: PayloadCodec> {
+    codec: C,
+    payload: Bytes,
+    threshold: usize,
+}
+
+impl<C: PayloadCodec> Deferred<C> {
+    /// The size of the encoded message.
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Returns whether the encoded message is empty.
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// The encoded message.
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Decode the message, on the blocking thread pool if it is larger than
+    /// the threshold of the codec.
+    ///
+    /// Outside of a tokio runtime, the message is always decoded inline.
+    pub async fn decode(self) -> Result<C::Decode, DecodeError> {
+        if self.payload.len() <= self.threshold {
+            return self.decode_inline();
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return self.decode_inline();
+        };
+        match handle.spawn_blocking(move || self.decode_inline()).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(DecodeError(format!("decoding task failed: {}", e))),
+        }
+    }
+
+    /// Decode the message on the current thread, regardless of its size.
+    pub fn decode_inline(self) -> Result<C::Decode, DecodeError> {
+        self.codec
+            .decode_payload(&self.payload)
+            .map_err(DecodeError)
+    }
+}
+
+impl<C: PayloadCodec> fmt::Debug for Deferred<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deferred")
+            .field("len", &self.payload.len())
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The error of decoding a [`Deferred`] message.
+///
+/// It converts to an `INTERNAL` status of every supported tonic version, as
+/// parse errors of the other codecs do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError(String);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for the offloading codec.
+macro_rules! impl_offload_codec {
+    ($tonic:ident) => {
+        impl<C: PayloadCodec> $tonic::codec::Codec for OffloadCodec<C> {
+            type Encode = C::Encode;
+            type Decode = Deferred<C>;
+
+            type Encoder = OffloadEncoder<C>;
+            type Decoder = OffloadDecoder<C>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                OffloadEncoder {
+                    codec: self.codec.clone(),
+                }
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                OffloadDecoder {
+                    codec: self.codec.clone(),
+                    threshold: self.threshold,
+                }
+            }
+        }
+
+        impl<C: PayloadCodec> $tonic::codec::Encoder for OffloadEncoder<C> {
+            type Item = C::Encode;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                let payload = self
+                    .codec
+                    .encode_payload(item)
+                    .map_err($tonic::Status::internal)?;
+                buf.put_slice(&payload);
+                Ok(())
+            }
+        }
+
+        impl<C: PayloadCodec> $tonic::codec::Decoder for OffloadDecoder<C> {
+            type Item = Deferred<C>;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                Ok(Some(Deferred {
+                    codec: self.codec.clone(),
+                    payload: buf.copy_to_bytes(buf.remaining()),
+                    threshold: self.threshold,
+                }))
+            }
+        }
+
+        impl From<DecodeError> for $tonic::Status {
+            fn from(error: DecodeError) -> Self {
+                // Map Protobuf parse errors to an INTERNAL status code, as per
+                // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                $tonic::Status::internal(error.0)
+            }
+        }
+    };
+}
+
+impl_offload_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_offload_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_offload_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_offload_codec!(tonic014);
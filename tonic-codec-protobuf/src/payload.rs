@@ -0,0 +1,82 @@
+use bytes::Bytes;
+
+/// A codec that converts messages to and from their serialized payloads, so
+/// that codecs transforming payloads, e.g. `EncryptedCodec` or
+/// `OffloadCodec`, can wrap it.
+pub trait PayloadCodec: Clone + Send + 'static {
+    /// The type of encoded messages.
+    type Encode: Send + 'static;
+    /// The type of decoded messages.
+    type Decode: Send + 'static;
+
+    /// Serializes `item`.
+    fn encode_payload(&self, item: Self::Encode) -> Result<Vec<u8>, String>;
+
+    /// Parses a message from `payload`.
+    fn decode_payload(&self, payload: &[u8]) -> Result<Self::Decode, String>;
+}
+
+#[cfg(feature = "protobuf-v3")]
+impl<T, U> PayloadCodec for crate::ProtobufCodecV3<T, U>
+where
+    T: protobuf::Message + Send + 'static,
+    U: protobuf::Message + Default + Send + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+
+    fn encode_payload(&self, item: T) -> Result<Vec<u8>, String> {
+        item.write_to_bytes().map_err(|e| e.to_string())
+    }
+
+    fn decode_payload(&self, payload: &[u8]) -> Result<U, String> {
+        U::parse_from_bytes(payload).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "protobuf-v3")]
+impl PayloadCodec for crate::DynamicCodec {
+    type Encode = Box<dyn protobuf::MessageDyn>;
+    type Decode = Box<dyn protobuf::MessageDyn>;
+
+    fn encode_payload(&self, item: Self::Encode) -> Result<Vec<u8>, String> {
+        item.write_to_bytes_dyn().map_err(|e| e.to_string())
+    }
+
+    fn decode_payload(&self, payload: &[u8]) -> Result<Self::Decode, String> {
+        self.decode
+            .parse_from_bytes(payload)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "protobuf-v2")]
+impl<T, U> PayloadCodec for crate::ProtobufCodecV2<T, U>
+where
+    T: protobuf2::Message + Clone + Send + 'static,
+    U: protobuf2::Message + Clone + Default + Send + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+
+    fn encode_payload(&self, item: T) -> Result<Vec<u8>, String> {
+        item.write_to_bytes().map_err(|e| e.to_string())
+    }
+
+    fn decode_payload(&self, payload: &[u8]) -> Result<U, String> {
+        U::parse_from_bytes(payload).map_err(|e| e.to_string())
+    }
+}
+
+impl PayloadCodec for crate::RawCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+
+    fn encode_payload(&self, item: Bytes) -> Result<Vec<u8>, String> {
+        Ok(item.into())
+    }
+
+    fn decode_payload(&self, payload: &[u8]) -> Result<Bytes, String> {
+        Ok(Bytes::copy_from_slice(payload))
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, marker::PhantomData};
+
+use bytes::{Buf, BufMut, Bytes};
+
+/// A [`Codec`](tonic::codec::Codec) that passes encoded messages through as
+/// bytes, without decoding them, e.g. for proxies.
+#[derive(Debug, Clone, Default)]
+pub struct RawCodec {
+    _priv: (),
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) that writes already encoded messages.
+#[derive(Debug, Clone, Default)]
+pub struct RawEncoder {
+    _priv: (),
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that returns the encoded messages.
+#[derive(Debug, Clone, Default)]
+pub struct RawDecoder {
+    _priv: (),
+}
+
+/// An already encoded message of type `T`, which [`PreEncodedCodec`] sends as
+/// is instead of serializing a message, e.g. a cached response.
+///
+/// `T` only keeps callers from sending bytes of the wrong type.
+pub struct PreEncoded<T> {
+    bytes: Bytes,
+    _pd: PhantomData<fn() -> T>,
+}
+
+impl<T> PreEncoded<T> {
+    /// Wrap `bytes`, which must be an encoded message of type `T`, e.g. the
+    /// output of `T::write_to_bytes`.
+    pub fn new(bytes: impl Into<Bytes>) -> Self {
+        PreEncoded {
+            bytes: bytes.into(),
+            _pd: PhantomData,
+        }
+    }
+
+    /// The encoded message.
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// Returns the encoded message.
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl<T> Clone for PreEncoded<T> {
+    fn clone(&self) -> Self {
+        PreEncoded::new(self.bytes.clone())
+    }
+}
+
+impl<T> fmt::Debug for PreEncoded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreEncoded")
+            .field("type", &std::any::type_name::<T>())
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+/// A [`Codec`](tonic::codec::Codec) that sends [`PreEncoded`] messages of
+/// another codec, and decodes messages with it.
+///
+/// The bytes of the messages must be encoded in the format of the wrapped
+/// codec, e.g. the protobuf wire format for `ProtobufCodecV3<T, U>`, which
+/// sends `PreEncoded<T>` messages with `PreEncodedCodec<ProtobufCodecV3<T,
+/// U>>`.
+#[derive(Debug, Clone, Default)]
+pub struct PreEncodedCodec<C> {
+    codec: C,
+}
+
+impl<C> PreEncodedCodec<C> {
+    /// Create a codec that decodes messages with `codec`.
+    pub fn new(codec: C) -> Self {
+        PreEncodedCodec { codec }
+    }
+}
+
+/// A [`Encoder`](tonic::codec::Encoder) that writes [`PreEncoded`] messages.
+pub struct PreEncodedEncoder<T> {
+    _pd: PhantomData<fn(T)>,
+}
+
+impl<T> fmt::Debug for PreEncodedEncoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreEncodedEncoder").finish()
+    }
+}
+
+/// Implements the `Codec`, `Encoder` and `Decoder` traits of the given tonic
+/// crate for the raw codec.
+macro_rules! impl_raw_codec {
+    ($tonic:ident) => {
+        impl $tonic::codec::Codec for RawCodec {
+            type Encode = Bytes;
+            type Decode = Bytes;
+
+            type Encoder = RawEncoder;
+            type Decoder = RawDecoder;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                RawEncoder::default()
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                RawDecoder::default()
+            }
+        }
+
+        impl $tonic::codec::Encoder for RawEncoder {
+            type Item = Bytes;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                buf.put(item);
+                Ok(())
+            }
+        }
+
+        impl<C: $tonic::codec::Codec> $tonic::codec::Codec for PreEncodedCodec<C> {
+            type Encode = PreEncoded<C::Encode>;
+            type Decode = C::Decode;
+
+            type Encoder = PreEncodedEncoder<C::Encode>;
+            type Decoder = C::Decoder;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                PreEncodedEncoder { _pd: PhantomData }
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                self.codec.decoder()
+            }
+        }
+
+        impl<T: Send + 'static> $tonic::codec::Encoder for PreEncodedEncoder<T> {
+            type Item = PreEncoded<T>;
+            type Error = $tonic::Status;
+
+            fn encode(
+                &mut self,
+                item: Self::Item,
+                buf: &mut $tonic::codec::EncodeBuf<'_>,
+            ) -> Result<(), Self::Error> {
+                buf.put(item.into_bytes());
+                Ok(())
+            }
+        }
+
+        impl $tonic::codec::Decoder for RawDecoder {
+            type Item = Bytes;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                Ok(Some(buf.copy_to_bytes(buf.remaining())))
+            }
+        }
+    };
+}
+
+impl_raw_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_raw_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_raw_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_raw_codec!(tonic014);
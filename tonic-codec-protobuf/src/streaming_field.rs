@@ -0,0 +1,83 @@
+use std::io::{self, BufRead, Write};
+
+use protobuf::{rt::WireType, CodedInputStream, CodedOutputStream, Message};
+
+/// The size of the chunks in which [`parse_streaming_field`] copies the
+/// streamed field.
+const CHUNK_LEN: u32 = 64 * 1024;
+
+/// Parses a message of type `M` from `reader`, except for its `bytes` field
+/// `field_number`, which is copied to `writer` instead, in chunks of 64 KiB.
+/// Returns the message without the field and the number of bytes written.
+///
+/// Messages dominated by one large field, e.g. snapshot chunks or file blobs,
+/// can thus be read from a file or a socket in constant memory, while
+/// `parse_from_reader` would hold the whole payload. The other fields are
+/// buffered and parsed as usual, wherever they appear in the encoding.
+///
+/// ```rust,ignore
+/// let mut file = File::create(&path)?;
+/// let (chunk, len): (SnapshotChunk, _) =
+///     tonic_codec_protobuf::parse_streaming_field(&mut reader, 3, &mut file)?;
+/// ```
+///
+/// `reader` must end with the message, wrap it with `Read::take` to read a
+/// message of a known length, e.g. from a [`frame`](crate::frame) header.
+/// Should the field occur more than once, all occurrences are written in
+/// order.
+///
+/// # Errors
+///
+/// Returns the errors of `reader` and `writer`, and
+/// [`io::ErrorKind::InvalidData`] if the message is malformed, if the field
+/// is not length-delimited, or if the message contains groups.
+pub fn parse_streaming_field<M: Message>(
+    reader: &mut dyn BufRead,
+    field_number: u32,
+    writer: &mut dyn Write,
+) -> io::Result<(M, u64)> {
+    let mut is = CodedInputStream::from_buf_read(reader);
+    let mut envelope = vec![];
+    let mut os = CodedOutputStream::vec(&mut envelope);
+    let mut chunk = vec![];
+    let mut written = 0;
+    while let Some(tag) = is.read_raw_tag_or_eof()? {
+        let (number, wire_type) = (tag >> 3, WireType::new(tag & 7));
+        match wire_type {
+            Some(WireType::LengthDelimited) if number == field_number => {
+                let mut len = is.read_raw_varint32()?;
+                while len > 0 {
+                    let n = len.min(CHUNK_LEN);
+                    is.read_raw_bytes_into(n, &mut chunk)?;
+                    writer.write_all(&chunk)?;
+                    len -= n;
+                    written += u64::from(n);
+                }
+            }
+            Some(_) if number == field_number => {
+                return Err(invalid_data(format!(
+                    "field {} is not length-delimited",
+                    field_number
+                )))
+            }
+            Some(WireType::StartGroup | WireType::EndGroup) => {
+                return Err(invalid_data(format!(
+                    "unsupported group in field {}",
+                    number
+                )))
+            }
+            Some(wire_type) => {
+                let value = is.read_unknown(wire_type)?;
+                os.write_unknown(number, value.get_ref())?;
+            }
+            None => return Err(invalid_data(format!("invalid wire type in tag {}", tag))),
+        }
+    }
+    os.flush()?;
+    drop(os);
+    Ok((M::parse_from_bytes(&envelope)?, written))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
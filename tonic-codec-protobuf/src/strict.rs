@@ -0,0 +1,285 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use bytes::Buf;
+use protobuf::{
+    reflect::{FieldDescriptor, MessageDescriptor, RuntimeFieldType, RuntimeType},
+    MessageFull,
+};
+
+use crate::ProtobufEncoderV3;
+
+/// The maximum depth of nested messages that are checked, as the recursion
+/// limit of rust-protobuf.
+const MAX_DEPTH: u32 = 100;
+
+/// A [`Codec`](tonic::codec::Codec) like
+/// [`ProtobufCodecV3`](crate::ProtobufCodecV3) that rejects messages in which a
+/// non-repeated field appears several times with different values.
+///
+/// Parsers keep the last value of such fields, so peers that keep the first
+/// one, e.g. a proxy checking requests, see a different message than the
+/// service does. Strict decoding rejects them with `INVALID_ARGUMENT`, in
+/// nested messages too, as well as several fields of the same oneof and map
+/// keys with several values. Repeating a field with the same value is
+/// accepted.
+#[derive(Debug, Clone, Default)]
+pub struct StrictCodec<T, U> {
+    _pd: PhantomData<(T, U)>,
+}
+
+/// A [`Decoder`](tonic::codec::Decoder) that strictly decodes `U`.
+#[derive(Debug, Clone, Default)]
+pub struct StrictDecoder<U> {
+    _pd: PhantomData<U>,
+}
+
+/// A value of a field on the wire. Varints are compared by value, so that
+/// overlong encodings of the same number do not conflict.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+fn read_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for i in 0..10 {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte < 0x80 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_bytes<'a>(buf: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if buf.len() < len {
+        return None;
+    }
+    let (bytes, rest) = buf.split_at(len);
+    *buf = rest;
+    Some(bytes)
+}
+
+/// Reads the value of a field of `wire_type`, or `None` if it is malformed or
+/// its groups nest deeper than [`MAX_DEPTH`].
+fn read_value<'a>(buf: &mut &'a [u8], field: u32, wire_type: u64, depth: u32) -> Option<Value<'a>> {
+    match wire_type {
+        0 => read_varint(buf).map(Value::Varint),
+        1 => read_bytes(buf, 8).map(Value::Bytes),
+        2 => {
+            let len = read_varint(buf)?;
+            read_bytes(buf, usize::try_from(len).ok()?).map(Value::Bytes)
+        }
+        3 => {
+            if depth >= MAX_DEPTH {
+                return None;
+            }
+            // A group spans the fields up to its end tag.
+            let start = *buf;
+            loop {
+                let tag = read_varint(buf)?;
+                let (number, wire_type) = ((tag >> 3) as u32, tag & 7);
+                if wire_type == 4 {
+                    if number != field {
+                        return None;
+                    }
+                    let len = start.len() - buf.len();
+                    return Some(Value::Bytes(&start[..len]));
+                }
+                read_value(buf, number, wire_type, depth + 1)?;
+            }
+        }
+        5 => read_bytes(buf, 4).map(Value::Bytes),
+        _ => None,
+    }
+}
+
+/// What a non-repeated field sets: the field itself, or the oneof containing
+/// it, of which only one field may be set.
+#[derive(PartialEq, Eq, Hash)]
+enum Slot {
+    Field(u32),
+    Oneof(String),
+}
+
+/// The key and the value of a map entry, if they are set.
+type Entry<'a> = (Option<Value<'a>>, Option<Value<'a>>);
+
+/// Reads the key and the value of an entry of the `map` field, or `None` if it
+/// is malformed.
+fn read_entry<'a>(
+    map: &FieldDescriptor,
+    mut buf: &'a [u8],
+    depth: u32,
+) -> Result<Option<Entry<'a>>, String> {
+    let (mut key, mut value) = (None, None);
+    while !buf.is_empty() {
+        let Some(tag) = read_varint(&mut buf) else {
+            return Ok(None);
+        };
+        let (number, wire_type) = ((tag >> 3) as u32, tag & 7);
+        let Some(field) = read_value(&mut buf, number, wire_type, depth) else {
+            return Ok(None);
+        };
+        let slot = match number {
+            1 => &mut key,
+            2 => &mut value,
+            _ => continue,
+        };
+        match slot {
+            Some(first) if *first != field => {
+                return Err(format!(
+                    "an entry of map {} sets its key or value multiple times",
+                    map.full_name()
+                ));
+            }
+            _ => *slot = Some(field),
+        }
+    }
+    Ok(Some((key, value)))
+}
+
+/// Checks that no non-repeated field of the `descriptor` message encoded in
+/// `buf` has conflicting values, that at most one field of each oneof is set,
+/// and that no map has a key with conflicting values.
+///
+/// Malformed messages pass the check, so that parsing them reports the error.
+fn check_fields(descriptor: &MessageDescriptor, mut buf: &[u8], depth: u32) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+    let mut seen = HashMap::new();
+    let mut entries = HashMap::new();
+    while !buf.is_empty() {
+        let Some(tag) = read_varint(&mut buf) else {
+            return Ok(());
+        };
+        let (number, wire_type) = ((tag >> 3) as u32, tag & 7);
+        let Some(value) = read_value(&mut buf, number, wire_type, depth) else {
+            return Ok(());
+        };
+        let Some(field) = descriptor.field_by_number(number) else {
+            continue;
+        };
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(ty) => {
+                if let (RuntimeType::Message(message), Value::Bytes(bytes)) = (ty, &value) {
+                    check_fields(&message, bytes, depth + 1)?;
+                }
+                let slot = match field.containing_oneof() {
+                    Some(oneof) => Slot::Oneof(oneof.name().to_owned()),
+                    None => Slot::Field(number),
+                };
+                match seen.get(&slot) {
+                    Some((first, _)) if *first != number => {
+                        return Err(format!(
+                            "fields {} and {} of the same oneof are both set",
+                            descriptor.field_by_number(*first).unwrap().full_name(),
+                            field.full_name()
+                        ));
+                    }
+                    Some((_, first)) if *first != value => {
+                        return Err(format!(
+                            "field {} appears multiple times with different values",
+                            field.full_name()
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        seen.insert(slot, (number, value));
+                    }
+                }
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(message)) => {
+                if let Value::Bytes(bytes) = value {
+                    check_fields(&message, bytes, depth + 1)?;
+                }
+            }
+            RuntimeFieldType::Map(_, value_type) => {
+                let Value::Bytes(bytes) = value else {
+                    return Ok(());
+                };
+                let Some((key, value)) = read_entry(&field, bytes, depth + 1)? else {
+                    return Ok(());
+                };
+                if let (RuntimeType::Message(message), Some(Value::Bytes(bytes))) =
+                    (value_type, &value)
+                {
+                    check_fields(&message, bytes, depth + 1)?;
+                }
+                match entries.get(&(number, key)) {
+                    Some(first) if *first != value => {
+                        return Err(format!(
+                            "a key of map {} appears multiple times with different values",
+                            field.full_name()
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        entries.insert((number, key), value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Implements the `Codec` and `Decoder` traits of the given tonic crate for
+/// the strict codec.
+macro_rules! impl_strict_codec {
+    ($tonic:ident) => {
+        impl<T, U> $tonic::codec::Codec for StrictCodec<T, U>
+        where
+            T: protobuf::Message + Send + 'static,
+            U: MessageFull + Send + 'static,
+        {
+            type Encode = T;
+            type Decode = U;
+
+            type Encoder = ProtobufEncoderV3<T>;
+            type Decoder = StrictDecoder<U>;
+
+            fn encoder(&mut self) -> Self::Encoder {
+                ProtobufEncoderV3::default()
+            }
+
+            fn decoder(&mut self) -> Self::Decoder {
+                StrictDecoder::default()
+            }
+        }
+
+        impl<U: MessageFull> $tonic::codec::Decoder for StrictDecoder<U> {
+            type Item = U;
+            type Error = $tonic::Status;
+
+            fn decode(
+                &mut self,
+                buf: &mut $tonic::codec::DecodeBuf<'_>,
+            ) -> Result<Option<Self::Item>, Self::Error> {
+                let bytes = buf.copy_to_bytes(buf.remaining());
+                check_fields(&U::descriptor(), &bytes, 0)
+                    .map_err($tonic::Status::invalid_argument)?;
+                let item = U::parse_from_bytes(&bytes).map_err(|error| {
+                    // Map Protobuf parse errors to an INTERNAL status code, as per
+                    // https://github.com/grpc/grpc/blob/master/doc/statuscodes.md
+                    $tonic::Status::new($tonic::Code::Internal, error.to_string())
+                })?;
+
+                Ok(Some(item))
+            }
+        }
+    };
+}
+
+impl_strict_codec!(tonic);
+#[cfg(feature = "tonic-0-12")]
+impl_strict_codec!(tonic012);
+#[cfg(feature = "tonic-0-13")]
+impl_strict_codec!(tonic013);
+#[cfg(feature = "tonic-0-14")]
+impl_strict_codec!(tonic014);
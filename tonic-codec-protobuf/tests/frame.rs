@@ -0,0 +1,189 @@
+use tonic_codec_protobuf::frame::{self, Frame, FrameError, FrameReader, OwnedFrame};
+
+#[test]
+fn test_frames() {
+    let mut buf = vec![];
+    frame::encode(false, b"abc", &mut buf).unwrap();
+    frame::encode(true, b"", &mut buf).unwrap();
+    let frames: Vec<_> = frame::frames(&buf).collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        frames,
+        [
+            Frame {
+                offset: 0,
+                compressed: false,
+                data: b"abc",
+            },
+            Frame {
+                offset: 8,
+                compressed: true,
+                data: b"",
+            },
+        ]
+    );
+
+    let mut frames = frame::frames(&buf[..10]);
+    assert!(frames.next().unwrap().is_ok());
+    assert_eq!(
+        frames.next().unwrap(),
+        Err(FrameError::Truncated {
+            offset: 8,
+            expected: 5,
+            actual: 2
+        })
+    );
+    assert_eq!(frames.next(), None);
+
+    let mut frames = frame::frames(&buf[..7]);
+    assert_eq!(
+        frames.next().unwrap(),
+        Err(FrameError::Truncated {
+            offset: 0,
+            expected: 8,
+            actual: 7
+        })
+    );
+
+    let mut frames = frame::frames(&[2, 0, 0, 0, 0]);
+    assert_eq!(
+        frames.next().unwrap(),
+        Err(FrameError::InvalidFlag { offset: 0, flag: 2 })
+    );
+    assert_eq!(frames.next(), None);
+}
+
+#[test]
+fn test_frame_reader() {
+    let mut buf = vec![];
+    frame::encode(false, b"abc", &mut buf).unwrap();
+    frame::encode(true, b"de", &mut buf).unwrap();
+
+    // Frames are split across chunks.
+    let mut reader = FrameReader::new();
+    let mut read = vec![];
+    for chunk in buf.chunks(3) {
+        reader
+            .feed(chunk, |frame| {
+                read.push((frame.compressed, frame.data.to_vec()))
+            })
+            .unwrap();
+    }
+    assert_eq!(read, [(false, b"abc".to_vec()), (true, b"de".to_vec())]);
+    assert_eq!(reader.error(), None);
+
+    let error = FrameError::InvalidFlag { offset: 0, flag: 2 };
+    assert_eq!(
+        reader.feed(&[2, 0, 0, 0, 0], |_| unreachable!()),
+        Err(error.clone())
+    );
+    assert_eq!(reader.feed(&buf, |_| unreachable!()), Err(error.clone()));
+    assert_eq!(reader.error(), Some(&error));
+}
+
+#[test]
+fn test_frame_reader_max_len() {
+    let mut buf = vec![];
+    frame::encode(false, b"abc", &mut buf).unwrap();
+    frame::encode(false, b"defg", &mut buf).unwrap();
+
+    // Frames up to the limit are read, whether complete or not.
+    let mut reader = FrameReader::with_max_len(3);
+    let mut read = vec![];
+    let result = reader.feed(&buf[..8], |frame| read.push(frame.data.to_vec()));
+    assert_eq!(result, Ok(()));
+    assert_eq!(read, [b"abc".to_vec()]);
+
+    // A longer frame fails once its header is complete, before its message.
+    let error = FrameError::TooLarge {
+        offset: 0,
+        len: 4,
+        max_len: 3,
+    };
+    assert_eq!(reader.feed(&buf[8..12], |_| unreachable!()), Ok(()));
+    assert_eq!(reader.feed(&buf[12..13], |_| unreachable!()), Err(error.clone()));
+    assert_eq!(reader.error(), Some(&error));
+    assert_eq!(
+        error.to_string(),
+        "frame of 4 bytes at offset 0 exceeds the limit of 3 bytes"
+    );
+
+    // Complete frames are checked as well.
+    let mut reader = FrameReader::with_max_len(3);
+    let result = reader.feed(&buf, |frame| assert_eq!(frame.data, b"abc"));
+    assert_eq!(
+        result,
+        Err(FrameError::TooLarge {
+            offset: 8,
+            len: 4,
+            max_len: 3,
+        })
+    );
+
+    // A header announcing 4GiB fails with the default limit.
+    let mut reader = FrameReader::new();
+    assert_eq!(
+        reader.feed(&[0, 0xff, 0xff, 0xff, 0xff], |_| unreachable!()),
+        Err(FrameError::TooLarge {
+            offset: 0,
+            len: u32::MAX as usize,
+            max_len: frame::DEFAULT_MAX_LEN,
+        })
+    );
+}
+
+#[test]
+fn test_read_write_frame() {
+    let mut buf = vec![];
+    frame::write_frame(&mut buf, false, b"abc").unwrap();
+    frame::write_frame(&mut buf, true, b"").unwrap();
+    let mut expected = vec![];
+    frame::encode(false, b"abc", &mut expected).unwrap();
+    frame::encode(true, b"", &mut expected).unwrap();
+    assert_eq!(buf, expected);
+
+    let mut reader = &buf[..];
+    assert_eq!(
+        frame::read_frame(&mut reader, 3).unwrap(),
+        Some(OwnedFrame {
+            compressed: false,
+            data: b"abc".to_vec(),
+        })
+    );
+    assert_eq!(
+        frame::read_frame(&mut reader, 3).unwrap(),
+        Some(OwnedFrame {
+            compressed: true,
+            data: vec![],
+        })
+    );
+    assert_eq!(frame::read_frame(&mut reader, 3).unwrap(), None);
+
+    // Messages over the limit are not read.
+    let error = frame::read_frame(&mut &buf[..], 2).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    for len in [2, 7] {
+        let error = frame::read_frame(&mut &buf[..len], 3).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    let error = frame::read_frame(&mut &[2, 0, 0, 0, 0][..], 3).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_too_large_frame() {
+    // Zeroed allocations are not touched, the length is checked first.
+    let data = vec![0u8; u32::MAX as usize + 1];
+
+    let mut buf = vec![1, 2];
+    let error = frame::encode(false, &data, &mut buf).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(buf, [1, 2]);
+
+    let mut buf = vec![];
+    let error = frame::write_frame(&mut buf, false, &data).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(buf.is_empty());
+}
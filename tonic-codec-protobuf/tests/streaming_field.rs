@@ -0,0 +1,58 @@
+#![cfg(feature = "protobuf-v3")]
+
+use std::io::{self, Read};
+
+use protobuf::{well_known_types::any::Any, CodedOutputStream, Message};
+use tonic_codec_protobuf::parse_streaming_field;
+
+#[test]
+fn test_streaming_field() {
+    let mut any = Any::new();
+    any.type_url = "type.googleapis.com/test.Blob".to_owned();
+    any.value = (0..200_000).map(|i| i as u8).collect();
+    let encoded = any.write_to_bytes().unwrap();
+
+    let mut blob = vec![];
+    let (envelope, len): (Any, _) =
+        parse_streaming_field(&mut encoded.as_slice(), 2, &mut blob).unwrap();
+    assert_eq!(envelope.type_url, any.type_url);
+    assert!(envelope.value.is_empty());
+    assert_eq!(len, 200_000);
+    assert_eq!(blob, any.value);
+
+    // The reader is limited to the message, e.g. the data of a frame.
+    let mut input = encoded.clone();
+    input.extend_from_slice(b"next frame");
+    let mut reader = io::BufReader::new(input.as_slice().take(encoded.len() as u64));
+    let mut blob = vec![];
+    let (envelope, _): (Any, _) = parse_streaming_field(&mut reader, 2, &mut blob).unwrap();
+    assert_eq!(envelope.type_url, any.type_url);
+    assert_eq!(blob, any.value);
+}
+
+#[test]
+fn test_streaming_field_errors() {
+    // The streamed field is written before the other fields.
+    let mut encoded = vec![];
+    let mut os = CodedOutputStream::vec(&mut encoded);
+    os.write_bytes(2, b"blob").unwrap();
+    os.write_string(1, "type").unwrap();
+    os.write_int32(2, 1).unwrap();
+    os.flush().unwrap();
+    drop(os);
+
+    let mut blob = vec![];
+    let e = parse_streaming_field::<Any>(&mut encoded.as_slice(), 2, &mut blob).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(blob, b"blob");
+
+    // Truncated.
+    let encoded = Any {
+        value: vec![1; 100],
+        ..Any::default()
+    }
+    .write_to_bytes()
+    .unwrap();
+    let e = parse_streaming_field::<Any>(&mut &encoded[..50], 2, &mut vec![]).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,472 @@
+//! Audit records of the calls of a server.
+//!
+//! An [`Auditor`] mounted as a layer emits one [`AuditRecord`] per gRPC call
+//! to an [`AuditSink`] once the call completes: its method, peer, message
+//! counts and sizes, status and duration, and the request fields marked
+//! `(tonic_protobuf.audit) = true`:
+//!
+//! ```protobuf
+//! import "tonic_protobuf/options.proto";
+//!
+//! message DeleteRequest {
+//!   string table = 1 [(tonic_protobuf.audit) = true];
+//!   string token = 2 [(tonic_protobuf.audit) = true, (tonic_protobuf.sensitive) = true];
+//! }
+//! ```
+//!
+//! Fields are captured from the first request message of a call, after it
+//! was redacted with [`tonic_types_protobuf::redact`], so fields that are also
+//! sensitive are captured masked. Sizes are those of the messages in the gRPC
+//! frames, excluding the frame headers.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Buf;
+use protobuf::{
+    reflect::{FieldDescriptor, RuntimeFieldType, RuntimeType},
+    MessageDyn,
+};
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    transport::server::TcpConnectInfo,
+    Code, Status,
+};
+use tonic_types_protobuf::{pb::options::exts, redact::redact};
+use tower_layer::Layer;
+
+use crate::{frame::HEADER_LEN, json, DescriptorPool, DynamicMethod};
+
+/// The request body an [`AuditService`] passes to its inner service, which
+/// `hyper::Body` converts from.
+pub type RequestStream = Box<dyn Stream<Item = Result<Bytes, StdError>> + Send>;
+
+/// Returns whether `field` is marked `(tonic_protobuf.audit) = true`.
+pub fn is_audited(field: &FieldDescriptor) -> bool {
+    field
+        .proto()
+        .options
+        .as_ref()
+        .and_then(|options| exts::audit.get(options))
+        .unwrap_or(false)
+}
+
+/// The audit record of a completed call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// The path of the method, e.g. `/pkg.Service/Method`.
+    pub method: String,
+    /// The address of the client, if served over TCP.
+    pub peer: Option<SocketAddr>,
+    /// The number of request messages.
+    pub request_messages: u64,
+    /// The total size of the request messages.
+    pub request_bytes: u64,
+    /// The number of response messages.
+    pub response_messages: u64,
+    /// The total size of the response messages.
+    pub response_bytes: u64,
+    /// The status code of the call, `CANCELLED` if it ended without a status.
+    pub code: Code,
+    /// The status message of the call.
+    pub message: String,
+    /// The time from receiving the call until its status was sent.
+    pub duration: Duration,
+    /// The audited request fields by path, e.g. `user.name`, with their
+    /// values in the proto3 JSON mapping.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl AuditRecord {
+    /// Returns the record as a compact JSON object, e.g. for a log line.
+    pub fn to_json(&self) -> String {
+        let fields: serde_json::Map<_, _> = self
+            .fields
+            .iter()
+            .map(|(path, value)| {
+                let value = serde_json::from_str(value).unwrap_or(serde_json::Value::Null);
+                (path.clone(), value)
+            })
+            .collect();
+        serde_json::json!({
+            "method": self.method,
+            "peer": self.peer.map(|peer| peer.to_string()),
+            "requestMessages": self.request_messages,
+            "requestBytes": self.request_bytes,
+            "responseMessages": self.response_messages,
+            "responseBytes": self.response_bytes,
+            "code": self.code as i32,
+            "message": self.message,
+            "durationMs": self.duration.as_secs_f64() * 1000.0,
+            "fields": fields,
+        })
+        .to_string()
+    }
+}
+
+/// Receives the [`AuditRecord`]s of an [`Auditor`].
+///
+/// Records are emitted on the tasks serving the calls, so sinks should hand
+/// them off, e.g. to a channel, rather than block.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Handles the record of a completed call.
+    fn record(&self, record: AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(AuditRecord) + Send + Sync + 'static,
+{
+    fn record(&self, record: AuditRecord) {
+        self(record)
+    }
+}
+
+/// Emits audit records of the gRPC calls of the wrapped service.
+#[derive(Clone)]
+pub struct Auditor {
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    sink: Arc<dyn AuditSink>,
+}
+
+impl Auditor {
+    /// Create an auditor that sends records to `sink`, with the request
+    /// fields of the methods of `pool`. Calls of other methods are audited
+    /// without fields.
+    pub fn new(pool: &DescriptorPool, sink: impl AuditSink) -> Self {
+        let methods = pool.methods().into_iter().map(|m| (m.path(), m)).collect();
+        Auditor {
+            methods: Arc::new(methods),
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Returns a layer that audits the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(auditor.into_layer())`.
+    pub fn into_layer(self) -> AuditLayer {
+        AuditLayer { auditor: self }
+    }
+}
+
+impl fmt::Debug for Auditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auditor")
+            .field("methods", &self.methods.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A layer that mounts an [`Auditor`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct AuditLayer {
+    auditor: Auditor,
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditService {
+            auditor: self.auditor.clone(),
+            inner,
+        }
+    }
+}
+
+/// Audits the gRPC calls of the inner service, and forwards other requests
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct AuditService<S> {
+    auditor: Auditor,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for AuditService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        if !grpc {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let method = self.auditor.methods.get(req.uri().path()).cloned();
+        let state = Arc::new(Mutex::new(CallState {
+            request: FrameCounter::new(method.is_some()),
+            response: FrameCounter::new(false),
+            status: None,
+        }));
+        let mut call = Call {
+            sink: self.auditor.sink.clone(),
+            method,
+            path: req.uri().path().to_owned(),
+            peer: req
+                .extensions()
+                .get::<TcpConnectInfo>()
+                .and_then(|info| info.remote_addr()),
+            start: Instant::now(),
+            state: state.clone(),
+            done: false,
+        };
+
+        let (parts, body) = req.into_parts();
+        let body: RequestStream = Box::new(CountedRequest {
+            body: Box::pin(body),
+            state,
+        });
+        let req = http::Request::from_parts(parts, B::from(body));
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let response = fut.await?;
+            let (parts, body) = response.into_parts();
+            // A trailers-only response carries the status in its headers.
+            if let Some(status) = Status::from_header_map(&parts.headers) {
+                call.state.lock().unwrap().status = Some(status);
+                call.finish();
+            }
+            let body = CountedResponse { body, call }.boxed_unsync();
+            Ok(http::Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// What was observed of a call so far, shared by its request and response
+/// bodies.
+struct CallState {
+    request: FrameCounter,
+    response: FrameCounter,
+    status: Option<Status>,
+}
+
+/// An audited call, that emits its record once it finished or was dropped.
+struct Call {
+    sink: Arc<dyn AuditSink>,
+    method: Option<DynamicMethod>,
+    path: String,
+    peer: Option<SocketAddr>,
+    start: Instant,
+    state: Arc<Mutex<CallState>>,
+    done: bool,
+}
+
+impl Call {
+    fn finish(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let state = self.state.lock().unwrap();
+        let (code, message) = match &state.status {
+            Some(status) => (status.code(), status.message().to_owned()),
+            None => (Code::Cancelled, "call ended without a status".to_owned()),
+        };
+        let fields = match (&self.method, &state.request.first) {
+            (Some(method), Some(first)) => capture(method, first),
+            _ => BTreeMap::new(),
+        };
+        let record = AuditRecord {
+            method: self.path.clone(),
+            peer: self.peer,
+            request_messages: state.request.messages,
+            request_bytes: state.request.bytes,
+            response_messages: state.response.messages,
+            response_bytes: state.response.bytes,
+            code,
+            message,
+            duration: self.start.elapsed(),
+            fields,
+        };
+        drop(state);
+        self.sink.record(record);
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Returns the audited fields of the request `data` of `method`.
+fn capture(method: &DynamicMethod, data: &[u8]) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    if let Ok(mut message) = method.input().parse_from_bytes(data) {
+        redact(&mut *message);
+        capture_message(&*message, "", &mut fields);
+    }
+    fields
+}
+
+fn capture_message(message: &dyn MessageDyn, prefix: &str, fields: &mut BTreeMap<String, String>) {
+    for field in message.descriptor_dyn().fields() {
+        let path = format!("{}{}", prefix, field.name());
+        if is_audited(&field) {
+            fields.insert(path, json::print_field(message, &field));
+        } else if let RuntimeFieldType::Singular(RuntimeType::Message(_)) =
+            field.runtime_field_type()
+        {
+            if field.has_field(message) {
+                let nested = field.get_message(message);
+                capture_message(&*nested, &format!("{}.", path), fields);
+            }
+        }
+    }
+}
+
+/// Counts the gRPC frames of a body, and keeps the first message if asked
+/// to.
+struct FrameCounter {
+    messages: u64,
+    bytes: u64,
+    header: Vec<u8>,
+    remaining: usize,
+    capture: bool,
+    current: Option<Vec<u8>>,
+    first: Option<Vec<u8>>,
+}
+
+impl FrameCounter {
+    fn new(capture: bool) -> Self {
+        FrameCounter {
+            messages: 0,
+            bytes: 0,
+            header: Vec::with_capacity(HEADER_LEN),
+            remaining: 0,
+            capture,
+            current: None,
+            first: None,
+        }
+    }
+
+    fn feed(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.remaining == 0 {
+                let n = (HEADER_LEN - self.header.len()).min(data.len());
+                self.header.extend_from_slice(&data[..n]);
+                data = &data[n..];
+                if self.header.len() < HEADER_LEN {
+                    return;
+                }
+                let len = u32::from_be_bytes(self.header[1..].try_into().unwrap()) as usize;
+                // Compressed messages can not be decoded for their fields.
+                let compressed = self.header[0] != 0;
+                self.header.clear();
+                self.messages += 1;
+                self.bytes += len as u64;
+                self.remaining = len;
+                if self.capture && self.messages == 1 && !compressed {
+                    self.current = Some(Vec::with_capacity(len));
+                }
+            } else {
+                let n = self.remaining.min(data.len());
+                if let Some(current) = &mut self.current {
+                    current.extend_from_slice(&data[..n]);
+                }
+                data = &data[n..];
+                self.remaining -= n;
+            }
+            if self.remaining == 0 && self.current.is_some() {
+                self.first = self.current.take();
+            }
+        }
+    }
+}
+
+/// A request body that counts its frames.
+struct CountedRequest<B> {
+    body: Pin<Box<B>>,
+    state: Arc<Mutex<CallState>>,
+}
+
+impl<B> Stream for CountedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(self.body.as_mut().poll_data(cx)) {
+            Some(Ok(mut data)) => {
+                let data = data.copy_to_bytes(data.remaining());
+                self.state.lock().unwrap().request.feed(&data);
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A response body that counts its frames, and finishes the call with the
+/// status of its trailers.
+struct CountedResponse {
+    body: BoxBody,
+    call: Call,
+}
+
+impl Body for CountedResponse {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let data = ready!(Pin::new(&mut self.body).poll_data(cx));
+        if let Some(Ok(data)) = &data {
+            self.call.state.lock().unwrap().response.feed(data);
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let trailers = ready!(Pin::new(&mut self.body).poll_trailers(cx));
+        if let Some(status) = trailers
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .and_then(Status::from_header_map)
+        {
+            self.call.state.lock().unwrap().status = Some(status);
+        }
+        self.call.finish();
+        Poll::Ready(trailers)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
@@ -0,0 +1,312 @@
+//! Caching of the responses of idempotent unary methods in clients.
+//!
+//! A [`ResponseCache`] is a layer over the channel of a client. Calls of the
+//! cached methods are keyed by their path and encoded request messages, so
+//! identical requests within the time to live of a response are answered
+//! from memory instead of the network. Only successful responses are cached.
+//!
+//! Code generated with `Builder::idempotent_option` lists the methods marked
+//! idempotent in the `IDEMPOTENT_METHODS` constant of client modules.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::BufMut;
+use http_body::Full;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Status,
+};
+use tower_layer::Layer;
+
+/// The path of a call and its encoded request messages.
+type Key = (String, Bytes);
+
+/// Caches the responses of idempotent unary methods in the wrapped client
+/// channel.
+///
+/// Requests are keyed by their path and messages only, so metadata, e.g.
+/// credentials, must not change the response of a cached method.
+#[derive(Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    methods: Arc<HashSet<String>>,
+    entries: Arc<Mutex<HashMap<Key, Entry>>>,
+}
+
+impl ResponseCache {
+    /// Create a cache that keeps responses for `ttl`. No method is cached
+    /// until added with [`ResponseCache::methods`].
+    pub fn new(ttl: Duration) -> Self {
+        ResponseCache {
+            ttl,
+            max_entries: 1024,
+            methods: Arc::default(),
+            entries: Arc::default(),
+        }
+    }
+
+    /// Cache the responses of the methods with `paths`, e.g.
+    /// `"/helloworld.Greeter/SayHello"` or the `IDEMPOTENT_METHODS` of a
+    /// generated client module.
+    pub fn methods<I>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let methods = Arc::make_mut(&mut self.methods);
+        methods.extend(paths.into_iter().map(|p| p.as_ref().to_owned()));
+        self
+    }
+
+    /// Set the maximum number of cached responses, 1024 by default. The
+    /// responses that expire first are evicted to make room.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns a layer that caches responses of the wrapped channel, e.g.
+    /// `ServiceBuilder::new().layer(cache.into_layer()).service(channel)`.
+    /// Layers of clones of the cache share its responses.
+    pub fn into_layer(self) -> ResponseCacheLayer {
+        ResponseCacheLayer { cache: self }
+    }
+
+    /// Drop all cached responses.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn get(&self, key: &Key) -> Option<Arc<CachedResponse>> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: Key, response: Arc<CachedResponse>) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.expires > now);
+        }
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let first = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires)
+                .map(|(key, _)| key.clone());
+            if let Some(first) = first {
+                entries.remove(&first);
+            }
+        }
+        let expires = now + self.ttl;
+        entries.insert(key, Entry { expires, response });
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("ttl", &self.ttl)
+            .field("max_entries", &self.max_entries)
+            .field("methods", &self.methods)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Entry {
+    expires: Instant,
+    response: Arc<CachedResponse>,
+}
+
+/// A buffered response, with its trailers.
+struct CachedResponse {
+    status: http::StatusCode,
+    version: http::Version,
+    headers: http::HeaderMap,
+    data: Bytes,
+    trailers: Option<http::HeaderMap>,
+}
+
+impl CachedResponse {
+    /// Returns whether the call succeeded, with a `grpc-status` of 0.
+    fn is_ok(&self) -> bool {
+        let headers = self.trailers.as_ref().unwrap_or(&self.headers);
+        self.status == http::StatusCode::OK && headers.get("grpc-status").is_some_and(|s| s == "0")
+    }
+
+    /// Returns a copy of the response, failing with `error` after its data.
+    fn replay(&self, error: Option<Status>) -> http::Response<BoxBody> {
+        let body = Replay {
+            data: Some(self.data.clone()).filter(|data| !data.is_empty()),
+            trailers: self.trailers.clone(),
+            error,
+        };
+        let mut response = http::Response::new(body.boxed_unsync());
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+/// A layer that mounts a [`ResponseCache`] in front of a client channel.
+#[derive(Debug, Clone)]
+pub struct ResponseCacheLayer {
+    cache: ResponseCache,
+}
+
+impl<S> Layer<S> for ResponseCacheLayer {
+    type Service = ResponseCacheService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResponseCacheService {
+            cache: self.cache.clone(),
+            inner,
+        }
+    }
+}
+
+/// Answers the calls of cached methods from a [`ResponseCache`], and
+/// forwards other calls unchanged.
+#[derive(Debug, Clone)]
+pub struct ResponseCacheService<S> {
+    cache: ResponseCache,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<BoxBody>> for ResponseCacheService<S>
+where
+    S: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<StdError>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let path = req.uri().path().to_owned();
+        if !self.cache.methods.contains(&path) {
+            let response = self.inner.call(req);
+            return Box::pin(async move { Ok(response.await?.map(boxed)) });
+        }
+
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let cache = self.cache.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let (request, _, error) = collect(body).await;
+            if let Some(status) = error {
+                return Ok(status.to_http());
+            }
+            let key = (path, request.clone());
+            if let Some(response) = cache.get(&key) {
+                return Ok(response.replay(None));
+            }
+
+            let body = Full::new(request).map_err(|never| -> Status { match never {} });
+            let req = http::Request::from_parts(parts, body.boxed_unsync());
+            let (parts, body) = inner.call(req).await?.into_parts();
+            let (data, trailers, error) = collect(body).await;
+            let response = Arc::new(CachedResponse {
+                status: parts.status,
+                version: parts.version,
+                headers: parts.headers,
+                data,
+                trailers,
+            });
+            if error.is_none() && response.is_ok() {
+                cache.insert(key, response.clone());
+            }
+            Ok(response.replay(error))
+        })
+    }
+}
+
+fn boxed<B>(body: B) -> BoxBody
+where
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<StdError>,
+{
+    body.map_err(|e| Status::from_error(e.into()))
+        .boxed_unsync()
+}
+
+/// Reads `body` to the end, and returns its data, its trailers and the error
+/// it failed with, if any.
+async fn collect<B>(body: B) -> (Bytes, Option<http::HeaderMap>, Option<Status>)
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<StdError>,
+{
+    let mut body = std::pin::pin!(body);
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => data.put(chunk),
+            Err(e) => return (data.into(), None, Some(Status::from_error(e.into()))),
+        }
+    }
+    match body.trailers().await {
+        Ok(trailers) => (data.into(), trailers, None),
+        Err(e) => (data.into(), None, Some(Status::from_error(e.into()))),
+    }
+}
+
+/// The body of a buffered response.
+struct Replay {
+    data: Option<Bytes>,
+    trailers: Option<http::HeaderMap>,
+    error: Option<Status>,
+}
+
+impl Body for Replay {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        if let Some(data) = self.data.take() {
+            return Poll::Ready(Some(Ok(data)));
+        }
+        Poll::Ready(self.error.take().map(Err))
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(self.trailers.take()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_none() && self.trailers.is_none() && self.error.is_none()
+    }
+}
@@ -0,0 +1,231 @@
+//! A client that calls methods described at runtime.
+
+use protobuf::MessageDyn;
+use tonic::{
+    client::Grpc, codegen::http::uri::PathAndQuery, transport::Channel, IntoStreamingRequest,
+    Request, Response, Status, Streaming,
+};
+use tonic_codec_protobuf::DynamicCodec;
+
+use crate::{DescriptorPool, DynamicMethod};
+
+/// A dynamic message, e.g. created with `MessageDescriptor::new_instance`.
+pub type DynamicMessage = Box<dyn MessageDyn>;
+
+/// A grpcurl-style client that sends and receives dynamic messages of any
+/// method, without generated code.
+#[derive(Debug, Clone)]
+pub struct DynamicClient {
+    inner: Grpc<Channel>,
+}
+
+impl DynamicClient {
+    /// Create a client that uses `channel`.
+    pub fn new(channel: Channel) -> Self {
+        DynamicClient {
+            inner: Grpc::new(channel),
+        }
+    }
+
+    /// Calls a unary method.
+    pub async fn unary(
+        &mut self,
+        method: &DynamicMethod,
+        request: Request<DynamicMessage>,
+    ) -> Result<Response<DynamicMessage>, Status> {
+        check_shape(method, false, false)?;
+        check_input(method, request.get_ref())?;
+        let (path, codec) = self.prepare(method).await?;
+        self.inner.unary(request, path, codec).await
+    }
+
+    /// Calls a server streaming method.
+    pub async fn server_streaming(
+        &mut self,
+        method: &DynamicMethod,
+        request: Request<DynamicMessage>,
+    ) -> Result<Response<Streaming<DynamicMessage>>, Status> {
+        check_shape(method, false, true)?;
+        check_input(method, request.get_ref())?;
+        let (path, codec) = self.prepare(method).await?;
+        self.inner.server_streaming(request, path, codec).await
+    }
+
+    /// Calls a client streaming method.
+    ///
+    /// The types of streamed requests are not checked against the method.
+    pub async fn client_streaming(
+        &mut self,
+        method: &DynamicMethod,
+        request: impl IntoStreamingRequest<Message = DynamicMessage>,
+    ) -> Result<Response<DynamicMessage>, Status> {
+        check_shape(method, true, false)?;
+        let (path, codec) = self.prepare(method).await?;
+        self.inner
+            .client_streaming(request.into_streaming_request(), path, codec)
+            .await
+    }
+
+    /// Calls a bidirectional streaming method.
+    ///
+    /// The types of streamed requests are not checked against the method.
+    pub async fn streaming(
+        &mut self,
+        method: &DynamicMethod,
+        request: impl IntoStreamingRequest<Message = DynamicMessage>,
+    ) -> Result<Response<Streaming<DynamicMessage>>, Status> {
+        check_shape(method, true, true)?;
+        let (path, codec) = self.prepare(method).await?;
+        self.inner
+            .streaming(request.into_streaming_request(), path, codec)
+            .await
+    }
+
+    /// Returns a handle that calls `method` with this client.
+    pub fn method(&self, method: &DynamicMethod) -> MethodHandle {
+        MethodHandle {
+            client: self.clone(),
+            method: method.clone(),
+        }
+    }
+
+    /// Returns the handles of all methods of the service with the
+    /// fully-qualified `service` name in `pool`, or `None` if the pool has no
+    /// such service.
+    pub fn service(&self, pool: &DescriptorPool, service: &str) -> Option<DynamicStub> {
+        let methods: Vec<_> = pool
+            .service(service)
+            .iter()
+            .map(|m| self.method(m))
+            .collect();
+        if methods.is_empty() {
+            return None;
+        }
+        Some(DynamicStub { methods })
+    }
+
+    async fn prepare(
+        &mut self,
+        method: &DynamicMethod,
+    ) -> Result<(PathAndQuery, DynamicCodec), Status> {
+        self.inner.ready().await.map_err(|e| {
+            Status::new(
+                tonic::Code::Unknown,
+                format!("Service was not ready: {}", e),
+            )
+        })?;
+        let path = PathAndQuery::try_from(method.path())
+            .map_err(|e| Status::invalid_argument(format!("invalid method path: {}", e)))?;
+        Ok((path, DynamicCodec::new(method.output().clone())))
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn check_shape(
+    method: &DynamicMethod,
+    client_streaming: bool,
+    server_streaming: bool,
+) -> Result<(), Status> {
+    if method.client_streaming() != client_streaming
+        || method.server_streaming() != server_streaming
+    {
+        return Err(Status::invalid_argument(format!(
+            "method {} is {}",
+            method.path(),
+            match (method.client_streaming(), method.server_streaming()) {
+                (false, false) => "unary",
+                (false, true) => "server streaming",
+                (true, false) => "client streaming",
+                (true, true) => "bidirectional streaming",
+            }
+        )));
+    }
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn check_input(method: &DynamicMethod, request: &DynamicMessage) -> Result<(), Status> {
+    if request.descriptor_dyn().full_name() != method.input().full_name() {
+        return Err(Status::invalid_argument(format!(
+            "method {} expects {}, got {}",
+            method.path(),
+            method.input().full_name(),
+            request.descriptor_dyn().full_name()
+        )));
+    }
+    Ok(())
+}
+
+/// A method bound to a [`DynamicClient`], the runtime counterpart of a
+/// method of a generated client.
+#[derive(Debug, Clone)]
+pub struct MethodHandle {
+    client: DynamicClient,
+    method: DynamicMethod,
+}
+
+impl MethodHandle {
+    /// The called method.
+    pub fn method(&self) -> &DynamicMethod {
+        &self.method
+    }
+
+    /// Returns an empty request message of the method.
+    pub fn new_request(&self) -> DynamicMessage {
+        self.method.input().new_instance()
+    }
+
+    /// Calls the method, which must be unary.
+    pub async fn unary(
+        &mut self,
+        request: Request<DynamicMessage>,
+    ) -> Result<Response<DynamicMessage>, Status> {
+        self.client.unary(&self.method, request).await
+    }
+
+    /// Calls the method, which must be server streaming.
+    pub async fn server_streaming(
+        &mut self,
+        request: Request<DynamicMessage>,
+    ) -> Result<Response<Streaming<DynamicMessage>>, Status> {
+        self.client.server_streaming(&self.method, request).await
+    }
+
+    /// Calls the method, which must be client streaming.
+    pub async fn client_streaming(
+        &mut self,
+        request: impl IntoStreamingRequest<Message = DynamicMessage>,
+    ) -> Result<Response<DynamicMessage>, Status> {
+        self.client.client_streaming(&self.method, request).await
+    }
+
+    /// Calls the method, which must be bidirectional streaming.
+    pub async fn streaming(
+        &mut self,
+        request: impl IntoStreamingRequest<Message = DynamicMessage>,
+    ) -> Result<Response<Streaming<DynamicMessage>>, Status> {
+        self.client.streaming(&self.method, request).await
+    }
+}
+
+/// The method handles of a service, the runtime counterpart of a generated
+/// client, see [`DynamicClient::service`].
+#[derive(Debug, Clone)]
+pub struct DynamicStub {
+    methods: Vec<MethodHandle>,
+}
+
+impl DynamicStub {
+    /// Returns the handle of the method named `name`, e.g. `Check`.
+    pub fn method(&self, name: &str) -> Option<MethodHandle> {
+        self.methods
+            .iter()
+            .find(|h| h.method.name() == name)
+            .cloned()
+    }
+
+    /// Returns the handles of all methods, in declaration order.
+    pub fn methods(&self) -> &[MethodHandle] {
+        &self.methods
+    }
+}
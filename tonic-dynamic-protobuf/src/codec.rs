@@ -0,0 +1,91 @@
+//! A codec that encodes generated messages in the JSON mapping, to serve
+//! `application/grpc+json` next to `application/grpc+proto`.
+
+use std::marker::PhantomData;
+
+use bytes::{Buf, BufMut};
+use protobuf::MessageFull;
+use tonic::{
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    Status,
+};
+use tonic_codec_protobuf::{Negotiated, ProtobufCodecV3};
+
+use crate::json;
+
+/// A codec that negotiates between the protobuf wire format and the JSON
+/// mapping per call, by the content-type of the request. Point
+/// `Builder::codec_path` of servers generated with `Builder::select_codec`
+/// to it.
+pub type ProtobufOrJsonCodec<T, U> = Negotiated<ProtobufCodecV3<T, U>, JsonCodec<T, U>>;
+
+/// A [`Codec`] that encodes and decodes rust-protobuf messages in the proto3
+/// JSON mapping, see [`json`](crate::json). Messages must be generated for
+/// the full runtime, which supports reflection.
+#[derive(Debug, Clone)]
+pub struct JsonCodec<T, U> {
+    _pd: PhantomData<(T, U)>,
+}
+
+impl<T, U> Default for JsonCodec<T, U> {
+    fn default() -> Self {
+        JsonCodec { _pd: PhantomData }
+    }
+}
+
+/// A [`Encoder`] that prints `T` as JSON.
+#[derive(Debug, Clone)]
+pub struct JsonEncoder<T> {
+    _pd: PhantomData<T>,
+}
+
+/// A [`Decoder`] that parses `U` from JSON.
+#[derive(Debug, Clone)]
+pub struct JsonDecoder<U> {
+    _pd: PhantomData<U>,
+}
+
+impl<T, U> Codec for JsonCodec<T, U>
+where
+    T: MessageFull,
+    U: MessageFull,
+{
+    type Encode = T;
+    type Decode = U;
+
+    type Encoder = JsonEncoder<T>;
+    type Decoder = JsonDecoder<U>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        JsonEncoder { _pd: PhantomData }
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        JsonDecoder { _pd: PhantomData }
+    }
+}
+
+impl<T: MessageFull> Encoder for JsonEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        buf.put_slice(json::print_to_string(&item).as_bytes());
+        Ok(())
+    }
+}
+
+impl<U: MessageFull> Decoder for JsonDecoder<U> {
+    type Item = U;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let data = buf.copy_to_bytes(buf.remaining());
+        // Map parse errors to INTERNAL like the protobuf codecs.
+        let value: serde_json::Value =
+            serde_json::from_slice(&data).map_err(|e| Status::internal(e.to_string()))?;
+        let mut item = U::new();
+        json::merge_from_value(&mut item, &value).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Some(item))
+    }
+}
@@ -0,0 +1,436 @@
+//! Inspection of the gRPC traffic passing a server or proxy, like a
+//! lightweight packet analyzer for debugging.
+//!
+//! An [`Inspector`] mounted as a layer prints every request and response
+//! message of the calls it inspects in the protobuf text format, and their
+//! status, as [`InspectEvent`]s to an [`InspectSink`]. Messages are decoded
+//! as dynamic messages of the methods of its [`DescriptorPool`], so the
+//! binary needs no generated types for them, e.g. in front of a
+//! [`GrpcProxy`](crate::GrpcProxy). Messages of methods missing from the
+//! pool, compressed messages and messages that fail to decode are printed
+//! with their sizes.
+//!
+//! Messages are redacted with [`tonic_types_protobuf::redact`] before they
+//! are printed. Calls can be filtered by method, and sampled at a rate.
+//! Messages longer than [`Inspector::max_message_size`] are reported as
+//! malformed frames instead of being buffered, and the rest of their body is
+//! not inspected.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+};
+
+use bytes::Buf;
+use protobuf::{reflect::MessageDescriptor, text_format};
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Code, Status,
+};
+use tonic_types_protobuf::redact::redact;
+use tower_layer::Layer;
+
+use crate::{
+    audit::RequestStream,
+    frame::{Frame, FrameReader, DEFAULT_MAX_LEN},
+    sample::sampled,
+    DescriptorPool, DynamicMethod,
+};
+
+/// An event of an inspected call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectEvent {
+    /// The number of the call among the inspected calls, to tell apart the
+    /// events of concurrent calls.
+    pub call: u64,
+    /// The path of the method, e.g. `/pkg.Service/Method`.
+    pub method: String,
+    /// What happened.
+    pub kind: EventKind,
+}
+
+/// What happened in an inspected call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// A request message, printed in the text format.
+    Request(String),
+    /// A response message, printed in the text format.
+    Response(String),
+    /// The call ended with the status code and message, `CANCELLED` if it
+    /// ended without a status.
+    Status(Code, String),
+}
+
+impl fmt::Display for InspectEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {} ", self.call, self.method)?;
+        match &self.kind {
+            EventKind::Request(text) => write!(f, "> {}", text),
+            EventKind::Response(text) => write!(f, "< {}", text),
+            EventKind::Status(code, message) if message.is_empty() => {
+                write!(f, "= {:?}", code)
+            }
+            EventKind::Status(code, message) => write!(f, "= {:?}: {}", code, message),
+        }
+    }
+}
+
+/// Receives the [`InspectEvent`]s of an [`Inspector`].
+///
+/// Events are emitted on the tasks serving the calls, so sinks should hand
+/// them off, e.g. to a channel or a log, rather than block.
+pub trait InspectSink: Send + Sync + 'static {
+    /// Handles an event of an inspected call.
+    fn event(&self, event: InspectEvent);
+}
+
+impl<F> InspectSink for F
+where
+    F: Fn(InspectEvent) + Send + Sync + 'static,
+{
+    fn event(&self, event: InspectEvent) {
+        self(event)
+    }
+}
+
+/// Prints the gRPC calls of the wrapped service.
+#[derive(Clone)]
+pub struct Inspector {
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    sink: Arc<dyn InspectSink>,
+    filter: Option<Arc<[String]>>,
+    rate: f64,
+    max_message_size: usize,
+    random: RandomState,
+    calls: Arc<AtomicU64>,
+}
+
+impl Inspector {
+    /// Create an inspector that sends the events of all calls to `sink`,
+    /// decoding the messages of the methods of `pool`.
+    pub fn new(pool: &DescriptorPool, sink: impl InspectSink) -> Self {
+        let methods = pool.methods().into_iter().map(|m| (m.path(), m)).collect();
+        Inspector {
+            methods: Arc::new(methods),
+            sink: Arc::new(sink),
+            filter: None,
+            rate: 1.0,
+            max_message_size: DEFAULT_MAX_LEN,
+            random: RandomState::new(),
+            calls: Arc::default(),
+        }
+    }
+
+    /// Only inspect the calls of the given methods, e.g.
+    /// `pkg.Service/Method`, or of all methods of the given services, e.g.
+    /// `pkg.Service`.
+    pub fn methods<I, N>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        self.filter = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the fraction of the calls that are inspected, from 0.0 to 1.0.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the maximum size of the messages that are inspected.
+    ///
+    /// Default: `4MB`
+    pub fn max_message_size(mut self, limit: usize) -> Self {
+        self.max_message_size = limit;
+        self
+    }
+
+    /// Returns a layer that inspects the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(inspector.into_layer())`.
+    pub fn into_layer(self) -> InspectLayer {
+        InspectLayer { inspector: self }
+    }
+
+    /// Returns whether the calls of `path`, e.g. `/pkg.Service/Method`, pass
+    /// the filter.
+    fn matches(&self, path: &str) -> bool {
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let name = path.trim_start_matches('/');
+        let service = name.rsplit_once('/').map_or(name, |(service, _)| service);
+        filter.iter().any(|f| f == name || f == service)
+    }
+}
+
+impl fmt::Debug for Inspector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspector")
+            .field("methods", &self.methods.len())
+            .field("filter", &self.filter)
+            .field("rate", &self.rate)
+            .field("max_message_size", &self.max_message_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A layer that mounts an [`Inspector`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct InspectLayer {
+    inspector: Inspector,
+}
+
+impl<S> Layer<S> for InspectLayer {
+    type Service = InspectService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InspectService {
+            inspector: self.inspector.clone(),
+            inner,
+        }
+    }
+}
+
+/// Inspects the gRPC calls of the inner service, and forwards other requests
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct InspectService<S> {
+    inspector: Inspector,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for InspectService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        if !grpc || !self.inspector.matches(req.uri().path()) {
+            return Box::pin(self.inner.call(req));
+        }
+        let inspector = &self.inspector;
+        let n = inspector.calls.fetch_add(1, Ordering::Relaxed);
+        if !sampled(inspector.rate, &inspector.random, n) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let path = req.uri().path().to_owned();
+        let method = inspector.methods.get(&path).cloned();
+        let call = Arc::new(Call {
+            sink: inspector.sink.clone(),
+            id: n,
+            path,
+            method,
+        });
+        let max_len = inspector.max_message_size;
+        let (parts, body) = req.into_parts();
+        let body: RequestStream = Box::new(InspectedRequest {
+            body: Box::pin(body),
+            call: call.clone(),
+            frames: FrameReader::with_max_len(max_len),
+        });
+        let req = http::Request::from_parts(parts, B::from(body));
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let response = fut.await?;
+            let (parts, body) = response.into_parts();
+            let mut body = InspectedResponse {
+                body,
+                call,
+                frames: FrameReader::with_max_len(max_len),
+                done: false,
+            };
+            // A trailers-only response carries the status in its headers.
+            if let Some(status) = Status::from_header_map(&parts.headers) {
+                body.finish(Some(status));
+            }
+            Ok(http::Response::from_parts(parts, body.boxed_unsync()))
+        })
+    }
+}
+
+/// An inspected call.
+struct Call {
+    sink: Arc<dyn InspectSink>,
+    id: u64,
+    path: String,
+    method: Option<DynamicMethod>,
+}
+
+impl Call {
+    fn emit(&self, kind: EventKind) {
+        self.sink.event(InspectEvent {
+            call: self.id,
+            method: self.path.clone(),
+            kind,
+        });
+    }
+
+    /// Emits the frames completed by `data` of the request or response.
+    fn feed(&self, frames: &mut FrameReader, request: bool, data: &[u8]) {
+        let descriptor = self.method.as_ref().map(|method| {
+            if request {
+                method.input()
+            } else {
+                method.output()
+            }
+        });
+        let kind = if request {
+            EventKind::Request
+        } else {
+            EventKind::Response
+        };
+        // Report a malformed body once, and ignore the rest.
+        if frames.error().is_some() {
+            return;
+        }
+        let result = frames.feed(data, |frame| self.emit(kind(print(descriptor, frame))));
+        if let Err(e) = result {
+            self.emit(kind(format!("<malformed frame: {}>", e)));
+        }
+    }
+}
+
+/// Prints the message of `frame` with `descriptor`, or describes it if it
+/// can not be decoded.
+fn print(descriptor: Option<&MessageDescriptor>, frame: Frame<'_>) -> String {
+    let len = frame.data.len();
+    if frame.compressed {
+        return format!("<compressed, {} bytes>", len);
+    }
+    let Some(descriptor) = descriptor else {
+        return format!("<{} bytes>", len);
+    };
+    match descriptor.parse_from_bytes(frame.data) {
+        Ok(mut message) => {
+            redact(&mut *message);
+            let text = text_format::print_to_string(&*message);
+            if text.is_empty() {
+                "{}".to_owned()
+            } else {
+                text
+            }
+        }
+        Err(e) => format!("<{} bytes, failed to decode: {}>", len, e),
+    }
+}
+
+/// A request body that emits its messages.
+struct InspectedRequest<B> {
+    body: Pin<Box<B>>,
+    call: Arc<Call>,
+    frames: FrameReader,
+}
+
+impl<B> Stream for InspectedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(self.body.as_mut().poll_data(cx)) {
+            Some(Ok(mut data)) => {
+                let data = data.copy_to_bytes(data.remaining());
+                let this = &mut *self;
+                this.call.feed(&mut this.frames, true, &data);
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A response body that emits its messages, and the status of its trailers.
+struct InspectedResponse {
+    body: BoxBody,
+    call: Arc<Call>,
+    frames: FrameReader,
+    done: bool,
+}
+
+impl InspectedResponse {
+    fn finish(&mut self, status: Option<Status>) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let kind = match status {
+            Some(status) => EventKind::Status(status.code(), status.message().to_owned()),
+            None => EventKind::Status(Code::Cancelled, "call ended without a status".to_owned()),
+        };
+        self.call.emit(kind);
+    }
+}
+
+impl Drop for InspectedResponse {
+    fn drop(&mut self) {
+        self.finish(None);
+    }
+}
+
+impl Body for InspectedResponse {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let data = ready!(Pin::new(&mut self.body).poll_data(cx));
+        if let Some(Ok(data)) = &data {
+            let this = &mut *self;
+            this.call.feed(&mut this.frames, false, data);
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let trailers = ready!(Pin::new(&mut self.body).poll_trailers(cx));
+        let status = trailers
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .and_then(Status::from_header_map);
+        self.finish(status);
+        Poll::Ready(trailers)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
@@ -0,0 +1,391 @@
+//! The proto3 JSON mapping of messages, implemented via reflection.
+//!
+//! Field names are printed in their JSON (lowerCamelCase) form, 64 bit
+//! integers as strings, bytes as standard base64 and enums by value name, see
+//! <https://protobuf.dev/programming-guides/proto3/#json>. Parsing accepts
+//! both the JSON and the original field names, and numbers either quoted or
+//! not. Well-known types are mapped like any other message, i.e. a
+//! `google.protobuf.Timestamp` is printed as `{"seconds": "1", "nanos": 2}`
+//! rather than as an RFC 3339 string.
+
+use std::fmt::{self, Write};
+
+use base64::Engine;
+use protobuf::{
+    reflect::{
+        FieldDescriptor, MessageDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef,
+        RuntimeFieldType, RuntimeType,
+    },
+    MessageDyn,
+};
+use serde_json::Value;
+
+/// An error when parsing a message from JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    path: String,
+    message: String,
+}
+
+impl ParseError {
+    fn new(path: &str, message: impl Into<String>) -> ParseError {
+        ParseError {
+            path: path.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    /// The path of the invalid value, e.g. `items[1].name`, empty if the
+    /// input is not a JSON object.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Prints `message` as compact JSON.
+pub fn print_to_string(message: &dyn MessageDyn) -> String {
+    let mut out = String::new();
+    write_message(&mut out, message, None);
+    out
+}
+
+/// Prints `message` as JSON indented by two spaces.
+pub fn print_to_string_pretty(message: &dyn MessageDyn) -> String {
+    let mut out = String::new();
+    write_message(&mut out, message, Some(0));
+    out
+}
+
+fn newline(out: &mut String, indent: Option<usize>) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        out.push_str(&"  ".repeat(indent));
+    }
+}
+
+fn write_message(out: &mut String, message: &dyn MessageDyn, indent: Option<usize>) {
+    let inner = indent.map(|i| i + 1);
+    let separator = if indent.is_some() { ": " } else { ":" };
+    let mut first = true;
+    out.push('{');
+    for field in message.descriptor_dyn().fields() {
+        let value = field.get_reflect(message);
+        match &value {
+            ReflectFieldRef::Optional(v) if v.value().is_none() => continue,
+            ReflectFieldRef::Repeated(v) if v.is_empty() => continue,
+            ReflectFieldRef::Map(v) if v.is_empty() => continue,
+            _ => {}
+        }
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        newline(out, inner);
+        write_string(out, field.json_name());
+        out.push_str(separator);
+        write_field(out, value, inner);
+    }
+    if !first {
+        newline(out, indent);
+    }
+    out.push('}');
+}
+
+/// Writes the value of a set field, at the indentation of its name.
+fn write_field(out: &mut String, value: ReflectFieldRef<'_>, indent: Option<usize>) {
+    let separator = if indent.is_some() { ": " } else { ":" };
+    match value {
+        ReflectFieldRef::Optional(v) => write_value(out, v.value().unwrap(), indent),
+        ReflectFieldRef::Repeated(values) => {
+            out.push('[');
+            for (i, v) in values.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, indent.map(|i| i + 1));
+                write_value(out, v, indent.map(|i| i + 1));
+            }
+            newline(out, indent);
+            out.push(']');
+        }
+        ReflectFieldRef::Map(map) => {
+            // Maps are unordered, sort entries for deterministic output.
+            let mut entries: Vec<_> = (&map).into_iter().map(|(k, v)| (map_key(&k), v)).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, indent.map(|i| i + 1));
+                write_string(out, &k);
+                out.push_str(separator);
+                write_value(out, v, indent.map(|i| i + 1));
+            }
+            newline(out, indent);
+            out.push('}');
+        }
+    }
+}
+
+/// Prints the value of `field` of `message` as compact JSON, e.g. `"abc"` or
+/// `[1,2]`, or `null` if it is not set.
+pub(crate) fn print_field(message: &dyn MessageDyn, field: &FieldDescriptor) -> String {
+    let value = field.get_reflect(message);
+    match &value {
+        ReflectFieldRef::Optional(v) if v.value().is_none() => return "null".to_owned(),
+        ReflectFieldRef::Repeated(v) if v.is_empty() => return "[]".to_owned(),
+        ReflectFieldRef::Map(v) if v.is_empty() => return "{}".to_owned(),
+        _ => {}
+    }
+    let mut out = String::new();
+    write_field(&mut out, value, None);
+    out
+}
+
+/// Map keys are always JSON strings.
+fn map_key(key: &ReflectValueRef<'_>) -> String {
+    match key {
+        ReflectValueRef::String(s) => (*s).to_owned(),
+        key => key.to_string(),
+    }
+}
+
+fn write_value(out: &mut String, value: ReflectValueRef<'_>, indent: Option<usize>) {
+    match value {
+        ReflectValueRef::U32(v) => write!(out, "{}", v).unwrap(),
+        ReflectValueRef::I32(v) => write!(out, "{}", v).unwrap(),
+        ReflectValueRef::U64(v) => write!(out, "\"{}\"", v).unwrap(),
+        ReflectValueRef::I64(v) => write!(out, "\"{}\"", v).unwrap(),
+        ReflectValueRef::F32(v) => write_float(out, v as f64),
+        ReflectValueRef::F64(v) => write_float(out, v),
+        ReflectValueRef::Bool(v) => write!(out, "{}", v).unwrap(),
+        ReflectValueRef::String(v) => write_string(out, v),
+        ReflectValueRef::Bytes(v) => {
+            write_string(out, &base64::engine::general_purpose::STANDARD.encode(v))
+        }
+        ReflectValueRef::Enum(descriptor, number) => match descriptor.value_by_number(number) {
+            Some(value) => write_string(out, value.name()),
+            None => write!(out, "{}", number).unwrap(),
+        },
+        ReflectValueRef::Message(message) => write_message(out, &*message, indent),
+    }
+}
+
+fn write_float(out: &mut String, v: f64) {
+    if v.is_nan() {
+        out.push_str("\"NaN\"");
+    } else if v.is_infinite() && v > 0.0 {
+        out.push_str("\"Infinity\"");
+    } else if v.is_infinite() {
+        out.push_str("\"-Infinity\"");
+    } else {
+        write!(out, "{}", v).unwrap();
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Parses a message of type `descriptor` from JSON.
+pub fn parse_from_str(
+    descriptor: &MessageDescriptor,
+    json: &str,
+) -> Result<Box<dyn MessageDyn>, ParseError> {
+    let mut message = descriptor.new_instance();
+    merge_from_str(&mut *message, json)?;
+    Ok(message)
+}
+
+/// Merges the fields of JSON object `json` into `message`.
+pub fn merge_from_str(message: &mut dyn MessageDyn, json: &str) -> Result<(), ParseError> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| ParseError::new("", e.to_string()))?;
+    merge_from_value(message, &value)
+}
+
+/// Merges the fields of JSON object `value` into `message`.
+pub fn merge_from_value(message: &mut dyn MessageDyn, value: &Value) -> Result<(), ParseError> {
+    merge_message(message, value, "")
+}
+
+fn merge_message(
+    message: &mut dyn MessageDyn,
+    value: &Value,
+    path: &str,
+) -> Result<(), ParseError> {
+    let Value::Object(object) = value else {
+        return Err(ParseError::new(path, "expected an object"));
+    };
+    let descriptor = message.descriptor_dyn();
+    for (name, value) in object {
+        let field = descriptor
+            .fields()
+            .find(|f| f.json_name() == name || f.name() == name)
+            .ok_or_else(|| {
+                ParseError::new(
+                    path,
+                    format!("unknown field {} of {}", name, descriptor.full_name()),
+                )
+            })?;
+        let path = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", path, name)
+        };
+        merge_field(message, &field, value, &path)?;
+    }
+    Ok(())
+}
+
+fn merge_field(
+    message: &mut dyn MessageDyn,
+    field: &FieldDescriptor,
+    value: &Value,
+    path: &str,
+) -> Result<(), ParseError> {
+    if value.is_null() {
+        field.clear_field(message);
+        return Ok(());
+    }
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeType::Message(_)) => {
+            merge_message(field.mut_message(message), value, path)
+        }
+        RuntimeFieldType::Singular(ty) => {
+            field.set_singular_field(message, parse_value(&ty, value, path)?);
+            Ok(())
+        }
+        RuntimeFieldType::Repeated(ty) => {
+            let Value::Array(values) = value else {
+                return Err(ParseError::new(path, "expected an array"));
+            };
+            let mut repeated = field.mut_repeated(message);
+            for (i, value) in values.iter().enumerate() {
+                repeated.push(parse_value(&ty, value, &format!("{}[{}]", path, i))?);
+            }
+            Ok(())
+        }
+        RuntimeFieldType::Map(key_type, value_type) => {
+            let Value::Object(entries) = value else {
+                return Err(ParseError::new(path, "expected an object"));
+            };
+            let mut map = field.mut_map(message);
+            for (key, value) in entries {
+                let path = format!("{}[{}]", path, key);
+                let key = parse_value(&key_type, &Value::String(key.clone()), &path)?;
+                map.insert(key, parse_value(&value_type, value, &path)?);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn parse_value(ty: &RuntimeType, value: &Value, path: &str) -> Result<ReflectValueBox, ParseError> {
+    let invalid = || ParseError::new(path, format!("invalid {} value {}", ty, value));
+    let value = match ty {
+        RuntimeType::I32 => ReflectValueBox::I32(parse_int(value).ok_or_else(invalid)?),
+        RuntimeType::I64 => ReflectValueBox::I64(parse_int(value).ok_or_else(invalid)?),
+        RuntimeType::U32 => ReflectValueBox::U32(parse_int(value).ok_or_else(invalid)?),
+        RuntimeType::U64 => ReflectValueBox::U64(parse_int(value).ok_or_else(invalid)?),
+        RuntimeType::F32 => ReflectValueBox::F32(parse_float(value).ok_or_else(invalid)? as f32),
+        RuntimeType::F64 => ReflectValueBox::F64(parse_float(value).ok_or_else(invalid)?),
+        RuntimeType::Bool => match value {
+            Value::Bool(v) => ReflectValueBox::Bool(*v),
+            // Map keys are always strings.
+            Value::String(v) if v == "true" => ReflectValueBox::Bool(true),
+            Value::String(v) if v == "false" => ReflectValueBox::Bool(false),
+            _ => return Err(invalid()),
+        },
+        RuntimeType::String => match value {
+            Value::String(v) => ReflectValueBox::String(v.clone()),
+            _ => return Err(invalid()),
+        },
+        RuntimeType::VecU8 => {
+            let Value::String(v) = value else {
+                return Err(invalid());
+            };
+            // Accept both the standard and the URL-safe alphabets, with or
+            // without padding.
+            let v = v.trim_end_matches('=').replace('-', "+").replace('_', "/");
+            let bytes = base64::engine::general_purpose::STANDARD_NO_PAD
+                .decode(v)
+                .map_err(|_| invalid())?;
+            ReflectValueBox::Bytes(bytes)
+        }
+        RuntimeType::Enum(descriptor) => {
+            let number = match value {
+                Value::String(name) => descriptor.value_by_name(name).map(|v| v.value()),
+                value => parse_int(value),
+            };
+            ReflectValueBox::Enum(descriptor.clone(), number.ok_or_else(invalid)?)
+        }
+        RuntimeType::Message(descriptor) => {
+            let mut message = descriptor.new_instance();
+            merge_message(&mut *message, value, path)?;
+            ReflectValueBox::Message(message)
+        }
+    };
+    Ok(value)
+}
+
+/// Parses an integer from a JSON number or string, e.g. `1`, `"1"` or `1.0`.
+fn parse_int<T: TryFrom<i64> + TryFrom<u64> + std::str::FromStr>(value: &Value) -> Option<T> {
+    match value {
+        Value::Number(n) => {
+            if let Some(v) = n.as_u64() {
+                T::try_from(v).ok()
+            } else if let Some(v) = n.as_i64() {
+                T::try_from(v).ok()
+            } else {
+                let v = n.as_f64()?;
+                if v.fract() != 0.0 {
+                    return None;
+                }
+                T::try_from(v as i64).ok()
+            }
+        }
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parses a float from a JSON number or string, including `"NaN"`,
+/// `"Infinity"` and `"-Infinity"`.
+fn parse_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            s => s.parse().ok(),
+        },
+        _ => None,
+    }
+}
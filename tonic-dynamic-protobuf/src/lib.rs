@@ -0,0 +1,39 @@
+//! Dynamic gRPC for tonic: call and serve methods described by rust-protobuf
+//! descriptors that are only known at runtime, e.g. loaded from `.proto`
+//! files or fetched via server reflection, and proxy calls without decoding
+//! them at all, transcode JSON requests for plain HTTP clients, serve
+//! `application/grpc+json` from generated services, audit, rate limit,
+//! sample, inspect and record the calls of a server, record which request
+//! fields are in use, or cache the responses of idempotent methods in
+//! clients.
+
+pub mod audit;
+pub mod cache;
+pub mod client;
+pub mod codec;
+pub mod inspect;
+pub mod json;
+pub mod limit;
+mod pool;
+pub mod proxy;
+pub mod record;
+pub mod sample;
+pub mod server;
+pub mod transcode;
+pub mod usage;
+
+pub use audit::Auditor;
+pub use cache::ResponseCache;
+pub use client::{DynamicClient, DynamicMessage, DynamicStub, MethodHandle};
+pub use codec::{JsonCodec, ProtobufOrJsonCodec};
+pub use inspect::Inspector;
+pub use limit::CostLimiter;
+pub use pool::{DescriptorPool, DynamicMethod};
+pub use proxy::GrpcProxy;
+pub use record::Recorder;
+pub use sample::{SampleWriter, Sampler};
+pub use server::DynamicServer;
+/// The gRPC message framing, which moved to `tonic-codec-protobuf`.
+pub use tonic_codec_protobuf::frame;
+pub use transcode::JsonTranscoder;
+pub use usage::FieldUsage;
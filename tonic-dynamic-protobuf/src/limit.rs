@@ -0,0 +1,372 @@
+//! Rate limiting of calls by the cost of their request messages rather than
+//! their number, so that a call carrying a large batch uses up more of the
+//! budget than one carrying a single item.
+//!
+//! A [`CostLimiter`] is a token bucket of cost units, refilled at a fixed
+//! rate up to a burst. Messages cost their encoded size by default, taken
+//! from the gRPC frame headers without decoding them, or the result of a
+//! cost function over the decoded message.
+//!
+//! Calls are admitted once their first request message arrived: if the
+//! bucket holds its cost, the cost is taken and the call is passed on,
+//! otherwise the call fails with `RESOURCE_EXHAUSTED` and a
+//! `google.rpc.RetryInfo` of when the cost will be available. Further
+//! messages of streaming calls are not rejected, but held back until the
+//! bucket refilled enough.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Buf;
+use protobuf::MessageDyn;
+use tokio::time::Sleep;
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Code, Status,
+};
+use tonic_types_protobuf::{ErrorDetails, StatusExt};
+use tower_layer::Layer;
+
+use crate::{audit::RequestStream, frame::HEADER_LEN, DescriptorPool, DynamicMethod};
+
+type CostFn = dyn Fn(&DynamicMethod, &dyn MessageDyn) -> u64 + Send + Sync;
+
+/// Limits the calls of the wrapped service by the cost of their request
+/// messages.
+#[derive(Clone)]
+pub struct CostLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    cost: Option<Arc<CostFn>>,
+}
+
+impl CostLimiter {
+    /// Create a limiter that admits `rate` cost units per second, and up to
+    /// `burst` at once. The bucket starts full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` or `burst` is zero.
+    pub fn new(rate: u64, burst: u64) -> Self {
+        assert!(rate > 0 && burst > 0, "rate and burst must be positive");
+        CostLimiter {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst as f64,
+                rate: rate as f64,
+                burst: burst as f64,
+                refilled: Instant::now(),
+            })),
+            methods: Arc::default(),
+            cost: None,
+        }
+    }
+
+    /// Charge the messages of the methods of `pool` with `cost`, instead of
+    /// their encoded size. Messages of other methods, compressed messages and
+    /// messages that fail to decode are still charged their size.
+    pub fn cost_fn<F>(mut self, pool: &DescriptorPool, cost: F) -> Self
+    where
+        F: Fn(&DynamicMethod, &dyn MessageDyn) -> u64 + Send + Sync + 'static,
+    {
+        let methods = pool.methods().into_iter().map(|m| (m.path(), m)).collect();
+        self.methods = Arc::new(methods);
+        self.cost = Some(Arc::new(cost));
+        self
+    }
+
+    /// Returns a layer that limits the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(limiter.into_layer())`. Layers of clones of
+    /// the limiter share its budget.
+    pub fn into_layer(self) -> CostLimitLayer {
+        CostLimitLayer { limiter: self }
+    }
+
+    /// Returns the cost units currently available, which are negative while
+    /// held back streaming messages are paid off.
+    pub fn available(&self) -> i64 {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.refill(Instant::now());
+        bucket.tokens.floor() as i64
+    }
+
+    /// Returns the cost of the message `data` of the call of `path`.
+    fn cost(&self, path: &str, compressed: bool, data: &[u8]) -> u64 {
+        let custom = self.cost.as_ref().zip(self.methods.get(path));
+        if let (Some((cost, method)), false) = (custom, compressed) {
+            if let Ok(message) = method.input().parse_from_bytes(data) {
+                return cost(method, &*message);
+            }
+        }
+        data.len() as u64
+    }
+}
+
+impl fmt::Debug for CostLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CostLimiter")
+            .field("bucket", &self.bucket)
+            .field("cost_fn", &self.cost.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A token bucket of cost units.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    rate: f64,
+    burst: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled = now;
+    }
+
+    /// Takes `cost` once at least `needed` tokens are available, or returns
+    /// how long it takes until they are.
+    fn take(&mut self, cost: u64, needed: u64) -> Result<(), Duration> {
+        self.refill(Instant::now());
+        let needed = needed as f64;
+        if self.tokens >= needed {
+            self.tokens -= cost as f64;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((needed - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// A layer that mounts a [`CostLimiter`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct CostLimitLayer {
+    limiter: CostLimiter,
+}
+
+impl<S> Layer<S> for CostLimitLayer {
+    type Service = CostLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CostLimitService {
+            limiter: self.limiter.clone(),
+            inner,
+        }
+    }
+}
+
+/// Admits the gRPC calls of the inner service within the budget of a
+/// [`CostLimiter`], and forwards other requests unchanged.
+#[derive(Debug, Clone)]
+pub struct CostLimitService<S> {
+    limiter: CostLimiter,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for CostLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        if !grpc {
+            return Box::pin(self.inner.call(req));
+        }
+        // The ready service handles this call, its clone the next one.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let mut request = LimitedRequest {
+                limiter,
+                path: parts.uri.path().to_owned(),
+                body: Box::pin(body),
+                frame: vec![],
+                unsplit: Bytes::new(),
+                held: None,
+                owed: 0,
+                sleep: None,
+            };
+            // Admit the call by its first message.
+            let first = match request.first_message().await {
+                Ok(first) => first,
+                Err(status) => return Ok(status.to_http()),
+            };
+            let (cost, data) = first;
+            if cost > 0 {
+                let (taken, burst) = {
+                    let mut bucket = request.limiter.bucket.lock().unwrap();
+                    (bucket.take(cost, cost), bucket.burst as u64)
+                };
+                if let Err(wait) = taken {
+                    let status = if cost > burst {
+                        Status::resource_exhausted(format!(
+                            "request costs {} units, more than the limit of {}",
+                            cost, burst
+                        ))
+                    } else {
+                        Status::with_error_details(
+                            Code::ResourceExhausted,
+                            format!("request costs {} units, over the rate limit", cost),
+                            ErrorDetails::with_retry_info(Some(wait)),
+                        )
+                    };
+                    return Ok(status.to_http());
+                }
+            }
+            request.held = Some(data).filter(|data| !data.is_empty());
+            let body: RequestStream = Box::new(request);
+            inner
+                .call(http::Request::from_parts(parts, B::from(body)))
+                .await
+        })
+    }
+}
+
+/// A request body whose messages are charged to a [`CostLimiter`].
+struct LimitedRequest<B> {
+    limiter: CostLimiter,
+    path: String,
+    body: Pin<Box<B>>,
+    /// The incomplete frame at the end of the data split so far.
+    frame: Vec<u8>,
+    /// Data read from the body, but not split into frames yet.
+    unsplit: Bytes,
+    /// Data to pass on once `owed` was taken from the bucket.
+    held: Option<Bytes>,
+    owed: u64,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<B> LimitedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    /// Reads the request until its first message is complete, and returns
+    /// its cost and the data read, with a cost of 0 if the request ended
+    /// before.
+    async fn first_message(&mut self) -> Result<(u64, Bytes), Status> {
+        let mut read = vec![];
+        while let Some(data) = std::future::poll_fn(|cx| self.body.as_mut().poll_data(cx)).await {
+            let mut data = data.map_err(|e| {
+                let e: StdError = e.into();
+                Status::internal(format!("failed to read request: {}", e))
+            })?;
+            self.unsplit = data.copy_to_bytes(data.remaining());
+            let (cost, data) = self.split();
+            read.extend_from_slice(&data);
+            if cost > 0 {
+                return Ok((cost, read.into()));
+            }
+        }
+        Ok((0, read.into()))
+    }
+
+    /// Splits the unsplit data up to the end of the next message, and returns
+    /// its cost, or 0 if all data was split without completing a message.
+    fn split(&mut self) -> (u64, Bytes) {
+        let mut split = 0;
+        while split < self.unsplit.len() {
+            let needed = match frame_len(&self.frame) {
+                Some(len) => HEADER_LEN + len - self.frame.len(),
+                None => HEADER_LEN - self.frame.len(),
+            };
+            let n = needed.min(self.unsplit.len() - split);
+            self.frame
+                .extend_from_slice(&self.unsplit[split..split + n]);
+            split += n;
+            if frame_len(&self.frame).is_some_and(|len| self.frame.len() == HEADER_LEN + len) {
+                let compressed = self.frame[0] != 0;
+                // Empty messages cost at least one unit, so that every
+                // message is charged.
+                let cost = self
+                    .limiter
+                    .cost(&self.path, compressed, &self.frame[HEADER_LEN..])
+                    .max(1);
+                self.frame.clear();
+                return (cost, self.unsplit.split_to(split));
+            }
+        }
+        (0, self.unsplit.split_to(split))
+    }
+}
+
+/// The message length of a frame, once its header is complete.
+fn frame_len(frame: &[u8]) -> Option<usize> {
+    let header = frame.get(..HEADER_LEN)?;
+    Some(u32::from_be_bytes(header[1..].try_into().unwrap()) as usize)
+}
+
+impl<B> Stream for LimitedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(sleep) = &mut self.sleep {
+                ready!(sleep.as_mut().poll(cx));
+                self.sleep = None;
+            }
+            if let Some(data) = self.held.take() {
+                if self.owed > 0 {
+                    let owed = self.owed;
+                    let taken = {
+                        let mut bucket = self.limiter.bucket.lock().unwrap();
+                        // Messages over the burst wait for a full bucket, and
+                        // leave it in debt.
+                        let needed = owed.min(bucket.burst as u64);
+                        bucket.take(owed, needed)
+                    };
+                    if let Err(wait) = taken {
+                        self.held = Some(data);
+                        self.sleep = Some(Box::pin(tokio::time::sleep(wait)));
+                        continue;
+                    }
+                    self.owed = 0;
+                }
+                return Poll::Ready(Some(Ok(data)));
+            }
+            if self.unsplit.is_empty() {
+                match ready!(self.body.as_mut().poll_data(cx)) {
+                    Some(Ok(mut data)) => self.unsplit = data.copy_to_bytes(data.remaining()),
+                    Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                    None => return Poll::Ready(None),
+                }
+            }
+            let (owed, data) = self.split();
+            self.owed = owed;
+            self.held = Some(data);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use protobuf::{
+    descriptor::FileDescriptorSet,
+    reflect::{FileDescriptor, MessageDescriptor},
+    Message,
+};
+use tonic_reflection_protobuf::{ClientError, ReflectionClient};
+
+/// A set of file descriptors to look up messages and methods by name.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorPool {
+    files: Vec<FileDescriptor>,
+}
+
+impl DescriptorPool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `file` and its dependencies, e.g. the `file_descriptor()` of a
+    /// module generated by rust-protobuf.
+    pub fn add_file(&mut self, file: &FileDescriptor) -> &mut Self {
+        if self.file(file.name()).is_none() {
+            for dep in file.deps() {
+                self.add_file(dep);
+            }
+            self.files.push(file.clone());
+        }
+        self
+    }
+
+    /// Add the files of `fds`, e.g. the output of `protoc
+    /// --descriptor_set_out`. Files already in the pool are skipped, other
+    /// files may depend on them.
+    pub fn add_file_descriptor_set(
+        &mut self,
+        fds: FileDescriptorSet,
+    ) -> protobuf::Result<&mut Self> {
+        let protos: Vec<_> = fds
+            .file
+            .into_iter()
+            .filter(|f| self.file(f.name()).is_none())
+            .collect();
+        let files = FileDescriptor::new_dynamic_fds(protos, &self.files)?;
+        self.files.extend(files);
+        Ok(self)
+    }
+
+    /// Add the files of an encoded `FileDescriptorSet`, e.g. received by a
+    /// plugin at runtime.
+    pub fn add_encoded_file_descriptor_set(&mut self, bytes: &[u8]) -> protobuf::Result<&mut Self> {
+        self.add_file_descriptor_set(FileDescriptorSet::parse_from_bytes(bytes)?)
+    }
+
+    /// Add the files of all services advertised by the server behind
+    /// `client`.
+    pub async fn add_from_reflection(
+        &mut self,
+        client: &mut ReflectionClient,
+    ) -> Result<&mut Self, ClientError> {
+        let mut files = HashMap::new();
+        let mut order = vec![];
+        for service in client.list_services().await? {
+            for file in client.file_descriptor_set(&service).await?.file {
+                if !files.contains_key(file.name()) {
+                    order.push(file.name().to_owned());
+                    files.insert(file.name().to_owned(), file);
+                }
+            }
+        }
+        let mut fds = FileDescriptorSet::new();
+        fds.file = order.iter().map(|n| files.remove(n).unwrap()).collect();
+        self.add_file_descriptor_set(fds)?;
+        Ok(self)
+    }
+
+    /// Returns all files of the pool.
+    pub fn files(&self) -> &[FileDescriptor] {
+        &self.files
+    }
+
+    /// Returns the file named `name`.
+    pub fn file(&self, name: &str) -> Option<&FileDescriptor> {
+        self.files.iter().find(|f| f.name() == name)
+    }
+
+    /// Returns the message with the fully-qualified `name`, with or without
+    /// a leading dot.
+    pub fn message(&self, name: &str) -> Option<MessageDescriptor> {
+        let name = format!(".{}", name.trim_start_matches('.'));
+        self.files
+            .iter()
+            .find_map(|f| f.message_by_full_name(&name))
+    }
+
+    /// Returns the fully-qualified names of all services.
+    pub fn services(&self) -> Vec<String> {
+        self.methods().into_iter().fold(vec![], |mut services, m| {
+            if services.last() != Some(&m.service) {
+                services.push(m.service);
+            }
+            services
+        })
+    }
+
+    /// Returns the methods of all services.
+    pub fn methods(&self) -> Vec<DynamicMethod> {
+        let mut methods = vec![];
+        for file in &self.files {
+            for service in file.services() {
+                let service_name = if file.package().is_empty() {
+                    service.proto().name().to_owned()
+                } else {
+                    format!("{}.{}", file.package(), service.proto().name())
+                };
+                for method in service.methods() {
+                    methods.push(DynamicMethod {
+                        service: service_name.clone(),
+                        name: method.proto().name().to_owned(),
+                        input: method.input_type(),
+                        output: method.output_type(),
+                        client_streaming: method.proto().client_streaming(),
+                        server_streaming: method.proto().server_streaming(),
+                    });
+                }
+            }
+        }
+        methods
+    }
+
+    /// Returns the methods of the service with the fully-qualified `name`, in
+    /// declaration order.
+    pub fn service(&self, name: &str) -> Vec<DynamicMethod> {
+        let name = name.trim_start_matches('.');
+        self.methods()
+            .into_iter()
+            .filter(|m| m.service == name)
+            .collect()
+    }
+
+    /// Returns the method named `name`, which is either a path like
+    /// `/pkg.Service/Method` (the leading slash is optional) or a
+    /// fully-qualified name like `pkg.Service.Method`.
+    pub fn method(&self, name: &str) -> Option<DynamicMethod> {
+        let name = name.trim_start_matches('/');
+        let (service, method) = name.rsplit_once('/').or_else(|| name.rsplit_once('.'))?;
+        self.methods()
+            .into_iter()
+            .find(|m| m.service == service && m.name == method)
+    }
+}
+
+/// A method of a service described at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMethod {
+    service: String,
+    name: String,
+    input: MessageDescriptor,
+    output: MessageDescriptor,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+impl DynamicMethod {
+    /// The fully-qualified name of the service, e.g. `pkg.Service`.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// The name of the method, e.g. `Method`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The HTTP/2 path of the method, e.g. `/pkg.Service/Method`.
+    pub fn path(&self) -> String {
+        format!("/{}/{}", self.service, self.name)
+    }
+
+    /// The request message type.
+    pub fn input(&self) -> &MessageDescriptor {
+        &self.input
+    }
+
+    /// The response message type.
+    pub fn output(&self) -> &MessageDescriptor {
+        &self.output
+    }
+
+    /// Whether the client sends a stream of requests.
+    pub fn client_streaming(&self) -> bool {
+        self.client_streaming
+    }
+
+    /// Whether the server replies with a stream of responses.
+    pub fn server_streaming(&self) -> bool {
+        self.server_streaming
+    }
+}
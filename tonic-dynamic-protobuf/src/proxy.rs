@@ -0,0 +1,288 @@
+//! A gRPC proxy that forwards calls to an upstream channel without decoding
+//! their messages.
+
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    future::Future,
+    pin::{pin, Pin},
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use bytes::BufMut;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{
+    body::BoxBody,
+    client::Grpc,
+    codec::{Codec, EncodeBuf, Encoder},
+    codegen::{
+        http, http::uri::PathAndQuery, Body, BoxFuture, BoxStream, Bytes, Service, StdError,
+    },
+    transport::Channel,
+    Code, Extensions, Request, Response, Status, Streaming,
+};
+use tonic_codec_protobuf::{RawCodec, RawDecoder};
+use tower_layer::Layer;
+
+/// Forwards any gRPC call, including methods unknown to this process, to an
+/// upstream channel.
+///
+/// Every call is proxied as a bidirectional stream of raw messages, which
+/// preserves the semantics of all four streaming shapes. Request and
+/// response metadata, trailing metadata and statuses are forwarded. A broken
+/// inbound stream resets the upstream call, so the upstream never takes it for
+/// a complete one, and its error is returned to the client.
+#[derive(Debug, Clone)]
+pub struct GrpcProxy {
+    upstream: Grpc<Channel>,
+    local: Arc<HashSet<String>>,
+}
+
+impl GrpcProxy {
+    /// Create a proxy that forwards calls to `upstream`.
+    pub fn new(upstream: Channel) -> Self {
+        GrpcProxy {
+            upstream: Grpc::new(upstream),
+            local: Arc::default(),
+        }
+    }
+
+    /// Serve the fully-qualified `service` locally instead of forwarding it,
+    /// when the proxy is mounted with [`GrpcProxy::into_layer`].
+    pub fn local_service(mut self, service: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.local).insert(service.into());
+        self
+    }
+
+    /// Returns a layer that forwards calls to the upstream, except those of
+    /// local services which reach the wrapped service, e.g.
+    /// `Server::builder().layer(proxy.into_layer()).add_service(health)`.
+    pub fn into_layer(self) -> ProxyLayer {
+        ProxyLayer { proxy: self }
+    }
+
+    fn is_local(&self, path: &str) -> bool {
+        let service = path.trim_start_matches('/').split('/').next();
+        service.is_some_and(|s| self.local.contains(s))
+    }
+
+    fn forward<B>(&self, req: http::Request<B>) -> BoxFuture<http::Response<BoxBody>, Infallible>
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        let svc = ForwardSvc {
+            upstream: self.upstream.clone(),
+            path: req
+                .uri()
+                .path_and_query()
+                .cloned()
+                .unwrap_or_else(|| PathAndQuery::from_static("/")),
+        };
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(RawCodec::default());
+            Ok(grpc.streaming(svc, req).await)
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for GrpcProxy
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        self.forward(req)
+    }
+}
+
+/// Forwards a single call to the upstream.
+struct ForwardSvc {
+    upstream: Grpc<Channel>,
+    path: PathAndQuery,
+}
+
+impl Service<Request<Streaming<Bytes>>> for ForwardSvc {
+    type Response = Response<BoxStream<Bytes>>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Status>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Streaming<Bytes>>) -> Self::Future {
+        let mut upstream = self.upstream.clone();
+        let path = self.path.clone();
+        Box::pin(async move {
+            upstream
+                .ready()
+                .await
+                .map_err(|e| Status::unavailable(format!("upstream is not ready: {}", e)))?;
+            let (metadata, _, messages) = request.into_parts();
+            let inbound_error = Arc::new(Mutex::new(None));
+            let error = inbound_error.clone();
+            #[allow(clippy::result_large_err)]
+            let messages = messages.map(move |message| {
+                if let Err(status) = &message {
+                    *error.lock().unwrap() = Some(status.clone());
+                }
+                message
+            });
+            let request = Request::from_parts(metadata, Extensions::default(), messages);
+            let response = match upstream.streaming(request, path, ForwardCodec).await {
+                Ok(response) => response,
+                Err(status) => return Err(inbound_error.lock().unwrap().take().unwrap_or(status)),
+            };
+            let (metadata, messages, extensions) = response.into_parts();
+            let messages = Forwarded {
+                messages,
+                inbound_error,
+                status: None,
+                done: false,
+            };
+            Ok(Response::from_parts(
+                metadata,
+                Box::pin(messages) as BoxStream<Bytes>,
+                extensions,
+            ))
+        })
+    }
+}
+
+/// A codec that sends inbound messages upstream, and fails on an inbound
+/// error so that the upstream call is reset instead of ended.
+struct ForwardCodec;
+
+struct ForwardEncoder;
+
+impl Codec for ForwardCodec {
+    type Encode = Result<Bytes, Status>;
+    type Decode = Bytes;
+
+    type Encoder = ForwardEncoder;
+    type Decoder = RawDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ForwardEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawDecoder::default()
+    }
+}
+
+impl Encoder for ForwardEncoder {
+    type Item = Result<Bytes, Status>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        buf.put(item?);
+        Ok(())
+    }
+}
+
+/// The upstream messages of a call, which end with the error of the inbound
+/// stream if it broke, or with the upstream trailers.
+struct Forwarded {
+    messages: Streaming<Bytes>,
+    inbound_error: Arc<Mutex<Option<Status>>>,
+    status: Option<Status>,
+    done: bool,
+}
+
+impl Stream for Forwarded {
+    type Item = Result<Bytes, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(self.status.take().map(Err));
+        }
+        let status = match ready!(Pin::new(&mut self.messages).poll_next(cx)) {
+            Some(Ok(message)) => return Poll::Ready(Some(Ok(message))),
+            // The upstream call is reset when the inbound stream breaks.
+            Some(Err(status)) => Some(self.inbound_error.lock().unwrap().take().unwrap_or(status)),
+            None => {
+                // Trailers are received along with the end of the stream, so
+                // they are ready. tonic servers only send trailers with a
+                // status, so they are sent with an OK one.
+                match pin!(self.messages.trailers()).poll(cx) {
+                    Poll::Ready(Ok(Some(trailers))) => {
+                        Some(Status::with_metadata(Code::Ok, "", trailers))
+                    }
+                    _ => None,
+                }
+            }
+        };
+        // tonic drops the messages it has buffered when a stream yields an
+        // error, so the status waits for them to be sent.
+        self.status = status;
+        self.done = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// A layer that mounts a [`GrpcProxy`] in front of the local services.
+#[derive(Debug, Clone)]
+pub struct ProxyLayer {
+    proxy: GrpcProxy,
+}
+
+impl<S> Layer<S> for ProxyLayer {
+    type Service = ProxyRouter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProxyRouter {
+            proxy: self.proxy.clone(),
+            inner,
+        }
+    }
+}
+
+/// Forwards calls to a [`GrpcProxy`], except those of local services which
+/// reach the inner service.
+#[derive(Debug, Clone)]
+pub struct ProxyRouter<S> {
+    proxy: GrpcProxy,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for ProxyRouter<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if self.proxy.is_local(req.uri().path()) {
+            Box::pin(self.inner.call(req))
+        } else {
+            let fut = self.proxy.forward(req);
+            Box::pin(async move {
+                match fut.await {
+                    Ok(res) => Ok(res),
+                    Err(never) => match never {},
+                }
+            })
+        }
+    }
+}
@@ -0,0 +1,523 @@
+//! Recording of all gRPC traffic passing a server or proxy, for debugging
+//! clients that can not be instrumented.
+//!
+//! A [`Recorder`] mounted as a layer, e.g. in front of a
+//! [`GrpcProxy`](crate::GrpcProxy) like in the `grpc-proxy` binary of
+//! `tonic-tools-protobuf`, copies the frames of every call byte for byte to
+//! files of a directory, without decoding them:
+//!
+//! * `{call}-{service}.{method}.requests` and `.responses` capture the
+//!   length-prefixed frames of a call, to be printed by `grpc-decode` or
+//!   replayed with `Recording::from_captures` of `tonic-testing-protobuf`.
+//! * `index.jsonl` has a JSON object per event: the start of every call, every
+//!   frame with its size and the microseconds since the start of its call, and
+//!   the status the call ended with.
+//!
+//! ```text
+//! {"call":0,"capture":"000000-demo.Accounts.Login","event":"start","method":"/demo.Accounts/Login","unix_micros":1760601600000000}
+//! {"call":0,"compressed":false,"event":"request","frame":0,"len":16,"micros":212}
+//! {"call":0,"compressed":false,"event":"response","frame":0,"len":4,"micros":687}
+//! {"call":0,"code":0,"event":"status","message":"","micros":701}
+//! ```
+//!
+//! Metadata is not recorded, as it often carries credentials, and messages
+//! are not redacted. Messages longer than [`Recorder::max_message_size`] are
+//! recorded as malformed frames instead of being buffered, and the rest of
+//! their body is not recorded. Files are written on a background thread and keep every
+//! frame, so recording is meant for debugging sessions; [`Sampler`]s are
+//! meant for production traffic.
+//!
+//! [`Sampler`]: crate::Sampler
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    task::{ready, Context, Poll},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use bytes::Buf;
+use serde_json::json;
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Code, Status,
+};
+use tower_layer::Layer;
+
+use crate::{
+    audit::RequestStream,
+    frame::{self, FrameReader, DEFAULT_MAX_LEN},
+};
+
+/// Records the gRPC calls of the wrapped service to a directory.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    tx: mpsc::Sender<Record>,
+    calls: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+    max_message_size: usize,
+}
+
+impl Recorder {
+    /// Create a recorder that writes to the directory `dir`, creating it if
+    /// needed. Calls are numbered from 0, so `dir` should be empty; an
+    /// existing index is appended to.
+    pub fn create(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+        let index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("index.jsonl"))?;
+        let (tx, rx) = mpsc::channel();
+        let failed = Arc::new(AtomicU64::new(0));
+        let mut writer = RecordWriter {
+            dir,
+            index: BufWriter::new(index),
+            captures: HashMap::new(),
+            failed: failed.clone(),
+        };
+        thread::Builder::new()
+            .name("grpc-recorder".to_owned())
+            .spawn(move || {
+                while let Ok(record) = rx.recv() {
+                    // Write what is queued, then flush once.
+                    for record in std::iter::once(record).chain(rx.try_iter()) {
+                        writer.write(record);
+                    }
+                    writer.flush();
+                }
+            })?;
+        Ok(Recorder {
+            tx,
+            calls: Arc::default(),
+            failed,
+            max_message_size: DEFAULT_MAX_LEN,
+        })
+    }
+
+    /// Set the maximum size of the messages that are recorded.
+    ///
+    /// Default: `4MB`
+    pub fn max_message_size(mut self, limit: usize) -> Self {
+        self.max_message_size = limit;
+        self
+    }
+
+    /// The number of events that could not be written.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Returns a layer that records the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(recorder.into_layer())`.
+    pub fn into_layer(self) -> RecordLayer {
+        RecordLayer { recorder: self }
+    }
+}
+
+/// A layer that mounts a [`Recorder`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+    recorder: Recorder,
+}
+
+impl<S> Layer<S> for RecordLayer {
+    type Service = RecordService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordService {
+            recorder: self.recorder.clone(),
+            inner,
+        }
+    }
+}
+
+/// Records the gRPC calls of the inner service, and forwards other requests
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct RecordService<S> {
+    recorder: Recorder,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for RecordService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        if !grpc {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let call = Arc::new(Call {
+            tx: self.recorder.tx.clone(),
+            id: self.recorder.calls.fetch_add(1, Ordering::Relaxed),
+            started: Instant::now(),
+        });
+        call.send(Record::Start {
+            call: call.id,
+            path: req.uri().path().to_owned(),
+            time: SystemTime::now(),
+        });
+        let max_len = self.recorder.max_message_size;
+        let (parts, body) = req.into_parts();
+        let body: RequestStream = Box::new(RecordedRequest {
+            body: Box::pin(body),
+            call: call.clone(),
+            frames: FrameReader::with_max_len(max_len),
+        });
+        let req = http::Request::from_parts(parts, B::from(body));
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let response = fut.await?;
+            let (parts, body) = response.into_parts();
+            let mut body = RecordedResponse {
+                body,
+                call,
+                frames: FrameReader::with_max_len(max_len),
+                done: false,
+            };
+            // A trailers-only response carries the status in its headers.
+            if let Some(status) = Status::from_header_map(&parts.headers) {
+                body.finish(Some(status));
+            }
+            Ok(http::Response::from_parts(parts, body.boxed_unsync()))
+        })
+    }
+}
+
+/// What the writer thread of a [`Recorder`] records.
+#[derive(Debug)]
+enum Record {
+    Start {
+        call: u64,
+        path: String,
+        time: SystemTime,
+    },
+    Frame {
+        call: u64,
+        request: bool,
+        compressed: bool,
+        data: Vec<u8>,
+        elapsed: Duration,
+    },
+    Malformed {
+        call: u64,
+        request: bool,
+        error: String,
+    },
+    End {
+        call: u64,
+        code: Code,
+        message: String,
+        elapsed: Duration,
+    },
+}
+
+/// A recorded call.
+struct Call {
+    tx: mpsc::Sender<Record>,
+    id: u64,
+    started: Instant,
+}
+
+impl Call {
+    fn send(&self, record: Record) {
+        // The writer thread only stops if it panicked.
+        let _ = self.tx.send(record);
+    }
+
+    /// Records the frames completed by `data` of the request or response.
+    fn feed(&self, frames: &mut FrameReader, request: bool, data: &[u8]) {
+        // Report a malformed body once, and ignore the rest.
+        if frames.error().is_some() {
+            return;
+        }
+        let result = frames.feed(data, |frame| {
+            self.send(Record::Frame {
+                call: self.id,
+                request,
+                compressed: frame.compressed,
+                data: frame.data.to_vec(),
+                elapsed: self.started.elapsed(),
+            })
+        });
+        if let Err(e) = result {
+            self.send(Record::Malformed {
+                call: self.id,
+                request,
+                error: e.to_string(),
+            });
+        }
+    }
+}
+
+/// The capture files of a call being recorded.
+struct Capture {
+    requests: BufWriter<File>,
+    responses: BufWriter<File>,
+    frames: [u64; 2],
+}
+
+/// Writes the records of a [`Recorder`] on its thread.
+struct RecordWriter {
+    dir: PathBuf,
+    index: BufWriter<File>,
+    captures: HashMap<u64, Capture>,
+    failed: Arc<AtomicU64>,
+}
+
+impl RecordWriter {
+    fn write(&mut self, record: Record) {
+        if self.try_write(record).is_err() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn try_write(&mut self, record: Record) -> io::Result<()> {
+        let event = match record {
+            Record::Start { call, path, time } => {
+                let capture = capture_name(call, &path);
+                let create = |suffix| -> io::Result<_> {
+                    let path = self.dir.join(format!("{}.{}", capture, suffix));
+                    Ok(BufWriter::new(File::create(path)?))
+                };
+                let files = Capture {
+                    requests: create("requests")?,
+                    responses: create("responses")?,
+                    frames: [0; 2],
+                };
+                self.captures.insert(call, files);
+                let unix_micros = time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_micros() as u64);
+                json!({
+                    "call": call,
+                    "event": "start",
+                    "method": path,
+                    "capture": capture,
+                    "unix_micros": unix_micros,
+                })
+            }
+            Record::Frame {
+                call,
+                request,
+                compressed,
+                data,
+                elapsed,
+            } => {
+                let Some(capture) = self.captures.get_mut(&call) else {
+                    return Ok(());
+                };
+                let (writer, n) = if request {
+                    (&mut capture.requests, &mut capture.frames[0])
+                } else {
+                    (&mut capture.responses, &mut capture.frames[1])
+                };
+                frame::write_frame(writer, compressed, &data)?;
+                *n += 1;
+                json!({
+                    "call": call,
+                    "event": direction(request),
+                    "frame": *n - 1,
+                    "compressed": compressed,
+                    "len": data.len(),
+                    "micros": elapsed.as_micros() as u64,
+                })
+            }
+            Record::Malformed {
+                call,
+                request,
+                error,
+            } => json!({
+                "call": call,
+                "event": direction(request),
+                "error": error,
+            }),
+            Record::End {
+                call,
+                code,
+                message,
+                elapsed,
+            } => {
+                if let Some(mut capture) = self.captures.remove(&call) {
+                    capture.requests.flush()?;
+                    capture.responses.flush()?;
+                }
+                json!({
+                    "call": call,
+                    "event": "status",
+                    "code": code as i32,
+                    "message": message,
+                    "micros": elapsed.as_micros() as u64,
+                })
+            }
+        };
+        serde_json::to_writer(&mut self.index, &event)?;
+        self.index.write_all(b"\n")
+    }
+
+    /// Flushes the index and the captures of the calls in progress, so that
+    /// the files can be followed while calls are recorded.
+    fn flush(&mut self) {
+        let mut result = self.index.flush();
+        for capture in self.captures.values_mut() {
+            result = result
+                .and(capture.requests.flush())
+                .and(capture.responses.flush());
+        }
+        if result.is_err() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn direction(request: bool) -> &'static str {
+    if request {
+        "request"
+    } else {
+        "response"
+    }
+}
+
+/// Returns the name of the capture files of call `call` of `path`, e.g.
+/// `000007-pkg.Service.Method` for `/pkg.Service/Method`.
+fn capture_name(call: u64, path: &str) -> String {
+    let method: String = path
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| match c {
+            '/' => '.',
+            c if c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{:06}-{}", call, method)
+}
+
+/// A request body that records its frames.
+struct RecordedRequest<B> {
+    body: Pin<Box<B>>,
+    call: Arc<Call>,
+    frames: FrameReader,
+}
+
+impl<B> Stream for RecordedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(self.body.as_mut().poll_data(cx)) {
+            Some(Ok(mut data)) => {
+                let data = data.copy_to_bytes(data.remaining());
+                let this = &mut *self;
+                this.call.feed(&mut this.frames, true, &data);
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A response body that records its frames, and the status of its trailers.
+struct RecordedResponse {
+    body: BoxBody,
+    call: Arc<Call>,
+    frames: FrameReader,
+    done: bool,
+}
+
+impl RecordedResponse {
+    fn finish(&mut self, status: Option<Status>) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let (code, message) = match status {
+            Some(status) => (status.code(), status.message().to_owned()),
+            None => (Code::Cancelled, "call ended without a status".to_owned()),
+        };
+        self.call.send(Record::End {
+            call: self.call.id,
+            code,
+            message,
+            elapsed: self.call.started.elapsed(),
+        });
+    }
+}
+
+impl Drop for RecordedResponse {
+    fn drop(&mut self) {
+        self.finish(None);
+    }
+}
+
+impl Body for RecordedResponse {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let data = ready!(Pin::new(&mut self.body).poll_data(cx));
+        if let Some(Ok(data)) = &data {
+            let this = &mut *self;
+            this.call.feed(&mut this.frames, false, data);
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let trailers = ready!(Pin::new(&mut self.body).poll_trailers(cx));
+        let status = trailers
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .and_then(Status::from_header_map);
+        self.finish(status);
+        Poll::Ready(trailers)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
@@ -0,0 +1,533 @@
+//! Sampling of the calls of a server, for later replay and offline analysis.
+//!
+//! A [`Sampler`] mounted as a layer persists a fraction of the calls of the
+//! methods of its [`DescriptorPool`] as [`Sample`]s: their method, time,
+//! request and response messages, and status. Messages are redacted with
+//! [`tonic_types_protobuf::redact`] before they leave the sampler, so calls of
+//! methods missing from the pool are never sampled.
+//!
+//! Unlike capturing all traffic, sampling is meant to be left enabled in
+//! production: calls that are not sampled are forwarded untouched, sampled
+//! calls keep at most [`Sampler::max_bytes`] of messages, and a
+//! [`SampleWriter`] appends samples to a file on a background thread,
+//! dropping them rather than slowing calls down when it falls behind. Files
+//! are read back with [`read_samples`].
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    fs::OpenOptions,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+    thread,
+    time::SystemTime,
+};
+
+use bytes::Buf;
+use protobuf::{well_known_types::timestamp::Timestamp, CodedInputStream, Message};
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Code, Status,
+};
+pub use tonic_types_protobuf::pb::sample::Sample;
+use tonic_types_protobuf::{redact::redact, time::TimestampExt};
+use tower_layer::Layer;
+
+use crate::{audit::RequestStream, frame::HEADER_LEN, DescriptorPool, DynamicMethod};
+
+/// Receives the [`Sample`]s of a [`Sampler`].
+///
+/// Samples are emitted on the tasks serving the calls, so sinks should hand
+/// them off, e.g. like [`SampleWriter`], rather than block.
+pub trait SampleSink: Send + Sync + 'static {
+    /// Handles the sample of a completed call.
+    fn sample(&self, sample: Sample);
+}
+
+impl<F> SampleSink for F
+where
+    F: Fn(Sample) + Send + Sync + 'static,
+{
+    fn sample(&self, sample: Sample) {
+        self(sample)
+    }
+}
+
+/// Persists a fraction of the gRPC calls of the wrapped service.
+#[derive(Clone)]
+pub struct Sampler {
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    sink: Arc<dyn SampleSink>,
+    rate: f64,
+    max_bytes: usize,
+    random: RandomState,
+    calls: Arc<AtomicU64>,
+}
+
+impl Sampler {
+    /// Create a sampler that sends 1% of the calls of the methods of `pool`
+    /// to `sink`.
+    pub fn new(pool: &DescriptorPool, sink: impl SampleSink) -> Self {
+        let methods = pool.methods().into_iter().map(|m| (m.path(), m)).collect();
+        Sampler {
+            methods: Arc::new(methods),
+            sink: Arc::new(sink),
+            rate: 0.01,
+            max_bytes: 64 * 1024,
+            random: RandomState::new(),
+            calls: Arc::default(),
+        }
+    }
+
+    /// Set the fraction of calls that are sampled, from 0.0 to 1.0.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the maximum total size of the messages kept of a call, 64 KiB by
+    /// default. Messages past it are left out, and the sample is marked
+    /// truncated.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns a layer that samples the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(sampler.into_layer())`.
+    pub fn into_layer(self) -> SampleLayer {
+        SampleLayer { sampler: self }
+    }
+
+    /// Returns whether to sample the next call.
+    fn sampled(&self) -> bool {
+        sampled(
+            self.rate,
+            &self.random,
+            self.calls.fetch_add(1, Ordering::Relaxed),
+        )
+    }
+}
+
+/// Returns whether to sample the `n`th call at `rate`, spreading the sampled
+/// calls by a random hash of `n`.
+pub(crate) fn sampled(rate: f64, random: &RandomState, n: u64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate <= 0.0 {
+        return false;
+    }
+    let mut hasher = random.build_hasher();
+    hasher.write_u64(n);
+    (hasher.finish() as f64 / u64::MAX as f64) < rate
+}
+
+impl fmt::Debug for Sampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sampler")
+            .field("methods", &self.methods.len())
+            .field("rate", &self.rate)
+            .field("max_bytes", &self.max_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A layer that mounts a [`Sampler`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct SampleLayer {
+    sampler: Sampler,
+}
+
+impl<S> Layer<S> for SampleLayer {
+    type Service = SampleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SampleService {
+            sampler: self.sampler.clone(),
+            inner,
+        }
+    }
+}
+
+/// Samples the gRPC calls of the inner service, and forwards other requests
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct SampleService<S> {
+    sampler: Sampler,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for SampleService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        let method = self.sampler.methods.get(req.uri().path()).cloned();
+        let method = match method {
+            Some(method) if grpc && self.sampler.sampled() => method,
+            _ => return Box::pin(self.inner.call(req)),
+        };
+
+        let state = Arc::new(Mutex::new(CallState {
+            requests: Collector::default(),
+            responses: Collector::default(),
+            budget: self.sampler.max_bytes,
+            status: None,
+        }));
+        let mut call = Call {
+            sink: self.sampler.sink.clone(),
+            method,
+            time: SystemTime::now(),
+            state: state.clone(),
+            done: false,
+        };
+
+        let (parts, body) = req.into_parts();
+        let body: RequestStream = Box::new(SampledRequest {
+            body: Box::pin(body),
+            state,
+        });
+        let req = http::Request::from_parts(parts, B::from(body));
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let response = fut.await?;
+            let (parts, body) = response.into_parts();
+            // A trailers-only response carries the status in its headers.
+            if let Some(status) = Status::from_header_map(&parts.headers) {
+                call.state.lock().unwrap().status = Some(status);
+                call.finish();
+            }
+            let body = SampledResponse { body, call }.boxed_unsync();
+            Ok(http::Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// What was collected of a sampled call so far, shared by its request and
+/// response bodies.
+struct CallState {
+    requests: Collector,
+    responses: Collector,
+    /// The size of messages that may still be kept.
+    budget: usize,
+    status: Option<Status>,
+}
+
+/// A sampled call, that emits its sample once it finished or was dropped.
+struct Call {
+    sink: Arc<dyn SampleSink>,
+    method: DynamicMethod,
+    time: SystemTime,
+    state: Arc<Mutex<CallState>>,
+    done: bool,
+}
+
+impl Call {
+    fn finish(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let mut state = self.state.lock().unwrap();
+        let mut sample = Sample::new();
+        sample.method = self.method.path();
+        sample.time = Timestamp::try_from_system_time(self.time).ok().into();
+        let requests = std::mem::take(&mut state.requests);
+        let responses = std::mem::take(&mut state.responses);
+        sample.truncated = requests.truncated || responses.truncated;
+        sample.requests = redacted(&self.method, true, requests.messages, &mut sample.truncated);
+        sample.responses = redacted(
+            &self.method,
+            false,
+            responses.messages,
+            &mut sample.truncated,
+        );
+        match &state.status {
+            Some(status) => {
+                sample.code = status.code() as i32;
+                sample.message = status.message().to_owned();
+            }
+            None => {
+                sample.code = Code::Cancelled as i32;
+                sample.message = "call ended without a status".to_owned();
+            }
+        }
+        drop(state);
+        self.sink.sample(sample);
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Returns the request or response `messages` of `method` with their
+/// sensitive fields masked. Messages that fail to parse are left out, and
+/// mark the sample `truncated`.
+fn redacted(
+    method: &DynamicMethod,
+    request: bool,
+    messages: Vec<Vec<u8>>,
+    truncated: &mut bool,
+) -> Vec<Vec<u8>> {
+    let descriptor = if request {
+        method.input()
+    } else {
+        method.output()
+    };
+    let redacted: Vec<_> = messages
+        .iter()
+        .filter_map(|data| {
+            let mut message = descriptor.parse_from_bytes(data).ok()?;
+            redact(&mut *message);
+            message.write_to_bytes_dyn().ok()
+        })
+        .collect();
+    *truncated |= redacted.len() < messages.len();
+    redacted
+}
+
+/// Collects the messages of the gRPC frames of a body.
+#[derive(Default)]
+struct Collector {
+    messages: Vec<Vec<u8>>,
+    truncated: bool,
+    header: Vec<u8>,
+    remaining: usize,
+    current: Option<Vec<u8>>,
+}
+
+impl Collector {
+    /// Collects the frames in `data`, as long as their messages fit in
+    /// `budget`.
+    fn feed(&mut self, mut data: &[u8], budget: &mut usize) {
+        while !data.is_empty() {
+            if self.remaining == 0 {
+                let n = (HEADER_LEN - self.header.len()).min(data.len());
+                self.header.extend_from_slice(&data[..n]);
+                data = &data[n..];
+                if self.header.len() < HEADER_LEN {
+                    return;
+                }
+                let len = u32::from_be_bytes(self.header[1..].try_into().unwrap()) as usize;
+                // Compressed messages can not be redacted.
+                let compressed = self.header[0] != 0;
+                self.header.clear();
+                self.remaining = len;
+                if compressed || len > *budget {
+                    self.truncated = true;
+                } else {
+                    *budget -= len;
+                    self.current = Some(Vec::with_capacity(len));
+                }
+            } else {
+                let n = self.remaining.min(data.len());
+                if let Some(current) = &mut self.current {
+                    current.extend_from_slice(&data[..n]);
+                }
+                data = &data[n..];
+                self.remaining -= n;
+            }
+            if self.remaining == 0 {
+                self.messages.extend(self.current.take());
+            }
+        }
+    }
+}
+
+/// A request body that collects its messages.
+struct SampledRequest<B> {
+    body: Pin<Box<B>>,
+    state: Arc<Mutex<CallState>>,
+}
+
+impl<B> Stream for SampledRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(self.body.as_mut().poll_data(cx)) {
+            Some(Ok(mut data)) => {
+                let data = data.copy_to_bytes(data.remaining());
+                let state = &mut *self.state.lock().unwrap();
+                state.requests.feed(&data, &mut state.budget);
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A response body that collects its messages, and finishes the call with
+/// the status of its trailers.
+struct SampledResponse {
+    body: BoxBody,
+    call: Call,
+}
+
+impl Body for SampledResponse {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let data = ready!(Pin::new(&mut self.body).poll_data(cx));
+        if let Some(Ok(data)) = &data {
+            let state = &mut *self.call.state.lock().unwrap();
+            state.responses.feed(data, &mut state.budget);
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let trailers = ready!(Pin::new(&mut self.body).poll_trailers(cx));
+        if let Some(status) = trailers
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .and_then(Status::from_header_map)
+        {
+            self.call.state.lock().unwrap().status = Some(status);
+        }
+        self.call.finish();
+        Poll::Ready(trailers)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+/// A [`SampleSink`] that appends length-delimited samples to a file, or any
+/// writer, on a background thread.
+///
+/// At most 1024 samples wait to be written; samples arriving while the queue
+/// is full are dropped and counted. Dropping the writer waits until the queued
+/// samples are written.
+pub struct SampleWriter {
+    tx: Option<mpsc::SyncSender<Sample>>,
+    thread: Option<thread::JoinHandle<()>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SampleWriter {
+    /// Create a writer that appends samples to the file at `path`, creating
+    /// it if needed.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(SampleWriter::new(file))
+    }
+
+    /// Create a writer that writes samples to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::sync_channel::<Sample>(1024);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let failed = dropped.clone();
+        let thread = thread::spawn(move || {
+            let mut writer = BufWriter::new(writer);
+            while let Ok(sample) = rx.recv() {
+                // Write what is queued, then flush once.
+                for sample in std::iter::once(sample).chain(rx.try_iter()) {
+                    if sample
+                        .write_length_delimited_to_writer(&mut writer)
+                        .is_err()
+                    {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                let _ = writer.flush();
+            }
+        });
+        SampleWriter {
+            tx: Some(tx),
+            thread: Some(thread),
+            dropped,
+        }
+    }
+
+    /// The number of samples dropped because the queue was full or writing
+    /// them failed.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl SampleSink for SampleWriter {
+    fn sample(&self, sample: Sample) {
+        let sent = self
+            .tx
+            .as_ref()
+            .is_some_and(|tx| tx.try_send(sample).is_ok());
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl fmt::Debug for SampleWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SampleWriter")
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SampleWriter {
+    fn drop(&mut self) {
+        drop(self.tx.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads the length-delimited samples written by a [`SampleWriter`].
+pub fn read_samples(mut reader: impl Read) -> protobuf::Result<Vec<Sample>> {
+    let mut input = CodedInputStream::new(&mut reader);
+    let mut samples = Vec::new();
+    while !input.eof()? {
+        samples.push(input.read_message()?);
+    }
+    Ok(samples)
+}
@@ -0,0 +1,301 @@
+//! A server that dispatches calls to handlers registered at runtime.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt,
+    future::Future,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use tonic::{
+    body::BoxBody,
+    codegen::{empty_body, http, Body, BoxFuture, BoxStream, Service, StdError},
+    Request, Response, Status, Streaming,
+};
+use tonic_codec_protobuf::DynamicCodec;
+use tower_layer::Layer;
+
+use crate::{DescriptorPool, DynamicMessage, DynamicMethod};
+
+type Handler<Req, Res> =
+    Arc<dyn Fn(Request<Req>) -> BoxFuture<Response<Res>, Status> + Send + Sync + 'static>;
+
+#[derive(Clone)]
+enum Route {
+    Unary(Handler<DynamicMessage, DynamicMessage>),
+    ServerStreaming(Handler<DynamicMessage, BoxStream<DynamicMessage>>),
+    ClientStreaming(Handler<Streaming<DynamicMessage>, DynamicMessage>),
+    Streaming(Handler<Streaming<DynamicMessage>, BoxStream<DynamicMessage>>),
+    Unimplemented,
+}
+
+struct Registered {
+    method: DynamicMethod,
+    route: Route,
+}
+
+/// A gRPC server whose methods are registered at runtime against their
+/// paths, e.g. `/pkg.Service/Method`, and handle dynamic messages.
+///
+/// Mount it next to generated services with [`DynamicServer::into_layer`],
+/// or serve it on its own as a tower service; unknown methods are answered
+/// with `UNIMPLEMENTED`.
+#[derive(Clone, Default)]
+pub struct DynamicServer {
+    routes: Arc<HashMap<String, Arc<Registered>>>,
+}
+
+impl fmt::Debug for DynamicServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicServer")
+            .field("methods", &self.routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl DynamicServer {
+    /// Create a server without methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler of the unary `method`.
+    pub fn unary<F, Fut>(self, method: &DynamicMethod, handler: F) -> Self
+    where
+        F: Fn(Request<DynamicMessage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<DynamicMessage>, Status>> + Send + 'static,
+    {
+        let handler: Handler<_, _> = Arc::new(move |request| Box::pin(handler(request)));
+        self.register(method, false, false, Route::Unary(handler))
+    }
+
+    /// Register a handler of the server streaming `method`.
+    pub fn server_streaming<F, Fut>(self, method: &DynamicMethod, handler: F) -> Self
+    where
+        F: Fn(Request<DynamicMessage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<BoxStream<DynamicMessage>>, Status>> + Send + 'static,
+    {
+        let handler: Handler<_, _> = Arc::new(move |request| Box::pin(handler(request)));
+        self.register(method, false, true, Route::ServerStreaming(handler))
+    }
+
+    /// Register a handler of the client streaming `method`.
+    pub fn client_streaming<F, Fut>(self, method: &DynamicMethod, handler: F) -> Self
+    where
+        F: Fn(Request<Streaming<DynamicMessage>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<DynamicMessage>, Status>> + Send + 'static,
+    {
+        let handler: Handler<_, _> = Arc::new(move |request| Box::pin(handler(request)));
+        self.register(method, true, false, Route::ClientStreaming(handler))
+    }
+
+    /// Register a handler of the bidirectional streaming `method`.
+    pub fn streaming<F, Fut>(self, method: &DynamicMethod, handler: F) -> Self
+    where
+        F: Fn(Request<Streaming<DynamicMessage>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<BoxStream<DynamicMessage>>, Status>> + Send + 'static,
+    {
+        let handler: Handler<_, _> = Arc::new(move |request| Box::pin(handler(request)));
+        self.register(method, true, true, Route::Streaming(handler))
+    }
+
+    /// Register every method of the service with the fully-qualified
+    /// `service` name in `pool` that has no handler yet, answering it with
+    /// `UNIMPLEMENTED`.
+    ///
+    /// Handlers registered later replace the placeholders. Mounted with
+    /// [`DynamicServer::into_layer`], the whole service is then served by
+    /// this server rather than the wrapped one.
+    pub fn scaffold(mut self, pool: &DescriptorPool, service: &str) -> Self {
+        let routes = Arc::make_mut(&mut self.routes);
+        for method in pool.service(service) {
+            routes.entry(method.path()).or_insert_with(|| {
+                Arc::new(Registered {
+                    method,
+                    route: Route::Unimplemented,
+                })
+            });
+        }
+        self
+    }
+
+    fn register(
+        mut self,
+        method: &DynamicMethod,
+        client_streaming: bool,
+        server_streaming: bool,
+        route: Route,
+    ) -> Self {
+        assert!(
+            method.client_streaming() == client_streaming
+                && method.server_streaming() == server_streaming,
+            "handler does not match the streaming shape of {}",
+            method.path()
+        );
+        Arc::make_mut(&mut self.routes).insert(
+            method.path(),
+            Arc::new(Registered {
+                method: method.clone(),
+                route,
+            }),
+        );
+        self
+    }
+
+    /// Returns the registered methods.
+    pub fn methods(&self) -> Vec<DynamicMethod> {
+        self.routes.values().map(|r| r.method.clone()).collect()
+    }
+
+    /// Returns a layer that dispatches the registered methods to this server
+    /// and everything else to the wrapped service, e.g.
+    /// `Server::builder().layer(dynamic.into_layer()).add_service(...)`.
+    pub fn into_layer(self) -> DynamicLayer {
+        DynamicLayer { server: self }
+    }
+
+    fn dispatch<B>(&self, req: http::Request<B>) -> BoxFuture<http::Response<BoxBody>, Infallible>
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        let registered = self
+            .routes
+            .get(req.uri().path())
+            .filter(|r| !matches!(r.route, Route::Unimplemented))
+            .cloned();
+        let Some(registered) = registered else {
+            return Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            });
+        };
+        Box::pin(async move {
+            let codec = DynamicCodec::new(registered.method.input().clone());
+            let mut grpc = tonic::server::Grpc::new(codec);
+            let res = match &registered.route {
+                Route::Unary(h) => grpc.unary(UnarySvc(h.clone()), req).await,
+                Route::ServerStreaming(h) => {
+                    grpc.server_streaming(ServerStreamingSvc(h.clone()), req)
+                        .await
+                }
+                Route::ClientStreaming(h) => {
+                    grpc.client_streaming(ClientStreamingSvc(h.clone()), req)
+                        .await
+                }
+                Route::Streaming(h) => grpc.streaming(StreamingSvc(h.clone()), req).await,
+                Route::Unimplemented => unreachable!(),
+            };
+            Ok(res)
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for DynamicServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        self.dispatch(req)
+    }
+}
+
+/// Declares a tower service adapting a handler to `tonic::server::Grpc`.
+/// The services are concrete types, generic ones trip up the lifetime
+/// inference of `dyn MessageDyn` in the boxed dispatch future.
+macro_rules! handler_svc {
+    ($($svc:ident: $req:ty => $res:ty),* $(,)?) => {$(
+        struct $svc(Handler<$req, $res>);
+
+        impl Service<Request<$req>> for $svc {
+            type Response = Response<$res>;
+            type Error = Status;
+            type Future = BoxFuture<Response<$res>, Status>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, request: Request<$req>) -> Self::Future {
+                (self.0)(request)
+            }
+        }
+    )*};
+}
+
+handler_svc!(
+    UnarySvc: DynamicMessage => DynamicMessage,
+    ServerStreamingSvc: DynamicMessage => BoxStream<DynamicMessage>,
+    ClientStreamingSvc: Streaming<DynamicMessage> => DynamicMessage,
+    StreamingSvc: Streaming<DynamicMessage> => BoxStream<DynamicMessage>,
+);
+
+/// A layer that mounts a [`DynamicServer`] in front of another service.
+#[derive(Debug, Clone)]
+pub struct DynamicLayer {
+    server: DynamicServer,
+}
+
+impl<S> Layer<S> for DynamicLayer {
+    type Service = DynamicRouter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DynamicRouter {
+            server: self.server.clone(),
+            inner,
+        }
+    }
+}
+
+/// Dispatches the methods of a [`DynamicServer`], and forwards other calls
+/// to the inner service.
+#[derive(Debug, Clone)]
+pub struct DynamicRouter<S> {
+    server: DynamicServer,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for DynamicRouter<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if self.server.routes.contains_key(req.uri().path()) {
+            let fut = self.server.dispatch(req);
+            Box::pin(async move {
+                match fut.await {
+                    Ok(res) => Ok(res),
+                    Err(never) => match never {},
+                }
+            })
+        } else {
+            Box::pin(self.inner.call(req))
+        }
+    }
+}
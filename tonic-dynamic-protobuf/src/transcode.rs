@@ -0,0 +1,307 @@
+//! Transcoding of JSON requests on gRPC routes, so that plain HTTP clients
+//! like curl can call the unary methods of a server.
+//!
+//! A `POST` of a JSON request message with `content-type: application/json`
+//! to the path of a method, e.g. `/pkg.Service/Method`, is converted to a
+//! gRPC call of the wrapped service, and its response message is returned as
+//! JSON. Failed calls are answered with the HTTP status closest to their gRPC
+//! code and a `{"code": ..., "message": ...}` body. Messages are mapped with
+//! protobuf-json-mapping, and request bodies larger than
+//! [`JsonTranscoder::max_request_size`] are answered with
+//! `413 Payload Too Large`.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Buf;
+use http_body::Full;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, BoxFuture, Bytes, Service, StdError},
+    Code, Status,
+};
+use tower_layer::Layer;
+
+use crate::{frame, DescriptorPool, DynamicMethod};
+
+/// The default limit of the size of JSON requests, as the default decoding
+/// limit of tonic.
+const DEFAULT_MAX_REQUEST_SIZE: usize = 4 * 1024 * 1024;
+
+/// Converts JSON requests to the unary methods of a [`DescriptorPool`] into
+/// gRPC calls of the wrapped service.
+#[derive(Debug, Clone)]
+pub struct JsonTranscoder {
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    max_request_size: usize,
+}
+
+impl JsonTranscoder {
+    /// Create a transcoder of all unary methods of `pool`.
+    pub fn new(pool: &DescriptorPool) -> Self {
+        let methods = pool
+            .methods()
+            .into_iter()
+            .filter(|m| !m.client_streaming() && !m.server_streaming())
+            .map(|m| (m.path(), m))
+            .collect();
+        JsonTranscoder {
+            methods: Arc::new(methods),
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+        }
+    }
+
+    /// Limits the size of JSON request bodies, larger ones are rejected with
+    /// `RESOURCE_EXHAUSTED`.
+    ///
+    /// Default: `4MB`
+    pub fn max_request_size(mut self, limit: usize) -> Self {
+        self.max_request_size = limit;
+        self
+    }
+
+    /// Returns a layer that transcodes JSON requests and passes everything
+    /// else through, e.g.
+    /// `Server::builder().accept_http1(true).layer(transcoder.into_layer())`.
+    pub fn into_layer(self) -> TranscodeLayer {
+        TranscodeLayer { transcoder: self }
+    }
+
+    fn method<B>(&self, req: &http::Request<B>) -> Option<&DynamicMethod> {
+        let json = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if !json || req.method() != http::Method::POST {
+            return None;
+        }
+        self.methods.get(req.uri().path())
+    }
+}
+
+/// A layer that mounts a [`JsonTranscoder`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct TranscodeLayer {
+    transcoder: JsonTranscoder,
+}
+
+impl<S> Layer<S> for TranscodeLayer {
+    type Service = TranscodeRouter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TranscodeRouter {
+            transcoder: self.transcoder.clone(),
+            inner,
+        }
+    }
+}
+
+/// Transcodes JSON requests to gRPC calls of the inner service, and forwards
+/// other requests unchanged.
+#[derive(Debug, Clone)]
+pub struct TranscodeRouter<S> {
+    transcoder: JsonTranscoder,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for TranscodeRouter<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Body + From<Bytes> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let Some(method) = self.transcoder.method(&req).cloned() else {
+            return Box::pin(self.inner.call(req));
+        };
+        let limit = self.transcoder.max_request_size;
+        // The ready service handles this call, its clone the next one.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            let message = match collect(body, limit).await {
+                Ok(Some((json, _))) => match encode_request(&method, &json) {
+                    Ok(message) => message,
+                    Err(status) => return Ok(error_response(status)),
+                },
+                Ok(None) => {
+                    let status = Status::resource_exhausted(format!(
+                        "request is larger than {} bytes",
+                        limit
+                    ));
+                    let mut response = error_response(status);
+                    *response.status_mut() = http::StatusCode::PAYLOAD_TOO_LARGE;
+                    return Ok(response);
+                }
+                Err(e) => {
+                    let status = Status::invalid_argument(format!("failed to read body: {}", e));
+                    return Ok(error_response(status));
+                }
+            };
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            parts.headers.insert(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static("application/grpc"),
+            );
+            parts
+                .headers
+                .insert(http::header::TE, http::HeaderValue::from_static("trailers"));
+            let response = inner
+                .call(http::Request::from_parts(parts, B::from(message)))
+                .await?;
+            Ok(decode_response(&method, response).await)
+        })
+    }
+}
+
+/// The whole body and the trailers of a request or a response.
+type Collected = (Vec<u8>, Option<http::HeaderMap>);
+
+/// Reads the whole `body` and its trailers, or returns `None` if the body is
+/// larger than `limit` bytes.
+async fn collect<B>(body: B, limit: usize) -> Result<Option<Collected>, StdError>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    let mut body = Box::pin(body);
+    let mut buf = vec![];
+    while let Some(chunk) = body.data().await {
+        let mut chunk = chunk.map_err(Into::into)?;
+        if buf.len() + chunk.remaining() > limit {
+            return Ok(None);
+        }
+        while chunk.has_remaining() {
+            let bytes = chunk.chunk();
+            buf.extend_from_slice(bytes);
+            let len = bytes.len();
+            chunk.advance(len);
+        }
+    }
+    let trailers = body.trailers().await.map_err(Into::into)?;
+    Ok(Some((buf, trailers)))
+}
+
+/// Parses the JSON request of `method` and returns it as a gRPC frame.
+#[allow(clippy::result_large_err)]
+fn encode_request(method: &DynamicMethod, json: &[u8]) -> Result<Bytes, Status> {
+    let json = std::str::from_utf8(json)
+        .map_err(|e| Status::invalid_argument(format!("request is not UTF-8: {}", e)))?;
+    // An empty body is an empty request, like `{}`.
+    let json = if json.trim().is_empty() { "{}" } else { json };
+    let message = protobuf_json_mapping::parse_dyn_from_str(method.input(), json)
+        .map_err(|e| Status::invalid_argument(format!("invalid request: {}", e)))?;
+    let data = message
+        .write_to_bytes_dyn()
+        .map_err(|e| Status::invalid_argument(format!("invalid request: {}", e)))?;
+    let mut buf = vec![];
+    frame::encode(false, &data, &mut buf)
+        .map_err(|e| Status::resource_exhausted(e.to_string()))?;
+    Ok(buf.into())
+}
+
+/// Converts the gRPC response of `method` to a JSON response.
+async fn decode_response(
+    method: &DynamicMethod,
+    response: http::Response<BoxBody>,
+) -> http::Response<BoxBody> {
+    let (parts, body) = response.into_parts();
+    // A trailers-only response carries the status in its headers.
+    if let Some(status) = Status::from_header_map(&parts.headers) {
+        if status.code() != Code::Ok {
+            return error_response(status);
+        }
+    }
+    // Responses of the local service are limited by its encoding limit.
+    let (data, trailers) = match collect(body, usize::MAX).await {
+        Ok(collected) => collected.unwrap_or_default(),
+        Err(e) => {
+            return error_response(Status::internal(format!("failed to read response: {}", e)))
+        }
+    };
+    if let Some(status) = trailers.as_ref().and_then(Status::from_header_map) {
+        if status.code() != Code::Ok {
+            return error_response(status);
+        }
+    }
+    let message = match frame::frames(&data).next() {
+        Some(Ok(frame)) if !frame.compressed => method.output().parse_from_bytes(frame.data),
+        Some(Ok(_)) => return error_response(Status::internal("response is compressed")),
+        Some(Err(e)) => return error_response(Status::internal(e.to_string())),
+        None => return error_response(Status::internal("response has no message")),
+    };
+    let message = match message {
+        Ok(message) => message,
+        Err(e) => {
+            return error_response(Status::internal(format!("invalid response: {}", e)));
+        }
+    };
+    let json = match protobuf_json_mapping::print_to_string(&*message) {
+        Ok(json) => json,
+        Err(e) => {
+            return error_response(Status::internal(format!("invalid response: {}", e)));
+        }
+    };
+    let mut response = json_response(http::StatusCode::OK, json);
+    // Forward the response metadata, but not the gRPC protocol headers.
+    for (name, value) in &parts.headers {
+        if name != http::header::CONTENT_TYPE && !name.as_str().starts_with("grpc-") {
+            response.headers_mut().append(name, value.clone());
+        }
+    }
+    response
+}
+
+fn error_response(status: Status) -> http::Response<BoxBody> {
+    let body = serde_json::json!({
+        "code": status.code() as i32,
+        "message": status.message(),
+    });
+    json_response(http_status(status.code()), body.to_string())
+}
+
+fn json_response(status: http::StatusCode, body: String) -> http::Response<BoxBody> {
+    http::Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(
+            Full::new(Bytes::from(body))
+                .map_err(|never| match never {})
+                .boxed_unsync(),
+        )
+        .unwrap()
+}
+
+/// The HTTP status of a gRPC code, as mapped by `google.rpc.Code`.
+fn http_status(code: Code) -> http::StatusCode {
+    match code {
+        Code::Ok => http::StatusCode::OK,
+        Code::Cancelled => http::StatusCode::from_u16(499).unwrap(),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            http::StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => http::StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => http::StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => http::StatusCode::CONFLICT,
+        Code::PermissionDenied => http::StatusCode::FORBIDDEN,
+        Code::Unauthenticated => http::StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => http::StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => http::StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => http::StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
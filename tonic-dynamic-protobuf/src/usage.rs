@@ -0,0 +1,326 @@
+//! Analytics of which request fields are actually populated, e.g. to learn
+//! whether deprecated fields are still in use before removing them.
+//!
+//! A [`FieldUsage`] mounted as a layer decodes every request message of the
+//! methods of its [`DescriptorPool`], and counts per method how many messages
+//! populated each field. Fields are counted by path, e.g. `user.name`, within
+//! singular and repeated message fields. A field is populated if it is set,
+//! or for repeated and map fields, if it is not empty; proto3 fields without
+//! presence are populated if they are not the default value.
+//!
+//! Deprecated fields are listed in the reports even when no message populated
+//! them. Compressed messages can not be decoded, and are not counted.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use bytes::Buf;
+use protobuf::{
+    reflect::{FieldDescriptor, MessageDescriptor, ReflectValueRef, RuntimeFieldType, RuntimeType},
+    MessageDyn,
+};
+use tokio_stream::Stream;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body, Bytes, Service, StdError},
+};
+use tower_layer::Layer;
+
+use crate::{audit::RequestStream, frame::FrameReader, DescriptorPool, DynamicMethod};
+
+/// Returns whether `field` is marked `deprecated = true`.
+pub fn is_deprecated(field: &FieldDescriptor) -> bool {
+    field
+        .proto()
+        .options
+        .as_ref()
+        .is_some_and(|options| options.deprecated())
+}
+
+/// How often a field was populated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldStats {
+    /// The number of request messages that populated the field.
+    pub count: u64,
+    /// Whether the field is marked `deprecated = true`.
+    pub deprecated: bool,
+}
+
+/// The field usage of the request messages of a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodUsage {
+    /// The number of request messages decoded.
+    pub messages: u64,
+    /// The populated and the deprecated fields by path, e.g. `user.name`.
+    pub fields: BTreeMap<String, FieldStats>,
+}
+
+impl MethodUsage {
+    /// Returns the deprecated fields with the number of messages that still
+    /// populated them.
+    pub fn deprecated(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.fields
+            .iter()
+            .filter(|(_, stats)| stats.deprecated)
+            .map(|(path, stats)| (path.as_str(), stats.count))
+    }
+}
+
+/// Counts the populated fields of the request messages of the wrapped
+/// service.
+#[derive(Clone)]
+pub struct FieldUsage {
+    methods: Arc<HashMap<String, DynamicMethod>>,
+    usage: Arc<Mutex<HashMap<String, MethodUsage>>>,
+}
+
+impl FieldUsage {
+    /// Create a recorder of the fields of the request messages of the methods
+    /// of `pool`. Calls of other methods are not recorded.
+    pub fn new(pool: &DescriptorPool) -> Self {
+        let methods = pool.methods().into_iter().map(|m| (m.path(), m)).collect();
+        FieldUsage {
+            methods: Arc::new(methods),
+            usage: Arc::default(),
+        }
+    }
+
+    /// Returns a layer that records the calls of the wrapped service, e.g.
+    /// `Server::builder().layer(usage.clone().into_layer())`. Layers of
+    /// clones of the recorder share its counts.
+    pub fn into_layer(self) -> FieldUsageLayer {
+        FieldUsageLayer { usage: self }
+    }
+
+    /// Returns the usage of the methods called so far, by path, e.g.
+    /// `/pkg.Service/Method`.
+    pub fn report(&self) -> BTreeMap<String, MethodUsage> {
+        let usage = self.usage.lock().unwrap();
+        usage
+            .iter()
+            .map(|(path, usage)| (path.clone(), usage.clone()))
+            .collect()
+    }
+
+    /// Clears the counts, e.g. after exporting a report.
+    pub fn reset(&self) {
+        self.usage.lock().unwrap().clear();
+    }
+
+    /// Counts the populated fields of the request message `data` of
+    /// `method`. Messages that fail to decode are not counted.
+    fn record(&self, method: &DynamicMethod, data: &[u8]) {
+        let Ok(message) = method.input().parse_from_bytes(data) else {
+            return;
+        };
+        let mut populated = BTreeSet::new();
+        populated_fields(&*message, "", &mut populated);
+
+        let mut usage = self.usage.lock().unwrap();
+        let usage = usage.entry(method.path()).or_insert_with(|| {
+            let mut fields = BTreeMap::new();
+            deprecated_fields(method.input(), "", &mut vec![], &mut fields);
+            MethodUsage {
+                messages: 0,
+                fields,
+            }
+        });
+        usage.messages += 1;
+        for (path, deprecated) in populated {
+            let stats = usage.fields.entry(path).or_insert(FieldStats {
+                count: 0,
+                deprecated,
+            });
+            stats.count += 1;
+        }
+    }
+}
+
+impl fmt::Debug for FieldUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldUsage")
+            .field("methods", &self.methods.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Adds the paths of the populated fields of `message` to `populated`, with
+/// whether they are deprecated.
+fn populated_fields(
+    message: &dyn MessageDyn,
+    prefix: &str,
+    populated: &mut BTreeSet<(String, bool)>,
+) {
+    for field in message.descriptor_dyn().fields() {
+        let path = format!("{}{}", prefix, field.name());
+        let nested_prefix = format!("{}.", path);
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(runtime_type) => {
+                if !field.has_field(message) {
+                    continue;
+                }
+                if let RuntimeType::Message(_) = runtime_type {
+                    let nested = field.get_message(message);
+                    populated_fields(&*nested, &nested_prefix, populated);
+                }
+            }
+            RuntimeFieldType::Repeated(runtime_type) => {
+                let values = field.get_repeated(message);
+                if values.is_empty() {
+                    continue;
+                }
+                if let RuntimeType::Message(_) = runtime_type {
+                    for value in &values {
+                        if let ReflectValueRef::Message(nested) = value {
+                            populated_fields(&*nested, &nested_prefix, populated);
+                        }
+                    }
+                }
+            }
+            RuntimeFieldType::Map(..) => {
+                if field.get_map(message).is_empty() {
+                    continue;
+                }
+            }
+        }
+        populated.insert((path, is_deprecated(&field)));
+    }
+}
+
+/// Adds the paths of the deprecated fields of `descriptor` to `fields`,
+/// without following the message types in `visiting` again.
+fn deprecated_fields(
+    descriptor: &MessageDescriptor,
+    prefix: &str,
+    visiting: &mut Vec<MessageDescriptor>,
+    fields: &mut BTreeMap<String, FieldStats>,
+) {
+    if visiting.contains(descriptor) {
+        return;
+    }
+    visiting.push(descriptor.clone());
+    for field in descriptor.fields() {
+        let path = format!("{}{}", prefix, field.name());
+        let nested = match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(nested))
+            | RuntimeFieldType::Repeated(RuntimeType::Message(nested)) => Some(nested),
+            _ => None,
+        };
+        if let Some(nested) = nested {
+            deprecated_fields(&nested, &format!("{}.", path), visiting, fields);
+        }
+        if is_deprecated(&field) {
+            fields.insert(
+                path,
+                FieldStats {
+                    count: 0,
+                    deprecated: true,
+                },
+            );
+        }
+    }
+    visiting.pop();
+}
+
+/// A layer that mounts a [`FieldUsage`] in front of gRPC services.
+#[derive(Debug, Clone)]
+pub struct FieldUsageLayer {
+    usage: FieldUsage,
+}
+
+impl<S> Layer<S> for FieldUsageLayer {
+    type Service = FieldUsageService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FieldUsageService {
+            usage: self.usage.clone(),
+            inner,
+        }
+    }
+}
+
+/// Records the field usage of the gRPC calls of the inner service, and
+/// forwards other requests unchanged.
+#[derive(Debug, Clone)]
+pub struct FieldUsageService<S> {
+    usage: FieldUsage,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for FieldUsageService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + From<RequestStream> + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/grpc"));
+        let method = self.usage.methods.get(req.uri().path()).cloned();
+        let method = match method {
+            Some(method) if grpc => method,
+            _ => return self.inner.call(req),
+        };
+
+        let (parts, body) = req.into_parts();
+        let body: RequestStream = Box::new(RecordedRequest {
+            body: Box::pin(body),
+            usage: self.usage.clone(),
+            method,
+            frames: FrameReader::new(),
+        });
+        self.inner
+            .call(http::Request::from_parts(parts, B::from(body)))
+    }
+}
+
+/// A request body that records the fields of its messages.
+struct RecordedRequest<B> {
+    body: Pin<Box<B>>,
+    usage: FieldUsage,
+    method: DynamicMethod,
+    frames: FrameReader,
+}
+
+impl<B> Stream for RecordedRequest<B>
+where
+    B: Body,
+    B::Error: Into<StdError>,
+{
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(self.body.as_mut().poll_data(cx)) {
+            Some(Ok(mut data)) => {
+                let data = data.copy_to_bytes(data.remaining());
+                let this = &mut *self;
+                // Leave malformed requests to the inner service.
+                let _ = this.frames.feed(&data, |frame| {
+                    if !frame.compressed {
+                        this.usage.record(&this.method, frame.data);
+                    }
+                });
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
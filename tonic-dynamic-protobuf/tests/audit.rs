@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use protobuf::{reflect::FileDescriptor, text_format, MessageDyn};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_dynamic_protobuf::{
+    audit::{is_audited, AuditRecord},
+    Auditor, DescriptorPool, DynamicClient, DynamicServer,
+};
+use tonic_types_protobuf::pb::options;
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+import "tonic_protobuf/options.proto";
+
+message User {
+    string name = 1 [(tonic_protobuf.audit) = true];
+    string password = 2 [(tonic_protobuf.audit) = true, (tonic_protobuf.sensitive) = true];
+    string email = 3;
+}
+
+message DeleteRequest {
+    string table = 1 [(tonic_protobuf.audit) = true];
+    repeated int64 ids = 2 [(tonic_protobuf.audit) = true];
+    User user = 3;
+    string comment = 4;
+}
+
+message DeleteResponse {
+    int64 deleted = 1;
+}
+
+service Tables {
+    rpc Delete(DeleteRequest) returns (DeleteResponse);
+    rpc Watch(DeleteRequest) returns (stream DeleteResponse);
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("tables.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .include(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tonic-types-protobuf/proto"
+        ))
+        .input(dir.path().join("tables.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let options = options::file_descriptor().clone();
+    let file = FileDescriptor::new_dynamic(fds.file[0].clone(), &[options]).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    pool
+}
+
+fn deleted(pool: &DescriptorPool, count: i64) -> Box<dyn MessageDyn> {
+    let descriptor = pool.message("demo.DeleteResponse").unwrap();
+    let mut response = descriptor.new_instance();
+    text_format::merge_from_str(&mut *response, &format!("deleted: {count}")).unwrap();
+    response
+}
+
+/// Serves the `Tables` service behind an auditor, and returns a channel to it
+/// and the emitted records.
+async fn serve(pool: &DescriptorPool) -> (Channel, mpsc::UnboundedReceiver<AuditRecord>) {
+    let delete = pool.method("demo.Tables/Delete").unwrap();
+    let watch = pool.method("demo.Tables/Watch").unwrap();
+    let (pool1, pool2) = (pool.clone(), pool.clone());
+    let server = DynamicServer::new()
+        .unary(&delete, move |request| {
+            let pool = pool1.clone();
+            async move {
+                let text = text_format::print_to_string(&**request.get_ref());
+                if text.contains("locked") {
+                    return Err(Status::permission_denied("table is locked"));
+                }
+                Ok(Response::new(deleted(&pool, 2)))
+            }
+        })
+        .server_streaming(&watch, move |_request| {
+            let pool = pool2.clone();
+            async move {
+                let stream = tokio_stream::iter(vec![Ok(deleted(&pool, 1)), Ok(deleted(&pool, 3))]);
+                Ok(Response::new(Box::pin(stream) as _))
+            }
+        });
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let auditor = Auditor::new(pool, move |record| {
+        let _ = tx.send(record);
+    });
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(auditor.into_layer())
+            .layer(server.into_layer())
+            .add_service(health)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    (channel, rx)
+}
+
+async fn next(records: &mut mpsc::UnboundedReceiver<AuditRecord>) -> AuditRecord {
+    tokio::time::timeout(Duration::from_secs(5), records.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn request(pool: &DescriptorPool, text: &str) -> Box<dyn MessageDyn> {
+    let mut request = pool.message("demo.DeleteRequest").unwrap().new_instance();
+    text_format::merge_from_str(&mut *request, text).unwrap();
+    request
+}
+
+#[test]
+fn test_is_audited() {
+    let user = pool().message("demo.User").unwrap();
+    let audited: Vec<_> = user
+        .fields()
+        .filter(is_audited)
+        .map(|f| f.name().to_owned())
+        .collect();
+    assert_eq!(audited, ["name", "password"]);
+}
+
+#[tokio::test]
+async fn test_audit_records() {
+    let pool = pool();
+    let delete = pool.method("demo.Tables/Delete").unwrap();
+    let (channel, mut records) = serve(&pool).await;
+    let mut client = DynamicClient::new(channel);
+
+    let text = r#"table: "users" ids: 1 ids: 2
+        user { name: "alice" password: "hunter2" email: "alice@example.com" }
+        comment: "cleanup""#;
+    let message = request(&pool, text);
+    let size = message.compute_size_dyn();
+    client.unary(&delete, Request::new(message)).await.unwrap();
+
+    let record = next(&mut records).await;
+    assert_eq!(record.method, "/demo.Tables/Delete");
+    assert!(record.peer.is_some());
+    assert_eq!(record.code, Code::Ok);
+    assert_eq!(record.request_messages, 1);
+    assert_eq!(record.request_bytes, size);
+    assert_eq!(record.response_messages, 1);
+    assert_eq!(record.response_bytes, deleted(&pool, 2).compute_size_dyn());
+    // Only audited fields are captured, and sensitive ones masked.
+    let fields: Vec<_> = record
+        .fields
+        .iter()
+        .map(|(path, value)| format!("{path}={value}"))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            r#"ids=["1","2"]"#,
+            r#"table="users""#,
+            r#"user.name="alice""#,
+            r#"user.password="[REDACTED]""#,
+        ]
+    );
+    let json: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+    assert_eq!(json["fields"]["table"], "users");
+    assert_eq!(json["code"], 0);
+
+    // Failed calls are audited with their status.
+    let status = client
+        .unary(&delete, Request::new(request(&pool, r#"table: "locked""#)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let record = next(&mut records).await;
+    assert_eq!(record.code, Code::PermissionDenied);
+    assert_eq!(record.message, "table is locked");
+    assert_eq!(record.response_messages, 0);
+    assert_eq!(record.fields["table"], r#""locked""#);
+}
+
+#[tokio::test]
+async fn test_audit_streaming() {
+    let pool = pool();
+    let watch = pool.method("demo.Tables/Watch").unwrap();
+    let (channel, mut records) = serve(&pool).await;
+    let mut client = DynamicClient::new(channel);
+
+    let mut stream = client
+        .server_streaming(&watch, Request::new(request(&pool, r#"table: "t""#)))
+        .await
+        .unwrap()
+        .into_inner();
+    while stream.message().await.unwrap().is_some() {}
+
+    let record = next(&mut records).await;
+    assert_eq!(record.method, "/demo.Tables/Watch");
+    assert_eq!(record.code, Code::Ok);
+    assert_eq!(record.response_messages, 2);
+    assert_eq!(
+        record.response_bytes,
+        deleted(&pool, 1).compute_size_dyn() + deleted(&pool, 3).compute_size_dyn()
+    );
+    assert_eq!(record.fields["table"], r#""t""#);
+}
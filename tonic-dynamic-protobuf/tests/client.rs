@@ -0,0 +1,94 @@
+use protobuf::reflect::{ReflectValueBox, ReflectValueRef};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Request,
+};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicClient};
+use tonic_reflection_protobuf::ReflectionClient;
+
+async fn serve() -> Channel {
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    let reflection = tonic_reflection_protobuf::Builder::configure()
+        .register_file_descriptor(tonic_health_protobuf::pb::health::file_descriptor())
+        .build_v1()
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(health)
+            .add_service(reflection)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_dynamic_calls() {
+    let channel = serve().await;
+    let mut pool = DescriptorPool::new();
+    pool.add_from_reflection(&mut ReflectionClient::new(channel.clone()))
+        .await
+        .unwrap();
+    let mut services = pool.services();
+    services.sort();
+    assert_eq!(
+        services,
+        [
+            "grpc.health.v1.Health",
+            "grpc.reflection.v1.ServerReflection"
+        ]
+    );
+
+    let mut client = DynamicClient::new(channel);
+
+    // Unary.
+    let check = pool.method("/grpc.health.v1.Health/Check").unwrap();
+    let request = check.input().new_instance();
+    let response = client
+        .unary(&check, Request::new(request))
+        .await
+        .unwrap()
+        .into_inner();
+    let status = check.output().field_by_name("status").unwrap();
+    match status.get_singular_field_or_default(&*response) {
+        ReflectValueRef::Enum(_, number) => assert_eq!(number, 1),
+        value => panic!("{value:?}"),
+    }
+
+    // Bidirectional streaming.
+    let info = pool
+        .method("grpc.reflection.v1.ServerReflection.ServerReflectionInfo")
+        .unwrap();
+    let mut request = info.input().new_instance();
+    info.input()
+        .field_by_name("list_services")
+        .unwrap()
+        .set_singular_field(&mut *request, ReflectValueBox::String("*".to_owned()));
+    let mut responses = client
+        .streaming(&info, tokio_stream::iter(vec![request]))
+        .await
+        .unwrap()
+        .into_inner();
+    let response = responses.message().await.unwrap().unwrap();
+    let list = info
+        .output()
+        .field_by_name("list_services_response")
+        .unwrap()
+        .get_message(&*response);
+    let service = list.descriptor_dyn().field_by_name("service").unwrap();
+    assert_eq!(service.get_repeated(&*list).len(), 2);
+
+    // Mismatched shapes are rejected.
+    let status = client
+        .unary(&info, Request::new(info.input().new_instance()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
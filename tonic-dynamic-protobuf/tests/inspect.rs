@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use protobuf::{reflect::FileDescriptor, text_format, MessageDyn};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_dynamic_protobuf::{
+    inspect::{EventKind, InspectEvent},
+    DescriptorPool, DynamicClient, DynamicServer, Inspector,
+};
+use tonic_health_protobuf::pb::{
+    health::HealthCheckRequest, health_tonic::health_client::HealthClient,
+};
+use tonic_types_protobuf::pb::options;
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+import "tonic_protobuf/options.proto";
+
+message LoginRequest {
+    string user = 1;
+    string password = 2 [(tonic_protobuf.sensitive) = true];
+}
+
+message LoginResponse {
+    string session = 1;
+}
+
+service Accounts {
+    rpc Login(LoginRequest) returns (LoginResponse);
+    rpc Sessions(LoginRequest) returns (stream LoginResponse);
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("accounts.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .include(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tonic-types-protobuf/proto"
+        ))
+        .input(dir.path().join("accounts.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let options = options::file_descriptor().clone();
+    let file = FileDescriptor::new_dynamic(fds.file[0].clone(), &[options]).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    pool
+}
+
+fn message(pool: &DescriptorPool, name: &str, text: &str) -> Box<dyn MessageDyn> {
+    let mut message = pool.message(name).unwrap().new_instance();
+    text_format::merge_from_str(&mut *message, text).unwrap();
+    message
+}
+
+/// Serves the `Accounts` and health services behind `inspector`.
+async fn serve(pool: &DescriptorPool, inspector: Inspector) -> Channel {
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let sessions = pool.method("demo.Accounts/Sessions").unwrap();
+    let (pool1, pool2) = (pool.clone(), pool.clone());
+    let server = DynamicServer::new()
+        .unary(&login, move |request| {
+            let pool = pool1.clone();
+            async move {
+                let text = text_format::print_to_string(&**request.get_ref());
+                if text.contains("mallory") {
+                    return Err(Status::permission_denied("user is locked"));
+                }
+                let session = r#"session: "s1""#;
+                Ok(Response::new(message(&pool, "demo.LoginResponse", session)))
+            }
+        })
+        .server_streaming(&sessions, move |_request| {
+            let pool = pool2.clone();
+            async move {
+                let sessions = ["s1", "s2"]
+                    .map(|s| message(&pool, "demo.LoginResponse", &format!("session: {s:?}")));
+                let stream = tokio_stream::iter(sessions).map(Ok);
+                Ok(Response::new(Box::pin(stream) as _))
+            }
+        });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(inspector.into_layer())
+            .layer(server.into_layer())
+            .add_service(tonic_health_protobuf::health_reporter().1)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Returns an inspector of the calls of `pool`, and the printed events.
+fn inspector(pool: &DescriptorPool) -> (Inspector, mpsc::UnboundedReceiver<String>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let inspector = Inspector::new(pool, move |event: InspectEvent| {
+        let _ = tx.send(event.to_string());
+    });
+    (inspector, rx)
+}
+
+async fn next(events: &mut mpsc::UnboundedReceiver<String>) -> String {
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_inspect_calls() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let (inspector, mut events) = inspector(&pool);
+    let channel = serve(&pool, inspector).await;
+    let mut client = DynamicClient::new(channel.clone());
+
+    let text = r#"user: "alice" password: "hunter2""#;
+    let request = message(&pool, "demo.LoginRequest", text);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    assert_eq!(
+        next(&mut events).await,
+        r#"#0 /demo.Accounts/Login > user: "alice" password: "[REDACTED]""#
+    );
+    assert_eq!(
+        next(&mut events).await,
+        r#"#0 /demo.Accounts/Login < session: "s1""#
+    );
+    assert_eq!(next(&mut events).await, "#0 /demo.Accounts/Login = Ok");
+
+    let request = message(&pool, "demo.LoginRequest", r#"user: "mallory""#);
+    client
+        .unary(&login, Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        next(&mut events).await,
+        r#"#1 /demo.Accounts/Login > user: "mallory""#
+    );
+    assert_eq!(
+        next(&mut events).await,
+        "#1 /demo.Accounts/Login = PermissionDenied: user is locked"
+    );
+
+    // Methods missing from the pool are printed with their sizes.
+    let mut health = HealthClient::new(channel);
+    health.check(HealthCheckRequest::default()).await.unwrap();
+    let check = "/grpc.health.v1.Health/Check";
+    assert_eq!(next(&mut events).await, format!("#2 {check} > <0 bytes>"));
+    assert_eq!(next(&mut events).await, format!("#2 {check} < <2 bytes>"));
+    assert_eq!(next(&mut events).await, format!("#2 {check} = Ok"));
+}
+
+#[tokio::test]
+async fn test_inspect_filter() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let sessions = pool.method("demo.Accounts/Sessions").unwrap();
+    let (inspector, mut events) = inspector(&pool);
+    let inspector = inspector.methods(["demo.Accounts/Sessions"]);
+    let channel = serve(&pool, inspector).await;
+    let mut client = DynamicClient::new(channel);
+
+    let request = message(&pool, "demo.LoginRequest", r#"user: "alice""#);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    let request = message(&pool, "demo.LoginRequest", "");
+    let response = client
+        .server_streaming(&sessions, Request::new(request))
+        .await
+        .unwrap();
+    let _: Vec<_> = response.into_inner().collect().await;
+
+    let mut printed = vec![];
+    for _ in 0..4 {
+        printed.push(next(&mut events).await);
+    }
+    assert_eq!(
+        printed,
+        [
+            "#0 /demo.Accounts/Sessions > {}",
+            r#"#0 /demo.Accounts/Sessions < session: "s1""#,
+            r#"#0 /demo.Accounts/Sessions < session: "s2""#,
+            "#0 /demo.Accounts/Sessions = Ok",
+        ]
+    );
+
+    // Services match all of their methods.
+    let (tx, mut kinds) = mpsc::unbounded_channel();
+    let inspector = Inspector::new(&pool, move |event: InspectEvent| {
+        let _ = tx.send(event.kind);
+    })
+    .methods(["demo.Accounts"]);
+    let channel = serve(&pool, inspector).await;
+    let mut client = DynamicClient::new(channel);
+    let request = message(&pool, "demo.LoginRequest", r#"user: "alice""#);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    let status = loop {
+        let kind = tokio::time::timeout(Duration::from_secs(5), kinds.recv()).await;
+        if let EventKind::Status(code, _) = kind.unwrap().unwrap() {
+            break code;
+        }
+    };
+    assert_eq!(status, Code::Ok);
+}
+
+#[tokio::test]
+async fn test_inspect_rate() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let (inspector, mut events) = inspector(&pool);
+    let channel = serve(&pool, inspector.rate(0.0)).await;
+    let mut client = DynamicClient::new(channel);
+    let request = message(&pool, "demo.LoginRequest", r#"user: "alice""#);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    drop(client);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_inspect_max_message_size() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let (inspector, mut events) = inspector(&pool);
+    let channel = serve(&pool, inspector.max_message_size(8)).await;
+    let mut client = DynamicClient::new(channel);
+
+    // The request is too large to inspect, the response is not.
+    let request = message(&pool, "demo.LoginRequest", r#"user: "alice-in-wonderland""#);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    assert_eq!(
+        next(&mut events).await,
+        "#0 /demo.Accounts/Login > <malformed frame: frame of 21 bytes at offset 0 exceeds \
+         the limit of 8 bytes>"
+    );
+    assert_eq!(
+        next(&mut events).await,
+        r#"#0 /demo.Accounts/Login < session: "s1""#
+    );
+    assert_eq!(next(&mut events).await, "#0 /demo.Accounts/Login = Ok");
+}
@@ -0,0 +1,117 @@
+use protobuf::{
+    descriptor::{
+        field_descriptor_proto::{Label, Type},
+        uninterpreted_option::NamePart,
+        DescriptorProto, FieldDescriptorProto, UninterpretedOption,
+    },
+    well_known_types::struct_::{Struct, Value},
+    MessageFull,
+};
+use tonic_dynamic_protobuf::json;
+
+#[test]
+fn test_print() {
+    let mut field = FieldDescriptorProto::new();
+    field.set_name("id".to_owned());
+    field.set_number(1);
+    field.set_label(Label::LABEL_OPTIONAL);
+    field.set_type(Type::TYPE_UINT64);
+    let mut message = DescriptorProto::new();
+    message.set_name("Quote\"d".to_owned());
+    message.field.push(field);
+    assert_eq!(
+        json::print_to_string(&message),
+        r#"{"name":"Quote\"d","field":[{"name":"id","number":1,"label":"LABEL_OPTIONAL","type":"TYPE_UINT64"}]}"#
+    );
+
+    let mut option = UninterpretedOption::new();
+    let mut part = NamePart::new();
+    part.set_name_part("a".to_owned());
+    part.set_is_extension(false);
+    option.name.push(part);
+    option.set_positive_int_value(u64::MAX);
+    option.set_double_value(f64::NAN);
+    option.set_string_value(b"\x00\xff".to_vec());
+    assert_eq!(
+        json::print_to_string(&option),
+        r#"{"name":[{"namePart":"a","isExtension":false}],"positiveIntValue":"18446744073709551615","doubleValue":"NaN","stringValue":"AP8="}"#
+    );
+}
+
+#[test]
+fn test_print_pretty() {
+    let mut value = Value::new();
+    value.set_number_value(1.5);
+    let mut message = Struct::new();
+    message.fields.insert("b".to_owned(), value.clone());
+    value.set_bool_value(true);
+    message.fields.insert("a".to_owned(), value);
+    assert_eq!(
+        json::print_to_string_pretty(&message),
+        r#"{
+  "fields": {
+    "a": {
+      "boolValue": true
+    },
+    "b": {
+      "numberValue": 1.5
+    }
+  }
+}"#
+    );
+    assert_eq!(json::print_to_string_pretty(&Struct::new()), "{}");
+}
+
+#[test]
+fn test_parse() {
+    let descriptor = UninterpretedOption::descriptor();
+    let message = json::parse_from_str(
+        &descriptor,
+        r#"{"name": [{"name_part": "a", "isExtension": true}], "positiveIntValue": "18446744073709551615",
+            "negativeIntValue": -3, "doubleValue": "-Infinity", "stringValue": "AP8", "aggregateValue": null}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        json::print_to_string(&*message),
+        r#"{"name":[{"namePart":"a","isExtension":true}],"positiveIntValue":"18446744073709551615","negativeIntValue":"-3","doubleValue":"-Infinity","stringValue":"AP8="}"#
+    );
+
+    let mut message = Struct::new();
+    json::merge_from_str(
+        &mut message,
+        r#"{"fields": {"a": {"boolValue": true}, "b": {"nullValue": "NULL_VALUE"}}}"#,
+    )
+    .unwrap();
+    assert!(message.fields["a"].bool_value());
+    assert!(message.fields["b"].has_null_value());
+
+    let mut field = FieldDescriptorProto::new();
+    json::merge_from_str(&mut field, r#"{"label": 3, "type": "TYPE_STRING"}"#).unwrap();
+    assert_eq!(field.label(), Label::LABEL_REPEATED);
+    assert_eq!(field.type_(), Type::TYPE_STRING);
+}
+
+#[test]
+fn test_parse_error() {
+    let descriptor = DescriptorProto::descriptor();
+    let cases = [
+        ("[]", "expected an object"),
+        (
+            r#"{"nam": "a"}"#,
+            "unknown field nam of google.protobuf.DescriptorProto",
+        ),
+        (r#"{"field": {}}"#, "field: expected an array"),
+        (
+            r#"{"field": [{"number": 1.5}]}"#,
+            "field[0].number: invalid i32 value 1.5",
+        ),
+        (
+            r#"{"field": [{"type": "TYPE_FOO"}]}"#,
+            "field[0].type: invalid google.protobuf.FieldDescriptorProto.Type value \"TYPE_FOO\"",
+        ),
+    ];
+    for (input, error) in cases {
+        let e = json::parse_from_str(&descriptor, input).err().unwrap();
+        assert!(e.to_string().starts_with(error), "{}: {}", input, e);
+    }
+}
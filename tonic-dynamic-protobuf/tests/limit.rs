@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use protobuf::{reflect::FileDescriptor, text_format, MessageDyn};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Code, Request, Response,
+};
+use tonic_dynamic_protobuf::{CostLimiter, DescriptorPool, DynamicClient, DynamicServer};
+use tonic_types_protobuf::StatusExt;
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Batch {
+    repeated string items = 1;
+}
+
+message Ack {
+    int64 count = 1;
+}
+
+service Ingest {
+    rpc Put(Batch) returns (Ack);
+    rpc PutStream(stream Batch) returns (Ack);
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("ingest.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .input(dir.path().join("ingest.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let file = FileDescriptor::new_dynamic(fds.file[0].clone(), &[]).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    pool
+}
+
+fn batch(pool: &DescriptorPool, items: usize) -> Box<dyn MessageDyn> {
+    let mut batch = pool.message("demo.Batch").unwrap().new_instance();
+    let text = "items: \"item\" ".repeat(items);
+    text_format::merge_from_str(&mut *batch, &text).unwrap();
+    batch
+}
+
+fn ack(pool: &DescriptorPool, count: usize) -> Box<dyn MessageDyn> {
+    let mut ack = pool.message("demo.Ack").unwrap().new_instance();
+    text_format::merge_from_str(&mut *ack, &format!("count: {count}")).unwrap();
+    ack
+}
+
+/// Serves the `Ingest` service behind `limiter`.
+async fn serve(pool: &DescriptorPool, limiter: CostLimiter) -> Channel {
+    let put = pool.method("demo.Ingest/Put").unwrap();
+    let put_stream = pool.method("demo.Ingest/PutStream").unwrap();
+    let (pool1, pool2) = (pool.clone(), pool.clone());
+    let server = DynamicServer::new()
+        .unary(&put, move |_request| {
+            let pool = pool1.clone();
+            async move { Ok(Response::new(ack(&pool, 1))) }
+        })
+        .client_streaming(&put_stream, move |request| {
+            let pool = pool2.clone();
+            async move {
+                let mut stream = request.into_inner();
+                let mut count = 0;
+                while stream.message().await?.is_some() {
+                    count += 1;
+                }
+                Ok(Response::new(ack(&pool, count)))
+            }
+        });
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(limiter.into_layer())
+            .layer(server.into_layer())
+            .add_service(health)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Charges a unit per item of a batch.
+fn items(pool: &DescriptorPool, rate: u64, burst: u64) -> CostLimiter {
+    CostLimiter::new(rate, burst).cost_fn(pool, |_method, message| {
+        let items = message.descriptor_dyn().field_by_name("items").unwrap();
+        items.get_repeated(message).len() as u64
+    })
+}
+
+#[tokio::test]
+async fn test_cost_fn() {
+    let pool = pool();
+    let put = pool.method("demo.Ingest/Put").unwrap();
+    let limiter = items(&pool, 1, 10);
+    let mut client = DynamicClient::new(serve(&pool, limiter.clone()).await);
+
+    client
+        .unary(&put, Request::new(batch(&pool, 6)))
+        .await
+        .unwrap();
+    assert!(limiter.available() < 5);
+
+    // Not enough budget is left for another large batch.
+    let status = client
+        .unary(&put, Request::new(batch(&pool, 6)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    let delay = status.get_error_details().retry_delay().unwrap();
+    assert!(delay > Duration::from_secs(1), "{delay:?}");
+
+    // A small batch still fits.
+    client
+        .unary(&put, Request::new(batch(&pool, 2)))
+        .await
+        .unwrap();
+
+    // Batches over the burst never fit.
+    let status = client
+        .unary(&put, Request::new(batch(&pool, 20)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.message().contains("more than the limit"), "{status}");
+}
+
+#[tokio::test]
+async fn test_size_cost() {
+    let pool = pool();
+    let put = pool.method("demo.Ingest/Put").unwrap();
+    let size = batch(&pool, 5).compute_size_dyn();
+    let mut client = DynamicClient::new(serve(&pool, CostLimiter::new(1, size + 1)).await);
+
+    client
+        .unary(&put, Request::new(batch(&pool, 5)))
+        .await
+        .unwrap();
+    let status = client
+        .unary(&put, Request::new(batch(&pool, 5)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
+
+#[tokio::test]
+async fn test_streams_are_throttled() {
+    let pool = pool();
+    let put_stream = pool.method("demo.Ingest/PutStream").unwrap();
+    // Every batch but the first waits for 250ms worth of budget.
+    let mut client = DynamicClient::new(serve(&pool, items(&pool, 20, 5)).await);
+
+    let start = Instant::now();
+    let batches: Vec<_> = (0..4).map(|_| batch(&pool, 5)).collect();
+    let ack = client
+        .client_streaming(&put_stream, tokio_stream::iter(batches))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(text_format::print_to_string(&*ack), "count: 4");
+    assert!(start.elapsed() >= Duration::from_millis(700));
+}
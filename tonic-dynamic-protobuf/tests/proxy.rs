@@ -0,0 +1,259 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use bytes::BufMut;
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::{
+    body::BoxBody,
+    client::Grpc,
+    codec::{Codec, EncodeBuf, Encoder},
+    codegen::{http, Body, BoxFuture, BoxStream, Bytes, Service, StdError},
+    metadata::MetadataMap,
+    server::{ClientStreamingService, NamedService, ServerStreamingService},
+    service::interceptor::InterceptedService,
+    transport::{Channel, Server},
+    Code, Request, Response, Status, Streaming,
+};
+use tonic_codec_protobuf::{RawCodec, RawDecoder};
+use tonic_dynamic_protobuf::GrpcProxy;
+use tonic_health_protobuf::pb::{
+    health::{health_check_response::ServingStatus, HealthCheckRequest},
+    health_tonic::health_client::HealthClient,
+};
+use tonic_reflection_protobuf::ReflectionClient;
+
+async fn listen() -> (TcpListenerStream, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    (TcpListenerStream::new(listener), addr)
+}
+
+async fn connect(addr: String) -> Channel {
+    Channel::from_shared(addr).unwrap().connect().await.unwrap()
+}
+
+#[allow(clippy::result_large_err)]
+fn check_token(request: Request<()>) -> Result<Request<()>, Status> {
+    match request.metadata().get("x-token") {
+        Some(token) if token == "secret" => Ok(request),
+        _ => Err(Status::unauthenticated("bad token")),
+    }
+}
+
+#[tokio::test]
+async fn test_proxy() {
+    // The upstream serves health checking behind a token check.
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    let (incoming, upstream_addr) = listen().await;
+    tokio::spawn(
+        Server::builder()
+            .add_service(InterceptedService::new(health, check_token))
+            .serve_with_incoming(incoming),
+    );
+
+    // The proxy serves reflection itself and forwards everything else.
+    let proxy = GrpcProxy::new(connect(upstream_addr).await)
+        .local_service("grpc.reflection.v1.ServerReflection");
+    let reflection = tonic_reflection_protobuf::Builder::configure()
+        .build_v1()
+        .unwrap();
+    let (incoming, proxy_addr) = listen().await;
+    tokio::spawn(
+        Server::builder()
+            .layer(proxy.into_layer())
+            .add_service(reflection)
+            .serve_with_incoming(incoming),
+    );
+    let channel = connect(proxy_addr).await;
+    let mut client = HealthClient::new(channel.clone());
+
+    // Metadata is forwarded.
+    let mut request = Request::new(HealthCheckRequest::new());
+    request
+        .metadata_mut()
+        .insert("x-token", "secret".parse().unwrap());
+    let resp = client.check(request).await.unwrap().into_inner();
+    assert_eq!(resp.status.enum_value(), Ok(ServingStatus::SERVING));
+
+    // Statuses are forwarded.
+    let status = client.check(HealthCheckRequest::new()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    assert_eq!(status.message(), "bad token");
+
+    // Streaming calls are forwarded.
+    let mut request = Request::new(HealthCheckRequest::new());
+    request
+        .metadata_mut()
+        .insert("x-token", "secret".parse().unwrap());
+    let mut stream = client.watch(request).await.unwrap().into_inner();
+    let resp = stream.message().await.unwrap().unwrap();
+    assert_eq!(resp.status.enum_value(), Ok(ServingStatus::SERVING));
+
+    // Local services are not forwarded.
+    let services = ReflectionClient::new(channel)
+        .list_services()
+        .await
+        .unwrap();
+    assert_eq!(services, ["grpc.reflection.v1.ServerReflection"]);
+}
+
+/// Reports the messages of `Collect` calls and how their streams end, and
+/// answers `Trail` calls with trailers.
+#[derive(Clone)]
+struct Upstream {
+    received: mpsc::UnboundedSender<Result<Option<Bytes>, Status>>,
+}
+
+impl ClientStreamingService<Bytes> for Upstream {
+    type Response = Bytes;
+    type Future = BoxFuture<Response<Bytes>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<Bytes>>) -> Self::Future {
+        let received = self.received.clone();
+        Box::pin(async move {
+            let mut messages = request.into_inner();
+            loop {
+                let message = messages.message().await;
+                received.send(message.clone()).unwrap();
+                match message {
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Ok(Response::new(Bytes::new())),
+                    Err(status) => return Err(status),
+                }
+            }
+        })
+    }
+}
+
+impl ServerStreamingService<Bytes> for Upstream {
+    type Response = Bytes;
+    type ResponseStream = BoxStream<Bytes>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, _request: Request<Bytes>) -> Self::Future {
+        Box::pin(async move {
+            let mut trailers = MetadataMap::new();
+            trailers.insert("x-trailer", "done".parse().unwrap());
+            let status = Status::with_metadata(Code::Ok, "", trailers);
+            let messages = tokio_stream::once(Err(status));
+            Ok(Response::new(Box::pin(messages) as BoxStream<Bytes>))
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for Upstream
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let svc = self.clone();
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(RawCodec::default());
+            Ok(match req.uri().path() {
+                "/test.Upstream/Collect" => grpc.client_streaming(svc, req).await,
+                _ => grpc.server_streaming(svc, req).await,
+            })
+        })
+    }
+}
+
+impl NamedService for Upstream {
+    const NAME: &'static str = "test.Upstream";
+}
+
+/// A codec that sends messages, and breaks the request stream on an error.
+struct BreakingCodec;
+
+struct BreakingEncoder;
+
+impl Codec for BreakingCodec {
+    type Encode = Result<Bytes, Status>;
+    type Decode = Bytes;
+    type Encoder = BreakingEncoder;
+    type Decoder = RawDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BreakingEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawDecoder::default()
+    }
+}
+
+impl Encoder for BreakingEncoder {
+    type Item = Result<Bytes, Status>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        buf.put(item?);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_proxy_streams() {
+    let (received, mut upstream_received) = mpsc::unbounded_channel();
+    let (incoming, upstream_addr) = listen().await;
+    tokio::spawn(
+        Server::builder()
+            .add_service(Upstream { received })
+            .serve_with_incoming(incoming),
+    );
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    let proxy = GrpcProxy::new(connect(upstream_addr).await);
+    let (incoming, proxy_addr) = listen().await;
+    tokio::spawn(
+        Server::builder()
+            .layer(proxy.into_layer())
+            .add_service(health)
+            .serve_with_incoming(incoming),
+    );
+    let mut grpc = Grpc::new(connect(proxy_addr).await);
+
+    // Trailers of successful calls are forwarded.
+    grpc.ready().await.unwrap();
+    let response = grpc
+        .server_streaming(
+            Request::new(Ok(Bytes::from_static(b"a"))),
+            "/test.Upstream/Trail".parse().unwrap(),
+            BreakingCodec,
+        )
+        .await
+        .unwrap();
+    let mut messages = response.into_inner();
+    assert_eq!(messages.message().await.unwrap(), None);
+    let trailers = messages.trailers().await.unwrap().unwrap();
+    assert_eq!(trailers.get("x-trailer").unwrap(), "done");
+
+    // A client stream that breaks after a message is not taken for a complete
+    // one by the upstream.
+    let (requests, rx) = mpsc::unbounded_channel();
+    requests.send(Ok(Bytes::from_static(b"a"))).unwrap();
+    grpc.ready().await.unwrap();
+    let call = tokio::spawn(async move {
+        grpc.client_streaming(
+            Request::new(UnboundedReceiverStream::new(rx)),
+            "/test.Upstream/Collect".parse().unwrap(),
+            BreakingCodec,
+        )
+        .await
+    });
+    let message = upstream_received.recv().await.unwrap().unwrap();
+    assert_eq!(message.unwrap(), "a");
+    requests.send(Err(Status::cancelled("broken"))).unwrap();
+    upstream_received.recv().await.unwrap().unwrap_err();
+    call.await.unwrap().unwrap_err();
+}
@@ -0,0 +1,127 @@
+use std::{path::Path, time::Duration};
+
+use protobuf::Message;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Code,
+};
+use tonic_dynamic_protobuf::{frame, Recorder};
+use tonic_health_protobuf::pb::{
+    health::{HealthCheckRequest, HealthCheckResponse},
+    health_tonic::health_client::HealthClient,
+};
+
+/// Serves the health service behind `recorder`.
+async fn serve(recorder: Recorder) -> Channel {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(recorder.into_layer())
+            .add_service(tonic_health_protobuf::health_reporter().1)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Waits until the index of `dir` has `n` status events, and returns all of
+/// its events.
+async fn index(dir: &Path, n: usize) -> Vec<Value> {
+    for _ in 0..100 {
+        let index = std::fs::read_to_string(dir.join("index.jsonl")).unwrap();
+        let events: Vec<Value> = index
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        if events.iter().filter(|e| e["event"] == "status").count() == n {
+            return events;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("calls were not recorded");
+}
+
+#[tokio::test]
+async fn test_record_calls() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Recorder::create(dir.path()).unwrap();
+    let channel = serve(recorder.clone()).await;
+    let mut client = HealthClient::new(channel);
+
+    client.check(HealthCheckRequest::default()).await.unwrap();
+    let mut request = HealthCheckRequest::new();
+    request.service = "missing".to_owned();
+    let status = client.check(request.clone()).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let events = index(dir.path(), 2).await;
+    let check = "/grpc.health.v1.Health/Check";
+    let summary: Vec<_> = events
+        .iter()
+        .map(|e| (e["call"].as_u64().unwrap(), e["event"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (0, "start"),
+            (0, "request"),
+            (0, "response"),
+            (0, "status"),
+            (1, "start"),
+            (1, "request"),
+            (1, "status"),
+        ]
+    );
+    assert_eq!(events[0]["method"], check);
+    assert_eq!(events[0]["capture"], "000000-grpc.health.v1.Health.Check");
+    assert_eq!(events[1]["len"], 0);
+    assert_eq!(events[2]["len"], 2);
+    assert_eq!(events[3]["code"], 0);
+    assert_eq!(events[6]["code"], Code::NotFound as i32);
+    assert!(events[6]["micros"].as_u64().unwrap() >= events[5]["micros"].as_u64().unwrap());
+
+    // The captures are the frames of the calls.
+    let capture = |name: &str| std::fs::read(dir.path().join(name)).unwrap();
+    let responses = capture("000000-grpc.health.v1.Health.Check.responses");
+    let frames: Vec<_> = frame::frames(&responses).map(Result::unwrap).collect();
+    assert_eq!(frames.len(), 1);
+    let response = HealthCheckResponse::parse_from_bytes(frames[0].data).unwrap();
+    assert_eq!(response.status.value(), 1);
+    let requests = capture("000001-grpc.health.v1.Health.Check.requests");
+    let mut encoded = vec![];
+    frame::encode(false, &request.write_to_bytes().unwrap(), &mut encoded).unwrap();
+    assert_eq!(requests, encoded);
+    assert!(capture("000001-grpc.health.v1.Health.Check.responses").is_empty());
+    assert_eq!(recorder.failed(), 0);
+}
+
+#[tokio::test]
+async fn test_record_max_message_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Recorder::create(dir.path()).unwrap().max_message_size(4);
+    let channel = serve(recorder).await;
+    let mut client = HealthClient::new(channel);
+
+    // The request is too large to record, the response is not.
+    let mut request = HealthCheckRequest::new();
+    request.service = "missing".to_owned();
+    let status = client.check(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let events = index(dir.path(), 1).await;
+    assert_eq!(events[1]["event"], "request");
+    assert_eq!(
+        events[1]["error"],
+        "frame of 9 bytes at offset 0 exceeds the limit of 4 bytes"
+    );
+    assert_eq!(events[2]["event"], "status");
+    let capture = dir.path().join("000000-grpc.health.v1.Health.Check.requests");
+    assert!(std::fs::read(capture).unwrap().is_empty());
+}
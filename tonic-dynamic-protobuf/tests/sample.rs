@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use protobuf::{reflect::FileDescriptor, text_format, MessageDyn};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_dynamic_protobuf::{
+    sample::{read_samples, Sample},
+    DescriptorPool, DynamicClient, DynamicServer, SampleWriter, Sampler,
+};
+use tonic_types_protobuf::pb::options;
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+import "tonic_protobuf/options.proto";
+
+message LoginRequest {
+    string user = 1;
+    string password = 2 [(tonic_protobuf.sensitive) = true];
+}
+
+message LoginResponse {
+    string session = 1;
+}
+
+service Accounts {
+    rpc Login(LoginRequest) returns (LoginResponse);
+    rpc Sessions(LoginRequest) returns (stream LoginResponse);
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("accounts.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .include(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tonic-types-protobuf/proto"
+        ))
+        .input(dir.path().join("accounts.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let options = options::file_descriptor().clone();
+    let file = FileDescriptor::new_dynamic(fds.file[0].clone(), &[options]).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    pool
+}
+
+fn message(pool: &DescriptorPool, name: &str, text: &str) -> Box<dyn MessageDyn> {
+    let mut message = pool.message(name).unwrap().new_instance();
+    text_format::merge_from_str(&mut *message, text).unwrap();
+    message
+}
+
+fn parse(pool: &DescriptorPool, name: &str, data: &[u8]) -> String {
+    let message = pool.message(name).unwrap().parse_from_bytes(data).unwrap();
+    text_format::print_to_string(&*message)
+}
+
+/// Serves the `Accounts` service behind `sampler`.
+async fn serve(pool: &DescriptorPool, sampler: Sampler) -> Channel {
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let sessions = pool.method("demo.Accounts/Sessions").unwrap();
+    let (pool1, pool2) = (pool.clone(), pool.clone());
+    let server = DynamicServer::new()
+        .unary(&login, move |request| {
+            let pool = pool1.clone();
+            async move {
+                let text = text_format::print_to_string(&**request.get_ref());
+                if text.contains("mallory") {
+                    return Err(Status::permission_denied("user is locked"));
+                }
+                let session = r#"session: "s1""#;
+                Ok(Response::new(message(&pool, "demo.LoginResponse", session)))
+            }
+        })
+        .server_streaming(&sessions, move |_request| {
+            let pool = pool2.clone();
+            async move {
+                let sessions = ["s1", "s2", "s3"]
+                    .map(|s| message(&pool, "demo.LoginResponse", &format!("session: {s:?}")));
+                let stream = tokio_stream::iter(sessions).map(Ok);
+                Ok(Response::new(Box::pin(stream) as _))
+            }
+        });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(sampler.into_layer())
+            .layer(server.into_layer())
+            .add_service(tonic_health_protobuf::health_reporter().1)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+fn channel_sink() -> (
+    impl Fn(Sample) + Send + Sync + 'static,
+    mpsc::UnboundedReceiver<Sample>,
+) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sink = move |sample| {
+        let _ = tx.send(sample);
+    };
+    (sink, rx)
+}
+
+async fn next(samples: &mut mpsc::UnboundedReceiver<Sample>) -> Sample {
+    tokio::time::timeout(Duration::from_secs(5), samples.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_sample_calls() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let sessions = pool.method("demo.Accounts/Sessions").unwrap();
+    let (sink, mut samples) = channel_sink();
+    let channel = serve(&pool, Sampler::new(&pool, sink).rate(1.0)).await;
+    let mut client = DynamicClient::new(channel);
+
+    let text = r#"user: "alice" password: "hunter2""#;
+    let request = message(&pool, "demo.LoginRequest", text);
+    client.unary(&login, Request::new(request)).await.unwrap();
+
+    let sample = next(&mut samples).await;
+    assert_eq!(sample.method, "/demo.Accounts/Login");
+    assert!(sample.time.is_some());
+    assert_eq!(sample.code, Code::Ok as i32);
+    assert!(!sample.truncated);
+    // Sensitive fields are masked.
+    assert_eq!(sample.requests.len(), 1);
+    assert_eq!(
+        parse(&pool, "demo.LoginRequest", &sample.requests[0]),
+        r#"user: "alice" password: "[REDACTED]""#
+    );
+    assert_eq!(sample.responses.len(), 1);
+
+    // Failed calls are sampled with their status.
+    let request = message(&pool, "demo.LoginRequest", r#"user: "mallory""#);
+    client
+        .unary(&login, Request::new(request))
+        .await
+        .unwrap_err();
+    let sample = next(&mut samples).await;
+    assert_eq!(sample.code, Code::PermissionDenied as i32);
+    assert_eq!(sample.message, "user is locked");
+    assert!(sample.responses.is_empty());
+
+    let request = message(&pool, "demo.LoginRequest", r#"user: "bob""#);
+    let response = client
+        .server_streaming(&sessions, Request::new(request))
+        .await
+        .unwrap();
+    let received: Vec<_> = response.into_inner().collect().await;
+    assert_eq!(received.len(), 3);
+    let sample = next(&mut samples).await;
+    assert_eq!(sample.method, "/demo.Accounts/Sessions");
+    let responses: Vec<_> = sample
+        .responses
+        .iter()
+        .map(|data| parse(&pool, "demo.LoginResponse", data))
+        .collect();
+    assert_eq!(
+        responses,
+        [r#"session: "s1""#, r#"session: "s2""#, r#"session: "s3""#]
+    );
+}
+
+#[tokio::test]
+async fn test_sample_rate_and_limits() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let sessions = pool.method("demo.Accounts/Sessions").unwrap();
+
+    let (sink, mut samples) = channel_sink();
+    let channel = serve(&pool, Sampler::new(&pool, sink).rate(0.0)).await;
+    let mut client = DynamicClient::new(channel);
+    let request = message(&pool, "demo.LoginRequest", r#"user: "alice""#);
+    client.unary(&login, Request::new(request)).await.unwrap();
+    drop(client);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(samples.try_recv().is_err());
+
+    // Keeps the request, and the first response.
+    let (sink, mut samples) = channel_sink();
+    let sampler = Sampler::new(&pool, sink).rate(1.0).max_bytes(16);
+    let channel = serve(&pool, sampler).await;
+    let mut client = DynamicClient::new(channel);
+    let request = message(&pool, "demo.LoginRequest", r#"user: "bob""#);
+    let response = client
+        .server_streaming(&sessions, Request::new(request))
+        .await
+        .unwrap();
+    let _: Vec<_> = response.into_inner().collect().await;
+    let sample = next(&mut samples).await;
+    assert!(sample.truncated);
+    assert_eq!(sample.requests.len(), 1);
+    assert_eq!(sample.responses.len(), 2);
+}
+
+#[tokio::test]
+async fn test_sample_writer() {
+    let pool = pool();
+    let login = pool.method("demo.Accounts/Login").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("samples.bin");
+    let writer = SampleWriter::create(&path).unwrap();
+    let channel = serve(&pool, Sampler::new(&pool, writer).rate(1.0)).await;
+    let mut client = DynamicClient::new(channel);
+
+    for user in ["alice", "bob"] {
+        let text = format!("user: {user:?}");
+        let request = message(&pool, "demo.LoginRequest", &text);
+        client.unary(&login, Request::new(request)).await.unwrap();
+    }
+
+    // Samples are written in the background.
+    let mut samples = vec![];
+    for _ in 0..100 {
+        samples = read_samples(std::fs::File::open(&path).unwrap()).unwrap_or_default();
+        if samples.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let users: Vec<_> = samples
+        .iter()
+        .map(|sample| parse(&pool, "demo.LoginRequest", &sample.requests[0]))
+        .collect();
+    assert_eq!(users, [r#"user: "alice""#, r#"user: "bob""#]);
+}
@@ -0,0 +1,175 @@
+use protobuf::{
+    descriptor::FileDescriptorSet, reflect::ReflectValueBox, EnumFull, Message, MessageDyn,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Response,
+};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicClient, DynamicServer};
+use tonic_health_protobuf::pb::{
+    health::{self, health_check_response::ServingStatus, HealthCheckRequest},
+    health_tonic::health_client::HealthClient,
+};
+use tonic_reflection_protobuf::ReflectionClient;
+
+fn response(status: ServingStatus) -> Box<dyn MessageDyn> {
+    let descriptor = DescriptorPool::new()
+        .add_file(health::file_descriptor())
+        .message("grpc.health.v1.HealthCheckResponse")
+        .unwrap();
+    let mut response = descriptor.new_instance();
+    descriptor
+        .field_by_name("status")
+        .unwrap()
+        .set_singular_field(
+            &mut *response,
+            ReflectValueBox::Enum(ServingStatus::enum_descriptor(), status as i32),
+        );
+    response
+}
+
+async fn serve(server: DynamicServer) -> Channel {
+    let reflection = tonic_reflection_protobuf::Builder::configure()
+        .build_v1()
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(server.into_layer())
+            .add_service(reflection)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_dynamic_server() {
+    let mut pool = DescriptorPool::new();
+    pool.add_file(health::file_descriptor());
+    let check = pool.method("grpc.health.v1.Health/Check").unwrap();
+    let watch = pool.method("grpc.health.v1.Health/Watch").unwrap();
+
+    let server = DynamicServer::new()
+        .unary(&check, |request| async move {
+            let service = request
+                .get_ref()
+                .descriptor_dyn()
+                .field_by_name("service")
+                .unwrap()
+                .get_singular_field_or_default(&**request.get_ref())
+                .to_str()
+                .unwrap()
+                .to_owned();
+            if service.is_empty() {
+                Ok(Response::new(response(ServingStatus::SERVING)))
+            } else {
+                Err(tonic::Status::not_found(service))
+            }
+        })
+        .server_streaming(&watch, |_request| async move {
+            let stream = tokio_stream::iter(vec![
+                Ok(response(ServingStatus::SERVING)),
+                Ok(response(ServingStatus::NOT_SERVING)),
+            ]);
+            Ok(Response::new(Box::pin(stream) as _))
+        });
+    assert_eq!(server.methods().len(), 2);
+    let channel = serve(server).await;
+
+    // Generated clients talk to dynamic handlers.
+    let mut client = HealthClient::new(channel.clone());
+    let resp = client
+        .check(HealthCheckRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(resp.status.enum_value(), Ok(ServingStatus::SERVING));
+
+    let mut request = HealthCheckRequest::new();
+    request.service = "foo.Bar".to_owned();
+    let status = client.check(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    assert_eq!(status.message(), "foo.Bar");
+
+    let mut stream = client
+        .watch(HealthCheckRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    let mut statuses = vec![];
+    while let Some(resp) = stream.message().await.unwrap() {
+        statuses.push(resp.status.enum_value().unwrap());
+    }
+    assert_eq!(
+        statuses,
+        [ServingStatus::SERVING, ServingStatus::NOT_SERVING]
+    );
+
+    // Other calls reach the wrapped services.
+    let services = ReflectionClient::new(channel)
+        .list_services()
+        .await
+        .unwrap();
+    assert_eq!(services, ["grpc.reflection.v1.ServerReflection"]);
+}
+
+#[tokio::test]
+async fn test_scaffold_from_descriptor_set() {
+    // A plugin only receives the encoded descriptors.
+    let mut fds = FileDescriptorSet::new();
+    fds.file.push(health::file_descriptor().proto().clone());
+    let mut pool = DescriptorPool::new();
+    pool.add_encoded_file_descriptor_set(&fds.write_to_bytes().unwrap())
+        .unwrap();
+    assert_eq!(pool.service("grpc.health.v1.Health").len(), 2);
+    assert!(pool.service("grpc.health.v1.Missing").is_empty());
+
+    let check = pool.method("grpc.health.v1.Health.Check").unwrap();
+    let server = DynamicServer::new()
+        .scaffold(&pool, "grpc.health.v1.Health")
+        .unary(&check, |_request| async move {
+            Ok(Response::new(response(ServingStatus::SERVING)))
+        });
+    assert_eq!(server.methods().len(), 2);
+    let channel = serve(server).await;
+
+    let client = DynamicClient::new(channel);
+    assert!(client.service(&pool, "grpc.health.v1.Missing").is_none());
+    let stub = client.service(&pool, "grpc.health.v1.Health").unwrap();
+    let names: Vec<_> = stub.methods().iter().map(|h| h.method().name()).collect();
+    assert_eq!(names, ["Check", "Watch"]);
+
+    let mut check = stub.method("Check").unwrap();
+    let request = check.new_request();
+    let resp = check
+        .unary(tonic::Request::new(request))
+        .await
+        .unwrap()
+        .into_inner();
+    let status = check.method().output().field_by_name("status").unwrap();
+    assert_eq!(
+        status.get_singular_field_or_default(&*resp).to_enum_value(),
+        Some(ServingStatus::SERVING as i32)
+    );
+
+    // Methods without a handler are claimed by the scaffold, not forwarded.
+    let mut watch = stub.method("Watch").unwrap();
+    let request = watch.new_request();
+    let status = watch
+        .server_streaming(tonic::Request::new(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unimplemented);
+    let status = watch
+        .unary(tonic::Request::new(watch.new_request()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
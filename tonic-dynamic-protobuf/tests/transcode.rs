@@ -0,0 +1,215 @@
+use hyper::{body::HttpBody, Body, Method, StatusCode};
+use protobuf::reflect::FileDescriptor;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Response,
+};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicServer, JsonTranscoder};
+use tonic_health_protobuf::pb::{
+    health::{self, health_check_response::ServingStatus, HealthCheckRequest},
+    health_tonic::health_client::HealthClient,
+};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package events;
+
+import "google/protobuf/duration.proto";
+import "google/protobuf/timestamp.proto";
+
+message Event {
+  google.protobuf.Timestamp time = 1;
+  google.protobuf.Duration delay = 2;
+}
+
+service Events {
+  rpc Echo(Event) returns (Event);
+}
+"#;
+
+/// Serves the health service behind a transcoder, and returns its origin.
+async fn serve() -> String {
+    serve_with(|transcoder| transcoder).await
+}
+
+/// Serves the health service behind a transcoder configured by `configure`,
+/// and returns its origin.
+async fn serve_with(configure: impl FnOnce(JsonTranscoder) -> JsonTranscoder) -> String {
+    let (mut reporter, health) = tonic_health_protobuf::health_reporter();
+    reporter
+        .set_service_status("pkg.Down", tonic_health_protobuf::ServingStatus::NotServing)
+        .await;
+    let mut pool = DescriptorPool::new();
+    pool.add_file(health::file_descriptor());
+    let transcoder = configure(JsonTranscoder::new(&pool));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        // Keep the reporter alive while serving.
+        let _reporter = reporter;
+        Server::builder()
+            .accept_http1(true)
+            .layer(transcoder.into_layer())
+            .add_service(health)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+    });
+    format!("http://{addr}")
+}
+
+async fn post(url: String, content_type: &str, body: &str) -> (StatusCode, String, String) {
+    let request = hyper::Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header("content-type", content_type)
+        .body(Body::from(body.to_owned()))
+        .unwrap();
+    let mut response = hyper::Client::new().request(request).await.unwrap();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|v| v.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    let mut body = vec![];
+    while let Some(chunk) = response.data().await {
+        body.extend(chunk.unwrap());
+    }
+    (
+        response.status(),
+        content_type,
+        String::from_utf8(body).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_json_calls() {
+    let origin = serve().await;
+    let check = format!("{origin}/grpc.health.v1.Health/Check");
+
+    let (status, content_type, body) = post(check.clone(), "application/json", "{}").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    assert_eq!(body, r#"{"status": "SERVING"}"#);
+
+    // An empty body is an empty request.
+    let (status, _, body) = post(check.clone(), "application/json", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"status": "SERVING"}"#);
+
+    let (status, _, body) = post(
+        check.clone(),
+        "application/json; charset=utf-8",
+        r#"{"service": "pkg.Down"}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"status": "NOT_SERVING"}"#);
+
+    // Errors are mapped to HTTP statuses.
+    let (status, content_type, body) = post(
+        check.clone(),
+        "application/json",
+        r#"{"service": "pkg.Missing"}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type, "application/json");
+    let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["code"], tonic::Code::NotFound as i32);
+
+    let (status, _, body) = post(check.clone(), "application/json", r#"{"nope": 1}"#).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("nope"), "{body}");
+
+    // Streaming methods are not transcoded, the request reaches the service.
+    let watch = format!("{origin}/grpc.health.v1.Health/Watch");
+    let (_, content_type, _) = post(watch, "application/json", "{}").await;
+    assert_eq!(content_type, "application/grpc");
+}
+
+#[tokio::test]
+async fn test_grpc_calls_pass_through() {
+    let origin = serve().await;
+    let channel = Channel::from_shared(origin)
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let response = HealthClient::new(channel)
+        .check(HealthCheckRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.status.enum_value(), Ok(ServingStatus::SERVING));
+}
+
+#[tokio::test]
+async fn test_request_size_limit() {
+    let origin = serve_with(|transcoder| transcoder.max_request_size(32)).await;
+    let check = format!("{origin}/grpc.health.v1.Health/Check");
+
+    let (status, _, body) = post(check.clone(), "application/json", r#"{"service": ""}"#).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"status": "SERVING"}"#);
+
+    let service = "a".repeat(32);
+    let (status, _, body) = post(
+        check,
+        "application/json",
+        &format!(r#"{{"service": "{service}"}}"#),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["code"], tonic::Code::ResourceExhausted as i32);
+}
+
+#[tokio::test]
+async fn test_well_known_types() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("events.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .input(dir.path().join("events.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let dependencies = [
+        protobuf::well_known_types::duration::file_descriptor().clone(),
+        protobuf::well_known_types::timestamp::file_descriptor().clone(),
+    ];
+    let file = fds.file.iter().find(|f| f.name() == "events.proto").unwrap();
+    let file = FileDescriptor::new_dynamic(file.clone(), &dependencies).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    let echo = pool.method("events.Events/Echo").unwrap();
+    let server = DynamicServer::new().unary(&echo, |request| async move {
+        Ok(Response::new(request.into_inner()))
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    tokio::spawn(
+        Server::builder()
+            .accept_http1(true)
+            .layer(JsonTranscoder::new(&pool).into_layer())
+            .layer(server.into_layer())
+            .add_service(health)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    // Timestamps and durations use their string forms, not plain messages.
+    let (status, _, body) = post(
+        format!("http://{addr}/events.Events/Echo"),
+        "application/json",
+        r#"{"time": "2024-01-02T03:04:05.5Z", "delay": "1.5s"}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(
+        body,
+        r#"{"time": "2024-01-02T03:04:05.500000000Z", "delay": "1.500000000s"}"#
+    );
+}
@@ -0,0 +1,180 @@
+use protobuf::{reflect::FileDescriptor, text_format, MessageDyn};
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{
+    transport::{Channel, Server},
+    Request, Response,
+};
+use tonic_dynamic_protobuf::{
+    usage::FieldStats, DescriptorPool, DynamicClient, DynamicServer, FieldUsage,
+};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Item {
+    string name = 1;
+    int32 legacy_count = 2 [deprecated = true];
+    Item parent = 3;
+}
+
+message PutRequest {
+    string key = 1;
+    string old_key = 2 [deprecated = true];
+    repeated Item items = 3;
+    map<string, string> labels = 4;
+    optional int32 version = 5;
+}
+
+message PutResponse {}
+
+service Store {
+    rpc Put(PutRequest) returns (PutResponse);
+    rpc PutStream(stream PutRequest) returns (PutResponse);
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("store.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .input(dir.path().join("store.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let file = FileDescriptor::new_dynamic(fds.file[0].clone(), &[]).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file(&file);
+    pool
+}
+
+fn request(pool: &DescriptorPool, text: &str) -> Box<dyn MessageDyn> {
+    let mut request = pool.message("demo.PutRequest").unwrap().new_instance();
+    text_format::merge_from_str(&mut *request, text).unwrap();
+    request
+}
+
+/// Serves the `Store` service behind `usage`.
+async fn serve(pool: &DescriptorPool, usage: FieldUsage) -> Channel {
+    let put = pool.method("demo.Store/Put").unwrap();
+    let put_stream = pool.method("demo.Store/PutStream").unwrap();
+    let response = pool.message("demo.PutResponse").unwrap();
+    let response2 = response.clone();
+    let server = DynamicServer::new()
+        .unary(&put, move |_request| {
+            let response = response.new_instance();
+            async move { Ok(Response::new(response)) }
+        })
+        .client_streaming(&put_stream, move |request| {
+            let response = response2.new_instance();
+            async move {
+                let _: Vec<_> = request.into_inner().collect().await;
+                Ok(Response::new(response))
+            }
+        });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(usage.into_layer())
+            .layer(server.into_layer())
+            .add_service(tonic_health_protobuf::health_reporter().1)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+fn stats(count: u64, deprecated: bool) -> FieldStats {
+    FieldStats { count, deprecated }
+}
+
+#[tokio::test]
+async fn test_field_usage() {
+    let pool = pool();
+    let put = pool.method("demo.Store/Put").unwrap();
+    let usage = FieldUsage::new(&pool);
+    let channel = serve(&pool, usage.clone()).await;
+    let mut client = DynamicClient::new(channel);
+
+    let requests = [
+        r#"key: "a" old_key: "b" version: 3"#,
+        r#"key: "a" items { name: "x" } items { parent { legacy_count: 1 } }"#,
+        r#"labels { key: "env" value: "prod" } items { name: "y" }"#,
+    ];
+    for text in requests {
+        let request = request(&pool, text);
+        client.unary(&put, Request::new(request)).await.unwrap();
+    }
+
+    let report = usage.report();
+    assert_eq!(report.keys().collect::<Vec<_>>(), ["/demo.Store/Put"]);
+    let put = &report["/demo.Store/Put"];
+    assert_eq!(put.messages, 3);
+    let fields: Vec<_> = put
+        .fields
+        .iter()
+        .map(|(path, stats)| (path.as_str(), *stats))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("items", stats(2, false)),
+            ("items.legacy_count", stats(0, true)),
+            ("items.name", stats(2, false)),
+            ("items.parent", stats(1, false)),
+            ("items.parent.legacy_count", stats(1, true)),
+            ("key", stats(2, false)),
+            ("labels", stats(1, false)),
+            ("old_key", stats(1, true)),
+            ("version", stats(1, false)),
+        ]
+    );
+    let deprecated: Vec<_> = put.deprecated().collect();
+    assert_eq!(
+        deprecated,
+        [
+            ("items.legacy_count", 0),
+            ("items.parent.legacy_count", 1),
+            ("old_key", 1)
+        ]
+    );
+
+    usage.reset();
+    assert!(usage.report().is_empty());
+}
+
+#[tokio::test]
+async fn test_field_usage_streaming() {
+    let pool = pool();
+    let put_stream = pool.method("demo.Store/PutStream").unwrap();
+    let usage = FieldUsage::new(&pool);
+    let channel = serve(&pool, usage.clone()).await;
+    let mut client = DynamicClient::new(channel);
+
+    let requests = vec![
+        request(&pool, r#"key: "a""#),
+        request(&pool, r#"key: "b" old_key: "c""#),
+        request(&pool, ""),
+    ];
+    client
+        .client_streaming(&put_stream, Request::new(tokio_stream::iter(requests)))
+        .await
+        .unwrap();
+
+    let report = usage.report();
+    let usage = &report["/demo.Store/PutStream"];
+    assert_eq!(usage.messages, 3);
+    assert_eq!(usage.fields["key"], stats(2, false));
+    assert_eq!(usage.fields["old_key"], stats(1, true));
+    assert_eq!(
+        usage.deprecated().map(|(path, _)| path).collect::<Vec<_>>(),
+        ["items.legacy_count", "old_key"]
+    );
+}
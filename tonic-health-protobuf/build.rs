@@ -0,0 +1,34 @@
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("No OUT_DIR defined");
+    let proto = "proto/grpc/health/v1/health.proto";
+
+    // Generate protobuf structs.
+    protobuf_codegen::Codegen::new()
+        .include("proto")
+        .input(proto)
+        .out_dir(&out_dir)
+        .run()
+        .unwrap();
+
+    // Generate tonic service stubs.
+    tonic_build_protobuf::Builder::new()
+        .out_dir(&out_dir)
+        .proto_path("crate::pb")
+        .file_name(|_pkg, svc| format!("{svc}_tonic"))
+        .compile(&[proto], &["proto"]);
+
+    // Generate mod file.
+    let content = r"
+pub mod health;
+pub mod health_tonic;
+";
+    let mod_path = std::path::Path::new(&out_dir).join("mod.rs");
+    let previous_content = std::fs::read(&mod_path);
+    if previous_content
+        .map(|previous_content| previous_content != content.as_bytes())
+        .unwrap_or(true)
+    {
+        std::fs::write(mod_path, content).unwrap();
+    }
+    println!("cargo:rerun-if-changed={proto}");
+}
@@ -0,0 +1,131 @@
+//! Helpers for clients of the `grpc.health.v1.Health` service, e.g. to block
+//! startup until dependencies report `Serving`.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio_stream::{Stream, StreamExt};
+use tonic::{
+    codegen::{Body, Bytes, StdError},
+    Code, Status, Streaming,
+};
+
+use crate::{
+    pb::{
+        health::{
+            health_check_response::ServingStatus as PbServingStatus, HealthCheckRequest,
+            HealthCheckResponse,
+        },
+        health_tonic::health_client::HealthClient,
+    },
+    ServingStatus,
+};
+
+/// The first and the longest delay before watching a service again, after
+/// the health service could not be reached.
+const MIN_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+impl From<PbServingStatus> for ServingStatus {
+    /// Converts a status of the wire, `SERVICE_UNKNOWN` is `Unknown`.
+    fn from(s: PbServingStatus) -> Self {
+        match s {
+            PbServingStatus::SERVING => Self::Serving,
+            PbServingStatus::NOT_SERVING => Self::NotServing,
+            PbServingStatus::UNKNOWN | PbServingStatus::SERVICE_UNKNOWN => Self::Unknown,
+        }
+    }
+}
+
+/// The statuses of a service, as reported by the `Watch` method of the
+/// health service. Returned by [`HealthClient::watch_serving`].
+pub struct ServingStream {
+    inner: Streaming<HealthCheckResponse>,
+}
+
+impl Stream for ServingStream {
+    type Item = Result<ServingStatus, Status>;
+
+    #[allow(clippy::result_large_err)]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx).map(|item| {
+            item.map(|response| {
+                response.map(|r| r.status.enum_value_or(PbServingStatus::UNKNOWN).into())
+            })
+        })
+    }
+}
+
+impl std::fmt::Debug for ServingStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServingStream").finish_non_exhaustive()
+    }
+}
+
+impl<T> HealthClient<T>
+where
+    T: tonic::client::GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Watches the status of `service`, the empty name being the overall
+    /// server. The stream yields the current status first, then every change.
+    pub async fn watch_serving(&mut self, service: &str) -> Result<ServingStream, Status> {
+        let mut request = HealthCheckRequest::new();
+        request.service = service.to_owned();
+        let inner = self.watch(request).await?.into_inner();
+        Ok(ServingStream { inner })
+    }
+
+    /// Waits until `service` reports `Serving`, for at most `timeout`.
+    ///
+    /// The health service is watched again, with backoff, while it can not be
+    /// reached, so lazily connected channels can wait for servers that are
+    /// still starting. Fails with `DEADLINE_EXCEEDED` on timeout, or with the
+    /// error of the health service otherwise, e.g. `UNIMPLEMENTED`.
+    pub async fn wait_for_serving(
+        &mut self,
+        service: &str,
+        timeout: Duration,
+    ) -> Result<(), Status> {
+        let mut last = None;
+        let wait = async {
+            let mut backoff = MIN_BACKOFF;
+            loop {
+                let status = match self.watch_serving(service).await {
+                    Ok(mut stream) => loop {
+                        match stream.next().await {
+                            Some(Ok(ServingStatus::Serving)) => return Ok(()),
+                            Some(Ok(status)) => {
+                                last = Some(status.to_string());
+                                backoff = MIN_BACKOFF;
+                            }
+                            Some(Err(status)) => break status,
+                            None => break Status::unavailable("watch ended"),
+                        }
+                    },
+                    Err(status) => status,
+                };
+                if status.code() != Code::Unavailable {
+                    return Err(status);
+                }
+                last = Some(status.message().to_owned());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "service {:?} is not serving after {:?}, last status: {}",
+                service,
+                timeout,
+                last.as_deref().unwrap_or("none")
+            ))),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! A tonic based gRPC Health Checking implementation for rust-protobuf.
+
+use std::fmt;
+
+pub mod pb {
+    //! Generated types of `grpc.health.v1`.
+
+    include!(concat!(env!("OUT_DIR"), "/mod.rs"));
+}
+
+pub mod client;
+pub mod server;
+
+pub use client::ServingStream;
+pub use server::{health_reporter, HealthReporter, HealthService};
+
+/// An enumeration of values representing gRPC service health.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServingStatus {
+    /// Unknown status
+    Unknown,
+    /// The service is currently up and serving requests.
+    Serving,
+    /// The service is currently down and not serving requests.
+    NotServing,
+}
+
+impl fmt::Display for ServingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServingStatus::Unknown => f.write_str("Unknown"),
+            ServingStatus::Serving => f.write_str("Serving"),
+            ServingStatus::NotServing => f.write_str("NotServing"),
+        }
+    }
+}
+
+impl From<ServingStatus> for pb::health::health_check_response::ServingStatus {
+    fn from(s: ServingStatus) -> Self {
+        match s {
+            ServingStatus::Unknown => Self::UNKNOWN,
+            ServingStatus::Serving => Self::SERVING,
+            ServingStatus::NotServing => Self::NOT_SERVING,
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! The `grpc.health.v1.Health` service and the handle that updates it.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{server::NamedService, Request, Response, Status};
+
+use crate::{
+    pb::{
+        health::{
+            health_check_response::ServingStatus as PbServingStatus, HealthCheckRequest,
+            HealthCheckResponse,
+        },
+        health_tonic::health_server::{Health, HealthServer},
+    },
+    ServingStatus,
+};
+
+/// The status watch of each service, by name. A `None` status means the
+/// service is unknown; entries are kept after clearing so that watchers see
+/// the service coming back.
+type Statuses = Arc<RwLock<HashMap<String, watch::Sender<Option<ServingStatus>>>>>;
+
+/// Creates a `HealthReporter` and a linked `HealthServer` pair. Together,
+/// these types can be used to serve the gRPC Health Checking service.
+///
+/// A `HealthReporter` is used to update the state of gRPC services.
+///
+/// A `HealthServer` is a Tonic gRPC server for the `grpc.health.v1.Health`,
+/// which can be added to a Tonic runtime using `add_service` on the runtime
+/// builder.
+///
+/// The overall server status (the empty service name) is `Serving`.
+pub fn health_reporter() -> (HealthReporter, HealthServer<HealthService>) {
+    let reporter = HealthReporter::new();
+    let service = HealthService::new(reporter.statuses.clone());
+    let server = HealthServer::new(service);
+
+    (reporter, server)
+}
+
+/// A handle providing methods to update the health status of gRPC services.
+/// A `HealthReporter` is connected to a `HealthServer` which serves the
+/// statuses over the `grpc.health.v1.Health` service.
+#[derive(Clone, Debug)]
+pub struct HealthReporter {
+    statuses: Statuses,
+}
+
+impl HealthReporter {
+    fn new() -> Self {
+        // According to the gRPC Health Check specification, the empty service
+        // "" corresponds to the overall server health.
+        let (server_status, _) = watch::channel(Some(ServingStatus::Serving));
+        let statuses = Arc::new(RwLock::new(HashMap::from([(String::new(), server_status)])));
+
+        HealthReporter { statuses }
+    }
+
+    /// Sets the status of the service implemented by `S` to `Serving`. This
+    /// notifies any watchers if there is a change in status.
+    pub async fn set_serving<S>(&mut self)
+    where
+        S: NamedService,
+    {
+        let service_name = <S as NamedService>::NAME;
+        self.set_service_status(service_name, ServingStatus::Serving)
+            .await;
+    }
+
+    /// Sets the status of the service implemented by `S` to `NotServing`.
+    /// This notifies any watchers if there is a change in status.
+    pub async fn set_not_serving<S>(&mut self)
+    where
+        S: NamedService,
+    {
+        let service_name = <S as NamedService>::NAME;
+        self.set_service_status(service_name, ServingStatus::NotServing)
+            .await;
+    }
+
+    /// Sets the status of the service with `service_name` to `status`. This
+    /// notifies any watchers if there is a change in status.
+    pub async fn set_service_status<S>(&mut self, service_name: S, status: ServingStatus)
+    where
+        S: AsRef<str>,
+    {
+        let mut writer = self.statuses.write().await;
+        writer
+            .entry(service_name.as_ref().to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .send_if_modified(|current| {
+                let modified = *current != Some(status);
+                *current = Some(status);
+                modified
+            });
+    }
+
+    /// Clear the status of the given service. Watchers of the service see it
+    /// as `SERVICE_UNKNOWN` from then on.
+    pub async fn clear_service_status(&mut self, service_name: &str) {
+        let reader = self.statuses.read().await;
+        if let Some(tx) = reader.get(service_name) {
+            tx.send_replace(None);
+        }
+    }
+}
+
+/// A service providing implementations of gRPC health checking protocol.
+#[derive(Debug)]
+pub struct HealthService {
+    statuses: Statuses,
+}
+
+impl HealthService {
+    fn new(statuses: Statuses) -> Self {
+        HealthService { statuses }
+    }
+}
+
+fn response(status: Option<ServingStatus>) -> HealthCheckResponse {
+    let mut response = HealthCheckResponse::new();
+    response.status = match status {
+        Some(status) => PbServingStatus::from(status),
+        None => PbServingStatus::SERVICE_UNKNOWN,
+    }
+    .into();
+    response
+}
+
+#[tonic::async_trait]
+impl Health for HealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let reader = self.statuses.read().await;
+        let status = reader
+            .get(request.get_ref().service.as_str())
+            .and_then(|tx| *tx.borrow());
+        match status {
+            Some(status) => Ok(Response::new(response(Some(status)))),
+            None => Err(Status::not_found("service not registered")),
+        }
+    }
+
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        // Unknown services are watched too, they report `SERVICE_UNKNOWN`
+        // until they are registered.
+        let mut receiver = self
+            .statuses
+            .write()
+            .await
+            .entry(request.into_inner().service)
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe();
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            loop {
+                let status = *receiver.borrow_and_update();
+                if tx.send(Ok(response(status))).await.is_err() {
+                    return;
+                }
+                tokio::select! {
+                    changed = receiver.changed() => if changed.is_err() {
+                        return;
+                    },
+                    _ = tx.closed() => return,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::transport::{Channel, Server};
+use tonic_health_protobuf::{
+    health_reporter,
+    pb::{
+        health::{health_check_response::ServingStatus, HealthCheckRequest},
+        health_tonic::health_client::HealthClient,
+    },
+    HealthReporter,
+    ServingStatus::{NotServing, Serving, Unknown},
+};
+
+async fn serve() -> (HealthReporter, HealthClient<Channel>) {
+    let (reporter, service) = health_reporter();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let client = HealthClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap();
+    (reporter, client)
+}
+
+fn request(service: &str) -> HealthCheckRequest {
+    let mut request = HealthCheckRequest::new();
+    request.service = service.to_owned();
+    request
+}
+
+#[tokio::test]
+async fn test_check() {
+    let (mut reporter, mut client) = serve().await;
+
+    let resp = client.check(request("")).await.unwrap().into_inner();
+    assert_eq!(resp.status.enum_value(), Ok(ServingStatus::SERVING));
+
+    let status = client.check(request("foo.Bar")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    reporter
+        .set_service_status("foo.Bar", tonic_health_protobuf::ServingStatus::NotServing)
+        .await;
+    let resp = client.check(request("foo.Bar")).await.unwrap().into_inner();
+    assert_eq!(resp.status.enum_value(), Ok(ServingStatus::NOT_SERVING));
+
+    reporter.clear_service_status("foo.Bar").await;
+    let status = client.check(request("foo.Bar")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+async fn next(
+    stream: &mut tonic::Streaming<tonic_health_protobuf::pb::health::HealthCheckResponse>,
+) -> Result<ServingStatus, i32> {
+    stream.message().await.unwrap().unwrap().status.enum_value()
+}
+
+#[tokio::test]
+async fn test_watch() {
+    let (mut reporter, mut client) = serve().await;
+
+    let mut stream = client.watch(request("foo.Bar")).await.unwrap().into_inner();
+    assert_eq!(next(&mut stream).await, Ok(ServingStatus::SERVICE_UNKNOWN));
+
+    reporter
+        .set_service_status("foo.Bar", tonic_health_protobuf::ServingStatus::Serving)
+        .await;
+    assert_eq!(next(&mut stream).await, Ok(ServingStatus::SERVING));
+
+    reporter
+        .set_service_status("foo.Bar", tonic_health_protobuf::ServingStatus::NotServing)
+        .await;
+    assert_eq!(next(&mut stream).await, Ok(ServingStatus::NOT_SERVING));
+
+    reporter.clear_service_status("foo.Bar").await;
+    assert_eq!(next(&mut stream).await, Ok(ServingStatus::SERVICE_UNKNOWN));
+}
+
+#[tokio::test]
+async fn test_watch_serving() {
+    let (mut reporter, mut client) = serve().await;
+
+    let mut stream = client.watch_serving("foo.Bar").await.unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Unknown);
+    reporter.set_service_status("foo.Bar", Serving).await;
+    assert_eq!(stream.next().await.unwrap().unwrap(), Serving);
+    reporter.set_service_status("foo.Bar", NotServing).await;
+    assert_eq!(stream.next().await.unwrap().unwrap(), NotServing);
+}
+
+#[tokio::test]
+async fn test_wait_for_serving() {
+    let (mut reporter, mut client) = serve().await;
+
+    client
+        .wait_for_serving("", Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    reporter.set_service_status("foo.Bar", NotServing).await;
+    let status = client
+        .wait_for_serving("foo.Bar", Duration::from_millis(200))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+    assert!(status.message().contains("NotServing"), "{status}");
+
+    let mut later = reporter.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        later.set_service_status("foo.Bar", Serving).await;
+    });
+    client
+        .wait_for_serving("foo.Bar", Duration::from_secs(5))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_for_starting_server() {
+    // Reserve a port, and serve on it only later.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect_lazy();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let (_, service) = health_reporter();
+        Server::builder()
+            .add_service(service)
+            .serve(addr)
+            .await
+            .unwrap();
+    });
+
+    HealthClient::new(channel)
+        .wait_for_serving("", Duration::from_secs(5))
+        .await
+        .unwrap();
+}
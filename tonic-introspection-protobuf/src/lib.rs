@@ -0,0 +1,198 @@
+//! A debug service that reports the RPC surface of a running tonic server.
+//!
+//! The `tonic_protobuf.introspection.v1.Introspection` service lists the
+//! registered services, their methods and streaming shapes, and the codec
+//! and codec limits each service is configured with. It is mounted like the
+//! health service, and services are registered through an
+//! [`IntrospectionRegistry`]:
+//!
+//! ```rust,ignore
+//! let (registry, introspection) = tonic_introspection_protobuf::introspection_service();
+//! registry.register(
+//!     ServiceInfo::of::<DebugServer<MyDebug>>(debugpb::file_descriptor())
+//!         .unwrap()
+//!         .codec(debugpb_debug_server::CODEC_PATH)
+//!         .max_decoding_message_size(16 << 20),
+//! );
+//! Server::builder()
+//!     .add_service(introspection)
+//!     .add_service(DebugServer::new(MyDebug).max_decoding_message_size(16 << 20))
+//!     .serve(addr)
+//!     .await?;
+//! ```
+
+pub mod pb {
+    //! Generated types of `tonic_protobuf.introspection.v1`.
+
+    include!(concat!(env!("OUT_DIR"), "/mod.rs"));
+}
+
+pub mod server;
+
+use protobuf::reflect::{FileDescriptor, ServiceDescriptor};
+pub use server::{introspection_service, IntrospectionRegistry, IntrospectionService};
+use tonic::{codec::CompressionEncoding, server::NamedService};
+
+/// A method of a registered service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodInfo {
+    /// The name of the method, e.g. `Get`.
+    pub name: String,
+    /// The fully qualified name of the request message.
+    pub input_type: String,
+    /// The fully qualified name of the response message.
+    pub output_type: String,
+    /// Whether the client streams requests.
+    pub client_streaming: bool,
+    /// Whether the server streams responses.
+    pub server_streaming: bool,
+}
+
+/// The RPC surface of a registered service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    name: String,
+    methods: Vec<MethodInfo>,
+    codec: String,
+    max_decoding_message_size: Option<usize>,
+    max_encoding_message_size: Option<usize>,
+    accept_compressed: Vec<CompressionEncoding>,
+    send_compressed: Vec<CompressionEncoding>,
+}
+
+impl ServiceInfo {
+    /// Create the info of the service `name`, e.g. `debugpb.Debug`, without
+    /// methods.
+    pub fn new(name: impl Into<String>) -> Self {
+        ServiceInfo {
+            name: name.into(),
+            methods: vec![],
+            codec: String::new(),
+            max_decoding_message_size: None,
+            max_encoding_message_size: None,
+            accept_compressed: vec![],
+            send_compressed: vec![],
+        }
+    }
+
+    /// Create the info of the service `name`, with the methods declared in
+    /// `file`. Returns `None` if `file` does not declare the service.
+    pub fn from_file(file: &FileDescriptor, name: &str) -> Option<Self> {
+        let service = file
+            .services()
+            .find(|s| full_name(file.package(), s) == name)?;
+        let mut info = ServiceInfo::new(name);
+        info.methods = service
+            .methods()
+            .map(|method| MethodInfo {
+                name: method.proto().name().to_owned(),
+                input_type: method.input_type().full_name().to_owned(),
+                output_type: method.output_type().full_name().to_owned(),
+                client_streaming: method.proto().client_streaming(),
+                server_streaming: method.proto().server_streaming(),
+            })
+            .collect();
+        Some(info)
+    }
+
+    /// Create the info of the generated service `S`, with the methods
+    /// declared in `file`. Returns `None` if `file` does not declare the
+    /// service.
+    pub fn of<S: NamedService>(file: &FileDescriptor) -> Option<Self> {
+        Self::from_file(file, S::NAME)
+    }
+
+    /// Add a method.
+    #[must_use]
+    pub fn method(mut self, method: MethodInfo) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    /// Set the path of the codec, e.g. the `CODEC_PATH` constant of a
+    /// generated server module.
+    #[must_use]
+    pub fn codec(mut self, codec: impl Into<String>) -> Self {
+        self.codec = codec.into();
+        self
+    }
+
+    /// Set the largest message the service decodes, as configured with
+    /// `max_decoding_message_size` on its server.
+    #[must_use]
+    pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+        self.max_decoding_message_size = Some(limit);
+        self
+    }
+
+    /// Set the largest message the service encodes, as configured with
+    /// `max_encoding_message_size` on its server.
+    #[must_use]
+    pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+        self.max_encoding_message_size = Some(limit);
+        self
+    }
+
+    /// Add a compression encoding accepted for requests, as configured with
+    /// `accept_compressed` on its server.
+    #[must_use]
+    pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        self.accept_compressed.push(encoding);
+        self
+    }
+
+    /// Add a compression encoding of responses, as configured with
+    /// `send_compressed` on its server.
+    #[must_use]
+    pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        self.send_compressed.push(encoding);
+        self
+    }
+
+    /// Returns the fully qualified name of the service.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the methods of the service.
+    pub fn methods(&self) -> &[MethodInfo] {
+        &self.methods
+    }
+
+    pub(crate) fn to_proto(&self) -> pb::introspection::Service {
+        let mut service = pb::introspection::Service::new();
+        service.name = self.name.clone();
+        service.codec = self.codec.clone();
+        for info in &self.methods {
+            let mut method = pb::introspection::Method::new();
+            method.name = info.name.clone();
+            method.path = format!("/{}/{}", self.name, info.name);
+            method.input_type = info.input_type.clone();
+            method.output_type = info.output_type.clone();
+            method.client_streaming = info.client_streaming;
+            method.server_streaming = info.server_streaming;
+            service.methods.push(method);
+        }
+        let limits = service.limits.mut_or_insert_default();
+        limits.max_decoding_message_size = self.max_decoding_message_size.map(|l| l as u64);
+        limits.max_encoding_message_size = self.max_encoding_message_size.map(|l| l as u64);
+        limits.accept_compressed = self.accept_compressed.iter().map(encoding_name).collect();
+        limits.send_compressed = self.send_compressed.iter().map(encoding_name).collect();
+        service
+    }
+}
+
+fn full_name(package: &str, service: &ServiceDescriptor) -> String {
+    if package.is_empty() {
+        service.proto().name().to_owned()
+    } else {
+        format!("{}.{}", package, service.proto().name())
+    }
+}
+
+/// Returns the name of `encoding` in the `grpc-encoding` header, e.g. `gzip`.
+fn encoding_name(encoding: &CompressionEncoding) -> String {
+    // tonic does not expose the header values, but the variants are named
+    // after them.
+    format!("{:?}", encoding).to_lowercase()
+}
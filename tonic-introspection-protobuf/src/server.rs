@@ -0,0 +1,99 @@
+//! The `tonic_protobuf.introspection.v1.Introspection` service and the
+//! registry of the services it reports.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use tonic::{Request, Response, Status};
+
+use crate::{
+    pb::{
+        introspection::{self, ListServicesRequest, ListServicesResponse},
+        introspection_tonic::introspection_server::{self, Introspection, IntrospectionServer},
+    },
+    ServiceInfo,
+};
+
+type Services = Arc<RwLock<BTreeMap<String, ServiceInfo>>>;
+
+/// Creates an `IntrospectionRegistry` and a linked `IntrospectionServer`
+/// pair.
+///
+/// Services registered with the `IntrospectionRegistry` are listed by the
+/// `IntrospectionServer`, which can be added to a tonic router with
+/// `add_service`. The introspection service registers itself.
+pub fn introspection_service() -> (
+    IntrospectionRegistry,
+    IntrospectionServer<IntrospectionService>,
+) {
+    let registry = IntrospectionRegistry {
+        services: Services::default(),
+    };
+    registry.register(
+        ServiceInfo::from_file(
+            introspection::file_descriptor(),
+            introspection_server::SERVICE_NAME,
+        )
+        .expect("introspection.proto declares the service")
+        .codec(introspection_server::CODEC_PATH),
+    );
+    let service = IntrospectionService {
+        services: registry.services.clone(),
+    };
+
+    (registry, IntrospectionServer::new(service))
+}
+
+/// A handle to register the services listed by an `IntrospectionServer`.
+#[derive(Clone, Debug)]
+pub struct IntrospectionRegistry {
+    services: Services,
+}
+
+impl IntrospectionRegistry {
+    /// Registers a service, replacing a previously registered service of
+    /// the same name.
+    pub fn register(&self, service: ServiceInfo) {
+        self.services
+            .write()
+            .unwrap()
+            .insert(service.name().to_owned(), service);
+    }
+
+    /// Removes the service `name`. Returns whether it was registered.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.services.write().unwrap().remove(name).is_some()
+    }
+
+    /// Returns the registered services, sorted by name.
+    pub fn services(&self) -> Vec<ServiceInfo> {
+        self.services.read().unwrap().values().cloned().collect()
+    }
+}
+
+/// A service providing the implementation of the
+/// `tonic_protobuf.introspection.v1.Introspection` service.
+#[derive(Debug)]
+pub struct IntrospectionService {
+    services: Services,
+}
+
+#[tonic::async_trait]
+impl Introspection for IntrospectionService {
+    async fn list_services(
+        &self,
+        _request: Request<ListServicesRequest>,
+    ) -> Result<Response<ListServicesResponse>, Status> {
+        let mut response = ListServicesResponse::new();
+        response.services = self
+            .services
+            .read()
+            .unwrap()
+            .values()
+            .map(ServiceInfo::to_proto)
+            .collect();
+        Ok(Response::new(response))
+    }
+}
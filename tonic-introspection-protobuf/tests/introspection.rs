@@ -0,0 +1,131 @@
+use protobuf::reflect::FileDescriptor;
+use tonic::codec::CompressionEncoding;
+use tonic_health_protobuf::{
+    pb::{health, health_tonic::health_server},
+    HealthService,
+};
+use tonic_introspection_protobuf::{
+    introspection_service,
+    pb::{
+        introspection::ListServicesRequest,
+        introspection_tonic::introspection_client::IntrospectionClient,
+    },
+    MethodInfo, ServiceInfo,
+};
+use tonic_testing_protobuf::duplex;
+
+fn health_file() -> FileDescriptor {
+    health::file_descriptor().clone()
+}
+
+#[tokio::test]
+async fn test_list_services() {
+    let (registry, introspection) = introspection_service();
+    registry.register(
+        ServiceInfo::of::<health_server::HealthServer<HealthService>>(&health_file())
+            .unwrap()
+            .codec(health_server::CODEC_PATH)
+            .max_decoding_message_size(1024)
+            .accept_compressed(CompressionEncoding::Gzip),
+    );
+    registry.register(ServiceInfo::new("demo.Empty"));
+
+    let mut client = duplex::client(introspection, IntrospectionClient::new).await;
+    let services = client
+        .list_services(ListServicesRequest::new())
+        .await
+        .unwrap()
+        .into_inner()
+        .services;
+
+    let names: Vec<_> = services.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "demo.Empty",
+            "grpc.health.v1.Health",
+            "tonic_protobuf.introspection.v1.Introspection"
+        ]
+    );
+
+    let health = &services[1];
+    assert_eq!(health.codec, "::tonic_codec_protobuf::ProtobufCodecV3");
+    let methods: Vec<_> = health
+        .methods
+        .iter()
+        .map(|m| {
+            (
+                m.path.as_str(),
+                m.input_type.as_str(),
+                m.client_streaming,
+                m.server_streaming,
+            )
+        })
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            (
+                "/grpc.health.v1.Health/Check",
+                "grpc.health.v1.HealthCheckRequest",
+                false,
+                false
+            ),
+            (
+                "/grpc.health.v1.Health/Watch",
+                "grpc.health.v1.HealthCheckRequest",
+                false,
+                true
+            ),
+        ]
+    );
+    assert_eq!(health.limits.max_decoding_message_size, Some(1024));
+    assert_eq!(health.limits.max_encoding_message_size, None);
+    assert_eq!(health.limits.accept_compressed, ["gzip"]);
+    assert!(health.limits.send_compressed.is_empty());
+
+    let introspection = &services[2];
+    assert_eq!(introspection.methods.len(), 1);
+    assert_eq!(
+        introspection.methods[0].path,
+        "/tonic_protobuf.introspection.v1.Introspection/ListServices"
+    );
+}
+
+#[tokio::test]
+async fn test_unregister() {
+    let (registry, introspection) = introspection_service();
+    registry.register(ServiceInfo::new("demo.Echo").method(MethodInfo {
+        name: "Echo".to_owned(),
+        input_type: "demo.Message".to_owned(),
+        output_type: "demo.Message".to_owned(),
+        client_streaming: true,
+        server_streaming: true,
+    }));
+    let mut client = duplex::client(introspection, IntrospectionClient::new).await;
+    let services = client
+        .list_services(ListServicesRequest::new())
+        .await
+        .unwrap()
+        .into_inner()
+        .services;
+    assert_eq!(services[0].name, "demo.Echo");
+    assert_eq!(services[0].methods[0].path, "/demo.Echo/Echo");
+    assert!(services[0].methods[0].client_streaming);
+
+    assert!(registry.unregister("demo.Echo"));
+    assert!(!registry.unregister("demo.Echo"));
+    let services = client
+        .list_services(ListServicesRequest::new())
+        .await
+        .unwrap()
+        .into_inner()
+        .services;
+    assert_eq!(services.len(), 1);
+    assert_eq!(registry.services().len(), 1);
+}
+
+#[test]
+fn test_unknown_service() {
+    assert!(ServiceInfo::from_file(&health_file(), "grpc.health.v1.Unknown").is_none());
+}
@@ -0,0 +1,53 @@
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("No OUT_DIR defined");
+    for version in ["v1", "v1alpha"] {
+        generate(
+            &format!("{out_dir}/{version}"),
+            &format!("proto/grpc/reflection/{version}/reflection.proto"),
+            &format!("crate::pb::{version}"),
+            r"
+pub mod reflection;
+pub mod server_reflection_tonic;
+",
+        );
+    }
+    generate(
+        &format!("{out_dir}/schema"),
+        "proto/tonic_protobuf/schema/v1/schema.proto",
+        "crate::pb::schema",
+        r"
+pub mod schema;
+pub mod schema_tonic;
+",
+    );
+}
+
+fn generate(out_dir: &str, proto: &str, proto_path: &str, mod_content: &str) {
+    std::fs::create_dir_all(out_dir).unwrap();
+
+    // Generate protobuf structs.
+    protobuf_codegen::Codegen::new()
+        .include("proto")
+        .input(proto)
+        .out_dir(out_dir)
+        .run()
+        .unwrap();
+
+    // Generate tonic service stubs.
+    tonic_build_protobuf::Builder::new()
+        .out_dir(out_dir)
+        .proto_path(proto_path)
+        .file_name(|_pkg, svc| format!("{svc}_tonic"))
+        .compile(&[proto], &["proto"]);
+
+    // Generate mod file.
+    let mod_path = std::path::Path::new(out_dir).join("mod.rs");
+    let previous_content = std::fs::read(&mod_path);
+    if previous_content
+        .map(|previous_content| previous_content != mod_content.as_bytes())
+        .unwrap_or(true)
+    {
+        std::fs::write(mod_path, mod_content).unwrap();
+    }
+    println!("cargo:rerun-if-changed={proto}");
+}
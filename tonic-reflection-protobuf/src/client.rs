@@ -0,0 +1,221 @@
+//! A client of the gRPC Server Reflection service.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+use protobuf::{
+    descriptor::{FileDescriptorProto, FileDescriptorSet},
+    reflect::FileDescriptor,
+    Message,
+};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::pb::v1::{
+    reflection::{
+        server_reflection_request::Message_request as MessageRequest,
+        server_reflection_response::Message_response as MessageResponse, ServerReflectionRequest,
+    },
+    server_reflection_tonic::server_reflection_client::ServerReflectionClient,
+};
+
+/// Represents an error returned by [`ReflectionClient`].
+#[derive(Debug)]
+pub enum ClientError {
+    /// The connection to the server failed.
+    Transport(tonic::transport::Error),
+    /// The reflection call failed.
+    Status(Box<tonic::Status>),
+    /// The server replied with an `ErrorResponse`.
+    Server { code: tonic::Code, message: String },
+    /// The server closed the stream without replying.
+    NoResponse,
+    /// The server replied with a response of the wrong kind.
+    UnexpectedResponse,
+    /// A file descriptor sent by the server can not be decoded or built.
+    Descriptor(protobuf::Error),
+}
+
+impl From<tonic::transport::Error> for ClientError {
+    fn from(e: tonic::transport::Error) -> Self {
+        ClientError::Transport(e)
+    }
+}
+
+impl From<tonic::Status> for ClientError {
+    fn from(e: tonic::Status) -> Self {
+        ClientError::Status(Box::new(e))
+    }
+}
+
+impl From<protobuf::Error> for ClientError {
+    fn from(e: protobuf::Error) -> Self {
+        ClientError::Descriptor(e)
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "transport error: {}", e),
+            ClientError::Status(s) => write!(f, "reflection call failed: {}", s),
+            ClientError::Server { code, message } => {
+                write!(f, "server error: {:?}: {}", code, message)
+            }
+            ClientError::NoResponse => write!(f, "server closed the stream without a response"),
+            ClientError::UnexpectedResponse => write!(f, "unexpected response from server"),
+            ClientError::Descriptor(e) => write!(f, "invalid file descriptor: {}", e),
+        }
+    }
+}
+
+/// A client that queries the `grpc.reflection.v1` service of a remote server
+/// and materializes rust-protobuf descriptors from its replies.
+#[derive(Debug, Clone)]
+pub struct ReflectionClient {
+    inner: ServerReflectionClient<Channel>,
+    host: String,
+}
+
+impl ReflectionClient {
+    /// Connect to the server at `dst`.
+    pub async fn connect<D>(dst: D) -> Result<Self, ClientError>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let channel = Endpoint::new(dst)?.connect().await?;
+        Ok(Self::new(channel))
+    }
+
+    /// Create a client that uses an existing channel.
+    pub fn new(channel: Channel) -> Self {
+        ReflectionClient {
+            inner: ServerReflectionClient::new(channel),
+            host: String::new(),
+        }
+    }
+
+    /// Set the `host` sent with every request, for servers that serve
+    /// several virtual hosts.
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Returns the fully-qualified names of the services the server
+    /// advertises.
+    pub async fn list_services(&mut self) -> Result<Vec<String>, ClientError> {
+        match self
+            .call(MessageRequest::ListServices(String::new()))
+            .await?
+        {
+            MessageResponse::ListServicesResponse(resp) => {
+                Ok(resp.service.into_iter().map(|s| s.name).collect())
+            }
+            _ => Err(ClientError::UnexpectedResponse),
+        }
+    }
+
+    /// Returns the file named `filename`, followed by the dependencies the
+    /// server chose to send along with it.
+    pub async fn file_by_filename(
+        &mut self,
+        filename: &str,
+    ) -> Result<Vec<FileDescriptorProto>, ClientError> {
+        self.files(MessageRequest::FileByFilename(filename.to_owned()))
+            .await
+    }
+
+    /// Returns the file that defines the fully-qualified `symbol`, followed
+    /// by the dependencies the server chose to send along with it.
+    pub async fn file_containing_symbol(
+        &mut self,
+        symbol: &str,
+    ) -> Result<Vec<FileDescriptorProto>, ClientError> {
+        self.files(MessageRequest::FileContainingSymbol(symbol.to_owned()))
+            .await
+    }
+
+    /// Returns the file that defines `symbol` together with all of its
+    /// transitive dependencies, fetching any the server did not send.
+    pub async fn file_descriptor_set(
+        &mut self,
+        symbol: &str,
+    ) -> Result<FileDescriptorSet, ClientError> {
+        let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+        let mut order = vec![];
+        let mut queue = VecDeque::new();
+        for file in self.file_containing_symbol(symbol).await? {
+            queue.extend(file.dependency.iter().cloned());
+            order.push(file.name().to_owned());
+            files.insert(file.name().to_owned(), file);
+        }
+        while let Some(name) = queue.pop_front() {
+            if files.contains_key(&name) {
+                continue;
+            }
+            for file in self.file_by_filename(&name).await? {
+                if files.contains_key(file.name()) {
+                    continue;
+                }
+                queue.extend(file.dependency.iter().cloned());
+                order.push(file.name().to_owned());
+                files.insert(file.name().to_owned(), file);
+            }
+        }
+
+        let mut fds = FileDescriptorSet::new();
+        fds.file = order
+            .iter()
+            .map(|name| files.remove(name).unwrap())
+            .collect();
+        Ok(fds)
+    }
+
+    /// Returns a dynamic rust-protobuf descriptor of the file that defines
+    /// `symbol`, which can be used to look up its messages and services.
+    pub async fn file_descriptor(&mut self, symbol: &str) -> Result<FileDescriptor, ClientError> {
+        let fds = self.file_descriptor_set(symbol).await?;
+        let name = fds.file[0].name().to_owned();
+        let files = FileDescriptor::new_dynamic_fds(fds.file, &[])?;
+        Ok(files.into_iter().find(|f| f.name() == name).unwrap())
+    }
+
+    async fn files(
+        &mut self,
+        request: MessageRequest,
+    ) -> Result<Vec<FileDescriptorProto>, ClientError> {
+        match self.call(request).await? {
+            MessageResponse::FileDescriptorResponse(resp) => resp
+                .file_descriptor_proto
+                .iter()
+                .map(|bytes| Ok(FileDescriptorProto::parse_from_bytes(bytes)?))
+                .collect(),
+            _ => Err(ClientError::UnexpectedResponse),
+        }
+    }
+
+    async fn call(&mut self, request: MessageRequest) -> Result<MessageResponse, ClientError> {
+        let mut req = ServerReflectionRequest::new();
+        req.host = self.host.clone();
+        req.message_request = Some(request);
+        let mut responses = self
+            .inner
+            .server_reflection_info(tokio_stream::once(req))
+            .await?
+            .into_inner();
+        let response = responses.message().await?.ok_or(ClientError::NoResponse)?;
+        match response.message_response {
+            Some(MessageResponse::ErrorResponse(e)) => Err(ClientError::Server {
+                code: tonic::Code::from_i32(e.error_code),
+                message: e.error_message,
+            }),
+            Some(resp) => Ok(resp),
+            None => Err(ClientError::UnexpectedResponse),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! A tonic based gRPC Server Reflection implementation for rust-protobuf.
+//!
+//! The crate also serves the registered descriptors through
+//! `tonic_protobuf.schema.v1.Schema`, see [`schema`].
+
+pub mod pb {
+    //! Generated types of the reflection protocol.
+
+    /// `grpc.reflection.v1`.
+    pub mod v1 {
+        include!(concat!(env!("OUT_DIR"), "/v1/mod.rs"));
+    }
+
+    /// `grpc.reflection.v1alpha`.
+    pub mod v1alpha {
+        include!(concat!(env!("OUT_DIR"), "/v1alpha/mod.rs"));
+    }
+
+    /// `tonic_protobuf.schema.v1`.
+    pub mod schema {
+        include!(concat!(env!("OUT_DIR"), "/schema/mod.rs"));
+    }
+}
+
+pub mod client;
+pub mod merge;
+pub mod schema;
+pub mod server;
+
+pub use client::{ClientError, ReflectionClient};
+pub use schema::{SchemaClient, SchemaService};
+pub use server::{Builder, Error, ReflectionService};
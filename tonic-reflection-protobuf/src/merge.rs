@@ -0,0 +1,202 @@
+//! Merging of descriptor sets of several code generators.
+//!
+//! A server may host services generated by prost-build next to services
+//! generated by this crate. prost-build writes the descriptors of its files
+//! to a `FileDescriptorSet`, e.g. with `file_descriptor_set_path`, while the
+//! descriptors of rust-protobuf come from the generated `file_descriptor()`
+//! functions. [`merge_file_descriptor_sets`] merges both into one set, so
+//! that reflection presents a single, complete schema to tooling:
+//!
+//! ```rust,ignore
+//! let merged = merge::merge_file_descriptor_sets([
+//!     FileDescriptorSet::parse_from_bytes(greeter::FILE_DESCRIPTOR_SET)?,
+//!     merge::file_descriptor_set(debugpb::file_descriptor()),
+//! ])?;
+//! let reflection = Builder::configure()
+//!     .register_file_descriptor_set(merged)
+//!     .build_v1()?;
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use protobuf::{
+    descriptor::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet},
+    reflect::FileDescriptor,
+};
+
+use crate::Error;
+
+/// Returns a set of `file_descriptor`, e.g. the one returned by
+/// `file_descriptor()` of a generated module, and of all of its
+/// dependencies.
+pub fn file_descriptor_set(file_descriptor: &FileDescriptor) -> FileDescriptorSet {
+    let mut fds = FileDescriptorSet::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([file_descriptor.clone()]);
+    while let Some(fd) = queue.pop_front() {
+        if seen.insert(fd.proto().name().to_owned()) {
+            fds.file.push(fd.proto().clone());
+            queue.extend(fd.deps().iter().cloned());
+        }
+    }
+    fds
+}
+
+/// Merges `sets` into one set with every file once, in the order they first
+/// appear.
+///
+/// Both generators usually carry some of the same files, e.g. the
+/// well-known types, possibly from different protobuf releases. Of the
+/// descriptors of a file, the one that defines all declarations of the
+/// others, with the same field numbers, is kept, e.g. of the newer release,
+/// and the first one if they declare the same. Comments are kept from the
+/// descriptors of prost-build, which carry them.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidFileDescriptorSet`] if descriptors of the same
+/// file declare incompatible definitions, if a symbol is defined by two
+/// files, e.g. by the same .proto file compiled under different paths, or if
+/// a file imports one that is in none of the sets.
+pub fn merge_file_descriptor_sets<I>(sets: I) -> Result<FileDescriptorSet, Error>
+where
+    I: IntoIterator<Item = FileDescriptorSet>,
+{
+    let mut files: Vec<FileDescriptorProto> = vec![];
+    let mut by_name = HashMap::new();
+    for set in sets {
+        for file in set.file {
+            if file.name.is_none() {
+                return Err(Error::InvalidFileDescriptorSet("missing name".to_owned()));
+            }
+            match by_name.get(file.name()) {
+                Some(&i) => {
+                    let kept = std::mem::take(&mut files[i]);
+                    files[i] = merge_file(kept, file)?;
+                }
+                None => {
+                    by_name.insert(file.name().to_owned(), files.len());
+                    files.push(file);
+                }
+            }
+        }
+    }
+
+    let mut defined_by = HashMap::new();
+    for file in &files {
+        for symbol in definitions(file).into_keys() {
+            if let Some(other) = defined_by.insert(symbol.clone(), file.name()) {
+                return Err(Error::InvalidFileDescriptorSet(format!(
+                    "{} is defined by both {} and {}",
+                    symbol,
+                    other,
+                    file.name()
+                )));
+            }
+        }
+        if let Some(missing) = file.dependency.iter().find(|d| !by_name.contains_key(*d)) {
+            return Err(Error::InvalidFileDescriptorSet(format!(
+                "{} imports {}, which is in none of the sets",
+                file.name(),
+                missing
+            )));
+        }
+    }
+
+    let mut merged = FileDescriptorSet::new();
+    merged.file = files;
+    Ok(merged)
+}
+
+/// Merges two descriptors of the same file.
+fn merge_file(
+    first: FileDescriptorProto,
+    second: FileDescriptorProto,
+) -> Result<FileDescriptorProto, Error> {
+    let (first_definitions, second_definitions) = (definitions(&first), definitions(&second));
+    let contains = |all: &BTreeMap<_, _>, some: &BTreeMap<_, _>| {
+        some.iter()
+            .all(|(symbol, number)| all.get(symbol) == Some(number))
+    };
+    let (mut kept, other) = if contains(&first_definitions, &second_definitions) {
+        (first, second)
+    } else if contains(&second_definitions, &first_definitions) {
+        (second, first)
+    } else {
+        return Err(Error::InvalidFileDescriptorSet(format!(
+            "{} is registered with incompatible definitions",
+            first.name()
+        )));
+    };
+    // Source locations only apply to the exact same declarations.
+    if kept.source_code_info.is_none() && other.source_code_info.is_some() {
+        let mut stripped = other.clone();
+        stripped.source_code_info.clear();
+        if stripped == kept {
+            kept.source_code_info = other.source_code_info;
+        }
+    }
+    Ok(kept)
+}
+
+/// Returns the fully-qualified symbols that `file` defines, with the numbers
+/// of fields and enum values.
+fn definitions(file: &FileDescriptorProto) -> BTreeMap<String, Option<i32>> {
+    let mut definitions = BTreeMap::new();
+    let package = file.package();
+    for message in &file.message_type {
+        message_definitions(package, message, &mut definitions);
+    }
+    for en in &file.enum_type {
+        enum_definitions(package, en, &mut definitions);
+    }
+    for service in &file.service {
+        let service_name = qualify(package, service.name());
+        for method in &service.method {
+            definitions.insert(qualify(&service_name, method.name()), None);
+        }
+        definitions.insert(service_name, None);
+    }
+    for extension in &file.extension {
+        definitions.insert(qualify(package, extension.name()), Some(extension.number()));
+    }
+    definitions
+}
+
+fn message_definitions(
+    prefix: &str,
+    message: &DescriptorProto,
+    definitions: &mut BTreeMap<String, Option<i32>>,
+) {
+    let message_name = qualify(prefix, message.name());
+    for nested in &message.nested_type {
+        message_definitions(&message_name, nested, definitions);
+    }
+    for en in &message.enum_type {
+        enum_definitions(&message_name, en, definitions);
+    }
+    for field in message.field.iter().chain(&message.extension) {
+        definitions.insert(qualify(&message_name, field.name()), Some(field.number()));
+    }
+    definitions.insert(message_name, None);
+}
+
+fn enum_definitions(
+    prefix: &str,
+    en: &EnumDescriptorProto,
+    definitions: &mut BTreeMap<String, Option<i32>>,
+) {
+    let enum_name = qualify(prefix, en.name());
+    for value in &en.value {
+        definitions.insert(qualify(&enum_name, value.name()), Some(value.number()));
+    }
+    definitions.insert(enum_name, None);
+}
+
+fn qualify(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
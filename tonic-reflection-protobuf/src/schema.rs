@@ -0,0 +1,163 @@
+//! A schema registry style endpoint, `tonic_protobuf.schema.v1.Schema`.
+//!
+//! [`SchemaService`] serves the files registered with a [`Builder`] as one
+//! encoded `FileDescriptorSet`, together with a version that changes whenever
+//! the set does. Unlike server reflection, clients fetch the whole schema in a
+//! single call, and [`SchemaClient`] only fetches it again once its version
+//! changed.
+//!
+//! [`Builder`]: crate::Builder
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use protobuf::{
+    descriptor::{FileDescriptorProto, FileDescriptorSet},
+    Message,
+};
+use tonic::{
+    transport::{Channel, Endpoint},
+    Request, Response, Status,
+};
+
+use crate::{
+    pb::schema::{
+        schema::{GetSchemaRequest, GetSchemaResponse},
+        schema_tonic::{schema_client, schema_server},
+    },
+    ClientError, Error,
+};
+
+/// The `tonic_protobuf.schema.v1.Schema` service, built by
+/// [`Builder::build_schema`](crate::Builder::build_schema).
+#[derive(Debug, Clone)]
+pub struct SchemaService {
+    version: String,
+    file_descriptor_set: Arc<Vec<u8>>,
+}
+
+impl SchemaService {
+    /// Encodes `files` with every file after its dependencies, in an order
+    /// that only depends on their contents.
+    pub(crate) fn new(files: &HashMap<String, Arc<FileDescriptorProto>>) -> Result<Self, Error> {
+        let mut names: Vec<_> = files.keys().collect();
+        names.sort();
+
+        let mut fds = FileDescriptorSet::new();
+        let mut seen = HashSet::new();
+        for name in names {
+            push_with_dependencies(files, name, &mut seen, &mut fds);
+        }
+        let encoded = fds
+            .write_to_bytes()
+            .map_err(|e| Error::InvalidFileDescriptorSet(e.to_string()))?;
+
+        Ok(SchemaService {
+            version: format!("{:016x}", fnv1a(&encoded)),
+            file_descriptor_set: Arc::new(encoded),
+        })
+    }
+
+    /// Returns the version of the served schema.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+fn push_with_dependencies(
+    files: &HashMap<String, Arc<FileDescriptorProto>>,
+    name: &str,
+    seen: &mut HashSet<String>,
+    fds: &mut FileDescriptorSet,
+) {
+    if !seen.insert(name.to_owned()) {
+        return;
+    }
+    // Dependencies that were not registered are left to the client.
+    let Some(fd) = files.get(name) else {
+        return;
+    };
+    for dependency in &fd.dependency {
+        push_with_dependencies(files, dependency, seen, fds);
+    }
+    fds.file.push(FileDescriptorProto::clone(fd));
+}
+
+/// The 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable across
+/// Rust releases, so that versions survive a rebuild of the server.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[tonic::async_trait]
+impl schema_server::Schema for SchemaService {
+    async fn get_schema(
+        &self,
+        request: Request<GetSchemaRequest>,
+    ) -> Result<Response<GetSchemaResponse>, Status> {
+        let mut response = GetSchemaResponse::new();
+        response.version = self.version.clone();
+        if request.get_ref().if_none_match == self.version {
+            response.not_modified = true;
+        } else {
+            response.file_descriptor_set = self.file_descriptor_set.to_vec();
+        }
+        Ok(Response::new(response))
+    }
+}
+
+/// A client of the `tonic_protobuf.schema.v1.Schema` service that caches the
+/// fetched schema.
+#[derive(Debug, Clone)]
+pub struct SchemaClient {
+    inner: schema_client::SchemaClient<Channel>,
+    cached: Option<(String, FileDescriptorSet)>,
+}
+
+impl SchemaClient {
+    /// Connect to the server at `dst`.
+    pub async fn connect<D>(dst: D) -> Result<Self, ClientError>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let channel = Endpoint::new(dst)?.connect().await?;
+        Ok(Self::new(channel))
+    }
+
+    /// Create a client that uses an existing channel.
+    pub fn new(channel: Channel) -> Self {
+        SchemaClient {
+            inner: schema_client::SchemaClient::new(channel),
+            cached: None,
+        }
+    }
+
+    /// Returns the version of the last fetched schema, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.cached.as_ref().map(|(version, _)| version.as_str())
+    }
+
+    /// Returns the schema of the server, which is only transferred again if
+    /// its version changed since the last call.
+    pub async fn schema(&mut self) -> Result<&FileDescriptorSet, ClientError> {
+        let mut request = GetSchemaRequest::new();
+        if let Some((version, _)) = &self.cached {
+            request.if_none_match = version.clone();
+        }
+        let response = self.inner.get_schema(request).await?.into_inner();
+        if !response.not_modified {
+            let fds = FileDescriptorSet::parse_from_bytes(&response.file_descriptor_set)?;
+            self.cached = Some((response.version, fds));
+        }
+        match &self.cached {
+            Some((_, fds)) => Ok(fds),
+            // The server claims the client has a schema it never sent.
+            None => Err(ClientError::UnexpectedResponse),
+        }
+    }
+}
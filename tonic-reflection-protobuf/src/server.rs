@@ -0,0 +1,505 @@
+//! The gRPC Server Reflection service.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    sync::Arc,
+};
+
+use protobuf::{
+    descriptor::{DescriptorProto, FileDescriptorProto, FileDescriptorSet},
+    reflect::FileDescriptor,
+    Message, MessageField,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+use crate::{merge, pb, SchemaService};
+
+/// Represents an error in the construction of a gRPC Reflection Service.
+#[derive(Debug)]
+pub enum Error {
+    /// An error was encountered decoding a `FileDescriptorSet` from a buffer.
+    DecodeError(protobuf::Error),
+    /// An invalid `FileDescriptorProto` was encountered.
+    InvalidFileDescriptorSet(String),
+}
+
+impl From<protobuf::Error> for Error {
+    fn from(e: protobuf::Error) -> Self {
+        Error::DecodeError(e)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DecodeError(e) => {
+                write!(f, "error decoding FileDescriptorSet from buffer: {}", e)
+            }
+            Error::InvalidFileDescriptorSet(s) => write!(f, "invalid FileDescriptorSet - {}", s),
+        }
+    }
+}
+
+/// A builder used to construct a gRPC Reflection Service.
+#[derive(Debug)]
+pub struct Builder<'b> {
+    file_descriptor_sets: Vec<FileDescriptorSet>,
+    encoded_file_descriptor_sets: Vec<&'b [u8]>,
+    include_reflection_service: bool,
+
+    service_names: Vec<String>,
+    use_all_service_names: bool,
+}
+
+impl<'b> Builder<'b> {
+    /// Create a new builder that can configure a gRPC Reflection Service.
+    pub fn configure() -> Self {
+        Builder {
+            file_descriptor_sets: Vec::new(),
+            encoded_file_descriptor_sets: Vec::new(),
+            include_reflection_service: true,
+
+            service_names: Vec::new(),
+            use_all_service_names: true,
+        }
+    }
+
+    /// Registers a rust-protobuf `FileDescriptor`, e.g. the one returned by
+    /// `file_descriptor()` of a generated module, together with all of its
+    /// dependencies.
+    pub fn register_file_descriptor(mut self, file_descriptor: &FileDescriptor) -> Self {
+        self.file_descriptor_sets
+            .push(merge::file_descriptor_set(file_descriptor));
+        self
+    }
+
+    /// Registers an instance of `FileDescriptorSet` with the gRPC Reflection
+    /// Service builder.
+    ///
+    /// Files already registered by another set are skipped. Merge the sets
+    /// of different code generators, e.g. of prost-build, with
+    /// [`merge::merge_file_descriptor_sets`] first, which checks that they
+    /// form one consistent schema.
+    pub fn register_file_descriptor_set(mut self, file_descriptor_set: FileDescriptorSet) -> Self {
+        self.file_descriptor_sets.push(file_descriptor_set);
+        self
+    }
+
+    /// Registers a byte slice containing an encoded `FileDescriptorSet` with
+    /// the gRPC Reflection Service builder.
+    pub fn register_encoded_file_descriptor_set(
+        mut self,
+        encoded_file_descriptor_set: &'b [u8],
+    ) -> Self {
+        self.encoded_file_descriptor_sets
+            .push(encoded_file_descriptor_set);
+        self
+    }
+
+    /// Serve the descriptor of the built service itself, e.g. the gRPC
+    /// Reflection Service descriptor via the Reflection Service. This is
+    /// enabled by default - set `include` to false to disable.
+    pub fn include_reflection_service(mut self, include: bool) -> Self {
+        self.include_reflection_service = include;
+        self
+    }
+
+    /// Advertise a fully-qualified gRPC service name.
+    ///
+    /// If not called, then all services present in the registered file
+    /// descriptor sets will be advertised.
+    pub fn with_service_name(mut self, name: impl Into<String>) -> Self {
+        self.use_all_service_names = false;
+        self.service_names.push(name.into());
+        self
+    }
+
+    /// Build a `grpc.reflection.v1` Reflection Service to be served via
+    /// tonic.
+    pub fn build_v1(
+        self,
+    ) -> Result<
+        pb::v1::server_reflection_tonic::server_reflection_server::ServerReflectionServer<
+            ReflectionService,
+        >,
+        Error,
+    > {
+        let service = self.build(pb::v1::reflection::file_descriptor())?;
+        Ok(
+            pb::v1::server_reflection_tonic::server_reflection_server::ServerReflectionServer::new(
+                service,
+            ),
+        )
+    }
+
+    /// Build a `grpc.reflection.v1alpha` Reflection Service to be served via
+    /// tonic.
+    pub fn build_v1alpha(
+        self,
+    ) -> Result<
+        pb::v1alpha::server_reflection_tonic::server_reflection_server::ServerReflectionServer<
+            ReflectionService,
+        >,
+        Error,
+    > {
+        let service = self.build(pb::v1alpha::reflection::file_descriptor())?;
+        Ok(pb::v1alpha::server_reflection_tonic::server_reflection_server::ServerReflectionServer::new(service))
+    }
+
+    /// Build a `tonic_protobuf.schema.v1.Schema` service that serves all
+    /// registered files as one versioned `FileDescriptorSet`, e.g. for
+    /// servers that do not serve reflection.
+    pub fn build_schema(
+        self,
+    ) -> Result<pb::schema::schema_tonic::schema_server::SchemaServer<SchemaService>, Error> {
+        let service = self.build(pb::schema::schema::file_descriptor())?;
+        Ok(pb::schema::schema_tonic::schema_server::SchemaServer::new(
+            SchemaService::new(&service.state.files)?,
+        ))
+    }
+
+    fn build(mut self, reflection: &FileDescriptor) -> Result<ReflectionService, Error> {
+        if self.include_reflection_service {
+            self = self.register_file_descriptor(reflection);
+        }
+
+        for encoded in &self.encoded_file_descriptor_sets {
+            let decoded = FileDescriptorSet::parse_from_bytes(encoded)?;
+            self.file_descriptor_sets.push(decoded);
+        }
+
+        let mut state = ReflectionServiceState::default();
+        for fds in std::mem::take(&mut self.file_descriptor_sets) {
+            for fd in fds.file {
+                if fd.name.is_none() {
+                    return Err(Error::InvalidFileDescriptorSet("missing name".to_string()));
+                }
+                if state.files.contains_key(fd.name()) {
+                    continue;
+                }
+                self.process_file(&mut state, &fd)?;
+                state.files.insert(fd.name().to_owned(), Arc::new(fd));
+            }
+        }
+        state.service_names = self.service_names;
+
+        Ok(ReflectionService {
+            state: Arc::new(state),
+        })
+    }
+
+    fn process_file(
+        &mut self,
+        state: &mut ReflectionServiceState,
+        fd: &FileDescriptorProto,
+    ) -> Result<(), Error> {
+        let file = fd.name();
+        let prefix = fd.package();
+
+        for msg in &fd.message_type {
+            process_message(state, file, prefix, msg)?;
+        }
+
+        for en in &fd.enum_type {
+            let enum_name = extract_name(prefix, "enum", en.name.as_ref())?;
+            for value in &en.value {
+                let value_name = extract_name(&enum_name, "enum value", value.name.as_ref())?;
+                state.symbols.insert(value_name, file.to_owned());
+            }
+            state.symbols.insert(enum_name, file.to_owned());
+        }
+
+        for service in &fd.service {
+            let service_name = extract_name(prefix, "service", service.name.as_ref())?;
+            if self.use_all_service_names {
+                self.service_names.push(service_name.clone());
+            }
+            for method in &service.method {
+                let method_name = extract_name(&service_name, "method", method.name.as_ref())?;
+                state.symbols.insert(method_name, file.to_owned());
+            }
+            state.symbols.insert(service_name, file.to_owned());
+        }
+
+        for extension in &fd.extension {
+            let extension_name = extract_name(prefix, "extension", extension.name.as_ref())?;
+            state.add_extension(file, extension.extendee(), extension.number());
+            state.symbols.insert(extension_name, file.to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+fn process_message(
+    state: &mut ReflectionServiceState,
+    file: &str,
+    prefix: &str,
+    msg: &DescriptorProto,
+) -> Result<(), Error> {
+    let message_name = extract_name(prefix, "message", msg.name.as_ref())?;
+
+    for nested in &msg.nested_type {
+        process_message(state, file, &message_name, nested)?;
+    }
+
+    for en in &msg.enum_type {
+        let enum_name = extract_name(&message_name, "enum", en.name.as_ref())?;
+        for value in &en.value {
+            let value_name = extract_name(&enum_name, "enum value", value.name.as_ref())?;
+            state.symbols.insert(value_name, file.to_owned());
+        }
+        state.symbols.insert(enum_name, file.to_owned());
+    }
+
+    for field in &msg.field {
+        let field_name = extract_name(&message_name, "field", field.name.as_ref())?;
+        state.symbols.insert(field_name, file.to_owned());
+    }
+
+    for oneof in &msg.oneof_decl {
+        let oneof_name = extract_name(&message_name, "oneof", oneof.name.as_ref())?;
+        state.symbols.insert(oneof_name, file.to_owned());
+    }
+
+    for extension in &msg.extension {
+        let extension_name = extract_name(&message_name, "extension", extension.name.as_ref())?;
+        state.add_extension(file, extension.extendee(), extension.number());
+        state.symbols.insert(extension_name, file.to_owned());
+    }
+
+    state.symbols.insert(message_name, file.to_owned());
+    Ok(())
+}
+
+fn extract_name(
+    prefix: &str,
+    name_type: &str,
+    maybe_name: Option<&String>,
+) -> Result<String, Error> {
+    match maybe_name {
+        None => Err(Error::InvalidFileDescriptorSet(format!(
+            "missing {} name",
+            name_type
+        ))),
+        Some(name) => {
+            if prefix.is_empty() {
+                Ok(name.to_string())
+            } else {
+                Ok(format!("{}.{}", prefix, name))
+            }
+        }
+    }
+}
+
+/// A reply to a reflection request, independent of the protocol version.
+enum Reply {
+    /// Encoded `FileDescriptorProto`s.
+    Files(Vec<Vec<u8>>),
+    ExtensionNumbers {
+        base_type_name: String,
+        numbers: Vec<i32>,
+    },
+    Services(Vec<String>),
+    Error(Code, String),
+}
+
+#[derive(Debug, Default)]
+struct ReflectionServiceState {
+    service_names: Vec<String>,
+    /// Files by name.
+    files: HashMap<String, Arc<FileDescriptorProto>>,
+    /// The name of the file that defines each fully-qualified symbol.
+    symbols: HashMap<String, String>,
+    /// The name of the file that defines each extension, keyed by the
+    /// fully-qualified extendee (without a leading dot) and field number.
+    extensions: HashMap<(String, i32), String>,
+    /// The extension numbers of each extendee.
+    extension_numbers: HashMap<String, Vec<i32>>,
+}
+
+impl ReflectionServiceState {
+    fn add_extension(&mut self, file: &str, extendee: &str, number: i32) {
+        let extendee = extendee.trim_start_matches('.').to_owned();
+        self.extension_numbers
+            .entry(extendee.clone())
+            .or_default()
+            .push(number);
+        self.extensions.insert((extendee, number), file.to_owned());
+    }
+
+    fn list_services(&self) -> Reply {
+        Reply::Services(self.service_names.clone())
+    }
+
+    fn file_containing_symbol(&self, symbol: &str) -> Reply {
+        match self.symbols.get(symbol) {
+            None => Reply::Error(Code::NotFound, format!("symbol '{}' not found", symbol)),
+            Some(file) => self.file_with_dependencies(file),
+        }
+    }
+
+    fn file_by_filename(&self, filename: &str) -> Reply {
+        if !self.files.contains_key(filename) {
+            return Reply::Error(Code::NotFound, format!("file '{}' not found", filename));
+        }
+        self.file_with_dependencies(filename)
+    }
+
+    fn file_containing_extension(&self, containing_type: &str, number: i32) -> Reply {
+        let key = (containing_type.to_owned(), number);
+        match self.extensions.get(&key) {
+            None => Reply::Error(
+                Code::NotFound,
+                format!("extension {} of '{}' not found", number, containing_type),
+            ),
+            Some(file) => self.file_with_dependencies(file),
+        }
+    }
+
+    fn all_extension_numbers_of_type(&self, extendee: &str) -> Reply {
+        // Some clients (e.g. grpcurl) expect this request to succeed, reply
+        // with no numbers for unknown types.
+        Reply::ExtensionNumbers {
+            base_type_name: extendee.to_owned(),
+            numbers: self
+                .extension_numbers
+                .get(extendee)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Encodes `file` and all of its transitive dependencies that are known.
+    fn file_with_dependencies(&self, file: &str) -> Reply {
+        let mut encoded = vec![];
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([file.to_owned()]);
+        while let Some(name) = queue.pop_front() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let Some(fd) = self.files.get(&name) else {
+                continue;
+            };
+            match fd.write_to_bytes() {
+                Ok(bytes) => encoded.push(bytes),
+                Err(e) => return Reply::Error(Code::Internal, format!("encoding error: {}", e)),
+            }
+            queue.extend(fd.dependency.iter().cloned());
+        }
+        Reply::Files(encoded)
+    }
+}
+
+/// The gRPC Server Reflection service, built by [`Builder`].
+#[derive(Debug, Clone)]
+pub struct ReflectionService {
+    state: Arc<ReflectionServiceState>,
+}
+
+/// Implements the `ServerReflection` trait of the given protocol version.
+macro_rules! impl_server_reflection {
+    ($version:ident) => {
+        #[tonic::async_trait]
+        impl pb::$version::server_reflection_tonic::server_reflection_server::ServerReflection
+            for ReflectionService
+        {
+            type ServerReflectionInfoStream =
+                ReceiverStream<Result<pb::$version::reflection::ServerReflectionResponse, Status>>;
+
+            async fn server_reflection_info(
+                &self,
+                request: Request<Streaming<pb::$version::reflection::ServerReflectionRequest>>,
+            ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+                use pb::$version::reflection::{
+                    server_reflection_request::Message_request as MessageRequest,
+                    server_reflection_response::Message_response as MessageResponse, ErrorResponse,
+                    ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+                    ServerReflectionResponse, ServiceResponse,
+                };
+
+                let mut requests = request.into_inner();
+                let (tx, rx) = mpsc::channel(1);
+                let state = self.state.clone();
+
+                tokio::spawn(async move {
+                    while let Ok(Some(request)) = requests.message().await {
+                        let reply = match &request.message_request {
+                            Some(MessageRequest::FileByFilename(s)) => state.file_by_filename(s),
+                            Some(MessageRequest::FileContainingSymbol(s)) => {
+                                state.file_containing_symbol(s)
+                            }
+                            Some(MessageRequest::FileContainingExtension(e)) => state
+                                .file_containing_extension(
+                                    e.containing_type.trim_start_matches('.'),
+                                    e.extension_number,
+                                ),
+                            Some(MessageRequest::AllExtensionNumbersOfType(s)) => {
+                                state.all_extension_numbers_of_type(s.trim_start_matches('.'))
+                            }
+                            Some(MessageRequest::ListServices(_)) => state.list_services(),
+                            None => Reply::Error(
+                                Code::InvalidArgument,
+                                "invalid MessageRequest".to_owned(),
+                            ),
+                        };
+
+                        let message_response = match reply {
+                            Reply::Files(files) => {
+                                let mut response = FileDescriptorResponse::new();
+                                response.file_descriptor_proto = files;
+                                MessageResponse::FileDescriptorResponse(response)
+                            }
+                            Reply::ExtensionNumbers {
+                                base_type_name,
+                                numbers,
+                            } => {
+                                let mut response = ExtensionNumberResponse::new();
+                                response.base_type_name = base_type_name;
+                                response.extension_number = numbers;
+                                MessageResponse::AllExtensionNumbersResponse(response)
+                            }
+                            Reply::Services(names) => {
+                                let mut response = ListServiceResponse::new();
+                                response.service = names
+                                    .into_iter()
+                                    .map(|name| {
+                                        let mut service = ServiceResponse::new();
+                                        service.name = name;
+                                        service
+                                    })
+                                    .collect();
+                                MessageResponse::ListServicesResponse(response)
+                            }
+                            Reply::Error(code, message) => {
+                                let mut response = ErrorResponse::new();
+                                response.error_code = code as i32;
+                                response.error_message = message;
+                                MessageResponse::ErrorResponse(response)
+                            }
+                        };
+
+                        let mut response = ServerReflectionResponse::new();
+                        response.valid_host = request.host.clone();
+                        response.message_response = Some(message_response);
+                        response.original_request = MessageField::some(request);
+                        if tx.send(Ok(response)).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+
+                Ok(Response::new(ReceiverStream::new(rx)))
+            }
+        }
+    };
+}
+
+impl_server_reflection!(v1);
+impl_server_reflection!(v1alpha);
@@ -0,0 +1,43 @@
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic_reflection_protobuf::{Builder, ClientError, ReflectionClient};
+
+async fn serve() -> ReflectionClient {
+    let service = Builder::configure().build_v1().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    ReflectionClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_client() {
+    let mut client = serve().await;
+
+    let services = client.list_services().await.unwrap();
+    assert_eq!(services, ["grpc.reflection.v1.ServerReflection"]);
+
+    let fd = client
+        .file_descriptor("grpc.reflection.v1.ServerReflection")
+        .await
+        .unwrap();
+    let service = fd.services().next().unwrap();
+    assert_eq!(service.proto().name(), "ServerReflection");
+    let method = service.methods().next().unwrap();
+    assert_eq!(
+        method.input_type().full_name(),
+        "grpc.reflection.v1.ServerReflectionRequest"
+    );
+
+    match client.file_by_filename("missing.proto").await {
+        Err(ClientError::Server { code, .. }) => assert_eq!(code, tonic::Code::NotFound),
+        res => panic!("{res:?}"),
+    }
+}
@@ -0,0 +1,143 @@
+use protobuf::{
+    descriptor::{FileDescriptorProto, FileDescriptorSet},
+    text_format, Message,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic_reflection_protobuf::{merge, pb, Builder, Error, ReflectionClient};
+
+fn file(text: &str) -> FileDescriptorProto {
+    text_format::parse_from_str(text).unwrap()
+}
+
+fn set(files: impl IntoIterator<Item = FileDescriptorProto>) -> FileDescriptorSet {
+    let mut set = FileDescriptorSet::new();
+    set.file = files.into_iter().collect();
+    set
+}
+
+fn names(set: &FileDescriptorSet) -> Vec<&str> {
+    set.file.iter().map(|file| file.name()).collect()
+}
+
+fn error(sets: Vec<FileDescriptorSet>) -> String {
+    match merge::merge_file_descriptor_sets(sets) {
+        Err(Error::InvalidFileDescriptorSet(message)) => message,
+        result => panic!("{result:?}"),
+    }
+}
+
+/// The descriptors prost-build writes, which carry comments, of a service
+/// importing a file of this crate.
+fn prost_set() -> Vec<u8> {
+    let greeter = file(
+        r#"
+        name: "greeter.proto"
+        package: "greeter"
+        dependency: "grpc/reflection/v1/reflection.proto"
+        message_type { name: "HelloRequest" field { name: "name" number: 1 type: TYPE_STRING } }
+        service {
+            name: "Greeter"
+            method {
+                name: "SayHello"
+                input_type: ".greeter.HelloRequest"
+                output_type: ".grpc.reflection.v1.ServerReflectionResponse"
+            }
+        }
+        "#,
+    );
+    let mut reflection = pb::v1::reflection::file_descriptor().proto().clone();
+    let mut location = protobuf::descriptor::source_code_info::Location::new();
+    location.leading_comments = Some(" Server reflection.\n".to_owned());
+    reflection
+        .source_code_info
+        .mut_or_insert_default()
+        .location
+        .push(location);
+    set([greeter, reflection]).write_to_bytes().unwrap()
+}
+
+#[tokio::test]
+async fn test_merged_reflection() {
+    let merged = merge::merge_file_descriptor_sets([
+        merge::file_descriptor_set(pb::v1::reflection::file_descriptor()),
+        FileDescriptorSet::parse_from_bytes(&prost_set()).unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(
+        names(&merged),
+        ["grpc/reflection/v1/reflection.proto", "greeter.proto"]
+    );
+    // Comments of prost-build are kept.
+    assert!(merged.file[0].source_code_info.is_some());
+
+    let service = Builder::configure()
+        .register_file_descriptor_set(merged)
+        .build_v1()
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let mut client = ReflectionClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap();
+    let mut services = client.list_services().await.unwrap();
+    services.sort();
+    assert_eq!(
+        services,
+        ["greeter.Greeter", "grpc.reflection.v1.ServerReflection"]
+    );
+    let fd = client.file_descriptor("greeter.Greeter").await.unwrap();
+    let method = fd.services().next().unwrap().methods().next().unwrap();
+    assert_eq!(
+        method.output_type().full_name(),
+        "grpc.reflection.v1.ServerReflectionResponse"
+    );
+}
+
+#[test]
+fn test_merge_newer_release() {
+    let old =
+        file(r#"name: "shared.proto" message_type { name: "A" field { name: "x" number: 1 } }"#);
+    let new = file(
+        r#"
+        name: "shared.proto"
+        message_type { name: "A" field { name: "x" number: 1 } field { name: "y" number: 2 } }
+        "#,
+    );
+    for sets in [
+        [set([old.clone()]), set([new.clone()])],
+        [set([new.clone()]), set([old.clone()])],
+    ] {
+        let merged = merge::merge_file_descriptor_sets(sets).unwrap();
+        assert_eq!(merged.file, vec![new.clone()]);
+    }
+}
+
+#[test]
+fn test_merge_errors() {
+    let a = file(r#"name: "a.proto" message_type { name: "A" field { name: "x" number: 1 } }"#);
+    let renumbered =
+        file(r#"name: "a.proto" message_type { name: "A" field { name: "x" number: 2 } }"#);
+    assert_eq!(
+        error(vec![set([a.clone()]), set([renumbered])]),
+        "a.proto is registered with incompatible definitions"
+    );
+
+    let moved = file(r#"name: "proto/a.proto" message_type { name: "A" }"#);
+    assert_eq!(
+        error(vec![set([a.clone()]), set([moved])]),
+        "A is defined by both a.proto and proto/a.proto"
+    );
+
+    let importer = file(r#"name: "b.proto" dependency: "missing.proto""#);
+    assert_eq!(
+        error(vec![set([a, importer])]),
+        "b.proto imports missing.proto, which is in none of the sets"
+    );
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use protobuf::{reflect::FileDescriptor, well_known_types};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use tonic_reflection_protobuf::{
+    pb::schema::{
+        schema::GetSchemaRequest, schema_tonic::schema_client::SchemaClient as RawClient,
+    },
+    Builder, SchemaClient,
+};
+
+async fn serve(builder: Builder<'_>) -> Channel {
+    let service = builder.build_schema().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn version(builder: Builder<'_>) -> String {
+    let mut client = SchemaClient::new(serve(builder).await);
+    client.schema().await.unwrap();
+    client.version().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn test_schema() {
+    let builder =
+        Builder::configure().register_file_descriptor(well_known_types::api::file_descriptor());
+    let mut client = SchemaClient::new(serve(builder).await);
+    assert_eq!(client.version(), None);
+
+    let fds = client.schema().await.unwrap().clone();
+    let names: Vec<_> = fds.file.iter().map(|f| f.name()).collect();
+    assert_eq!(
+        names,
+        [
+            "google/protobuf/any.proto",
+            "google/protobuf/source_context.proto",
+            "google/protobuf/type.proto",
+            "google/protobuf/api.proto",
+            "tonic_protobuf/schema/v1/schema.proto",
+        ]
+    );
+    let mut seen = HashSet::new();
+    for file in &fds.file {
+        for dependency in &file.dependency {
+            assert!(seen.contains(dependency), "{}", file.name());
+        }
+        seen.insert(file.name().to_owned());
+    }
+
+    let files = FileDescriptor::new_dynamic_fds(fds.file.clone(), &[]).unwrap();
+    let api = files
+        .iter()
+        .find(|f| f.name() == "google/protobuf/api.proto");
+    assert!(api
+        .unwrap()
+        .message_by_package_relative_name("Api")
+        .is_some());
+
+    // A cached schema is returned again without a transfer.
+    let version = client.version().unwrap().to_owned();
+    assert_eq!(client.schema().await.unwrap(), &fds);
+    assert_eq!(client.version(), Some(version.as_str()));
+}
+
+#[tokio::test]
+async fn test_if_none_match() {
+    let mut client = RawClient::new(serve(Builder::configure()).await);
+    let response = client
+        .get_schema(GetSchemaRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!response.not_modified);
+    assert!(!response.file_descriptor_set.is_empty());
+
+    let mut request = GetSchemaRequest::new();
+    request.if_none_match = response.version.clone();
+    let not_modified = client.get_schema(request).await.unwrap().into_inner();
+    assert!(not_modified.not_modified);
+    assert_eq!(not_modified.version, response.version);
+    assert!(not_modified.file_descriptor_set.is_empty());
+
+    let mut request = GetSchemaRequest::new();
+    request.if_none_match = "stale".to_owned();
+    let modified = client.get_schema(request).await.unwrap().into_inner();
+    assert!(!modified.not_modified);
+    assert_eq!(modified.file_descriptor_set, response.file_descriptor_set);
+}
+
+#[tokio::test]
+async fn test_version() {
+    let any = well_known_types::any::file_descriptor();
+    let api = well_known_types::api::file_descriptor();
+
+    // The version only depends on the registered files.
+    let a = version(
+        Builder::configure()
+            .register_file_descriptor(any)
+            .register_file_descriptor(api),
+    )
+    .await;
+    let b = version(
+        Builder::configure()
+            .register_file_descriptor(api)
+            .register_file_descriptor(any),
+    )
+    .await;
+    assert_eq!(a, b);
+    assert_eq!(a.len(), 16);
+
+    let c = version(
+        Builder::configure()
+            .register_file_descriptor(api)
+            .include_reflection_service(false),
+    )
+    .await;
+    assert_ne!(a, c);
+}
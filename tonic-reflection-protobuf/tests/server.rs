@@ -0,0 +1,85 @@
+use protobuf::{descriptor::FileDescriptorProto, Message};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use tonic_reflection_protobuf::{
+    pb::v1::{
+        reflection::{
+            server_reflection_request::Message_request as MessageRequest,
+            server_reflection_response::Message_response as MessageResponse,
+            ServerReflectionRequest,
+        },
+        server_reflection_tonic::server_reflection_client::ServerReflectionClient,
+    },
+    Builder,
+};
+
+async fn serve() -> ServerReflectionClient<Channel> {
+    let service = Builder::configure().build_v1().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    ServerReflectionClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap()
+}
+
+async fn reflect(
+    client: &mut ServerReflectionClient<Channel>,
+    request: MessageRequest,
+) -> MessageResponse {
+    let mut req = ServerReflectionRequest::new();
+    req.message_request = Some(request);
+    let mut responses = client
+        .server_reflection_info(tokio_stream::iter(vec![req]))
+        .await
+        .unwrap()
+        .into_inner();
+    let response = responses.message().await.unwrap().unwrap();
+    assert!(response.original_request.is_some());
+    response.message_response.unwrap()
+}
+
+#[tokio::test]
+async fn test_list_services() {
+    let mut client = serve().await;
+    match reflect(&mut client, MessageRequest::ListServices(String::new())).await {
+        MessageResponse::ListServicesResponse(resp) => {
+            let names: Vec<_> = resp.service.iter().map(|s| s.name.as_str()).collect();
+            assert_eq!(names, ["grpc.reflection.v1.ServerReflection"]);
+        }
+        resp => panic!("{resp:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_file_containing_symbol() {
+    let mut client = serve().await;
+    let request = MessageRequest::FileContainingSymbol(
+        "grpc.reflection.v1.ServerReflectionRequest.host".to_owned(),
+    );
+    match reflect(&mut client, request).await {
+        MessageResponse::FileDescriptorResponse(resp) => {
+            assert_eq!(resp.file_descriptor_proto.len(), 1);
+            let fd = FileDescriptorProto::parse_from_bytes(&resp.file_descriptor_proto[0]).unwrap();
+            assert_eq!(fd.package(), "grpc.reflection.v1");
+        }
+        resp => panic!("{resp:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_not_found() {
+    let mut client = serve().await;
+    let request = MessageRequest::FileByFilename("missing.proto".to_owned());
+    match reflect(&mut client, request).await {
+        MessageResponse::ErrorResponse(resp) => {
+            assert_eq!(resp.error_code, tonic::Code::NotFound as i32);
+        }
+        resp => panic!("{resp:?}"),
+    }
+}
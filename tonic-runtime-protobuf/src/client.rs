@@ -0,0 +1,132 @@
+//! Helpers making the calls of generated client methods.
+
+use tonic::{
+    body::BoxBody,
+    client::{Grpc, GrpcService},
+    codec::{Codec, Streaming},
+    codegen::{http::uri::PathAndQuery, tokio_stream::Stream, Body, Bytes, GrpcMethod, StdError},
+    Code, Request, Response, Status,
+};
+
+/// Waits until `grpc` is ready to make a call.
+pub async fn ready<T>(grpc: &mut Grpc<T>) -> Result<(), Status>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+{
+    grpc.ready().await.map_err(|e| {
+        Status::new(
+            Code::Unknown,
+            format!("Service was not ready: {}", e.into()),
+        )
+    })
+}
+
+/// Makes the unary call of the method `path`, `method` of `service`.
+pub async fn unary<T, C>(
+    grpc: &mut Grpc<T>,
+    mut request: Request<C::Encode>,
+    path: &'static str,
+    service: &'static str,
+    method: &'static str,
+    codec: C,
+) -> Result<Response<C::Decode>, Status>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    C: Codec,
+    C::Encode: Send + Sync + 'static,
+    C::Decode: Send + Sync + 'static,
+{
+    ready(grpc).await?;
+    request
+        .extensions_mut()
+        .insert(GrpcMethod::new(service, method));
+    grpc.unary(request, PathAndQuery::from_static(path), codec)
+        .await
+}
+
+/// Makes the client streaming call of the method `path`, `method` of
+/// `service`.
+pub async fn client_streaming<T, C, S>(
+    grpc: &mut Grpc<T>,
+    mut request: Request<S>,
+    path: &'static str,
+    service: &'static str,
+    method: &'static str,
+    codec: C,
+) -> Result<Response<C::Decode>, Status>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    C: Codec,
+    C::Encode: Send + Sync + 'static,
+    C::Decode: Send + Sync + 'static,
+    S: Stream<Item = C::Encode> + Send + 'static,
+{
+    ready(grpc).await?;
+    request
+        .extensions_mut()
+        .insert(GrpcMethod::new(service, method));
+    grpc.client_streaming(request, PathAndQuery::from_static(path), codec)
+        .await
+}
+
+/// Makes the server streaming call of the method `path`, `method` of
+/// `service`.
+pub async fn server_streaming<T, C>(
+    grpc: &mut Grpc<T>,
+    mut request: Request<C::Encode>,
+    path: &'static str,
+    service: &'static str,
+    method: &'static str,
+    codec: C,
+) -> Result<Response<Streaming<C::Decode>>, Status>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    C: Codec,
+    C::Encode: Send + Sync + 'static,
+    C::Decode: Send + Sync + 'static,
+{
+    ready(grpc).await?;
+    request
+        .extensions_mut()
+        .insert(GrpcMethod::new(service, method));
+    grpc.server_streaming(request, PathAndQuery::from_static(path), codec)
+        .await
+}
+
+/// Makes the bidirectional streaming call of the method `path`, `method` of
+/// `service`.
+pub async fn streaming<T, C, S>(
+    grpc: &mut Grpc<T>,
+    mut request: Request<S>,
+    path: &'static str,
+    service: &'static str,
+    method: &'static str,
+    codec: C,
+) -> Result<Response<Streaming<C::Decode>>, Status>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    C: Codec,
+    C::Encode: Send + Sync + 'static,
+    C::Decode: Send + Sync + 'static,
+    S: Stream<Item = C::Encode> + Send + 'static,
+{
+    ready(grpc).await?;
+    request
+        .extensions_mut()
+        .insert(GrpcMethod::new(service, method));
+    grpc.streaming(request, PathAndQuery::from_static(path), codec)
+        .await
+}
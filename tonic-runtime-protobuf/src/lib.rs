@@ -0,0 +1,16 @@
+//! Generic call helpers for the clients and servers generated by
+//! tonic-build-protobuf with `Builder::runtime_helpers`.
+//!
+//! tonic-build expands every method into its own glue: clients wait for the
+//! transport, attach the method to the request and pick the call shape, and
+//! servers declare a service struct per method and configure a
+//! `tonic::server::Grpc` for it. With runtime helpers, generated methods
+//! instead pass their path, codec and handler to the functions of
+//! [`client`] and [`server`], which keeps generated files smaller and
+//! compiles the glue once per call shape instead of once per method.
+//!
+//! The helpers are not meant to be called by hand, their signatures follow
+//! the generated code.
+
+pub mod client;
+pub mod server;
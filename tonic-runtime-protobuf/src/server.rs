@@ -0,0 +1,147 @@
+//! Helpers serving the calls of generated server methods.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    task::{Context, Poll},
+};
+
+use tonic::{
+    body::BoxBody,
+    codec::{Codec, EnabledCompressionEncodings},
+    codegen::{http, tokio_stream::Stream, Body, BoxFuture, Service, StdError},
+    server::Grpc,
+    Request, Response, Status, Streaming,
+};
+
+/// The compression and message size configuration of a generated server.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Config {
+    /// The encodings of compressed requests that are accepted.
+    pub accept_compression_encodings: EnabledCompressionEncodings,
+    /// The encodings responses are compressed with, if the client accepts
+    /// them.
+    pub send_compression_encodings: EnabledCompressionEncodings,
+    /// The maximum size of a decoded message.
+    pub max_decoding_message_size: Option<usize>,
+    /// The maximum size of an encoded message.
+    pub max_encoding_message_size: Option<usize>,
+}
+
+impl Config {
+    fn grpc<C: Codec>(self, codec: C) -> Grpc<C> {
+        Grpc::new(codec)
+            .apply_compression_config(
+                self.accept_compression_encodings,
+                self.send_compression_encodings,
+            )
+            .apply_max_message_size_config(
+                self.max_decoding_message_size,
+                self.max_encoding_message_size,
+            )
+    }
+}
+
+/// Serves the calls of a method with a function returning the response
+/// future of a request.
+struct Handler<F>(F);
+
+impl<F, Fut, R, T> Service<Request<R>> for Handler<F>
+where
+    F: FnMut(Request<R>) -> Fut,
+    Fut: Future<Output = Result<Response<T>, Status>>,
+{
+    type Response = Response<T>;
+    type Error = Status;
+    type Future = Fut;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<R>) -> Fut {
+        (self.0)(request)
+    }
+}
+
+/// Serves the unary call `req` with `handler`.
+pub fn unary<C, F, Fut, B>(
+    req: http::Request<B>,
+    codec: C,
+    config: Config,
+    handler: F,
+) -> BoxFuture<http::Response<BoxBody>, Infallible>
+where
+    C: Codec + Send + 'static,
+    F: FnMut(Request<C::Decode>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<C::Encode>, Status>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = config.grpc(codec);
+        Ok(grpc.unary(Handler(handler), req).await)
+    })
+}
+
+/// Serves the client streaming call `req` with `handler`.
+pub fn client_streaming<C, F, Fut, B>(
+    req: http::Request<B>,
+    codec: C,
+    config: Config,
+    handler: F,
+) -> BoxFuture<http::Response<BoxBody>, Infallible>
+where
+    C: Codec + Send + 'static,
+    F: FnMut(Request<Streaming<C::Decode>>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<C::Encode>, Status>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = config.grpc(codec);
+        Ok(grpc.client_streaming(Handler(handler), req).await)
+    })
+}
+
+/// Serves the server streaming call `req` with `handler`.
+pub fn server_streaming<C, F, Fut, S, B>(
+    req: http::Request<B>,
+    codec: C,
+    config: Config,
+    handler: F,
+) -> BoxFuture<http::Response<BoxBody>, Infallible>
+where
+    C: Codec + Send + 'static,
+    F: FnMut(Request<C::Decode>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: Stream<Item = Result<C::Encode, Status>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = config.grpc(codec);
+        Ok(grpc.server_streaming(Handler(handler), req).await)
+    })
+}
+
+/// Serves the bidirectional streaming call `req` with `handler`.
+pub fn streaming<C, F, Fut, S, B>(
+    req: http::Request<B>,
+    codec: C,
+    config: Config,
+    handler: F,
+) -> BoxFuture<http::Response<BoxBody>, Infallible>
+where
+    C: Codec + Send + 'static,
+    F: FnMut(Request<Streaming<C::Decode>>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: Stream<Item = Result<C::Encode, Status>> + Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = config.grpc(codec);
+        Ok(grpc.streaming(Handler(handler), req).await)
+    })
+}
@@ -0,0 +1,126 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use protobuf::well_known_types::wrappers::StringValue;
+use tonic::{
+    body::BoxBody,
+    client::Grpc,
+    codegen::{http, tokio_stream, tokio_stream::StreamExt, BoxFuture, Service},
+    server::NamedService,
+    transport::Body,
+    Request, Response, Status,
+};
+use tonic_codec_protobuf::ProtobufCodecV3;
+use tonic_runtime_protobuf::{client, server};
+use tonic_testing_protobuf::duplex;
+
+type Codec = ProtobufCodecV3<StringValue, StringValue>;
+
+/// Echoes the value of `Echo/Unary`, and every character of it in
+/// `Echo/Chars`.
+#[derive(Clone)]
+struct Echo;
+
+impl NamedService for Echo {
+    const NAME: &'static str = "test.Echo";
+}
+
+impl Service<http::Request<Body>> for Echo {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let codec = Codec::default();
+        let config = server::Config::default();
+        match req.uri().path() {
+            "/test.Echo/Unary" => server::unary(
+                req,
+                codec,
+                config,
+                |request: Request<StringValue>| async move {
+                    if request.get_ref().value.is_empty() {
+                        return Err(Status::invalid_argument("empty value"));
+                    }
+                    Ok(Response::new(request.into_inner()))
+                },
+            ),
+            "/test.Echo/Chars" => server::server_streaming(
+                req,
+                codec,
+                config,
+                |request: Request<StringValue>| async move {
+                    let chars: Vec<_> = request
+                        .into_inner()
+                        .value
+                        .chars()
+                        .map(|c| value(&c.to_string()))
+                        .collect();
+                    Ok(Response::new(tokio_stream::iter(chars).map(Ok)))
+                },
+            ),
+            _ => Box::pin(async { Ok(http::Response::new(tonic::body::empty_body())) }),
+        }
+    }
+}
+
+fn value(value: &str) -> StringValue {
+    let mut message = StringValue::new();
+    message.value = value.to_owned();
+    message
+}
+
+#[tokio::test]
+async fn test_unary() {
+    let mut grpc = Grpc::new(duplex::connect_service(Echo).await);
+    let response = client::unary(
+        &mut grpc,
+        Request::new(value("hello")),
+        "/test.Echo/Unary",
+        "test.Echo",
+        "Unary",
+        Codec::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.into_inner(), value("hello"));
+
+    let status = client::unary(
+        &mut grpc,
+        Request::new(value("")),
+        "/test.Echo/Unary",
+        "test.Echo",
+        "Unary",
+        Codec::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_server_streaming() {
+    let mut grpc = Grpc::new(duplex::connect_service(Echo).await);
+    let mut stream = client::server_streaming(
+        &mut grpc,
+        Request::new(value("abc")),
+        "/test.Echo/Chars",
+        "test.Echo",
+        "Chars",
+        Codec::default(),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let mut chars = String::new();
+    while let Some(message) = stream.message().await.unwrap() {
+        chars.push_str(&message.value);
+    }
+    assert_eq!(chars, "abc");
+}
@@ -0,0 +1,173 @@
+//! [proptest](https://docs.rs/proptest) strategies for messages described by
+//! descriptors.
+//!
+//! Generated messages are valid: required fields are always set, enums only
+//! take declared values and nesting is bounded by [`Config::max_depth`].
+//!
+//! ```rust,ignore
+//! proptest! {
+//!     #[test]
+//!     fn round_trip(message in arbitrary::message(Request::descriptor())) {
+//!         roundtrip::check(&*message).unwrap();
+//!     }
+//! }
+//! ```
+
+use proptest::{collection, prelude::*, sample};
+use protobuf::{
+    reflect::{FieldDescriptor, MessageDescriptor, ReflectValueBox, RuntimeFieldType, RuntimeType},
+    MessageDyn,
+};
+
+/// Limits of generated messages.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The maximum nesting of message fields, deeper message fields are
+    /// left unset unless they are required.
+    pub max_depth: usize,
+    /// The maximum number of elements of repeated and map fields.
+    pub max_repeated: usize,
+    /// The maximum length of string and bytes fields, in chars and bytes.
+    pub max_len: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_depth: 3,
+            max_repeated: 4,
+            max_len: 16,
+        }
+    }
+}
+
+/// Returns a strategy generating messages of type `descriptor` with the
+/// default [`Config`].
+pub fn message(descriptor: MessageDescriptor) -> BoxedStrategy<Box<dyn MessageDyn>> {
+    message_with(descriptor, &Config::default())
+}
+
+/// Returns a strategy generating messages of type `descriptor`.
+pub fn message_with(
+    descriptor: MessageDescriptor,
+    config: &Config,
+) -> BoxedStrategy<Box<dyn MessageDyn>> {
+    message_at(descriptor, config, config.max_depth)
+}
+
+/// The generated value of a field.
+#[derive(Debug, Clone)]
+enum FieldValue {
+    Singular(Option<ReflectValueBox>),
+    Repeated(Vec<ReflectValueBox>),
+    Map(Vec<(ReflectValueBox, ReflectValueBox)>),
+}
+
+fn message_at(
+    descriptor: MessageDescriptor,
+    config: &Config,
+    depth: usize,
+) -> BoxedStrategy<Box<dyn MessageDyn>> {
+    let (fields, values): (Vec<_>, Vec<_>) = descriptor
+        .fields()
+        .filter_map(|field| {
+            let value = field_value(&field, config, depth)?;
+            Some((field, value))
+        })
+        .unzip();
+    values
+        .prop_map(move |values| {
+            let mut message = descriptor.new_instance();
+            for (field, value) in fields.iter().zip(values) {
+                set_field(&mut *message, field, value);
+            }
+            message
+        })
+        .boxed()
+}
+
+fn set_field(message: &mut dyn MessageDyn, field: &FieldDescriptor, value: FieldValue) {
+    match value {
+        FieldValue::Singular(None) => {}
+        FieldValue::Singular(Some(value)) => field.set_singular_field(message, value),
+        FieldValue::Repeated(values) => {
+            let mut repeated = field.mut_repeated(message);
+            for value in values {
+                repeated.push(value);
+            }
+        }
+        FieldValue::Map(entries) => {
+            let mut map = field.mut_map(message);
+            for (key, value) in entries {
+                map.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Returns `None` for message fields beyond the maximum depth.
+fn field_value(
+    field: &FieldDescriptor,
+    config: &Config,
+    depth: usize,
+) -> Option<BoxedStrategy<FieldValue>> {
+    let strategy = match field.runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeType::Message(descriptor)) if field.is_required() => {
+            message_at(descriptor, config, depth.saturating_sub(1))
+                .prop_map(|m| FieldValue::Singular(Some(ReflectValueBox::Message(m))))
+                .boxed()
+        }
+        RuntimeFieldType::Singular(ty) if field.is_required() => value(&ty, config, depth)?
+            .prop_map(|v| FieldValue::Singular(Some(v)))
+            .boxed(),
+        RuntimeFieldType::Singular(ty) => proptest::option::of(value(&ty, config, depth)?)
+            .prop_map(FieldValue::Singular)
+            .boxed(),
+        RuntimeFieldType::Repeated(ty) => {
+            collection::vec(value(&ty, config, depth)?, 0..=config.max_repeated)
+                .prop_map(FieldValue::Repeated)
+                .boxed()
+        }
+        RuntimeFieldType::Map(key, ty) => {
+            let entry = (value(&key, config, depth)?, value(&ty, config, depth)?);
+            collection::vec(entry, 0..=config.max_repeated)
+                .prop_map(FieldValue::Map)
+                .boxed()
+        }
+    };
+    Some(strategy)
+}
+
+fn value(
+    ty: &RuntimeType,
+    config: &Config,
+    depth: usize,
+) -> Option<BoxedStrategy<ReflectValueBox>> {
+    let strategy = match ty {
+        RuntimeType::I32 => any::<i32>().prop_map(ReflectValueBox::I32).boxed(),
+        RuntimeType::I64 => any::<i64>().prop_map(ReflectValueBox::I64).boxed(),
+        RuntimeType::U32 => any::<u32>().prop_map(ReflectValueBox::U32).boxed(),
+        RuntimeType::U64 => any::<u64>().prop_map(ReflectValueBox::U64).boxed(),
+        RuntimeType::F32 => any::<f32>().prop_map(ReflectValueBox::F32).boxed(),
+        RuntimeType::F64 => any::<f64>().prop_map(ReflectValueBox::F64).boxed(),
+        RuntimeType::Bool => any::<bool>().prop_map(ReflectValueBox::Bool).boxed(),
+        RuntimeType::String => collection::vec(any::<char>(), 0..=config.max_len)
+            .prop_map(|chars| ReflectValueBox::String(chars.into_iter().collect()))
+            .boxed(),
+        RuntimeType::VecU8 => collection::vec(any::<u8>(), 0..=config.max_len)
+            .prop_map(ReflectValueBox::Bytes)
+            .boxed(),
+        RuntimeType::Enum(descriptor) => {
+            let numbers: Vec<i32> = descriptor.values().map(|v| v.value()).collect();
+            let descriptor = descriptor.clone();
+            sample::select(numbers)
+                .prop_map(move |n| ReflectValueBox::Enum(descriptor.clone(), n))
+                .boxed()
+        }
+        RuntimeType::Message(_) if depth == 0 => return None,
+        RuntimeType::Message(descriptor) => message_at(descriptor.clone(), config, depth - 1)
+            .prop_map(ReflectValueBox::Message)
+            .boxed(),
+    };
+    Some(strategy)
+}
@@ -0,0 +1,141 @@
+//! Round trips of messages through codecs, for benchmarks.
+//!
+//! `tonic-build-protobuf` writes a criterion bench per service with
+//! `Builder::write_benches`, which measures [`roundtrip`] for the default
+//! request and response of every method, and for the [`samples`] of their
+//! types:
+//!
+//! ```rust,ignore
+//! for (name, message) in bench::samples::<GetRequest>(SAMPLES) {
+//!     group.bench_function(format!("request/{}", name), |b| {
+//!         b.iter(|| bench::roundtrip(ProtobufCodecV3::default(), message.clone()))
+//!     });
+//! }
+//! ```
+
+use std::{
+    convert::Infallible,
+    fs,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use protobuf::{text_format, MessageFull};
+use tonic::{
+    body::BoxBody,
+    client::Grpc,
+    codec::Codec,
+    codegen::{http, http::uri::PathAndQuery, Body, Bytes},
+    Request, Status,
+};
+use tonic_dynamic_protobuf::json;
+
+use crate::runtime::block_on;
+
+/// Returns the default message of type `M` named `default`, followed by the
+/// sample messages in the directory `dir/{full name of M}`, named after
+/// their files: `.txtpb` files in the text format and `.json` files in the
+/// proto3 JSON mapping, in the order of their names.
+///
+/// # Panics
+///
+/// Panics if a sample can not be read or parsed.
+pub fn samples<M: MessageFull>(dir: impl AsRef<Path>) -> Vec<(String, M)> {
+    let mut samples = vec![("default".to_owned(), M::default())];
+    let dir = dir.as_ref().join(M::descriptor().full_name());
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return samples;
+    };
+    let mut paths: Vec<_> = entries.map(|entry| entry.unwrap().path()).collect();
+    paths.sort();
+    for path in paths {
+        let (Some(name), Some(extension)) = (path.file_stem(), path.extension()) else {
+            continue;
+        };
+        let json = match extension.to_str() {
+            Some("txtpb") => false,
+            Some("json") => true,
+            _ => continue,
+        };
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("read sample {}: {}", path.display(), e));
+        let message = if json {
+            json::parse_from_str(&M::descriptor(), &text)
+                .map(|message| *message.downcast_box::<M>().unwrap())
+                .map_err(|e| e.to_string())
+        } else {
+            text_format::parse_from_str::<M>(&text).map_err(|e| e.to_string())
+        }
+        .unwrap_or_else(|e| panic!("parse sample {}: {}", path.display(), e));
+        samples.push((name.to_string_lossy().into_owned(), message));
+    }
+    samples
+}
+
+/// Encodes `message` with `codec` into the body of a request, and decodes it
+/// from the body of the response of a server echoing the request, the way a
+/// tonic client and server do in a call, and returns the decoded message.
+///
+/// The call runs on a current-thread runtime of the calling thread, so the
+/// measured time includes the framing and the bookkeeping of a call, without
+/// any I/O.
+///
+/// # Panics
+///
+/// Panics if the message can not be encoded or decoded.
+pub fn roundtrip<C, M>(codec: C, message: M) -> M
+where
+    C: Codec<Encode = M, Decode = M> + Send + 'static,
+    M: Send + Sync + 'static,
+{
+    try_roundtrip(codec, message).unwrap_or_else(|status| panic!("round trip failed: {}", status))
+}
+
+/// Like [`roundtrip`], but returns the status of the call if the message can
+/// not be encoded or decoded.
+#[allow(clippy::result_large_err)]
+pub(crate) fn try_roundtrip<C, M>(codec: C, message: M) -> Result<M, Status>
+where
+    C: Codec<Encode = M, Decode = M> + Send + 'static,
+    M: Send + Sync + 'static,
+{
+    let echo = tower::service_fn(|request: http::Request<BoxBody>| async move {
+        Ok::<_, Infallible>(http::Response::new(Echo(request.into_body())))
+    });
+    block_on(async {
+        Grpc::new(echo)
+            .unary(
+                Request::new(message),
+                PathAndQuery::from_static("/tonic_testing_protobuf.Bench/Roundtrip"),
+                codec,
+            )
+            .await
+            .map(|response| response.into_inner())
+    })
+}
+
+/// The body of a response echoing a request body, which ends with an `OK`
+/// status.
+struct Echo(BoxBody);
+
+impl Body for Echo {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.0).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+        Poll::Ready(Ok(Some(trailers)))
+    }
+}
@@ -0,0 +1,183 @@
+//! Field-level comparison of messages.
+//!
+//! [`assert_proto_eq!`](crate::assert_proto_eq) reports which fields of two
+//! messages differ, instead of printing both messages in full:
+//!
+//! ```text
+//! messages differ:
+//!   items[2].name: "foo" != "bar"
+//!   labels["zone"]: "a" != <unset>
+//! ```
+
+use std::fmt;
+
+use protobuf::{
+    reflect::{ReflectEq, ReflectEqMode, ReflectFieldRef, ReflectValueRef},
+    MessageDyn,
+};
+
+/// A difference between two messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The path of the field, e.g. `items[2].name` or `labels["zone"]`.
+    pub path: String,
+    /// The value in the left message, `None` if unset.
+    pub left: Option<String>,
+    /// The value in the right message, `None` if unset.
+    pub right: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let left = self.left.as_deref().unwrap_or("<unset>");
+        let right = self.right.as_deref().unwrap_or("<unset>");
+        write!(f, "{}: {} != {}", self.path, left, right)
+    }
+}
+
+/// Returns the differences between `left` and `right`, empty if they are
+/// equal. `NaN` values are equal to each other.
+///
+/// Messages of different types are reported as a single difference of the
+/// root path `.`.
+pub fn diff(left: &dyn MessageDyn, right: &dyn MessageDyn) -> Vec<Difference> {
+    let mut differences = vec![];
+    let (left_type, right_type) = (left.descriptor_dyn(), right.descriptor_dyn());
+    if left_type != right_type {
+        differences.push(Difference {
+            path: ".".to_owned(),
+            left: Some(left_type.full_name().to_owned()),
+            right: Some(right_type.full_name().to_owned()),
+        });
+    } else {
+        diff_message(left, right, "", &mut differences);
+    }
+    differences
+}
+
+/// Formats `differences` as a panic message.
+pub fn format_differences(differences: &[Difference]) -> String {
+    let mut message = "messages differ:".to_owned();
+    for difference in differences {
+        message.push_str("\n  ");
+        message.push_str(&difference.to_string());
+    }
+    message
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn diff_message(
+    left: &dyn MessageDyn,
+    right: &dyn MessageDyn,
+    path: &str,
+    differences: &mut Vec<Difference>,
+) {
+    for field in left.descriptor_dyn().fields() {
+        let path = join(path, field.name());
+        match (field.get_reflect(left), field.get_reflect(right)) {
+            (ReflectFieldRef::Optional(l), ReflectFieldRef::Optional(r)) => {
+                diff_value(l.value(), r.value(), path, differences)
+            }
+            (ReflectFieldRef::Repeated(l), ReflectFieldRef::Repeated(r)) => {
+                for i in 0..l.len().max(r.len()) {
+                    let l = (i < l.len()).then(|| l.get(i));
+                    let r = (i < r.len()).then(|| r.get(i));
+                    diff_value(l, r, format!("{}[{}]", path, i), differences);
+                }
+            }
+            (ReflectFieldRef::Map(l), ReflectFieldRef::Map(r)) => {
+                // Maps are unordered, compare entries in key order.
+                let mut keys: Vec<_> = (&l).into_iter().map(|(k, _)| k).collect();
+                keys.extend(
+                    (&r).into_iter()
+                        .map(|(k, _)| k)
+                        .filter(|k| l.get(k.clone()).is_none()),
+                );
+                keys.sort_by_key(format_key);
+                for key in keys {
+                    let path = format!("{}[{}]", path, format_key(&key));
+                    diff_value(l.get(key.clone()), r.get(key), path, differences);
+                }
+            }
+            _ => unreachable!("messages of the same type"),
+        }
+    }
+
+    let (l, r) = (left.special_fields_dyn(), right.special_fields_dyn());
+    if l.unknown_fields() != r.unknown_fields() {
+        differences.push(Difference {
+            path: join(path, "<unknown fields>"),
+            left: Some(format!("{:?}", l.unknown_fields())),
+            right: Some(format!("{:?}", r.unknown_fields())),
+        });
+    }
+}
+
+fn diff_value(
+    left: Option<ReflectValueRef<'_>>,
+    right: Option<ReflectValueRef<'_>>,
+    path: String,
+    differences: &mut Vec<Difference>,
+) {
+    match (&left, &right) {
+        (Some(ReflectValueRef::Message(l)), Some(ReflectValueRef::Message(r))) => {
+            diff_message(&**l, &**r, &path, differences)
+        }
+        (Some(l), Some(r)) if l.reflect_eq(r, &ReflectEqMode::nan_equal()) => {}
+        (None, None) => {}
+        _ => differences.push(Difference {
+            path,
+            left: left.as_ref().map(format_value),
+            right: right.as_ref().map(format_value),
+        }),
+    }
+}
+
+fn format_key(key: &ReflectValueRef<'_>) -> String {
+    format_value(key)
+}
+
+fn format_value(value: &ReflectValueRef<'_>) -> String {
+    match value {
+        ReflectValueRef::String(s) => format!("{:?}", s),
+        ReflectValueRef::Bytes(b) => format!("b\"{}\"", b.escape_ascii()),
+        ReflectValueRef::Message(m) => {
+            format!("{{ {} }}", protobuf::text_format::print_to_string(&**m))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Asserts that two rust-protobuf messages are equal, and otherwise panics
+/// listing the fields that differ.
+///
+/// ```rust,ignore
+/// assert_proto_eq!(response, expected);
+/// assert_proto_eq!(response, expected, "response of call {}", i);
+/// ```
+#[macro_export]
+macro_rules! assert_proto_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let differences = $crate::diff::diff(&$left, &$right);
+        if !differences.is_empty() {
+            panic!("{}", $crate::diff::format_differences(&differences));
+        }
+    }};
+    ($left:expr, $right:expr, $($arg:tt)+) => {{
+        let differences = $crate::diff::diff(&$left, &$right);
+        if !differences.is_empty() {
+            panic!(
+                "{}: {}",
+                format_args!($($arg)+),
+                $crate::diff::format_differences(&differences)
+            );
+        }
+    }};
+}
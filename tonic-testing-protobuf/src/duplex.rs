@@ -0,0 +1,98 @@
+//! In-process transport over a [`tokio::io::duplex`] pair.
+//!
+//! Serves generated services without binding a socket, so that service tests
+//! need no port management:
+//!
+//! ```rust,ignore
+//! let mut client = duplex::client(DebugServer::new(MyDebug), DebugClient::new).await;
+//! let reply = client.get(request).await?;
+//! ```
+
+use std::{convert::Infallible, io};
+
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Body as HttpBody, Bytes, Service, StdError},
+    server::NamedService,
+    transport::{
+        server::{Router, Routes},
+        Body, Channel, Endpoint, Server, Uri,
+    },
+};
+use tower::Layer;
+
+/// The buffer size of each direction of the duplex pair.
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Serves `router` on one end of a duplex pair, and returns a channel
+/// connected to the other end.
+///
+/// The router may have layers, e.g.
+/// `Server::builder().layer(layer).add_service(svc)`. The server runs on a
+/// spawned task until the channel is dropped.
+///
+/// # Panics
+///
+/// Panics if called outside of a tokio runtime, or if the channel can not be
+/// connected.
+pub async fn connect<L, ResBody>(router: Router<L>) -> Channel
+where
+    L: Layer<Routes> + Send + 'static,
+    L::Service:
+        Service<http::Request<Body>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    <L::Service as Service<http::Request<Body>>>::Future: Send + 'static,
+    <L::Service as Service<http::Request<Body>>>::Error: Into<StdError> + Send,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<StdError>,
+{
+    let (client, server) = tokio::io::duplex(BUFFER_SIZE);
+    tokio::spawn(router.serve_with_incoming(tokio_stream::once(Ok::<_, io::Error>(server))));
+
+    // A duplex pair carries exactly one connection, the channel can not
+    // reconnect once it is closed.
+    let mut client = Some(client);
+    Endpoint::from_static("http://[::]:50051")
+        .connect_with_connector(tower::service_fn(move |_: Uri| {
+            let client = client.take();
+            async move {
+                client.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotConnected, "duplex stream is closed")
+                })
+            }
+        }))
+        .await
+        .expect("connect to duplex stream")
+}
+
+/// Serves `service` on one end of a duplex pair, and returns a channel
+/// connected to the other end.
+///
+/// See [`connect`] for details.
+pub async fn connect_service<S>(service: S) -> Channel
+where
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>, Error = Infallible>
+        + NamedService
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    connect(Server::builder().add_service(service)).await
+}
+
+/// Serves `service` on one end of a duplex pair, and returns a client created
+/// by `new_client` over a channel connected to the other end, e.g.
+/// `duplex::client(DebugServer::new(svc), DebugClient::new)`.
+///
+/// See [`connect`] for details.
+pub async fn client<S, C>(service: S, new_client: impl FnOnce(Channel) -> C) -> C
+where
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>, Error = Infallible>
+        + NamedService
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    new_client(connect_service(service).await)
+}
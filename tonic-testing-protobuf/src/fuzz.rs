@@ -0,0 +1,63 @@
+//! Fuzzing of the methods of generated servers with arbitrary request
+//! bodies.
+//!
+//! [`call`] sends arbitrary bytes as the request body of a call to a server,
+//! so that they pass through the gRPC framing and the codec into the handler
+//! of the method, and reads the whole response. `tonic-build-protobuf` writes
+//! a cargo-fuzz target per method calling it with
+//! `Builder::write_fuzz_targets`:
+//!
+//! ```rust,ignore
+//! #![no_main]
+//!
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     ::tonic_testing_protobuf::fuzz::call(
+//!         my_service::services::debugpb::DebugServer::new(my_service::fuzz::debug()),
+//!         "/debugpb.Debug/Get",
+//!         data,
+//!     );
+//! });
+//! ```
+//!
+//! Panics of handlers are not caught, so that the fuzzer reports them. Request
+//! bodies captured from real traffic, e.g. the `.requests` files recorded by
+//! `grpc-proxy`, make a good seed corpus.
+
+use tonic::{
+    codegen::{http, Body as HttpBody, Bytes, Service},
+    transport::Body,
+};
+use tower::ServiceExt;
+
+use crate::runtime::block_on;
+
+/// Calls the method of `path`, e.g. `/pkg.Service/Method`, of `service` with
+/// `data` as the request body, i.e. length-prefixed gRPC frames, and reads
+/// the response to its end.
+///
+/// The call runs on a current-thread runtime of the calling thread.
+///
+/// # Panics
+///
+/// Panics if `path` is not a valid URI path, or if the handler panics.
+pub fn call<S, ResBody>(service: S, path: &str, data: &[u8])
+where
+    S: Service<http::Request<Body>, Response = http::Response<ResBody>>,
+    ResBody: HttpBody<Data = Bytes>,
+{
+    let request = http::Request::post(path)
+        .header(http::header::CONTENT_TYPE, "application/grpc")
+        .header(http::header::TE, "trailers")
+        .body(Body::from(data.to_vec()))
+        .expect("invalid method path");
+    block_on(async {
+        let Ok(response) = service.oneshot(request).await else {
+            return;
+        };
+        let mut body = std::pin::pin!(response.into_body());
+        while let Some(Ok(_)) = body.data().await {}
+        let _ = body.trailers().await;
+    });
+}
@@ -0,0 +1,12 @@
+//! Test support for tonic services generated by `tonic-build-protobuf`.
+
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod bench;
+pub mod diff;
+pub mod duplex;
+pub mod fuzz;
+pub mod random;
+pub mod replay;
+pub mod roundtrip;
+mod runtime;
@@ -0,0 +1,176 @@
+//! Random messages described by descriptors, e.g. to drive load tests or
+//! exercise service handlers with realistic payloads.
+//!
+//! Unlike the proptest strategies of `arbitrary`, messages are generated
+//! directly from a [`rand::Rng`] and their sizes are configured with ranges,
+//! so that payloads resemble production traffic rather than edge cases.
+//! Generated messages are valid: required fields are always set, enums only
+//! take declared values and at most one field of each oneof is set.
+//!
+//! ```rust,ignore
+//! let mut rng = StdRng::seed_from_u64(7);
+//! let config = Config { string_len: 64..=256, ..Default::default() };
+//! let request = config.generate(&Request::descriptor(), &mut rng);
+//! ```
+
+use std::ops::RangeInclusive;
+
+use protobuf::{
+    reflect::{FieldDescriptor, MessageDescriptor, ReflectValueBox, RuntimeFieldType, RuntimeType},
+    MessageDyn,
+};
+use rand::{distributions::Alphanumeric, seq::SliceRandom, Rng};
+
+/// Sizes of generated messages.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The length of string fields, in ASCII alphanumeric chars.
+    pub string_len: RangeInclusive<usize>,
+    /// The length of bytes fields.
+    pub bytes_len: RangeInclusive<usize>,
+    /// The number of elements of repeated and map fields.
+    pub repeated_len: RangeInclusive<usize>,
+    /// The maximum nesting of message fields, deeper message fields are
+    /// left unset unless they are required.
+    pub max_depth: usize,
+    /// The probability that a field without presence requirements is set,
+    /// between 0 and 1.
+    pub presence: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            string_len: 8..=32,
+            bytes_len: 8..=64,
+            repeated_len: 1..=4,
+            max_depth: 3,
+            presence: 1.0,
+        }
+    }
+}
+
+impl Config {
+    /// Generates a message of type `descriptor`.
+    pub fn generate<R: Rng + ?Sized>(
+        &self,
+        descriptor: &MessageDescriptor,
+        rng: &mut R,
+    ) -> Box<dyn MessageDyn> {
+        self.message(descriptor, self.max_depth, rng)
+    }
+
+    fn message<R: Rng + ?Sized>(
+        &self,
+        descriptor: &MessageDescriptor,
+        depth: usize,
+        rng: &mut R,
+    ) -> Box<dyn MessageDyn> {
+        let mut message = descriptor.new_instance();
+        for oneof in descriptor.oneofs().filter(|o| !o.is_synthetic()) {
+            let fields: Vec<_> = oneof.fields().collect();
+            if let Some(field) = fields.choose(rng) {
+                if rng.gen_bool(self.presence) {
+                    self.set_field(&mut *message, field, depth, rng);
+                }
+            }
+        }
+        for field in descriptor.fields() {
+            // Fields of oneofs were set above. Proto3 optional fields are in
+            // synthetic oneofs, which `containing_oneof` skips.
+            if field.containing_oneof().is_some()
+                || !(field.is_required() || rng.gen_bool(self.presence))
+            {
+                continue;
+            }
+            self.set_field(&mut *message, &field, depth, rng);
+        }
+        message
+    }
+
+    fn set_field<R: Rng + ?Sized>(
+        &self,
+        message: &mut dyn MessageDyn,
+        field: &FieldDescriptor,
+        depth: usize,
+        rng: &mut R,
+    ) {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(descriptor)) if field.is_required() => {
+                let value = self.message(&descriptor, depth.saturating_sub(1), rng);
+                field.set_singular_field(message, ReflectValueBox::Message(value));
+            }
+            RuntimeFieldType::Singular(ty) => {
+                if let Some(value) = self.value(&ty, depth, rng) {
+                    field.set_singular_field(message, value);
+                }
+            }
+            RuntimeFieldType::Repeated(ty) => {
+                let len = rng.gen_range(self.repeated_len.clone());
+                let mut repeated = field.mut_repeated(message);
+                for _ in 0..len {
+                    match self.value(&ty, depth, rng) {
+                        Some(value) => repeated.push(value),
+                        None => break,
+                    }
+                }
+            }
+            RuntimeFieldType::Map(key, ty) => {
+                let len = rng.gen_range(self.repeated_len.clone());
+                let mut map = field.mut_map(message);
+                for _ in 0..len {
+                    match (self.value(&key, depth, rng), self.value(&ty, depth, rng)) {
+                        (Some(key), Some(value)) => map.insert(key, value),
+                        _ => break,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `None` for messages beyond the maximum depth.
+    fn value<R: Rng + ?Sized>(
+        &self,
+        ty: &RuntimeType,
+        depth: usize,
+        rng: &mut R,
+    ) -> Option<ReflectValueBox> {
+        let value = match ty {
+            RuntimeType::I32 => ReflectValueBox::I32(rng.gen()),
+            RuntimeType::I64 => ReflectValueBox::I64(rng.gen()),
+            RuntimeType::U32 => ReflectValueBox::U32(rng.gen()),
+            RuntimeType::U64 => ReflectValueBox::U64(rng.gen()),
+            RuntimeType::F32 => ReflectValueBox::F32(rng.gen()),
+            RuntimeType::F64 => ReflectValueBox::F64(rng.gen()),
+            RuntimeType::Bool => ReflectValueBox::Bool(rng.gen()),
+            RuntimeType::String => {
+                let len = rng.gen_range(self.string_len.clone());
+                let chars = rng.sample_iter(Alphanumeric).take(len);
+                ReflectValueBox::String(chars.map(char::from).collect())
+            }
+            RuntimeType::VecU8 => {
+                let mut bytes = vec![0; rng.gen_range(self.bytes_len.clone())];
+                rng.fill(&mut bytes[..]);
+                ReflectValueBox::Bytes(bytes)
+            }
+            RuntimeType::Enum(descriptor) => {
+                let values: Vec<_> = descriptor.values().collect();
+                let number = values.choose(rng).map_or(0, |v| v.value());
+                ReflectValueBox::Enum(descriptor.clone(), number)
+            }
+            RuntimeType::Message(_) if depth == 0 => return None,
+            RuntimeType::Message(descriptor) => {
+                ReflectValueBox::Message(self.message(descriptor, depth - 1, rng))
+            }
+        };
+        Some(value)
+    }
+}
+
+/// Generates a message of type `descriptor` with the default [`Config`].
+pub fn message<R: Rng + ?Sized>(
+    descriptor: &MessageDescriptor,
+    rng: &mut R,
+) -> Box<dyn MessageDyn> {
+    Config::default().generate(descriptor, rng)
+}
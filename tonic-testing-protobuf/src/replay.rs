@@ -0,0 +1,438 @@
+//! Replay of recorded calls, for regression tests of service handlers.
+//!
+//! A [`Recording`] holds the request messages a method received, and the
+//! responses and status it answered with, e.g. read from capture files of
+//! length-prefixed gRPC frames like those printed by `grpc-decode`.
+//! [`replay`] sends the recorded requests to a service again, and compares
+//! its answer to the recorded one within a [`Tolerance`]:
+//!
+//! ```rust,ignore
+//! let recording = Recording::from_captures(method, &requests, &responses)?;
+//! let tolerance = Tolerance::new()
+//!     .ignore_field("updated_at")
+//!     .float_epsilon(1e-9);
+//! replay(channel, &recording, &tolerance).await?;
+//! ```
+//!
+//! Mismatches are reported with the paths of the differing fields, with
+//! values printed as `recorded != replayed`:
+//!
+//! ```text
+//! replay of /demo.Sensor/Read differs:
+//!   responses[0].value: 1.5 != 2.5
+//!   status: Ok != NotFound
+//! ```
+
+use protobuf::{
+    reflect::{ReflectFieldRef, ReflectValueBox, ReflectValueRef, RuntimeFieldType, RuntimeType},
+    MessageDyn,
+};
+use tonic::{
+    client::Grpc,
+    codegen::{http::uri::PathAndQuery, Bytes},
+    transport::Channel,
+    Code, Request,
+};
+use tonic_codec_protobuf::RawCodec;
+use tonic_dynamic_protobuf::{frame, json, DynamicMethod};
+
+use crate::diff::diff;
+
+/// A recorded call of a method.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// The called method.
+    pub method: DynamicMethod,
+    /// The encoded request messages, in the order they were sent.
+    pub requests: Vec<Bytes>,
+    /// The encoded response messages, in the order they were received.
+    pub responses: Vec<Bytes>,
+    /// The status code the call ended with.
+    pub code: Code,
+    /// The status message the call ended with.
+    pub message: String,
+}
+
+impl Recording {
+    /// Create a recording of a call of `method` without messages, that ended
+    /// with `OK`.
+    pub fn new(method: DynamicMethod) -> Self {
+        Recording {
+            method,
+            requests: vec![],
+            responses: vec![],
+            code: Code::Ok,
+            message: String::new(),
+        }
+    }
+
+    /// Create a recording of a call of `method` that ended with `OK`, from
+    /// the captured request and response bodies, i.e. concatenated gRPC
+    /// frames.
+    ///
+    /// Compressed frames are not supported.
+    pub fn from_captures(
+        method: DynamicMethod,
+        requests: &[u8],
+        responses: &[u8],
+    ) -> Result<Self, String> {
+        let mut recording = Recording::new(method);
+        recording.requests = messages(requests).map_err(|e| format!("requests: {}", e))?;
+        recording.responses = messages(responses).map_err(|e| format!("responses: {}", e))?;
+        Ok(recording)
+    }
+
+    /// Set the status the call ended with.
+    #[must_use]
+    pub fn status(mut self, code: Code, message: impl Into<String>) -> Self {
+        self.code = code;
+        self.message = message.into();
+        self
+    }
+}
+
+fn messages(capture: &[u8]) -> Result<Vec<Bytes>, String> {
+    frame::frames(capture)
+        .map(|frame| {
+            let frame = frame.map_err(|e| e.to_string())?;
+            if frame.compressed {
+                return Err(format!("frame at offset {} is compressed", frame.offset));
+            }
+            Ok(Bytes::copy_from_slice(frame.data))
+        })
+        .collect()
+}
+
+/// The differences between recorded and replayed responses that are
+/// accepted.
+///
+/// By default, responses must be equal and in the same order, and the status
+/// must have the same code and message.
+#[derive(Debug, Clone, Default)]
+pub struct Tolerance {
+    ignored_fields: Vec<String>,
+    float_epsilon: f64,
+    unordered_responses: bool,
+    ignore_status_message: bool,
+}
+
+impl Tolerance {
+    /// Create a tolerance that accepts no differences.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore the field at `path` of the responses, given as field names
+    /// separated by dots, e.g. `items.updated_at`. Paths through repeated
+    /// and map fields apply to all of their values.
+    #[must_use]
+    pub fn ignore_field(mut self, path: impl Into<String>) -> Self {
+        self.ignored_fields.push(path.into());
+        self
+    }
+
+    /// Accept `float` and `double` values that differ by at most `epsilon`.
+    #[must_use]
+    pub fn float_epsilon(mut self, epsilon: f64) -> Self {
+        self.float_epsilon = epsilon;
+        self
+    }
+
+    /// Accept responses of a streaming method in any order.
+    #[must_use]
+    pub fn unordered_responses(mut self) -> Self {
+        self.unordered_responses = true;
+        self
+    }
+
+    /// Accept a different status message, as long as the code is the same.
+    #[must_use]
+    pub fn ignore_status_message(mut self) -> Self {
+        self.ignore_status_message = true;
+        self
+    }
+}
+
+/// Sends the requests of `recording` to its method on `channel`, and checks
+/// that the responses and status match the recorded ones within
+/// `tolerance`.
+///
+/// Any method shape is replayed as a bidirectional streaming call, which is
+/// the same on the wire.
+pub async fn replay(
+    channel: Channel,
+    recording: &Recording,
+    tolerance: &Tolerance,
+) -> Result<(), String> {
+    let path = recording.method.path();
+    let (responses, code, message) = call(channel, recording).await?;
+
+    let mut mismatches = compare_responses(recording, responses, tolerance);
+    if code != recording.code {
+        mismatches.push(format!("status: {:?} != {:?}", recording.code, code));
+    }
+    if !tolerance.ignore_status_message && message != recording.message {
+        mismatches.push(format!(
+            "status message: {:?} != {:?}",
+            recording.message, message
+        ));
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "replay of {} differs:\n  {}",
+            path,
+            mismatches.join("\n  ")
+        ))
+    }
+}
+
+async fn call(
+    channel: Channel,
+    recording: &Recording,
+) -> Result<(Vec<Bytes>, Code, String), String> {
+    let mut grpc = Grpc::new(channel);
+    grpc.ready()
+        .await
+        .map_err(|e| format!("service was not ready: {}", e))?;
+    let path = PathAndQuery::try_from(recording.method.path())
+        .map_err(|e| format!("invalid method path: {}", e))?;
+    let requests = tokio_stream::iter(recording.requests.clone());
+
+    let mut responses = vec![];
+    let status = match grpc
+        .streaming(Request::new(requests), path, RawCodec::default())
+        .await
+    {
+        Ok(response) => {
+            let mut stream = response.into_inner();
+            loop {
+                match stream.message().await {
+                    Ok(Some(message)) => responses.push(message),
+                    Ok(None) => break None,
+                    Err(status) => break Some(status),
+                }
+            }
+        }
+        Err(status) => Some(status),
+    };
+    Ok(match status {
+        Some(status) => (responses, status.code(), status.message().to_owned()),
+        None => (responses, Code::Ok, String::new()),
+    })
+}
+
+fn compare_responses(
+    recording: &Recording,
+    replayed: Vec<Bytes>,
+    tolerance: &Tolerance,
+) -> Vec<String> {
+    let mut mismatches = vec![];
+    let decode = |kind: &str, index: usize, bytes: &[u8]| {
+        let mut message = recording
+            .method
+            .output()
+            .parse_from_bytes(bytes)
+            .map_err(|e| {
+                format!(
+                    "responses[{}]: can not decode {} response: {}",
+                    index, kind, e
+                )
+            })?;
+        for path in &tolerance.ignored_fields {
+            clear_field(&mut *message, path);
+        }
+        Ok::<_, String>(message)
+    };
+    let (mut recorded, mut actual) = (vec![], vec![]);
+    for (index, bytes) in recording.responses.iter().enumerate() {
+        match decode("recorded", index, bytes) {
+            Ok(message) => recorded.push(message),
+            Err(e) => mismatches.push(e),
+        }
+    }
+    for (index, bytes) in replayed.iter().enumerate() {
+        match decode("replayed", index, bytes) {
+            Ok(message) => actual.push(message),
+            Err(e) => mismatches.push(e),
+        }
+    }
+    if !mismatches.is_empty() {
+        return mismatches;
+    }
+
+    if tolerance.unordered_responses {
+        let mut unmatched: Vec<_> = recorded.iter().map(Some).collect();
+        for message in &actual {
+            let found = unmatched.iter_mut().find(|expected| {
+                expected.is_some_and(|expected| {
+                    let mut message = message.clone_box();
+                    snap_floats(&mut *message, &**expected, tolerance.float_epsilon);
+                    diff(&**expected, &*message).is_empty()
+                })
+            });
+            match found {
+                Some(expected) => *expected = None,
+                None => mismatches.push(format!(
+                    "unexpected response: {}",
+                    json::print_to_string(&**message)
+                )),
+            }
+        }
+        for expected in unmatched.into_iter().flatten() {
+            mismatches.push(format!(
+                "missing response: {}",
+                json::print_to_string(&**expected)
+            ));
+        }
+        return mismatches;
+    }
+
+    for (index, (expected, message)) in recorded.iter().zip(&mut actual).enumerate() {
+        snap_floats(&mut **message, &**expected, tolerance.float_epsilon);
+        for difference in diff(&**expected, &**message) {
+            mismatches.push(format!("responses[{}].{}", index, difference));
+        }
+    }
+    for (index, expected) in recorded.iter().enumerate().skip(actual.len()) {
+        mismatches.push(format!(
+            "missing responses[{}]: {}",
+            index,
+            json::print_to_string(&**expected)
+        ));
+    }
+    for (index, message) in actual.iter().enumerate().skip(recorded.len()) {
+        mismatches.push(format!(
+            "unexpected responses[{}]: {}",
+            index,
+            json::print_to_string(&**message)
+        ));
+    }
+    mismatches
+}
+
+/// Clears the field at the dot separated `path` of `message`.
+fn clear_field(message: &mut dyn MessageDyn, path: &str) {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let Some(field) = message.descriptor_dyn().field_by_name(name) else {
+        return;
+    };
+    let Some(rest) = rest else {
+        field.clear_field(message);
+        return;
+    };
+
+    match field.get_reflect(&*message) {
+        ReflectFieldRef::Optional(value) => {
+            if let Some(ReflectValueRef::Message(_)) = value.value() {
+                clear_field(field.mut_message(message), rest);
+            }
+        }
+        ReflectFieldRef::Repeated(repeated) => {
+            if !matches!(repeated.element_type(), RuntimeType::Message(_)) {
+                return;
+            }
+            let len = repeated.len();
+            let mut repeated = field.mut_repeated(message);
+            for index in 0..len {
+                let mut value = repeated.get(index).to_box();
+                if let ReflectValueBox::Message(element) = &mut value {
+                    clear_field(&mut **element, rest);
+                }
+                repeated.set(index, value);
+            }
+        }
+        ReflectFieldRef::Map(map) => {
+            if !matches!(map.value_type(), RuntimeType::Message(_)) {
+                return;
+            }
+            let entries: Vec<_> = map
+                .into_iter()
+                .map(|(key, value)| (key.to_box(), value.to_box()))
+                .collect();
+            let mut map = field.mut_map(message);
+            for (key, mut value) in entries {
+                if let ReflectValueBox::Message(element) = &mut value {
+                    clear_field(&mut **element, rest);
+                }
+                map.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Replaces the `float` and `double` values of `actual` with those of
+/// `expected` where they differ by at most `epsilon`, so that they compare
+/// equal.
+fn snap_floats(actual: &mut dyn MessageDyn, expected: &dyn MessageDyn, epsilon: f64) {
+    if epsilon <= 0.0 || actual.descriptor_dyn() != expected.descriptor_dyn() {
+        return;
+    }
+    for field in expected.descriptor_dyn().fields() {
+        let snappable = |t: &RuntimeType| {
+            matches!(
+                t,
+                RuntimeType::F32 | RuntimeType::F64 | RuntimeType::Message(_)
+            )
+        };
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(t) if snappable(&t) => {
+                let (Some(e), Some(a)) = (field.get_singular(expected), field.get_singular(actual))
+                else {
+                    continue;
+                };
+                let value = snap(a.to_box(), &e, epsilon);
+                field.set_singular_field(actual, value);
+            }
+            RuntimeFieldType::Repeated(t) if snappable(&t) => {
+                let e = field.get_repeated(expected);
+                if e.len() != field.get_repeated(actual).len() {
+                    continue;
+                }
+                let mut a = field.mut_repeated(actual);
+                for index in 0..e.len() {
+                    let value = snap(a.get(index).to_box(), &e.get(index), epsilon);
+                    a.set(index, value);
+                }
+            }
+            RuntimeFieldType::Map(_, t) if snappable(&t) => {
+                let snapped: Vec<_> = field
+                    .get_map(expected)
+                    .into_iter()
+                    .filter_map(|(key, e)| {
+                        let a = field.get_map(actual).get(key.clone())?.to_box();
+                        Some((key.to_box(), snap(a, &e, epsilon)))
+                    })
+                    .collect();
+                let mut a = field.mut_map(actual);
+                for (key, value) in snapped {
+                    a.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn snap(actual: ReflectValueBox, expected: &ReflectValueRef, epsilon: f64) -> ReflectValueBox {
+    match (actual, expected) {
+        (ReflectValueBox::F32(a), ReflectValueRef::F32(e))
+            if (f64::from(a) - f64::from(*e)).abs() <= epsilon =>
+        {
+            ReflectValueBox::F32(*e)
+        }
+        (ReflectValueBox::F64(a), ReflectValueRef::F64(e)) if (a - e).abs() <= epsilon => {
+            ReflectValueBox::F64(*e)
+        }
+        (ReflectValueBox::Message(mut a), ReflectValueRef::Message(e)) => {
+            snap_floats(&mut *a, &**e, epsilon);
+            ReflectValueBox::Message(a)
+        }
+        (actual, _) => actual,
+    }
+}
@@ -0,0 +1,98 @@
+//! Round-trip checks of messages through the codecs.
+//!
+//! Messages are encoded into the body of a request and decoded from the
+//! body of the response of a server echoing it, the way a tonic client and
+//! server do in a call, see [`bench::roundtrip`](crate::bench::roundtrip).
+//! Dynamic messages, e.g. generated by [`arbitrary`](crate::arbitrary), go
+//! through `DynamicCodec` and the proto3 JSON mapping, generated messages
+//! through `ProtobufCodecV3` and `JsonCodec` with [`check_codecs`], and, with
+//! the `protobuf-v2` feature, messages generated by rust-protobuf v2 through
+//! `ProtobufCodecV2` with [`check_v2`].
+//!
+//! The checks run the call on a current-thread runtime of the calling thread,
+//! and panic if called from within a runtime.
+
+use protobuf::{MessageDyn, MessageFull};
+use tonic_codec_protobuf::{DynamicCodec, ProtobufCodecV3};
+use tonic_dynamic_protobuf::{json, JsonCodec};
+
+use crate::{
+    bench::try_roundtrip,
+    diff::{diff, format_differences},
+};
+
+/// Checks that `message` survives both the binary and the JSON round trip.
+pub fn check(message: &dyn MessageDyn) -> Result<(), String> {
+    check_binary(message)?;
+    check_json(message)
+}
+
+/// Checks that `message` survives an encode → decode round trip through
+/// `DynamicCodec`.
+pub fn check_binary(message: &dyn MessageDyn) -> Result<(), String> {
+    let codec = DynamicCodec::new(message.descriptor_dyn());
+    let decoded = try_roundtrip(codec, message.clone_box())
+        .map_err(|status| format!("DynamicCodec: {}", status.message()))?;
+    compare("binary", message, &*decoded)
+}
+
+/// Checks that `message` survives a print → parse round trip in the proto3
+/// JSON mapping, which `JsonCodec` uses.
+pub fn check_json(message: &dyn MessageDyn) -> Result<(), String> {
+    let printed = json::print_to_string(message);
+    let parsed = json::parse_from_str(&message.descriptor_dyn(), &printed)
+        .map_err(|e| format!("parse JSON {}: {}", printed, e))?;
+    compare("JSON", message, &*parsed)
+}
+
+/// Checks that the generated `message` survives a round trip through
+/// `ProtobufCodecV3` and through `JsonCodec`.
+pub fn check_codecs<M: MessageFull>(message: &M) -> Result<(), String> {
+    let decoded = try_roundtrip(ProtobufCodecV3::<M, M>::default(), message.clone())
+        .map_err(|status| format!("ProtobufCodecV3: {}", status.message()))?;
+    compare("ProtobufCodecV3", message, &decoded)?;
+    let decoded = try_roundtrip(JsonCodec::<M, M>::default(), message.clone())
+        .map_err(|status| format!("JsonCodec: {}", status.message()))?;
+    compare("JsonCodec", message, &decoded)
+}
+
+/// Checks that `message`, generated by rust-protobuf v2, survives a round
+/// trip through `ProtobufCodecV2`.
+#[cfg(feature = "protobuf-v2")]
+pub fn check_v2<M>(message: &M) -> Result<(), String>
+where
+    M: protobuf2::Message + Clone + Default + PartialEq,
+{
+    use tonic_codec_protobuf::ProtobufCodecV2;
+
+    let decoded = try_roundtrip(ProtobufCodecV2::<M, M>::default(), message.clone())
+        .map_err(|status| format!("ProtobufCodecV2: {}", status.message()))?;
+    if decoded == *message {
+        Ok(())
+    } else {
+        Err(format!(
+            "ProtobufCodecV2 round trip of {} failed:\n  expected: {:?}\n  actual:   {:?}",
+            M::descriptor_static().full_name(),
+            message,
+            decoded
+        ))
+    }
+}
+
+fn compare(
+    encoding: &str,
+    original: &dyn MessageDyn,
+    decoded: &dyn MessageDyn,
+) -> Result<(), String> {
+    let differences = diff(original, decoded);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} round trip of {} failed, {}",
+            encoding,
+            original.descriptor_dyn().full_name(),
+            format_differences(&differences)
+        ))
+    }
+}
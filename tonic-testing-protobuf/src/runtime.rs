@@ -0,0 +1,29 @@
+//! The runtime of the synchronous helpers that drive services.
+
+use std::{cell::OnceCell, future::Future};
+
+use tokio::runtime::{Builder, Runtime};
+
+thread_local! {
+    /// The runtime of each calling thread, reused across calls.
+    static RUNTIME: OnceCell<Runtime> = const { OnceCell::new() };
+}
+
+/// Runs `future` to completion on a current-thread runtime of the calling
+/// thread.
+///
+/// # Panics
+///
+/// Panics if called from within a runtime.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.with(|runtime| {
+        runtime
+            .get_or_init(|| {
+                Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to build the runtime")
+            })
+            .block_on(future)
+    })
+}
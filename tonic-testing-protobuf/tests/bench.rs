@@ -0,0 +1,36 @@
+use tonic_codec_protobuf::ProtobufCodecV3;
+use tonic_health_protobuf::pb::health::HealthCheckRequest;
+use tonic_testing_protobuf::bench;
+
+#[test]
+fn test_roundtrip() {
+    let mut request = HealthCheckRequest::new();
+    request.service = "foo".to_owned();
+    let decoded = bench::roundtrip(ProtobufCodecV3::default(), request.clone());
+    assert_eq!(decoded, request);
+    let decoded = bench::roundtrip(ProtobufCodecV3::default(), HealthCheckRequest::new());
+    assert_eq!(decoded, HealthCheckRequest::new());
+}
+
+#[test]
+fn test_samples() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_eq!(
+        bench::samples::<HealthCheckRequest>(dir.path()),
+        [("default".to_owned(), HealthCheckRequest::new())]
+    );
+
+    let samples = dir.path().join("grpc.health.v1.HealthCheckRequest");
+    std::fs::create_dir(&samples).unwrap();
+    std::fs::write(samples.join("b_json.json"), r#"{"service": "bar"}"#).unwrap();
+    std::fs::write(samples.join("a_text.txtpb"), r#"service: "foo""#).unwrap();
+    std::fs::write(samples.join("README.md"), "ignored").unwrap();
+    let names: Vec<_> = bench::samples::<HealthCheckRequest>(dir.path())
+        .into_iter()
+        .map(|(name, message)| format!("{}: {:?}", name, message.service))
+        .collect();
+    assert_eq!(
+        names,
+        ["default: \"\"", "a_text: \"foo\"", "b_json: \"bar\""]
+    );
+}
@@ -0,0 +1,88 @@
+use protobuf::{
+    descriptor::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto},
+    well_known_types::struct_::{Struct, Value},
+};
+use tonic_testing_protobuf::{
+    assert_proto_eq,
+    diff::{diff, Difference},
+};
+
+fn field(name: &str, number: i32) -> FieldDescriptorProto {
+    let mut field = FieldDescriptorProto::new();
+    field.set_name(name.to_owned());
+    field.set_number(number);
+    field
+}
+
+fn difference(path: &str, left: Option<&str>, right: Option<&str>) -> Difference {
+    Difference {
+        path: path.to_owned(),
+        left: left.map(str::to_owned),
+        right: right.map(str::to_owned),
+    }
+}
+
+#[test]
+fn test_diff() {
+    let mut left = DescriptorProto::new();
+    left.set_name("A".to_owned());
+    left.field.push(field("a", 1));
+    left.field.push(field("b", 2));
+    let mut right = left.clone();
+    assert_eq!(diff(&left, &right), []);
+    assert_proto_eq!(left, right);
+
+    right.set_name("B".to_owned());
+    right.field[1].set_number(3);
+    right.field.push(field("c", 4));
+    assert_eq!(
+        diff(&left, &right),
+        [
+            difference("name", Some("\"A\""), Some("\"B\"")),
+            difference("field[1].number", Some("2"), Some("3")),
+            difference("field[2]", None, Some("{ name: \"c\" number: 4 }")),
+        ]
+    );
+
+    assert_eq!(
+        diff(&left, &FileDescriptorProto::new()),
+        [difference(
+            ".",
+            Some("google.protobuf.DescriptorProto"),
+            Some("google.protobuf.FileDescriptorProto")
+        )]
+    );
+}
+
+#[test]
+fn test_diff_map() {
+    let mut value = Value::new();
+    value.set_number_value(f64::NAN);
+    let mut left = Struct::new();
+    left.fields.insert("nan".to_owned(), value.clone());
+    left.fields.insert("a".to_owned(), value.clone());
+    let mut right = left.clone();
+    assert_eq!(diff(&left, &right), []);
+
+    value.set_string_value("x".to_owned());
+    right.fields.insert("a".to_owned(), value.clone());
+    right.fields.insert("b".to_owned(), value);
+    left.fields.remove("nan");
+    assert_eq!(
+        diff(&left, &right),
+        [
+            difference("fields[\"a\"].number_value", Some("NaN"), None),
+            difference("fields[\"a\"].string_value", None, Some("\"x\"")),
+            difference("fields[\"b\"]", None, Some("{ string_value: \"x\" }")),
+            difference("fields[\"nan\"]", None, Some("{ number_value: NaN }")),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "response 1: messages differ:\n  name: \"A\" != <unset>")]
+fn test_assert_proto_eq() {
+    let mut left = DescriptorProto::new();
+    left.set_name("A".to_owned());
+    assert_proto_eq!(left, DescriptorProto::new(), "response {}", 1);
+}
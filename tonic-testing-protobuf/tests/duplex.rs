@@ -0,0 +1,39 @@
+use tonic_health_protobuf::pb::{
+    health::{health_check_response::ServingStatus, HealthCheckRequest},
+    health_tonic::health_client::HealthClient,
+};
+use tonic_testing_protobuf::duplex;
+
+#[tokio::test]
+async fn test_client() {
+    let (mut reporter, service) = tonic_health_protobuf::health_reporter();
+    reporter
+        .set_service_status("foo", tonic_health_protobuf::ServingStatus::Serving)
+        .await;
+    let mut client = duplex::client(service, HealthClient::new).await;
+
+    let mut request = HealthCheckRequest::new();
+    request.service = "foo".to_owned();
+    let response = client.check(request).await.unwrap().into_inner();
+    assert_eq!(response.status.enum_value(), Ok(ServingStatus::SERVING));
+
+    // The channel can be shared by several clients.
+    let mut request = HealthCheckRequest::new();
+    request.service = "bar".to_owned();
+    let status = client.clone().check(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_connect_router() {
+    let (_, service) = tonic_health_protobuf::health_reporter();
+    let router = tonic::transport::Server::builder().add_service(service);
+    let mut client = HealthClient::new(duplex::connect(router).await);
+
+    let response = client
+        .check(HealthCheckRequest::new())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.status.enum_value(), Ok(ServingStatus::SERVING));
+}
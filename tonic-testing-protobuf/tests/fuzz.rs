@@ -0,0 +1,69 @@
+use std::{
+    convert::Infallible,
+    panic,
+    sync::{Arc, Mutex},
+};
+
+use protobuf::Message;
+use tonic::{
+    body::{empty_body, BoxBody},
+    codegen::{http, Body as _},
+    transport::Body,
+};
+use tonic_dynamic_protobuf::frame;
+use tonic_health_protobuf::pb::health::HealthCheckRequest;
+use tonic_testing_protobuf::fuzz;
+
+#[test]
+fn test_call() {
+    let (_, service) = tonic_health_protobuf::health_reporter();
+    let mut data = vec![];
+    frame::encode(
+        false,
+        &HealthCheckRequest::new().write_to_bytes().unwrap(),
+        &mut data,
+    )
+    .unwrap();
+    let path = "/grpc.health.v1.Health/Check";
+    fuzz::call(service.clone(), path, &data);
+    // Malformed frames and messages are rejected by the server.
+    fuzz::call(service.clone(), path, &[0, 0, 0, 0, 3, 0xff, 0xff, 0xff]);
+    fuzz::call(service, path, &[1, 2, 3]);
+}
+
+#[test]
+fn test_call_request() {
+    let received = Arc::new(Mutex::new(None));
+    let service = {
+        let received = received.clone();
+        tower::service_fn(move |request: http::Request<Body>| {
+            let received = received.clone();
+            async move {
+                let path = request.uri().path().to_owned();
+                let content_type = request.headers()[http::header::CONTENT_TYPE].clone();
+                let mut body = request.into_body();
+                let mut data = vec![];
+                while let Some(chunk) = body.data().await {
+                    data.extend_from_slice(&chunk.unwrap());
+                }
+                *received.lock().unwrap() = Some((path, content_type, data));
+                Ok::<_, Infallible>(http::Response::new(empty_body()))
+            }
+        })
+    };
+    fuzz::call(service, "/pkg.Service/Method", b"data");
+    let (path, content_type, body) = received.lock().unwrap().take().unwrap();
+    assert_eq!(path, "/pkg.Service/Method");
+    assert_eq!(content_type, "application/grpc");
+    assert_eq!(body, b"data");
+
+    // Panics of handlers reach the fuzzer.
+    let service = tower::service_fn(|_: http::Request<Body>| async {
+        if true {
+            panic!("bug in handler");
+        }
+        Ok::<_, Infallible>(http::Response::new(BoxBody::default()))
+    });
+    let result = panic::catch_unwind(|| fuzz::call(service, "/pkg.Service/Method", b""));
+    assert!(result.is_err());
+}
@@ -0,0 +1,70 @@
+use protobuf::{
+    descriptor::FileDescriptorProto,
+    well_known_types::{api::Api, struct_::Value},
+    MessageDyn, MessageFull,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use tonic_testing_protobuf::{
+    random::{self, Config},
+    roundtrip,
+};
+
+fn downcast<M: MessageFull>(message: Box<dyn MessageDyn>) -> M {
+    *message.downcast_box::<M>().unwrap()
+}
+
+#[test]
+fn test_valid() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..32 {
+        let message = random::message(&FileDescriptorProto::descriptor(), &mut rng);
+        assert!(message.is_initialized_dyn());
+        roundtrip::check(&*message).unwrap();
+
+        let value: Value = downcast(random::message(&Value::descriptor(), &mut rng));
+        assert!(value.kind.is_some());
+    }
+}
+
+#[test]
+fn test_seeded() {
+    let generate = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        random::message(&Api::descriptor(), &mut rng)
+            .write_to_bytes_dyn()
+            .unwrap()
+    };
+    assert_eq!(generate(7), generate(7));
+    assert_ne!(generate(7), generate(8));
+}
+
+#[test]
+fn test_sizes() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let config = Config {
+        string_len: 5..=5,
+        repeated_len: 2..=2,
+        ..Default::default()
+    };
+    let api: Api = downcast(config.generate(&Api::descriptor(), &mut rng));
+    assert_eq!(api.name.len(), 5);
+    assert_eq!(api.methods.len(), 2);
+    assert!(api.methods.iter().all(|m| m.request_type_url.len() == 5));
+    assert_eq!(api.source_context.file_name.len(), 5);
+
+    let config = Config {
+        presence: 0.0,
+        ..Default::default()
+    };
+    let api: Api = downcast(config.generate(&Api::descriptor(), &mut rng));
+    assert_eq!(api, Api::new());
+
+    let config = Config {
+        max_depth: 0,
+        ..Default::default()
+    };
+    let api: Api = downcast(config.generate(&Api::descriptor(), &mut rng));
+    assert!(api.methods.is_empty());
+    assert!(api.source_context.is_none());
+    assert!(!api.name.is_empty());
+}
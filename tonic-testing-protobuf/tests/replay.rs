@@ -0,0 +1,290 @@
+use protobuf::{descriptor::FileDescriptorProto, text_format, MessageDyn};
+use tonic::{codegen::Bytes, transport::Server, Code, Response, Status};
+use tonic_dynamic_protobuf::{frame, json, DescriptorPool, DynamicMessage, DynamicServer};
+use tonic_testing_protobuf::{
+    duplex,
+    replay::{replay, Recording, Tolerance},
+};
+
+const SENSOR: &str = r#"
+name: "sensor.proto"
+package: "demo"
+syntax: "proto3"
+message_type {
+  name: "Query"
+  field { name: "id" number: 1 type: TYPE_STRING label: LABEL_OPTIONAL json_name: "id" }
+}
+message_type {
+  name: "Reading"
+  field { name: "id" number: 1 type: TYPE_STRING label: LABEL_OPTIONAL json_name: "id" }
+  field { name: "value" number: 2 type: TYPE_DOUBLE label: LABEL_OPTIONAL json_name: "value" }
+  field { name: "at" number: 3 type: TYPE_INT64 label: LABEL_OPTIONAL json_name: "at" }
+}
+service {
+  name: "Sensor"
+  method { name: "Read" input_type: ".demo.Query" output_type: ".demo.Reading" }
+  method {
+    name: "List"
+    input_type: ".demo.Query"
+    output_type: ".demo.Reading"
+    server_streaming: true
+  }
+}
+"#;
+
+fn pool() -> DescriptorPool {
+    let file: FileDescriptorProto = text_format::parse_from_str(SENSOR).unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_set(protobuf::descriptor::FileDescriptorSet {
+        file: vec![file],
+        ..Default::default()
+    })
+    .unwrap();
+    pool
+}
+
+/// How the served handlers answer.
+#[derive(Clone, Copy, Default)]
+struct Handlers {
+    /// Added to all values.
+    offset: f64,
+    /// The timestamp of all readings.
+    at: i64,
+    /// Whether `List` returns its readings in reverse order.
+    reversed: bool,
+}
+
+fn reading(pool: &DescriptorPool, id: &str, value: f64, at: i64) -> DynamicMessage {
+    let json = format!(r#"{{"id": "{id}", "value": {value}, "at": "{at}"}}"#);
+    json::parse_from_str(&pool.message("demo.Reading").unwrap(), &json).unwrap()
+}
+
+fn encode(message: &dyn MessageDyn) -> Bytes {
+    message.write_to_bytes_dyn().unwrap().into()
+}
+
+async fn serve(handlers: Handlers) -> tonic::transport::Channel {
+    let pool = pool();
+    let read_pool = pool.clone();
+    let list_pool = pool.clone();
+    let server = DynamicServer::new()
+        .unary(&pool.method("demo.Sensor/Read").unwrap(), move |request| {
+            let pool = read_pool.clone();
+            async move {
+                let query = json::print_to_string(&**request.get_ref());
+                if query.contains("missing") {
+                    return Err(Status::not_found(format!("no sensor {}", handlers.at)));
+                }
+                Ok(Response::new(reading(
+                    &pool,
+                    "a",
+                    1.5 + handlers.offset,
+                    handlers.at,
+                )))
+            }
+        })
+        .server_streaming(&pool.method("demo.Sensor/List").unwrap(), move |_| {
+            let pool = list_pool.clone();
+            async move {
+                let mut readings = vec![
+                    Ok(reading(&pool, "a", 1.5 + handlers.offset, handlers.at)),
+                    Ok(reading(&pool, "b", 2.5 + handlers.offset, handlers.at)),
+                ];
+                if handlers.reversed {
+                    readings.reverse();
+                }
+                Ok(Response::new(
+                    Box::pin(tokio_stream::iter(readings)) as tonic::codegen::BoxStream<_>
+                ))
+            }
+        });
+    let (_, health) = tonic_health_protobuf::health_reporter();
+    duplex::connect(
+        Server::builder()
+            .layer(server.into_layer())
+            .add_service(health),
+    )
+    .await
+}
+
+fn read_recording(pool: &DescriptorPool) -> Recording {
+    let query =
+        json::parse_from_str(&pool.message("demo.Query").unwrap(), r#"{"id": "a"}"#).unwrap();
+    let mut recording = Recording::new(pool.method("demo.Sensor/Read").unwrap());
+    recording.requests.push(encode(&*query));
+    recording
+        .responses
+        .push(encode(&*reading(pool, "a", 1.5, 0)));
+    recording
+}
+
+fn list_recording(pool: &DescriptorPool) -> Recording {
+    let query = json::parse_from_str(&pool.message("demo.Query").unwrap(), "{}").unwrap();
+    let mut recording = Recording::new(pool.method("demo.Sensor/List").unwrap());
+    recording.requests.push(encode(&*query));
+    recording
+        .responses
+        .push(encode(&*reading(pool, "a", 1.5, 0)));
+    recording
+        .responses
+        .push(encode(&*reading(pool, "b", 2.5, 0)));
+    recording
+}
+
+#[tokio::test]
+async fn test_replay_matches() {
+    let pool = pool();
+    let channel = serve(Handlers::default()).await;
+    replay(channel.clone(), &read_recording(&pool), &Tolerance::new())
+        .await
+        .unwrap();
+    replay(channel, &list_recording(&pool), &Tolerance::new())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_from_captures() {
+    let pool = pool();
+    let recorded = read_recording(&pool);
+    let (mut requests, mut responses) = (vec![], vec![]);
+    frame::encode(false, &recorded.requests[0], &mut requests).unwrap();
+    frame::encode(false, &recorded.responses[0], &mut responses).unwrap();
+    let recording =
+        Recording::from_captures(recorded.method.clone(), &requests, &responses).unwrap();
+    assert_eq!(recording.requests, recorded.requests);
+    assert_eq!(recording.responses, recorded.responses);
+
+    let channel = serve(Handlers::default()).await;
+    replay(channel, &recording, &Tolerance::new())
+        .await
+        .unwrap();
+
+    let error = Recording::from_captures(recorded.method, &requests[..3], &responses).unwrap_err();
+    assert!(error.starts_with("requests: "), "{}", error);
+}
+
+#[tokio::test]
+async fn test_replay_reports_differences() {
+    let pool = pool();
+    let channel = serve(Handlers {
+        offset: 1.0,
+        at: 7,
+        ..Default::default()
+    })
+    .await;
+    let error = replay(channel, &read_recording(&pool), &Tolerance::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "replay of /demo.Sensor/Read differs:\n  responses[0].value: 1.5 != 2.5\n  responses[0].at: <unset> != 7"
+    );
+}
+
+#[tokio::test]
+async fn test_replay_tolerance() {
+    let pool = pool();
+    let channel = serve(Handlers {
+        offset: 1e-7,
+        at: 7,
+        ..Default::default()
+    })
+    .await;
+    let tolerance = Tolerance::new().ignore_field("at").float_epsilon(1e-6);
+    replay(channel.clone(), &read_recording(&pool), &tolerance)
+        .await
+        .unwrap();
+    replay(channel.clone(), &list_recording(&pool), &tolerance)
+        .await
+        .unwrap();
+
+    let tolerance = Tolerance::new().ignore_field("at").float_epsilon(1e-8);
+    let error = replay(channel, &read_recording(&pool), &tolerance)
+        .await
+        .unwrap_err();
+    assert!(
+        error.contains("responses[0].value: 1.5 != 1.5000001"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_replay_unordered_responses() {
+    let pool = pool();
+    let channel = serve(Handlers {
+        reversed: true,
+        ..Default::default()
+    })
+    .await;
+    let error = replay(channel.clone(), &list_recording(&pool), &Tolerance::new())
+        .await
+        .unwrap_err();
+    assert!(
+        error.contains("responses[0].id: \"a\" != \"b\""),
+        "{}",
+        error
+    );
+
+    replay(
+        channel.clone(),
+        &list_recording(&pool),
+        &Tolerance::new().unordered_responses(),
+    )
+    .await
+    .unwrap();
+
+    let mut recording = list_recording(&pool);
+    recording.responses.pop();
+    let error = replay(channel, &recording, &Tolerance::new().unordered_responses())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "replay of /demo.Sensor/List differs:\n  unexpected response: {\"id\":\"b\",\"value\":2.5}"
+    );
+}
+
+#[tokio::test]
+async fn test_replay_status() {
+    let pool = pool();
+    let channel = serve(Handlers {
+        at: 7,
+        ..Default::default()
+    })
+    .await;
+    let query =
+        json::parse_from_str(&pool.message("demo.Query").unwrap(), r#"{"id": "missing"}"#).unwrap();
+    let mut recording = Recording::new(pool.method("demo.Sensor/Read").unwrap())
+        .status(Code::NotFound, "no sensor 0");
+    recording.requests.push(encode(&*query));
+
+    let error = replay(channel.clone(), &recording, &Tolerance::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "replay of /demo.Sensor/Read differs:\n  status message: \"no sensor 0\" != \"no sensor 7\""
+    );
+    replay(
+        channel.clone(),
+        &recording,
+        &Tolerance::new().ignore_status_message(),
+    )
+    .await
+    .unwrap();
+
+    let recording = recording.status(Code::Ok, "");
+    let error = replay(
+        channel,
+        &recording,
+        &Tolerance::new().ignore_status_message(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        "replay of /demo.Sensor/Read differs:\n  status: Ok != NotFound"
+    );
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "proptest")]
+
+use proptest::prelude::*;
+use protobuf::{
+    descriptor::FileDescriptorProto,
+    well_known_types::{struct_::Struct, type_::Type},
+    MessageFull,
+};
+use tonic_testing_protobuf::{arbitrary, roundtrip};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_proto2(message in arbitrary::message(FileDescriptorProto::descriptor())) {
+        roundtrip::check(&*message).map_err(TestCaseError::fail)?;
+    }
+
+    #[test]
+    fn test_proto3(message in arbitrary::message(Type::descriptor())) {
+        roundtrip::check(&*message).map_err(TestCaseError::fail)?;
+    }
+
+    #[test]
+    fn test_codecs(message in arbitrary::message(FileDescriptorProto::descriptor())) {
+        let message = *message.downcast_box::<FileDescriptorProto>().unwrap();
+        roundtrip::check_codecs(&message).map_err(TestCaseError::fail)?;
+    }
+
+    #[test]
+    fn test_recursive_map(message in arbitrary::message_with(
+        Struct::descriptor(),
+        &arbitrary::Config { max_depth: 6, ..Default::default() },
+    )) {
+        roundtrip::check(&*message).map_err(TestCaseError::fail)?;
+    }
+}
+
+#[test]
+fn test_check() {
+    let mut message = FileDescriptorProto::new();
+    message.set_name("a.proto".to_owned());
+    roundtrip::check(&message).unwrap();
+    roundtrip::check_codecs(&message).unwrap();
+}
+
+#[cfg(feature = "protobuf-v2")]
+#[test]
+fn test_check_v2() {
+    let mut message = protobuf2::descriptor::FileDescriptorProto::new();
+    message.set_name("a.proto".to_owned());
+    message.mut_dependency().push("b.proto".to_owned());
+    roundtrip::check_v2(&message).unwrap();
+}
@@ -0,0 +1,114 @@
+//! A minimal command-line parser.
+//!
+//! Options are written as `--name value` or `--name=value`, flags as `--name`.
+
+use std::{collections::VecDeque, time::Duration};
+
+/// Parsed command-line arguments.
+#[derive(Debug, Default)]
+pub struct Args {
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+    positional: VecDeque<String>,
+}
+
+impl Args {
+    /// Parses `args`, without the program name. Arguments named in `flags`
+    /// take no value.
+    pub fn parse<I>(args: I, flags: &[&str]) -> Result<Args, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args);
+                break;
+            }
+            if !arg.starts_with('-') || arg == "-" {
+                parsed.positional.push_back(arg);
+                continue;
+            }
+            let name = arg.trim_start_matches('-');
+            if let Some((name, value)) = name.split_once('=') {
+                parsed.options.push((name.to_owned(), value.to_owned()));
+            } else if flags.contains(&name) {
+                parsed.flags.push(name.to_owned());
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value of {}", arg))?;
+                parsed.options.push((name.to_owned(), value));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Removes and returns the last value of option `name`.
+    pub fn option(&mut self, name: &str) -> Option<String> {
+        self.options_all(name).pop()
+    }
+
+    /// Removes and returns all values of option `name`.
+    pub fn options_all(&mut self, name: &str) -> Vec<String> {
+        let (matched, rest) = std::mem::take(&mut self.options)
+            .into_iter()
+            .partition(|(n, _)| n == name);
+        self.options = rest;
+        matched.into_iter().map(|(_, v)| v).collect()
+    }
+
+    /// Returns whether option `name` is set, without removing it.
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    /// Removes and parses the last value of option `name`.
+    pub fn parse_option<T>(&mut self, name: &str) -> Result<Option<T>, String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.option(name)
+            .map(|v| v.parse().map_err(|e| format!("invalid --{}: {}", name, e)))
+            .transpose()
+    }
+
+    /// Removes and parses the last value of option `name` as a number of
+    /// seconds, failing on negative, non-finite or overflowing values.
+    pub fn parse_seconds(&mut self, name: &str) -> Result<Option<Duration>, String> {
+        self.parse_option(name)?
+            .map(|seconds: f64| {
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| format!("invalid --{}: {}", name, e))
+            })
+            .transpose()
+    }
+
+    /// Removes flag `name`, returns whether it was set.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let len = self.flags.len();
+        self.flags.retain(|f| f != name);
+        self.flags.len() != len
+    }
+
+    /// Removes and returns the next positional argument.
+    pub fn positional(&mut self) -> Option<String> {
+        self.positional.pop_front()
+    }
+
+    /// Fails if any argument was not consumed.
+    pub fn finish(self) -> Result<(), String> {
+        if let Some((name, _)) = self.options.first() {
+            return Err(format!("unknown option --{}", name));
+        }
+        if let Some(name) = self.flags.first() {
+            return Err(format!("unknown flag --{}", name));
+        }
+        if let Some(arg) = self.positional.front() {
+            return Err(format!("unexpected argument {}", arg));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Calls any method of a gRPC server, like grpcurl.
+//!
+//! ```sh
+//! grpc-call localhost:50051 list
+//! grpc-call -d '{"service": ""}' localhost:50051 grpc.health.v1.Health/Check
+//! ```
+
+use std::{
+    io::{self, Cursor, Read},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Channel, Request, Status};
+use tonic_dynamic_protobuf::{DynamicClient, DynamicMessage, DynamicMethod};
+use tonic_tools_protobuf::{args::Args, client, format::Format, schema};
+use tonic_types_protobuf::redact;
+
+const USAGE: &str = "\
+Usage: grpc-call [OPTIONS] ADDRESS list [SERVICE]
+       grpc-call [OPTIONS] ADDRESS METHOD
+
+Lists the services or methods of the server at ADDRESS, or calls METHOD, e.g.
+pkg.Service/Method. Descriptors are fetched via server reflection unless
+schema options are given.
+
+Options:
+    -d, --data DATA       the request messages, `@FILE` reads them from a file
+                          and `@-` from stdin, an empty message by default
+    --format FORMAT       `json` (default) or `text`, text format messages in a
+                          stream are separated by the ASCII record separator
+    -H, --header HEADER   a `key: value` request header, may be repeated, the
+                          values of `-bin` keys are base64 encoded
+    --timeout SECONDS     the timeout of the call
+    --redact              mask response fields marked
+                          `(tonic_protobuf.sensitive) = true`
+    -h, --help            print this help
+";
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), String> {
+    let mut args = Args::parse(std::env::args().skip(1), &["redact", "h", "help"])?;
+    if args.flag("h") || args.flag("help") {
+        println!("{}\n{}", USAGE, schema::USAGE);
+        return Ok(());
+    }
+    let format: Format = args.parse_option("format")?.unwrap_or_default();
+    let mut data = args.options_all("data");
+    data.extend(args.options_all("d"));
+    let mut headers = args.options_all("header");
+    headers.extend(args.options_all("H"));
+    let timeout = args.parse_seconds("timeout")?;
+    let redact = args.flag("redact");
+    let address = args.positional().ok_or("missing ADDRESS")?;
+    let target = args.positional().ok_or("missing METHOD")?;
+    let list_service = if target == "list" {
+        args.positional()
+    } else {
+        None
+    };
+    if data.len() > 1 {
+        return Err("--data may only be given once".to_owned());
+    }
+
+    let channel = client::connect(&address).await?;
+    let pool = client::descriptors(&mut args, &channel).await?;
+
+    if target == "list" {
+        args.finish()?;
+        match list_service {
+            None => pool.services().iter().for_each(|s| println!("{}", s)),
+            Some(service) => {
+                let methods: Vec<_> = pool
+                    .methods()
+                    .into_iter()
+                    .filter(|m| m.service() == service)
+                    .collect();
+                if methods.is_empty() {
+                    return Err(format!("service {} not found", service));
+                }
+                for method in methods {
+                    println!("{}.{}", method.service(), method.name());
+                }
+            }
+        }
+        return Ok(());
+    }
+    args.finish()?;
+
+    let method = pool
+        .method(&target)
+        .ok_or_else(|| format!("method {} not found", target))?;
+    let call = Call {
+        method,
+        format,
+        headers,
+        timeout,
+        redact,
+    };
+    call.run(channel, data.pop()).await
+}
+
+struct Call {
+    method: DynamicMethod,
+    format: Format,
+    headers: Vec<String>,
+    timeout: Option<Duration>,
+    redact: bool,
+}
+
+impl Call {
+    async fn run(&self, channel: Channel, data: Option<String>) -> Result<(), String> {
+        let (mut requests, error) = self.requests(data)?;
+        let mut client = DynamicClient::new(channel);
+
+        let result = if self.method.client_streaming() {
+            let request = self.request(ReceiverStream::new(requests))?;
+            if self.method.server_streaming() {
+                self.print_stream(client.streaming(&self.method, request).await)
+                    .await
+            } else {
+                self.print_one(client.client_streaming(&self.method, request).await)
+            }
+        } else {
+            let message = requests.recv().await;
+            let more = requests.recv().await.is_some();
+            if let Some(e) = error.lock().unwrap().take() {
+                return Err(format!("parse request: {}", e));
+            }
+            let message = message.ok_or("no request message")?;
+            if more {
+                return Err(format!(
+                    "method {} takes exactly one request message",
+                    self.method.name()
+                ));
+            }
+            let request = self.request(message)?;
+            if self.method.server_streaming() {
+                self.print_stream(client.server_streaming(&self.method, request).await)
+                    .await
+            } else {
+                self.print_one(client.unary(&self.method, request).await)
+            }
+        };
+
+        // Report a malformed request first, it likely caused the call to fail.
+        if let Some(e) = error.lock().unwrap().take() {
+            return Err(format!("parse request: {}", e));
+        }
+        result.map_err(|status| {
+            format!(
+                "call failed:\n  Code: {:?}\n  Message: {}",
+                status.code(),
+                status.message()
+            )
+        })
+    }
+
+    /// Streams the request messages from `data` as they are parsed. The
+    /// stream ends early on a parse error, which is kept in the returned
+    /// slot.
+    #[allow(clippy::type_complexity)]
+    fn requests(
+        &self,
+        data: Option<String>,
+    ) -> Result<(mpsc::Receiver<DynamicMessage>, Arc<Mutex<Option<String>>>), String> {
+        let reader: Box<dyn Read + Send> = match data.as_deref() {
+            // Unary calls send an empty message, streaming calls no message.
+            None if self.method.client_streaming() => Box::new(io::empty()),
+            None => Box::new(Cursor::new(b"{}".to_vec())),
+            Some("@-") => Box::new(io::stdin()),
+            Some(data) => match data.strip_prefix('@') {
+                Some(path) => Box::new(
+                    std::fs::File::open(path).map_err(|e| format!("open {}: {}", path, e))?,
+                ),
+                None => Box::new(Cursor::new(data.as_bytes().to_vec())),
+            },
+        };
+        // An empty JSON object is also a valid empty text format message.
+        let format = if data.is_none() {
+            Format::Json
+        } else {
+            self.format
+        };
+
+        let (tx, rx) = mpsc::channel(1);
+        let error = Arc::new(Mutex::new(None));
+        let slot = error.clone();
+        let descriptor = self.method.input().clone();
+        std::thread::spawn(move || {
+            let result = format.parse_stream(&descriptor, reader, |message| {
+                tx.blocking_send(message).is_ok()
+            });
+            if let Err(e) = result {
+                *slot.lock().unwrap() = Some(e);
+            }
+        });
+        Ok((rx, error))
+    }
+
+    fn request<T>(&self, message: T) -> Result<Request<T>, String> {
+        let mut request = Request::new(message);
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout);
+        }
+        client::append_headers(request.metadata_mut(), &self.headers)?;
+        Ok(request)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn print_one(
+        &self,
+        response: Result<tonic::Response<DynamicMessage>, Status>,
+    ) -> Result<(), Status> {
+        self.print(response?.into_inner());
+        Ok(())
+    }
+
+    async fn print_stream(
+        &self,
+        response: Result<tonic::Response<tonic::Streaming<DynamicMessage>>, Status>,
+    ) -> Result<(), Status> {
+        let mut stream = response?.into_inner();
+        while let Some(message) = stream.message().await? {
+            self.print(message);
+        }
+        Ok(())
+    }
+
+    fn print(&self, mut message: DynamicMessage) {
+        if self.redact {
+            redact::redact(&mut *message);
+        }
+        println!("{}", self.format.print(&*message).trim_end());
+    }
+}
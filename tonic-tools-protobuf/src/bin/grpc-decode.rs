@@ -0,0 +1,134 @@
+//! Decodes captured gRPC frames with descriptors.
+//!
+//! ```sh
+//! grpc-decode --proto debugpb.proto -I proto --method debugpb.Debug/Get capture.bin
+//! ```
+
+use std::io::{self, Read, Write};
+
+use protobuf::{reflect::MessageDescriptor, MessageDyn};
+use tonic_dynamic_protobuf::{frame, json};
+use tonic_tools_protobuf::{args::Args, format::Format, schema};
+use tonic_types_protobuf::redact;
+
+const USAGE: &str = "\
+Usage: grpc-decode [OPTIONS] (--message NAME | --method NAME) [FILE]
+
+Decodes the length-prefixed gRPC frames in FILE, or stdin, and prints the
+messages.
+
+Options:
+    --message NAME   the full name of the message type, e.g. pkg.Request
+    --method NAME    a method, e.g. pkg.Service/Method, to decode its requests
+    --response       decode responses of --method instead of requests
+    --unframed       decode the input as a single message without a frame header
+    --format FORMAT  `text` (default) or `json`
+    --redact         mask fields marked `(tonic_protobuf.sensitive) = true`
+    -h, --help       print this help
+";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = Args::parse(
+        std::env::args().skip(1),
+        &["response", "unframed", "redact", "h", "help"],
+    )?;
+    if args.flag("h") || args.flag("help") {
+        println!("{}\n{}", USAGE, schema::USAGE);
+        return Ok(());
+    }
+    let pool = schema::load(&mut args)?;
+    let response = args.flag("response");
+    let descriptor = match (args.option("message"), args.option("method")) {
+        (Some(name), None) => pool
+            .message(&name)
+            .ok_or_else(|| format!("message {} not found", name))?,
+        (None, Some(name)) => {
+            let method = pool
+                .method(&name)
+                .ok_or_else(|| format!("method {} not found", name))?;
+            if response {
+                method.output().clone()
+            } else {
+                method.input().clone()
+            }
+        }
+        _ => return Err("exactly one of --message and --method is required".to_owned()),
+    };
+    let format = args.parse_option("format")?.unwrap_or(Format::Text);
+    let unframed = args.flag("unframed");
+    let redact = args.flag("redact");
+    let path = args.positional();
+    args.finish()?;
+
+    let mut input = vec![];
+    match path.as_deref() {
+        None | Some("-") => io::stdin().read_to_end(&mut input),
+        Some(path) => std::fs::File::open(path).and_then(|mut f| f.read_to_end(&mut input)),
+    }
+    .map_err(|e| format!("read input: {}", e))?;
+
+    let mut out = io::stdout().lock();
+    if unframed {
+        let message = parse(&descriptor, &input, redact)?;
+        let printed = format.print(&*message);
+        return writeln!(out, "{}", printed.trim_end()).map_err(|e| e.to_string());
+    }
+    for (index, frame) in frame::frames(&input).enumerate() {
+        let frame = frame.map_err(|e| e.to_string())?;
+        // Compressed messages can not be decoded without the grpc-encoding
+        // of the call, print the header only.
+        let message = if frame.compressed {
+            None
+        } else {
+            Some(parse(&descriptor, frame.data, redact)?)
+        };
+        let written = if format == Format::Json {
+            let message = message.map_or("null".to_owned(), |m| json::print_to_string(&*m));
+            writeln!(
+                out,
+                "{{\"frame\":{},\"offset\":{},\"compressed\":{},\"length\":{},\"message\":{}}}",
+                index,
+                frame.offset,
+                frame.compressed,
+                frame.data.len(),
+                message
+            )
+        } else {
+            writeln!(
+                out,
+                "# frame {}: offset={} compressed={} length={}",
+                index,
+                frame.offset,
+                frame.compressed,
+                frame.data.len()
+            )
+            .and_then(|_| match message {
+                Some(message) => writeln!(out, "{}", format.print(&*message).trim_end()),
+                None => Ok(()),
+            })
+        };
+        written.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn parse(
+    descriptor: &MessageDescriptor,
+    data: &[u8],
+    redact: bool,
+) -> Result<Box<dyn MessageDyn>, String> {
+    let mut message = descriptor
+        .parse_from_bytes(data)
+        .map_err(|e| format!("decode {}: {}", descriptor.full_name(), e))?;
+    if redact {
+        redact::redact(&mut *message);
+    }
+    Ok(message)
+}
@@ -0,0 +1,348 @@
+//! Load tests any method of a gRPC server, like ghz.
+//!
+//! ```sh
+//! grpc-load -c 16 -z 30 -d '{"key": "a"}' localhost:50051 debugpb.Debug/Get
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    io::{self, Cursor, Read},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use hdrhistogram::Histogram;
+use rand::{rngs::StdRng, SeedableRng};
+use tonic::{metadata::MetadataMap, transport::Channel, Code, Request, Status};
+use tonic_dynamic_protobuf::{DynamicClient, DynamicMessage, DynamicMethod};
+use tonic_testing_protobuf::random;
+use tonic_tools_protobuf::{args::Args, client, format::Format, schema};
+
+const USAGE: &str = "\
+Usage: grpc-load [OPTIONS] ADDRESS METHOD
+
+Calls METHOD, e.g. pkg.Service/Method, of the server at ADDRESS from concurrent
+workers, and reports the latency distribution and status codes of the calls.
+Descriptors are fetched via server reflection unless schema options are given.
+
+Options:
+    -d, --data DATA          the request messages, `@FILE` reads them from a
+                             file and `@-` from stdin; unary and server
+                             streaming calls cycle through them, client
+                             streaming calls send all of them
+    --random                 send random messages instead of --data
+    --format FORMAT          `json` (default) or `text`
+    -H, --header HEADER      a `key: value` request header, may be repeated,
+                             the values of `-bin` keys are base64 encoded
+    -c, --concurrency N      the number of concurrent workers, 10 by default
+    -n, --total N            stop after N calls, 200 by default without
+                             --duration
+    -z, --duration SECONDS   stop after SECONDS
+    --timeout SECONDS        the timeout of each call
+    -h, --help               print this help
+";
+
+/// Latencies are recorded in microseconds, up to an hour.
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), String> {
+    let mut args = Args::parse(std::env::args().skip(1), &["random", "h", "help"])?;
+    if args.flag("h") || args.flag("help") {
+        println!("{}\n{}", USAGE, schema::USAGE);
+        return Ok(());
+    }
+    let format: Format = args.parse_option("format")?.unwrap_or_default();
+    let mut data = args.options_all("data");
+    data.extend(args.options_all("d"));
+    let random = args.flag("random");
+    let mut headers = args.options_all("header");
+    headers.extend(args.options_all("H"));
+    let concurrency = match args.parse_option::<usize>("concurrency")? {
+        Some(n) => Some(n),
+        None => args.parse_option("c")?,
+    }
+    .unwrap_or(10);
+    let total: Option<u64> = match args.parse_option("total")? {
+        Some(n) => Some(n),
+        None => args.parse_option("n")?,
+    };
+    let duration = match args.parse_seconds("duration")? {
+        Some(d) => Some(d),
+        None => args.parse_seconds("z")?,
+    };
+    let timeout = args.parse_seconds("timeout")?;
+    let address = args.positional().ok_or("missing ADDRESS")?;
+    let target = args.positional().ok_or("missing METHOD")?;
+    if data.len() > 1 {
+        return Err("--data may only be given once".to_owned());
+    }
+    if random && !data.is_empty() {
+        return Err("--random and --data are mutually exclusive".to_owned());
+    }
+    if concurrency == 0 {
+        return Err("--concurrency must be at least 1".to_owned());
+    }
+
+    let channel = client::connect(&address).await?;
+    let pool = client::descriptors(&mut args, &channel).await?;
+    args.finish()?;
+    let method = pool
+        .method(&target)
+        .ok_or_else(|| format!("method {} not found", target))?;
+
+    let payload = if random {
+        Payload::Random
+    } else {
+        Payload::Messages(read_messages(&method, format, data.pop())?)
+    };
+    let mut metadata = MetadataMap::new();
+    client::append_headers(&mut metadata, &headers)?;
+    let load = Arc::new(Load {
+        method,
+        payload,
+        metadata,
+        timeout,
+        total: match (total, duration) {
+            (None, None) => Some(200),
+            (total, _) => total,
+        },
+        // A duration past what `Instant` can represent never ends.
+        deadline: duration.and_then(|d| Instant::now().checked_add(d)),
+        started: AtomicU64::new(0),
+    });
+
+    let start = Instant::now();
+    let workers: Vec<_> = (0..concurrency)
+        .map(|_| tokio::spawn(load.clone().work(channel.clone())))
+        .collect();
+    let mut report = Report::new();
+    for worker in workers {
+        report.merge(worker.await.map_err(|e| e.to_string())?);
+    }
+    report.print(start.elapsed());
+    Ok(())
+}
+
+/// Reads the request messages of `data`, see `--data`.
+fn read_messages(
+    method: &DynamicMethod,
+    format: Format,
+    data: Option<String>,
+) -> Result<Vec<DynamicMessage>, String> {
+    let reader: Box<dyn Read> = match data.as_deref() {
+        // Send empty messages by default.
+        None => return Ok(vec![method.input().new_instance()]),
+        Some("@-") => Box::new(io::stdin()),
+        Some(data) => match data.strip_prefix('@') {
+            Some(path) => {
+                Box::new(std::fs::File::open(path).map_err(|e| format!("open {}: {}", path, e))?)
+            }
+            None => Box::new(Cursor::new(data.as_bytes().to_vec())),
+        },
+    };
+    let mut messages = vec![];
+    format
+        .parse_stream(method.input(), reader, |message| {
+            messages.push(message);
+            true
+        })
+        .map_err(|e| format!("parse request: {}", e))?;
+    if messages.is_empty() {
+        return Err("no request message".to_owned());
+    }
+    Ok(messages)
+}
+
+enum Payload {
+    Messages(Vec<DynamicMessage>),
+    Random,
+}
+
+struct Load {
+    method: DynamicMethod,
+    payload: Payload,
+    metadata: MetadataMap,
+    timeout: Option<Duration>,
+    total: Option<u64>,
+    deadline: Option<Instant>,
+    /// The number of calls started by all workers.
+    started: AtomicU64,
+}
+
+impl Load {
+    /// Calls the method until the total or the deadline is reached.
+    async fn work(self: Arc<Self>, channel: Channel) -> Report {
+        let mut client = DynamicClient::new(channel);
+        let mut rng = StdRng::from_entropy();
+        let mut report = Report::new();
+        loop {
+            if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                break;
+            }
+            let n = self.started.fetch_add(1, Ordering::Relaxed);
+            if self.total.is_some_and(|total| n >= total) {
+                break;
+            }
+            let messages = match &self.payload {
+                Payload::Random => vec![random::message(self.method.input(), &mut rng)],
+                Payload::Messages(messages) if self.method.client_streaming() => {
+                    messages.iter().map(|m| m.clone_box()).collect()
+                }
+                Payload::Messages(messages) => {
+                    vec![messages[n as usize % messages.len()].clone_box()]
+                }
+            };
+            let start = Instant::now();
+            let result = self.call(&mut client, messages).await;
+            report.record(start.elapsed(), result);
+        }
+        report
+    }
+
+    /// Makes one call, returns the number of responses.
+    async fn call(
+        &self,
+        client: &mut DynamicClient,
+        mut messages: Vec<DynamicMessage>,
+    ) -> Result<u64, Status> {
+        let method = &self.method;
+        let mut stream = match (method.client_streaming(), method.server_streaming()) {
+            (false, false) => {
+                let request = self.request(messages.pop().unwrap());
+                client.unary(method, request).await?;
+                return Ok(1);
+            }
+            (true, false) => {
+                let request = self.request(tokio_stream::iter(messages));
+                let call: BoxFuture<'_, _> = Box::pin(client.client_streaming(method, request));
+                call.await?;
+                return Ok(1);
+            }
+            (false, true) => {
+                let request = self.request(messages.pop().unwrap());
+                client.server_streaming(method, request).await?.into_inner()
+            }
+            (true, true) => {
+                let request = self.request(tokio_stream::iter(messages));
+                let call: BoxFuture<'_, _> = Box::pin(client.streaming(method, request));
+                call.await?.into_inner()
+            }
+        };
+        let mut responses = 0;
+        while stream.message().await?.is_some() {
+            responses += 1;
+        }
+        Ok(responses)
+    }
+
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout);
+        }
+        *request.metadata_mut() = self.metadata.clone();
+        request
+    }
+}
+
+/// Streaming calls are boxed to keep the futures of the workers `Send`, which
+/// rustc fails to prove otherwise, see rust-lang/rust#102211.
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Status>> + Send + 'a>>;
+
+/// The results of the calls of one or all workers.
+struct Report {
+    latencies: Histogram<u64>,
+    codes: BTreeMap<i32, (Code, u64)>,
+    responses: u64,
+    /// The message of the first error by status code.
+    errors: BTreeMap<i32, String>,
+}
+
+impl Report {
+    fn new() -> Self {
+        Report {
+            latencies: Histogram::new_with_bounds(1, MAX_LATENCY_US, 3).unwrap(),
+            codes: BTreeMap::new(),
+            responses: 0,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, latency: Duration, result: Result<u64, Status>) {
+        let micros = (latency.as_micros() as u64).clamp(1, MAX_LATENCY_US);
+        self.latencies.record(micros).unwrap();
+        let code = match result {
+            Ok(responses) => {
+                self.responses += responses;
+                Code::Ok
+            }
+            Err(status) => {
+                self.errors
+                    .entry(status.code() as i32)
+                    .or_insert_with(|| status.message().to_owned());
+                status.code()
+            }
+        };
+        self.codes.entry(code as i32).or_insert((code, 0)).1 += 1;
+    }
+
+    fn merge(&mut self, other: Report) {
+        self.latencies.add(&other.latencies).unwrap();
+        for (key, (code, count)) in other.codes {
+            self.codes.entry(key).or_insert((code, 0)).1 += count;
+        }
+        self.responses += other.responses;
+        for (key, message) in other.errors {
+            self.errors.entry(key).or_insert(message);
+        }
+    }
+
+    fn print(&self, elapsed: Duration) {
+        let ms = |micros: u64| format!("{:.2} ms", micros as f64 / 1000.0);
+        let count = self.latencies.len();
+        println!("Summary:");
+        println!("  Count:        {}", count);
+        println!("  Total:        {:.2} s", elapsed.as_secs_f64());
+        if count > 0 {
+            println!("  Slowest:      {}", ms(self.latencies.max()));
+            println!("  Fastest:      {}", ms(self.latencies.min()));
+            println!("  Average:      {:.2} ms", self.latencies.mean() / 1000.0);
+        }
+        println!(
+            "  Requests/sec: {:.2}",
+            count as f64 / elapsed.as_secs_f64()
+        );
+        println!("  Responses:    {}", self.responses);
+
+        if count > 0 {
+            println!();
+            println!("Latency distribution:");
+            for quantile in [10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0] {
+                let latency = self.latencies.value_at_percentile(quantile);
+                println!("  {} % in {}", quantile, ms(latency));
+            }
+        }
+
+        println!();
+        println!("Status code distribution:");
+        for (key, (code, count)) in &self.codes {
+            match self.errors.get(key) {
+                Some(message) => println!("  [{:?}] {} responses, e.g. {:?}", code, count, message),
+                None => println!("  [{:?}] {} responses", code, count),
+            }
+        }
+    }
+}
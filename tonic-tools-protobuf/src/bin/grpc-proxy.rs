@@ -0,0 +1,67 @@
+//! Forwards gRPC calls to a server and records their frames, to debug
+//! clients that can not be instrumented.
+//!
+//! ```sh
+//! grpc-proxy --listen 127.0.0.1:50052 --record calls localhost:50051
+//! ```
+
+use std::net::SocketAddr;
+
+use tonic::transport::{server::Routes, Server};
+use tonic_dynamic_protobuf::{GrpcProxy, Recorder};
+use tonic_tools_protobuf::{args::Args, client};
+
+const USAGE: &str = "\
+Usage: grpc-proxy [OPTIONS] --record DIR UPSTREAM
+
+Listens for gRPC calls, forwards them unmodified to the server at UPSTREAM, and
+records their frames to DIR. The frames of every call are captured to
+DIR/NNNNNN-pkg.Service.Method.requests and .responses, which grpc-decode
+prints, and DIR/index.jsonl lists the calls, the sizes and timing of their
+frames, and their statuses. Metadata is forwarded but not recorded.
+
+Options:
+    --listen ADDR   the address to listen on, 127.0.0.1:50052 by default
+    --record DIR    the directory to record to, created if needed
+    -h, --help      print this help
+";
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), String> {
+    let mut args = Args::parse(std::env::args().skip(1), &["h", "help"])?;
+    if args.flag("h") || args.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let listen: SocketAddr = args
+        .parse_option("listen")?
+        .unwrap_or_else(|| ([127, 0, 0, 1], 50052).into());
+    let dir = args
+        .option("record")
+        .ok_or_else(|| "missing --record".to_owned())?;
+    let upstream = args
+        .positional()
+        .ok_or_else(|| "missing UPSTREAM".to_owned())?;
+    args.finish()?;
+
+    let recorder = Recorder::create(&dir).map_err(|e| format!("record to {}: {}", dir, e))?;
+    let proxy = GrpcProxy::new(client::connect(&upstream).await?);
+    eprintln!(
+        "forwarding {} to {}, recording to {}",
+        listen, upstream, dir
+    );
+    Server::builder()
+        .layer(recorder.into_layer())
+        .layer(proxy.into_layer())
+        .add_routes(Routes::default())
+        .serve(listen)
+        .await
+        .map_err(|e| format!("serve {}: {}", listen, e))
+}
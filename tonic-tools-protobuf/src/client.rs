@@ -0,0 +1,72 @@
+//! Connecting to a server and building calls from the command line.
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use tonic::{
+    metadata::{AsciiMetadataValue, BinaryMetadataValue, MetadataKey, MetadataMap},
+    transport::{Channel, Endpoint},
+};
+use tonic_dynamic_protobuf::DescriptorPool;
+use tonic_reflection_protobuf::ReflectionClient;
+
+use crate::{args::Args, schema};
+
+/// Connects to `address`, a URI or a `host:port` pair served over plaintext
+/// HTTP/2.
+pub async fn connect(address: &str) -> Result<Channel, String> {
+    let address = if address.contains("://") {
+        address.to_owned()
+    } else {
+        format!("http://{}", address)
+    };
+    Endpoint::from_shared(address.clone())
+        .map_err(|e| format!("invalid address {}: {}", address, e))?
+        .connect()
+        .await
+        .map_err(|e| format!("connect {}: {}", address, e))
+}
+
+/// Builds a descriptor pool from the schema options in `args`, or from the
+/// server reflection of `channel` if there are none.
+pub async fn descriptors(args: &mut Args, channel: &Channel) -> Result<DescriptorPool, String> {
+    if args.has_option("descriptor-set") || args.has_option("proto") {
+        return schema::load(args);
+    }
+    let mut pool = DescriptorPool::new();
+    pool.add_from_reflection(&mut ReflectionClient::new(channel.clone()))
+        .await
+        .map_err(|e| format!("server reflection: {}", e))?;
+    Ok(pool)
+}
+
+/// Base64 with optional padding, which gRPC accepts for binary metadata.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Appends `key: value` request headers to `metadata`. Values of keys ending
+/// in `-bin` are base64 encoded, like grpcurl takes them.
+pub fn append_headers(metadata: &mut MetadataMap, headers: &[String]) -> Result<(), String> {
+    for header in headers {
+        let (key, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header {:?}, expected `key: value`", header))?;
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid header {}: {}", key, e);
+        if key.ends_with("-bin") {
+            let key: MetadataKey<_> = key.parse().map_err(|e| invalid(&e))?;
+            let value = BASE64.decode(value).map_err(|e| invalid(&e))?;
+            let value = BinaryMetadataValue::from_bytes(&value);
+            metadata.append_bin(key, value);
+        } else {
+            let key: MetadataKey<_> = key.parse().map_err(|e| invalid(&e))?;
+            let value: AsciiMetadataValue = value.parse().map_err(|e| invalid(&e))?;
+            metadata.append(key, value);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Reading and printing messages as JSON or text format.
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    str::FromStr,
+};
+
+use protobuf::{reflect::MessageDescriptor, MessageDyn};
+use serde_json::Value;
+use tonic_dynamic_protobuf::{json, DynamicMessage};
+
+/// Text format messages in a stream are separated by the ASCII record
+/// separator, like grpcurl does.
+pub const RECORD_SEPARATOR: u8 = 0x1e;
+
+/// The format of messages on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// The proto3 JSON mapping.
+    #[default]
+    Json,
+    /// The protobuf text format.
+    Text,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "text" => Ok(Format::Text),
+            _ => Err(format!("unknown format {}, expected json or text", s)),
+        }
+    }
+}
+
+impl Format {
+    /// Prints `message` for humans.
+    pub fn print(self, message: &dyn MessageDyn) -> String {
+        match self {
+            Format::Json => json::print_to_string_pretty(message),
+            Format::Text => protobuf::text_format::print_to_string_pretty(message),
+        }
+    }
+
+    /// Parses a single message.
+    pub fn parse(
+        self,
+        descriptor: &MessageDescriptor,
+        input: &str,
+    ) -> Result<DynamicMessage, String> {
+        match self {
+            Format::Json => json::parse_from_str(descriptor, input).map_err(|e| e.to_string()),
+            Format::Text => {
+                let mut message = descriptor.new_instance();
+                protobuf::text_format::merge_from_str(&mut *message, input)
+                    .map_err(|e| e.to_string())?;
+                Ok(message)
+            }
+        }
+    }
+
+    /// Parses a stream of messages from `reader`, calling `f` with each
+    /// message as soon as it is read, until `f` returns false.
+    ///
+    /// JSON messages are simply concatenated, text format messages are
+    /// separated by [`RECORD_SEPARATOR`].
+    pub fn parse_stream<R, F>(
+        self,
+        descriptor: &MessageDescriptor,
+        reader: R,
+        mut f: F,
+    ) -> Result<(), String>
+    where
+        R: Read,
+        F: FnMut(DynamicMessage) -> bool,
+    {
+        match self {
+            Format::Json => {
+                let values = serde_json::Deserializer::from_reader(reader).into_iter::<Value>();
+                for value in values {
+                    let value = value.map_err(|e| e.to_string())?;
+                    let mut message = descriptor.new_instance();
+                    json::merge_from_value(&mut *message, &value).map_err(|e| e.to_string())?;
+                    if !f(message) {
+                        break;
+                    }
+                }
+            }
+            Format::Text => {
+                let mut reader = BufReader::new(reader);
+                loop {
+                    let mut record = vec![];
+                    let n = reader
+                        .read_until(RECORD_SEPARATOR, &mut record)
+                        .map_err(|e| e.to_string())?;
+                    if n == 0 {
+                        break;
+                    }
+                    if record.last() == Some(&RECORD_SEPARATOR) {
+                        record.pop();
+                    }
+                    let record = String::from_utf8(record).map_err(|e| e.to_string())?;
+                    if record.trim().is_empty() {
+                        continue;
+                    }
+                    if !f(self.parse(descriptor, &record)?) {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
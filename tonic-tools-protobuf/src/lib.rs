@@ -0,0 +1,14 @@
+//! Command-line tools for gRPC services described by rust-protobuf
+//! descriptors:
+//!
+//! * `grpc-call` calls any method of a server, like grpcurl.
+//! * `grpc-decode` decodes captured gRPC frames.
+//! * `grpc-load` load tests any method, like ghz.
+//! * `grpc-proxy` forwards calls to a server and records their frames.
+//!
+//! The modules of this library are shared by the binaries.
+
+pub mod args;
+pub mod client;
+pub mod format;
+pub mod schema;
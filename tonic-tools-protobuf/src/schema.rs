@@ -0,0 +1,66 @@
+//! Loading descriptors from the command line.
+
+use std::path::Path;
+
+use protobuf::{descriptor::FileDescriptorSet, Message};
+use tonic_dynamic_protobuf::DescriptorPool;
+
+use crate::args::Args;
+
+/// The schema options shared by the tools.
+pub const USAGE: &str = "\
+Schema options:
+    --descriptor-set FILE  a FileDescriptorSet, e.g. written by `protoc -o`
+    --proto FILE           a .proto file, parsed without protoc, may be relative
+                           to an import path
+    -I, --include DIR      an import path of the .proto files";
+
+/// Builds a descriptor pool from the `--descriptor-set`, `--proto` and
+/// `--include` options in `args`.
+pub fn load(args: &mut Args) -> Result<DescriptorPool, String> {
+    let mut pool = DescriptorPool::new();
+    for path in args.options_all("descriptor-set") {
+        let bytes = std::fs::read(&path).map_err(|e| format!("read {}: {}", path, e))?;
+        let fds = FileDescriptorSet::parse_from_bytes(&bytes)
+            .map_err(|e| format!("parse {}: {}", path, e))?;
+        pool.add_file_descriptor_set(fds)
+            .map_err(|e| format!("load {}: {}", path, e))?;
+    }
+
+    let mut protos = args.options_all("proto");
+    let mut includes = args.options_all("include");
+    includes.extend(args.options_all("I"));
+    if !protos.is_empty() {
+        if includes.is_empty() {
+            includes.push(".".to_owned());
+        }
+        // Like protoc, accept .proto files relative to an import path.
+        for proto in &mut protos {
+            if !Path::new(proto).exists() {
+                if let Some(path) = includes
+                    .iter()
+                    .map(|include| Path::new(include).join(&*proto))
+                    .find(|path| path.exists())
+                {
+                    *proto = path.to_string_lossy().into_owned();
+                }
+            }
+        }
+        // Keep the imported files, e.g. of custom options.
+        let parsed = protobuf_parse::Parser::new()
+            .pure()
+            .includes(&includes)
+            .inputs(&protos)
+            .parse_and_typecheck()
+            .map_err(|e| format!("parse protos: {:#}", e))?;
+        let mut fds = FileDescriptorSet::new();
+        fds.file = parsed.file_descriptors;
+        pool.add_file_descriptor_set(fds)
+            .map_err(|e| format!("load protos: {}", e))?;
+    }
+
+    if pool.files().is_empty() {
+        return Err("no descriptors, use --descriptor-set or --proto".to_owned());
+    }
+    Ok(pool)
+}
@@ -0,0 +1,244 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use protobuf::reflect::ReflectValueBox;
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{transport::Server, Response, Status};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicServer};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Number {
+    int64 value = 1;
+}
+service Calc {
+    rpc Echo(Number) returns (Number);
+    rpc Sum(stream Number) returns (Number);
+}
+"#;
+
+fn number(pool: &DescriptorPool, value: i64) -> Box<dyn protobuf::MessageDyn> {
+    let descriptor = pool.message("demo.Number").unwrap();
+    let mut message = descriptor.new_instance();
+    descriptor
+        .field_by_name("value")
+        .unwrap()
+        .set_singular_field(&mut *message, ReflectValueBox::I64(value));
+    message
+}
+
+fn value(message: &dyn protobuf::MessageDyn) -> i64 {
+    let field = message.descriptor_dyn().field_by_name("value").unwrap();
+    field
+        .get_singular_field_or_default(message)
+        .to_i64()
+        .unwrap()
+}
+
+async fn serve(dir: &tempfile::TempDir) -> String {
+    std::fs::write(dir.path().join("calc.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .input(dir.path().join("calc.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_set(fds.clone()).unwrap();
+
+    let echo = pool.method("demo.Calc/Echo").unwrap();
+    let sum = pool.method("demo.Calc/Sum").unwrap();
+    let echo_pool = pool.clone();
+    let sum_pool = pool.clone();
+    let server = DynamicServer::new()
+        .unary(&echo, move |request| {
+            let pool = echo_pool.clone();
+            async move {
+                let value = value(&**request.get_ref());
+                if value < 0 {
+                    return Err(Status::invalid_argument("negative value"));
+                }
+                // Adds the first byte of binary metadata, to check headers.
+                match request.metadata().get_bin("x-add-bin") {
+                    Some(add) => {
+                        let add = add.to_bytes().unwrap()[0] as i64;
+                        Ok(Response::new(number(&pool, value + add)))
+                    }
+                    None => Ok(Response::new(request.into_inner())),
+                }
+            }
+        })
+        .client_streaming(&sum, move |request| {
+            let pool = sum_pool.clone();
+            async move {
+                let mut stream = request.into_inner();
+                let mut total = 0;
+                while let Some(message) = stream.next().await {
+                    total += value(&*message?);
+                }
+                Ok(Response::new(number(&pool, total)))
+            }
+        });
+    let reflection = tonic_reflection_protobuf::Builder::configure()
+        .register_file_descriptor_set(fds)
+        .build_v1()
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(server.into_layer())
+            .add_service(reflection)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    addr.to_string()
+}
+
+async fn call(args: Vec<String>, stdin: &'static str) -> (bool, String, String) {
+    tokio::task::spawn_blocking(move || {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_grpc-call"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    })
+    .await
+    .unwrap()
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_with_reflection() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr = serve(&dir).await;
+
+    let (ok, stdout, stderr) = call(args(&[&addr, "list"]), "").await;
+    assert!(ok, "{}", stderr);
+    assert!(stdout.lines().any(|l| l == "demo.Calc"), "{}", stdout);
+
+    let (ok, stdout, stderr) = call(args(&[&addr, "list", "demo.Calc"]), "").await;
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "demo.Calc.Echo\ndemo.Calc.Sum\n");
+
+    let (ok, stdout, stderr) = call(
+        args(&["-d", r#"{"value": 7}"#, &addr, "demo.Calc/Echo"]),
+        "",
+    )
+    .await;
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "{\n  \"value\": \"7\"\n}\n");
+
+    let (ok, _, stderr) = call(
+        args(&["-d", r#"{"value": -1}"#, &addr, "demo.Calc/Echo"]),
+        "",
+    )
+    .await;
+    assert!(!ok);
+    assert!(stderr.contains("Code: InvalidArgument"), "{}", stderr);
+    assert!(stderr.contains("Message: negative value"), "{}", stderr);
+
+    let (ok, _, stderr) = call(args(&["-d", r#"{"valu": 1}"#, &addr, "demo.Calc/Echo"]), "").await;
+    assert!(!ok);
+    assert!(
+        stderr.contains("parse request: unknown field valu"),
+        "{}",
+        stderr
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr = serve(&dir).await;
+
+    // Binary metadata values are base64 encoded, with optional padding.
+    for header in ["x-add-bin: Kg", "x-add-bin: Kg=="] {
+        let (ok, stdout, stderr) = call(
+            args(&["-H", header, "-d", r#"{"value": 7}"#, &addr, "demo.Calc/Echo"]),
+            "",
+        )
+        .await;
+        assert!(ok, "{}", stderr);
+        assert_eq!(stdout, "{\n  \"value\": \"49\"\n}\n");
+    }
+    let (ok, _, stderr) = call(args(&["-H", "x-add-bin: K*", &addr, "demo.Calc/Echo"]), "").await;
+    assert!(!ok);
+    assert!(stderr.contains("invalid header x-add-bin"), "{}", stderr);
+
+    let (ok, stdout, stderr) = call(args(&["--timeout", "0.5", &addr, "demo.Calc/Echo"]), "").await;
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "{}\n");
+    for timeout in ["-1", "inf", "1e30"] {
+        let (ok, _, stderr) =
+            call(args(&["--timeout", timeout, &addr, "demo.Calc/Echo"]), "").await;
+        assert!(!ok);
+        assert!(stderr.starts_with("error: invalid --timeout: "), "{}", stderr);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_streaming_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr = serve(&dir).await;
+    let include = dir.path().to_str().unwrap();
+
+    let (ok, stdout, stderr) = call(
+        args(&[
+            "--proto",
+            "calc.proto",
+            "-I",
+            include,
+            "-d",
+            "@-",
+            &addr,
+            "demo.Calc/Sum",
+        ]),
+        r#"{"value": 1} {"value": "2"}
+        {"value": 3}"#,
+    )
+    .await;
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "{\n  \"value\": \"6\"\n}\n");
+
+    let (ok, stdout, stderr) = call(
+        args(&[
+            "--proto",
+            "calc.proto",
+            "-I",
+            include,
+            "--format",
+            "text",
+            "-d",
+            "@-",
+            &addr,
+            "demo.Calc/Sum",
+        ]),
+        "value: 4\x1evalue: 5\n",
+    )
+    .await;
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "value: 9\n");
+}
@@ -0,0 +1,145 @@
+use std::{io::Write, process::Command};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Request {
+    string key = 1;
+    int64 version = 2;
+}
+message Response {
+    bytes value = 1;
+}
+service Store {
+    rpc Get(Request) returns (Response);
+}
+"#;
+
+fn frame(compressed: bool, data: &[u8], buf: &mut Vec<u8>) {
+    tonic_dynamic_protobuf::frame::encode(compressed, data, buf).unwrap();
+}
+
+fn decode(dir: &tempfile::TempDir, args: &[&str], input: &[u8]) -> (bool, String, String) {
+    let input_path = dir.path().join("capture.bin");
+    std::fs::File::create(&input_path)
+        .unwrap()
+        .write_all(input)
+        .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_grpc-decode"))
+        .arg("--proto")
+        .arg("store.proto")
+        .arg("-I")
+        .arg(dir.path())
+        .args(args)
+        .arg(&input_path)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_decode() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("store.proto"), PROTO).unwrap();
+
+    // Request { key: "a", version: 3 }
+    let request = b"\x0a\x01a\x10\x03";
+    let mut input = vec![];
+    frame(false, request, &mut input);
+    frame(true, b"\x00\x01", &mut input);
+    let (ok, stdout, stderr) = decode(&dir, &["--method", "demo.Store/Get"], &input);
+    assert!(ok, "{}", stderr);
+    assert_eq!(
+        stdout,
+        "# frame 0: offset=0 compressed=false length=5\n\
+         key: \"a\"\n\
+         version: 3\n\
+         # frame 1: offset=10 compressed=true length=2\n"
+    );
+
+    let (ok, stdout, stderr) = decode(
+        &dir,
+        &["--message", "demo.Request", "--format=json"],
+        &input[..10],
+    );
+    assert!(ok, "{}", stderr);
+    assert_eq!(
+        stdout,
+        "{\"frame\":0,\"offset\":0,\"compressed\":false,\"length\":5,\
+         \"message\":{\"key\":\"a\",\"version\":\"3\"}}\n"
+    );
+
+    // Response { value: "\xff" }
+    let (ok, stdout, stderr) = decode(
+        &dir,
+        &[
+            "--method",
+            "/demo.Store/Get",
+            "--response",
+            "--unframed",
+            "--format",
+            "json",
+        ],
+        b"\x0a\x01\xff",
+    );
+    assert!(ok, "{}", stderr);
+    assert_eq!(stdout, "{\n  \"value\": \"/w==\"\n}\n");
+}
+
+#[test]
+fn test_decode_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("store.proto"), PROTO).unwrap();
+
+    let (ok, _, stderr) = decode(&dir, &["--message", "demo.Request"], b"\x00\x00\x00");
+    assert!(!ok);
+    assert!(stderr.contains("truncated frame at offset 0"), "{}", stderr);
+
+    let (ok, _, stderr) = decode(&dir, &["--message", "demo.Missing"], b"");
+    assert!(!ok);
+    assert!(
+        stderr.contains("message demo.Missing not found"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_decode_redact() {
+    let dir = tempfile::tempdir().unwrap();
+    let proto = PROTO
+        .replace(
+            "package demo;",
+            "package demo;\nimport \"tonic_protobuf/options.proto\";",
+        )
+        .replace(
+            "string key = 1;",
+            "string key = 1 [(tonic_protobuf.sensitive) = true];",
+        );
+    std::fs::write(dir.path().join("store.proto"), proto).unwrap();
+    let include = concat!(env!("CARGO_MANIFEST_DIR"), "/../tonic-types-protobuf/proto");
+
+    let mut input = vec![];
+    frame(false, b"\x0a\x01a\x10\x03", &mut input);
+    let args = ["-I", include, "--message", "demo.Request", "--format=json"];
+    let (ok, stdout, stderr) = decode(&dir, &args, &input);
+    assert!(ok, "{}", stderr);
+    assert!(
+        stdout.contains("{\"key\":\"a\",\"version\":\"3\"}"),
+        "{}",
+        stdout
+    );
+
+    let (ok, stdout, stderr) = decode(&dir, &[&args[..], &["--redact"]].concat(), &input);
+    assert!(ok, "{}", stderr);
+    assert!(
+        stdout.contains("{\"key\":\"[REDACTED]\",\"version\":\"3\"}"),
+        "{}",
+        stdout
+    );
+}
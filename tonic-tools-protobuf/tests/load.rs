@@ -0,0 +1,185 @@
+use std::process::Command;
+
+use protobuf::reflect::ReflectValueBox;
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{codegen::BoxStream, transport::Server, Response, Status};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicServer};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Number {
+    int64 value = 1;
+}
+service Calc {
+    rpc Echo(Number) returns (Number);
+    rpc Sum(stream Number) returns (Number);
+    rpc Range(Number) returns (stream Number);
+}
+"#;
+
+fn number(pool: &DescriptorPool, value: i64) -> Box<dyn protobuf::MessageDyn> {
+    let descriptor = pool.message("demo.Number").unwrap();
+    let mut message = descriptor.new_instance();
+    descriptor
+        .field_by_name("value")
+        .unwrap()
+        .set_singular_field(&mut *message, ReflectValueBox::I64(value));
+    message
+}
+
+fn value(message: &dyn protobuf::MessageDyn) -> i64 {
+    let field = message.descriptor_dyn().field_by_name("value").unwrap();
+    field
+        .get_singular_field_or_default(message)
+        .to_i64()
+        .unwrap()
+}
+
+async fn serve() -> String {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("calc.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .input(dir.path().join("calc.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_set(fds.clone()).unwrap();
+
+    let sum_pool = pool.clone();
+    let range_pool = pool.clone();
+    let server = DynamicServer::new()
+        .unary(
+            &pool.method("demo.Calc/Echo").unwrap(),
+            |request| async move {
+                if value(&**request.get_ref()) < 0 {
+                    return Err(Status::invalid_argument("negative value"));
+                }
+                Ok(Response::new(request.into_inner()))
+            },
+        )
+        .client_streaming(&pool.method("demo.Calc/Sum").unwrap(), move |request| {
+            let pool = sum_pool.clone();
+            async move {
+                let mut stream = request.into_inner();
+                let mut total = 0i64;
+                while let Some(message) = stream.next().await {
+                    total = total.wrapping_add(value(&*message?));
+                }
+                Ok(Response::new(number(&pool, total)))
+            }
+        })
+        .server_streaming(&pool.method("demo.Calc/Range").unwrap(), move |request| {
+            let pool = range_pool.clone();
+            async move {
+                let numbers: Vec<_> = (0..value(&**request.get_ref()))
+                    .map(|i| number(&pool, i))
+                    .map(Ok)
+                    .collect();
+                Ok(Response::new(
+                    Box::pin(tokio_stream::iter(numbers)) as BoxStream<_>
+                ))
+            }
+        });
+    let reflection = tonic_reflection_protobuf::Builder::configure()
+        .register_file_descriptor_set(fds)
+        .build_v1()
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(server.into_layer())
+            .add_service(reflection)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    addr.to_string()
+}
+
+async fn load(args: &[&str]) -> (bool, String, String) {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    tokio::task::spawn_blocking(move || {
+        let output = Command::new(env!("CARGO_BIN_EXE_grpc-load"))
+            .args(args)
+            .output()
+            .unwrap();
+        (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_load_unary() {
+    let addr = serve().await;
+
+    let args = ["-n", "20", "-c", "4", "-d", r#"{"value": 1}"#];
+    let (ok, stdout, stderr) = load(&[&args[..], &[&addr, "demo.Calc/Echo"]].concat()).await;
+    assert!(ok, "{}", stderr);
+    assert!(stdout.contains("  Count:        20\n"), "{}", stdout);
+    assert!(stdout.contains("  Responses:    20\n"), "{}", stdout);
+    assert!(stdout.contains("  99 % in "), "{}", stdout);
+    assert!(stdout.contains("  [Ok] 20 responses\n"), "{}", stdout);
+
+    // Unary calls cycle through the request messages.
+    let (ok, stdout, stderr) = load(&[
+        "--total",
+        "4",
+        "--format",
+        "text",
+        "-d",
+        "value: 1\x1evalue: -1",
+        &addr,
+        "demo.Calc/Echo",
+    ])
+    .await;
+    assert!(ok, "{}", stderr);
+    assert!(stdout.contains("  [Ok] 2 responses\n"), "{}", stdout);
+    assert!(
+        stdout.contains("  [InvalidArgument] 2 responses, e.g. \"negative value\"\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_load_streaming() {
+    let addr = serve().await;
+
+    let (ok, stdout, stderr) =
+        load(&["-n", "4", "-d", r#"{"value": 3}"#, &addr, "demo.Calc/Range"]).await;
+    assert!(ok, "{}", stderr);
+    assert!(stdout.contains("  Count:        4\n"), "{}", stdout);
+    assert!(stdout.contains("  Responses:    12\n"), "{}", stdout);
+
+    let (ok, stdout, stderr) =
+        load(&["-z", "0.2", "-c", "2", "--random", &addr, "demo.Calc/Sum"]).await;
+    assert!(ok, "{}", stderr);
+    assert!(stdout.contains("  [Ok] "), "{}", stdout);
+    assert!(!stdout.contains("  Count:        0\n"), "{}", stdout);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_load_errors() {
+    let addr = serve().await;
+
+    let (ok, _, stderr) = load(&["--random", "-d", "{}", &addr, "demo.Calc/Echo"]).await;
+    assert!(!ok);
+    assert!(stderr.contains("mutually exclusive"), "{}", stderr);
+
+    let (ok, _, stderr) = load(&[&addr, "demo.Calc/Missing"]).await;
+    assert!(!ok);
+    assert!(
+        stderr.contains("method demo.Calc/Missing not found"),
+        "{}",
+        stderr
+    );
+}
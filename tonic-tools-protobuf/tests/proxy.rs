@@ -0,0 +1,188 @@
+use std::{
+    net::TcpListener as StdTcpListener,
+    path::Path,
+    process::{Child, Command},
+    time::Duration,
+};
+
+use protobuf::reflect::ReflectValueBox;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Code, Request, Response, Status,
+};
+use tonic_dynamic_protobuf::{DescriptorPool, DynamicClient, DynamicServer};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+message Number {
+    int64 value = 1;
+}
+service Calc {
+    rpc Echo(Number) returns (Number);
+}
+"#;
+
+fn number(pool: &DescriptorPool, value: i64) -> Box<dyn protobuf::MessageDyn> {
+    let descriptor = pool.message("demo.Number").unwrap();
+    let mut message = descriptor.new_instance();
+    descriptor
+        .field_by_name("value")
+        .unwrap()
+        .set_singular_field(&mut *message, ReflectValueBox::I64(value));
+    message
+}
+
+fn value(message: &dyn protobuf::MessageDyn) -> i64 {
+    let field = message.descriptor_dyn().field_by_name("value").unwrap();
+    field
+        .get_singular_field_or_default(message)
+        .to_i64()
+        .unwrap()
+}
+
+/// Serves `demo.Calc` from the `.proto` file in `dir`, and returns its pool
+/// and address.
+async fn serve(dir: &Path) -> (DescriptorPool, String) {
+    std::fs::write(dir.join("calc.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir)
+        .input(dir.join("calc.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_set(fds).unwrap();
+
+    let server = DynamicServer::new().unary(
+        &pool.method("demo.Calc/Echo").unwrap(),
+        |request| async move {
+            if value(&**request.get_ref()) < 0 {
+                return Err(Status::invalid_argument("negative value"));
+            }
+            Ok(Response::new(request.into_inner()))
+        },
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .layer(server.into_layer())
+            .add_routes(Default::default())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    (pool, addr.to_string())
+}
+
+/// Kills the proxy when the test ends.
+struct Proxy(Child);
+
+impl Drop for Proxy {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Starts a proxy to `upstream` recording to `record`, and connects to it.
+async fn proxy(upstream: &str, record: &Path) -> (Proxy, Channel) {
+    let listen = StdTcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_grpc-proxy"))
+        .args(["--listen", &listen.to_string(), "--record"])
+        .arg(record)
+        .arg(upstream)
+        .spawn()
+        .unwrap();
+    let proxy = Proxy(child);
+    for _ in 0..100 {
+        let endpoint = Channel::from_shared(format!("http://{listen}")).unwrap();
+        if let Ok(channel) = endpoint.connect().await {
+            return (proxy, channel);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("proxy did not start");
+}
+
+/// Waits until the index in `dir` has `n` statuses, and returns its lines.
+async fn index(dir: &Path, n: usize) -> Vec<serde_json::Value> {
+    for _ in 0..100 {
+        let index = std::fs::read_to_string(dir.join("index.jsonl")).unwrap_or_default();
+        let events: Vec<serde_json::Value> = index
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        if events.iter().filter(|e| e["event"] == "status").count() == n {
+            return events;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("calls were not recorded");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_proxy_records_calls() {
+    let dir = tempfile::tempdir().unwrap();
+    let (pool, upstream) = serve(dir.path()).await;
+    let record = dir.path().join("calls");
+    let (_proxy, channel) = proxy(&upstream, &record).await;
+    let echo = pool.method("demo.Calc/Echo").unwrap();
+    let mut client = DynamicClient::new(channel);
+
+    let response = client
+        .unary(&echo, Request::new(number(&pool, 7)))
+        .await
+        .unwrap();
+    assert_eq!(value(&**response.get_ref()), 7);
+    let status = client
+        .unary(&echo, Request::new(number(&pool, -1)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "negative value");
+
+    let events = index(&record, 2).await;
+    let events: Vec<_> = events
+        .iter()
+        .map(|e| match e["event"].as_str().unwrap() {
+            "start" => format!("#{} start {}", e["call"], e["capture"]),
+            "status" => format!("#{} status {} {}", e["call"], e["code"], e["message"]),
+            event => format!("#{} {} {} bytes", e["call"], event, e["len"]),
+        })
+        .collect();
+    assert_eq!(
+        events,
+        [
+            r#"#0 start "000000-demo.Calc.Echo""#,
+            "#0 request 2 bytes",
+            "#0 response 2 bytes",
+            r#"#0 status 0 """#,
+            r#"#1 start "000001-demo.Calc.Echo""#,
+            "#1 request 11 bytes",
+            r#"#1 status 3 "negative value""#,
+        ]
+    );
+
+    // The captures are decoded by grpc-decode.
+    let output = Command::new(env!("CARGO_BIN_EXE_grpc-decode"))
+        .arg("--proto")
+        .arg(dir.path().join("calc.proto"))
+        .arg("-I")
+        .arg(dir.path())
+        .args(["--method", "demo.Calc/Echo", "--response"])
+        .arg(record.join("000000-demo.Calc.Echo.responses"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "# frame 0: offset=0 compressed=false length=2\nvalue: 7\n"
+    );
+}
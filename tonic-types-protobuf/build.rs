@@ -0,0 +1,29 @@
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("No OUT_DIR defined");
+    let protos = [
+        "proto/google/rpc/status.proto",
+        "proto/google/rpc/error_details.proto",
+        "proto/google/longrunning/operations.proto",
+        "proto/tonic_protobuf/options.proto",
+        "proto/tonic_protobuf/sample.proto",
+    ];
+
+    // Generate protobuf structs, and their mod file.
+    protobuf_codegen::Codegen::new()
+        .include("proto")
+        .inputs(protos)
+        .out_dir(&out_dir)
+        .run()
+        .unwrap();
+
+    // Generate tonic service stubs of `google.longrunning.Operations`.
+    tonic_build_protobuf::Builder::new()
+        .out_dir(&out_dir)
+        .proto_path("crate::pb")
+        .file_name(|_pkg, svc| format!("{svc}_tonic"))
+        .compile(&["proto/google/longrunning/operations.proto"], &["proto"]);
+
+    for proto in protos {
+        println!("cargo:rerun-if-changed={proto}");
+    }
+}
@@ -0,0 +1,130 @@
+//! Helpers to pack messages into `google.protobuf.Any`, and to unpack them
+//! with a runtime [`TypeRegistry`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{OnceLock, RwLock},
+};
+
+use protobuf::{
+    reflect::{FileDescriptor, MessageDescriptor},
+    well_known_types::any::Any,
+    MessageDyn, MessageFull,
+};
+
+/// The type URL prefix used by [`Any::pack`] and other gRPC implementations.
+pub const DEFAULT_TYPE_URL_PREFIX: &str = "type.googleapis.com";
+
+/// Returns the type URL of the message type `full_name` under `prefix`, e.g.
+/// `type.googleapis.com/google.rpc.ErrorInfo`.
+pub fn type_url(prefix: &str, full_name: &str) -> String {
+    format!("{}/{}", prefix.trim_end_matches('/'), full_name)
+}
+
+/// Returns the full message name of a type URL, i.e. the part after the last
+/// `/`.
+pub fn type_name(type_url: &str) -> &str {
+    match type_url.rfind('/') {
+        Some(i) => &type_url[i + 1..],
+        None => type_url,
+    }
+}
+
+/// Packs `message` with the type URL prefix `prefix`.
+pub fn pack_with_prefix(message: &dyn MessageDyn, prefix: &str) -> protobuf::Result<Any> {
+    let mut any = Any::new();
+    any.type_url = type_url(prefix, message.descriptor_dyn().full_name());
+    any.value = message.write_to_bytes_dyn()?;
+    Ok(any)
+}
+
+/// Represents an error in unpacking an `Any` with a [`TypeRegistry`].
+#[derive(Debug)]
+pub enum UnpackError {
+    /// The type of the packed message is not registered.
+    UnknownType(String),
+    /// The packed message can not be decoded.
+    Decode(protobuf::Error),
+}
+
+impl std::error::Error for UnpackError {}
+
+impl fmt::Display for UnpackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnpackError::UnknownType(t) => write!(f, "unknown message type: {}", t),
+            UnpackError::Decode(e) => write!(f, "failed to decode packed message: {}", e),
+        }
+    }
+}
+
+/// A registry of message types, keyed by full name, used to unpack `Any`s
+/// whose type is only known at runtime.
+///
+/// Messages of generated types are unpacked as those types, and can be
+/// downcast to them with `MessageDyn::downcast_box`. tonic-build-protobuf
+/// generates a `register_types` function registering every compiled message
+/// type with `Builder::register_types`.
+#[derive(Debug, Clone, Default)]
+pub struct TypeRegistry {
+    types: HashMap<String, MessageDescriptor>,
+}
+
+impl TypeRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process-wide registry, e.g. populated by plugins at startup
+    /// and read wherever `Any`s are unpacked.
+    pub fn global() -> &'static RwLock<TypeRegistry> {
+        static GLOBAL: OnceLock<RwLock<TypeRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(RwLock::default)
+    }
+
+    /// Register the generated message type `M`.
+    pub fn register<M: MessageFull>(&mut self) -> &mut Self {
+        self.register_descriptor(M::descriptor())
+    }
+
+    /// Register a message type by its descriptor, which may be dynamic.
+    pub fn register_descriptor(&mut self, descriptor: MessageDescriptor) -> &mut Self {
+        self.types
+            .insert(descriptor.full_name().to_owned(), descriptor);
+        self
+    }
+
+    /// Register all message types of `file`, including nested ones.
+    pub fn register_file(&mut self, file: &FileDescriptor) -> &mut Self {
+        fn register_nested(registry: &mut TypeRegistry, descriptor: MessageDescriptor) {
+            for nested in descriptor.nested_messages() {
+                if !nested.is_map_entry() {
+                    register_nested(registry, nested);
+                }
+            }
+            registry.register_descriptor(descriptor);
+        }
+
+        for message in file.messages() {
+            register_nested(self, message);
+        }
+        self
+    }
+
+    /// Returns the descriptor of a type URL or a full message name.
+    pub fn find(&self, type_url: &str) -> Option<&MessageDescriptor> {
+        self.types.get(type_name(type_url))
+    }
+
+    /// Unpacks `any` as the registered type of its type URL.
+    pub fn unpack(&self, any: &Any) -> Result<Box<dyn MessageDyn>, UnpackError> {
+        let descriptor = self
+            .find(&any.type_url)
+            .ok_or_else(|| UnpackError::UnknownType(any.type_url.clone()))?;
+        descriptor
+            .parse_from_bytes(&any.value)
+            .map_err(UnpackError::Decode)
+    }
+}
@@ -0,0 +1,320 @@
+use std::{collections::HashMap, time::Duration};
+
+use protobuf::{well_known_types::any::Any, MessageField, MessageFull};
+
+use crate::pb::error_details::{
+    bad_request::FieldViolation, precondition_failure, quota_failure, BadRequest, ErrorInfo,
+    LocalizedMessage, PreconditionFailure, QuotaFailure, RetryInfo,
+};
+
+/// The standard error details of a `google.rpc.Status`, with typed
+/// constructors and getters so that callers don't need to pack `Any`s by
+/// hand.
+///
+/// Each standard detail type appears at most once. Details of other types
+/// are ignored, use [`StatusExt::details_any`](crate::StatusExt::details_any)
+/// to access them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorDetails {
+    retry_info: Option<RetryInfo>,
+    bad_request: Option<BadRequest>,
+    quota_failure: Option<QuotaFailure>,
+    precondition_failure: Option<PreconditionFailure>,
+    error_info: Option<ErrorInfo>,
+    localized_message: Option<LocalizedMessage>,
+}
+
+impl ErrorDetails {
+    /// Create empty error details.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create error details with a `RetryInfo`.
+    pub fn with_retry_info(retry_delay: Option<Duration>) -> Self {
+        let mut details = Self::new();
+        details.set_retry_info(retry_delay);
+        details
+    }
+
+    /// Create error details with a `BadRequest` that has a single field
+    /// violation.
+    pub fn with_bad_request_violation(
+        field: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        let mut details = Self::new();
+        details.add_bad_request_violation(field, description);
+        details
+    }
+
+    /// Create error details with a `QuotaFailure` that has a single violation.
+    pub fn with_quota_failure_violation(
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        let mut details = Self::new();
+        details.add_quota_failure_violation(subject, description);
+        details
+    }
+
+    /// Create error details with a `PreconditionFailure` that has a single
+    /// violation.
+    pub fn with_precondition_failure_violation(
+        violation_type: impl Into<String>,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        let mut details = Self::new();
+        details.add_precondition_failure_violation(violation_type, subject, description);
+        details
+    }
+
+    /// Create error details with an `ErrorInfo`.
+    pub fn with_error_info(
+        reason: impl Into<String>,
+        domain: impl Into<String>,
+        metadata: HashMap<String, String>,
+    ) -> Self {
+        let mut details = Self::new();
+        details.set_error_info(reason, domain, metadata);
+        details
+    }
+
+    /// Create error details with a `LocalizedMessage`.
+    pub fn with_localized_message(locale: impl Into<String>, message: impl Into<String>) -> Self {
+        let mut details = Self::new();
+        details.set_localized_message(locale, message);
+        details
+    }
+
+    /// Set the `RetryInfo`.
+    pub fn set_retry_info(&mut self, retry_delay: Option<Duration>) -> &mut Self {
+        let mut retry_info = RetryInfo::new();
+        retry_info.retry_delay = MessageField::from_option(retry_delay.map(Into::into));
+        self.retry_info = Some(retry_info);
+        self
+    }
+
+    /// Add a field violation to the `BadRequest`.
+    pub fn add_bad_request_violation(
+        &mut self,
+        field: impl Into<String>,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        let mut violation = FieldViolation::new();
+        violation.field = field.into();
+        violation.description = description.into();
+        self.bad_request
+            .get_or_insert_with(BadRequest::new)
+            .field_violations
+            .push(violation);
+        self
+    }
+
+    /// Add a violation to the `QuotaFailure`.
+    pub fn add_quota_failure_violation(
+        &mut self,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        let mut violation = quota_failure::Violation::new();
+        violation.subject = subject.into();
+        violation.description = description.into();
+        self.quota_failure
+            .get_or_insert_with(QuotaFailure::new)
+            .violations
+            .push(violation);
+        self
+    }
+
+    /// Add a violation to the `PreconditionFailure`.
+    pub fn add_precondition_failure_violation(
+        &mut self,
+        violation_type: impl Into<String>,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        let mut violation = precondition_failure::Violation::new();
+        violation.type_ = violation_type.into();
+        violation.subject = subject.into();
+        violation.description = description.into();
+        self.precondition_failure
+            .get_or_insert_with(PreconditionFailure::new)
+            .violations
+            .push(violation);
+        self
+    }
+
+    /// Set the `ErrorInfo`.
+    pub fn set_error_info(
+        &mut self,
+        reason: impl Into<String>,
+        domain: impl Into<String>,
+        metadata: HashMap<String, String>,
+    ) -> &mut Self {
+        let mut error_info = ErrorInfo::new();
+        error_info.reason = reason.into();
+        error_info.domain = domain.into();
+        error_info.metadata = metadata;
+        self.error_info = Some(error_info);
+        self
+    }
+
+    /// Set the `LocalizedMessage`.
+    pub fn set_localized_message(
+        &mut self,
+        locale: impl Into<String>,
+        message: impl Into<String>,
+    ) -> &mut Self {
+        let mut localized_message = LocalizedMessage::new();
+        localized_message.locale = locale.into();
+        localized_message.message = message.into();
+        self.localized_message = Some(localized_message);
+        self
+    }
+
+    /// Returns the `RetryInfo`.
+    pub fn retry_info(&self) -> Option<&RetryInfo> {
+        self.retry_info.as_ref()
+    }
+
+    /// Returns the retry delay of the `RetryInfo`. Negative delays are
+    /// returned as zero.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        let delay = self.retry_info.as_ref()?.retry_delay.as_ref()?;
+        if delay.seconds < 0 || delay.nanos < 0 {
+            return Some(Duration::ZERO);
+        }
+        Some(delay.clone().into())
+    }
+
+    /// Returns the `BadRequest`.
+    pub fn bad_request(&self) -> Option<&BadRequest> {
+        self.bad_request.as_ref()
+    }
+
+    /// Returns the `QuotaFailure`.
+    pub fn quota_failure(&self) -> Option<&QuotaFailure> {
+        self.quota_failure.as_ref()
+    }
+
+    /// Returns the `PreconditionFailure`.
+    pub fn precondition_failure(&self) -> Option<&PreconditionFailure> {
+        self.precondition_failure.as_ref()
+    }
+
+    /// Returns the `ErrorInfo`.
+    pub fn error_info(&self) -> Option<&ErrorInfo> {
+        self.error_info.as_ref()
+    }
+
+    /// Returns the `LocalizedMessage`.
+    pub fn localized_message(&self) -> Option<&LocalizedMessage> {
+        self.localized_message.as_ref()
+    }
+
+    /// Returns true if no detail is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Packs the details into `Any`s.
+    pub(crate) fn to_any(&self) -> Vec<Any> {
+        fn pack<M: MessageFull>(details: &mut Vec<Any>, message: &Option<M>) {
+            if let Some(message) = message {
+                details.push(Any::pack(message).expect("failed to encode error detail"));
+            }
+        }
+
+        let mut details = vec![];
+        pack(&mut details, &self.retry_info);
+        pack(&mut details, &self.bad_request);
+        pack(&mut details, &self.quota_failure);
+        pack(&mut details, &self.precondition_failure);
+        pack(&mut details, &self.error_info);
+        pack(&mut details, &self.localized_message);
+        details
+    }
+
+    /// Unpacks the standard details in `details`, skipping other types.
+    pub(crate) fn from_any(details: &[Any]) -> Result<Self, protobuf::Error> {
+        fn unpack<M: MessageFull>(any: &Any, message: &mut Option<M>) -> protobuf::Result<()> {
+            if let Some(m) = any.unpack()? {
+                *message = Some(m);
+            }
+            Ok(())
+        }
+
+        let mut error_details = Self::new();
+        for any in details {
+            unpack(any, &mut error_details.retry_info)?;
+            unpack(any, &mut error_details.bad_request)?;
+            unpack(any, &mut error_details.quota_failure)?;
+            unpack(any, &mut error_details.precondition_failure)?;
+            unpack(any, &mut error_details.error_info)?;
+            unpack(any, &mut error_details.localized_message)?;
+        }
+        Ok(error_details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use protobuf::well_known_types::any::Any;
+
+    use super::ErrorDetails;
+    use crate::pb::error_details::ErrorInfo;
+
+    fn all_details() -> ErrorDetails {
+        let mut details = ErrorDetails::with_retry_info(Some(Duration::from_secs(1)));
+        details
+            .add_bad_request_violation("key", "must not be empty")
+            .add_quota_failure_violation("project:1", "daily limit")
+            .add_precondition_failure_violation("TOS", "user:1", "not accepted")
+            .set_error_info("EXPIRED", "example.com", HashMap::new())
+            .set_localized_message("en-US", "Expired");
+        details
+    }
+
+    #[test]
+    fn test_any_round_trip() {
+        let details = all_details();
+        let any = details.to_any();
+        let type_urls: Vec<_> = any.iter().map(|any| any.type_url.as_str()).collect();
+        assert_eq!(
+            type_urls,
+            [
+                "type.googleapis.com/google.rpc.RetryInfo",
+                "type.googleapis.com/google.rpc.BadRequest",
+                "type.googleapis.com/google.rpc.QuotaFailure",
+                "type.googleapis.com/google.rpc.PreconditionFailure",
+                "type.googleapis.com/google.rpc.ErrorInfo",
+                "type.googleapis.com/google.rpc.LocalizedMessage",
+            ]
+        );
+        assert_eq!(ErrorDetails::from_any(&any).unwrap(), details);
+
+        assert!(ErrorDetails::new().to_any().is_empty());
+        assert!(ErrorDetails::from_any(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_from_any() {
+        let mut custom = Any::new();
+        custom.type_url = "type.googleapis.com/example.Custom".to_owned();
+        let mut any = vec![custom];
+        any.extend(ErrorDetails::with_localized_message("en-US", "Expired").to_any());
+        // Other types are skipped.
+        assert_eq!(
+            ErrorDetails::from_any(&any).unwrap(),
+            ErrorDetails::with_localized_message("en-US", "Expired")
+        );
+
+        let mut broken = Any::pack(&ErrorInfo::new()).unwrap();
+        broken.value = b"\xff".to_vec();
+        assert!(ErrorDetails::from_any(&[broken]).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! `google.protobuf.FieldMask` helpers that work on any rust-protobuf message
+//! through reflection, e.g. for AIP-134 style Update RPCs.
+
+use std::{collections::BTreeMap, fmt};
+
+use protobuf::{
+    reflect::{FieldDescriptor, MessageDescriptor, ReflectFieldRef, RuntimeFieldType, RuntimeType},
+    well_known_types::field_mask::FieldMask,
+    MessageDyn,
+};
+
+/// Represents an error in applying a field mask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMaskError {
+    /// The path does not name a field, or traverses a field that is not a
+    /// singular message.
+    InvalidPath(String),
+    /// The source and target of a merge are different message types.
+    TypeMismatch { source: String, target: String },
+}
+
+impl std::error::Error for FieldMaskError {}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMaskError::InvalidPath(path) => write!(f, "invalid field mask path: {}", path),
+            FieldMaskError::TypeMismatch { source, target } => {
+                write!(f, "can not merge {} into {}", source, target)
+            }
+        }
+    }
+}
+
+/// Checks that every path of `mask` names a field of `descriptor`, only
+/// traversing singular message fields.
+pub fn validate(mask: &FieldMask, descriptor: &MessageDescriptor) -> Result<(), FieldMaskError> {
+    for path in &mask.paths {
+        let mut descriptor = descriptor.clone();
+        let mut segments = path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let field = descriptor
+                .field_by_name(segment)
+                .ok_or_else(|| FieldMaskError::InvalidPath(path.clone()))?;
+            if segments.peek().is_some() {
+                descriptor = singular_message(&field)
+                    .ok_or_else(|| FieldMaskError::InvalidPath(path.clone()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Clears every field of `message` that is not covered by `mask`.
+pub fn trim(mask: &FieldMask, message: &mut dyn MessageDyn) -> Result<(), FieldMaskError> {
+    validate(mask, &message.descriptor_dyn())?;
+    trim_tree(&PathTree::new(mask), message);
+    Ok(())
+}
+
+/// Copies the fields covered by `mask` from `source` to `target`.
+///
+/// Covered fields replace the target fields, including repeated and map
+/// fields, and fields unset in `source` are cleared in `target`, as the
+/// update mask of AIP-134 requires.
+pub fn merge(
+    mask: &FieldMask,
+    source: &dyn MessageDyn,
+    target: &mut dyn MessageDyn,
+) -> Result<(), FieldMaskError> {
+    let descriptor = source.descriptor_dyn();
+    if descriptor != target.descriptor_dyn() {
+        return Err(FieldMaskError::TypeMismatch {
+            source: descriptor.full_name().to_owned(),
+            target: target.descriptor_dyn().full_name().to_owned(),
+        });
+    }
+    validate(mask, &descriptor)?;
+    merge_tree(&PathTree::new(mask), source, target);
+    Ok(())
+}
+
+fn singular_message(field: &FieldDescriptor) -> Option<MessageDescriptor> {
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeType::Message(descriptor)) => Some(descriptor),
+        _ => None,
+    }
+}
+
+/// The paths of a field mask as a tree of field names. A node without
+/// children covers the whole field.
+#[derive(Debug, Default)]
+struct PathTree(BTreeMap<String, PathTree>);
+
+impl PathTree {
+    fn new(mask: &FieldMask) -> Self {
+        let mut root = PathTree::default();
+        for path in &mask.paths {
+            let mut node = &mut root;
+            let mut segments = path.split('.').peekable();
+            while let Some(segment) = segments.next() {
+                let is_new = !node.0.contains_key(segment);
+                let child = node.0.entry(segment.to_owned()).or_default();
+                if segments.peek().is_none() {
+                    // The whole field is covered.
+                    child.0.clear();
+                    break;
+                }
+                if !is_new && child.0.is_empty() {
+                    // A parent path already covers the whole field.
+                    break;
+                }
+                node = child;
+            }
+        }
+        root
+    }
+}
+
+fn trim_tree(tree: &PathTree, message: &mut dyn MessageDyn) {
+    for field in message.descriptor_dyn().fields() {
+        match tree.0.get(field.name()) {
+            None => field.clear_field(message),
+            Some(subtree) if subtree.0.is_empty() => {}
+            Some(subtree) => {
+                if field.has_field(message) {
+                    trim_tree(subtree, field.mut_message(message));
+                }
+            }
+        }
+    }
+}
+
+fn merge_tree(tree: &PathTree, source: &dyn MessageDyn, target: &mut dyn MessageDyn) {
+    let descriptor = source.descriptor_dyn();
+    for (name, subtree) in &tree.0 {
+        let field = descriptor.field_by_name(name).unwrap();
+        if subtree.0.is_empty() {
+            copy_field(&field, source, target);
+        } else {
+            let source = field.get_message(source);
+            merge_tree(subtree, &*source, field.mut_message(target));
+        }
+    }
+}
+
+fn copy_field(field: &FieldDescriptor, source: &dyn MessageDyn, target: &mut dyn MessageDyn) {
+    field.clear_field(target);
+    match field.get_reflect(source) {
+        ReflectFieldRef::Optional(value) => {
+            if let Some(value) = value.value() {
+                field.set_singular_field(target, value.to_box());
+            }
+        }
+        ReflectFieldRef::Repeated(values) => {
+            let mut target = field.mut_repeated(target);
+            for value in &values {
+                target.push(value.to_box());
+            }
+        }
+        ReflectFieldRef::Map(values) => {
+            let mut target = field.mut_map(target);
+            for (key, value) in &values {
+                target.insert(key.to_box(), value.to_box());
+            }
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! Streaming types shaped like those of grpcio, for migrating code bases
+//! whose call sites are written against grpcio's streaming API.
+//!
+//! grpcio hands out a sink of requests and a stream of responses as soon as a
+//! streaming call starts, while tonic takes the request stream as an argument
+//! and returns the response stream once the server answered. The helpers of
+//! this module spawn the tonic call and return grpcio-like halves instead, so
+//! that the code driving them only needs its imports changed:
+//!
+//! ```rust,ignore
+//! use futures::{SinkExt, TryStreamExt};
+//! use tonic_types_protobuf::grpcio::{self, WriteFlags};
+//!
+//! let mut client = client.clone();
+//! let (mut tx, mut rx) = grpcio::duplex_streaming(16, move |requests| async move {
+//!     client.batch_commands(requests).await
+//! });
+//! tx.send((request, WriteFlags::default())).await?;
+//! tx.close().await?;
+//! while let Some(response) = rx.try_next().await? {
+//!     // ...
+//! }
+//! ```
+//!
+//! On the server, [`response_sink`] pairs a [`DuplexSink`] with the stream
+//! returned by the handler, and [`RequestStream`] names tonic's stream of
+//! requests.
+//!
+//! Errors are `tonic::Status`es rather than `grpcio::Error`s; sending to a
+//! call that ended fails with [`Code::Cancelled`]. Where code still deals in
+//! grpcio statuses, [`RpcStatus`] converts from and into `tonic::Status`.
+
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_channel::mpsc;
+use futures_sink::Sink;
+use tokio::task::JoinHandle;
+use tonic::{
+    codegen::{tokio_stream::Stream, Bytes},
+    Code, Response, Status,
+};
+
+/// The stream of requests of client-streaming and bidirectional methods, as
+/// passed to handlers.
+pub type RequestStream<T> = tonic::Streaming<T>;
+
+/// Flags of a message written to a [`StreamingCallSink`] or a
+/// [`DuplexSink`].
+///
+/// The flags are accepted for compatibility only: tonic batches writes and
+/// compresses messages as configured on the client or server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteFlags {
+    buffer_hint: bool,
+    force_no_compress: bool,
+}
+
+impl WriteFlags {
+    /// Hints that the message may be buffered.
+    pub fn buffer_hint(mut self, need_buffer: bool) -> WriteFlags {
+        self.buffer_hint = need_buffer;
+        self
+    }
+
+    /// Asks for the message not to be compressed.
+    pub fn force_no_compress(mut self, no_compress: bool) -> WriteFlags {
+        self.force_no_compress = no_compress;
+        self
+    }
+
+    /// Returns whether the message may be buffered.
+    pub fn get_buffer_hint(self) -> bool {
+        self.buffer_hint
+    }
+
+    /// Returns whether the message must not be compressed.
+    pub fn get_force_no_compress(self) -> bool {
+        self.force_no_compress
+    }
+}
+
+fn ended<E>(_: E) -> Status {
+    Status::cancelled("the call ended")
+}
+
+/// Starts a bidirectional streaming call, returning a sink of its requests
+/// and a stream of its responses.
+///
+/// `call` makes the tonic call with the given request stream, e.g.
+/// `move |requests| async move { client.chat(requests).await }` with a clone
+/// of the client. It is spawned on the current tokio runtime, and the sink
+/// queues about `buffer` requests while the server does not read them.
+/// Dropping the receiver cancels the call.
+///
+/// # Panics
+///
+/// Panics if called outside of a tokio runtime.
+pub fn duplex_streaming<Req, Resp, F, Fut, S>(
+    buffer: usize,
+    call: F,
+) -> (ClientDuplexSender<Req>, ClientDuplexReceiver<Resp>)
+where
+    F: FnOnce(SinkRequests<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: Stream<Item = Result<Resp, Status>> + Send + 'static,
+    Resp: 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer);
+    let call = call(SinkRequests { rx });
+    (StreamingCallSink { tx }, ClientDuplexReceiver::spawn(call))
+}
+
+/// Starts a client streaming call, returning a sink of its requests and a
+/// future of its response.
+///
+/// `call` is spawned as for [`duplex_streaming`]. Dropping the receiver
+/// cancels the call.
+///
+/// # Panics
+///
+/// Panics if called outside of a tokio runtime.
+pub fn client_streaming<Req, Resp, F, Fut>(
+    buffer: usize,
+    call: F,
+) -> (ClientCStreamSender<Req>, ClientCStreamReceiver<Resp>)
+where
+    F: FnOnce(SinkRequests<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<Resp>, Status>> + Send + 'static,
+    Resp: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer);
+    let call = call(SinkRequests { rx });
+    let receiver = ClientCStreamReceiver {
+        call: tokio::spawn(call),
+    };
+    (StreamingCallSink { tx }, receiver)
+}
+
+/// Starts a server streaming call, returning a stream of its responses.
+///
+/// `call` makes the tonic call, e.g.
+/// `async move { client.list(request).await }` with a clone of the client, and
+/// is spawned on the current tokio runtime. Dropping the receiver cancels the
+/// call.
+///
+/// # Panics
+///
+/// Panics if called outside of a tokio runtime.
+pub fn server_streaming<Resp, Fut, S>(call: Fut) -> ClientSStreamReceiver<Resp>
+where
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: Stream<Item = Result<Resp, Status>> + Send + 'static,
+    Resp: 'static,
+{
+    ClientDuplexReceiver::spawn(call)
+}
+
+/// The sink of the requests of a call, see [`duplex_streaming`] and
+/// [`client_streaming`].
+///
+/// Closing the sink, e.g. with `SinkExt::close`, ends the request stream.
+pub struct StreamingCallSink<T> {
+    tx: mpsc::Sender<T>,
+}
+
+/// The sink of the requests of a client streaming call.
+pub type ClientCStreamSender<T> = StreamingCallSink<T>;
+/// The sink of the requests of a bidirectional streaming call.
+pub type ClientDuplexSender<T> = StreamingCallSink<T>;
+
+impl<T> fmt::Debug for StreamingCallSink<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingCallSink")
+            .field("closed", &self.tx.is_closed())
+            .finish()
+    }
+}
+
+impl<T> Sink<(T, WriteFlags)> for StreamingCallSink<T> {
+    type Error = Status;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        self.tx.poll_ready(cx).map_err(ended)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, (message, _): (T, WriteFlags)) -> Result<(), Status> {
+        self.tx.start_send(message).map_err(ended)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Pin::new(&mut self.tx).poll_flush(cx).map_err(ended)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Pin::new(&mut self.tx).poll_close(cx).map_err(ended)
+    }
+}
+
+/// The requests written to a [`StreamingCallSink`], which serve as the
+/// request stream of the tonic call.
+pub struct SinkRequests<T> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> fmt::Debug for SinkRequests<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SinkRequests").finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for SinkRequests<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+enum ReceiverState<T> {
+    /// The call is waiting for the response of the server.
+    Pending(JoinHandle<Result<BoxStream<T>, Status>>),
+    Streaming(BoxStream<T>),
+    Done,
+}
+
+/// The stream of the responses of a call, see [`duplex_streaming`] and
+/// [`server_streaming`].
+pub struct ClientDuplexReceiver<T> {
+    state: ReceiverState<T>,
+}
+
+/// The stream of the responses of a server streaming call.
+pub type ClientSStreamReceiver<T> = ClientDuplexReceiver<T>;
+
+impl<T: 'static> ClientDuplexReceiver<T> {
+    fn spawn<Fut, S>(call: Fut) -> Self
+    where
+        Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+        S: Stream<Item = Result<T, Status>> + Send + 'static,
+    {
+        let call = tokio::spawn(async move {
+            call.await
+                .map(|response| Box::pin(response.into_inner()) as BoxStream<T>)
+        });
+        ClientDuplexReceiver {
+            state: ReceiverState::Pending(call),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ClientDuplexReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state {
+            ReceiverState::Pending(_) => "Pending",
+            ReceiverState::Streaming(_) => "Streaming",
+            ReceiverState::Done => "Done",
+        };
+        f.debug_struct("ClientDuplexReceiver")
+            .field("state", &state)
+            .finish()
+    }
+}
+
+impl<T> Stream for ClientDuplexReceiver<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match &mut self.state {
+                ReceiverState::Pending(call) => {
+                    match std::task::ready!(Pin::new(call).poll(cx)).map_err(join_error)? {
+                        Ok(stream) => self.state = ReceiverState::Streaming(stream),
+                        Err(status) => {
+                            self.state = ReceiverState::Done;
+                            return Poll::Ready(Some(Err(status)));
+                        }
+                    }
+                }
+                ReceiverState::Streaming(stream) => {
+                    let item = std::task::ready!(stream.as_mut().poll_next(cx));
+                    if item.is_none() {
+                        self.state = ReceiverState::Done;
+                    }
+                    return Poll::Ready(item);
+                }
+                ReceiverState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<T> Drop for ClientDuplexReceiver<T> {
+    fn drop(&mut self) {
+        if let ReceiverState::Pending(call) = &self.state {
+            call.abort();
+        }
+    }
+}
+
+/// The response of a client streaming call, see [`client_streaming`].
+pub struct ClientCStreamReceiver<T> {
+    call: JoinHandle<Result<Response<T>, Status>>,
+}
+
+impl<T> fmt::Debug for ClientCStreamReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCStreamReceiver")
+            .field("finished", &self.call.is_finished())
+            .finish()
+    }
+}
+
+impl<T> Future for ClientCStreamReceiver<T> {
+    type Output = Result<T, Status>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let response = std::task::ready!(Pin::new(&mut self.call).poll(cx)).map_err(join_error)?;
+        Poll::Ready(response.map(Response::into_inner))
+    }
+}
+
+impl<T> Drop for ClientCStreamReceiver<T> {
+    fn drop(&mut self) {
+        self.call.abort();
+    }
+}
+
+/// Resumes the panic of a spawned call. Calls are only aborted when their
+/// receiver is dropped, so a receiver never sees a cancelled call.
+fn join_error(e: tokio::task::JoinError) -> Status {
+    std::panic::resume_unwind(e.into_panic())
+}
+
+/// Creates the sink of the responses of a streaming method, and the stream
+/// to return from its handler, which queues about `buffer` responses.
+///
+/// ```rust,ignore
+/// async fn chat(
+///     &self,
+///     request: Request<RequestStream<Note>>,
+/// ) -> Result<Response<Self::ChatStream>, Status> {
+///     let (sink, responses) = grpcio::response_sink(16);
+///     tokio::spawn(chat(request.into_inner(), sink));
+///     Ok(Response::new(responses))
+/// }
+/// ```
+///
+/// Closing the sink, e.g. with `SinkExt::close`, ends the call with an OK
+/// status, and [`DuplexSink::fail`] ends it with an error.
+pub fn response_sink<T>(buffer: usize) -> (DuplexSink<T>, SinkResponses<T>) {
+    let (tx, rx) = mpsc::channel(buffer);
+    (DuplexSink { tx }, SinkResponses { rx })
+}
+
+/// The sink of the responses of a streaming method, see [`response_sink`].
+pub struct DuplexSink<T> {
+    tx: mpsc::Sender<Result<T, Status>>,
+}
+
+/// The sink of the responses of a server streaming method.
+pub type ServerStreamingSink<T> = DuplexSink<T>;
+
+impl<T> fmt::Debug for DuplexSink<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplexSink")
+            .field("closed", &self.tx.is_closed())
+            .finish()
+    }
+}
+
+impl<T> DuplexSink<T> {
+    /// Ends the call with `status`, after the queued responses.
+    pub async fn fail(mut self, status: Status) -> Result<(), Status> {
+        poll_fn(|cx| self.tx.poll_ready(cx)).await.map_err(ended)?;
+        self.tx.start_send(Err(status)).map_err(ended)?;
+        poll_fn(|cx| Pin::new(&mut self.tx).poll_close(cx))
+            .await
+            .map_err(ended)
+    }
+}
+
+impl<T> Sink<(T, WriteFlags)> for DuplexSink<T> {
+    type Error = Status;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        self.tx.poll_ready(cx).map_err(ended)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, (message, _): (T, WriteFlags)) -> Result<(), Status> {
+        self.tx.start_send(Ok(message)).map_err(ended)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Pin::new(&mut self.tx).poll_flush(cx).map_err(ended)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Pin::new(&mut self.tx).poll_close(cx).map_err(ended)
+    }
+}
+
+/// The responses written to a [`DuplexSink`], which serve as the stream type
+/// of the method, e.g. `type ChatStream = SinkResponses<Note>;`.
+pub struct SinkResponses<T> {
+    rx: mpsc::Receiver<Result<T, Status>>,
+}
+
+impl<T> fmt::Debug for SinkResponses<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SinkResponses").finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for SinkResponses<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+/// A gRPC status code, shaped like grpcio's `RpcStatusCode`.
+///
+/// Codes are kept as numbers, so that codes unknown to tonic survive a round
+/// trip through [`RpcStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RpcStatusCode(i32);
+
+impl RpcStatusCode {
+    /// Not an error, returned on success.
+    pub const OK: RpcStatusCode = RpcStatusCode(0);
+    /// The operation was cancelled, typically by the caller.
+    pub const CANCELLED: RpcStatusCode = RpcStatusCode(1);
+    /// Unknown error.
+    pub const UNKNOWN: RpcStatusCode = RpcStatusCode(2);
+    /// The client specified an invalid argument.
+    pub const INVALID_ARGUMENT: RpcStatusCode = RpcStatusCode(3);
+    /// The deadline expired before the operation could complete.
+    pub const DEADLINE_EXCEEDED: RpcStatusCode = RpcStatusCode(4);
+    /// Some requested entity was not found.
+    pub const NOT_FOUND: RpcStatusCode = RpcStatusCode(5);
+    /// The entity that a client attempted to create already exists.
+    pub const ALREADY_EXISTS: RpcStatusCode = RpcStatusCode(6);
+    /// The caller does not have permission to execute the operation.
+    pub const PERMISSION_DENIED: RpcStatusCode = RpcStatusCode(7);
+    /// Some resource has been exhausted.
+    pub const RESOURCE_EXHAUSTED: RpcStatusCode = RpcStatusCode(8);
+    /// The system is not in a state required for the operation.
+    pub const FAILED_PRECONDITION: RpcStatusCode = RpcStatusCode(9);
+    /// The operation was aborted, typically due to a concurrency issue.
+    pub const ABORTED: RpcStatusCode = RpcStatusCode(10);
+    /// The operation was attempted past the valid range.
+    pub const OUT_OF_RANGE: RpcStatusCode = RpcStatusCode(11);
+    /// The operation is not implemented or not supported.
+    pub const UNIMPLEMENTED: RpcStatusCode = RpcStatusCode(12);
+    /// Internal error.
+    pub const INTERNAL: RpcStatusCode = RpcStatusCode(13);
+    /// The service is currently unavailable.
+    pub const UNAVAILABLE: RpcStatusCode = RpcStatusCode(14);
+    /// Unrecoverable data loss or corruption.
+    pub const DATA_LOSS: RpcStatusCode = RpcStatusCode(15);
+    /// The request does not have valid authentication credentials.
+    pub const UNAUTHENTICATED: RpcStatusCode = RpcStatusCode(16);
+}
+
+impl From<i32> for RpcStatusCode {
+    fn from(code: i32) -> Self {
+        RpcStatusCode(code)
+    }
+}
+
+impl From<RpcStatusCode> for i32 {
+    fn from(code: RpcStatusCode) -> Self {
+        code.0
+    }
+}
+
+impl From<Code> for RpcStatusCode {
+    fn from(code: Code) -> Self {
+        RpcStatusCode(code as i32)
+    }
+}
+
+impl From<RpcStatusCode> for Code {
+    /// Converts the code, mapping codes unknown to tonic to
+    /// [`Code::Unknown`].
+    fn from(code: RpcStatusCode) -> Self {
+        Code::from_i32(code.0)
+    }
+}
+
+/// The status of a call, shaped like grpcio's `RpcStatus`, for translating
+/// errors between grpcio and tonic at the boundary of migration shims.
+///
+/// The details are the encoded `google.rpc.Status` sent in the
+/// `grpc-status-details-bin` trailer, as in [`Status::details`], so rich
+/// errors convert losslessly. The metadata of a `tonic::Status`, which
+/// grpcio does not keep in its statuses, is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcStatus {
+    code: RpcStatusCode,
+    message: String,
+    details: Vec<u8>,
+}
+
+impl RpcStatus {
+    /// Creates a status with `code`, without a message.
+    pub fn new<T: Into<RpcStatusCode>>(code: T) -> RpcStatus {
+        RpcStatus::with_details(code, String::new(), vec![])
+    }
+
+    /// Creates a status with `code` and `message`.
+    pub fn with_message<T: Into<RpcStatusCode>>(code: T, message: String) -> RpcStatus {
+        RpcStatus::with_details(code, message, vec![])
+    }
+
+    /// Creates a status with `code`, `message` and encoded `details`.
+    pub fn with_details<T: Into<RpcStatusCode>>(
+        code: T,
+        message: String,
+        details: Vec<u8>,
+    ) -> RpcStatus {
+        RpcStatus {
+            code: code.into(),
+            message,
+            details,
+        }
+    }
+
+    /// Returns the code.
+    pub fn code(&self) -> RpcStatusCode {
+        self.code
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the encoded details.
+    pub fn details(&self) -> &[u8] {
+        &self.details
+    }
+}
+
+impl From<Status> for RpcStatus {
+    fn from(status: Status) -> Self {
+        RpcStatus {
+            code: status.code().into(),
+            message: status.message().to_owned(),
+            details: status.details().to_vec(),
+        }
+    }
+}
+
+impl From<RpcStatus> for Status {
+    fn from(status: RpcStatus) -> Self {
+        Status::with_details(
+            status.code.into(),
+            status.message,
+            Bytes::from(status.details),
+        )
+    }
+}
@@ -0,0 +1,35 @@
+//! The gRPC richer error model for tonic with rust-protobuf.
+//!
+//! Errors are sent as a `google.rpc.Status` packed in the details of a
+//! `tonic::Status`, which tonic transmits in the `grpc-status-details-bin`
+//! trailer. This is the encoding used by the gRPC implementations of other
+//! languages, see <https://grpc.io/docs/guides/error/#richer-error-model>.
+
+pub mod pb {
+    //! Generated types of `google.rpc` and `google.longrunning`, the custom
+    //! options of `tonic_protobuf/options.proto`, and the records of
+    //! `tonic_protobuf/sample.proto`.
+
+    include!(concat!(env!("OUT_DIR"), "/mod.rs"));
+    include!(concat!(env!("OUT_DIR"), "/operations_tonic.rs"));
+}
+
+pub mod any;
+mod error_details;
+pub mod field_mask;
+#[cfg(feature = "grpcio-compat")]
+pub mod grpcio;
+pub mod longrunning;
+pub mod metadata;
+#[cfg(feature = "prost")]
+pub mod prost;
+pub mod redact;
+mod richer_error;
+mod rpc_error;
+pub mod schema;
+pub mod stream;
+pub mod time;
+
+pub use error_details::ErrorDetails;
+pub use richer_error::StatusExt;
+pub use rpc_error::{ResultExt, RpcError};
@@ -0,0 +1,181 @@
+//! Typed access to long-running operations, see
+//! [AIP-151](https://google.aip.dev/151).
+//!
+//! Methods returning a `google.longrunning.Operation` declare the messages
+//! packed in its response and metadata with the
+//! `google.longrunning.operation_info` method option. [`TypedOperation`]
+//! unpacks them, and polls the operation through an [`OperationsClient`] until
+//! it is done:
+//!
+//! ```rust,ignore
+//! let operation: TypedOperation<Book, CreateBookMetadata> =
+//!     TypedOperation::new(client.create_book(request).await?.into_inner());
+//! let book = operation.wait(&mut operations, Duration::from_secs(1)).await?;
+//! ```
+//!
+//! Clients generated with `Builder::longrunning_operations` of
+//! tonic-build-protobuf return typed operations from `{method}_operation`
+//! methods.
+
+use std::{fmt, marker::PhantomData, time::Duration};
+
+use protobuf::{well_known_types::any::Any, Message, MessageFull};
+use tonic::{
+    codegen::{Body, Bytes, StdError},
+    Code, Status,
+};
+
+pub use crate::pb::{
+    operations::{
+        operation, CancelOperationRequest, DeleteOperationRequest, GetOperationRequest,
+        ListOperationsRequest, ListOperationsResponse, Operation, OperationInfo,
+        WaitOperationRequest,
+    },
+    operations_client::OperationsClient,
+    operations_server::{Operations, OperationsServer},
+};
+
+/// An operation whose response is a `R` and whose metadata is a `M`.
+pub struct TypedOperation<R, M> {
+    operation: Operation,
+    _types: PhantomData<fn() -> (R, M)>,
+}
+
+impl<R, M> fmt::Debug for TypedOperation<R, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedOperation")
+            .field(&self.operation)
+            .finish()
+    }
+}
+
+impl<R, M> Clone for TypedOperation<R, M> {
+    fn clone(&self) -> Self {
+        TypedOperation::new(self.operation.clone())
+    }
+}
+
+impl<R, M> TypedOperation<R, M> {
+    /// Wraps `operation`, whose response and metadata types are not checked
+    /// until they are unpacked.
+    pub fn new(operation: Operation) -> Self {
+        TypedOperation {
+            operation,
+            _types: PhantomData,
+        }
+    }
+
+    /// The server-assigned name of the operation.
+    pub fn name(&self) -> &str {
+        &self.operation.name
+    }
+
+    /// Returns whether the operation completed, with a response or an error.
+    pub fn is_done(&self) -> bool {
+        self.operation.done
+    }
+
+    /// The latest state of the operation.
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    /// Returns the latest state of the operation.
+    pub fn into_inner(self) -> Operation {
+        self.operation
+    }
+}
+
+impl<R: MessageFull, M: MessageFull> TypedOperation<R, M> {
+    /// Unpacks the metadata, or returns `None` if the operation has none.
+    ///
+    /// Metadata of another type than `M` is reported as an `Internal` status.
+    #[allow(clippy::result_large_err)]
+    pub fn metadata(&self) -> Result<Option<M>, Status> {
+        self.operation
+            .metadata
+            .as_ref()
+            .map(unpack::<M>)
+            .transpose()
+    }
+
+    /// Unpacks the response, or returns `None` if the operation is not done
+    /// yet.
+    ///
+    /// The error of a failed operation is returned as a status that carries
+    /// its `google.rpc.Status`, see [`StatusExt`](crate::StatusExt). A
+    /// response of another type than `R` is reported as an `Internal` status.
+    #[allow(clippy::result_large_err)]
+    pub fn result(&self) -> Result<Option<R>, Status> {
+        if !self.operation.done {
+            return Ok(None);
+        }
+        match &self.operation.result {
+            Some(operation::Result::Response(response)) => unpack(response).map(Some),
+            Some(operation::Result::Error(error)) => {
+                let details = error.write_to_bytes().unwrap_or_default();
+                Err(Status::with_details(
+                    Code::from_i32(error.code),
+                    error.message.clone(),
+                    details.into(),
+                ))
+            }
+            None => Err(Status::internal(format!(
+                "operation {} is done without a result",
+                self.operation.name
+            ))),
+        }
+    }
+
+    /// Fetches the latest state of the operation with `GetOperation`.
+    pub async fn refresh<T>(&mut self, client: &mut OperationsClient<T>) -> Result<(), Status>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        let mut request = GetOperationRequest::new();
+        request.name.clone_from(&self.operation.name);
+        self.operation = client.get_operation(request).await?.into_inner();
+        Ok(())
+    }
+
+    /// Polls the operation every `interval` until it is done, and returns its
+    /// response. Wrap the future with `tokio::time::timeout` to bound the wait.
+    pub async fn wait<T>(
+        mut self,
+        client: &mut OperationsClient<T>,
+        interval: Duration,
+    ) -> Result<R, Status>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        loop {
+            if let Some(response) = self.result()? {
+                return Ok(response);
+            }
+            tokio::time::sleep(interval).await;
+            self.refresh(client).await?;
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn unpack<M: MessageFull>(any: &Any) -> Result<M, Status> {
+    match any.unpack::<M>() {
+        Ok(Some(message)) => Ok(message),
+        Ok(None) => Err(Status::internal(format!(
+            "operation carries {}, expected {}",
+            any.type_url,
+            M::descriptor().full_name()
+        ))),
+        Err(e) => Err(Status::internal(format!(
+            "failed to decode {}: {}",
+            any.type_url, e
+        ))),
+    }
+}
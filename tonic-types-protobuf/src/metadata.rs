@@ -0,0 +1,107 @@
+//! Helpers to carry rust-protobuf messages in binary (`-bin`) metadata.
+//!
+//! tonic base64 encodes binary metadata values on the wire, the helpers here
+//! only add the protobuf (de)serialization and typed errors.
+
+use std::fmt;
+
+use protobuf::Message;
+use tonic::metadata::{BinaryMetadataKey, MetadataMap, MetadataValue};
+
+/// Represents an error in carrying a message in metadata.
+#[derive(Debug)]
+pub enum MetadataError {
+    /// The key is not a valid binary metadata key, i.e. it does not end with
+    /// `-bin` or contains invalid characters.
+    InvalidKey(String),
+    /// The message can not be encoded.
+    Encode(protobuf::Error),
+    /// The value of the key is not valid base64.
+    InvalidValue(String),
+    /// The value of the key can not be decoded as the message.
+    Decode(String, protobuf::Error),
+}
+
+impl std::error::Error for MetadataError {}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::InvalidKey(key) => write!(f, "invalid binary metadata key: {}", key),
+            MetadataError::Encode(e) => write!(f, "failed to encode message: {}", e),
+            MetadataError::InvalidValue(key) => {
+                write!(f, "invalid base64 value of metadata {}", key)
+            }
+            MetadataError::Decode(key, e) => {
+                write!(f, "failed to decode message of metadata {}: {}", key, e)
+            }
+        }
+    }
+}
+
+/// Extends `MetadataMap` with typed access to messages in binary entries.
+pub trait MetadataMapExt {
+    /// Inserts `message` at `key`, replacing any existing values.
+    fn insert_message<M: Message>(&mut self, key: &str, message: &M) -> Result<(), MetadataError>;
+
+    /// Appends `message` to the values of `key`.
+    fn append_message<M: Message>(&mut self, key: &str, message: &M) -> Result<(), MetadataError>;
+
+    /// Returns the first value of `key` decoded as `M`, or `None` if the key
+    /// is absent.
+    fn get_message<M: Message>(&self, key: &str) -> Result<Option<M>, MetadataError>;
+
+    /// Returns all values of `key` decoded as `M`.
+    fn get_all_messages<M: Message>(&self, key: &str) -> Result<Vec<M>, MetadataError>;
+}
+
+fn binary_key(key: &str) -> Result<BinaryMetadataKey, MetadataError> {
+    BinaryMetadataKey::from_bytes(key.as_bytes())
+        .map_err(|_| MetadataError::InvalidKey(key.to_owned()))
+}
+
+fn encode<M: Message>(
+    message: &M,
+) -> Result<MetadataValue<tonic::metadata::Binary>, MetadataError> {
+    let bytes = message.write_to_bytes().map_err(MetadataError::Encode)?;
+    Ok(MetadataValue::from_bytes(&bytes))
+}
+
+fn decode<M: Message>(
+    key: &str,
+    value: &MetadataValue<tonic::metadata::Binary>,
+) -> Result<M, MetadataError> {
+    let bytes = value
+        .to_bytes()
+        .map_err(|_| MetadataError::InvalidValue(key.to_owned()))?;
+    M::parse_from_bytes(&bytes).map_err(|e| MetadataError::Decode(key.to_owned(), e))
+}
+
+impl MetadataMapExt for MetadataMap {
+    fn insert_message<M: Message>(&mut self, key: &str, message: &M) -> Result<(), MetadataError> {
+        let key = binary_key(key)?;
+        self.insert_bin(key, encode(message)?);
+        Ok(())
+    }
+
+    fn append_message<M: Message>(&mut self, key: &str, message: &M) -> Result<(), MetadataError> {
+        let key = binary_key(key)?;
+        self.append_bin(key, encode(message)?);
+        Ok(())
+    }
+
+    fn get_message<M: Message>(&self, key: &str) -> Result<Option<M>, MetadataError> {
+        let bin_key = binary_key(key)?;
+        self.get_bin(bin_key)
+            .map(|value| decode(key, value))
+            .transpose()
+    }
+
+    fn get_all_messages<M: Message>(&self, key: &str) -> Result<Vec<M>, MetadataError> {
+        let bin_key = binary_key(key)?;
+        self.get_all_bin(bin_key)
+            .iter()
+            .map(|value| decode(key, value))
+            .collect()
+    }
+}
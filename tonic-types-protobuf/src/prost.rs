@@ -0,0 +1,167 @@
+//! Conversions between the well-known types of `prost-types` and of
+//! rust-protobuf, for workspaces mixing both, e.g. to embed the prost
+//! messages that tonic crates hand out into rust-protobuf messages.
+//!
+//! ```rust,ignore
+//! use tonic_types_protobuf::prost::ProstConvert;
+//!
+//! let created = Timestamp::from_prost(prost_timestamp);
+//! let prost_struct = labels.into_prost();
+//! ```
+//!
+//! Both represent the same wire format, so conversions are lossless and
+//! values are copied as they are, without validating ranges. Unknown fields
+//! of rust-protobuf messages, which prost does not keep, are dropped.
+
+use protobuf::{
+    well_known_types::{
+        any::Any,
+        duration::Duration,
+        struct_::{value::Kind, ListValue, Struct, Value},
+        timestamp::Timestamp,
+    },
+    EnumOrUnknown,
+};
+
+/// A rust-protobuf well-known type with a `prost-types` counterpart.
+pub trait ProstConvert: Sized {
+    /// The `prost-types` counterpart, e.g. `prost_types::Timestamp`.
+    type Prost;
+
+    /// Converts the `prost-types` counterpart.
+    fn from_prost(value: Self::Prost) -> Self;
+
+    /// Converts into the `prost-types` counterpart.
+    fn into_prost(self) -> Self::Prost;
+}
+
+impl ProstConvert for Timestamp {
+    type Prost = prost_types::Timestamp;
+
+    fn from_prost(value: prost_types::Timestamp) -> Self {
+        let mut timestamp = Timestamp::new();
+        timestamp.seconds = value.seconds;
+        timestamp.nanos = value.nanos;
+        timestamp
+    }
+
+    fn into_prost(self) -> prost_types::Timestamp {
+        prost_types::Timestamp {
+            seconds: self.seconds,
+            nanos: self.nanos,
+        }
+    }
+}
+
+impl ProstConvert for Duration {
+    type Prost = prost_types::Duration;
+
+    fn from_prost(value: prost_types::Duration) -> Self {
+        let mut duration = Duration::new();
+        duration.seconds = value.seconds;
+        duration.nanos = value.nanos;
+        duration
+    }
+
+    fn into_prost(self) -> prost_types::Duration {
+        prost_types::Duration {
+            seconds: self.seconds,
+            nanos: self.nanos,
+        }
+    }
+}
+
+impl ProstConvert for Any {
+    type Prost = prost_types::Any;
+
+    fn from_prost(value: prost_types::Any) -> Self {
+        let mut any = Any::new();
+        any.type_url = value.type_url;
+        any.value = value.value;
+        any
+    }
+
+    fn into_prost(self) -> prost_types::Any {
+        prost_types::Any {
+            type_url: self.type_url,
+            value: self.value,
+        }
+    }
+}
+
+impl ProstConvert for Struct {
+    type Prost = prost_types::Struct;
+
+    fn from_prost(value: prost_types::Struct) -> Self {
+        let mut s = Struct::new();
+        s.fields = value
+            .fields
+            .into_iter()
+            .map(|(key, value)| (key, Value::from_prost(value)))
+            .collect();
+        s
+    }
+
+    fn into_prost(self) -> prost_types::Struct {
+        prost_types::Struct {
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(key, value)| (key, value.into_prost()))
+                .collect(),
+        }
+    }
+}
+
+impl ProstConvert for ListValue {
+    type Prost = prost_types::ListValue;
+
+    fn from_prost(value: prost_types::ListValue) -> Self {
+        let mut list = ListValue::new();
+        list.values = value.values.into_iter().map(Value::from_prost).collect();
+        list
+    }
+
+    fn into_prost(self) -> prost_types::ListValue {
+        prost_types::ListValue {
+            values: self.values.into_iter().map(Value::into_prost).collect(),
+        }
+    }
+}
+
+impl ProstConvert for Value {
+    type Prost = prost_types::Value;
+
+    fn from_prost(value: prost_types::Value) -> Self {
+        use prost_types::value::Kind as ProstKind;
+
+        let mut v = Value::new();
+        v.kind = value.kind.map(|kind| match kind {
+            ProstKind::NullValue(null) => Kind::NullValue(EnumOrUnknown::from_i32(null)),
+            ProstKind::NumberValue(number) => Kind::NumberValue(number),
+            ProstKind::StringValue(string) => Kind::StringValue(string),
+            ProstKind::BoolValue(bool) => Kind::BoolValue(bool),
+            ProstKind::StructValue(s) => Kind::StructValue(Struct::from_prost(s)),
+            ProstKind::ListValue(list) => Kind::ListValue(ListValue::from_prost(list)),
+        });
+        v
+    }
+
+    fn into_prost(self) -> prost_types::Value {
+        use prost_types::value::Kind as ProstKind;
+
+        prost_types::Value {
+            kind: self.kind.and_then(|kind| match kind {
+                Kind::NullValue(null) => Some(ProstKind::NullValue(null.value())),
+                Kind::NumberValue(number) => Some(ProstKind::NumberValue(number)),
+                Kind::StringValue(string) => Some(ProstKind::StringValue(string)),
+                Kind::BoolValue(bool) => Some(ProstKind::BoolValue(bool)),
+                Kind::StructValue(s) => Some(ProstKind::StructValue(s.into_prost())),
+                Kind::ListValue(list) => Some(ProstKind::ListValue(list.into_prost())),
+                // Kinds added to rust-protobuf later have no prost
+                // counterpart.
+                _ => None,
+            }),
+        }
+    }
+}
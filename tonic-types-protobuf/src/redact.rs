@@ -0,0 +1,110 @@
+//! Masks the fields marked `(tonic_protobuf.sensitive) = true` in any
+//! rust-protobuf message through reflection, before it is logged, printed or
+//! captured.
+//!
+//! The option is declared in `tonic_protobuf/options.proto`, shipped in the
+//! `proto` directory of this crate. It is read from the descriptors, so
+//! redaction works the same for generated and dynamic messages.
+
+use protobuf::{
+    reflect::{
+        FieldDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef, RuntimeFieldType,
+        RuntimeType,
+    },
+    MessageDyn,
+};
+
+use crate::pb::options::exts;
+
+/// The value sensitive string and bytes fields are replaced with.
+pub const PLACEHOLDER: &str = "[REDACTED]";
+
+/// Returns whether `field` is marked `(tonic_protobuf.sensitive) = true`.
+pub fn is_sensitive(field: &FieldDescriptor) -> bool {
+    field
+        .proto()
+        .options
+        .as_ref()
+        .and_then(|options| exts::sensitive.get(options))
+        .unwrap_or(false)
+}
+
+/// Masks the sensitive fields of `message` and of all messages nested in it.
+///
+/// Sensitive string and bytes fields, including repeated ones, are replaced
+/// with [`PLACEHOLDER`], so that the output still shows they were set. Other
+/// sensitive fields are cleared, including maps, whose keys may be sensitive
+/// as well.
+pub fn redact(message: &mut dyn MessageDyn) {
+    for field in message.descriptor_dyn().fields() {
+        let sensitive = is_sensitive(&field);
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(_) if !field.has_field(message) => {}
+            RuntimeFieldType::Singular(RuntimeType::Message(_)) if !sensitive => {
+                redact(field.mut_message(message));
+            }
+            RuntimeFieldType::Singular(element) if sensitive => match placeholder(&element) {
+                Some(value) => field.set_singular_field(message, value),
+                None => field.clear_field(message),
+            },
+            RuntimeFieldType::Repeated(element) if sensitive => {
+                if placeholder(&element).is_none() {
+                    field.clear_field(message);
+                    continue;
+                }
+                let mut values = field.mut_repeated(message);
+                for index in 0..values.len() {
+                    values.set(index, placeholder(&element).unwrap());
+                }
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(_)) => {
+                let values: Vec<_> = match field.get_reflect(message) {
+                    ReflectFieldRef::Repeated(values) => values.into_iter().map(redacted).collect(),
+                    _ => unreachable!(),
+                };
+                let mut repeated = field.mut_repeated(message);
+                for (index, value) in values.into_iter().enumerate() {
+                    repeated.set(index, value);
+                }
+            }
+            RuntimeFieldType::Map(..) if sensitive => field.clear_field(message),
+            RuntimeFieldType::Map(_, RuntimeType::Message(_)) => {
+                let entries: Vec<_> = match field.get_reflect(message) {
+                    ReflectFieldRef::Map(map) => map
+                        .into_iter()
+                        .map(|(key, value)| (key.to_box(), redacted(value)))
+                        .collect(),
+                    _ => unreachable!(),
+                };
+                let mut map = field.mut_map(message);
+                for (key, value) in entries {
+                    map.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns a copy of `message` with its sensitive fields masked, see
+/// [`redact`].
+pub fn redacted_copy(message: &dyn MessageDyn) -> Box<dyn MessageDyn> {
+    let mut copy = message.clone_box();
+    redact(&mut *copy);
+    copy
+}
+
+fn placeholder(element: &RuntimeType) -> Option<ReflectValueBox> {
+    match element {
+        RuntimeType::String => Some(ReflectValueBox::String(PLACEHOLDER.to_owned())),
+        RuntimeType::VecU8 => Some(ReflectValueBox::Bytes(PLACEHOLDER.as_bytes().to_vec())),
+        _ => None,
+    }
+}
+
+fn redacted(value: ReflectValueRef) -> ReflectValueBox {
+    match value {
+        ReflectValueRef::Message(message) => ReflectValueBox::Message(redacted_copy(&*message)),
+        value => value.to_box(),
+    }
+}
@@ -0,0 +1,78 @@
+use protobuf::{well_known_types::any::Any, Message, MessageFull};
+use tonic::{Code, Status};
+
+use crate::{pb::status::Status as RpcStatus, ErrorDetails};
+
+/// Extends `tonic::Status` with the gRPC richer error model.
+pub trait StatusExt: Sized {
+    /// Creates a `Status` whose details carry a `google.rpc.Status` with the
+    /// given code, message and details.
+    ///
+    /// Details are usually packed with `Any::pack`.
+    fn with_details_any(code: Code, message: impl Into<String>, details: Vec<Any>) -> Self;
+
+    /// Decodes the `google.rpc.Status` carried by the details.
+    ///
+    /// Returns `Ok(None)` if the status has no details.
+    fn check_rpc_status(&self) -> Result<Option<RpcStatus>, protobuf::Error>;
+
+    /// Returns the details of the carried `google.rpc.Status`, or nothing if
+    /// there are none or they can not be decoded.
+    fn details_any(&self) -> Vec<Any>;
+
+    /// Returns the first detail of type `M`, if any.
+    fn get_detail<M: MessageFull>(&self) -> Option<M>;
+
+    /// Creates a `Status` whose details carry the standard error `details`.
+    fn with_error_details(code: Code, message: impl Into<String>, details: ErrorDetails) -> Self {
+        Self::with_details_any(code, message, details.to_any())
+    }
+
+    /// Decodes the standard error details carried by the status.
+    fn check_error_details(&self) -> Result<ErrorDetails, protobuf::Error> {
+        match self.check_rpc_status()? {
+            Some(status) => ErrorDetails::from_any(&status.details),
+            None => Ok(ErrorDetails::new()),
+        }
+    }
+
+    /// Returns the standard error details carried by the status, or empty
+    /// details if they can not be decoded.
+    fn get_error_details(&self) -> ErrorDetails {
+        self.check_error_details().unwrap_or_default()
+    }
+}
+
+impl StatusExt for Status {
+    fn with_details_any(code: Code, message: impl Into<String>, details: Vec<Any>) -> Self {
+        let message = message.into();
+        let mut status = RpcStatus::new();
+        status.code = code as i32;
+        status.message = message.clone();
+        status.details = details;
+        let details = status
+            .write_to_bytes()
+            .expect("failed to encode google.rpc.Status");
+        Status::with_details(code, message, details.into())
+    }
+
+    fn check_rpc_status(&self) -> Result<Option<RpcStatus>, protobuf::Error> {
+        if self.details().is_empty() {
+            return Ok(None);
+        }
+        RpcStatus::parse_from_bytes(self.details()).map(Some)
+    }
+
+    fn details_any(&self) -> Vec<Any> {
+        match self.check_rpc_status() {
+            Ok(Some(status)) => status.details,
+            _ => vec![],
+        }
+    }
+
+    fn get_detail<M: MessageFull>(&self) -> Option<M> {
+        self.details_any()
+            .iter()
+            .find_map(|any| any.unpack::<M>().ok().flatten())
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use protobuf::well_known_types::any::Any;
+use tonic::{Code, Status};
+
+use crate::{ErrorDetails, StatusExt};
+
+/// An application error that maps to a gRPC status with rich error details.
+///
+/// Implement it for domain errors and convert them with
+/// [`impl_status_from!`](crate::impl_status_from) or
+/// [`ResultExt::into_status`], so that handlers can use `?` on them:
+///
+/// ```rust,ignore
+/// impl RpcError for StoreError {
+///     fn code(&self) -> Code {
+///         match self {
+///             StoreError::NotFound(_) => Code::NotFound,
+///             StoreError::Busy => Code::Unavailable,
+///         }
+///     }
+///
+///     fn details(&self) -> ErrorDetails {
+///         match self {
+///             StoreError::Busy => ErrorDetails::with_retry_info(Some(Duration::from_secs(1))),
+///             _ => ErrorDetails::new(),
+///         }
+///     }
+/// }
+/// tonic_types_protobuf::impl_status_from!(StoreError);
+///
+/// async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+///     let value = self.store.get(&request.get_ref().key)?;
+///     ...
+/// }
+/// ```
+pub trait RpcError: fmt::Display {
+    /// The status code of the error.
+    fn code(&self) -> Code;
+
+    /// The status message, the `Display` output by default.
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    /// The standard error details, none by default.
+    fn details(&self) -> ErrorDetails {
+        ErrorDetails::new()
+    }
+
+    /// Additional details, packed with `Any::pack`, none by default.
+    fn details_any(&self) -> Vec<Any> {
+        vec![]
+    }
+
+    /// Converts the error to a `Status`.
+    ///
+    /// The details are packed in a `google.rpc.Status`, a plain status is
+    /// returned if there are none.
+    fn to_status(&self) -> Status {
+        let mut details = self.details().to_any();
+        details.extend(self.details_any());
+        if details.is_empty() {
+            Status::new(self.code(), self.message())
+        } else {
+            Status::with_details_any(self.code(), self.message(), details)
+        }
+    }
+}
+
+/// Converts the error of a `Result` to a `Status`.
+pub trait ResultExt<T> {
+    /// Converts the error with [`RpcError::to_status`].
+    #[allow(clippy::result_large_err)]
+    fn into_status(self) -> Result<T, Status>;
+}
+
+impl<T, E: RpcError> ResultExt<T> for Result<T, E> {
+    fn into_status(self) -> Result<T, Status> {
+        self.map_err(|e| e.to_status())
+    }
+}
+
+/// Implements `From<E> for tonic::Status` for the given [`RpcError`] types.
+#[macro_export]
+macro_rules! impl_status_from {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl ::std::convert::From<$ty> for ::tonic::Status {
+                fn from(error: $ty) -> Self {
+                    $crate::RpcError::to_status(&error)
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,223 @@
+//! Detection of clients and servers built from different versions of the
+//! .proto files of a service.
+//!
+//! With `Builder::build_schema_hash(true)`, client and server modules get a
+//! `SCHEMA_HASH` constant, a hash of the methods of the service and the
+//! messages they use. Clients send it in the [`SCHEMA_HASH_KEY`] metadata with
+//! [`SchemaHashInterceptor`], and servers compare it with their own with
+//! [`SchemaCheckLayer`]:
+//!
+//! ```rust,ignore
+//! let client = StorageClient::with_interceptor(
+//!     channel,
+//!     SchemaHashInterceptor::new(storage_client::SCHEMA_HASH),
+//! );
+//!
+//! Server::builder()
+//!     .layer(
+//!         SchemaCheckLayer::new()
+//!             .service(storage_server::SERVICE_NAME, storage_server::SCHEMA_HASH)
+//!             .on_mismatch(|mismatch| eprintln!("{}", mismatch)),
+//!     )
+//!     .add_service(StorageServer::new(storage))
+//! ```
+//!
+//! Calls without the metadata, e.g. of clients not sending it yet, and calls
+//! of services without an expected hash are passed on unchecked.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use tonic::{
+    body::BoxBody,
+    codegen::{http, BoxFuture, Service},
+    metadata::AsciiMetadataValue,
+    service::Interceptor,
+    Request, Status,
+};
+use tower_layer::Layer;
+
+/// The metadata key of the schema hash of the called service.
+pub const SCHEMA_HASH_KEY: &str = "tonic-protobuf-schema-hash";
+
+/// An interceptor sending the schema hash of the called service with every
+/// request of a client.
+#[derive(Debug, Clone)]
+pub struct SchemaHashInterceptor {
+    hash: AsciiMetadataValue,
+}
+
+impl SchemaHashInterceptor {
+    /// Creates an interceptor sending `hash`, the `SCHEMA_HASH` of the client
+    /// module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hash` is not a valid metadata value.
+    pub fn new(hash: &'static str) -> Self {
+        SchemaHashInterceptor {
+            hash: AsciiMetadataValue::from_static(hash),
+        }
+    }
+}
+
+impl Interceptor for SchemaHashInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request
+            .metadata_mut()
+            .insert(SCHEMA_HASH_KEY, self.hash.clone());
+        Ok(request)
+    }
+}
+
+/// A call whose client sent another schema hash than the server expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// The path of the call, e.g. `/storage.Storage/Get`.
+    pub path: String,
+    /// The full name of the service, e.g. `storage.Storage`.
+    pub service: String,
+    /// The hash of the server.
+    pub expected: String,
+    /// The hash sent by the client.
+    pub actual: String,
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "schema of {} differs in call {}: the client has {}, the server {}",
+            self.service, self.path, self.actual, self.expected
+        )
+    }
+}
+
+type MismatchFn = dyn Fn(&SchemaMismatch) + Send + Sync;
+
+/// A layer checking the schema hashes sent by clients, see the
+/// [module](self) documentation.
+#[derive(Clone)]
+pub struct SchemaCheckLayer {
+    hashes: Arc<HashMap<String, String>>,
+    reject: bool,
+    on_mismatch: Option<Arc<MismatchFn>>,
+}
+
+impl Default for SchemaCheckLayer {
+    fn default() -> Self {
+        SchemaCheckLayer {
+            hashes: Arc::default(),
+            reject: true,
+            on_mismatch: None,
+        }
+    }
+}
+
+impl fmt::Debug for SchemaCheckLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaCheckLayer")
+            .field("hashes", &self.hashes)
+            .field("reject", &self.reject)
+            .field("on_mismatch", &self.on_mismatch.is_some())
+            .finish()
+    }
+}
+
+impl SchemaCheckLayer {
+    /// Creates a layer that expects no hashes, and rejects mismatching calls.
+    pub fn new() -> Self {
+        SchemaCheckLayer::default()
+    }
+
+    /// Expects clients of the service `name`, e.g. the `SERVICE_NAME` of the
+    /// server module, to send `hash`, e.g. its `SCHEMA_HASH`.
+    pub fn service(mut self, name: impl Into<String>, hash: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.hashes).insert(name.into(), hash.into());
+        self
+    }
+
+    /// Enable or disable rejecting mismatching calls with
+    /// `FAILED_PRECONDITION`. Otherwise they are only reported to
+    /// [`SchemaCheckLayer::on_mismatch`], e.g. while rolling out a schema
+    /// change.
+    ///
+    /// Defaults to `true`.
+    pub fn reject(mut self, enable: bool) -> Self {
+        self.reject = enable;
+        self
+    }
+
+    /// Calls `f` with every mismatching call, e.g. to log it or count it.
+    pub fn on_mismatch<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SchemaMismatch) + Send + Sync + 'static,
+    {
+        self.on_mismatch = Some(Arc::new(f));
+        self
+    }
+
+    /// Returns the mismatch of the call of `path` with the metadata `headers`,
+    /// if any.
+    fn check(&self, path: &str, headers: &http::HeaderMap) -> Option<SchemaMismatch> {
+        let actual = headers.get(SCHEMA_HASH_KEY)?.to_str().ok()?;
+        let service = path.strip_prefix('/')?.split('/').next()?;
+        let expected = self.hashes.get(service)?;
+        (actual != expected).then(|| SchemaMismatch {
+            path: path.to_owned(),
+            service: service.to_owned(),
+            expected: expected.clone(),
+            actual: actual.to_owned(),
+        })
+    }
+}
+
+impl<S> Layer<S> for SchemaCheckLayer {
+    type Service = SchemaCheck<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SchemaCheck {
+            layer: self.clone(),
+            inner,
+        }
+    }
+}
+
+/// Checks the schema hashes sent to the inner service, see
+/// [`SchemaCheckLayer`].
+#[derive(Debug, Clone)]
+pub struct SchemaCheck<S> {
+    layer: SchemaCheckLayer,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for SchemaCheck<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(mismatch) = self.layer.check(req.uri().path(), req.headers()) {
+            if let Some(on_mismatch) = &self.layer.on_mismatch {
+                on_mismatch(&mismatch);
+            }
+            if self.layer.reject {
+                let response = Status::failed_precondition(mismatch.to_string()).to_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+        Box::pin(self.inner.call(req))
+    }
+}
@@ -0,0 +1,194 @@
+//! Bounded channels for the responses of server-streaming methods.
+//!
+//! Handlers of server-streaming methods usually spawn a task that produces
+//! the messages and return the receiving end of a channel as the response
+//! stream. [`channel`] creates such a pair with an explicit buffer:
+//! [`StreamSender::send`] waits while `buffer` messages are queued, so a
+//! slow client slows the producer down instead of growing the queue.
+//!
+//! ```rust,ignore
+//! type ListLoansStream = ResponseStream<Loan>;
+//!
+//! async fn list_loans(
+//!     &self,
+//!     request: Request<ListLoansRequest>,
+//! ) -> Result<Response<Self::ListLoansStream>, Status> {
+//!     let loans = self.db.loans(&request.get_ref().member);
+//!     Ok(stream::spawn(stream::DEFAULT_BUFFER, |tx| async move {
+//!         for loan in loans {
+//!             if tx.send(loan).await.is_err() {
+//!                 // The client went away.
+//!                 return;
+//!             }
+//!         }
+//!     })
+//!     .into_response())
+//! }
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::sync::mpsc;
+use tonic::{codegen::tokio_stream::Stream, Response, Status};
+
+/// A buffer size suitable for most streams.
+pub const DEFAULT_BUFFER: usize = 16;
+
+/// Creates a bounded channel of responses, which queues at most `buffer`
+/// messages.
+///
+/// # Panics
+///
+/// Panics if `buffer` is 0.
+pub fn channel<T>(buffer: usize) -> (StreamSender<T>, ResponseStream<T>) {
+    let (tx, rx) = mpsc::channel(buffer);
+    (StreamSender { tx }, ResponseStream { rx })
+}
+
+/// Creates a bounded channel of responses with [`channel`], and spawns the
+/// task `f` sending to it on the current tokio runtime.
+///
+/// # Panics
+///
+/// Panics if `buffer` is 0, or if called outside of a tokio runtime.
+pub fn spawn<T, F, Fut>(buffer: usize, f: F) -> ResponseStream<T>
+where
+    F: FnOnce(StreamSender<T>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (tx, rx) = channel(buffer);
+    tokio::spawn(f(tx));
+    rx
+}
+
+/// The error of sending to a stream whose [`ResponseStream`] is dropped,
+/// because the call ended, e.g. as the client cancelled it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("response stream closed")
+    }
+}
+
+impl std::error::Error for Closed {}
+
+/// The sending half of a response stream, see [`channel`].
+pub struct StreamSender<T> {
+    tx: mpsc::Sender<Result<T, Status>>,
+}
+
+impl<T> Clone for StreamSender<T> {
+    fn clone(&self) -> Self {
+        StreamSender {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for StreamSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamSender")
+            .field("capacity", &self.tx.capacity())
+            .field("max_capacity", &self.tx.max_capacity())
+            .finish()
+    }
+}
+
+impl<T> StreamSender<T> {
+    /// Sends `message`, waiting while the buffer is full.
+    pub async fn send(&self, message: T) -> Result<(), Closed> {
+        self.tx.send(Ok(message)).await.map_err(|_| Closed)
+    }
+
+    /// Ends the stream with `status`, after the queued messages. Messages
+    /// sent afterwards are not delivered.
+    pub async fn send_error(&self, status: Status) -> Result<(), Closed> {
+        self.tx.send(Err(status)).await.map_err(|_| Closed)
+    }
+
+    /// Sends `message` if the buffer is not full, and returns it otherwise.
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.tx.try_send(Ok(message)).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(Ok(message)) => TrySendError::Full(message),
+            mpsc::error::TrySendError::Closed(Ok(message)) => TrySendError::Closed(message),
+            _ => unreachable!("sent a message"),
+        })
+    }
+
+    /// Waits until the [`ResponseStream`] is dropped, e.g. to stop producing
+    /// messages once the client cancelled the call.
+    pub async fn closed(&self) {
+        self.tx.closed().await
+    }
+
+    /// Returns whether the [`ResponseStream`] is dropped.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// The error of [`StreamSender::try_send`], holding the message that was not
+/// sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The buffer is full.
+    Full(T),
+    /// The [`ResponseStream`] is dropped.
+    Closed(T),
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("response stream buffer full"),
+            TrySendError::Closed(_) => f.write_str("response stream closed"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for TrySendError<T> {}
+
+/// The receiving half of a response stream, see [`channel`], which serves as
+/// the stream type of server-streaming methods, e.g.
+/// `type ListLoansStream = ResponseStream<Loan>;`.
+pub struct ResponseStream<T> {
+    rx: mpsc::Receiver<Result<T, Status>>,
+}
+
+impl<T> fmt::Debug for ResponseStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream").finish_non_exhaustive()
+    }
+}
+
+impl<T> ResponseStream<T> {
+    /// Wraps the stream in a `tonic::Response`, for returning it from a
+    /// handler.
+    pub fn into_response(self) -> Response<Self> {
+        Response::new(self)
+    }
+
+    /// Boxes the stream, for methods whose stream type is
+    /// `Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>`.
+    pub fn boxed(self) -> Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>
+    where
+        T: Send + 'static,
+    {
+        Box::pin(self)
+    }
+}
+
+impl<T> Stream for ResponseStream<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
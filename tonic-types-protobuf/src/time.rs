@@ -0,0 +1,259 @@
+//! Checked conversions between the well-known `Timestamp` and `Duration`
+//! types and their `std::time` counterparts.
+//!
+//! Unlike the conversions shipped with rust-protobuf, these validate the
+//! ranges defined by `google/protobuf/timestamp.proto` and
+//! `google/protobuf/duration.proto` instead of panicking or wrapping.
+//!
+//! With the `chrono` and `time` features, they also convert from and into
+//! `chrono::DateTime<Utc>` and `chrono::TimeDelta`, and
+//! `time::OffsetDateTime` and `time::Duration`.
+
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use protobuf::well_known_types::{duration::Duration, timestamp::Timestamp};
+
+/// Seconds of `0001-01-01T00:00:00Z`, the minimum valid `Timestamp`.
+pub const TIMESTAMP_MIN_SECONDS: i64 = -62_135_596_800;
+/// Seconds of `9999-12-31T23:59:59Z`, the maximum valid `Timestamp`.
+pub const TIMESTAMP_MAX_SECONDS: i64 = 253_402_300_799;
+/// The maximum absolute seconds of a valid `Duration`, about 10,000 years.
+pub const DURATION_MAX_SECONDS: i64 = 315_576_000_000;
+
+const NANOS_PER_SECOND: i32 = 1_000_000_000;
+
+/// Represents an error in converting a time value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeError {
+    /// The value is outside the range of the target type.
+    OutOfRange,
+    /// The nanoseconds are out of range or their sign does not match the
+    /// seconds.
+    InvalidNanos,
+    /// A negative `Duration` can not be converted to `std::time::Duration`.
+    Negative,
+}
+
+impl std::error::Error for TimeError {}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeError::OutOfRange => f.write_str("time value out of range"),
+            TimeError::InvalidNanos => f.write_str("invalid nanoseconds"),
+            TimeError::Negative => f.write_str("negative duration"),
+        }
+    }
+}
+
+/// Checked conversions of `google.protobuf.Timestamp`.
+pub trait TimestampExt: Sized {
+    /// Converts a `SystemTime`, failing if it is outside the `Timestamp`
+    /// range.
+    fn try_from_system_time(time: SystemTime) -> Result<Self, TimeError>;
+
+    /// Converts to a `SystemTime`, failing if the timestamp is invalid or not
+    /// representable on this platform.
+    fn to_system_time(&self) -> Result<SystemTime, TimeError>;
+
+    /// Converts a `chrono::DateTime<Utc>`, failing if it is outside the
+    /// `Timestamp` range or a leap second.
+    #[cfg(feature = "chrono")]
+    fn try_from_chrono(time: chrono::DateTime<chrono::Utc>) -> Result<Self, TimeError>;
+
+    /// Converts to a `chrono::DateTime<Utc>`, failing if the timestamp is
+    /// invalid.
+    #[cfg(feature = "chrono")]
+    fn to_chrono(&self) -> Result<chrono::DateTime<chrono::Utc>, TimeError>;
+
+    /// Converts a `time::OffsetDateTime`, failing if it is outside the
+    /// `Timestamp` range.
+    #[cfg(feature = "time")]
+    fn try_from_offset_date_time(time: ::time::OffsetDateTime) -> Result<Self, TimeError>;
+
+    /// Converts to a UTC `time::OffsetDateTime`, failing if the timestamp is
+    /// invalid.
+    #[cfg(feature = "time")]
+    fn to_offset_date_time(&self) -> Result<::time::OffsetDateTime, TimeError>;
+}
+
+impl TimestampExt for Timestamp {
+    fn try_from_system_time(time: SystemTime) -> Result<Self, TimeError> {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => (
+                i64::try_from(d.as_secs()).map_err(|_| TimeError::OutOfRange)?,
+                d.subsec_nanos() as i32,
+            ),
+            Err(e) => {
+                // Before the epoch, nanos still count forward from seconds.
+                let d = e.duration();
+                let seconds = i64::try_from(d.as_secs()).map_err(|_| TimeError::OutOfRange)?;
+                match d.subsec_nanos() as i32 {
+                    0 => (-seconds, 0),
+                    nanos => (-seconds - 1, NANOS_PER_SECOND - nanos),
+                }
+            }
+        };
+        new_timestamp(seconds, nanos)
+    }
+
+    fn to_system_time(&self) -> Result<SystemTime, TimeError> {
+        check_timestamp(self)?;
+        let nanos = std::time::Duration::from_nanos(self.nanos as u64);
+        let time = if self.seconds >= 0 {
+            UNIX_EPOCH.checked_add(std::time::Duration::from_secs(self.seconds as u64))
+        } else {
+            UNIX_EPOCH.checked_sub(std::time::Duration::from_secs(self.seconds.unsigned_abs()))
+        };
+        time.and_then(|t| t.checked_add(nanos))
+            .ok_or(TimeError::OutOfRange)
+    }
+
+    #[cfg(feature = "chrono")]
+    fn try_from_chrono(time: chrono::DateTime<chrono::Utc>) -> Result<Self, TimeError> {
+        // chrono represents leap seconds with nanos past a full second.
+        let nanos = i32::try_from(time.timestamp_subsec_nanos())
+            .ok()
+            .filter(|nanos| *nanos < NANOS_PER_SECOND)
+            .ok_or(TimeError::InvalidNanos)?;
+        new_timestamp(time.timestamp(), nanos)
+    }
+
+    #[cfg(feature = "chrono")]
+    fn to_chrono(&self) -> Result<chrono::DateTime<chrono::Utc>, TimeError> {
+        check_timestamp(self)?;
+        chrono::DateTime::from_timestamp(self.seconds, self.nanos as u32)
+            .ok_or(TimeError::OutOfRange)
+    }
+
+    #[cfg(feature = "time")]
+    fn try_from_offset_date_time(time: ::time::OffsetDateTime) -> Result<Self, TimeError> {
+        new_timestamp(time.unix_timestamp(), time.nanosecond() as i32)
+    }
+
+    #[cfg(feature = "time")]
+    fn to_offset_date_time(&self) -> Result<::time::OffsetDateTime, TimeError> {
+        check_timestamp(self)?;
+        let nanos = self.seconds as i128 * NANOS_PER_SECOND as i128 + self.nanos as i128;
+        ::time::OffsetDateTime::from_unix_timestamp_nanos(nanos)
+            .map_err(|_| TimeError::OutOfRange)
+    }
+}
+
+fn new_timestamp(seconds: i64, nanos: i32) -> Result<Timestamp, TimeError> {
+    if !(TIMESTAMP_MIN_SECONDS..=TIMESTAMP_MAX_SECONDS).contains(&seconds) {
+        return Err(TimeError::OutOfRange);
+    }
+    let mut timestamp = Timestamp::new();
+    timestamp.seconds = seconds;
+    timestamp.nanos = nanos;
+    Ok(timestamp)
+}
+
+fn check_timestamp(timestamp: &Timestamp) -> Result<(), TimeError> {
+    if !(0..NANOS_PER_SECOND).contains(&timestamp.nanos) {
+        return Err(TimeError::InvalidNanos);
+    }
+    if !(TIMESTAMP_MIN_SECONDS..=TIMESTAMP_MAX_SECONDS).contains(&timestamp.seconds) {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(())
+}
+
+/// Checked conversions of `google.protobuf.Duration`.
+pub trait DurationExt: Sized {
+    /// Converts a `std::time::Duration`, failing if it is outside the
+    /// `Duration` range.
+    fn try_from_std(duration: std::time::Duration) -> Result<Self, TimeError>;
+
+    /// Converts to a `std::time::Duration`, failing if the duration is
+    /// invalid or negative.
+    fn to_std(&self) -> Result<std::time::Duration, TimeError>;
+
+    /// Converts a `chrono::TimeDelta`, failing if it is outside the
+    /// `Duration` range.
+    #[cfg(feature = "chrono")]
+    fn try_from_chrono(duration: chrono::TimeDelta) -> Result<Self, TimeError>;
+
+    /// Converts to a `chrono::TimeDelta`, failing if the duration is
+    /// invalid.
+    #[cfg(feature = "chrono")]
+    fn to_chrono(&self) -> Result<chrono::TimeDelta, TimeError>;
+
+    /// Converts a `time::Duration`, failing if it is outside the `Duration`
+    /// range.
+    #[cfg(feature = "time")]
+    fn try_from_time(duration: ::time::Duration) -> Result<Self, TimeError>;
+
+    /// Converts to a `time::Duration`, failing if the duration is invalid.
+    #[cfg(feature = "time")]
+    fn to_time(&self) -> Result<::time::Duration, TimeError>;
+}
+
+impl DurationExt for Duration {
+    fn try_from_std(duration: std::time::Duration) -> Result<Self, TimeError> {
+        let seconds = i64::try_from(duration.as_secs()).map_err(|_| TimeError::OutOfRange)?;
+        new_duration(seconds, duration.subsec_nanos() as i32)
+    }
+
+    fn to_std(&self) -> Result<std::time::Duration, TimeError> {
+        check_duration(self)?;
+        if self.seconds < 0 || self.nanos < 0 {
+            return Err(TimeError::Negative);
+        }
+        Ok(std::time::Duration::new(
+            self.seconds as u64,
+            self.nanos as u32,
+        ))
+    }
+    #[cfg(feature = "chrono")]
+    fn try_from_chrono(duration: chrono::TimeDelta) -> Result<Self, TimeError> {
+        new_duration(duration.num_seconds(), duration.subsec_nanos())
+    }
+
+    #[cfg(feature = "chrono")]
+    fn to_chrono(&self) -> Result<chrono::TimeDelta, TimeError> {
+        check_duration(self)?;
+        Ok(chrono::TimeDelta::seconds(self.seconds)
+            + chrono::TimeDelta::nanoseconds(self.nanos as i64))
+    }
+
+    #[cfg(feature = "time")]
+    fn try_from_time(duration: ::time::Duration) -> Result<Self, TimeError> {
+        new_duration(duration.whole_seconds(), duration.subsec_nanoseconds())
+    }
+
+    #[cfg(feature = "time")]
+    fn to_time(&self) -> Result<::time::Duration, TimeError> {
+        check_duration(self)?;
+        Ok(::time::Duration::new(self.seconds, self.nanos))
+    }
+}
+
+/// Builds a `Duration` from seconds and nanos of the same sign.
+fn new_duration(seconds: i64, nanos: i32) -> Result<Duration, TimeError> {
+    if seconds.unsigned_abs() > DURATION_MAX_SECONDS as u64 {
+        return Err(TimeError::OutOfRange);
+    }
+    let mut d = Duration::new();
+    d.seconds = seconds;
+    d.nanos = nanos;
+    Ok(d)
+}
+
+fn check_duration(duration: &Duration) -> Result<(), TimeError> {
+    if duration.nanos <= -NANOS_PER_SECOND
+        || duration.nanos >= NANOS_PER_SECOND
+        || (duration.seconds > 0 && duration.nanos < 0)
+        || (duration.seconds < 0 && duration.nanos > 0)
+    {
+        return Err(TimeError::InvalidNanos);
+    }
+    if duration.seconds.unsigned_abs() > DURATION_MAX_SECONDS as u64 {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(())
+}
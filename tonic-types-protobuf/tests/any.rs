@@ -0,0 +1,54 @@
+use protobuf::{well_known_types::any::Any, MessageFull};
+use tonic_types_protobuf::{
+    any::{pack_with_prefix, type_name, TypeRegistry, UnpackError},
+    pb::{
+        error_details::{self, bad_request::FieldViolation, ErrorInfo},
+        status::Status,
+    },
+};
+
+#[test]
+fn test_pack_with_prefix() {
+    let mut info = ErrorInfo::new();
+    info.reason = "STOCKOUT".to_owned();
+
+    let any = pack_with_prefix(&info, "example.com/types/").unwrap();
+    assert_eq!(any.type_url, "example.com/types/google.rpc.ErrorInfo");
+    assert_eq!(type_name(&any.type_url), "google.rpc.ErrorInfo");
+    // The prefix does not matter when unpacking.
+    assert_eq!(any.unpack::<ErrorInfo>().unwrap(), Some(info));
+}
+
+#[test]
+fn test_type_registry() {
+    let mut registry = TypeRegistry::new();
+    registry
+        .register::<Status>()
+        .register_file(error_details::file_descriptor());
+    assert!(registry
+        .find("google.rpc.BadRequest.FieldViolation")
+        .is_some());
+    assert!(registry
+        .find("type.googleapis.com/google.rpc.Status")
+        .is_some());
+
+    let mut violation = FieldViolation::new();
+    violation.field = "name".to_owned();
+    let any = Any::pack(&violation).unwrap();
+    let message = registry.unpack(&any).unwrap();
+    assert_eq!(
+        message.descriptor_dyn().full_name(),
+        FieldViolation::descriptor().full_name()
+    );
+    assert_eq!(
+        message.downcast_box::<FieldViolation>().unwrap().as_ref(),
+        &violation
+    );
+
+    let mut any = Any::new();
+    any.type_url = "type.googleapis.com/foo.Bar".to_owned();
+    assert!(matches!(
+        registry.unpack(&any),
+        Err(UnpackError::UnknownType(_))
+    ));
+}
@@ -0,0 +1,95 @@
+use protobuf::{
+    well_known_types::{duration::Duration, field_mask::FieldMask},
+    MessageField, MessageFull,
+};
+use tonic_types_protobuf::{
+    field_mask::{merge, trim, validate, FieldMaskError},
+    pb::error_details::{ErrorInfo, RetryInfo},
+};
+
+fn mask(paths: &[&str]) -> FieldMask {
+    let mut mask = FieldMask::new();
+    mask.paths = paths.iter().map(|p| p.to_string()).collect();
+    mask
+}
+
+fn error_info() -> ErrorInfo {
+    let mut info = ErrorInfo::new();
+    info.reason = "STOCKOUT".to_owned();
+    info.domain = "example.com".to_owned();
+    info.metadata.insert("zone".to_owned(), "a".to_owned());
+    info
+}
+
+fn retry_info(seconds: i64, nanos: i32) -> RetryInfo {
+    let mut delay = Duration::new();
+    delay.seconds = seconds;
+    delay.nanos = nanos;
+    let mut info = RetryInfo::new();
+    info.retry_delay = MessageField::some(delay);
+    info
+}
+
+#[test]
+fn test_validate() {
+    let descriptor = RetryInfo::descriptor();
+    validate(&mask(&["retry_delay", "retry_delay.nanos"]), &descriptor).unwrap();
+    assert_eq!(
+        validate(&mask(&["retry_delay.minutes"]), &descriptor),
+        Err(FieldMaskError::InvalidPath(
+            "retry_delay.minutes".to_owned()
+        ))
+    );
+    assert!(validate(&mask(&["reason.len"]), &ErrorInfo::descriptor()).is_err());
+    assert!(validate(&mask(&["metadata.zone"]), &ErrorInfo::descriptor()).is_err());
+}
+
+#[test]
+fn test_trim() {
+    let mut info = error_info();
+    trim(&mask(&["reason", "metadata"]), &mut info).unwrap();
+    assert_eq!(info.reason, "STOCKOUT");
+    assert_eq!(info.domain, "");
+    assert_eq!(info.metadata.len(), 1);
+
+    let mut info = retry_info(3, 5);
+    trim(&mask(&["retry_delay.seconds"]), &mut info).unwrap();
+    assert_eq!(info, retry_info(3, 0));
+
+    let mut info = retry_info(3, 5);
+    trim(&mask(&["retry_delay.seconds", "retry_delay"]), &mut info).unwrap();
+    assert_eq!(info, retry_info(3, 5));
+}
+
+#[test]
+fn test_merge() {
+    let mut source = error_info();
+    source.reason = "QUOTA".to_owned();
+    source.metadata.clear();
+    source.metadata.insert("region".to_owned(), "us".to_owned());
+
+    let mut target = error_info();
+    merge(&mask(&["reason", "metadata"]), &source, &mut target).unwrap();
+    assert_eq!(target.reason, "QUOTA");
+    assert_eq!(target.domain, "example.com");
+    assert_eq!(target.metadata, source.metadata);
+
+    let mut target = retry_info(3, 5);
+    merge(
+        &mask(&["retry_delay.nanos"]),
+        &retry_info(7, 9),
+        &mut target,
+    )
+    .unwrap();
+    assert_eq!(target, retry_info(3, 9));
+
+    // Unset source fields clear the target.
+    let mut target = retry_info(3, 5);
+    merge(&mask(&["retry_delay"]), &RetryInfo::new(), &mut target).unwrap();
+    assert_eq!(target, RetryInfo::new());
+
+    assert!(matches!(
+        merge(&mask(&["reason"]), &source, &mut RetryInfo::new()),
+        Err(FieldMaskError::TypeMismatch { .. })
+    ));
+}
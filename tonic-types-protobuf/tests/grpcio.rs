@@ -0,0 +1,132 @@
+#![cfg(feature = "grpcio-compat")]
+
+use std::time::Duration;
+
+use futures::{future, stream, SinkExt, StreamExt, TryStreamExt};
+use tokio::sync::oneshot;
+use tonic::{Code, Response, Status};
+use tonic_types_protobuf::{
+    grpcio::{self, RpcStatus, RpcStatusCode, SinkRequests, WriteFlags},
+    ErrorDetails, StatusExt,
+};
+
+#[tokio::test]
+async fn test_duplex_streaming() {
+    let (mut tx, mut rx) = grpcio::duplex_streaming(1, |requests: SinkRequests<i32>| async move {
+        Ok(Response::new(requests.map(|n| n * 2).map(Ok)))
+    });
+    for n in 1..=3 {
+        tx.send((n, WriteFlags::default().buffer_hint(true)))
+            .await
+            .unwrap();
+        assert_eq!(rx.try_next().await.unwrap(), Some(n * 2));
+    }
+    tx.close().await.unwrap();
+    assert_eq!(rx.try_next().await.unwrap(), None);
+
+    let (_tx, mut rx) = grpcio::duplex_streaming(1, |_: SinkRequests<i32>| async move {
+        Err::<Response<stream::Empty<Result<i32, Status>>>, _>(Status::unavailable("down"))
+    });
+    assert_eq!(
+        rx.next().await.unwrap().unwrap_err().code(),
+        Code::Unavailable
+    );
+    assert!(rx.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_dropped_receiver() {
+    let (dropped_tx, dropped_rx) = oneshot::channel::<()>();
+    let (mut tx, rx) = grpcio::duplex_streaming(1, |_: SinkRequests<i32>| async move {
+        let _dropped = dropped_tx;
+        future::pending::<Result<Response<stream::Empty<Result<i32, Status>>>, Status>>().await
+    });
+    drop(rx);
+    // The call is cancelled, which drops its request stream.
+    assert!(dropped_rx.await.is_err());
+    let status = tx.send((1, WriteFlags::default())).await.unwrap_err();
+    assert_eq!(status.code(), Code::Cancelled);
+}
+
+#[tokio::test]
+async fn test_client_streaming() {
+    let (mut tx, rx) = grpcio::client_streaming(1, |requests: SinkRequests<i32>| async move {
+        Ok(Response::new(
+            requests.fold(0, |sum, n| async move { sum + n }).await,
+        ))
+    });
+    tx.send_all(&mut stream::iter([1, 2, 3].map(|n| (n, WriteFlags::default()))).map(Ok))
+        .await
+        .unwrap();
+    tx.close().await.unwrap();
+    assert_eq!(rx.await.unwrap(), 6);
+}
+
+#[tokio::test]
+async fn test_server_streaming() {
+    let rx = grpcio::server_streaming(async {
+        Ok(Response::new(stream::iter([
+            Ok(1),
+            Err(Status::aborted("done")),
+        ])))
+    });
+    let responses: Vec<_> = rx.collect().await;
+    assert_eq!(*responses[0].as_ref().unwrap(), 1);
+    assert_eq!(responses[1].as_ref().unwrap_err().code(), Code::Aborted);
+    assert_eq!(responses.len(), 2);
+}
+
+#[tokio::test]
+async fn test_response_sink() {
+    let (mut sink, responses) = grpcio::response_sink(4);
+    sink.send((1, WriteFlags::default())).await.unwrap();
+    sink.send((2, WriteFlags::default())).await.unwrap();
+    sink.close().await.unwrap();
+    assert_eq!(responses.try_collect::<Vec<_>>().await.unwrap(), [1, 2]);
+
+    let (mut sink, mut responses) = grpcio::response_sink(4);
+    sink.send((1, WriteFlags::default())).await.unwrap();
+    sink.fail(Status::not_found("gone")).await.unwrap();
+    assert_eq!(responses.next().await.unwrap().unwrap(), 1);
+    assert_eq!(
+        responses.next().await.unwrap().unwrap_err().code(),
+        Code::NotFound
+    );
+    assert!(responses.next().await.is_none());
+
+    // The client went away.
+    let (mut sink, responses) = grpcio::response_sink::<i32>(4);
+    drop(responses);
+    let status = sink.send((1, WriteFlags::default())).await.unwrap_err();
+    assert_eq!(status.code(), Code::Cancelled);
+}
+
+#[test]
+fn test_rpc_status() {
+    let status = Status::with_error_details(
+        Code::Unavailable,
+        "leader changed",
+        ErrorDetails::with_retry_info(Some(Duration::from_millis(500))),
+    );
+    let rpc_status = RpcStatus::from(status.clone());
+    assert_eq!(rpc_status.code(), RpcStatusCode::UNAVAILABLE);
+    assert_eq!(rpc_status.message(), "leader changed");
+    assert_eq!(rpc_status.details(), status.details());
+
+    let status = Status::from(rpc_status);
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(status.message(), "leader changed");
+    assert_eq!(
+        status.get_error_details().retry_delay(),
+        Some(Duration::from_millis(500))
+    );
+
+    // Codes unknown to tonic are kept by `RpcStatus`.
+    let rpc_status = RpcStatus::with_message(42, "new code".to_owned());
+    assert_eq!(i32::from(rpc_status.code()), 42);
+    assert_eq!(Status::from(rpc_status).code(), Code::Unknown);
+    assert_eq!(
+        RpcStatus::new(Code::NotFound).code(),
+        RpcStatusCode::NOT_FOUND
+    );
+}
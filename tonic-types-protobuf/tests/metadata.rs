@@ -0,0 +1,51 @@
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic_types_protobuf::{
+    metadata::{MetadataError, MetadataMapExt},
+    pb::error_details::RequestInfo,
+};
+
+fn request_info(id: &str) -> RequestInfo {
+    let mut info = RequestInfo::new();
+    info.request_id = id.to_owned();
+    info
+}
+
+#[test]
+fn test_messages_in_metadata() {
+    let mut map = MetadataMap::new();
+    assert_eq!(map.get_message::<RequestInfo>("ctx-bin").unwrap(), None);
+
+    map.insert_message("ctx-bin", &request_info("a")).unwrap();
+    map.append_message("ctx-bin", &request_info("b")).unwrap();
+    assert_eq!(
+        map.get_message::<RequestInfo>("ctx-bin").unwrap(),
+        Some(request_info("a"))
+    );
+    assert_eq!(
+        map.get_all_messages::<RequestInfo>("ctx-bin").unwrap(),
+        [request_info("a"), request_info("b")]
+    );
+
+    // Survives the base64 encoding used on the wire.
+    let headers = map.clone().into_headers();
+    let map = MetadataMap::from_headers(headers);
+    assert_eq!(
+        map.get_message::<RequestInfo>("ctx-bin").unwrap(),
+        Some(request_info("a"))
+    );
+}
+
+#[test]
+fn test_errors() {
+    let mut map = MetadataMap::new();
+    assert!(matches!(
+        map.insert_message("ctx", &request_info("a")),
+        Err(MetadataError::InvalidKey(_))
+    ));
+
+    map.insert_bin("bad-bin", MetadataValue::from_bytes(b"\xff\xff\xff"));
+    assert!(matches!(
+        map.get_message::<RequestInfo>("bad-bin"),
+        Err(MetadataError::Decode(..))
+    ));
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "prost")]
+
+use std::collections::BTreeMap;
+
+use protobuf::well_known_types::{
+    any::Any,
+    duration::Duration,
+    struct_::{value::Kind, NullValue, Struct},
+    timestamp::Timestamp,
+};
+use tonic_types_protobuf::prost::ProstConvert;
+
+#[test]
+fn test_time() {
+    let timestamp = prost_types::Timestamp {
+        seconds: 1_700_000_000,
+        nanos: 5,
+    };
+    let converted = Timestamp::from_prost(timestamp.clone());
+    assert_eq!((converted.seconds, converted.nanos), (1_700_000_000, 5));
+    assert_eq!(converted.into_prost(), timestamp);
+
+    let duration = prost_types::Duration {
+        seconds: -3,
+        nanos: -500,
+    };
+    assert_eq!(
+        Duration::from_prost(duration.clone()).into_prost(),
+        duration
+    );
+}
+
+#[test]
+fn test_any() {
+    let any = prost_types::Any {
+        type_url: "type.googleapis.com/google.protobuf.Duration".to_owned(),
+        value: vec![8, 3],
+    };
+    let converted = Any::from_prost(any.clone());
+    assert_eq!(converted.type_url, any.type_url);
+    assert_eq!(converted.value, any.value);
+    assert_eq!(converted.into_prost(), any);
+}
+
+#[test]
+fn test_struct() {
+    use prost_types::value::Kind as ProstKind;
+
+    let value = |kind| prost_types::Value { kind: Some(kind) };
+    let nested = prost_types::Struct {
+        fields: BTreeMap::from([("on".to_owned(), value(ProstKind::BoolValue(true)))]),
+    };
+    let s = prost_types::Struct {
+        fields: BTreeMap::from([
+            ("null".to_owned(), value(ProstKind::NullValue(0))),
+            ("number".to_owned(), value(ProstKind::NumberValue(1.5))),
+            (
+                "list".to_owned(),
+                value(ProstKind::ListValue(prost_types::ListValue {
+                    values: vec![value(ProstKind::StringValue("a".to_owned()))],
+                })),
+            ),
+            ("nested".to_owned(), value(ProstKind::StructValue(nested))),
+            ("unset".to_owned(), prost_types::Value { kind: None }),
+        ]),
+    };
+
+    let converted = Struct::from_prost(s.clone());
+    assert_eq!(converted.fields.len(), 5);
+    assert_eq!(
+        converted.fields["null"].kind,
+        Some(Kind::NullValue(NullValue::NULL_VALUE.into()))
+    );
+    assert_eq!(
+        converted.fields["number"].kind,
+        Some(Kind::NumberValue(1.5))
+    );
+    let Some(Kind::StructValue(nested)) = &converted.fields["nested"].kind else {
+        panic!("not a struct: {:?}", converted.fields["nested"]);
+    };
+    assert_eq!(nested.fields["on"].kind, Some(Kind::BoolValue(true)));
+    assert_eq!(converted.fields["unset"].kind, None);
+    assert_eq!(converted.into_prost(), s);
+}
@@ -0,0 +1,92 @@
+use protobuf::{reflect::FileDescriptor, text_format};
+use tonic_types_protobuf::{
+    pb::options,
+    redact::{is_sensitive, redact, redacted_copy},
+};
+
+const PROTO: &str = r#"
+syntax = "proto3";
+package demo;
+
+import "tonic_protobuf/options.proto";
+
+message Login {
+    string user = 1;
+    string password = 2 [(tonic_protobuf.sensitive) = true];
+    repeated bytes keys = 3 [(tonic_protobuf.sensitive) = true];
+    int64 pin = 4 [(tonic_protobuf.sensitive) = true];
+    Login delegate = 5;
+    repeated Login others = 6;
+    map<string, Login> by_name = 7;
+    map<string, string> secrets = 8 [(tonic_protobuf.sensitive) = true];
+    Login sudo = 9 [(tonic_protobuf.sensitive) = true];
+}
+"#;
+
+fn login() -> FileDescriptor {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("login.proto"), PROTO).unwrap();
+    let fds = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir.path())
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto"))
+        .input(dir.path().join("login.proto"))
+        .file_descriptor_set()
+        .unwrap();
+    let options = options::file_descriptor().clone();
+    FileDescriptor::new_dynamic(fds.file[0].clone(), &[options]).unwrap()
+}
+
+#[test]
+fn test_is_sensitive() {
+    let login = login().message_by_package_relative_name("Login").unwrap();
+    let sensitive: Vec<_> = login
+        .fields()
+        .filter(is_sensitive)
+        .map(|f| f.name().to_owned())
+        .collect();
+    assert_eq!(sensitive, ["password", "keys", "pin", "secrets", "sudo"]);
+}
+
+#[test]
+fn test_redact() {
+    let login = login().message_by_package_relative_name("Login").unwrap();
+    let mut message = login.new_instance();
+    text_format::merge_from_str(
+        &mut *message,
+        r#"
+        user: "alice"
+        password: "hunter2"
+        keys: "a"
+        keys: "b"
+        pin: 1234
+        delegate { user: "bob" password: "123456" }
+        others { password: "qwerty" }
+        others { user: "carol" }
+        by_name { key: "dave" value { password: "letmein" } }
+        secrets { key: "k" value: "v" }
+        sudo { user: "root" }
+        "#,
+    )
+    .unwrap();
+
+    let copy = redacted_copy(&*message);
+    redact(&mut *message);
+    assert!(copy.reflect_eq_dyn(&*message, &Default::default()));
+    assert_eq!(
+        text_format::print_to_string(&*message),
+        "user: \"alice\" \
+         password: \"[REDACTED]\" \
+         keys: \"[REDACTED]\" \
+         keys: \"[REDACTED]\" \
+         delegate {user: \"bob\" password: \"[REDACTED]\"} \
+         others {password: \"[REDACTED]\"} \
+         others {user: \"carol\"} \
+         by_name {key: \"dave\" value {password: \"[REDACTED]\"}}"
+    );
+
+    // Unset fields stay unset.
+    let mut empty = login.new_instance();
+    redact(&mut *empty);
+    assert_eq!(text_format::print_to_string(&*empty), "");
+}
@@ -0,0 +1,93 @@
+use std::{fmt, time::Duration};
+
+use protobuf::well_known_types::{any::Any, wrappers::StringValue};
+use tonic::{Code, Status};
+use tonic_types_protobuf::{ErrorDetails, ResultExt, RpcError, StatusExt};
+
+#[derive(Debug)]
+enum StoreError {
+    NotFound(String),
+    Busy,
+    InvalidKey(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NotFound(key) => write!(f, "key {} not found", key),
+            StoreError::Busy => f.write_str("store is busy"),
+            StoreError::InvalidKey(key) => write!(f, "invalid key {:?}", key),
+        }
+    }
+}
+
+impl RpcError for StoreError {
+    fn code(&self) -> Code {
+        match self {
+            StoreError::NotFound(_) => Code::NotFound,
+            StoreError::Busy => Code::Unavailable,
+            StoreError::InvalidKey(_) => Code::InvalidArgument,
+        }
+    }
+
+    fn details(&self) -> ErrorDetails {
+        match self {
+            StoreError::Busy => ErrorDetails::with_retry_info(Some(Duration::from_secs(1))),
+            StoreError::InvalidKey(_) => {
+                ErrorDetails::with_bad_request_violation("key", "must not be empty")
+            }
+            StoreError::NotFound(_) => ErrorDetails::new(),
+        }
+    }
+
+    fn details_any(&self) -> Vec<Any> {
+        match self {
+            StoreError::InvalidKey(key) => {
+                let mut value = StringValue::new();
+                value.value = key.clone();
+                vec![Any::pack(&value).unwrap()]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+tonic_types_protobuf::impl_status_from!(StoreError);
+
+fn get(key: &str) -> Result<String, StoreError> {
+    match key {
+        "" => Err(StoreError::InvalidKey(key.to_owned())),
+        "busy" => Err(StoreError::Busy),
+        "a" => Ok("1".to_owned()),
+        _ => Err(StoreError::NotFound(key.to_owned())),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn handler(key: &str) -> Result<String, Status> {
+    Ok(get(key)?)
+}
+
+#[test]
+fn test_rpc_error() {
+    assert_eq!(handler("a").unwrap(), "1");
+
+    let status = handler("b").unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(status.message(), "key b not found");
+    assert!(status.details().is_empty());
+
+    let status = handler("busy").unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(
+        status.get_error_details().retry_delay(),
+        Some(Duration::from_secs(1))
+    );
+
+    let status = get("").into_status().unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let details = status.get_error_details();
+    assert_eq!(details.bad_request().unwrap().field_violations.len(), 1);
+    assert_eq!(status.get_detail::<StringValue>().unwrap().value, "");
+    assert_eq!(status.details_any().len(), 2);
+}
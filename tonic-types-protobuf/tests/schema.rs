@@ -0,0 +1,105 @@
+use std::{
+    convert::Infallible,
+    future::{ready, Ready},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Service},
+    service::Interceptor,
+    Code, Request, Status,
+};
+use tonic_types_protobuf::schema::{
+    SchemaCheckLayer, SchemaHashInterceptor, SchemaMismatch, SCHEMA_HASH_KEY,
+};
+use tower_layer::Layer;
+
+/// Answers every call with OK.
+#[derive(Clone)]
+struct Ok200;
+
+impl Service<http::Request<()>> for Ok200 {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: http::Request<()>) -> Self::Future {
+        ready(Ok(http::Response::new(tonic::body::empty_body())))
+    }
+}
+
+fn request(path: &str, hash: Option<&str>) -> http::Request<()> {
+    let mut request = http::Request::builder().uri(path);
+    if let Some(hash) = hash {
+        request = request.header(SCHEMA_HASH_KEY, hash);
+    }
+    request.body(()).unwrap()
+}
+
+/// Returns the gRPC status of `response`, if it is an error.
+fn status(response: &http::Response<BoxBody>) -> Option<Status> {
+    Status::from_header_map(response.headers())
+}
+
+#[test]
+fn test_interceptor() {
+    let mut interceptor = SchemaHashInterceptor::new("0123456789abcdef");
+    let request = interceptor.call(Request::new(())).unwrap();
+    assert_eq!(
+        request.metadata().get(SCHEMA_HASH_KEY).unwrap(),
+        "0123456789abcdef"
+    );
+}
+
+#[tokio::test]
+async fn test_check() {
+    let mismatches = Arc::new(Mutex::new(vec![]));
+    let layer = SchemaCheckLayer::new()
+        .service("storage.Storage", "0123456789abcdef")
+        .on_mismatch({
+            let mismatches = Arc::clone(&mismatches);
+            move |mismatch| mismatches.lock().unwrap().push(mismatch.clone())
+        });
+    let mut service = layer.layer(Ok200);
+
+    // Matching, unsent and unchecked hashes.
+    for req in [
+        request("/storage.Storage/Get", Some("0123456789abcdef")),
+        request("/storage.Storage/Get", None),
+        request("/other.Other/Get", Some("ffffffffffffffff")),
+    ] {
+        let response = service.call(req).await.unwrap();
+        assert!(status(&response).is_none());
+    }
+    assert!(mismatches.lock().unwrap().is_empty());
+
+    let response = service
+        .call(request("/storage.Storage/Get", Some("ffffffffffffffff")))
+        .await
+        .unwrap();
+    assert_eq!(status(&response).unwrap().code(), Code::FailedPrecondition);
+    assert_eq!(
+        *mismatches.lock().unwrap(),
+        [SchemaMismatch {
+            path: "/storage.Storage/Get".to_owned(),
+            service: "storage.Storage".to_owned(),
+            expected: "0123456789abcdef".to_owned(),
+            actual: "ffffffffffffffff".to_owned(),
+        }]
+    );
+
+    // Only report mismatches.
+    let mut service = layer.reject(false).layer(Ok200);
+    let response = service
+        .call(request("/storage.Storage/Get", Some("ffffffffffffffff")))
+        .await
+        .unwrap();
+    assert!(status(&response).is_none());
+    assert_eq!(mismatches.lock().unwrap().len(), 2);
+}
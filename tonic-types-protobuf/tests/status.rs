@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use protobuf::well_known_types::any::Any;
+use tonic::{Code, Status};
+use tonic_types_protobuf::{
+    pb::error_details::{ErrorInfo, RetryInfo},
+    ErrorDetails, StatusExt,
+};
+
+#[test]
+fn test_details_round_trip() {
+    let mut info = ErrorInfo::new();
+    info.reason = "QUOTA_EXCEEDED".to_owned();
+    info.domain = "example.com".to_owned();
+    info.metadata.insert("region".to_owned(), "us".to_owned());
+
+    let status = Status::with_details_any(
+        Code::ResourceExhausted,
+        "quota exceeded",
+        vec![Any::pack(&info).unwrap()],
+    );
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_eq!(status.message(), "quota exceeded");
+
+    // Survive a round trip through the HTTP trailers.
+    let response = status.to_http();
+    let header_map = response.headers();
+    assert!(header_map.contains_key("grpc-status-details-bin"));
+    let status = Status::from_header_map(header_map).unwrap();
+
+    let rpc_status = status.check_rpc_status().unwrap().unwrap();
+    assert_eq!(rpc_status.code, Code::ResourceExhausted as i32);
+    assert_eq!(rpc_status.message, "quota exceeded");
+    assert_eq!(status.details_any().len(), 1);
+    assert_eq!(status.get_detail::<ErrorInfo>(), Some(info));
+    assert_eq!(status.get_detail::<RetryInfo>(), None);
+}
+
+#[test]
+fn test_details_any_round_trip() {
+    let mut first = ErrorInfo::new();
+    first.reason = "FIRST".to_owned();
+    let mut second = ErrorInfo::new();
+    second.reason = "SECOND".to_owned();
+    let mut retry = RetryInfo::new();
+    retry.retry_delay.mut_or_insert_default().seconds = 3;
+    // Details of types unknown to the receiver are kept as they are.
+    let mut custom = Any::new();
+    custom.type_url = "type.googleapis.com/example.Custom".to_owned();
+    custom.value = b"\x08\x01".to_vec();
+    let details = vec![
+        Any::pack(&first).unwrap(),
+        custom,
+        Any::pack(&retry).unwrap(),
+        Any::pack(&second).unwrap(),
+    ];
+
+    let status = Status::with_details_any(Code::Unavailable, "try again", details.clone());
+    let status = Status::from_header_map(status.to_http().headers()).unwrap();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(status.message(), "try again");
+
+    let rpc_status = status.check_rpc_status().unwrap().unwrap();
+    assert_eq!(rpc_status.code, Code::Unavailable as i32);
+    assert_eq!(rpc_status.message, "try again");
+    assert_eq!(rpc_status.details, details);
+    assert_eq!(status.details_any(), details);
+    // The first detail of each type is returned.
+    assert_eq!(status.get_detail::<ErrorInfo>(), Some(first));
+    assert_eq!(status.get_detail::<RetryInfo>(), Some(retry));
+
+    // Without details, the code and message are still carried.
+    let status = Status::with_details_any(Code::NotFound, "missing", vec![]);
+    let rpc_status = status.check_rpc_status().unwrap().unwrap();
+    assert_eq!(rpc_status.code, Code::NotFound as i32);
+    assert_eq!(rpc_status.message, "missing");
+    assert!(status.details_any().is_empty());
+    assert_eq!(status.get_detail::<ErrorInfo>(), None);
+
+    // Details that can not be decoded are skipped.
+    let mut broken = Any::pack(&ErrorInfo::new()).unwrap();
+    broken.value = b"\xff".to_vec();
+    let status = Status::with_details_any(Code::Internal, "boom", vec![broken]);
+    assert_eq!(status.details_any().len(), 1);
+    assert_eq!(status.get_detail::<ErrorInfo>(), None);
+}
+
+#[test]
+fn test_no_details() {
+    let status = Status::internal("boom");
+    assert_eq!(status.check_rpc_status().unwrap(), None);
+    assert!(status.details_any().is_empty());
+
+    let status = Status::with_details(Code::Internal, "boom", b"\xff\xff".to_vec().into());
+    assert!(status.check_rpc_status().is_err());
+    assert!(status.details_any().is_empty());
+}
+
+#[test]
+fn test_error_details() {
+    let mut details = ErrorDetails::with_bad_request_violation("key", "must not be empty");
+    details
+        .add_bad_request_violation("value", "too long")
+        .set_retry_info(Some(Duration::from_millis(1500)))
+        .set_localized_message("en-US", "Invalid request");
+
+    let status = Status::with_error_details(Code::InvalidArgument, "invalid request", details);
+    assert_eq!(status.details_any().len(), 3);
+
+    let details = status.check_error_details().unwrap();
+    let violations = &details.bad_request().unwrap().field_violations;
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[1].field, "value");
+    assert_eq!(details.retry_delay(), Some(Duration::from_millis(1500)));
+    assert_eq!(
+        details.localized_message().unwrap().message,
+        "Invalid request"
+    );
+    assert!(details.error_info().is_none());
+    assert!(details.quota_failure().is_none());
+
+    assert!(Status::internal("boom").get_error_details().is_empty());
+}
+
+#[test]
+fn test_error_details_round_trip() {
+    let mut details = ErrorDetails::with_error_info(
+        "EXPIRED",
+        "example.com",
+        [("region".to_owned(), "us".to_owned())].into(),
+    );
+    details
+        .set_retry_info(Some(Duration::from_secs(2)))
+        .add_quota_failure_violation("project:1", "daily limit")
+        .add_precondition_failure_violation("TOS", "user:1", "not accepted");
+
+    let status = Status::with_error_details(Code::FailedPrecondition, "expired", details.clone());
+    let status = Status::from_header_map(status.to_http().headers()).unwrap();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert_eq!(status.message(), "expired");
+    assert_eq!(status.check_error_details().unwrap(), details);
+    assert_eq!(status.get_error_details(), details);
+    assert_eq!(status.get_detail::<ErrorInfo>().unwrap().reason, "EXPIRED");
+
+    // Details packed by hand are read back as well.
+    let retry_info = details.retry_info().unwrap();
+    let status = Status::with_details_any(
+        Code::Unavailable,
+        "later",
+        vec![Any::pack(retry_info).unwrap()],
+    );
+    assert_eq!(
+        status.get_error_details(),
+        ErrorDetails::with_retry_info(Some(Duration::from_secs(2)))
+    );
+
+    // Details that can not be decoded are an error, or empty details.
+    let mut broken = Any::pack(retry_info).unwrap();
+    broken.value = b"\xff".to_vec();
+    let status = Status::with_details_any(Code::Unavailable, "later", vec![broken]);
+    assert!(status.check_error_details().is_err());
+    assert!(status.get_error_details().is_empty());
+}
+
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use tonic::{codegen::tokio_stream::StreamExt, Code, Status};
+use tonic_types_protobuf::stream::{self, Closed, TrySendError};
+
+#[tokio::test]
+async fn test_channel() {
+    let (tx, mut rx) = stream::channel(2);
+    tx.send(1).await.unwrap();
+    tx.send(2).await.unwrap();
+    assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+    // The sender waits until the buffer has room.
+    let send = tokio::spawn({
+        let tx = tx.clone();
+        async move { tx.send(3).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!send.is_finished());
+    assert_eq!(rx.next().await.unwrap().unwrap(), 1);
+    send.await.unwrap().unwrap();
+    assert_eq!(rx.next().await.unwrap().unwrap(), 2);
+    tx.send_error(Status::aborted("done")).await.unwrap();
+    drop(tx);
+
+    assert_eq!(rx.next().await.unwrap().unwrap(), 3);
+    assert_eq!(rx.next().await.unwrap().unwrap_err().code(), Code::Aborted);
+    assert!(rx.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_closed() {
+    let (tx, rx) = stream::channel(1);
+    assert!(!tx.is_closed());
+    drop(rx);
+    tx.closed().await;
+    assert!(tx.is_closed());
+    assert_eq!(tx.send("message").await, Err(Closed));
+    assert_eq!(tx.try_send("message"), Err(TrySendError::Closed("message")));
+}
+
+#[tokio::test]
+async fn test_spawn() {
+    let rx = stream::spawn(stream::DEFAULT_BUFFER, |tx| async move {
+        for i in 0..100 {
+            tx.send(i).await.unwrap();
+        }
+    });
+    let messages: Vec<_> = rx.boxed().map(Result::unwrap).collect().await;
+    assert_eq!(messages, (0..100).collect::<Vec<_>>());
+}
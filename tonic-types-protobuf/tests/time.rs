@@ -0,0 +1,134 @@
+use std::time::{Duration as StdDuration, UNIX_EPOCH};
+
+use protobuf::well_known_types::{duration::Duration, timestamp::Timestamp};
+use tonic_types_protobuf::time::{
+    DurationExt, TimeError, TimestampExt, TIMESTAMP_MAX_SECONDS, TIMESTAMP_MIN_SECONDS,
+};
+
+fn timestamp(seconds: i64, nanos: i32) -> Timestamp {
+    let mut ts = Timestamp::new();
+    ts.seconds = seconds;
+    ts.nanos = nanos;
+    ts
+}
+
+fn duration(seconds: i64, nanos: i32) -> Duration {
+    let mut d = Duration::new();
+    d.seconds = seconds;
+    d.nanos = nanos;
+    d
+}
+
+#[test]
+fn test_timestamp() {
+    let time = UNIX_EPOCH + StdDuration::new(1_700_000_000, 5);
+    let ts = Timestamp::try_from_system_time(time).unwrap();
+    assert_eq!(ts, timestamp(1_700_000_000, 5));
+    assert_eq!(ts.to_system_time().unwrap(), time);
+
+    // Before the epoch.
+    let time = UNIX_EPOCH - StdDuration::new(1, 250_000_000);
+    let ts = Timestamp::try_from_system_time(time).unwrap();
+    assert_eq!(ts, timestamp(-2, 750_000_000));
+    assert_eq!(ts.to_system_time().unwrap(), time);
+
+    assert_eq!(
+        timestamp(TIMESTAMP_MAX_SECONDS + 1, 0).to_system_time(),
+        Err(TimeError::OutOfRange)
+    );
+    assert_eq!(
+        timestamp(TIMESTAMP_MIN_SECONDS - 1, 0).to_system_time(),
+        Err(TimeError::OutOfRange)
+    );
+    assert_eq!(
+        timestamp(0, -1).to_system_time(),
+        Err(TimeError::InvalidNanos)
+    );
+    assert_eq!(
+        Timestamp::try_from_system_time(
+            UNIX_EPOCH + StdDuration::from_secs(TIMESTAMP_MAX_SECONDS as u64 + 1)
+        ),
+        Err(TimeError::OutOfRange)
+    );
+}
+
+#[test]
+fn test_duration() {
+    let d = Duration::try_from_std(StdDuration::from_millis(1500)).unwrap();
+    assert_eq!(d, duration(1, 500_000_000));
+    assert_eq!(d.to_std().unwrap(), StdDuration::from_millis(1500));
+
+    assert_eq!(duration(-1, 0).to_std(), Err(TimeError::Negative));
+    assert_eq!(duration(0, -5).to_std(), Err(TimeError::Negative));
+    assert_eq!(duration(1, -5).to_std(), Err(TimeError::InvalidNanos));
+    assert_eq!(
+        duration(0, 1_000_000_000).to_std(),
+        Err(TimeError::InvalidNanos)
+    );
+    assert_eq!(
+        Duration::try_from_std(StdDuration::from_secs(u64::MAX)),
+        Err(TimeError::OutOfRange)
+    );
+    assert_eq!(duration(i64::MIN, 0).to_std(), Err(TimeError::OutOfRange));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono() {
+    use chrono::{DateTime, TimeDelta};
+
+    let time = DateTime::from_timestamp(-2, 750_000_000).unwrap();
+    let ts = Timestamp::try_from_chrono(time).unwrap();
+    assert_eq!(ts, timestamp(-2, 750_000_000));
+    assert_eq!(ts.to_chrono().unwrap(), time);
+
+    // A leap second.
+    let time = DateTime::from_timestamp(1_483_228_799, 1_500_000_000).unwrap();
+    assert_eq!(
+        Timestamp::try_from_chrono(time),
+        Err(TimeError::InvalidNanos)
+    );
+    assert_eq!(
+        timestamp(TIMESTAMP_MAX_SECONDS + 1, 0).to_chrono(),
+        Err(TimeError::OutOfRange)
+    );
+
+    let delta = TimeDelta::milliseconds(-1500);
+    let d = Duration::try_from_chrono(delta).unwrap();
+    assert_eq!(d, duration(-1, -500_000_000));
+    assert_eq!(d.to_chrono().unwrap(), delta);
+    assert_eq!(
+        Duration::try_from_chrono(TimeDelta::MIN),
+        Err(TimeError::OutOfRange)
+    );
+    assert_eq!(duration(i64::MIN, 0).to_chrono(), Err(TimeError::OutOfRange));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_time() {
+    use time::{OffsetDateTime, UtcOffset};
+
+    let time = OffsetDateTime::from_unix_timestamp_nanos(-1_250_000_000).unwrap();
+    let ts = Timestamp::try_from_offset_date_time(time).unwrap();
+    assert_eq!(ts, timestamp(-2, 750_000_000));
+    assert_eq!(ts.to_offset_date_time().unwrap(), time);
+
+    // The offset does not change the instant.
+    let local = time.to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+    assert_eq!(Timestamp::try_from_offset_date_time(local).unwrap(), ts);
+    assert_eq!(
+        timestamp(TIMESTAMP_MIN_SECONDS - 1, 0).to_offset_date_time(),
+        Err(TimeError::OutOfRange)
+    );
+
+    let delta = time::Duration::milliseconds(-1500);
+    let d = Duration::try_from_time(delta).unwrap();
+    assert_eq!(d, duration(-1, -500_000_000));
+    assert_eq!(d.to_time().unwrap(), delta);
+    assert_eq!(
+        Duration::try_from_time(time::Duration::MIN),
+        Err(TimeError::OutOfRange)
+    );
+    assert_eq!(duration(1, -5).to_time(), Err(TimeError::InvalidNanos));
+}